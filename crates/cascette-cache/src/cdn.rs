@@ -2,15 +2,28 @@
 //!
 //! This module provides CDN client functionality for fetching content
 //! when cache misses occur, including retry logic, range requests,
-//! and connection pooling.
+//! and connection pooling. Content caches can also prefetch files that
+//! later operations will need, such as the encoding and root files.
 
 use crate::{
     error::{NgdpCacheError, NgdpCacheResult},
+    key::ConfigKey,
+    memory_cache::MemoryCache,
     ngdp::{ArchiveCache, ContentAddressedCache, NgdpResolutionCache},
+    singleflight::SingleFlight,
 };
 use bytes::Bytes;
 use cascette_crypto::{ContentKey, EncodingKey};
-use std::{sync::Arc, time::Duration};
+use futures::stream::{self, StreamExt};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+use tokio::sync::Semaphore;
 
 /// CDN client configuration
 #[derive(Debug, Clone)]
@@ -83,6 +96,14 @@ impl CdnClient {
         }
     }
 
+    /// Host that downloads are sent to
+    fn host(&self) -> &str {
+        // Mock implementation - requests only go to the first CDN URL
+        let url = self.config.cdn_urls.first().map_or("", String::as_str);
+        let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+        rest.split('/').next().unwrap_or_default()
+    }
+
     /// Fetch content by key
     #[allow(clippy::unused_async)] // Keep async for consistent public API
     pub async fn fetch_content(&self, content_key: ContentKey) -> NgdpCacheResult<Bytes> {
@@ -234,12 +255,19 @@ impl MockHttpClient {
 /// Cache with CDN fallback
 ///
 /// This wrapper adds CDN fetching capability to any cache implementation,
-/// automatically fetching from CDN on cache misses.
+/// automatically fetching from CDN on cache misses. Concurrent misses on
+/// the same file share one download.
 pub struct CdnBackedCache<C, K> {
     /// Underlying cache
     cache: Arc<C>,
     /// CDN client
     cdn: Arc<CdnClient>,
+    /// Downloads in flight
+    inflight: SingleFlight<Bytes, NgdpCacheError>,
+    /// Counts of background prefetches
+    prefetch: Arc<PrefetchCounters>,
+    /// Prefetch download slots per CDN host
+    host_permits: Arc<HostPermits>,
     /// Phantom data for key type
     _phantom: std::marker::PhantomData<K>,
 }
@@ -247,12 +275,77 @@ pub struct CdnBackedCache<C, K> {
 impl<C, K> CdnBackedCache<C, K> {
     /// Create a new CDN-backed cache
     pub fn new(cache: Arc<C>, cdn: Arc<CdnClient>) -> Self {
+        let host_permits = Arc::new(HostPermits::new(cdn.config.connection_pool_size));
         Self {
             cache,
             cdn,
+            inflight: SingleFlight::new(),
+            prefetch: Arc::new(PrefetchCounters::default()),
+            host_permits,
             _phantom: std::marker::PhantomData,
         }
     }
+
+    /// Get the counts of background prefetches so far
+    pub fn prefetch_stats(&self) -> PrefetchStats {
+        PrefetchStats {
+            requested: self.prefetch.requested.load(Ordering::Relaxed),
+            already_cached: self.prefetch.already_cached.load(Ordering::Relaxed),
+            completed: self.prefetch.completed.load(Ordering::Relaxed),
+            failed: self.prefetch.failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Counts of background prefetches
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PrefetchStats {
+    /// Keys passed to prefetch calls
+    pub requested: u64,
+    /// Keys that were already cached and not downloaded
+    pub already_cached: u64,
+    /// Keys downloaded and stored in the cache
+    pub completed: u64,
+    /// Keys whose download or store failed
+    pub failed: u64,
+}
+
+/// Shared counters behind [`PrefetchStats`]
+#[derive(Debug, Default)]
+struct PrefetchCounters {
+    requested: AtomicU64,
+    already_cached: AtomicU64,
+    completed: AtomicU64,
+    failed: AtomicU64,
+}
+
+/// Download slots per CDN host
+///
+/// Every prefetch of a cache draws from the same semaphore for a host, so
+/// overlapping prefetch calls cannot exceed the per-host limit together.
+#[derive(Debug)]
+struct HostPermits {
+    per_host: usize,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl HostPermits {
+    fn new(per_host: usize) -> Self {
+        Self {
+            per_host: per_host.max(1),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Semaphore limiting downloads from `host`
+    fn for_host(&self, host: &str) -> Arc<Semaphore> {
+        let mut hosts = self.hosts.lock().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(
+            hosts
+                .entry(host.to_string())
+                .or_insert_with(|| Arc::new(Semaphore::new(self.per_host))),
+        )
+    }
 }
 
 /// CDN-backed NGDP resolution cache
@@ -275,9 +368,15 @@ impl CdnNgdpResolutionCache {
         }
 
         // Cache miss - fetch root file from CDN
-        let root_data = self.cdn.fetch_content(root_content_key).await?;
-        self.cache
-            .cache_root_file(root_content_key, root_data)
+        let flight_key = format!("root:{}", root_content_key.to_hex());
+        self.inflight
+            .run(&flight_key, async {
+                let root_data = self.cdn.fetch_content(root_content_key).await?;
+                self.cache
+                    .cache_root_file(root_content_key, root_data.clone())
+                    .await?;
+                Ok(root_data)
+            })
             .await?;
 
         // Try again with cached data
@@ -302,9 +401,64 @@ where
         }
 
         // Cache miss - fetch from CDN
-        let data = self.cdn.fetch_content(content_key).await?;
-        self.cache.put_validated(content_key, data.clone()).await?;
-        Ok(data)
+        let flight_key = format!("content:{}", content_key.to_hex());
+        self.inflight
+            .run(&flight_key, async {
+                let data = self.cdn.fetch_content(content_key).await?;
+                self.cache.put_validated(content_key, data.clone()).await?;
+                Ok(data)
+            })
+            .await
+    }
+}
+
+impl<C> CdnContentCache<C>
+where
+    C: crate::traits::AsyncCache<crate::key::BlteBlockKey> + Send + Sync + 'static,
+{
+    /// Download content into the cache in the background
+    ///
+    /// Keys that are already cached are skipped. At most
+    /// [`CdnConfig::connection_pool_size`] prefetch downloads run at once
+    /// per CDN host, counted across all prefetch calls on this cache, and
+    /// a key that is also requested through [`get_with_fallback`] is only
+    /// downloaded once. Failed downloads are counted in
+    /// [`prefetch_stats`] and otherwise ignored; the file is fetched again
+    /// when it is requested.
+    ///
+    /// [`get_with_fallback`]: Self::get_with_fallback
+    /// [`prefetch_stats`]: CdnBackedCache::prefetch_stats
+    pub fn prefetch(self: &Arc<Self>, keys: Vec<ContentKey>) -> tokio::task::JoinHandle<()> {
+        self.prefetch
+            .requested
+            .fetch_add(keys.len() as u64, Ordering::Relaxed);
+        let this = Arc::clone(self);
+        let permits = self.host_permits.for_host(self.cdn.host());
+        let concurrency = self.host_permits.per_host;
+        tokio::spawn(async move {
+            stream::iter(keys)
+                .for_each_concurrent(concurrency, |content_key| {
+                    let this = &this;
+                    let permits = &permits;
+                    async move {
+                        let counter =
+                            if let Ok(Some(_)) = this.cache.get_validated(content_key).await {
+                                &this.prefetch.already_cached
+                            } else {
+                                let permit = permits.acquire().await;
+                                let fetched = this.get_with_fallback(content_key).await;
+                                drop(permit);
+                                if fetched.is_ok() {
+                                    &this.prefetch.completed
+                                } else {
+                                    &this.prefetch.failed
+                                }
+                            };
+                        counter.fetch_add(1, Ordering::Relaxed);
+                    }
+                })
+                .await;
+        })
     }
 }
 
@@ -328,14 +482,57 @@ where
         }
 
         // Cache miss - fetch from CDN
-        let data = self
-            .cdn
-            .fetch_archive_range(archive_id, offset, length)
-            .await?;
-        self.cache
-            .put_range(archive_id, offset, length, data.clone())
-            .await?;
-        Ok(data)
+        let flight_key = format!("archive:{archive_id}:{offset}:{length}");
+        self.inflight
+            .run(&flight_key, async {
+                let data = self
+                    .cdn
+                    .fetch_archive_range(archive_id, offset, length)
+                    .await?;
+                self.cache
+                    .put_range(archive_id, offset, length, data.clone())
+                    .await?;
+                Ok(data)
+            })
+            .await
+    }
+}
+
+/// CDN-backed config file cache
+pub type CdnConfigCache<C> = CdnBackedCache<C, ConfigKey>;
+
+impl<C> CdnConfigCache<C>
+where
+    C: crate::traits::AsyncCache<ConfigKey> + Send + Sync,
+{
+    /// Get a config file from the cache without contacting the CDN
+    pub async fn get_cached(&self, key: &ConfigKey) -> NgdpCacheResult<Option<Bytes>> {
+        Ok(self.cache.get(key).await?)
+    }
+
+    /// Get config file with CDN fallback
+    pub async fn get_with_fallback(&self, key: &ConfigKey) -> NgdpCacheResult<Bytes> {
+        // Try cache first
+        if let Some(data) = self.cache.get(key).await? {
+            return Ok(data);
+        }
+
+        // The CDN path is built from the first four hex digits
+        if key.hash.len() != 32 || !key.hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(NgdpCacheError::CdnFetchFailed(format!(
+                "Invalid config hash: {}",
+                key.hash
+            )));
+        }
+
+        // Cache miss - fetch from CDN
+        self.inflight
+            .run(key.as_cache_key(), async {
+                let data = self.cdn.fetch_config(&key.hash).await?;
+                self.cache.put(key.clone(), data.clone()).await?;
+                Ok(data)
+            })
+            .await
     }
 }
 
@@ -416,6 +613,15 @@ impl CdnCacheStack {
         let cache = Arc::new(NgdpResolutionCache::new(config)?);
         Ok(CdnNgdpResolutionCache::new(cache, self.cdn.clone()))
     }
+
+    /// Create a new CDN-backed config file cache
+    pub fn create_config_cache(
+        &self,
+        config: crate::config::MemoryCacheConfig,
+    ) -> NgdpCacheResult<CdnConfigCache<MemoryCache<ConfigKey>>> {
+        let cache = Arc::new(MemoryCache::new(config)?);
+        Ok(CdnConfigCache::new(cache, self.cdn.clone()))
+    }
 }
 
 #[cfg(test)]
@@ -490,4 +696,120 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_cdn_config_cache_fallback() {
+        let stack = CdnCacheBuilder::new()
+            .build()
+            .expect("CDN cache stack should build successfully");
+        let configs = stack
+            .create_config_cache(crate::config::MemoryCacheConfig::default())
+            .expect("Config cache creation should succeed");
+        let key = ConfigKey::new("buildconfig", "0123456789abcdef0123456789abcdef");
+
+        assert!(configs.get_cached(&key).await.expect("lookup").is_none());
+        configs
+            .get_with_fallback(&key)
+            .await
+            .expect("CDN fetch should succeed");
+        configs
+            .get_with_fallback(&key)
+            .await
+            .expect("Cached fetch should succeed");
+        assert!(configs.get_cached(&key).await.expect("lookup").is_some());
+
+        // Only the first lookup reached the CDN
+        let metrics = stack.cdn().metrics().expect("metrics should succeed");
+        assert_eq!(metrics.total_requests, 1);
+
+        let invalid = ConfigKey::new("buildconfig", "abc");
+        assert!(configs.get_with_fallback(&invalid).await.is_err());
+    }
+
+    fn content_cache(
+        stack: &CdnCacheStack,
+    ) -> Arc<CdnContentCache<MemoryCache<crate::key::BlteBlockKey>>> {
+        let inner = Arc::new(
+            MemoryCache::new(crate::config::MemoryCacheConfig::default())
+                .expect("Memory cache creation should succeed"),
+        );
+        let cache = Arc::new(ContentAddressedCache::new(
+            inner,
+            Arc::new(crate::validation::NgdpValidationHooks::new()),
+        ));
+        Arc::new(CdnContentCache::new(cache, stack.cdn().clone()))
+    }
+
+    #[tokio::test]
+    async fn test_prefetched_content_is_served_from_cache() {
+        let stack = CdnCacheBuilder::new()
+            .build()
+            .expect("CDN cache stack should build successfully");
+        let cache = content_cache(&stack);
+        // The mock CDN serves the same body for every key
+        let key = ContentKey::from_data(b"mock content data");
+
+        cache
+            .prefetch(vec![key, ContentKey::from_data(b"other")])
+            .await
+            .expect("Prefetch task should finish");
+        assert_eq!(
+            cache.prefetch_stats(),
+            PrefetchStats {
+                requested: 2,
+                already_cached: 0,
+                completed: 1,
+                failed: 1,
+            }
+        );
+        let requests = stack.cdn().metrics().expect("metrics").total_requests;
+        assert_eq!(requests, 2);
+
+        let data = cache
+            .get_with_fallback(key)
+            .await
+            .expect("Cached fetch should succeed");
+        assert_eq!(data.as_ref(), b"mock content data");
+        assert_eq!(
+            stack.cdn().metrics().expect("metrics").total_requests,
+            requests
+        );
+
+        // Prefetching a cached key does not download it again
+        cache
+            .prefetch(vec![key])
+            .await
+            .expect("Prefetch task should finish");
+        assert_eq!(cache.prefetch_stats().already_cached, 1);
+        assert_eq!(
+            stack.cdn().metrics().expect("metrics").total_requests,
+            requests
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prefetches_share_per_host_limit() {
+        let stack = CdnCacheBuilder::new()
+            .with_cdn_config(CdnConfig {
+                connection_pool_size: 1,
+                ..CdnConfig::default()
+            })
+            .build()
+            .expect("CDN cache stack should build successfully");
+        let cache = content_cache(&stack);
+        let key = ContentKey::from_data(b"mock content data");
+
+        // Take the only slot for the host, as another prefetch would
+        let permits = cache.host_permits.for_host("level3.blizzard.com");
+        let held = permits.acquire().await.expect("Semaphore should be open");
+
+        let prefetch = cache.prefetch(vec![key]);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(cache.prefetch_stats().completed, 0);
+        assert_eq!(stack.cdn().metrics().expect("metrics").total_requests, 0);
+
+        drop(held);
+        prefetch.await.expect("Prefetch task should finish");
+        assert_eq!(cache.prefetch_stats().completed, 1);
+    }
 }
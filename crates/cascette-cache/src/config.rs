@@ -6,12 +6,13 @@
 
 #![allow(missing_docs)]
 
+use crate::key::CacheKeyKind;
 use crate::traits::{EvictionPolicy, InvalidationStrategy};
 use serde::{Deserialize, Serialize};
-use std::{path::PathBuf, time::Duration};
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
 
 /// Memory cache configuration
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MemoryCacheConfig {
     pub max_entries: usize,
     /// Bytes; None for unlimited
@@ -22,6 +23,13 @@ pub struct MemoryCacheConfig {
     pub invalidation_strategy: InvalidationStrategy,
     pub enable_metrics: bool,
     pub cleanup_interval: Duration,
+    /// Fraction by which each entry's TTL is randomized, see
+    /// [`with_ttl_jitter`](Self::with_ttl_jitter)
+    #[serde(default)]
+    pub ttl_jitter: f32,
+    /// Seed that makes the TTL jitter deterministic per key
+    #[serde(default)]
+    pub ttl_jitter_seed: Option<u64>,
 }
 
 impl Default for MemoryCacheConfig {
@@ -34,6 +42,8 @@ impl Default for MemoryCacheConfig {
             invalidation_strategy: InvalidationStrategy::default(),
             enable_metrics: true,
             cleanup_interval: Duration::from_secs(60), // 1 minute
+            ttl_jitter: 0.0,
+            ttl_jitter_seed: None,
         }
     }
 }
@@ -63,11 +73,33 @@ impl MemoryCacheConfig {
         self
     }
 
+    /// Randomize each entry's TTL by up to ±`fraction` when it is inserted
+    ///
+    /// Entries stored together then expire spread out rather than all at
+    /// once. A jitter of 0.1 on a 300s TTL spreads expiry across 270–330s.
+    pub fn with_ttl_jitter(mut self, fraction: f32) -> Self {
+        self.ttl_jitter = fraction;
+        self
+    }
+
+    /// Derive each key's TTL jitter from `seed` instead of a random number
+    ///
+    /// The same key then always gets the same TTL, which keeps tests
+    /// reproducible.
+    pub fn with_ttl_jitter_seed(mut self, seed: u64) -> Self {
+        self.ttl_jitter_seed = Some(seed);
+        self
+    }
+
     pub fn validate(&self) -> Result<(), String> {
         if self.max_entries == 0 {
             return Err("max_entries must be greater than 0".to_string());
         }
 
+        if !(0.0..=1.0).contains(&self.ttl_jitter) {
+            return Err("ttl_jitter must be between 0.0 and 1.0".to_string());
+        }
+
         if let Some(max_bytes) = self.max_memory_bytes
             && max_bytes == 0
         {
@@ -99,6 +131,15 @@ pub struct DiskCacheConfig {
     /// Avoids too many files in one directory
     pub use_subdirectories: bool,
     pub subdirectory_levels: usize,
+    /// Entries of at least this many bytes are read through a memory map;
+    /// None reads every entry into memory. Ignored on Windows
+    #[serde(default)]
+    pub mmap_threshold: Option<usize>,
+    /// Age after which entries are evicted, by key kind
+    ///
+    /// Kinds without a maximum age are only evicted by TTL or size pressure.
+    #[serde(default, with = "by_kind_name")]
+    pub max_age_by_kind: BTreeMap<CacheKeyKind, Duration>,
 }
 
 impl Default for DiskCacheConfig {
@@ -115,6 +156,8 @@ impl Default for DiskCacheConfig {
             sync_interval: Duration::from_secs(30),     // 30 seconds
             use_subdirectories: true,
             subdirectory_levels: 2,
+            mmap_threshold: None,
+            max_age_by_kind: BTreeMap::new(),
         }
     }
 }
@@ -148,6 +191,54 @@ impl DiskCacheConfig {
         self
     }
 
+    /// Read entries of at least `threshold` bytes through a memory map
+    ///
+    /// Mapped reads return `Bytes` that share the page cache instead of
+    /// copying the file into a new buffer. Where a file cannot be mapped
+    /// the entry is read into memory as usual.
+    ///
+    /// Mapped entries change under their readers if another process writes
+    /// to the cache files, so only enable this for a cache directory owned
+    /// by this cache. Windows always reads entries into memory, since a
+    /// mapped file there cannot be replaced or removed.
+    pub fn with_mmap_threshold(mut self, threshold: usize) -> Self {
+        self.mmap_threshold = Some(threshold);
+        self
+    }
+
+    /// Evict entries of `kind` once they are older than `max_age`
+    ///
+    /// Age counts from when the entry was written; for files left by an
+    /// earlier run, from their modification time. Entries older than their
+    /// maximum age are removed whenever limits are enforced, even if they
+    /// have not expired.
+    ///
+    /// ```
+    /// use cascette_cache::config::DiskCacheConfig;
+    /// use cascette_cache::key::CacheKeyKind;
+    /// use std::time::Duration;
+    ///
+    /// let config = DiskCacheConfig::new("cache")
+    ///     .with_max_age(CacheKeyKind::Config, Duration::from_secs(7 * 24 * 3600))
+    ///     .with_max_age(CacheKeyKind::ArchiveIndex, Duration::from_secs(30 * 24 * 3600));
+    /// assert_eq!(config.max_age(CacheKeyKind::Blte), None);
+    /// ```
+    pub fn with_max_age(mut self, kind: CacheKeyKind, max_age: Duration) -> Self {
+        self.max_age_by_kind.insert(kind, max_age);
+        self
+    }
+
+    /// Maximum age configured for `kind`
+    pub fn max_age(&self, kind: CacheKeyKind) -> Option<Duration> {
+        self.max_age_by_kind.get(&kind).copied()
+    }
+
+    /// Whether an entry of `kind` that is `age` old is past its maximum age
+    pub(crate) fn exceeds_max_age(&self, kind: Option<CacheKeyKind>, age: Duration) -> bool {
+        kind.and_then(|kind| self.max_age(kind))
+            .is_some_and(|max_age| age > max_age)
+    }
+
     pub fn validate(&self) -> Result<(), String> {
         if self.max_files == 0 {
             return Err("max_files must be greater than 0".to_string());
@@ -173,6 +264,14 @@ impl DiskCacheConfig {
             );
         }
 
+        if let Some((kind, _)) = self
+            .max_age_by_kind
+            .iter()
+            .find(|(_, max_age)| max_age.is_zero())
+        {
+            return Err(format!("max age of {kind} entries must be greater than 0"));
+        }
+
         Ok(())
     }
 }
@@ -183,6 +282,71 @@ pub struct MultiLayerCacheConfig {
     pub layers: Vec<LayerConfig>,
     pub promotion_strategy: PromotionStrategy,
     pub enable_cross_layer_stats: bool,
+    /// Copy entries found in a lower layer into L1 when the promotion
+    /// strategy allows it
+    ///
+    /// The copy keeps the entry's remaining TTL and is written in the
+    /// background, so the read that finds the entry is not delayed.
+    #[serde(default)]
+    pub promote_on_hit: bool,
+    /// TTL of entries stored without an explicit TTL, by key kind
+    ///
+    /// Kinds without a policy use the default TTL of the layer.
+    #[serde(default, with = "by_kind_name")]
+    pub ttl_policies: BTreeMap<CacheKeyKind, TtlPolicy>,
+}
+
+/// How long entries of one key kind live in a multi-layer cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TtlPolicy {
+    /// Entries expire after the duration
+    Expires(Duration),
+    /// Entries never expire and leave the cache only under size pressure
+    ///
+    /// Suits content-addressed data such as BLTE blocks, which cannot
+    /// change once published.
+    Immutable,
+}
+
+impl From<Duration> for TtlPolicy {
+    fn from(ttl: Duration) -> Self {
+        Self::Expires(ttl)
+    }
+}
+
+/// Per-kind settings keyed by kind name, independent of the `serde` feature
+///
+/// Use with `#[serde(with = "cascette_cache::config::by_kind_name")]` on a
+/// `BTreeMap<CacheKeyKind, _>` field.
+pub mod by_kind_name {
+    use super::{BTreeMap, CacheKeyKind};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serialize the map with kind names as keys
+    pub fn serialize<S: Serializer, V: Serialize>(
+        settings: &BTreeMap<CacheKeyKind, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        settings
+            .iter()
+            .map(|(kind, value)| (kind.name(), value))
+            .collect::<BTreeMap<_, _>>()
+            .serialize(serializer)
+    }
+
+    /// Deserialize a map keyed by kind names
+    pub fn deserialize<'de, D: Deserializer<'de>, V: Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<CacheKeyKind, V>, D::Error> {
+        BTreeMap::<String, V>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, value)| {
+                let kind = name.parse().map_err(serde::de::Error::custom)?;
+                Ok((kind, value))
+            })
+            .collect()
+    }
 }
 
 impl MultiLayerCacheConfig {
@@ -191,6 +355,8 @@ impl MultiLayerCacheConfig {
             layers: Vec::new(),
             promotion_strategy: PromotionStrategy::OnHit,
             enable_cross_layer_stats: true,
+            promote_on_hit: false,
+            ttl_policies: BTreeMap::new(),
         }
     }
 
@@ -209,6 +375,33 @@ impl MultiLayerCacheConfig {
         self
     }
 
+    pub fn with_promote_on_hit(mut self, enable: bool) -> Self {
+        self.promote_on_hit = enable;
+        self
+    }
+
+    /// Set the TTL of entries of `kind` that are stored without one
+    ///
+    /// ```
+    /// use cascette_cache::config::{MultiLayerCacheConfig, TtlPolicy};
+    /// use cascette_cache::key::CacheKeyKind;
+    /// use std::time::Duration;
+    ///
+    /// let config = MultiLayerCacheConfig::new()
+    ///     .with_ttl_policy(CacheKeyKind::Ribbit, Duration::from_secs(300))
+    ///     .with_ttl_policy(CacheKeyKind::Content, TtlPolicy::Immutable);
+    /// assert_eq!(config.ttl_policy(CacheKeyKind::Content), Some(TtlPolicy::Immutable));
+    /// ```
+    pub fn with_ttl_policy(mut self, kind: CacheKeyKind, policy: impl Into<TtlPolicy>) -> Self {
+        self.ttl_policies.insert(kind, policy.into());
+        self
+    }
+
+    /// TTL policy configured for `kind`
+    pub fn ttl_policy(&self, kind: CacheKeyKind) -> Option<TtlPolicy> {
+        self.ttl_policies.get(&kind).copied()
+    }
+
     pub fn validate(&self) -> Result<(), String> {
         if self.layers.is_empty() {
             return Err("At least one layer must be configured".to_string());
@@ -228,7 +421,7 @@ impl Default for MultiLayerCacheConfig {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum LayerConfig {
     Memory(MemoryCacheConfig),
     Disk(DiskCacheConfig),
@@ -419,6 +612,31 @@ mod tests {
 
         config.max_entries = 0;
         assert!(config.validate().is_err());
+
+        assert!(
+            MemoryCacheConfig::new()
+                .with_ttl_jitter(1.0)
+                .validate()
+                .is_ok()
+        );
+        assert!(
+            MemoryCacheConfig::new()
+                .with_ttl_jitter(1.5)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            MemoryCacheConfig::new()
+                .with_ttl_jitter(-0.1)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            MemoryCacheConfig::new()
+                .with_ttl_jitter(f32::NAN)
+                .validate()
+                .is_err()
+        );
     }
 
     #[test]
@@ -623,6 +841,25 @@ mod tests {
         assert!(config7.validate().is_ok());
     }
 
+    #[test]
+    fn test_multi_layer_config_ttl_policies() {
+        let config = MultiLayerCacheConfig::new()
+            .add_memory_layer(MemoryCacheConfig::new())
+            .with_ttl_policy(CacheKeyKind::Ribbit, Duration::from_secs(300))
+            .with_ttl_policy(CacheKeyKind::Content, TtlPolicy::Immutable);
+        assert_eq!(
+            config.ttl_policy(CacheKeyKind::Ribbit),
+            Some(TtlPolicy::Expires(Duration::from_secs(300)))
+        );
+        assert_eq!(config.ttl_policy(CacheKeyKind::Config), None);
+
+        let json = serde_json::to_value(&config).expect("Test operation should succeed");
+        assert_eq!(json["ttl_policies"]["content"], "immutable");
+        let parsed: MultiLayerCacheConfig =
+            serde_json::from_value(json).expect("Test operation should succeed");
+        assert_eq!(parsed, config);
+    }
+
     #[test]
     fn test_multi_layer_config_empty_layers() {
         let config = MultiLayerCacheConfig::new();
@@ -1,7 +1,7 @@
 //! Disk-based cache implementation for persistent NGDP content storage
 //!
 //! This module provides a high-performance disk cache using:
-//! - Memory-mapped files for efficient large file handling
+//! - Optional memory-mapped reads for large entries
 //! - Hierarchical directory structure to avoid filesystem bottlenecks
 //! - Atomic file operations for consistency
 //! - Background compaction and cleanup tasks
@@ -16,25 +16,34 @@
 use crate::{
     config::DiskCacheConfig,
     error::{CacheError, CacheResult},
-    key::CacheKey,
+    key::{CacheKey, CacheKeyKind},
+    singleflight::SingleFlight,
     stats::AtomicCacheMetrics,
     traits::AsyncCache,
+    validation::{ValidationHooks, check_before_put},
 };
 use async_trait::async_trait;
 use bytes::Bytes;
+use futures::future::BoxFuture;
+#[cfg(not(windows))]
+use memmap2::Mmap;
 use std::{
     collections::HashMap,
-    fs::{self, File, OpenOptions},
+    fs::{self, File},
     io::{Read, Write},
     path::{Path, PathBuf},
     sync::{
         Arc, RwLock,
-        atomic::{AtomicU64, AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
     },
     time::{Duration, Instant, SystemTime},
 };
 use tokio::{sync::Semaphore, time::interval};
 
+/// Size-pressure eviction stops once usage is at this percentage of the
+/// limits, so the next few writes do not immediately trigger another scan
+const EVICTION_LOW_WATER_PERCENT: u64 = 90;
+
 /// Disk cache entry metadata
 #[derive(Debug, Clone)]
 struct DiskCacheEntry {
@@ -67,7 +76,6 @@ impl DiskCacheEntry {
     }
 
     /// Get the age of this entry
-    #[allow(dead_code)]
     fn age(&self) -> Duration {
         SystemTime::now()
             .duration_since(self.created_at)
@@ -106,6 +114,49 @@ pub struct DiskCache<K: CacheKey> {
     cleanup_handle: Option<tokio::task::JoinHandle<()>>,
     /// Background sync task handle
     sync_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Whether files left by previous runs have been accounted for
+    limits_scanned: AtomicBool,
+    /// Whether a limit enforcement pass is running
+    enforcing: AtomicBool,
+    /// Bytes in files not in the index (left by previous runs), as of the
+    /// last scan
+    untracked_usage: AtomicU64,
+    /// Number of files not in the index, as of the last scan
+    untracked_files: AtomicUsize,
+    /// Loads in flight from `get_or_insert_with`
+    inflight: SingleFlight<Bytes, CacheError>,
+    /// Hooks consulted before every put
+    validation_hooks: Option<Arc<dyn ValidationHooks>>,
+    /// Reads served from a memory map
+    mmap_reads: AtomicU64,
+    /// Reads over the mmap threshold that fell back to a buffered read
+    mmap_fallbacks: AtomicU64,
+}
+
+/// Counts of reads over [`DiskCacheConfig::mmap_threshold`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MmapStats {
+    /// Reads served from a memory map
+    pub mapped_reads: u64,
+    /// Reads that could not map the file and copied it into memory
+    pub fallback_reads: u64,
+}
+
+/// A cache file found on disk during limit enforcement
+struct ScannedFile {
+    path: PathBuf,
+    size_bytes: u64,
+    last_accessed: SystemTime,
+    expired: bool,
+    /// Whether the file belonged to an index entry when the scan started
+    tracked: bool,
+}
+
+/// Index entry state captured before a directory scan
+struct TrackedSnapshot<K> {
+    key: K,
+    /// Identifies the write; a later put of the same key replaces it
+    created_at: SystemTime,
 }
 
 impl<K: CacheKey + 'static> DiskCache<K> {
@@ -130,6 +181,14 @@ impl<K: CacheKey + 'static> DiskCache<K> {
             io_semaphore,
             cleanup_handle: None,
             sync_handle: None,
+            limits_scanned: AtomicBool::new(false),
+            enforcing: AtomicBool::new(false),
+            untracked_usage: AtomicU64::new(0),
+            untracked_files: AtomicUsize::new(0),
+            inflight: SingleFlight::new(),
+            validation_hooks: None,
+            mmap_reads: AtomicU64::new(0),
+            mmap_fallbacks: AtomicU64::new(0),
         };
 
         // Note: For now, we won't rebuild the index from disk files
@@ -197,7 +256,8 @@ impl<K: CacheKey + 'static> DiskCache<K> {
 
                     // Find expired entries and old entries
                     for (key, entry) in index_guard.iter() {
-                        if entry.is_expired() {
+                        if entry.is_expired() || config.exceeds_max_age(key.key_kind(), entry.age())
+                        {
                             entries_to_remove.push(key.clone());
                         } else if entry.age() > Duration::from_secs(24 * 60 * 60) {
                             // Also clean up entries older than 24 hours
@@ -317,6 +377,10 @@ impl<K: CacheKey + 'static> DiskCache<K> {
     }
 
     /// Write data to disk file atomically
+    ///
+    /// Each write goes to its own temporary file in the target directory,
+    /// so concurrent puts of one key never write into the same file; the
+    /// last rename wins.
     async fn write_file(&self, path: &Path, data: &Bytes) -> CacheResult<()> {
         let _permit = self
             .io_semaphore
@@ -324,45 +388,42 @@ impl<K: CacheKey + 'static> DiskCache<K> {
             .await
             .map_err(|_| CacheError::Backend("Failed to acquire I/O semaphore".to_string()))?;
 
-        // Write to temporary file first for atomicity
-        let temp_path = path.with_extension("tmp");
+        let parent = path.parent().unwrap_or_else(|| Path::new("."));
+        fs::create_dir_all(parent).map_err(CacheError::Io)?;
 
-        // Ensure parent directory exists
-        if let Some(parent) = temp_path.parent() {
-            fs::create_dir_all(parent).map_err(CacheError::Io)?;
-        }
+        // Write to temporary file first for atomicity; the `.tmp` suffix
+        // keeps directory scans from counting or evicting it
+        let mut temp = tempfile::Builder::new()
+            .suffix(".tmp")
+            .tempfile_in(parent)
+            .map_err(CacheError::Io)?;
+        temp.write_all(data).map_err(CacheError::Io)?;
+        temp.flush().map_err(CacheError::Io)?;
 
+        // Force data to disk for durability in cache operations
+        #[cfg(unix)]
         {
-            let mut file = OpenOptions::new()
-                .write(true)
-                .create(true)
-                .truncate(true)
-                .open(&temp_path)
-                .map_err(CacheError::Io)?;
-
-            file.write_all(data).map_err(CacheError::Io)?;
-            file.flush().map_err(CacheError::Io)?;
-
-            // Force data to disk for durability in cache operations
-            #[cfg(unix)]
-            {
-                use std::os::unix::io::AsRawFd;
-                // SAFETY: fsync is called on a valid file descriptor obtained from a File.
-                // The file is guaranteed to be open and valid at this point.
-                #[allow(unsafe_code)]
-                unsafe {
-                    libc::fsync(file.as_raw_fd());
-                }
+            use std::os::unix::io::AsRawFd;
+            // SAFETY: fsync is called on a valid file descriptor obtained from a File.
+            // The file is guaranteed to be open and valid at this point.
+            #[allow(unsafe_code)]
+            unsafe {
+                libc::fsync(temp.as_file().as_raw_fd());
             }
         }
 
         // Atomic rename
-        fs::rename(&temp_path, path).map_err(CacheError::Io)?;
+        temp.persist(path).map_err(|e| CacheError::Io(e.error))?;
 
         Ok(())
     }
 
     /// Read data from disk file
+    ///
+    /// Files of at least [`DiskCacheConfig::mmap_threshold`] bytes are
+    /// mapped rather than copied; if mapping fails they are read normally.
+    /// Windows always reads normally: a mapped file there could not be
+    /// replaced or removed until every mapping of it is dropped.
     async fn read_file(&self, path: &Path) -> CacheResult<Bytes> {
         let _permit = self
             .io_semaphore
@@ -374,29 +435,144 @@ impl<K: CacheKey + 'static> DiskCache<K> {
         let metadata = file.metadata().map_err(CacheError::Io)?;
         let file_size = metadata.len() as usize;
 
-        // For large files, consider using memory-mapped I/O
-        if file_size >= 16 * 1024 * 1024 {
-            // Use memory-mapped file for large files
-            self.read_file_mmap(path, file_size)
-        } else {
-            // Read directly for smaller files
-            let mut buffer = Vec::with_capacity(file_size);
-            file.read_to_end(&mut buffer).map_err(CacheError::Io)?;
-            Ok(Bytes::from(buffer))
+        #[cfg(not(windows))]
+        if self
+            .config
+            .mmap_threshold
+            .is_some_and(|threshold| file_size >= threshold)
+        {
+            // SAFETY: the mapping is only sound while nobody writes to or
+            // truncates the file, which this cache cannot enforce. The cache
+            // itself never does: `write_file` replaces entries by renaming a
+            // new file over them, leaving mapped files untouched, and
+            // eviction only unlinks them. The cache directory must therefore
+            // not be modified by other processes while mapped entries are
+            // alive; `mmap_threshold` is opt-in for that reason.
+            #[allow(unsafe_code)]
+            match unsafe { Mmap::map(&file) } {
+                Ok(mmap) => {
+                    self.mmap_reads.fetch_add(1, Ordering::Relaxed);
+                    return Ok(Bytes::from_owner(mmap));
+                }
+                Err(_) => {
+                    self.mmap_fallbacks.fetch_add(1, Ordering::Relaxed);
+                }
+            }
         }
-    }
 
-    /// Read large file using memory mapping
-    #[allow(clippy::unused_self)] // Kept for trait consistency
-    fn read_file_mmap(&self, path: &Path, expected_size: usize) -> CacheResult<Bytes> {
-        // For now, fall back to regular read
-        // In production, this would use memmap2 crate for memory-mapped I/O
-        let mut file = File::open(path).map_err(CacheError::Io)?;
-        let mut buffer = Vec::with_capacity(expected_size);
+        let mut buffer = Vec::with_capacity(file_size);
         file.read_to_end(&mut buffer).map_err(CacheError::Io)?;
         Ok(Bytes::from(buffer))
     }
 
+    /// Counts of memory-mapped and fallback reads
+    pub fn mmap_stats(&self) -> MmapStats {
+        MmapStats {
+            mapped_reads: self.mmap_reads.load(Ordering::Relaxed),
+            fallback_reads: self.mmap_fallbacks.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Check every put with the [`ValidationHooks::before_put`] of `hooks`
+    ///
+    /// Hooks are installed before the cache is shared, so every put made
+    /// through it is checked.
+    #[must_use]
+    pub fn with_validation_hooks(mut self, hooks: Arc<dyn ValidationHooks>) -> Self {
+        self.validation_hooks = Some(hooks);
+        self
+    }
+
+    /// Check if validation hooks are set
+    pub fn has_validation_hooks(&self) -> bool {
+        self.validation_hooks.is_some()
+    }
+
+    /// Store an entry that never expires
+    ///
+    /// The entry leaves the cache only when it is removed or evicted to
+    /// make room.
+    pub async fn put_without_ttl(&self, key: K, value: Bytes) -> CacheResult<()> {
+        self.insert(key, value, None).await
+    }
+
+    /// Store an entry that expires after `ttl`, or never with `None`
+    async fn insert(&self, key: K, value: Bytes, ttl: Option<Duration>) -> CacheResult<()> {
+        check_before_put(self.validation_hooks.as_ref(), key.as_cache_key(), &value)?;
+        let start_time = Instant::now();
+        let size_bytes = value.len();
+
+        // An entry larger than the whole cache can never fit
+        if self
+            .config
+            .max_disk_bytes
+            .is_some_and(|max| size_bytes > max)
+        {
+            return Err(CacheError::CapacityExceeded);
+        }
+
+        let file_path = self.get_file_path(&key);
+
+        // Write data to disk
+        self.write_file(&file_path, &value).await?;
+
+        // Update index
+        {
+            let mut index = self
+                .index
+                .write()
+                .map_err(|_| CacheError::LockTimeout("index write lock".to_string()))?;
+
+            let entry = DiskCacheEntry::new(file_path.clone(), size_bytes, ttl);
+
+            if let Some(old_entry) = index.insert(key, entry) {
+                // Updating existing entry - adjust disk usage
+                let old_size = old_entry.size_bytes as u64;
+                let new_size = size_bytes as u64;
+
+                if new_size > old_size {
+                    self.disk_usage
+                        .fetch_add(new_size - old_size, Ordering::Relaxed);
+                } else {
+                    self.disk_usage
+                        .fetch_sub(old_size - new_size, Ordering::Relaxed);
+                }
+
+                // Clean up old file if path changed
+                if old_entry.file_path != file_path {
+                    let _ = fs::remove_file(&old_entry.file_path);
+                }
+            } else {
+                // New entry
+                self.entry_count.fetch_add(1, Ordering::Relaxed);
+                self.disk_usage
+                    .fetch_add(size_bytes as u64, Ordering::Relaxed);
+            }
+        }
+        // Recorded before enforcing limits, which may already evict it
+        self.metrics.record_put(size_bytes, start_time.elapsed());
+
+        // The first write also accounts for files left by previous runs
+        if self.over_limits() || !self.limits_scanned.load(Ordering::Relaxed) {
+            self.enforce_limits_after_put(file_path).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Time left before the entry for `key` expires
+    ///
+    /// Returns `None` if there is no entry for `key` or it does not expire,
+    /// and `Duration::ZERO` once it has expired.
+    pub fn remaining_ttl(&self, key: &K) -> Option<Duration> {
+        let expires_at = self.index.read().ok()?.get(key)?.expires_at?;
+        Some(
+            expires_at
+                .duration_since(SystemTime::now())
+                .unwrap_or_default(),
+        )
+    }
+
     /// Get current cache statistics
     pub fn cache_stats(&self) -> crate::stats::CacheStats {
         let snapshot = self.metrics.fast_snapshot();
@@ -425,6 +601,198 @@ impl<K: CacheKey + 'static> DiskCache<K> {
         }
     }
 
+    /// Evict expired and least-recently-accessed files until within limits
+    ///
+    /// Scans the cache directory so files written by previous runs (which
+    /// are not in the in-memory index) count against `max_disk_bytes` and
+    /// `max_files`. Tracked entries use their recorded access time and TTL;
+    /// untracked files fall back to the filesystem access time (or
+    /// modification time when atime is unavailable) and expire once older
+    /// than `default_ttl`. Entries past the maximum age of their key kind
+    /// ([`DiskCacheConfig::with_max_age`]) are removed like expired ones.
+    /// Under size pressure, files are evicted until usage is back to 90% of
+    /// the limits.
+    ///
+    /// The scan runs without holding the index lock. Writes call this
+    /// automatically, on a blocking thread, once tracked usage exceeds the
+    /// limits.
+    ///
+    /// Returns the number of files removed.
+    pub fn enforce_limits(&self) -> CacheResult<usize> {
+        let (tracked, scan_state) = self.snapshot_tracked()?;
+        let files = scan_cache_files(&self.config, &scan_state)?;
+        self.evict_scanned(files, &tracked, None)
+    }
+
+    /// Enforce limits after writing `protected`, scanning on a blocking thread
+    ///
+    /// Skipped if another pass is already running; that pass sees this
+    /// write's usage.
+    async fn enforce_limits_after_put(&self, protected: PathBuf) -> CacheResult<usize> {
+        if self
+            .enforcing
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return Ok(0);
+        }
+        let result = async {
+            let (tracked, scan_state) = self.snapshot_tracked()?;
+            let config = self.config.clone();
+            let files = tokio::task::spawn_blocking(move || scan_cache_files(&config, &scan_state))
+                .await
+                .map_err(|e| CacheError::Backend(format!("cache scan task failed: {e}")))??;
+            self.evict_scanned(files, &tracked, Some(&protected))
+        }
+        .await;
+        self.enforcing.store(false, Ordering::Release);
+        result
+    }
+
+    /// Capture the index for a directory scan, holding the read lock briefly
+    ///
+    /// Returns the entries by file path, and the access time and expiry of
+    /// each path for the scan itself.
+    #[allow(clippy::type_complexity)]
+    fn snapshot_tracked(
+        &self,
+    ) -> CacheResult<(
+        HashMap<PathBuf, TrackedSnapshot<K>>,
+        HashMap<PathBuf, (SystemTime, bool)>,
+    )> {
+        let index = self
+            .index
+            .read()
+            .map_err(|_| CacheError::LockTimeout("index read lock".to_string()))?;
+        let mut tracked = HashMap::with_capacity(index.len());
+        let mut scan_state = HashMap::with_capacity(index.len());
+        for (key, entry) in index.iter() {
+            let expired =
+                entry.is_expired() || self.config.exceeds_max_age(key.key_kind(), entry.age());
+            tracked.insert(
+                entry.file_path.clone(),
+                TrackedSnapshot {
+                    key: key.clone(),
+                    created_at: entry.created_at,
+                },
+            );
+            scan_state.insert(entry.file_path.clone(), (entry.last_accessed, expired));
+        }
+        Ok((tracked, scan_state))
+    }
+
+    /// Remove expired files, then least-recently-accessed files down to the
+    /// low-water mark, and record untracked usage from the scan
+    fn evict_scanned(
+        &self,
+        files: Vec<ScannedFile>,
+        tracked: &HashMap<PathBuf, TrackedSnapshot<K>>,
+        protected: Option<&Path>,
+    ) -> CacheResult<usize> {
+        self.limits_scanned.store(true, Ordering::Relaxed);
+        let is_protected = |file: &ScannedFile| protected == Some(file.path.as_path());
+
+        // Expired files go first regardless of size pressure
+        let (expired, mut live): (Vec<_>, Vec<_>) = files
+            .into_iter()
+            .partition(|f| f.expired && !is_protected(f));
+
+        // Oldest access first; the entry being written is never a candidate
+        live.sort_by_key(|f| (is_protected(f), f.last_accessed));
+
+        let mut total_bytes: u64 = live.iter().map(|f| f.size_bytes).sum();
+        let mut total_files = live.len();
+        let max_bytes = self.config.max_disk_bytes.map(|m| m as u64);
+        let over_limits =
+            max_bytes.is_some_and(|max| total_bytes > max) || total_files > self.config.max_files;
+        let low_water_bytes = max_bytes.map(|max| max * EVICTION_LOW_WATER_PERCENT / 100);
+        let low_water_files = (self.config.max_files as u64 * EVICTION_LOW_WATER_PERCENT / 100)
+            .try_into()
+            .unwrap_or(usize::MAX);
+
+        let mut to_remove = expired;
+        if over_limits {
+            let mut kept = Vec::with_capacity(live.len());
+            for file in live {
+                let over_bytes = low_water_bytes.is_some_and(|low| total_bytes > low);
+                let over_files = total_files > low_water_files;
+                if (over_bytes || over_files) && !is_protected(&file) {
+                    total_bytes -= file.size_bytes;
+                    total_files -= 1;
+                    to_remove.push(file);
+                } else {
+                    kept.push(file);
+                }
+            }
+            live = kept;
+        }
+
+        let mut removed = 0;
+        {
+            let mut index = self
+                .index
+                .write()
+                .map_err(|_| CacheError::LockTimeout("index write lock".to_string()))?;
+            let mut untracked_paths = None;
+            for file in &to_remove {
+                let path = &file.path;
+                if let Some(snapshot) = tracked.get(path) {
+                    // Skip entries rewritten since the scan started
+                    let unchanged = index.get(&snapshot.key).is_some_and(|entry| {
+                        entry.created_at == snapshot.created_at && entry.file_path == *path
+                    });
+                    if !unchanged || fs::remove_file(path).is_err() {
+                        continue;
+                    }
+                    if index.remove(&snapshot.key).is_some() {
+                        self.entry_count.fetch_sub(1, Ordering::Relaxed);
+                        self.disk_usage
+                            .fetch_sub(file.size_bytes, Ordering::Relaxed);
+                        self.metrics.record_eviction(file.size_bytes as usize);
+                    }
+                } else {
+                    // A put during the scan may have claimed the path
+                    let claimed = untracked_paths.get_or_insert_with(|| {
+                        index
+                            .values()
+                            .map(|entry| entry.file_path.clone())
+                            .collect::<std::collections::HashSet<_>>()
+                    });
+                    if claimed.contains(&file.path) || fs::remove_file(&file.path).is_err() {
+                        continue;
+                    }
+                }
+                // Untracked files were never recorded as puts
+                removed += 1;
+            }
+        }
+
+        // Files that stay untracked keep counting against the limits
+        let untracked: Vec<_> = live.iter().filter(|f| !f.tracked).collect();
+        self.untracked_files
+            .store(untracked.len(), Ordering::Relaxed);
+        self.untracked_usage.store(
+            untracked.iter().map(|f| f.size_bytes).sum(),
+            Ordering::Relaxed,
+        );
+
+        Ok(removed)
+    }
+
+    /// Whether tracked usage, plus untracked files from the last scan,
+    /// exceeds the configured limits
+    fn over_limits(&self) -> bool {
+        let usage =
+            self.disk_usage.load(Ordering::Relaxed) + self.untracked_usage.load(Ordering::Relaxed);
+        let files =
+            self.entry_count.load(Ordering::Relaxed) + self.untracked_files.load(Ordering::Relaxed);
+        let over_bytes = self
+            .config
+            .max_disk_bytes
+            .is_some_and(|max| usage > max as u64);
+        over_bytes || files > self.config.max_files
+    }
+
     /// Recursively clear all files and subdirectories in the cache directory
     #[allow(clippy::self_only_used_in_recursion)]
     fn clear_directory_recursive(&self, dir: &std::path::Path) -> CacheResult<()> {
@@ -476,6 +844,99 @@ impl<K: CacheKey + 'static> DiskCache<K> {
     }
 }
 
+/// Collect cache files (excluding in-flight `.tmp` files) for limit enforcement
+///
+/// `tracked` holds the access time and expiry of files in the index.
+fn scan_cache_files(
+    config: &DiskCacheConfig,
+    tracked: &HashMap<PathBuf, (SystemTime, bool)>,
+) -> CacheResult<Vec<ScannedFile>> {
+    let mut files = Vec::new();
+    scan_directory(
+        config,
+        &config.cache_dir,
+        tracked,
+        SystemTime::now(),
+        &mut files,
+    )?;
+    Ok(files)
+}
+
+fn scan_directory(
+    config: &DiskCacheConfig,
+    dir: &Path,
+    tracked: &HashMap<PathBuf, (SystemTime, bool)>,
+    now: SystemTime,
+    files: &mut Vec<ScannedFile>,
+) -> CacheResult<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir).map_err(CacheError::Io)? {
+        let entry = entry.map_err(CacheError::Io)?;
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        if metadata.is_dir() {
+            scan_directory(config, &path, tracked, now, files)?;
+            continue;
+        }
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("tmp"))
+        {
+            continue;
+        }
+
+        let (last_accessed, expired, is_tracked) =
+            if let Some((accessed, expired)) = tracked.get(&path) {
+                (*accessed, *expired, true)
+            } else {
+                let modified = metadata.modified().unwrap_or(now);
+                let accessed = metadata.accessed().unwrap_or(modified).max(modified);
+                let age = now.duration_since(modified).unwrap_or(Duration::ZERO);
+                let kind = canonical_key_of(config, &path)
+                    .as_deref()
+                    .and_then(CacheKeyKind::of);
+                let expired = config.default_ttl.is_some_and(|ttl| age > ttl)
+                    || config.exceeds_max_age(kind, age);
+                (accessed, expired, false)
+            };
+
+        files.push(ScannedFile {
+            path,
+            size_bytes: metadata.len(),
+            last_accessed,
+            expired,
+            tracked: is_tracked,
+        });
+    }
+
+    Ok(())
+}
+
+/// Canonical key string of the cache file at `path`
+///
+/// Files are stored under their canonical key below the hashed
+/// subdirectories; keys containing `/` span further directories.
+fn canonical_key_of(config: &DiskCacheConfig, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(&config.cache_dir).ok()?;
+    let skip = if config.use_subdirectories {
+        config.subdirectory_levels
+    } else {
+        0
+    };
+    let segments = relative
+        .components()
+        .skip(skip)
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+    (!segments.is_empty()).then(|| segments.join("/"))
+}
+
 #[async_trait]
 impl<K: CacheKey + 'static> AsyncCache<K> for DiskCache<K> {
     async fn get(&self, key: &K) -> CacheResult<Option<Bytes>> {
@@ -582,50 +1043,7 @@ impl<K: CacheKey + 'static> AsyncCache<K> for DiskCache<K> {
     }
 
     async fn put_with_ttl(&self, key: K, value: Bytes, ttl: Duration) -> CacheResult<()> {
-        let start_time = Instant::now();
-        let size_bytes = value.len();
-
-        let file_path = self.get_file_path(&key);
-
-        // Write data to disk
-        self.write_file(&file_path, &value).await?;
-
-        // Update index
-        {
-            let mut index = self
-                .index
-                .write()
-                .map_err(|_| CacheError::LockTimeout("index write lock".to_string()))?;
-
-            let entry = DiskCacheEntry::new(file_path.clone(), size_bytes, Some(ttl));
-
-            if let Some(old_entry) = index.insert(key, entry) {
-                // Updating existing entry - adjust disk usage
-                let old_size = old_entry.size_bytes as u64;
-                let new_size = size_bytes as u64;
-
-                if new_size > old_size {
-                    self.disk_usage
-                        .fetch_add(new_size - old_size, Ordering::Relaxed);
-                } else {
-                    self.disk_usage
-                        .fetch_sub(old_size - new_size, Ordering::Relaxed);
-                }
-
-                // Clean up old file if path changed
-                if old_entry.file_path != file_path {
-                    let _ = fs::remove_file(&old_entry.file_path);
-                }
-            } else {
-                // New entry
-                self.entry_count.fetch_add(1, Ordering::Relaxed);
-                self.disk_usage
-                    .fetch_add(size_bytes as u64, Ordering::Relaxed);
-            }
-        }
-
-        self.metrics.record_put(size_bytes, start_time.elapsed());
-        Ok(())
+        self.insert(key, value, Some(ttl)).await
     }
 
     async fn contains(&self, key: &K) -> CacheResult<bool> {
@@ -681,6 +1099,8 @@ impl<K: CacheKey + 'static> AsyncCache<K> for DiskCache<K> {
 
         self.entry_count.store(0, Ordering::Relaxed);
         self.disk_usage.store(0, Ordering::Relaxed);
+        self.untracked_files.store(0, Ordering::Relaxed);
+        self.untracked_usage.store(0, Ordering::Relaxed);
         self.metrics.reset();
 
         // Also clean up any remaining files and subdirectories
@@ -693,6 +1113,34 @@ impl<K: CacheKey + 'static> AsyncCache<K> for DiskCache<K> {
         Ok(self.cache_stats())
     }
 
+    /// Scans the cache directory, so files written by earlier processes are
+    /// included when their name parses as a key of type `K`
+    async fn keys(&self) -> CacheResult<Vec<K>> {
+        let (mut tracked, scan_state) = self.snapshot_tracked()?;
+        let config = self.config.clone();
+        let files = tokio::task::spawn_blocking(move || scan_cache_files(&config, &scan_state))
+            .await
+            .map_err(|e| CacheError::Backend(format!("cache scan task failed: {e}")))??;
+        Ok(files
+            .into_iter()
+            .filter(|file| !file.expired)
+            .filter_map(|file| match tracked.remove(&file.path) {
+                Some(snapshot) => Some(snapshot.key),
+                None => canonical_key_of(&self.config, &file.path)
+                    .as_deref()
+                    .and_then(K::from_cache_key),
+            })
+            .collect())
+    }
+
+    async fn get_or_insert_with(
+        &self,
+        key: K,
+        load: BoxFuture<'_, CacheResult<Bytes>>,
+    ) -> CacheResult<Bytes> {
+        self.inflight.get_or_insert_with(self, key, load).await
+    }
+
     async fn size(&self) -> CacheResult<usize> {
         let index_size = self.entry_count.load(Ordering::Relaxed);
 
@@ -851,4 +1299,459 @@ mod tests {
             .count();
         assert_eq!(file_count, 0);
     }
+
+    /// Store a 100-byte value and wait so access times are distinguishable
+    async fn put_sized(cache: &DiskCache<RibbitKey>, name: &str) -> RibbitKey {
+        let key = RibbitKey::new(name, "us");
+        cache
+            .put(key.clone(), Bytes::from(vec![0u8; 100]))
+            .await
+            .expect("Operation should succeed");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        key
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_evicts_least_recently_accessed() {
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let config = DiskCacheConfig::new(temp_dir.path())
+            .with_subdirectories(false, 0)
+            .with_max_disk_usage(300);
+        let cache = DiskCache::new(config).expect("Operation should succeed");
+
+        let a = put_sized(&cache, "a").await;
+        let b = put_sized(&cache, "b").await;
+        let c = put_sized(&cache, "c").await;
+
+        // Touch `a` so `b` becomes the least recently accessed
+        assert!(
+            cache
+                .get(&a)
+                .await
+                .expect("Operation should succeed")
+                .is_some()
+        );
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // Exceeding the cap on write evicts down to 90% of it (270 bytes),
+        // least recently accessed first: `b`, then `c`
+        let d = put_sized(&cache, "d").await;
+
+        assert!(cache.contains(&a).await.expect("Operation should succeed"));
+        assert!(!cache.contains(&b).await.expect("Operation should succeed"));
+        assert!(!cache.contains(&c).await.expect("Operation should succeed"));
+        assert!(cache.contains(&d).await.expect("Operation should succeed"));
+        assert_eq!(cache.size().await.expect("Operation should succeed"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_counts_untracked_usage_between_scans() {
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let config = DiskCacheConfig::new(temp_dir.path())
+            .with_subdirectories(false, 0)
+            .with_max_disk_usage(350);
+
+        for name in ["old1", "old2"] {
+            fs::write(temp_dir.path().join(name), vec![0u8; 100])
+                .expect("Operation should succeed");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        let cache = DiskCache::new(config).expect("Operation should succeed");
+
+        // The first write scans and finds 300 bytes in use
+        put_sized(&cache, "a").await;
+        assert!(temp_dir.path().join("old1").exists());
+
+        // Tracked usage alone (200 bytes) is under the cap, but with the
+        // files from the earlier run it is not
+        put_sized(&cache, "b").await;
+        assert!(!temp_dir.path().join("old1").exists());
+        assert!(temp_dir.path().join("old2").exists());
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_rejects_put_failing_validation() {
+        use crate::key::ContentCacheKey;
+        use crate::validation::NgdpValidationHooks;
+        use cascette_crypto::ContentKey;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let cache = DiskCache::new(DiskCacheConfig::new(temp_dir.path()))
+            .expect("Operation should succeed")
+            .with_validation_hooks(Arc::new(NgdpValidationHooks::new().with_put_validation()));
+        let cache = Arc::new(cache);
+
+        let expected = Bytes::from("expected content");
+        let key = ContentCacheKey::new(ContentKey::from_data(&expected));
+        let result = cache.put(key.clone(), Bytes::from("tampered")).await;
+        assert!(matches!(
+            result,
+            Err(CacheError::ContentValidationFailed(_))
+        ));
+        let result = cache
+            .put_without_ttl(key.clone(), Bytes::from("tampered"))
+            .await;
+        assert!(matches!(
+            result,
+            Err(CacheError::ContentValidationFailed(_))
+        ));
+        assert!(
+            !cache
+                .contains(&key)
+                .await
+                .expect("Operation should succeed")
+        );
+        assert_eq!(cache.size().await.expect("Operation should succeed"), 0);
+
+        // Shared caches check puts under different keys alike
+        let other = Bytes::from("other content");
+        let other_key = ContentCacheKey::new(ContentKey::from_data(&other));
+        let shared = Arc::clone(&cache);
+        let task_other = other.clone();
+        tokio::spawn(async move { shared.put(other_key, task_other).await })
+            .await
+            .expect("Task should finish")
+            .expect("Matching content should be stored");
+        cache
+            .put(key.clone(), expected.clone())
+            .await
+            .expect("Matching content should be stored");
+        assert_eq!(
+            cache.get(&key).await.expect("Operation should succeed"),
+            Some(expected)
+        );
+        assert_eq!(cache.size().await.expect("Operation should succeed"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_rejects_entry_larger_than_cap() {
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let config = DiskCacheConfig::new(temp_dir.path())
+            .with_subdirectories(false, 0)
+            .with_max_disk_usage(300);
+        let cache = DiskCache::new(config).expect("Operation should succeed");
+
+        let a = put_sized(&cache, "a").await;
+
+        let huge = RibbitKey::new("huge", "us");
+        let result = cache.put(huge.clone(), Bytes::from(vec![0u8; 301])).await;
+        assert!(matches!(result, Err(CacheError::CapacityExceeded)));
+
+        // Existing entries are not sacrificed for an entry that cannot fit
+        assert!(cache.contains(&a).await.expect("Operation should succeed"));
+        assert!(
+            !cache
+                .contains(&huge)
+                .await
+                .expect("Operation should succeed")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_enforce_limits_counts_previous_runs() {
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let config = DiskCacheConfig::new(temp_dir.path())
+            .with_subdirectories(false, 0)
+            .with_max_disk_usage(250);
+
+        // Files left by an earlier process are not in the new index
+        for name in ["old1", "old2", "old3"] {
+            fs::write(temp_dir.path().join(name), vec![0u8; 100])
+                .expect("Operation should succeed");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        let cache: DiskCache<RibbitKey> = DiskCache::new(config).expect("Operation should succeed");
+        assert_eq!(cache.enforce_limits().expect("Operation should succeed"), 1);
+
+        assert!(!temp_dir.path().join("old1").exists());
+        assert!(temp_dir.path().join("old2").exists());
+        assert!(temp_dir.path().join("old3").exists());
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_enforce_limits_removes_expired() {
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let config = DiskCacheConfig::new(temp_dir.path()).with_subdirectories(false, 0);
+        let cache = DiskCache::new(config).expect("Operation should succeed");
+
+        let short = RibbitKey::new("short", "us");
+        let long = RibbitKey::new("long", "us");
+        cache
+            .put_with_ttl(short.clone(), Bytes::from("a"), Duration::from_millis(10))
+            .await
+            .expect("Operation should succeed");
+        cache
+            .put_with_ttl(long.clone(), Bytes::from("b"), Duration::from_secs(60))
+            .await
+            .expect("Operation should succeed");
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        assert_eq!(cache.enforce_limits().expect("Operation should succeed"), 1);
+        assert_eq!(cache.size().await.expect("Operation should succeed"), 1);
+        assert!(
+            cache
+                .contains(&long)
+                .await
+                .expect("Operation should succeed")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_enforce_limits_applies_max_age_by_kind() {
+        use crate::key::{ConfigKey, TypedCacheKey};
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let config = DiskCacheConfig::new(temp_dir.path())
+            .with_subdirectories(false, 0)
+            .with_max_age(CacheKeyKind::Config, Duration::from_millis(10));
+        let cache = DiskCache::new(config).expect("Operation should succeed");
+
+        // A config file left by an earlier run is also past its maximum age
+        let old_config = ConfigKey::new("cdnconfig", "0123456789abcdef");
+        fs::write(
+            temp_dir.path().join(old_config.as_cache_key()),
+            b"cdn config",
+        )
+        .expect("Operation should succeed");
+
+        let config_key = TypedCacheKey::Config(ConfigKey::new("buildconfig", "fedcba9876543210"));
+        let ribbit_key = TypedCacheKey::Ribbit(RibbitKey::new("versions", "us"));
+        cache
+            .put(config_key.clone(), Bytes::from("build config"))
+            .await
+            .expect("Operation should succeed");
+        cache
+            .put(ribbit_key.clone(), Bytes::from("versions"))
+            .await
+            .expect("Operation should succeed");
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        // Neither entry has expired, but the config one is past its maximum age
+        assert_eq!(cache.enforce_limits().expect("Operation should succeed"), 2);
+        assert!(
+            !cache
+                .contains(&config_key)
+                .await
+                .expect("Operation should succeed")
+        );
+        assert!(
+            cache
+                .contains(&ribbit_key)
+                .await
+                .expect("Operation should succeed")
+        );
+        assert!(!temp_dir.path().join(old_config.as_cache_key()).exists());
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_max_age_applies_to_untracked_cdn_files() {
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let config = DiskCacheConfig::new(temp_dir.path())
+            .with_subdirectories(false, 0)
+            .with_max_age(CacheKeyKind::ArchiveIndex, Duration::from_millis(10));
+
+        // CDN keys contain `/`, so their files sit in nested directories
+        let index = temp_dir.path().join("cdn/tpr/wow/data/ab/cd/abcdef.index");
+        let data = temp_dir.path().join("cdn/tpr/wow/data/ab/cd/abcdef");
+        for path in [&index, &data] {
+            fs::create_dir_all(path.parent().expect("Operation should succeed"))
+                .expect("Operation should succeed");
+            fs::write(path, b"cdn file").expect("Operation should succeed");
+        }
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        let cache: DiskCache<RibbitKey> = DiskCache::new(config).expect("Operation should succeed");
+        assert_eq!(cache.enforce_limits().expect("Operation should succeed"), 1);
+        assert!(!index.exists());
+        assert!(data.exists());
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_disk_cache_maps_entries_over_threshold() {
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let config = DiskCacheConfig::new(temp_dir.path()).with_mmap_threshold(1024);
+        let cache = DiskCache::new(config).expect("Operation should succeed");
+
+        let small = RibbitKey::new("small", "us");
+        let large = RibbitKey::new("large", "us");
+        let large_value: Vec<u8> = (0..4096u32).map(|i| (i % 251) as u8).collect();
+        cache
+            .put(small.clone(), Bytes::from(vec![7u8; 100]))
+            .await
+            .expect("Operation should succeed");
+        cache
+            .put(large.clone(), Bytes::from(large_value.clone()))
+            .await
+            .expect("Operation should succeed");
+
+        let data = cache.get(&small).await.expect("Operation should succeed");
+        assert_eq!(data, Some(Bytes::from(vec![7u8; 100])));
+        let data = cache.get(&large).await.expect("Operation should succeed");
+        assert_eq!(data.as_deref(), Some(large_value.as_slice()));
+        assert_eq!(
+            cache.mmap_stats(),
+            MmapStats {
+                mapped_reads: 1,
+                fallback_reads: 0,
+            }
+        );
+
+        // Without a threshold nothing is mapped
+        let cache: DiskCache<RibbitKey> = DiskCache::new(DiskCacheConfig::new(temp_dir.path()))
+            .expect("Operation should succeed");
+        let data = cache.get(&large).await.expect("Operation should succeed");
+        assert_eq!(data.as_deref(), Some(large_value.as_slice()));
+        assert_eq!(cache.mmap_stats(), MmapStats::default());
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_disk_cache_mapped_reads_during_replace() {
+        const SIZE: usize = 256 * 1024;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let config = DiskCacheConfig::new(temp_dir.path()).with_mmap_threshold(1024);
+        let cache = Arc::new(DiskCache::new(config).expect("Operation should succeed"));
+        let key = RibbitKey::new("encoding", "us");
+
+        cache
+            .put(key.clone(), Bytes::from(vec![0u8; SIZE]))
+            .await
+            .expect("Operation should succeed");
+        let first = cache
+            .get(&key)
+            .await
+            .expect("Operation should succeed")
+            .expect("Entry should exist");
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let cache = Arc::clone(&cache);
+                let key = key.clone();
+                tokio::spawn(async move {
+                    for _ in 0..50 {
+                        let data = cache
+                            .get(&key)
+                            .await
+                            .expect("Operation should succeed")
+                            .expect("Entry should exist");
+                        // Every read sees one complete version
+                        assert_eq!(data.len(), SIZE);
+                        assert!(data.iter().all(|b| *b == data[0]), "torn read");
+                        tokio::task::yield_now().await;
+                    }
+                })
+            })
+            .collect();
+
+        for version in 1..=20u8 {
+            cache
+                .put(key.clone(), Bytes::from(vec![version; SIZE]))
+                .await
+                .expect("Operation should succeed");
+        }
+        for reader in readers {
+            reader.await.expect("Reader should not panic");
+        }
+
+        // A mapping taken before the replacements still holds the old data
+        assert!(first.iter().all(|b| *b == 0));
+        let last = cache
+            .get(&key)
+            .await
+            .expect("Operation should succeed")
+            .expect("Entry should exist");
+        assert!(last.iter().all(|b| *b == 20));
+        let stats = cache.mmap_stats();
+        assert_eq!(stats.fallback_reads, 0);
+        assert!(stats.mapped_reads >= 202, "{stats:?}");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_disk_cache_concurrent_puts_to_one_key() {
+        const SIZE: usize = 64 * 1024;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let cache = Arc::new(
+            DiskCache::new(DiskCacheConfig::new(temp_dir.path()))
+                .expect("Operation should succeed"),
+        );
+        let key = RibbitKey::new("versions", "us");
+
+        let writers: Vec<_> = (1..=8u8)
+            .map(|version| {
+                let cache = Arc::clone(&cache);
+                let key = key.clone();
+                tokio::spawn(async move {
+                    for _ in 0..10 {
+                        cache
+                            .put(key.clone(), Bytes::from(vec![version; SIZE]))
+                            .await
+                            .expect("Concurrent put should succeed");
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.await.expect("Writer should not panic");
+        }
+
+        // One complete version wins, and no temporary files are left
+        let data = cache
+            .get(&key)
+            .await
+            .expect("Operation should succeed")
+            .expect("Entry should exist");
+        assert_eq!(data.len(), SIZE);
+        assert!(data.iter().all(|b| *b == data[0]), "torn write");
+        let path = cache.get_file_path(&key);
+        let files = fs::read_dir(path.parent().expect("Entry should have a parent"))
+            .expect("Operation should succeed")
+            .count();
+        assert_eq!(files, 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_disk_cache_enforce_limits_during_large_put() {
+        const SIZE: usize = 4 * 1024 * 1024;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        // Every scan evicts all files it counts
+        let cache = Arc::new(
+            DiskCache::new(DiskCacheConfig::new(temp_dir.path()).with_max_files(1))
+                .expect("Operation should succeed"),
+        );
+        let done = Arc::new(AtomicBool::new(false));
+
+        let enforcer = {
+            let cache = Arc::clone(&cache);
+            let done = Arc::clone(&done);
+            tokio::task::spawn_blocking(move || {
+                while !done.load(Ordering::Acquire) {
+                    cache.enforce_limits().expect("Operation should succeed");
+                }
+            })
+        };
+
+        let mut results = Vec::new();
+        for i in 0..20 {
+            results.push(
+                cache
+                    .put(
+                        RibbitKey::new(format!("versions{i}"), "us"),
+                        Bytes::from(vec![7; SIZE]),
+                    )
+                    .await,
+            );
+        }
+        done.store(true, Ordering::Release);
+        enforcer.await.expect("Enforcer should not panic");
+
+        // The in-flight temporary files must not be evicted
+        for result in results {
+            result.expect("Put should succeed while limits are enforced");
+        }
+    }
 }
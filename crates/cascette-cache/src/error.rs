@@ -62,6 +62,10 @@ pub enum CacheError {
     #[error("Invalid content key format: {0}")]
     InvalidContentKey(String),
 
+    /// Cache key string is not the canonical form of any key
+    #[error("Invalid cache key: {0}")]
+    InvalidCacheKey(String),
+
     /// Storage configuration error (e.g., unavailable storage backend)
     #[error("Configuration error: {0}")]
     Config(String),
@@ -71,6 +75,31 @@ pub enum CacheError {
     StorageQuotaExceeded,
 }
 
+/// IO errors are not `Clone`; a clone keeps the kind and message only
+impl Clone for CacheError {
+    fn clone(&self) -> Self {
+        match self {
+            Self::KeyNotFound(key) => Self::KeyNotFound(key.clone()),
+            Self::EntryExpired(key) => Self::EntryExpired(key.clone()),
+            Self::CapacityExceeded => Self::CapacityExceeded,
+            Self::InvalidConfiguration(msg) => Self::InvalidConfiguration(msg.clone()),
+            Self::Serialization(msg) => Self::Serialization(msg.clone()),
+            Self::Deserialization(msg) => Self::Deserialization(msg.clone()),
+            Self::Io(err) => Self::Io(std::io::Error::new(err.kind(), err.to_string())),
+            Self::Backend(msg) => Self::Backend(msg.clone()),
+            Self::Invalidation(msg) => Self::Invalidation(msg.clone()),
+            Self::LockTimeout(msg) => Self::LockTimeout(msg.clone()),
+            Self::Corruption(msg) => Self::Corruption(msg.clone()),
+            Self::ContentValidationFailed(msg) => Self::ContentValidationFailed(msg.clone()),
+            Self::ContentParsingFailed(msg) => Self::ContentParsingFailed(msg.clone()),
+            Self::InvalidContentKey(msg) => Self::InvalidContentKey(msg.clone()),
+            Self::InvalidCacheKey(msg) => Self::InvalidCacheKey(msg.clone()),
+            Self::Config(msg) => Self::Config(msg.clone()),
+            Self::StorageQuotaExceeded => Self::StorageQuotaExceeded,
+        }
+    }
+}
+
 impl From<hex::FromHexError> for CacheError {
     fn from(err: hex::FromHexError) -> Self {
         Self::Deserialization(err.to_string())
@@ -90,7 +119,7 @@ impl From<hex::FromHexError> for CacheError {
 /// This enum extends basic cache errors with NGDP/CASC-specific error types that provide
 /// meaningful context for content validation, BLTE operations, CDN interactions, and
 /// streaming operations commonly used in NGDP workloads.
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum NgdpCacheError {
     /// Content validation failed for a specific content key
     ///
@@ -244,6 +273,10 @@ mod tests {
                 assert!(error_str.contains("Invalid content key format"));
                 assert!(error_str.contains(msg));
             }
+            CacheError::InvalidCacheKey(msg) => {
+                assert!(error_str.contains("Invalid cache key"));
+                assert!(error_str.contains(msg));
+            }
             CacheError::Config(msg) => {
                 assert!(error_str.contains("Configuration error"));
                 assert!(error_str.contains(msg));
@@ -308,6 +341,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cache_error_clone_keeps_io_kind() {
+        let error = CacheError::from(IoError::new(ErrorKind::PermissionDenied, "Access denied"));
+        let clone = error.clone();
+        assert_eq!(clone.to_string(), error.to_string());
+        assert!(matches!(clone, CacheError::Io(err) if err.kind() == ErrorKind::PermissionDenied));
+    }
+
     #[test]
     fn test_cache_error_debug() {
         let error = CacheError::KeyNotFound("debug-test".to_string());
@@ -371,6 +412,7 @@ mod tests {
             CacheError::ContentValidationFailed(String::new()),
             CacheError::ContentParsingFailed(String::new()),
             CacheError::InvalidContentKey(String::new()),
+            CacheError::InvalidCacheKey(String::new()),
             CacheError::Config(String::new()),
             CacheError::StorageQuotaExceeded,
         ];
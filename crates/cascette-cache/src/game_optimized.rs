@@ -9,6 +9,7 @@
 #![allow(clippy::cast_precision_loss)] // Statistics/metrics calculations intentionally accept precision loss
 #![allow(clippy::suboptimal_flops)] // Exponential moving average clarity is more important than FMA optimization
 
+use cascette_formats::bpsv::{BpsvBuilder, BpsvDocument, BpsvField, BpsvType, BpsvValue};
 use std::sync::Arc;
 use std::{
     collections::{HashMap, VecDeque},
@@ -148,6 +149,103 @@ impl CacheAccessAnalyzer {
         patterns.sort_by(|a, b| b.1.access_count.cmp(&a.1.access_count));
         patterns
     }
+
+    /// Recommend key-type prefixes for bulk eviction
+    ///
+    /// Each tracked key type is scored by combining its miss rate, how rarely
+    /// it appears in the recent access history, and its average entry size
+    /// relative to the largest tracked type. Returns up to `budget` key types,
+    /// highest score (best eviction candidate) first.
+    pub fn recommend_eviction_candidates(&self, budget: usize) -> Vec<String> {
+        const MISS_WEIGHT: f64 = 0.4;
+        const COLD_WEIGHT: f64 = 0.35;
+        const SIZE_WEIGHT: f64 = 0.25;
+
+        if budget == 0 {
+            return Vec::new();
+        }
+
+        let recent = self.recent_access_counts();
+        let Ok(pattern_map) = self.access_patterns.lock() else {
+            return Vec::new();
+        };
+
+        let max_recent = recent.values().copied().max().unwrap_or(0).max(1) as f64;
+        let max_size = pattern_map
+            .values()
+            .map(|p| p.avg_size)
+            .max()
+            .unwrap_or(0)
+            .max(1) as f64;
+
+        let mut scored: Vec<(f64, &String)> = pattern_map
+            .iter()
+            .map(|(key_type, pattern)| {
+                let recent_count = recent.get(key_type).copied().unwrap_or(0) as f64;
+                let miss = 1.0 - pattern.hit_rate.clamp(0.0, 1.0);
+                let cold = 1.0 - recent_count / max_recent;
+                let size = pattern.avg_size as f64 / max_size;
+                let score = MISS_WEIGHT * miss + COLD_WEIGHT * cold + SIZE_WEIGHT * size;
+                (score, key_type)
+            })
+            .collect();
+
+        // Highest score first, key type as a deterministic tie-breaker
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        scored
+            .into_iter()
+            .take(budget)
+            .map(|(_, key_type)| key_type.clone())
+            .collect()
+    }
+
+    /// Export access pattern statistics as a BPSV report
+    ///
+    /// Contains the same key types as [`Self::get_access_patterns`], in the
+    /// same order. Hit rates are formatted with four decimal places since
+    /// BPSV has no floating point type.
+    pub fn access_patterns_as_bpsv(&self) -> BpsvDocument {
+        let recent = self.recent_access_counts();
+
+        let mut builder = BpsvBuilder::new();
+        builder.add_fields(vec![
+            BpsvField::new("KeyType", BpsvType::String(0)),
+            BpsvField::new("AccessCount", BpsvType::Dec(8)),
+            BpsvField::new("RecentAccesses", BpsvType::Dec(8)),
+            BpsvField::new("HitRate", BpsvType::String(0)),
+            BpsvField::new("AvgSize", BpsvType::Dec(8)),
+            BpsvField::new("AvgIntervalMs", BpsvType::Dec(8)),
+        ]);
+
+        for (key_type, stats) in self.get_access_patterns() {
+            let recent_count = recent.get(&key_type).copied().unwrap_or(0);
+            let row = vec![
+                BpsvValue::String(key_type),
+                BpsvValue::Dec(i64::try_from(stats.access_count).unwrap_or(i64::MAX)),
+                BpsvValue::Dec(i64::try_from(recent_count).unwrap_or(i64::MAX)),
+                BpsvValue::String(format!("{:.4}", stats.hit_rate)),
+                BpsvValue::Dec(i64::try_from(stats.avg_size).unwrap_or(i64::MAX)),
+                BpsvValue::Dec(i64::try_from(stats.avg_interval.as_millis()).unwrap_or(i64::MAX)),
+            ];
+            // Row width always matches the six fields above
+            if builder.add_row(row).is_err() {
+                break;
+            }
+        }
+
+        builder.build()
+    }
+
+    /// Count accesses per key type in the recent access history
+    fn recent_access_counts(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        if let Ok(accesses) = self.recent_accesses.lock() {
+            for event in accesses.iter() {
+                *counts.entry(event.key_type.clone()).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
 }
 
 /// Statistics for a specific access pattern
@@ -198,4 +296,88 @@ mod tests {
         assert!(content_pattern.1.access_count >= 5);
         assert!(content_pattern.1.hit_rate > 0.4); // Should reflect exponential moving average
     }
+
+    #[test]
+    fn test_recommend_eviction_candidates_ranking() {
+        let analyzer = CacheAccessAnalyzer::new(AnalyzerConfig::default());
+
+        // Hot, small, always hits: worst eviction candidate
+        for _ in 0..20 {
+            analyzer.record_access("config", true, 1_000);
+        }
+        // Warm, medium, mixed hits
+        for i in 0..10 {
+            analyzer.record_access("encoding", i % 2 == 0, 50_000);
+        }
+        // Cold, large, always misses: best eviction candidate
+        analyzer.record_access("archive", false, 4_000_000);
+
+        let candidates = analyzer.recommend_eviction_candidates(3);
+        assert_eq!(candidates, vec!["archive", "encoding", "config"]);
+
+        // Budget truncates from the top of the ranking
+        assert_eq!(analyzer.recommend_eviction_candidates(1), vec!["archive"]);
+        assert!(analyzer.recommend_eviction_candidates(0).is_empty());
+    }
+
+    #[test]
+    fn test_recommend_eviction_candidates_size_breaks_hit_rate_tie() {
+        let analyzer = CacheAccessAnalyzer::new(AnalyzerConfig::default());
+
+        // Identical access histories, only the size differs
+        for _ in 0..5 {
+            analyzer.record_access("small", false, 100);
+            analyzer.record_access("large", false, 10_000);
+        }
+
+        let candidates = analyzer.recommend_eviction_candidates(10);
+        assert_eq!(candidates, vec!["large", "small"]);
+    }
+
+    #[test]
+    fn test_access_patterns_as_bpsv() {
+        let analyzer = CacheAccessAnalyzer::new(AnalyzerConfig::default());
+        for _ in 0..6 {
+            analyzer.record_access("content", true, 5000);
+        }
+        // Below min_pattern_accesses, not reported
+        analyzer.record_access("rare", false, 10);
+
+        let doc = analyzer.access_patterns_as_bpsv();
+        assert_eq!(
+            doc.field_names(),
+            vec![
+                "KeyType",
+                "AccessCount",
+                "RecentAccesses",
+                "HitRate",
+                "AvgSize",
+                "AvgIntervalMs"
+            ]
+        );
+        assert_eq!(doc.row_count(), 1);
+
+        let row = &doc.rows()[0];
+        let schema = doc.schema();
+        assert_eq!(
+            row.get_by_name("KeyType", schema)
+                .and_then(|v| v.as_string()),
+            Some("content")
+        );
+        assert_eq!(
+            row.get_by_name("AccessCount", schema)
+                .and_then(|v| v.as_dec()),
+            Some(6)
+        );
+        assert_eq!(
+            row.get_by_name("RecentAccesses", schema)
+                .and_then(|v| v.as_dec()),
+            Some(6)
+        );
+
+        // Round-trips through the BPSV text format
+        let text = cascette_formats::bpsv::format(&doc);
+        let parsed = cascette_formats::bpsv::parse(&text).expect("report should parse");
+        assert_eq!(parsed.row_count(), 1);
+    }
 }
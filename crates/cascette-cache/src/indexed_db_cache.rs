@@ -618,6 +618,15 @@ impl<K: CacheKey + 'static> AsyncCache<K> for IndexedDbCache<K> {
         self.count_entries().await
     }
 
+    async fn keys(&self) -> CacheResult<Vec<K>> {
+        let entries = self.get_all_entries().await?;
+        Ok(entries
+            .iter()
+            .filter(|e| !e.is_expired())
+            .filter_map(|e| K::from_cache_key(&e.key))
+            .collect())
+    }
+
     async fn is_empty(&self) -> CacheResult<bool> {
         Ok(self.size().await? == 0)
     }
@@ -3,14 +3,46 @@
 //! This module defines the key types used across different cache layers in the
 //! NGDP/CASC system. Each key type is designed for specific use cases and
 //! provides efficient serialization, hashing, and comparison.
+//!
+//! # Canonical string form
+//!
+//! Every key renders to a canonical string through [`CacheKey::as_cache_key`]
+//! and `Display`, and parses back from it through `FromStr`. The first
+//! `:`-separated segment names the key family (`ribbit`, `config`, `blte`,
+//! `content`, `index`, `manifest`, `root`, `encoding`, `archive`), so keys of
+//! different kinds can never render to the same string:
+//!
+//! ```text
+//! ribbit:us:summary                 RibbitKey (region, endpoint)
+//! ribbit:eu:wow:versions            RibbitKey (region, product, endpoint)
+//! config:buildconfig:<hash>         ConfigKey
+//! blte:<ekey>[:<block>]             BlteKey
+//! blte:raw|decompressed:<ckey>:b<n> BlteBlockKey
+//! content:<ckey>                    ContentCacheKey
+//! index:<archive>:<hash>            ArchiveIndexKey
+//! manifest:<type>:<ckey>[:<ver>]    ManifestKey
+//! root:raw|parsed:<ckey>[:v<n>]     RootFileKey
+//! encoding:raw|parsed:<ekey>[:p<n>] EncodingFileKey
+//! archive:<archive>:<offset>+<len>  ArchiveRangeKey
+//! ```
+//!
+//! `BlteKey` and `BlteBlockKey` share the `blte` prefix and are told apart
+//! by the second segment, which is a 32-digit hex key for `BlteKey` and
+//! `raw` or `decompressed` for `BlteBlockKey`. Free-form string fields have
+//! `%` and `:` percent-escaped, so a field can never be mistaken for a
+//! separator. Keys are lowercase hex throughout, and only the canonical
+//! string of a key parses; [`TypedCacheKey`] reconstructs a key of any kind.
 
 #![allow(missing_docs)]
 
 use cascette_crypto::{ContentKey, EncodingKey, Jenkins96};
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Write};
+use std::str::FromStr;
 use std::sync::OnceLock;
 
+use crate::error::CacheError;
+
 /// Pre-computed hash for fast cache key lookups.
 /// Uses Jenkins96, optimized for NGDP workloads with hot path caching.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -42,6 +74,22 @@ impl FastHash {
     }
 }
 
+/// Free-form key field with `%` and `:` percent-escaped
+struct Escaped<'a>(&'a str);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '%' => f.write_str("%25")?,
+                ':' => f.write_str("%3A")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Reduces allocations for NGDP-specific key patterns
 struct CacheKeyBuffer {
     buffer: String,
@@ -56,23 +104,22 @@ impl CacheKeyBuffer {
 
     fn format_ribbit(&mut self, region: &str, endpoint: &str, product: Option<&str>) -> &str {
         self.buffer.clear();
-        self.buffer.push_str("ribbit:");
-        self.buffer.push_str(region);
+        let _ = write!(&mut self.buffer, "ribbit:{}", Escaped(region));
         if let Some(p) = product {
-            self.buffer.push(':');
-            self.buffer.push_str(p);
+            let _ = write!(&mut self.buffer, ":{}", Escaped(p));
         }
-        self.buffer.push(':');
-        self.buffer.push_str(endpoint);
+        let _ = write!(&mut self.buffer, ":{}", Escaped(endpoint));
         &self.buffer
     }
 
     fn format_config(&mut self, config_type: &str, hash: &str) -> &str {
         self.buffer.clear();
-        self.buffer.push_str("config:");
-        self.buffer.push_str(config_type);
-        self.buffer.push(':');
-        self.buffer.push_str(hash);
+        let _ = write!(
+            &mut self.buffer,
+            "config:{}:{}",
+            Escaped(config_type),
+            Escaped(hash)
+        );
         &self.buffer
     }
 
@@ -392,8 +439,13 @@ impl ArchiveIndexKey {
     }
 
     pub fn as_cache_key(&self) -> &str {
-        self.cached_key
-            .get_or_init(|| format!("index:{}:{}", self.archive_name, self.index_hash))
+        self.cached_key.get_or_init(|| {
+            format!(
+                "index:{}:{}",
+                Escaped(&self.archive_name),
+                Escaped(&self.index_hash)
+            )
+        })
     }
 
     pub fn fast_hash(&self) -> FastHash {
@@ -467,9 +519,15 @@ impl ManifestKey {
         self.cached_key.get_or_init(|| match &self.version {
             Some(version) => format!(
                 "manifest:{}:{}:{}",
-                self.manifest_type, self.content_key, version
+                Escaped(&self.manifest_type),
+                self.content_key,
+                Escaped(version)
+            ),
+            None => format!(
+                "manifest:{}:{}",
+                Escaped(&self.manifest_type),
+                self.content_key
             ),
-            None => format!("manifest:{}:{}", self.manifest_type, self.content_key),
         })
     }
 
@@ -694,7 +752,9 @@ impl ArchiveRangeKey {
         self.cached_key.get_or_init(|| {
             format!(
                 "archive:{}:{}+{}",
-                self.archive_id, self.start_offset, self.length
+                Escaped(&self.archive_id),
+                self.start_offset,
+                self.length
             )
         })
     }
@@ -800,6 +860,26 @@ pub trait CacheKey: fmt::Debug + Clone + PartialEq + Eq + std::hash::Hash + Send
     fn fast_hash(&self) -> FastHash {
         FastHash::from_string(self.as_cache_key())
     }
+
+    /// Kind of the key, derived from its canonical string
+    ///
+    /// `None` for keys outside the built-in families.
+    fn key_kind(&self) -> Option<CacheKeyKind> {
+        CacheKeyKind::of(self.as_cache_key())
+    }
+
+    /// Key with the canonical string `s`
+    ///
+    /// Lets persistent caches enumerate the keys of entries they only know
+    /// by their stored name. `None` if `s` is not a key of this type; the
+    /// default never parses.
+    fn from_cache_key(s: &str) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        let _ = s;
+        None
+    }
 }
 
 impl CacheKey for RibbitKey {
@@ -810,6 +890,10 @@ impl CacheKey for RibbitKey {
     fn fast_hash(&self) -> FastHash {
         RibbitKey::fast_hash(self)
     }
+
+    fn from_cache_key(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
 }
 
 impl CacheKey for ConfigKey {
@@ -820,6 +904,10 @@ impl CacheKey for ConfigKey {
     fn fast_hash(&self) -> FastHash {
         ConfigKey::fast_hash(self)
     }
+
+    fn from_cache_key(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
 }
 
 impl CacheKey for BlteKey {
@@ -830,6 +918,10 @@ impl CacheKey for BlteKey {
     fn fast_hash(&self) -> FastHash {
         BlteKey::fast_hash(self)
     }
+
+    fn from_cache_key(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
 }
 
 impl CacheKey for ContentCacheKey {
@@ -840,6 +932,10 @@ impl CacheKey for ContentCacheKey {
     fn fast_hash(&self) -> FastHash {
         ContentCacheKey::fast_hash(self)
     }
+
+    fn from_cache_key(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
 }
 
 impl CacheKey for ArchiveIndexKey {
@@ -850,6 +946,10 @@ impl CacheKey for ArchiveIndexKey {
     fn fast_hash(&self) -> FastHash {
         ArchiveIndexKey::fast_hash(self)
     }
+
+    fn from_cache_key(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
 }
 
 impl CacheKey for ManifestKey {
@@ -860,6 +960,10 @@ impl CacheKey for ManifestKey {
     fn fast_hash(&self) -> FastHash {
         ManifestKey::fast_hash(self)
     }
+
+    fn from_cache_key(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
 }
 
 impl CacheKey for RootFileKey {
@@ -870,6 +974,10 @@ impl CacheKey for RootFileKey {
     fn fast_hash(&self) -> FastHash {
         RootFileKey::fast_hash(self)
     }
+
+    fn from_cache_key(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
 }
 
 impl CacheKey for EncodingFileKey {
@@ -880,6 +988,10 @@ impl CacheKey for EncodingFileKey {
     fn fast_hash(&self) -> FastHash {
         EncodingFileKey::fast_hash(self)
     }
+
+    fn from_cache_key(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
 }
 
 impl CacheKey for ArchiveRangeKey {
@@ -890,6 +1002,10 @@ impl CacheKey for ArchiveRangeKey {
     fn fast_hash(&self) -> FastHash {
         ArchiveRangeKey::fast_hash(self)
     }
+
+    fn from_cache_key(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
 }
 
 impl CacheKey for BlteBlockKey {
@@ -900,6 +1016,419 @@ impl CacheKey for BlteBlockKey {
     fn fast_hash(&self) -> FastHash {
         BlteBlockKey::fast_hash(self)
     }
+
+    fn from_cache_key(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+}
+
+/// Kind of a cache key, for filtering dumps and invalidation messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CacheKeyKind {
+    Ribbit,
+    Config,
+    Blte,
+    Content,
+    ArchiveIndex,
+    Manifest,
+    RootFile,
+    EncodingFile,
+    ArchiveRange,
+    BlteBlock,
+}
+
+impl CacheKeyKind {
+    /// Every key kind.
+    pub const ALL: [Self; 10] = [
+        Self::Ribbit,
+        Self::Config,
+        Self::Blte,
+        Self::Content,
+        Self::ArchiveIndex,
+        Self::Manifest,
+        Self::RootFile,
+        Self::EncodingFile,
+        Self::ArchiveRange,
+        Self::BlteBlock,
+    ];
+
+    /// Stable name of the kind, e.g. `"archive-range"`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Ribbit => "ribbit",
+            Self::Config => "config",
+            Self::Blte => "blte",
+            Self::Content => "content",
+            Self::ArchiveIndex => "archive-index",
+            Self::Manifest => "manifest",
+            Self::RootFile => "root-file",
+            Self::EncodingFile => "encoding-file",
+            Self::ArchiveRange => "archive-range",
+            Self::BlteBlock => "blte-block",
+        }
+    }
+
+    /// Kind of the key with canonical string `canonical`, if it is one of
+    /// the built-in kinds.
+    ///
+    /// Only the prefix is inspected, plus the second segment to tell
+    /// `Blte` from `BlteBlock`.
+    ///
+    /// Keys mirroring CDN paths, `cdn/{path}/{config|data|patch}/{aa}/{bb}/{hash}`,
+    /// are classified by content type: config files as `Config`, archive
+    /// indices (`.index`) as `ArchiveIndex`, and data and patch files, which
+    /// are BLTE-encoded, as `Blte`.
+    pub fn of(canonical: &str) -> Option<Self> {
+        if let Some(cdn_path) = canonical.strip_prefix("cdn/") {
+            return Self::of_cdn_path(cdn_path);
+        }
+        let mut segments = canonical.split(':');
+        match segments.next()? {
+            "blte" => match segments.next() {
+                Some("raw" | "decompressed") => Some(Self::BlteBlock),
+                _ => Some(Self::Blte),
+            },
+            prefix => Self::ALL.into_iter().find(|kind| kind.prefix() == prefix),
+        }
+    }
+
+    /// Kind of a CDN path key with the `cdn/` prefix removed
+    fn of_cdn_path(path: &str) -> Option<Self> {
+        let segments: Vec<&str> = path.split('/').collect();
+        // {path...}/{type}/{aa}/{bb}/{hash}, with at least one path segment
+        let [_, .., content_type, _, _, file] = segments.as_slice() else {
+            return None;
+        };
+        match *content_type {
+            "config" => Some(Self::Config),
+            "data"
+                if std::path::Path::new(file)
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("index")) =>
+            {
+                Some(Self::ArchiveIndex)
+            }
+            "data" | "patch" => Some(Self::Blte),
+            _ => None,
+        }
+    }
+
+    /// First segment of the canonical string of keys of this kind.
+    ///
+    /// `Blte` and `BlteBlock` share the `blte` prefix.
+    pub const fn prefix(self) -> &'static str {
+        match self {
+            Self::Ribbit => "ribbit",
+            Self::Config => "config",
+            Self::Blte | Self::BlteBlock => "blte",
+            Self::Content => "content",
+            Self::ArchiveIndex => "index",
+            Self::Manifest => "manifest",
+            Self::RootFile => "root",
+            Self::EncodingFile => "encoding",
+            Self::ArchiveRange => "archive",
+        }
+    }
+}
+
+impl fmt::Display for CacheKeyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for CacheKeyKind {
+    type Err = CacheError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| CacheError::InvalidCacheKey(format!("unknown key kind: {s}")))
+    }
+}
+
+/// A cache key of any kind, reconstructed from its canonical string.
+///
+/// Used where keys cross a process boundary or are shown to a user, such as
+/// invalidation messages and cache dumps.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypedCacheKey {
+    Ribbit(RibbitKey),
+    Config(ConfigKey),
+    Blte(BlteKey),
+    Content(ContentCacheKey),
+    ArchiveIndex(ArchiveIndexKey),
+    Manifest(ManifestKey),
+    RootFile(RootFileKey),
+    EncodingFile(EncodingFileKey),
+    ArchiveRange(ArchiveRangeKey),
+    BlteBlock(BlteBlockKey),
+}
+
+impl TypedCacheKey {
+    /// Parse the canonical string of a key.
+    ///
+    /// Only canonical strings are accepted: uppercase hex, unnecessary
+    /// escapes or leading zeros are rejected, so each key has exactly one
+    /// string form.
+    pub fn parse(s: &str) -> Result<Self, CacheError> {
+        let invalid = || CacheError::InvalidCacheKey(s.to_string());
+        let (prefix, rest) = s.split_once(':').ok_or_else(invalid)?;
+        let parts: Vec<&str> = rest.split(':').collect();
+
+        let key = match (prefix, parts.as_slice()) {
+            ("ribbit", [region, endpoint]) => Self::Ribbit(RibbitKey::new(
+                unescape(endpoint).ok_or_else(invalid)?,
+                unescape(region).ok_or_else(invalid)?,
+            )),
+            ("ribbit", [region, product, endpoint]) => Self::Ribbit(RibbitKey::with_product(
+                unescape(endpoint).ok_or_else(invalid)?,
+                unescape(region).ok_or_else(invalid)?,
+                unescape(product).ok_or_else(invalid)?,
+            )),
+            ("config", [config_type, hash]) => Self::Config(ConfigKey::new(
+                unescape(config_type).ok_or_else(invalid)?,
+                unescape(hash).ok_or_else(invalid)?,
+            )),
+            ("blte", [block_type, ckey, block]) if is_flag(block_type, "decompressed") => {
+                let content_key = ContentKey::from_hex(ckey).map_err(|_| invalid())?;
+                let block_index = number(block, "b").ok_or_else(invalid)?;
+                Self::BlteBlock(if *block_type == "raw" {
+                    BlteBlockKey::new_raw(content_key, block_index)
+                } else {
+                    BlteBlockKey::new_decompressed(content_key, block_index)
+                })
+            }
+            ("blte", [ekey]) => Self::Blte(BlteKey::new(
+                EncodingKey::from_hex(ekey).map_err(|_| invalid())?,
+            )),
+            ("blte", [ekey, block]) => Self::Blte(BlteKey::with_block(
+                EncodingKey::from_hex(ekey).map_err(|_| invalid())?,
+                number(block, "").ok_or_else(invalid)?,
+            )),
+            ("content", [ckey]) => Self::Content(ContentCacheKey::new(
+                ContentKey::from_hex(ckey).map_err(|_| invalid())?,
+            )),
+            ("index", [archive_name, index_hash]) => Self::ArchiveIndex(ArchiveIndexKey::new(
+                unescape(archive_name).ok_or_else(invalid)?,
+                unescape(index_hash).ok_or_else(invalid)?,
+            )),
+            ("manifest", [manifest_type, ckey, version @ ..]) if version.len() <= 1 => {
+                let manifest_type = unescape(manifest_type).ok_or_else(invalid)?;
+                let content_key = ContentKey::from_hex(ckey).map_err(|_| invalid())?;
+                Self::Manifest(match version {
+                    [version] => ManifestKey::with_version(
+                        manifest_type,
+                        content_key,
+                        unescape(version).ok_or_else(invalid)?,
+                    ),
+                    _ => ManifestKey::new(manifest_type, content_key),
+                })
+            }
+            ("root", [content_type, ckey, version @ ..])
+                if is_flag(content_type, "parsed") && version.len() <= 1 =>
+            {
+                let is_parsed = *content_type == "parsed";
+                let content_key = ContentKey::from_hex(ckey).map_err(|_| invalid())?;
+                Self::RootFile(match version {
+                    [version] => RootFileKey::with_version(
+                        content_key,
+                        is_parsed,
+                        number(version, "v").ok_or_else(invalid)?,
+                    ),
+                    _ if is_parsed => RootFileKey::new_parsed(content_key),
+                    _ => RootFileKey::new_raw(content_key),
+                })
+            }
+            ("encoding", [content_type, ekey, page @ ..])
+                if is_flag(content_type, "parsed") && page.len() <= 1 =>
+            {
+                let is_parsed = *content_type == "parsed";
+                let encoding_key = EncodingKey::from_hex(ekey).map_err(|_| invalid())?;
+                Self::EncodingFile(match page {
+                    [page] => EncodingFileKey::with_page(
+                        encoding_key,
+                        number(page, "p").ok_or_else(invalid)?,
+                        is_parsed,
+                    ),
+                    _ if is_parsed => EncodingFileKey::new_parsed(encoding_key),
+                    _ => EncodingFileKey::new_raw(encoding_key),
+                })
+            }
+            ("archive", [archive_id, range]) => {
+                let (offset, length) = range.split_once('+').ok_or_else(invalid)?;
+                Self::ArchiveRange(ArchiveRangeKey::new(
+                    unescape(archive_id).ok_or_else(invalid)?,
+                    number(offset, "").ok_or_else(invalid)?,
+                    number(length, "").ok_or_else(invalid)?,
+                ))
+            }
+            _ => return Err(invalid()),
+        };
+
+        // Rejects every non-canonical spelling of the key
+        if key.as_cache_key() == s {
+            Ok(key)
+        } else {
+            Err(invalid())
+        }
+    }
+
+    /// Kind of the key.
+    pub const fn kind(&self) -> CacheKeyKind {
+        match self {
+            Self::Ribbit(_) => CacheKeyKind::Ribbit,
+            Self::Config(_) => CacheKeyKind::Config,
+            Self::Blte(_) => CacheKeyKind::Blte,
+            Self::Content(_) => CacheKeyKind::Content,
+            Self::ArchiveIndex(_) => CacheKeyKind::ArchiveIndex,
+            Self::Manifest(_) => CacheKeyKind::Manifest,
+            Self::RootFile(_) => CacheKeyKind::RootFile,
+            Self::EncodingFile(_) => CacheKeyKind::EncodingFile,
+            Self::ArchiveRange(_) => CacheKeyKind::ArchiveRange,
+            Self::BlteBlock(_) => CacheKeyKind::BlteBlock,
+        }
+    }
+
+    pub fn as_cache_key(&self) -> &str {
+        match self {
+            Self::Ribbit(key) => key.as_cache_key(),
+            Self::Config(key) => key.as_cache_key(),
+            Self::Blte(key) => key.as_cache_key(),
+            Self::Content(key) => key.as_cache_key(),
+            Self::ArchiveIndex(key) => key.as_cache_key(),
+            Self::Manifest(key) => key.as_cache_key(),
+            Self::RootFile(key) => key.as_cache_key(),
+            Self::EncodingFile(key) => key.as_cache_key(),
+            Self::ArchiveRange(key) => key.as_cache_key(),
+            Self::BlteBlock(key) => key.as_cache_key(),
+        }
+    }
+}
+
+/// Whether `value` is `"raw"` or the alternative flag name.
+fn is_flag(value: &str, alternative: &str) -> bool {
+    value == "raw" || value == alternative
+}
+
+/// Parse a number with a fixed prefix, such as `b3` or `p12`.
+fn number<T: FromStr>(segment: &str, prefix: &str) -> Option<T> {
+    segment.strip_prefix(prefix)?.parse().ok()
+}
+
+/// Undo [`Escaped`], rejecting malformed escapes.
+fn unescape(segment: &str) -> Option<String> {
+    let mut out = String::with_capacity(segment.len());
+    let mut rest = segment;
+    while let Some(pos) = rest.find('%') {
+        out.push_str(&rest[..pos]);
+        let escape = rest.get(pos..pos + 3)?;
+        out.push(match escape {
+            "%25" => '%',
+            "%3A" => ':',
+            _ => return None,
+        });
+        rest = &rest[pos + 3..];
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+impl fmt::Display for TypedCacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_cache_key())
+    }
+}
+
+impl FromStr for TypedCacheKey {
+    type Err = CacheError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl CacheKey for TypedCacheKey {
+    fn as_cache_key(&self) -> &str {
+        Self::as_cache_key(self)
+    }
+    fn from_cache_key(s: &str) -> Option<Self> {
+        Self::parse(s).ok()
+    }
+}
+
+/// Wraps a key type in [`TypedCacheKey`] and parses it from its canonical
+/// string.
+macro_rules! typed_key_conversions {
+    ($($variant:ident($key:ty)),* $(,)?) => {
+        $(
+            impl From<$key> for TypedCacheKey {
+                fn from(key: $key) -> Self {
+                    Self::$variant(key)
+                }
+            }
+
+            impl FromStr for $key {
+                type Err = CacheError;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    match TypedCacheKey::parse(s)? {
+                        TypedCacheKey::$variant(key) => Ok(key),
+                        other => Err(CacheError::InvalidCacheKey(format!(
+                            "expected a {} key, found a {} key: {s}",
+                            CacheKeyKind::$variant,
+                            other.kind()
+                        ))),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+typed_key_conversions!(
+    Ribbit(RibbitKey),
+    Config(ConfigKey),
+    Blte(BlteKey),
+    Content(ContentCacheKey),
+    ArchiveIndex(ArchiveIndexKey),
+    Manifest(ManifestKey),
+    RootFile(RootFileKey),
+    EncodingFile(EncodingFileKey),
+    ArchiveRange(ArchiveRangeKey),
+    BlteBlock(BlteBlockKey),
+);
+
+#[cfg(feature = "serde")]
+impl Serialize for CacheKeyKind {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for CacheKeyKind {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Serializes as the canonical string form.
+#[cfg(feature = "serde")]
+impl Serialize for TypedCacheKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_cache_key())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for TypedCacheKey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let key = String::deserialize(deserializer)?;
+        Self::parse(&key).map_err(serde::de::Error::custom)
+    }
 }
 
 #[cfg(test)]
@@ -1178,6 +1707,258 @@ mod tests {
         assert_eq!(map.len(), 2);
     }
 
+    #[test]
+    fn test_typed_key_parse_existing_forms() {
+        let content_key = ContentKey::from_data(b"typed key");
+        let encoding_key = EncodingKey::from_data(b"typed key");
+
+        let keys: Vec<TypedCacheKey> = vec![
+            RibbitKey::new("summary", "us").into(),
+            RibbitKey::with_product("versions", "eu", "wow").into(),
+            ConfigKey::new("buildconfig", "abcd1234").into(),
+            BlteKey::new(encoding_key).into(),
+            BlteKey::with_block(encoding_key, 5).into(),
+            ContentCacheKey::new(content_key).into(),
+            ArchiveIndexKey::new("data.000", "hash123").into(),
+            ManifestKey::with_version("encoding", content_key, "v2").into(),
+            RootFileKey::with_version(content_key, true, 2).into(),
+            EncodingFileKey::with_page(encoding_key, 3, false).into(),
+            ArchiveRangeKey::new("data.001", 1024, 4096).into(),
+            BlteBlockKey::new_decompressed(content_key, 15).into(),
+        ];
+
+        for key in keys {
+            let parsed: TypedCacheKey = key.to_string().parse().expect("canonical key");
+            assert_eq!(parsed, key);
+        }
+
+        assert_eq!(
+            "ribbit:us:summary"
+                .parse::<RibbitKey>()
+                .expect("ribbit key"),
+            RibbitKey::new("summary", "us")
+        );
+        assert_eq!(
+            TypedCacheKey::parse(&format!("blte:raw:{content_key}:b0"))
+                .expect("block key")
+                .kind(),
+            CacheKeyKind::BlteBlock
+        );
+    }
+
+    #[test]
+    fn test_typed_key_escapes_separators() {
+        let key = RibbitKey::with_product("products/wow:cdns", "us", "100%");
+        assert_eq!(key.as_cache_key(), "ribbit:us:100%25:products/wow%3Acdns");
+        assert_eq!(
+            key.as_cache_key()
+                .parse::<RibbitKey>()
+                .expect("escaped key"),
+            key
+        );
+
+        // Without escaping these two would both render as ribbit:us:a:b
+        let with_product = RibbitKey::with_product("b", "us", "a");
+        let with_colon = RibbitKey::new("a:b", "us");
+        assert_ne!(with_product.as_cache_key(), with_colon.as_cache_key());
+    }
+
+    #[test]
+    fn test_typed_key_rejects_non_canonical() {
+        let content_key = ContentKey::from_data(b"typed key");
+        let upper = content_key.to_string().to_uppercase();
+
+        for input in [
+            "",
+            "ribbit",
+            "ribbit:us",
+            "unknown:us:summary",
+            "content:abcd",
+            &format!("content:{upper}"),
+            &format!("root:raw:{content_key}:v02"),
+            &format!("root:cooked:{content_key}"),
+            "archive:data.001:1024",
+            "archive:data.001:+1024+4096",
+            "config:buildconfig:abc%3a",
+            "config:buildconfig:abc%4",
+            "config:build%63onfig:abc",
+        ] {
+            assert!(
+                matches!(
+                    TypedCacheKey::parse(input),
+                    Err(CacheError::InvalidCacheKey(_))
+                ),
+                "{input} should not parse"
+            );
+        }
+
+        let err = format!("content:{content_key}")
+            .parse::<BlteKey>()
+            .expect_err("wrong kind");
+        assert!(err.to_string().contains("expected a blte key"), "{err}");
+    }
+
+    #[test]
+    fn test_cache_key_kind_names() {
+        for kind in CacheKeyKind::ALL {
+            assert_eq!(kind.to_string().parse::<CacheKeyKind>().ok(), Some(kind));
+        }
+        assert!("index".parse::<CacheKeyKind>().is_err());
+        assert_eq!(
+            CacheKeyKind::BlteBlock.prefix(),
+            CacheKeyKind::Blte.prefix()
+        );
+    }
+
+    #[test]
+    fn test_key_kind_from_canonical_string() {
+        let content_key = ContentKey::from_data(b"content");
+        assert_eq!(
+            RibbitKey::new("summary", "us").key_kind(),
+            Some(CacheKeyKind::Ribbit)
+        );
+        assert_eq!(
+            BlteKey::new(EncodingKey::from_data(b"blte")).key_kind(),
+            Some(CacheKeyKind::Blte)
+        );
+        assert_eq!(
+            BlteBlockKey::new_raw(content_key, 3).key_kind(),
+            Some(CacheKeyKind::BlteBlock)
+        );
+        assert_eq!(
+            ContentCacheKey::new(content_key).key_kind(),
+            Some(CacheKeyKind::Content)
+        );
+
+        let typed = TypedCacheKey::from(ArchiveRangeKey::new("data.001", 0, 16));
+        assert_eq!(typed.key_kind(), Some(typed.kind()));
+        assert_eq!(
+            CacheKeyKind::of("cdn/tpr/wow/config/ab/cd/abcd"),
+            Some(CacheKeyKind::Config)
+        );
+        assert_eq!(
+            CacheKeyKind::of("cdn/tpr/wow/data/ab/cd/abcd.index"),
+            Some(CacheKeyKind::ArchiveIndex)
+        );
+        assert_eq!(
+            CacheKeyKind::of("cdn/tpr/wow/data/ab/cd/abcd"),
+            Some(CacheKeyKind::Blte)
+        );
+        assert_eq!(
+            CacheKeyKind::of("cdn/tpr/wow/patch/ab/cd/abcd"),
+            Some(CacheKeyKind::Blte)
+        );
+        assert_eq!(CacheKeyKind::of("cdn/config/ab/cd/abcd"), None);
+        assert_eq!(CacheKeyKind::of("cdn/tpr/wow/install/ab/cd/abcd"), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_typed_key_serde() {
+        let key = TypedCacheKey::from(ArchiveRangeKey::new("data.001", 1024, 4096));
+        let json = serde_json::to_string(&key).expect("serialize");
+        assert_eq!(json, "\"archive:data.001:1024+4096\"");
+        assert_eq!(
+            serde_json::from_str::<TypedCacheKey>(&json).expect("deserialize"),
+            key
+        );
+
+        let kind = serde_json::to_string(&key.kind()).expect("serialize");
+        assert_eq!(kind, "\"archive-range\"");
+        assert!(serde_json::from_str::<TypedCacheKey>("\"archive:x\"").is_err());
+    }
+
+    mod proptest_tests {
+        use super::*;
+        use proptest::prelude::*;
+
+        fn content_key() -> impl Strategy<Value = ContentKey> {
+            prop::array::uniform16(any::<u8>()).prop_map(ContentKey::from_bytes)
+        }
+
+        fn encoding_key() -> impl Strategy<Value = EncodingKey> {
+            prop::array::uniform16(any::<u8>()).prop_map(EncodingKey::from_bytes)
+        }
+
+        /// Free-form fields, biased towards separators and escapes
+        fn field() -> impl Strategy<Value = String> {
+            prop_oneof![any::<String>(), "[a-z0-9:%+/._-]{0,12}"]
+        }
+
+        fn typed_key() -> impl Strategy<Value = TypedCacheKey> {
+            prop_oneof![
+                (field(), field(), prop::option::of(field())).prop_map(
+                    |(endpoint, region, product)| match product {
+                        Some(product) => RibbitKey::with_product(endpoint, region, product),
+                        None => RibbitKey::new(endpoint, region),
+                    }
+                    .into()
+                ),
+                (field(), field()).prop_map(|(t, hash)| ConfigKey::new(t, hash).into()),
+                (encoding_key(), prop::option::of(any::<u32>())).prop_map(|(ekey, block)| {
+                    match block {
+                        Some(block) => BlteKey::with_block(ekey, block),
+                        None => BlteKey::new(ekey),
+                    }
+                    .into()
+                }),
+                content_key().prop_map(|ckey| ContentCacheKey::new(ckey).into()),
+                (field(), field()).prop_map(|(name, hash)| ArchiveIndexKey::new(name, hash).into()),
+                (field(), content_key(), prop::option::of(field())).prop_map(
+                    |(t, ckey, version)| match version {
+                        Some(version) => ManifestKey::with_version(t, ckey, version),
+                        None => ManifestKey::new(t, ckey),
+                    }
+                    .into()
+                ),
+                (content_key(), any::<bool>(), prop::option::of(any::<u8>())).prop_map(
+                    |(ckey, is_parsed, version)| match (version, is_parsed) {
+                        (Some(version), _) => RootFileKey::with_version(ckey, is_parsed, version),
+                        (None, true) => RootFileKey::new_parsed(ckey),
+                        (None, false) => RootFileKey::new_raw(ckey),
+                    }
+                    .into()
+                ),
+                (
+                    encoding_key(),
+                    any::<bool>(),
+                    prop::option::of(any::<u32>())
+                )
+                    .prop_map(|(ekey, is_parsed, page)| match (page, is_parsed) {
+                        (Some(page), _) => EncodingFileKey::with_page(ekey, page, is_parsed),
+                        (None, true) => EncodingFileKey::new_parsed(ekey),
+                        (None, false) => EncodingFileKey::new_raw(ekey),
+                    }
+                    .into()),
+                (field(), any::<u64>(), any::<u32>())
+                    .prop_map(|(id, offset, len)| ArchiveRangeKey::new(id, offset, len).into()),
+                (content_key(), any::<u32>(), any::<bool>()).prop_map(
+                    |(ckey, block, decompressed)| if decompressed {
+                        BlteBlockKey::new_decompressed(ckey, block)
+                    } else {
+                        BlteBlockKey::new_raw(ckey, block)
+                    }
+                    .into()
+                ),
+            ]
+        }
+
+        proptest! {
+            /// Every key parses back from its canonical string
+            #[test]
+            fn typed_key_round_trip(key in typed_key()) {
+                let parsed = TypedCacheKey::parse(key.as_cache_key());
+                prop_assert_eq!(parsed.ok(), Some(key));
+            }
+
+            /// Distinct keys never share a canonical string
+            #[test]
+            fn typed_key_strings_are_unique(a in typed_key(), b in typed_key()) {
+                prop_assert_eq!(a == b, a.as_cache_key() == b.as_cache_key());
+            }
+        }
+    }
+
     // Additional tests for the rest of the functionality...
     // (keeping the existing tests but not repeating them all here)
 }
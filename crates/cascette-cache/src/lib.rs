@@ -11,7 +11,7 @@
 //! - **Multi-Layer Caching**: Support for hierarchical cache layers (L1, L2, L3, etc.)
 //! - **Type-Safe Keys**: Strongly-typed cache keys for different data types
 //! - **Async Operations**: Full async/await support for non-blocking cache operations
-//! - **Flexible Eviction**: Multiple eviction policies (LRU, LFU, TTL, size-based)
+//! - **Flexible Eviction**: Multiple eviction policies (LRU, LFU, ARC, TTL, size-based)
 //! - **Metrics**: Detailed performance and usage statistics
 //! - **Memory Pooling**: Optimized memory allocation for NGDP file patterns
 //! - **Thread-Safe**: Designed for high-concurrency NGDP server environments
@@ -255,6 +255,7 @@ pub mod game_optimized;
 pub mod key;
 pub mod pool;
 pub mod simd;
+pub mod singleflight;
 pub mod stats;
 pub mod traits;
 
@@ -299,6 +300,7 @@ pub use pool::{NgdpMemoryPool, NgdpSizeClass};
 pub use simd::{
     CpuFeatures, SimdHashOperations, SimdStats, detect_cpu_features, global_simd_stats,
 };
+pub use singleflight::SingleFlight;
 pub use stats::{CacheStats, FastCacheMetrics};
 // Native-only stats exports
 #[cfg(not(target_arch = "wasm32"))]
@@ -329,7 +331,7 @@ pub use validation::{
 
 // Re-export native cache implementations
 #[cfg(not(target_arch = "wasm32"))]
-pub use disk_cache::DiskCache;
+pub use disk_cache::{DiskCache, MmapStats};
 #[cfg(not(target_arch = "wasm32"))]
 pub use integration::{ArchiveOps, BlteBlockOps, EncodingFileOps, FormatConfig, RootFileOps};
 #[cfg(not(target_arch = "wasm32"))]
@@ -341,14 +343,15 @@ pub use multi_layer::{LayerStats, MultiLayerCacheImpl, MultiLayerStats as MultiL
 #[cfg(not(target_arch = "wasm32"))]
 pub use ngdp::{
     ArchiveCache, ArchiveMetadata, BlockMetadata, BlteBlockCache, ContentAddressedCache,
-    ContentValidationMetrics, NgdpResolutionCache, NgdpResolutionConfig, ResolutionMetrics,
+    ContentValidationMetrics, EntryStatus, NgdpResolutionCache, NgdpResolutionConfig,
+    RegionResolution, ResolutionMetrics, VerificationReport,
 };
 
 // Re-export CDN integration components (native only)
 #[cfg(not(target_arch = "wasm32"))]
 pub use cdn::{
     CdnArchiveCache, CdnBackedCache, CdnCacheBuilder, CdnCacheStack, CdnClient, CdnConfig,
-    CdnContentCache, CdnMetrics, CdnNgdpResolutionCache,
+    CdnConfigCache, CdnContentCache, CdnMetrics, CdnNgdpResolutionCache, PrefetchStats,
 };
 
 // ============================================================================
@@ -371,8 +374,9 @@ pub mod prelude {
         config::CacheConfig,
         error::{CacheError, CacheResult, NgdpCacheError, NgdpCacheResult, to_ngdp_result},
         key::{
-            ArchiveIndexKey, ArchiveRangeKey, BlteBlockKey, BlteKey, CacheKey, ConfigKey,
-            ContentCacheKey, EncodingFileKey, FastHash, ManifestKey, RibbitKey, RootFileKey,
+            ArchiveIndexKey, ArchiveRangeKey, BlteBlockKey, BlteKey, CacheKey, CacheKeyKind,
+            ConfigKey, ContentCacheKey, EncodingFileKey, FastHash, ManifestKey, RibbitKey,
+            RootFileKey, TypedCacheKey,
         },
         pool::{NgdpMemoryPool, NgdpSizeClass, allocate_thread_local, deallocate_thread_local},
         stats::{CacheStats, FastCacheMetrics},
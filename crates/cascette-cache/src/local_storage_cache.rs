@@ -431,6 +431,26 @@ impl<K: CacheKey + 'static> AsyncCache<K> for LocalStorageCache<K> {
         Ok(count)
     }
 
+    async fn keys(&self) -> CacheResult<Vec<K>> {
+        let storage = get_local_storage()?;
+        let mut keys = Vec::new();
+
+        for storage_key in self.get_all_keys()? {
+            let Some(cache_key) = storage_key.strip_prefix(KEY_PREFIX) else {
+                continue;
+            };
+            if let Ok(Some(value_str)) = storage.get_item(&storage_key)
+                && let Ok(entry) = serde_json::from_str::<StoredEntry>(&value_str)
+                && !entry.is_expired()
+                && let Some(key) = K::from_cache_key(cache_key)
+            {
+                keys.push(key);
+            }
+        }
+
+        Ok(keys)
+    }
+
     async fn is_empty(&self) -> CacheResult<bool> {
         Ok(self.size().await? == 0)
     }
@@ -3,6 +3,7 @@
 //! This module provides a high-performance in-memory cache using:
 //! - DashMap for concurrent access with minimal lock contention
 //! - LRU eviction policy with atomic timestamp tracking
+//! - ARC (Adaptive Replacement Cache) eviction for scan-heavy workloads
 //! - Memory-optimized entry storage with `bytes::Bytes`
 //! - Background cleanup tasks for expired entries
 //! - Metrics collection with the optimized stats system
@@ -16,15 +17,19 @@ use crate::{
     config::MemoryCacheConfig,
     error::{CacheError, CacheResult},
     key::CacheKey,
+    singleflight::SingleFlight,
     stats::AtomicCacheMetrics,
-    traits::AsyncCache,
+    traits::{AsyncCache, EvictionPolicy},
+    validation::{ValidationHooks, check_before_put},
 };
 use async_trait::async_trait;
 use bytes::Bytes;
 use dashmap::DashMap;
+use futures::future::BoxFuture;
 use std::{
+    collections::{BTreeMap, HashMap},
     sync::{
-        Arc,
+        Arc, Mutex, MutexGuard, PoisonError,
         atomic::{AtomicU64, AtomicUsize, Ordering},
     },
     time::{Duration, Instant},
@@ -91,6 +96,174 @@ impl MemoryCacheEntryInner {
     }
 }
 
+/// The four ARC lists a key can be on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArcList {
+    /// T1: resident, seen once recently
+    Recent = 0,
+    /// T2: resident, seen at least twice
+    Frequent = 1,
+    /// B1: recently evicted from T1
+    RecentGhost = 2,
+    /// B2: recently evicted from T2
+    FrequentGhost = 3,
+}
+
+/// Adaptive Replacement Cache bookkeeping for [`EvictionPolicy::Arc`]
+///
+/// New keys enter T1 and move to T2 when hit again, so a scan of one-off
+/// keys only cycles through T1. Evicted keys are remembered on the ghost
+/// lists B1 and B2. Admitting a key found on a ghost list moves the target
+/// size `p` of T1 towards the list that would have kept it. Each list is
+/// ordered oldest first by an insertion sequence number.
+#[derive(Debug)]
+struct ArcState<K> {
+    capacity: usize,
+    /// Target size `p` of T1
+    target: usize,
+    lists: [BTreeMap<u64, K>; 4],
+    location: HashMap<K, (ArcList, u64)>,
+    next_seq: u64,
+}
+
+impl<K: CacheKey> ArcState<K> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            target: 0,
+            lists: Default::default(),
+            location: HashMap::new(),
+            next_seq: 0,
+        }
+    }
+
+    fn len(&self, list: ArcList) -> usize {
+        self.lists[list as usize].len()
+    }
+
+    fn resident(&self) -> usize {
+        self.len(ArcList::Recent) + self.len(ArcList::Frequent)
+    }
+
+    /// Add `key` as the newest entry of `list`
+    fn push(&mut self, key: K, list: ArcList) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.lists[list as usize].insert(seq, key.clone());
+        self.location.insert(key, (list, seq));
+    }
+
+    /// Remove `key` from whichever list holds it
+    fn take(&mut self, key: &K) -> Option<ArcList> {
+        let (list, seq) = self.location.remove(key)?;
+        self.lists[list as usize].remove(&seq);
+        Some(list)
+    }
+
+    fn pop_oldest(&mut self, list: ArcList) -> Option<K> {
+        let (_, key) = self.lists[list as usize].pop_first()?;
+        self.location.remove(&key);
+        Some(key)
+    }
+
+    /// Record a hit on a resident key
+    fn touch(&mut self, key: &K) {
+        if matches!(
+            self.location.get(key),
+            Some((ArcList::Recent | ArcList::Frequent, _))
+        ) {
+            self.take(key);
+            self.push(key.clone(), ArcList::Frequent);
+        }
+    }
+
+    /// Forget a key removed from storage other than by eviction
+    fn forget(&mut self, key: &K) {
+        self.take(key);
+    }
+
+    /// Admit `key` into the cache, returning the keys to evict from storage
+    fn admit(&mut self, key: &K) -> Vec<K> {
+        let mut evicted = Vec::new();
+        match self.location.get(key).map(|(list, _)| *list) {
+            Some(ArcList::Recent | ArcList::Frequent) => self.touch(key),
+            Some(ArcList::RecentGhost) => {
+                // T1 was too small to keep this key
+                let delta =
+                    (self.len(ArcList::FrequentGhost) / self.len(ArcList::RecentGhost)).max(1);
+                self.target = (self.target + delta).min(self.capacity);
+                self.take(key);
+                evicted.extend(self.replace(false));
+                self.push(key.clone(), ArcList::Frequent);
+            }
+            Some(ArcList::FrequentGhost) => {
+                // T2 was too small to keep this key
+                let delta =
+                    (self.len(ArcList::RecentGhost) / self.len(ArcList::FrequentGhost)).max(1);
+                self.target = self.target.saturating_sub(delta);
+                self.take(key);
+                evicted.extend(self.replace(true));
+                self.push(key.clone(), ArcList::Frequent);
+            }
+            None => {
+                let recent = self.len(ArcList::Recent) + self.len(ArcList::RecentGhost);
+                let total = recent + self.len(ArcList::Frequent) + self.len(ArcList::FrequentGhost);
+                if recent >= self.capacity {
+                    if self.len(ArcList::Recent) < self.capacity {
+                        self.pop_oldest(ArcList::RecentGhost);
+                        evicted.extend(self.replace(false));
+                    } else {
+                        evicted.extend(self.pop_oldest(ArcList::Recent));
+                    }
+                } else if total >= self.capacity {
+                    if total >= 2 * self.capacity {
+                        self.pop_oldest(ArcList::FrequentGhost);
+                    }
+                    evicted.extend(self.replace(false));
+                }
+                self.push(key.clone(), ArcList::Recent);
+            }
+        }
+        evicted
+    }
+
+    /// Make room for one entry if the cache is full
+    fn replace(&mut self, hit_frequent_ghost: bool) -> Option<K> {
+        if self.resident() < self.capacity {
+            return None;
+        }
+        self.evict_one(hit_frequent_ghost)
+    }
+
+    /// Move the oldest entry of T1 or T2 to its ghost list
+    ///
+    /// T1 gives up an entry while it is above its target size.
+    fn evict_one(&mut self, hit_frequent_ghost: bool) -> Option<K> {
+        let recent = self.len(ArcList::Recent);
+        let from_recent = recent > 0
+            && (recent > self.target
+                || (hit_frequent_ghost && recent == self.target)
+                || self.len(ArcList::Frequent) == 0);
+        let (from, ghost) = if from_recent {
+            (ArcList::Recent, ArcList::RecentGhost)
+        } else {
+            (ArcList::Frequent, ArcList::FrequentGhost)
+        };
+
+        let key = self.pop_oldest(from)?;
+        self.push(key.clone(), ghost);
+        Some(key)
+    }
+}
+
+/// SplitMix64 finalizer, mixing a seed into well-distributed bits
+const fn splitmix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
 /// High-performance in-memory cache implementation
 ///
 /// Uses DashMap for concurrent access and implements various eviction policies
@@ -108,6 +281,12 @@ pub struct MemoryCache<K: CacheKey> {
     metrics: Arc<AtomicCacheMetrics>,
     /// Background cleanup task handle
     cleanup_handle: Option<tokio::task::JoinHandle<()>>,
+    /// ARC lists, present with [`EvictionPolicy::Arc`]
+    arc: Option<Arc<Mutex<ArcState<K>>>>,
+    /// Loads in flight from `get_or_insert_with`
+    inflight: SingleFlight<Bytes, CacheError>,
+    /// Hooks consulted before every put
+    validation_hooks: Option<Arc<dyn ValidationHooks>>,
 }
 
 impl<K: CacheKey + 'static> MemoryCache<K> {
@@ -119,6 +298,8 @@ impl<K: CacheKey + 'static> MemoryCache<K> {
 
         let storage = DashMap::with_capacity(config.max_entries.min(1024));
         let metrics = Arc::new(AtomicCacheMetrics::new());
+        let arc = (config.eviction_policy == EvictionPolicy::Arc)
+            .then(|| Arc::new(Mutex::new(ArcState::new(config.max_entries))));
 
         Ok(Self {
             storage,
@@ -127,9 +308,32 @@ impl<K: CacheKey + 'static> MemoryCache<K> {
             memory_usage: AtomicU64::new(0),
             metrics,
             cleanup_handle: None,
+            arc,
+            inflight: SingleFlight::new(),
+            validation_hooks: None,
         })
     }
 
+    /// `ttl` randomized by the configured jitter
+    ///
+    /// With a jitter seed, the offset depends only on the seed and the key.
+    fn jittered_ttl(&self, key: &K, ttl: Duration) -> Duration {
+        if self.config.ttl_jitter <= 0.0 {
+            return ttl;
+        }
+
+        let bits = self
+            .config
+            .ttl_jitter_seed
+            .map_or_else(rand::random, |seed| {
+                splitmix64(seed ^ key.fast_hash().hash64)
+            });
+        // Uniform in [0, 1) from the top 52 bits
+        let unit = f64::from_bits((0x3ff << 52) | (bits >> 12)) - 1.0;
+        let offset = unit.mul_add(2.0, -1.0) * f64::from(self.config.ttl_jitter);
+        ttl.mul_f64(1.0 + offset)
+    }
+
     /// Create a new memory cache and start background cleanup task
     pub fn new_with_cleanup(config: MemoryCacheConfig) -> CacheResult<Self> {
         let cleanup_interval = config.cleanup_interval;
@@ -146,6 +350,7 @@ impl<K: CacheKey + 'static> MemoryCache<K> {
     fn start_cleanup_task(&mut self, cleanup_interval: Duration) {
         let storage = self.storage.clone();
         let metrics = Arc::clone(&self.metrics);
+        let arc = self.arc.clone();
 
         let handle = tokio::spawn(async move {
             let mut interval = interval(cleanup_interval);
@@ -170,6 +375,11 @@ impl<K: CacheKey + 'static> MemoryCache<K> {
                     if let Some((_, entry)) = storage.remove(&key) {
                         removed_count += 1;
                         freed_bytes += entry.size_bytes;
+                        if let Some(arc) = &arc {
+                            arc.lock()
+                                .unwrap_or_else(PoisonError::into_inner)
+                                .forget(&key);
+                        }
                     }
                 }
 
@@ -218,9 +428,60 @@ impl<K: CacheKey + 'static> MemoryCache<K> {
             crate::traits::EvictionPolicy::Fifo => self.evict_fifo(evict_count),
             crate::traits::EvictionPolicy::Random => self.evict_random(evict_count),
             crate::traits::EvictionPolicy::Ttl => self.evict_expired(),
+            // ARC evicts as entries are admitted, see `put_with_ttl`
+            crate::traits::EvictionPolicy::Arc => {}
         }
     }
 
+    /// Remove an entry chosen for eviction
+    fn evict_key(&self, key: &K) {
+        if let Some((_, entry)) = self.storage.remove(key) {
+            self.entry_count.fetch_sub(1, Ordering::Relaxed);
+            self.memory_usage
+                .fetch_sub(entry.size_bytes as u64, Ordering::Relaxed);
+            self.metrics.record_eviction(entry.size_bytes);
+        }
+    }
+
+    /// Admit `key` into the ARC lists, evicting until `size_bytes` more fit
+    fn admit_arc(&self, arc: &mut ArcState<K>, key: &K, size_bytes: usize) {
+        if let Some(max) = self.config.max_memory_bytes {
+            while self.memory_usage.load(Ordering::Relaxed) + size_bytes as u64 > max as u64 {
+                match arc.evict_one(false) {
+                    Some(victim) => self.evict_key(&victim),
+                    None => break,
+                }
+            }
+        }
+        for victim in arc.admit(key) {
+            self.evict_key(&victim);
+        }
+    }
+
+    /// Lock the ARC lists, if the policy is ARC
+    ///
+    /// Writers hold the lock while changing the storage so the lists and
+    /// the storage agree.
+    fn lock_arc(&self) -> Option<MutexGuard<'_, ArcState<K>>> {
+        self.arc
+            .as_ref()
+            .map(|arc| arc.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Remove the entry for `key`, returning its size
+    fn remove_entry(&self, key: &K) -> Option<usize> {
+        let mut arc_state = self.lock_arc();
+        let (_, entry) = self.storage.remove(key)?;
+        self.entry_count.fetch_sub(1, Ordering::Relaxed);
+        self.memory_usage
+            .fetch_sub(entry.size_bytes as u64, Ordering::Relaxed);
+        if let Some(arc) = arc_state.as_deref_mut() {
+            arc.forget(key);
+        }
+        drop(arc_state);
+        Some(entry.size_bytes)
+    }
+
     /// Evict entries using LRU policy
     fn evict_lru(&self, count: usize) {
         let mut candidates: Vec<(K, u64)> = self
@@ -337,7 +598,91 @@ impl<K: CacheKey + 'static> MemoryCache<K> {
         }
     }
 
+    /// Check every put with the [`ValidationHooks::before_put`] of `hooks`
+    ///
+    /// Hooks are installed before the cache is shared, so every put made
+    /// through it is checked.
+    #[must_use]
+    pub fn with_validation_hooks(mut self, hooks: Arc<dyn ValidationHooks>) -> Self {
+        self.validation_hooks = Some(hooks);
+        self
+    }
+
+    /// Check if validation hooks are set
+    pub fn has_validation_hooks(&self) -> bool {
+        self.validation_hooks.is_some()
+    }
+
+    /// Store an entry that never expires
+    ///
+    /// The entry leaves the cache only when it is removed or evicted to
+    /// make room.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ContentValidationFailed` if the validation hooks reject
+    /// the data.
+    pub fn put_without_ttl(&self, key: K, value: Bytes) -> CacheResult<()> {
+        check_before_put(self.validation_hooks.as_ref(), key.as_cache_key(), &value)?;
+        self.insert(key, value, None);
+        Ok(())
+    }
+
+    /// Store an entry that expires after `ttl`, or never with `None`
+    fn insert(&self, key: K, value: Bytes, ttl: Option<Duration>) {
+        let start_time = Instant::now();
+        let size_bytes = value.len();
+
+        // ARC chooses evictions as it admits the key, and keeps its lock
+        // until the entry is stored so the lists match the storage
+        let mut arc_state = self.lock_arc();
+        if let Some(arc) = arc_state.as_deref_mut() {
+            self.admit_arc(arc, &key, size_bytes);
+        } else if self.needs_eviction() {
+            // Check capacity and evict if necessary
+            self.perform_eviction();
+        }
+
+        let entry = Arc::new(MemoryCacheEntryInner::new(value, size_bytes, ttl));
+
+        // Insert or update entry
+        if let Some(old_entry) = self.storage.insert(key, entry) {
+            // Updating existing entry - adjust memory usage
+            let old_size = old_entry.size_bytes as u64;
+            let new_size = size_bytes as u64;
+
+            if new_size > old_size {
+                self.memory_usage
+                    .fetch_add(new_size - old_size, Ordering::Relaxed);
+            } else {
+                self.memory_usage
+                    .fetch_sub(old_size - new_size, Ordering::Relaxed);
+            }
+        } else {
+            // New entry
+            self.entry_count.fetch_add(1, Ordering::Relaxed);
+            self.memory_usage
+                .fetch_add(size_bytes as u64, Ordering::Relaxed);
+        }
+        drop(arc_state);
+
+        self.metrics.record_put(size_bytes, start_time.elapsed());
+    }
+
+    /// Time left before the entry for `key` expires
+    ///
+    /// Returns `None` if there is no entry for `key` or it does not expire,
+    /// and `Duration::ZERO` once it has expired.
+    pub fn remaining_ttl(&self, key: &K) -> Option<Duration> {
+        let expires_at = self.storage.get(key)?.expires_at?;
+        Some(expires_at.saturating_duration_since(Instant::now()))
+    }
+
     /// Get current cache statistics
+    ///
+    /// [`CacheStats::hit_rate`](crate::stats::CacheStats::hit_rate) and
+    /// `eviction_count` allow comparing eviction policies on the same
+    /// workload.
     pub fn cache_stats(&self) -> crate::stats::CacheStats {
         let snapshot = self.metrics.fast_snapshot();
         let current_entries = self.entry_count.load(Ordering::Relaxed);
@@ -351,9 +696,9 @@ impl<K: CacheKey + 'static> MemoryCache<K> {
             get_count: snapshot.get_count,
             hit_count: snapshot.hit_count,
             miss_count: snapshot.get_count - snapshot.hit_count,
-            put_count: 0,        // Would need separate counter
-            remove_count: 0,     // Would need separate counter
-            eviction_count: 0,   // Would need separate counter
+            put_count: 0,    // Would need separate counter
+            remove_count: 0, // Would need separate counter
+            eviction_count: self.metrics.snapshot().eviction_count,
             expiration_count: 0, // Would need separate counter
             entry_count: current_entries,
             memory_usage_bytes: current_memory as usize,
@@ -374,15 +719,10 @@ impl<K: CacheKey + 'static> AsyncCache<K> for MemoryCache<K> {
         if let Some(entry) = self.storage.get(key) {
             if entry.is_expired() {
                 // Need to collect info and drop the guard before removing
-                let size_bytes = entry.size_bytes;
                 drop(entry); // Drop the guard before attempting to remove
 
                 // Remove expired entry
-                if self.storage.remove(key).is_some() {
-                    self.entry_count.fetch_sub(1, Ordering::Relaxed);
-                    self.memory_usage
-                        .fetch_sub(size_bytes as u64, Ordering::Relaxed);
-                }
+                self.remove_entry(key);
 
                 self.metrics.record_get(false, start_time.elapsed());
                 return Ok(None);
@@ -391,6 +731,12 @@ impl<K: CacheKey + 'static> AsyncCache<K> for MemoryCache<K> {
             // Update access statistics
             entry.update_access();
             let value = entry.value.clone();
+            // Release the shard before taking the ARC lock, which `put`
+            // holds while it removes entries
+            drop(entry);
+            if let Some(arc) = self.lock_arc().as_deref_mut() {
+                arc.touch(key);
+            }
 
             self.metrics.record_get(true, start_time.elapsed());
             Ok(Some(value))
@@ -407,37 +753,9 @@ impl<K: CacheKey + 'static> AsyncCache<K> for MemoryCache<K> {
     }
 
     async fn put_with_ttl(&self, key: K, value: Bytes, ttl: Duration) -> CacheResult<()> {
-        let start_time = Instant::now();
-        let size_bytes = value.len();
-
-        // Check capacity and evict if necessary
-        if self.needs_eviction() {
-            self.perform_eviction();
-        }
-
-        let entry = Arc::new(MemoryCacheEntryInner::new(value, size_bytes, Some(ttl)));
-
-        // Insert or update entry
-        if let Some(old_entry) = self.storage.insert(key, entry) {
-            // Updating existing entry - adjust memory usage
-            let old_size = old_entry.size_bytes as u64;
-            let new_size = size_bytes as u64;
-
-            if new_size > old_size {
-                self.memory_usage
-                    .fetch_add(new_size - old_size, Ordering::Relaxed);
-            } else {
-                self.memory_usage
-                    .fetch_sub(old_size - new_size, Ordering::Relaxed);
-            }
-        } else {
-            // New entry
-            self.entry_count.fetch_add(1, Ordering::Relaxed);
-            self.memory_usage
-                .fetch_add(size_bytes as u64, Ordering::Relaxed);
-        }
-
-        self.metrics.record_put(size_bytes, start_time.elapsed());
+        check_before_put(self.validation_hooks.as_ref(), key.as_cache_key(), &value)?;
+        let ttl = self.jittered_ttl(&key, ttl);
+        self.insert(key, value, Some(ttl));
         Ok(())
     }
 
@@ -445,15 +763,10 @@ impl<K: CacheKey + 'static> AsyncCache<K> for MemoryCache<K> {
         if let Some(entry) = self.storage.get(key) {
             if entry.is_expired() {
                 // Need to collect info and drop the guard before removing
-                let size_bytes = entry.size_bytes;
                 drop(entry); // Drop the guard before attempting to remove
 
                 // Clean up expired entry
-                if self.storage.remove(key).is_some() {
-                    self.entry_count.fetch_sub(1, Ordering::Relaxed);
-                    self.memory_usage
-                        .fetch_sub(size_bytes as u64, Ordering::Relaxed);
-                }
+                self.remove_entry(key);
                 Ok(false)
             } else {
                 Ok(true)
@@ -464,20 +777,18 @@ impl<K: CacheKey + 'static> AsyncCache<K> for MemoryCache<K> {
     }
 
     async fn remove(&self, key: &K) -> CacheResult<bool> {
-        if let Some((_, entry)) = self.storage.remove(key) {
-            self.entry_count.fetch_sub(1, Ordering::Relaxed);
-            self.memory_usage
-                .fetch_sub(entry.size_bytes as u64, Ordering::Relaxed);
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+        Ok(self.remove_entry(key).is_some())
     }
 
     async fn clear(&self) -> CacheResult<()> {
+        let mut arc_state = self.lock_arc();
+        if let Some(arc) = arc_state.as_deref_mut() {
+            *arc = ArcState::new(self.config.max_entries);
+        }
         self.storage.clear();
         self.entry_count.store(0, Ordering::Relaxed);
         self.memory_usage.store(0, Ordering::Relaxed);
+        drop(arc_state);
         self.metrics.reset();
         Ok(())
     }
@@ -489,6 +800,23 @@ impl<K: CacheKey + 'static> AsyncCache<K> for MemoryCache<K> {
     async fn size(&self) -> CacheResult<usize> {
         Ok(self.entry_count.load(Ordering::Relaxed))
     }
+
+    async fn keys(&self) -> CacheResult<Vec<K>> {
+        Ok(self
+            .storage
+            .iter()
+            .filter(|entry| !entry.value().is_expired())
+            .map(|entry| entry.key().clone())
+            .collect())
+    }
+
+    async fn get_or_insert_with(
+        &self,
+        key: K,
+        load: BoxFuture<'_, CacheResult<Bytes>>,
+    ) -> CacheResult<Bytes> {
+        self.inflight.get_or_insert_with(self, key, load).await
+    }
 }
 
 impl<K: CacheKey> Drop for MemoryCache<K> {
@@ -509,6 +837,58 @@ mod tests {
     use crate::{config::MemoryCacheConfig, key::RibbitKey, traits::EvictionPolicy};
     use std::time::Duration;
 
+    /// Remaining TTLs of `count` fresh entries put into `cache`
+    async fn put_and_read_ttls(cache: &MemoryCache<RibbitKey>, count: usize) -> Vec<Duration> {
+        let mut ttls = Vec::with_capacity(count);
+        for i in 0..count {
+            let key = RibbitKey::new(format!("versions-{i}"), "us");
+            cache
+                .put(key.clone(), Bytes::from("data"))
+                .await
+                .expect("Test operation should succeed");
+            ttls.push(cache.remaining_ttl(&key).expect("Entry should expire"));
+        }
+        ttls
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_ttl_jitter_spreads_expiry() {
+        let config = MemoryCacheConfig::new()
+            .with_default_ttl(Duration::from_secs(300))
+            .with_ttl_jitter(0.1);
+        let cache = MemoryCache::new(config).expect("Test operation should succeed");
+
+        let ttls = put_and_read_ttls(&cache, 200).await;
+        let min = ttls.iter().min().expect("TTLs");
+        let max = ttls.iter().max().expect("TTLs");
+        assert!(*min >= Duration::from_secs(269), "min {min:?}");
+        assert!(*max <= Duration::from_secs(330), "max {max:?}");
+        // Expiry is spread over most of the window
+        assert!(
+            max.saturating_sub(*min) > Duration::from_secs(40),
+            "{min:?}..{max:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_ttl_jitter_is_deterministic_with_seed() {
+        let config = MemoryCacheConfig::new()
+            .with_default_ttl(Duration::from_secs(300))
+            .with_ttl_jitter(0.1)
+            .with_ttl_jitter_seed(42);
+        let first = MemoryCache::new(config.clone()).expect("Test operation should succeed");
+        let second = MemoryCache::new(config).expect("Test operation should succeed");
+
+        let first = put_and_read_ttls(&first, 20).await;
+        let second = put_and_read_ttls(&second, 20).await;
+        for (a, b) in first.iter().zip(&second) {
+            let difference = a.abs_diff(*b);
+            assert!(difference < Duration::from_secs(1), "{a:?} vs {b:?}");
+        }
+        assert!(first.iter().any(|ttl| *ttl < Duration::from_secs(295)));
+        assert!(first.iter().any(|ttl| *ttl > Duration::from_secs(305)));
+    }
+
     #[tokio::test]
     async fn test_memory_cache_basic_operations() {
         let config = MemoryCacheConfig::new()
@@ -546,6 +926,51 @@ mod tests {
         assert_eq!(cache.size().await.expect("Operation should succeed"), 0);
     }
 
+    #[tokio::test]
+    async fn test_memory_cache_rejects_put_failing_validation() {
+        use crate::key::ContentCacheKey;
+        use crate::validation::NgdpValidationHooks;
+        use cascette_crypto::ContentKey;
+
+        let cache = MemoryCache::new(MemoryCacheConfig::new())
+            .expect("Test operation should succeed")
+            .with_validation_hooks(Arc::new(NgdpValidationHooks::new().with_put_validation()));
+        assert!(cache.has_validation_hooks());
+
+        // Each put is checked against the content key it is stored under
+        let mut keys = Vec::new();
+        for content in ["first content", "second content"] {
+            let key = ContentCacheKey::new(ContentKey::from_data(content.as_bytes()));
+            cache
+                .put(key.clone(), Bytes::from(content))
+                .await
+                .expect("Matching content should be stored");
+            keys.push(key);
+        }
+
+        let key = ContentCacheKey::new(ContentKey::from_data(b"expected content"));
+        let result = cache.put(key.clone(), Bytes::from("tampered")).await;
+        assert!(matches!(
+            result,
+            Err(CacheError::ContentValidationFailed(_))
+        ));
+        let result = cache.put_without_ttl(keys[0].clone(), Bytes::from("second content"));
+        assert!(matches!(
+            result,
+            Err(CacheError::ContentValidationFailed(_))
+        ));
+        assert!(
+            !cache
+                .contains(&key)
+                .await
+                .expect("Operation should succeed")
+        );
+        assert_eq!(
+            cache.get(&keys[0]).await.expect("Operation should succeed"),
+            Some(Bytes::from("first content"))
+        );
+    }
+
     #[tokio::test]
     async fn test_memory_cache_ttl_expiration() {
         let config = MemoryCacheConfig::new().with_max_entries(100);
@@ -626,6 +1051,93 @@ mod tests {
         // This is a simplified test of the eviction mechanism
     }
 
+    /// Run a patch-style trace: every round reads four manifests twice,
+    /// then stores a burst of 20 content keys that are never read again.
+    async fn run_patch_trace(policy: EvictionPolicy) -> MemoryCache<RibbitKey> {
+        let config = MemoryCacheConfig::new()
+            .with_max_entries(10)
+            .with_eviction_policy(policy);
+        let cache = MemoryCache::new(config).expect("Test operation should succeed");
+        let manifests: Vec<_> = (0..4)
+            .map(|i| RibbitKey::new(format!("manifest{i}"), "us"))
+            .collect();
+
+        let mut content = 0;
+        for _round in 0..8 {
+            for key in &manifests {
+                for _read in 0..2 {
+                    let cached = cache.get(key).await.expect("Operation should succeed");
+                    if cached.is_none() {
+                        cache
+                            .put(key.clone(), Bytes::from("manifest"))
+                            .await
+                            .expect("Test operation should succeed");
+                    }
+                }
+            }
+            for _ in 0..20 {
+                let key = RibbitKey::new(format!("content{content}"), "us");
+                cache
+                    .put(key, Bytes::from("content"))
+                    .await
+                    .expect("Test operation should succeed");
+                content += 1;
+            }
+        }
+        cache
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_arc_survives_scans() {
+        let arc = run_patch_trace(EvictionPolicy::Arc).await;
+        let lru = run_patch_trace(EvictionPolicy::Lru).await;
+
+        // ARC misses each manifest once; LRU loses them to every burst
+        let arc_stats = arc.cache_stats();
+        assert_eq!(arc_stats.get_count, 64);
+        assert_eq!(arc_stats.hit_count, 60);
+        let lru_stats = lru.cache_stats();
+        assert!(lru_stats.hit_rate() <= 0.5, "{lru_stats:?}");
+        assert!(arc_stats.hit_rate() > lru_stats.hit_rate());
+
+        // Manifests stay resident while the one-off content cycles through
+        for i in 0..4 {
+            let key = RibbitKey::new(format!("manifest{i}"), "us");
+            assert!(arc.contains(&key).await.expect("Operation should succeed"));
+        }
+        let first = RibbitKey::new("content0", "us");
+        assert!(
+            !arc.contains(&first)
+                .await
+                .expect("Operation should succeed")
+        );
+        assert_eq!(arc.size().await.expect("Operation should succeed"), 10);
+        assert_eq!(arc_stats.eviction_count, 160 - 6);
+    }
+
+    #[test]
+    fn test_arc_ghost_hit_adapts_target() {
+        let key = |name: &str| RibbitKey::new(name, "us");
+        let mut arc = ArcState::new(2);
+
+        assert!(arc.admit(&key("a")).is_empty());
+        assert!(arc.admit(&key("b")).is_empty());
+        arc.touch(&key("a"));
+
+        // The cache is full, so the one-off `b` leaves T1 for B1
+        assert_eq!(arc.admit(&key("c")), vec![key("b")]);
+        assert_eq!(arc.len(ArcList::RecentGhost), 1);
+
+        // Seeing `b` again grows T1's target and costs T2 an entry
+        assert_eq!(arc.admit(&key("b")), vec![key("a")]);
+        assert_eq!(arc.target, 1);
+        assert_eq!(arc.len(ArcList::Frequent), 1);
+        assert_eq!(arc.len(ArcList::FrequentGhost), 1);
+
+        arc.forget(&key("c"));
+        assert_eq!(arc.resident(), 1);
+    }
+
     #[tokio::test]
     async fn test_memory_cache_clear() {
         let config = MemoryCacheConfig::new().with_max_entries(100);
@@ -13,12 +13,13 @@
 #![allow(missing_docs)]
 
 use crate::{
-    config::{LayerConfig, MultiLayerCacheConfig, PromotionStrategy},
+    config::{LayerConfig, MultiLayerCacheConfig, PromotionStrategy, TtlPolicy},
     disk_cache::DiskCache,
     error::{CacheError, CacheResult, NgdpCacheError},
     key::CacheKey,
     memory_cache::MemoryCache,
     simd::{CpuFeatures, SimdHashOperations, detect_cpu_features, global_simd_stats},
+    singleflight::SingleFlight,
     stats::AtomicCacheMetrics,
     traits::{AsyncCache, MultiLayerCache},
     validation::{NgdpBytes, ValidationHooks, ValidationMetrics, ValidationResult},
@@ -26,8 +27,9 @@ use crate::{
 use async_trait::async_trait;
 use bytes::Bytes;
 use cascette_crypto::ContentKey;
+use futures::future::BoxFuture;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::{
         Arc, RwLock,
         atomic::{AtomicU64, Ordering},
@@ -84,6 +86,15 @@ enum CacheLayer<K: CacheKey> {
     Disk(Arc<DiskCache<K>>),
 }
 
+impl<K: CacheKey> Clone for CacheLayer<K> {
+    fn clone(&self) -> Self {
+        match self {
+            CacheLayer::Memory(cache) => CacheLayer::Memory(Arc::clone(cache)),
+            CacheLayer::Disk(cache) => CacheLayer::Disk(Arc::clone(cache)),
+        }
+    }
+}
+
 impl<K: CacheKey + 'static> CacheLayer<K> {
     async fn get(&self, key: &K) -> CacheResult<Option<Bytes>> {
         match self {
@@ -106,6 +117,32 @@ impl<K: CacheKey + 'static> CacheLayer<K> {
         }
     }
 
+    /// Store an entry with `policy`, or with the layer's default TTL
+    async fn put_with_policy(
+        &self,
+        key: K,
+        value: Bytes,
+        policy: Option<TtlPolicy>,
+    ) -> CacheResult<()> {
+        match (self, policy) {
+            (_, None) => self.put(key, value).await,
+            (_, Some(TtlPolicy::Expires(ttl))) => self.put_with_ttl(key, value, ttl).await,
+            (CacheLayer::Memory(cache), Some(TtlPolicy::Immutable)) => {
+                cache.put_without_ttl(key, value)
+            }
+            (CacheLayer::Disk(cache), Some(TtlPolicy::Immutable)) => {
+                cache.put_without_ttl(key, value).await
+            }
+        }
+    }
+
+    fn remaining_ttl(&self, key: &K) -> Option<Duration> {
+        match self {
+            CacheLayer::Memory(cache) => cache.remaining_ttl(key),
+            CacheLayer::Disk(cache) => cache.remaining_ttl(key),
+        }
+    }
+
     async fn contains(&self, key: &K) -> CacheResult<bool> {
         match self {
             CacheLayer::Memory(cache) => cache.contains(key).await,
@@ -134,6 +171,13 @@ impl<K: CacheKey + 'static> CacheLayer<K> {
         }
     }
 
+    async fn keys(&self) -> CacheResult<Vec<K>> {
+        match self {
+            CacheLayer::Memory(cache) => cache.keys().await,
+            CacheLayer::Disk(cache) => cache.keys().await,
+        }
+    }
+
     async fn stats(&self) -> CacheResult<crate::stats::CacheStats> {
         match self {
             CacheLayer::Memory(cache) => cache.stats().await,
@@ -161,13 +205,15 @@ pub struct MultiLayerCacheImpl<K: CacheKey> {
     /// Per-layer miss counters
     layer_misses: Vec<AtomicU64>,
     /// Promotion counters
-    promotion_count: AtomicU64,
+    promotion_count: Arc<AtomicU64>,
     /// Optional validation hooks for content integrity
     validation_hooks: Option<Arc<dyn ValidationHooks>>,
     /// Validation metrics
     validation_metrics: ValidationMetrics,
     /// CPU feature detection for SIMD optimizations
     cpu_features: CpuFeatures,
+    /// Loads in flight from `get_or_insert_with`
+    inflight: SingleFlight<Bytes, CacheError>,
 }
 
 impl<K: CacheKey + 'static> MultiLayerCacheImpl<K> {
@@ -219,43 +265,107 @@ impl<K: CacheKey + 'static> MultiLayerCacheImpl<K> {
             metrics: Arc::new(AtomicCacheMetrics::new()),
             layer_hits,
             layer_misses,
-            promotion_count: AtomicU64::new(0),
+            promotion_count: Arc::new(AtomicU64::new(0)),
             validation_hooks: None,
             validation_metrics: ValidationMetrics::new(),
             cpu_features,
+            inflight: SingleFlight::new(),
         })
     }
 
-    /// Check if a key should be promoted based on the configured strategy
-    fn should_promote(&self, key: &K, current_layer: usize) -> bool {
+    /// Check if an entry should be promoted based on the configured strategy
+    fn should_promote(&self, tracker: &PromotionTracker, current_layer: usize) -> bool {
         if current_layer == 0 {
             return false; // Already in top layer
         }
 
-        let tracker = self
-            .promotion_tracker
-            .read()
-            .map_err(|_| CacheError::LockTimeout("promotion tracker read lock".to_string()));
-
-        if let Ok(tracker_guard) = tracker {
-            if let Some(tracker) = tracker_guard.get(key) {
-                match &self.config.promotion_strategy {
-                    PromotionStrategy::OnHit => true,
-                    PromotionStrategy::AfterNHits(n) => tracker.hit_count >= *n as u64,
-                    PromotionStrategy::FrequencyBased { threshold } => {
-                        tracker.access_frequency() >= *threshold
-                    }
-                    PromotionStrategy::AgeBased { min_age } => tracker.age() >= *min_age,
-                    PromotionStrategy::Manual => false,
-                }
-            } else {
-                false
+        match &self.config.promotion_strategy {
+            PromotionStrategy::OnHit => true,
+            PromotionStrategy::AfterNHits(n) => tracker.hit_count >= *n as u64,
+            PromotionStrategy::FrequencyBased { threshold } => {
+                tracker.access_frequency() >= *threshold
             }
-        } else {
-            false
+            PromotionStrategy::AgeBased { min_age } => tracker.age() >= *min_age,
+            PromotionStrategy::Manual => false,
         }
     }
 
+    /// Record a hit for `key` at `layer_index` and decide whether to promote it
+    ///
+    /// Returns true when `promote_on_hit` is enabled and the promotion
+    /// strategy allows copying the entry to L1.
+    fn track_hit(&self, key: &K, layer_index: usize) -> bool {
+        let Ok(mut tracker) = self.promotion_tracker.write() else {
+            return false;
+        };
+        let entry_tracker = tracker
+            .entry(key.clone())
+            .and_modify(PromotionTracker::update_access)
+            .or_insert_with(|| PromotionTracker::new(layer_index));
+        self.config.promote_on_hit && self.should_promote(entry_tracker, layer_index)
+    }
+
+    /// TTL policy configured for the kind of `key`
+    fn ttl_policy(&self, key: &K) -> Option<TtlPolicy> {
+        self.config.ttl_policy(key.key_kind()?)
+    }
+
+    /// Store `value` in L1 with `policy` and start tracking it for promotion
+    async fn put_to_l1(&self, key: K, value: Bytes, policy: Option<TtlPolicy>) -> CacheResult<()> {
+        let start_time = Instant::now();
+        let size_bytes = value.len();
+
+        let result = self.layers[0]
+            .put_with_policy(key.clone(), value, policy)
+            .await;
+
+        // Initialize promotion tracking
+        if result.is_ok()
+            && let Ok(mut tracker) = self.promotion_tracker.write()
+        {
+            tracker.insert(key, PromotionTracker::new(0));
+        }
+
+        self.metrics.record_put(size_bytes, start_time.elapsed());
+        result
+    }
+
+    /// Copy a value found in a lower layer to L1 in the background
+    ///
+    /// The L1 entry gets the remaining TTL of the entry it was found in, so
+    /// it never outlives it. Entries without an expiry use the TTL policy
+    /// of their kind, or the L1 default.
+    fn spawn_promotion(&self, key: K, value: Bytes, from_layer: usize) {
+        let policy = match self.layers[from_layer].remaining_ttl(&key) {
+            Some(Duration::ZERO) => return, // Expired since it was read
+            Some(ttl) => Some(TtlPolicy::Expires(ttl)),
+            None => self.ttl_policy(&key),
+        };
+
+        let target = self.layers[0].clone();
+        let promotion_tracker = Arc::clone(&self.promotion_tracker);
+        let promotion_count = Arc::clone(&self.promotion_count);
+        tokio::spawn(async move {
+            let result = target.put_with_policy(key.clone(), value, policy).await;
+            if let Err(e) = result {
+                eprintln!(
+                    "Failed to promote key {:?} from layer {}: {}",
+                    key.as_cache_key(),
+                    from_layer,
+                    e
+                );
+                return;
+            }
+
+            if let Ok(mut tracker) = promotion_tracker.write()
+                && let Some(entry_tracker) = tracker.get_mut(&key)
+            {
+                entry_tracker.current_layer = 0;
+            }
+            promotion_count.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+
     /// Promote an entry from a lower layer to a higher layer
     async fn promote_entry(&self, key: K, from_layer: usize, to_layer: usize) -> CacheResult<bool> {
         if from_layer <= to_layer {
@@ -265,7 +375,10 @@ impl<K: CacheKey + 'static> MultiLayerCacheImpl<K> {
         // Get value from source layer
         if let Some(value) = self.layers[from_layer].get(&key).await? {
             // Put in target layer
-            self.layers[to_layer].put(key.clone(), value).await?;
+            let policy = self.ttl_policy(&key);
+            self.layers[to_layer]
+                .put_with_policy(key.clone(), value, policy)
+                .await?;
 
             // Update promotion tracking
             if let Ok(mut tracker) = self.promotion_tracker.write()
@@ -453,7 +566,10 @@ impl<K: CacheKey + 'static> MultiLayerCacheImpl<K> {
         };
 
         // Store in first layer (L1 - fastest) if validation passed
-        self.layers[0].put(key.clone(), value).await?;
+        let policy = self.ttl_policy(&key);
+        self.layers[0]
+            .put_with_policy(key.clone(), value, policy)
+            .await?;
 
         // Initialize promotion tracking
         if let Ok(mut tracker) = self.promotion_tracker.write() {
@@ -545,14 +661,8 @@ impl<K: CacheKey + 'static> MultiLayerCacheImpl<K> {
                     // Found in this layer
                     self.layer_hits[layer_index].fetch_add(1, Ordering::Relaxed);
 
-                    // Update promotion tracking (same as before)
-                    if let Ok(mut tracker) = self.promotion_tracker.write() {
-                        if let Some(entry_tracker) = tracker.get_mut(key) {
-                            entry_tracker.update_access();
-                        } else {
-                            tracker.insert(key.clone(), PromotionTracker::new(layer_index));
-                        }
-                    }
+                    // Only validated content is promoted
+                    let promotion = self.track_hit(key, layer_index).then(|| value.clone());
 
                     // Create NgdpBytes wrapper
                     let ngdp_bytes = if let Some(content_key) = expected_content_key {
@@ -602,6 +712,10 @@ impl<K: CacheKey + 'static> MultiLayerCacheImpl<K> {
                         }
                     }
 
+                    if let Some(value) = promotion {
+                        self.spawn_promotion(key.clone(), value, layer_index);
+                    }
+
                     self.metrics.record_get(true, start_time.elapsed());
                     return Ok(Some(ngdp_bytes));
                 }
@@ -763,43 +877,8 @@ impl<K: CacheKey + 'static> AsyncCache<K> for MultiLayerCacheImpl<K> {
                     // Found in this layer
                     self.layer_hits[layer_index].fetch_add(1, Ordering::Relaxed);
 
-                    // Update promotion tracking
-                    if let Ok(mut tracker) = self.promotion_tracker.write() {
-                        if let Some(entry_tracker) = tracker.get_mut(key) {
-                            entry_tracker.update_access();
-
-                            // Check for promotion opportunity
-                            if self.should_promote(key, layer_index) {
-                                // DESIGN DECISION: Cache promotion is deferred
-                                //
-                                // Background promotion from L2 (disk) to L1 (memory) is not implemented
-                                // to avoid complexity:
-                                //
-                                // Option 1: Arc<Self> with tokio::spawn
-                                //   - Would require changing the struct to be Arc-wrapped at construction
-                                //   - All internal methods would need Arc<Self> instead of &self
-                                //   - Significant refactoring across the crate
-                                //
-                                // Option 2: Background task queue (mpsc channel)
-                                //   - Cleaner separation but adds channel overhead
-                                //   - Requires a separate background task to process promotions
-                                //   - Queue could fill up during high load
-                                //
-                                // Option 3: Synchronous promotion (call promote_entry here)
-                                //   - Adds latency to cache hits
-                                //   - Could cause cascading delays
-                                //   - Not suitable for hot path
-                                //
-                                // Current behavior: Tracking is updated but promotion is skipped.
-                                // Entries remain in L2 until natural eviction or explicit put to L1.
-                                // This is acceptable for CASC file caching where read patterns are
-                                // typically sequential (one-time access) rather than hot-spot based.
-                                let _ = layer_index; // Silence unused warning
-                            }
-                        } else {
-                            // First access - start tracking
-                            tracker.insert(key.clone(), PromotionTracker::new(layer_index));
-                        }
+                    if self.track_hit(key, layer_index) {
+                        self.spawn_promotion(key.clone(), value.clone(), layer_index);
                     }
 
                     self.metrics.record_get(true, start_time.elapsed());
@@ -828,38 +907,13 @@ impl<K: CacheKey + 'static> AsyncCache<K> for MultiLayerCacheImpl<K> {
     }
 
     async fn put(&self, key: K, value: Bytes) -> CacheResult<()> {
-        let start_time = Instant::now();
-
-        // Store in first layer (L1 - fastest)
-        let result = self.layers[0].put(key.clone(), value).await;
-
-        // Initialize promotion tracking
-        if result.is_ok()
-            && let Ok(mut tracker) = self.promotion_tracker.write()
-        {
-            tracker.insert(key, PromotionTracker::new(0));
-        }
-
-        self.metrics.record_put(0, start_time.elapsed()); // Size not easily available here
-        result
+        let policy = self.ttl_policy(&key);
+        self.put_to_l1(key, value, policy).await
     }
 
     async fn put_with_ttl(&self, key: K, value: Bytes, ttl: Duration) -> CacheResult<()> {
-        let start_time = Instant::now();
-        let size_bytes = value.len();
-
-        // Store in first layer (L1 - fastest)
-        let result = self.layers[0].put_with_ttl(key.clone(), value, ttl).await;
-
-        // Initialize promotion tracking
-        if result.is_ok()
-            && let Ok(mut tracker) = self.promotion_tracker.write()
-        {
-            tracker.insert(key, PromotionTracker::new(0));
-        }
-
-        self.metrics.record_put(size_bytes, start_time.elapsed());
-        result
+        self.put_to_l1(key, value, Some(TtlPolicy::Expires(ttl)))
+            .await
     }
 
     async fn contains(&self, key: &K) -> CacheResult<bool> {
@@ -961,6 +1015,14 @@ impl<K: CacheKey + 'static> AsyncCache<K> for MultiLayerCacheImpl<K> {
         })
     }
 
+    async fn get_or_insert_with(
+        &self,
+        key: K,
+        load: BoxFuture<'_, CacheResult<Bytes>>,
+    ) -> CacheResult<Bytes> {
+        self.inflight.get_or_insert_with(self, key, load).await
+    }
+
     async fn size(&self) -> CacheResult<usize> {
         let mut total_size = 0;
         for layer in &self.layers {
@@ -968,6 +1030,20 @@ impl<K: CacheKey + 'static> AsyncCache<K> for MultiLayerCacheImpl<K> {
         }
         Ok(total_size)
     }
+
+    /// Keys present in any layer, each listed once
+    async fn keys(&self) -> CacheResult<Vec<K>> {
+        let mut seen = HashSet::new();
+        let mut keys = Vec::new();
+        for layer in &self.layers {
+            for key in layer.keys().await? {
+                if seen.insert(key.clone()) {
+                    keys.push(key);
+                }
+            }
+        }
+        Ok(keys)
+    }
 }
 
 #[async_trait]
@@ -997,7 +1073,8 @@ impl<K: CacheKey + 'static> MultiLayerCache<K> for MultiLayerCacheImpl<K> {
             )));
         }
 
-        self.layers[layer].put(key, value).await
+        let policy = self.ttl_policy(&key);
+        self.layers[layer].put_with_policy(key, value, policy).await
     }
 
     async fn promote(&self, key: &K, from_layer: usize, to_layer: usize) -> CacheResult<bool> {
@@ -1197,10 +1274,177 @@ mod tests {
         assert_eq!(from_l1_after, Some(value));
     }
 
-    // NOTE: Auto-promotion from L2 to L1 is intentionally deferred.
-    // See the DESIGN DECISION comment in get() method for rationale.
-    // Manual promotion via promote_entry() is tested in test_manual_promotion above.
-    // A test for automatic promotion would be added when/if Option 2 (task queue) is implemented.
+    fn create_promoting_cache(
+        disk_ttl: Duration,
+        promote_on_hit: bool,
+    ) -> (MultiLayerCacheImpl<RibbitKey>, TempDir) {
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+
+        let config = MultiLayerCacheConfig::new()
+            .add_memory_layer(
+                MemoryCacheConfig::new()
+                    .with_max_entries(100)
+                    .with_default_ttl(Duration::from_secs(300)),
+            )
+            .add_disk_layer(
+                DiskCacheConfig::new(temp_dir.path())
+                    .with_max_files(1000)
+                    .with_default_ttl(disk_ttl),
+            )
+            .with_promote_on_hit(promote_on_hit);
+
+        let cache = MultiLayerCacheImpl::new(config).expect("Operation should succeed");
+        (cache, temp_dir)
+    }
+
+    /// Wait for a background promotion of `key` to reach L1
+    async fn wait_for_l1(cache: &MultiLayerCacheImpl<RibbitKey>, key: &RibbitKey) -> bool {
+        for _ in 0..100 {
+            if cache
+                .get_from_layer(key, 0)
+                .await
+                .expect("Operation should succeed")
+                .is_some()
+            {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn test_promote_on_hit() {
+        let (cache, _temp_dir) = create_promoting_cache(Duration::from_secs(3600), true);
+        let key = RibbitKey::new("cdns", "us");
+        let value = Bytes::from("cdn data");
+
+        cache
+            .put_to_layer(key.clone(), value.clone(), 1)
+            .await
+            .expect("Operation should succeed");
+
+        // The first lookup is served by L2 and queues the promotion
+        let first = cache.get(&key).await.expect("Operation should succeed");
+        assert_eq!(first, Some(value.clone()));
+        assert_eq!(cache.layer_hits[1].load(Ordering::Relaxed), 1);
+        assert!(wait_for_l1(&cache, &key).await);
+
+        // The next lookup is an L1 hit
+        let l1_hits = cache.layer_hits[0].load(Ordering::Relaxed);
+        let second = cache.get(&key).await.expect("Operation should succeed");
+        assert_eq!(second, Some(value));
+        assert_eq!(cache.layer_hits[0].load(Ordering::Relaxed), l1_hits + 1);
+        assert_eq!(cache.layer_hits[1].load(Ordering::Relaxed), 1);
+        assert_eq!(cache.promotion_count.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ttl_policies_by_key_kind() {
+        use crate::key::{CacheKeyKind, ContentCacheKey, TypedCacheKey};
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        // Both layers default to a short TTL that the content policy overrides
+        let config = MultiLayerCacheConfig::new()
+            .add_memory_layer(
+                MemoryCacheConfig::new()
+                    .with_max_entries(100)
+                    .with_default_ttl(Duration::from_millis(100)),
+            )
+            .add_disk_layer(
+                DiskCacheConfig::new(temp_dir.path())
+                    .with_max_files(1000)
+                    .with_default_ttl(Duration::from_millis(100)),
+            )
+            .with_ttl_policy(CacheKeyKind::Ribbit, Duration::from_millis(150))
+            .with_ttl_policy(CacheKeyKind::Content, TtlPolicy::Immutable);
+        let cache: MultiLayerCacheImpl<TypedCacheKey> =
+            MultiLayerCacheImpl::new(config).expect("Operation should succeed");
+
+        let versions = TypedCacheKey::from(RibbitKey::new("versions", "us"));
+        let content = TypedCacheKey::from(ContentCacheKey::new(ContentKey::from_data(b"block")));
+        for key in [&versions, &content] {
+            cache
+                .put(key.clone(), Bytes::from("data"))
+                .await
+                .expect("Operation should succeed");
+        }
+        cache
+            .put_to_layer(content.clone(), Bytes::from("data"), 1)
+            .await
+            .expect("Operation should succeed");
+
+        assert!(
+            cache.layers[0]
+                .remaining_ttl(&versions)
+                .is_some_and(|ttl| ttl > Duration::from_millis(100))
+        );
+        assert_eq!(cache.layers[0].remaining_ttl(&content), None);
+        assert_eq!(cache.layers[1].remaining_ttl(&content), None);
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(
+            cache
+                .get(&versions)
+                .await
+                .expect("Operation should succeed"),
+            None
+        );
+        assert_eq!(
+            cache.get(&content).await.expect("Operation should succeed"),
+            Some(Bytes::from("data"))
+        );
+
+        // An explicit TTL still wins over the policy
+        cache
+            .put_with_ttl(content.clone(), Bytes::from("data"), Duration::from_secs(5))
+            .await
+            .expect("Operation should succeed");
+        assert!(cache.layers[0].remaining_ttl(&content).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_promoted_entry_keeps_remaining_ttl() {
+        let (cache, _temp_dir) = create_promoting_cache(Duration::from_millis(400), true);
+        let key = RibbitKey::new("versions", "us");
+
+        cache
+            .put_to_layer(key.clone(), Bytes::from("versions data"), 1)
+            .await
+            .expect("Operation should succeed");
+        cache.get(&key).await.expect("Operation should succeed");
+        assert!(wait_for_l1(&cache, &key).await);
+
+        // L1 has a 300s default TTL but the copy expires with the L2 entry
+        let l1_ttl = cache.layers[0]
+            .remaining_ttl(&key)
+            .expect("Promoted entry should expire");
+        let l2_ttl = cache.layers[1]
+            .remaining_ttl(&key)
+            .expect("L2 entry should expire");
+        assert!(l1_ttl <= l2_ttl + Duration::from_millis(50));
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let from_l1 = cache
+            .get_from_layer(&key, 0)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(from_l1, None);
+    }
+
+    #[tokio::test]
+    async fn test_no_promotion_when_disabled() {
+        let (cache, _temp_dir) = create_promoting_cache(Duration::from_secs(3600), false);
+        let key = RibbitKey::new("bgdl", "us");
+
+        cache
+            .put_to_layer(key.clone(), Bytes::from("bgdl data"), 1)
+            .await
+            .expect("Operation should succeed");
+        cache.get(&key).await.expect("Operation should succeed");
+        assert!(!wait_for_l1(&cache, &key).await);
+        assert_eq!(cache.promotion_count.load(Ordering::Relaxed), 0);
+    }
 
     #[tokio::test]
     async fn test_multi_layer_stats() {
@@ -8,18 +8,25 @@
 #![allow(clippy::cast_precision_loss)] // Statistics/metrics calculations intentionally accept precision loss
 
 use crate::{
-    error::{NgdpCacheError, NgdpCacheResult},
+    cdn::CdnConfigCache,
+    error::{CacheError, CacheResult, NgdpCacheError, NgdpCacheResult},
     integration::{EncodingFileOps, FormatConfig, RootFileOps},
-    key::{ArchiveRangeKey, BlteBlockKey, EncodingFileKey, RootFileKey},
+    key::{
+        ArchiveIndexKey, ArchiveRangeKey, BlteBlockKey, ConfigKey, EncodingFileKey, RootFileKey,
+    },
     memory_cache::MemoryCache,
-    traits::AsyncCache,
+    traits::{AsyncCache, CacheWarming},
     validation::{NgdpValidationHooks, ValidationHooks},
 };
+use async_trait::async_trait;
 use bytes::Bytes;
 use cascette_crypto::{ContentKey, EncodingKey};
+use cascette_formats::bpsv::BpsvDocument;
+use cascette_formats::config::CdnConfig;
 use cascette_formats::root::{ContentFlags, LocaleFlags};
+use futures::stream::{self, Stream, StreamExt};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::{Arc, RwLock},
     time::{Duration, Instant},
 };
@@ -39,6 +46,8 @@ pub struct NgdpResolutionConfig {
     pub enable_validation: bool,
     /// Format parsing configuration
     pub format_config: FormatConfig,
+    /// Maximum number of config files prefetched at once while warming
+    pub warming_concurrency: usize,
 }
 
 impl Default for NgdpResolutionConfig {
@@ -50,6 +59,7 @@ impl Default for NgdpResolutionConfig {
             encoding_file_ttl: Duration::from_secs(7200), // 2 hours
             enable_validation: true,
             format_config: FormatConfig::default(),
+            warming_concurrency: 8,
         }
     }
 }
@@ -73,6 +83,53 @@ pub struct NgdpResolutionCache {
     config: NgdpResolutionConfig,
     /// Resolution metrics
     metrics: Arc<RwLock<ResolutionMetrics>>,
+    /// Config hashes resolved per product and region
+    regions: Arc<RwLock<HashMap<(String, String), RegionResolution>>>,
+    /// Archive index keys per CDN config hash
+    archive_indices: Arc<RwLock<HashMap<String, Vec<ArchiveIndexKey>>>>,
+    /// Config file cache used to prefetch configs while warming
+    config_prefetch: Option<Arc<dyn ConfigSource>>,
+}
+
+/// Config file cache the resolution cache prefetches through
+///
+/// Erases the storage backend of the injected [`CdnConfigCache`].
+#[async_trait]
+trait ConfigSource: Send + Sync {
+    async fn get_cached(&self, key: &ConfigKey) -> NgdpCacheResult<Option<Bytes>>;
+
+    async fn get_with_fallback(&self, key: &ConfigKey) -> NgdpCacheResult<Bytes>;
+}
+
+#[async_trait]
+impl<C> ConfigSource for CdnConfigCache<C>
+where
+    C: AsyncCache<ConfigKey> + Send + Sync + 'static,
+{
+    async fn get_cached(&self, key: &ConfigKey) -> NgdpCacheResult<Option<Bytes>> {
+        Self::get_cached(self, key).await
+    }
+
+    async fn get_with_fallback(&self, key: &ConfigKey) -> NgdpCacheResult<Bytes> {
+        Self::get_with_fallback(self, key).await
+    }
+}
+
+/// Config hashes resolved for one region of a product
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionResolution {
+    /// Build config hash
+    pub build_config: String,
+    /// CDN config hash
+    pub cdn_config: String,
+    /// Keyring config hash, if the region has one
+    pub key_ring: Option<String>,
+    /// Version name (e.g. `1.14.2.42597`)
+    pub versions_name: Option<String>,
+    /// CDN path from the cdns document (e.g. `tpr/wow`)
+    pub cdn_path: Option<String>,
+    /// CDN hosts from the cdns document
+    pub cdn_hosts: Vec<String>,
 }
 
 /// Metrics for NGDP resolution operations
@@ -92,6 +149,14 @@ pub struct ResolutionMetrics {
     pub encoding_cache_misses: u64,
     /// Average resolution time in microseconds
     pub avg_resolution_time_us: u64,
+    /// Number of region or archive index lookups answered from the cache
+    pub config_cache_hits: u64,
+    /// Number of region or archive index lookups not in the cache
+    pub config_cache_misses: u64,
+    /// Number of entries loaded by cache warming
+    pub warmed_entries: u64,
+    /// Number of entries cache warming failed to load
+    pub warming_failures: u64,
 }
 
 impl NgdpResolutionCache {
@@ -115,9 +180,25 @@ impl NgdpResolutionCache {
             validation,
             config,
             metrics: Arc::new(RwLock::new(ResolutionMetrics::default())),
+            regions: Arc::new(RwLock::new(HashMap::new())),
+            archive_indices: Arc::new(RwLock::new(HashMap::new())),
+            config_prefetch: None,
         })
     }
 
+    /// Prefetch config files through `configs` while warming
+    ///
+    /// Any storage backend works for `configs`. Without a config cache,
+    /// warming only records config hashes.
+    #[must_use]
+    pub fn with_config_prefetch<C>(mut self, configs: Arc<CdnConfigCache<C>>) -> Self
+    where
+        C: AsyncCache<ConfigKey> + Send + Sync + 'static,
+    {
+        self.config_prefetch = Some(configs);
+        self
+    }
+
     /// Resolve a file path to its content key using root file
     pub async fn resolve_file_to_content(
         &self,
@@ -266,6 +347,247 @@ impl NgdpResolutionCache {
             .unwrap_or_default()
     }
 
+    /// Look up the config hashes warmed for `region` of `product`
+    pub fn resolve_region(
+        &self,
+        product: &str,
+        region: &str,
+    ) -> NgdpCacheResult<Option<RegionResolution>> {
+        let result = self
+            .regions
+            .read()
+            .map_err(|_| NgdpCacheError::StreamProcessingError("Lock poisoned".to_string()))?
+            .get(&(product.to_string(), region.to_string()))
+            .cloned();
+        self.record_config_lookup(result.is_some())?;
+        Ok(result)
+    }
+
+    /// Look up the archive index keys listed by the CDN config `cdn_config`
+    pub fn archive_index_keys(
+        &self,
+        cdn_config: &str,
+    ) -> NgdpCacheResult<Option<Vec<ArchiveIndexKey>>> {
+        let result = self
+            .archive_indices
+            .read()
+            .map_err(|_| NgdpCacheError::StreamProcessingError("Lock poisoned".to_string()))?
+            .get(&cdn_config.to_ascii_lowercase())
+            .cloned();
+        self.record_config_lookup(result.is_some())?;
+        Ok(result)
+    }
+
+    /// Get a prefetched config file without contacting the CDN
+    pub async fn cached_config(&self, key: &ConfigKey) -> NgdpCacheResult<Option<Bytes>> {
+        match &self.config_prefetch {
+            Some(configs) => {
+                let result = configs.get_cached(key).await?;
+                self.record_config_lookup(result.is_some())?;
+                Ok(result)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Warm the cache from the versions and cdns documents of `product`
+    ///
+    /// Every versions row with a region and valid build and CDN config
+    /// hashes is recorded under `product` for
+    /// [`resolve_region`](Self::resolve_region), joined with the cdns row of the same name. If a config cache was
+    /// injected with [`with_config_prefetch`](Self::with_config_prefetch),
+    /// each distinct config file is prefetched as well and the archives of
+    /// every CDN config are recorded for
+    /// [`archive_index_keys`](Self::archive_index_keys).
+    ///
+    /// Rows and config files that cannot be loaded are counted in
+    /// [`ResolutionMetrics::warming_failures`] instead of failing the call.
+    /// Returns the number of entries loaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ParseFailed` if `versions` lacks the `Region`, `BuildConfig`
+    /// or `CDNConfig` field.
+    pub async fn warm_from_documents(
+        &self,
+        product: &str,
+        versions: &BpsvDocument,
+        cdns: &BpsvDocument,
+    ) -> NgdpCacheResult<usize> {
+        for field in ["Region", "BuildConfig", "CDNConfig"] {
+            if !versions.has_field(field) {
+                return Err(NgdpCacheError::ParseFailed(format!(
+                    "versions document has no {} field",
+                    field
+                )));
+            }
+        }
+
+        let cdn_schema = cdns.schema();
+        let cdn_entries: HashMap<&str, (Option<String>, Vec<String>)> = cdns
+            .rows()
+            .iter()
+            .filter_map(|row| {
+                let name = row.get_raw_by_name("Name", cdn_schema)?;
+                let path = row
+                    .get_raw_by_name("Path", cdn_schema)
+                    .filter(|path| !path.is_empty())
+                    .map(str::to_string);
+                let hosts = row
+                    .get_raw_by_name("Hosts", cdn_schema)
+                    .map(|hosts| hosts.split_whitespace().map(str::to_string).collect())
+                    .unwrap_or_default();
+                Some((name, (path, hosts)))
+            })
+            .collect();
+
+        let schema = versions.schema();
+        let mut resolved = Vec::new();
+        let mut failures = 0;
+        for row in versions.rows() {
+            let region = row.get_raw_by_name("Region", schema).unwrap_or_default();
+            let build_config = row
+                .get_raw_by_name("BuildConfig", schema)
+                .and_then(normalize_hash);
+            let cdn_config = row
+                .get_raw_by_name("CDNConfig", schema)
+                .and_then(normalize_hash);
+            let (Some(build_config), Some(cdn_config)) = (build_config, cdn_config) else {
+                failures += 1;
+                continue;
+            };
+            if region.is_empty() {
+                failures += 1;
+                continue;
+            }
+
+            let (cdn_path, cdn_hosts) = cdn_entries.get(region).cloned().unwrap_or_default();
+            resolved.push((
+                (product.to_string(), region.to_string()),
+                RegionResolution {
+                    build_config,
+                    cdn_config,
+                    key_ring: row
+                        .get_raw_by_name("KeyRing", schema)
+                        .and_then(normalize_hash),
+                    versions_name: row
+                        .get_raw_by_name("VersionsName", schema)
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_string),
+                    cdn_path,
+                    cdn_hosts,
+                },
+            ));
+        }
+
+        let mut config_keys = Vec::new();
+        let mut seen = HashSet::new();
+        for (_, resolution) in &resolved {
+            for (config_type, hash) in [
+                ("buildconfig", &resolution.build_config),
+                ("cdnconfig", &resolution.cdn_config),
+            ] {
+                if seen.insert((config_type, hash.as_str())) {
+                    config_keys.push(ConfigKey::new(config_type, hash.as_str()));
+                }
+            }
+        }
+
+        let warmed = resolved.len();
+        self.regions
+            .write()
+            .map_err(|_| NgdpCacheError::StreamProcessingError("Lock poisoned".to_string()))?
+            .extend(resolved);
+        self.record_warming(warmed, failures)?;
+
+        let prefetched = if self.config_prefetch.is_some() {
+            self.prefetch_configs(config_keys).await?
+        } else {
+            0
+        };
+
+        Ok(warmed + prefetched)
+    }
+
+    /// Prefetch config files, recording the archives of CDN configs
+    async fn prefetch_configs(&self, keys: Vec<ConfigKey>) -> NgdpCacheResult<usize> {
+        let Some(configs) = &self.config_prefetch else {
+            return Ok(0);
+        };
+
+        let results: Vec<_> = stream::iter(keys)
+            .map(|key| async move {
+                let result = configs.get_with_fallback(&key).await;
+                (key, result)
+            })
+            .buffer_unordered(self.config.warming_concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut warmed = 0;
+        let mut failures = 0;
+        let mut archives = Vec::new();
+        for (key, result) in results {
+            let Ok(data) = result else {
+                failures += 1;
+                continue;
+            };
+            warmed += 1;
+
+            if key.config_type == "cdnconfig" {
+                match CdnConfig::parse(data.as_ref()) {
+                    Ok(cdn_config) => {
+                        let keys: Vec<_> = cdn_config
+                            .archives()
+                            .into_iter()
+                            .map(|archive| {
+                                ArchiveIndexKey::new(
+                                    archive.content_key.as_str(),
+                                    archive.content_key.as_str(),
+                                )
+                            })
+                            .collect();
+                        warmed += keys.len();
+                        archives.push((key.hash.to_ascii_lowercase(), keys));
+                    }
+                    Err(_) => failures += 1,
+                }
+            }
+        }
+
+        self.archive_indices
+            .write()
+            .map_err(|_| NgdpCacheError::StreamProcessingError("Lock poisoned".to_string()))?
+            .extend(archives);
+        self.record_warming(warmed, failures)?;
+        Ok(warmed)
+    }
+
+    fn record_config_lookup(&self, hit: bool) -> NgdpCacheResult<()> {
+        let mut metrics = self
+            .metrics
+            .write()
+            .map_err(|_| NgdpCacheError::StreamProcessingError("Lock poisoned".to_string()))?;
+        if hit {
+            metrics.config_cache_hits += 1;
+        } else {
+            metrics.config_cache_misses += 1;
+        }
+        drop(metrics);
+        Ok(())
+    }
+
+    fn record_warming(&self, warmed: usize, failures: usize) -> NgdpCacheResult<()> {
+        let mut metrics = self
+            .metrics
+            .write()
+            .map_err(|_| NgdpCacheError::StreamProcessingError("Lock poisoned".to_string()))?;
+        metrics.warmed_entries += warmed as u64;
+        metrics.warming_failures += failures as u64;
+        drop(metrics);
+        Ok(())
+    }
+
     /// Store a root file in cache
     pub async fn cache_root_file(
         &self,
@@ -287,6 +609,29 @@ impl NgdpResolutionCache {
     }
 }
 
+#[async_trait]
+impl CacheWarming<ConfigKey> for NgdpResolutionCache {
+    /// Prefetch `keys` through the injected config cache
+    async fn warm(&self, keys: Vec<ConfigKey>) -> CacheResult<usize> {
+        self.prefetch_configs(keys)
+            .await
+            .map_err(|e| CacheError::Backend(e.to_string()))
+    }
+
+    fn supports_warming(&self) -> bool {
+        self.config_prefetch.is_some()
+    }
+}
+
+/// Lowercase a 32 digit hex hash, rejecting anything else
+fn normalize_hash(hash: &str) -> Option<String> {
+    (hash.len() == 32 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then(|| hash.to_ascii_lowercase())
+}
+
+/// Number of entries read at once while verifying a content-addressed cache
+const VERIFY_CONCURRENCY: usize = 16;
+
 /// Content-Addressed Cache with integrity verification
 ///
 /// This cache ensures content integrity by validating content keys match
@@ -300,6 +645,30 @@ pub struct ContentAddressedCache<C> {
     metrics: Arc<RwLock<ContentValidationMetrics>>,
 }
 
+/// Outcome of verifying one stored entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryStatus {
+    /// The data hashes to its content key
+    Verified,
+    /// The data does not hash to its content key
+    Corrupted,
+    /// The entry was removed from the underlying cache while verifying
+    Missing,
+}
+
+/// Result of verifying every entry of a content-addressed cache
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct VerificationReport {
+    /// Entries whose data matches their content key
+    pub verified_ok: u64,
+    /// Entries whose data does not match their content key
+    pub corrupted: u64,
+    /// Entries evicted or removed from the underlying cache
+    pub missing: u64,
+    /// Content keys of the corrupted entries
+    pub corrupted_keys: Vec<ContentKey>,
+}
+
 /// Metrics for content validation operations
 #[derive(Debug, Default, Clone)]
 pub struct ContentValidationMetrics {
@@ -386,7 +755,70 @@ where
         }
 
         let key = BlteBlockKey::new_raw(content_key, 0);
-        self.inner.put(key, data).await.map_err(Into::into)
+        self.inner.put(key, data).await?;
+        Ok(())
+    }
+
+    /// Verify every content entry of the underlying cache
+    ///
+    /// The keys are enumerated from the underlying cache, so entries stored
+    /// by other means or by earlier processes are checked too. Each entry is
+    /// read back and its MD5 compared with its content key. Corrupted
+    /// entries are left in place.
+    ///
+    /// Fails if the underlying cache cannot enumerate its keys.
+    pub async fn verify_all(&self) -> CacheResult<VerificationReport> {
+        let mut report = VerificationReport::default();
+
+        let mut results = std::pin::pin!(self.verify_all_stream().await?);
+        while let Some(result) = results.next().await {
+            let (content_key, status) = result?;
+            match status {
+                EntryStatus::Verified => report.verified_ok += 1,
+                EntryStatus::Corrupted => {
+                    report.corrupted += 1;
+                    report.corrupted_keys.push(content_key);
+                }
+                EntryStatus::Missing => report.missing += 1,
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Verify every content entry of the underlying cache as a stream
+    ///
+    /// Yields the status of each entry as it is checked, without collecting
+    /// a report, for caches too large to hold the results in memory. Only
+    /// the keys are enumerated up front; entries are read a few at a time
+    /// and in no particular order. Blocks other than the whole-content
+    /// entries written by [`put_validated`](Self::put_validated) are
+    /// skipped.
+    pub async fn verify_all_stream(
+        &self,
+    ) -> CacheResult<impl Stream<Item = CacheResult<(ContentKey, EntryStatus)>> + '_> {
+        let keys: Vec<ContentKey> = self
+            .inner
+            .keys()
+            .await?
+            .into_iter()
+            .filter(|key| !key.is_decompressed && key.block_index == 0)
+            .map(|key| key.content_key)
+            .collect();
+
+        Ok(stream::iter(keys)
+            .map(move |content_key| async move {
+                let key = BlteBlockKey::new_raw(content_key, 0);
+                let status = match self.inner.get(&key).await? {
+                    None => EntryStatus::Missing,
+                    Some(data) if ContentKey::from_data(&data) == content_key => {
+                        EntryStatus::Verified
+                    }
+                    Some(_) => EntryStatus::Corrupted,
+                };
+                Ok((content_key, status))
+            })
+            .buffer_unordered(VERIFY_CONCURRENCY))
     }
 
     /// Get validation metrics
@@ -711,7 +1143,8 @@ where
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
-    use crate::config::MemoryCacheConfig;
+    use crate::config::{DiskCacheConfig, MemoryCacheConfig};
+    use crate::disk_cache::DiskCache;
 
     #[tokio::test]
     async fn test_ngdp_resolution_cache_creation() {
@@ -748,6 +1181,121 @@ mod tests {
         assert_eq!(metrics.successful_validations, 1);
     }
 
+    #[tokio::test]
+    async fn test_verify_all_detects_corruption() {
+        let config = MemoryCacheConfig::default();
+        let inner = Arc::new(MemoryCache::new(config).expect("Operation should succeed"));
+        let validation = Arc::new(NgdpValidationHooks::default());
+        let cache = ContentAddressedCache::new(Arc::clone(&inner), validation);
+
+        let mut keys = Vec::new();
+        for i in 0..20 {
+            let data = Bytes::from(format!("content {i}"));
+            let content_key = ContentKey::from_data(&data);
+            cache
+                .put_validated(content_key, data)
+                .await
+                .expect("Operation should succeed");
+            keys.push(content_key);
+        }
+
+        let report = cache.verify_all().await.expect("Operation should succeed");
+        assert_eq!(report.verified_ok, 20);
+        assert_eq!(report.corrupted, 0);
+        assert_eq!(report.missing, 0);
+
+        // Overwrite one entry behind the cache's back and drop another
+        inner
+            .put(BlteBlockKey::new_raw(keys[3], 0), Bytes::from("bit rot"))
+            .await
+            .expect("Operation should succeed");
+        inner
+            .remove(&BlteBlockKey::new_raw(keys[7], 0))
+            .await
+            .expect("Operation should succeed");
+
+        let report = cache.verify_all().await.expect("Operation should succeed");
+        assert_eq!(report.verified_ok, 18);
+        assert_eq!(report.corrupted, 1);
+        assert_eq!(report.missing, 0);
+        assert_eq!(report.corrupted_keys, vec![keys[3]]);
+    }
+
+    #[tokio::test]
+    async fn test_verify_all_checks_entries_from_earlier_processes() {
+        let temp_dir = tempfile::TempDir::new().expect("Operation should succeed");
+        let data = Bytes::from("written by an earlier process");
+        let content_key = ContentKey::from_data(&data);
+        let unrelated = Bytes::from("stored without validation");
+        let unrelated_key = ContentKey::from_data(&unrelated);
+
+        {
+            let inner = Arc::new(
+                DiskCache::new(DiskCacheConfig::new(temp_dir.path()))
+                    .expect("Operation should succeed"),
+            );
+            let cache = ContentAddressedCache::new(
+                Arc::clone(&inner),
+                Arc::new(NgdpValidationHooks::default()),
+            );
+            cache
+                .put_validated(content_key, data)
+                .await
+                .expect("Operation should succeed");
+            inner
+                .put(
+                    BlteBlockKey::new_raw(unrelated_key, 0),
+                    Bytes::from("bit rot"),
+                )
+                .await
+                .expect("Operation should succeed");
+        }
+
+        let inner = Arc::new(
+            DiskCache::new(DiskCacheConfig::new(temp_dir.path()))
+                .expect("Operation should succeed"),
+        );
+        let cache = ContentAddressedCache::new(inner, Arc::new(NgdpValidationHooks::default()));
+        let report = cache.verify_all().await.expect("Operation should succeed");
+        assert_eq!(report.verified_ok, 1);
+        assert_eq!(report.corrupted, 1);
+        assert_eq!(report.corrupted_keys, vec![unrelated_key]);
+    }
+
+    #[tokio::test]
+    async fn test_verify_all_stream() {
+        let config = MemoryCacheConfig::default();
+        let inner = Arc::new(MemoryCache::new(config).expect("Operation should succeed"));
+        let validation = Arc::new(NgdpValidationHooks::default());
+        let cache = ContentAddressedCache::new(Arc::clone(&inner), validation);
+
+        let good = Bytes::from("good");
+        let bad = Bytes::from("bad");
+        let good_key = ContentKey::from_data(&good);
+        let bad_key = ContentKey::from_data(&bad);
+        for (key, data) in [(good_key, good), (bad_key, bad)] {
+            cache
+                .put_validated(key, data)
+                .await
+                .expect("Operation should succeed");
+        }
+        inner
+            .put(BlteBlockKey::new_raw(bad_key, 0), Bytes::from("bae"))
+            .await
+            .expect("Operation should succeed");
+
+        let results: HashMap<_, _> = cache
+            .verify_all_stream()
+            .await
+            .expect("Operation should succeed")
+            .map(|result| result.expect("Operation should succeed"))
+            .collect()
+            .await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[&good_key], EntryStatus::Verified);
+        assert_eq!(results[&bad_key], EntryStatus::Corrupted);
+    }
+
     #[tokio::test]
     async fn test_blte_block_cache() {
         let config = MemoryCacheConfig::default();
@@ -859,4 +1407,231 @@ mod tests {
         assert!(overlapping.contains(&(0, 1024)));
         assert!(overlapping.contains(&(1024, 1024)));
     }
+
+    const BUILD_CONFIG: &str = "0123456789abcdef0123456789abcdef";
+    const CDN_CONFIG: &str = "fedcba9876543210fedcba9876543210";
+
+    fn versions_document() -> BpsvDocument {
+        cascette_formats::bpsv::parse(&format!(
+            "Region!STRING:0|BuildConfig!HEX:16|CDNConfig!HEX:16|KeyRing!HEX:16|BuildId!DEC:4|VersionsName!String:0\n\
+             ## seqn = 1\n\
+             us|{BUILD_CONFIG}|{CDN_CONFIG}||42597|1.14.2.42597\n\
+             eu|{}|{CDN_CONFIG}||42597|1.14.2.42597\n\
+             kr||{CDN_CONFIG}||42597|1.14.2.42597\n",
+            BUILD_CONFIG.to_ascii_uppercase()
+        ))
+        .expect("Versions document should parse")
+    }
+
+    fn cdns_document() -> BpsvDocument {
+        cascette_formats::bpsv::parse(
+            "Name!STRING:0|Path!STRING:0|Hosts!STRING:0|Servers!STRING:0|ConfigPath!STRING:0\n\
+             ## seqn = 1\n\
+             us|tpr/wow|us.cdn.example.com level3.example.com||tpr/configs/data\n",
+        )
+        .expect("CDNs document should parse")
+    }
+
+    #[tokio::test]
+    async fn test_warm_from_documents_resolves_regions() {
+        let cache = NgdpResolutionCache::new(NgdpResolutionConfig::default())
+            .expect("Operation should succeed");
+        assert!(!cache.supports_warming());
+
+        let warmed = cache
+            .warm_from_documents("wow_classic_era", &versions_document(), &cdns_document())
+            .await
+            .expect("Warming should succeed");
+        assert_eq!(warmed, 2);
+
+        let us = cache
+            .resolve_region("wow_classic_era", "us")
+            .expect("Lookup should succeed")
+            .expect("us should be warmed");
+        assert_eq!(us.build_config, BUILD_CONFIG);
+        assert_eq!(us.cdn_config, CDN_CONFIG);
+        assert_eq!(us.key_ring, None);
+        assert_eq!(us.versions_name.as_deref(), Some("1.14.2.42597"));
+        assert_eq!(us.cdn_path.as_deref(), Some("tpr/wow"));
+        assert_eq!(us.cdn_hosts.len(), 2);
+
+        // Hashes are lowercased and regions missing from cdns have no hosts
+        let eu = cache
+            .resolve_region("wow_classic_era", "eu")
+            .expect("Lookup should succeed")
+            .expect("eu should be warmed");
+        assert_eq!(eu.build_config, BUILD_CONFIG);
+        assert!(eu.cdn_hosts.is_empty());
+
+        // The kr row has no build config
+        assert!(
+            cache
+                .resolve_region("wow_classic_era", "kr")
+                .expect("Lookup")
+                .is_none()
+        );
+
+        let metrics = cache.metrics();
+        assert_eq!(metrics.warmed_entries, 2);
+        assert_eq!(metrics.warming_failures, 1);
+        assert_eq!(metrics.config_cache_hits, 2);
+        assert_eq!(metrics.config_cache_misses, 1);
+    }
+
+    #[tokio::test]
+    async fn test_warm_from_documents_keys_regions_by_product() {
+        let cache = NgdpResolutionCache::new(NgdpResolutionConfig::default())
+            .expect("Operation should succeed");
+        let other_build = "00000000000000000000000000000001";
+        let other_versions = cascette_formats::bpsv::parse(&format!(
+            "Region!STRING:0|BuildConfig!HEX:16|CDNConfig!HEX:16\n\
+             ## seqn = 1\n\
+             us|{other_build}|{CDN_CONFIG}\n"
+        ))
+        .expect("Versions document should parse");
+
+        cache
+            .warm_from_documents("wow_classic_era", &versions_document(), &cdns_document())
+            .await
+            .expect("Warming should succeed");
+        cache
+            .warm_from_documents("wow", &other_versions, &cdns_document())
+            .await
+            .expect("Warming should succeed");
+
+        let classic = cache
+            .resolve_region("wow_classic_era", "us")
+            .expect("Lookup should succeed")
+            .expect("Classic us should be warmed");
+        let retail = cache
+            .resolve_region("wow", "us")
+            .expect("Lookup should succeed")
+            .expect("Retail us should be warmed");
+        assert_eq!(classic.build_config, BUILD_CONFIG);
+        assert_eq!(retail.build_config, other_build);
+        assert!(cache.resolve_region("wow", "eu").expect("Lookup").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_warm_from_documents_requires_config_fields() {
+        let cache = NgdpResolutionCache::new(NgdpResolutionConfig::default())
+            .expect("Operation should succeed");
+        let versions = cascette_formats::bpsv::parse("Region!STRING:0\n## seqn = 1\nus\n")
+            .expect("Document should parse");
+
+        let result = cache
+            .warm_from_documents("wow_classic_era", &versions, &cdns_document())
+            .await;
+        assert!(matches!(result, Err(NgdpCacheError::ParseFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_warmed_lookups_do_not_touch_cdn() {
+        let cdn = Arc::new(crate::cdn::CdnClient::new(crate::cdn::CdnConfig::default()));
+        let config_store = Arc::new(
+            MemoryCache::new(MemoryCacheConfig::default()).expect("Operation should succeed"),
+        );
+        // The CDN config is already cached, the build config is fetched
+        config_store
+            .put(
+                ConfigKey::new("cdnconfig", CDN_CONFIG),
+                Bytes::from(
+                    "# CDN Configuration\narchives = 00112233445566778899aabbccddeeff \
+                     ffeeddccbbaa99887766554433221100\narchive-group = \
+                     0f0e0d0c0b0a09080706050403020100\n",
+                ),
+            )
+            .await
+            .expect("Operation should succeed");
+        let configs = Arc::new(CdnConfigCache::new(config_store, cdn.clone()));
+
+        let cache = NgdpResolutionCache::new(NgdpResolutionConfig {
+            warming_concurrency: 2,
+            ..NgdpResolutionConfig::default()
+        })
+        .expect("Operation should succeed")
+        .with_config_prefetch(configs);
+        assert!(cache.supports_warming());
+
+        let warmed = cache
+            .warm_from_documents("wow_classic_era", &versions_document(), &cdns_document())
+            .await
+            .expect("Warming should succeed");
+        // Two regions, two config files and two archive indices
+        assert_eq!(warmed, 6);
+        let requests = cdn
+            .metrics()
+            .expect("metrics should succeed")
+            .total_requests;
+        assert_eq!(requests, 1);
+
+        let us = cache
+            .resolve_region("wow_classic_era", "us")
+            .expect("Lookup should succeed")
+            .expect("us should be warmed");
+        for key in [
+            ConfigKey::new("buildconfig", us.build_config.as_str()),
+            ConfigKey::new("cdnconfig", us.cdn_config.as_str()),
+        ] {
+            assert!(
+                cache
+                    .cached_config(&key)
+                    .await
+                    .expect("Lookup should succeed")
+                    .is_some()
+            );
+        }
+        let archives = cache
+            .archive_index_keys(&us.cdn_config)
+            .expect("Lookup should succeed")
+            .expect("Archive indices should be warmed");
+        assert_eq!(archives.len(), 2);
+        assert_eq!(archives[0].archive_name, "00112233445566778899aabbccddeeff");
+
+        let metrics = cache.metrics();
+        assert_eq!(metrics.config_cache_hits, 4);
+        assert_eq!(metrics.config_cache_misses, 0);
+        assert_eq!(
+            cdn.metrics()
+                .expect("metrics should succeed")
+                .total_requests,
+            requests
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cache_warming_trait_counts_failures() {
+        let cdn = Arc::new(crate::cdn::CdnClient::new(crate::cdn::CdnConfig::default()));
+        // Any storage backend can serve as the config cache
+        let temp_dir = tempfile::tempdir().expect("Operation should succeed");
+        let config_store = Arc::new(
+            crate::disk_cache::DiskCache::<ConfigKey>::new(crate::config::DiskCacheConfig::new(
+                temp_dir.path(),
+            ))
+            .expect("Operation should succeed"),
+        );
+        config_store
+            .put(
+                ConfigKey::new("buildconfig", BUILD_CONFIG),
+                Bytes::from("# Build Configuration\n"),
+            )
+            .await
+            .expect("Operation should succeed");
+        let cache = NgdpResolutionCache::new(NgdpResolutionConfig::default())
+            .expect("Operation should succeed")
+            .with_config_prefetch(Arc::new(CdnConfigCache::new(config_store, cdn)));
+
+        let warmed = cache
+            .warm(vec![
+                ConfigKey::new("buildconfig", BUILD_CONFIG),
+                ConfigKey::new("buildconfig", "not-a-hash"),
+            ])
+            .await
+            .expect("Warming should succeed");
+        assert_eq!(warmed, 1);
+
+        let metrics = cache.metrics();
+        assert_eq!(metrics.warmed_entries, 1);
+        assert_eq!(metrics.warming_failures, 1);
+    }
 }
@@ -0,0 +1,260 @@
+//! Coalescing of concurrent cache misses
+//!
+//! When many tasks miss on the same key at once, each of them would
+//! otherwise load the value from the backing store, which for CDN-backed
+//! caches means one download per task. A [`SingleFlight`] runs one load per
+//! key at a time and hands its result to every caller that asked for the
+//! key while it was running.
+//!
+//! Results are only shared while the load is in flight. Once it completes
+//! the key is forgotten, so a later failure is loaded again rather than
+//! served from here.
+
+use crate::{error::CacheResult, key::CacheKey, traits::AsyncCache};
+use bytes::Bytes;
+use dashmap::DashMap;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+#[cfg(not(target_arch = "wasm32"))]
+use futures::future::BoxFuture as LoadFuture;
+#[cfg(target_arch = "wasm32")]
+use futures::future::LocalBoxFuture as LoadFuture;
+
+/// Deduplicates concurrent loads of the same key
+///
+/// By default a failed load hands its error to every waiting caller. With
+/// [`retry_on_error`](Self::retry_on_error) each waiter instead runs its own
+/// load in turn until one succeeds.
+pub struct SingleFlight<T, E> {
+    /// Loads in flight, by canonical cache key
+    calls: DashMap<String, Arc<OnceCell<Result<T, E>>>>,
+    retry_on_error: bool,
+}
+
+impl<T: Clone, E: Clone> SingleFlight<T, E> {
+    /// Create a new single-flight group that shares errors
+    pub fn new() -> Self {
+        Self {
+            calls: DashMap::new(),
+            retry_on_error: false,
+        }
+    }
+
+    /// Let waiters retry with their own load when the shared one fails
+    pub fn retry_on_error(mut self, retry: bool) -> Self {
+        self.retry_on_error = retry;
+        self
+    }
+
+    /// Number of keys with a load in flight
+    pub fn in_flight(&self) -> usize {
+        self.calls.len()
+    }
+
+    /// Run `load` for `key`, or wait for the load already running for it
+    ///
+    /// `load` is dropped without being awaited when another caller's load
+    /// provides the result. If the caller running the shared load is
+    /// cancelled, one of the waiters takes over with its own load.
+    ///
+    /// The returned future is `Send` when `load`, `T` and `E` are.
+    #[allow(clippy::future_not_send)]
+    pub async fn run<F>(&self, key: &str, load: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+    {
+        let call = self.calls.entry(key.to_owned()).or_default().clone();
+
+        let result = if self.retry_on_error {
+            // Only successes are stored, so the next waiter loads on failure
+            match call.get_or_try_init(|| async { load.await.map(Ok) }).await {
+                Ok(shared) => shared.clone(),
+                Err(err) => Err(err),
+            }
+        } else {
+            call.get_or_init(|| load).await.clone()
+        };
+
+        // Later callers start a new load, unless one already has. A failed
+        // load with retries stays registered for the waiters still on it.
+        self.calls.remove_if(key, |_, current| {
+            Arc::ptr_eq(current, &call) && (call.initialized() || Arc::strong_count(&call) == 2)
+        });
+        result
+    }
+}
+
+impl<T: Clone, E: Clone> Default for SingleFlight<T, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, E> std::fmt::Debug for SingleFlight<T, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SingleFlight")
+            .field("in_flight", &self.calls.len())
+            .field("retry_on_error", &self.retry_on_error)
+            .finish()
+    }
+}
+
+impl SingleFlight<Bytes, crate::error::CacheError> {
+    /// [`AsyncCache::get_or_insert_with`] for `cache`, with concurrent
+    /// misses on the same key sharing one `load`
+    pub async fn get_or_insert_with<K, C>(
+        &self,
+        cache: &C,
+        key: K,
+        load: LoadFuture<'_, CacheResult<Bytes>>,
+    ) -> CacheResult<Bytes>
+    where
+        K: CacheKey,
+        C: AsyncCache<K> + ?Sized,
+    {
+        if let Some(value) = cache.get(&key).await? {
+            return Ok(value);
+        }
+
+        self.run(key.as_cache_key(), async {
+            // A load that finished after the miss above has stored the value
+            if cache.contains(&key).await?
+                && let Some(value) = cache.get(&key).await?
+            {
+                return Ok(value);
+            }
+
+            let value = load.await?;
+            cache.put(key.clone(), value.clone()).await?;
+            Ok(value)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::config::MemoryCacheConfig;
+    use crate::error::CacheError;
+    use crate::key::RibbitKey;
+    use crate::memory_cache::MemoryCache;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    const CALLERS: usize = 50;
+
+    /// A load that counts its runs and takes long enough for all callers
+    /// to pile up behind it
+    async fn slow_load(runs: Arc<AtomicUsize>, result: CacheResult<Bytes>) -> CacheResult<Bytes> {
+        runs.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        result
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_misses_load_once() {
+        let cache = Arc::new(
+            MemoryCache::<RibbitKey>::new(MemoryCacheConfig::default())
+                .expect("Cache should be created"),
+        );
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..CALLERS)
+            .map(|_| {
+                let cache = Arc::clone(&cache);
+                let runs = Arc::clone(&runs);
+                tokio::spawn(async move {
+                    let key = RibbitKey::new("versions", "us");
+                    let load = slow_load(runs, Ok(Bytes::from_static(b"data")));
+                    cache.get_or_insert_with(key, Box::pin(load)).await
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            let value = task
+                .await
+                .expect("Task should not panic")
+                .expect("Load should succeed");
+            assert_eq!(value, Bytes::from_static(b"data"));
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        // The value was stored, so later calls do not load at all
+        let key = RibbitKey::new("versions", "us");
+        let load = slow_load(Arc::clone(&runs), Ok(Bytes::new()));
+        let value = cache
+            .get_or_insert_with(key, Box::pin(load))
+            .await
+            .expect("Cached value should be returned");
+        assert_eq!(value, Bytes::from_static(b"data"));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_shared_by_waiters() {
+        let flight = Arc::new(SingleFlight::<Bytes, CacheError>::new());
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..CALLERS)
+            .map(|_| {
+                let flight = Arc::clone(&flight);
+                let runs = Arc::clone(&runs);
+                tokio::spawn(async move {
+                    let load = slow_load(runs, Err(CacheError::Backend("offline".to_string())));
+                    flight.run("versions", load).await
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            let result = task.await.expect("Task should not panic");
+            assert!(matches!(result, Err(CacheError::Backend(msg)) if msg == "offline"));
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(flight.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_error_runs_waiter_loads() {
+        let flight = Arc::new(SingleFlight::<Bytes, CacheError>::new().retry_on_error(true));
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let failing = {
+            let flight = Arc::clone(&flight);
+            let runs = Arc::clone(&runs);
+            tokio::spawn(async move {
+                let load = slow_load(runs, Err(CacheError::Backend("offline".to_string())));
+                flight.run("versions", load).await
+            })
+        };
+        // Let the failing load start before the others join it
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let waiters: Vec<_> = (0..CALLERS)
+            .map(|_| {
+                let flight = Arc::clone(&flight);
+                let runs = Arc::clone(&runs);
+                tokio::spawn(async move {
+                    let load = slow_load(runs, Ok(Bytes::from_static(b"data")));
+                    flight.run("versions", load).await
+                })
+            })
+            .collect();
+
+        let result = failing.await.expect("Task should not panic");
+        assert!(matches!(result, Err(CacheError::Backend(_))));
+        for task in waiters {
+            let value = task
+                .await
+                .expect("Task should not panic")
+                .expect("Retried load should succeed");
+            assert_eq!(value, Bytes::from_static(b"data"));
+        }
+        // The failed load, then one retry shared by all waiters
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+}
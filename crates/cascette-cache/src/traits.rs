@@ -17,9 +17,17 @@
 //! On WASM, caches are single-threaded and use browser storage APIs that
 //! are not `Send`. The trait uses `#[async_trait(?Send)]` on WASM.
 
-use crate::{error::CacheResult, key::CacheKey, stats::CacheStats};
+use crate::{
+    error::{CacheError, CacheResult},
+    key::CacheKey,
+    stats::CacheStats,
+};
 use async_trait::async_trait;
 use bytes::Bytes;
+#[cfg(not(target_arch = "wasm32"))]
+use futures::future::BoxFuture;
+#[cfg(target_arch = "wasm32")]
+use futures::future::LocalBoxFuture;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
@@ -56,9 +64,41 @@ pub trait AsyncCache<K: CacheKey>: Send + Sync {
     /// Entry count, not byte size.
     async fn size(&self) -> CacheResult<usize>;
 
+    /// Keys of all unexpired entries, in no particular order.
+    ///
+    /// Persistent caches include entries written by earlier processes. The
+    /// default reports that the backend cannot enumerate its keys.
+    async fn keys(&self) -> CacheResult<Vec<K>> {
+        Err(CacheError::Backend(
+            "cache does not support key enumeration".to_string(),
+        ))
+    }
+
     async fn is_empty(&self) -> CacheResult<bool> {
         Ok(self.size().await? == 0)
     }
+
+    /// Returns the cached value, or stores and returns the one `load`
+    /// produces. `load` is only awaited on a miss.
+    ///
+    /// The default runs one `load` per missing caller. The built-in caches
+    /// coalesce concurrent misses on the same key into a single `load`, see
+    /// [`SingleFlight`](crate::singleflight::SingleFlight).
+    async fn get_or_insert_with(
+        &self,
+        key: K,
+        load: BoxFuture<'_, CacheResult<Bytes>>,
+    ) -> CacheResult<Bytes>
+    where
+        K: 'async_trait,
+    {
+        if let Some(value) = self.get(&key).await? {
+            return Ok(value);
+        }
+        let value = load.await?;
+        self.put(key, value.clone()).await?;
+        Ok(value)
+    }
 }
 
 // ============================================================================
@@ -90,9 +130,41 @@ pub trait AsyncCache<K: CacheKey> {
     /// Entry count, not byte size.
     async fn size(&self) -> CacheResult<usize>;
 
+    /// Keys of all unexpired entries, in no particular order.
+    ///
+    /// Persistent caches include entries written by earlier processes. The
+    /// default reports that the backend cannot enumerate its keys.
+    async fn keys(&self) -> CacheResult<Vec<K>> {
+        Err(CacheError::Backend(
+            "cache does not support key enumeration".to_string(),
+        ))
+    }
+
     async fn is_empty(&self) -> CacheResult<bool> {
         Ok(self.size().await? == 0)
     }
+
+    /// Returns the cached value, or stores and returns the one `load`
+    /// produces. `load` is only awaited on a miss.
+    ///
+    /// The default runs one `load` per missing caller. The built-in caches
+    /// coalesce concurrent misses on the same key into a single `load`, see
+    /// [`SingleFlight`](crate::singleflight::SingleFlight).
+    async fn get_or_insert_with(
+        &self,
+        key: K,
+        load: LocalBoxFuture<'_, CacheResult<Bytes>>,
+    ) -> CacheResult<Bytes>
+    where
+        K: 'async_trait,
+    {
+        if let Some(value) = self.get(&key).await? {
+            return Ok(value);
+        }
+        let value = load.await?;
+        self.put(key, value.clone()).await?;
+        Ok(value)
+    }
 }
 
 // ============================================================================
@@ -212,6 +284,9 @@ pub enum EvictionPolicy {
     Fifo,
     Random,
     Ttl,
+    /// Adaptive Replacement Cache: balances recency against frequency so
+    /// that a scan of one-off keys does not flush frequently reused ones
+    Arc,
 }
 
 // ============================================================================
@@ -537,6 +612,7 @@ mod tests {
             EvictionPolicy::Fifo,
             EvictionPolicy::Random,
             EvictionPolicy::Ttl,
+            EvictionPolicy::Arc,
         ];
 
         for policy in policies {
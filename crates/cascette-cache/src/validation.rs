@@ -8,6 +8,7 @@
 #![allow(missing_docs)]
 
 use crate::error::{CacheError, CacheResult, NgdpCacheError, NgdpCacheResult};
+use crate::key::ContentCacheKey;
 use async_trait::async_trait;
 use bytes::Bytes;
 use cascette_crypto::{ContentKey, EncodingKey, Jenkins96, TactKey};
@@ -113,6 +114,34 @@ pub trait ValidationHooks: Send + Sync {
     fn get_metrics(&self) -> Option<&ValidationMetrics> {
         None
     }
+
+    /// Called by caches before storing `data` under `key`, the canonical
+    /// [`CacheKey::as_cache_key`](crate::key::CacheKey::as_cache_key) string.
+    /// An invalid result rejects the put. Default accepts everything.
+    fn before_put(&self, key: &str, data: &[u8]) -> ValidationResult {
+        let _ = key;
+        ValidationResult::valid(
+            std::time::Duration::ZERO,
+            std::time::Duration::ZERO,
+            data.len(),
+        )
+    }
+}
+
+/// Run the `before_put` hook of a cache, if it has one
+pub(crate) fn check_before_put(
+    hooks: Option<&std::sync::Arc<dyn ValidationHooks>>,
+    key: &str,
+    data: &[u8],
+) -> CacheResult<()> {
+    match hooks {
+        Some(hooks) if !hooks.before_put(key, data).is_valid => {
+            Err(CacheError::ContentValidationFailed(format!(
+                "Content validation failed for key: {key}"
+            )))
+        }
+        _ => Ok(()),
+    }
 }
 
 /// Validates content by computing MD5 hash and comparing with the content key.
@@ -348,6 +377,10 @@ impl ValidationHooks for std::sync::Arc<dyn ValidationHooks> {
     fn get_metrics(&self) -> Option<&ValidationMetrics> {
         (**self).get_metrics()
     }
+
+    fn before_put(&self, key: &str, data: &[u8]) -> ValidationResult {
+        (**self).before_put(key, data)
+    }
 }
 
 /// Enhanced NGDP validation hooks with support for multiple hash algorithms
@@ -358,6 +391,8 @@ pub struct NgdpValidationHooks {
     pub md5_hooks: Md5ValidationHooks,
     pub tact_key: Option<TactKey>,
     pub jenkins96_validation: bool,
+    /// Check puts under content keys, see [`Self::with_put_validation`]
+    pub put_validation: bool,
 }
 
 impl NgdpValidationHooks {
@@ -366,6 +401,7 @@ impl NgdpValidationHooks {
             md5_hooks: Md5ValidationHooks::new(),
             tact_key: None,
             jenkins96_validation: false,
+            put_validation: false,
         }
     }
 
@@ -374,6 +410,7 @@ impl NgdpValidationHooks {
             md5_hooks: Md5ValidationHooks::new(),
             tact_key: Some(tact_key),
             jenkins96_validation: false,
+            put_validation: false,
         }
     }
 
@@ -382,6 +419,18 @@ impl NgdpValidationHooks {
         self
     }
 
+    /// Reject puts of content whose data does not hash to its key
+    ///
+    /// A cache using these hooks computes the MD5 of data stored under a
+    /// [`ContentCacheKey`] in
+    /// [`ValidationHooks::before_put`] and refuses to store it unless it
+    /// matches that key's content key. Puts under other keys are not
+    /// content addressed and are accepted.
+    pub fn with_put_validation(mut self) -> Self {
+        self.put_validation = true;
+        self
+    }
+
     pub fn validate_jenkins96(
         &self,
         expected_hash: u64,
@@ -516,6 +565,36 @@ impl ValidationHooks for NgdpValidationHooks {
     fn get_metrics(&self) -> Option<&ValidationMetrics> {
         self.md5_hooks.get_metrics()
     }
+
+    fn before_put(&self, key: &str, data: &[u8]) -> ValidationResult {
+        let expected_key = self
+            .put_validation
+            .then(|| key.parse::<ContentCacheKey>().ok())
+            .flatten();
+        let Some(expected_key) = expected_key else {
+            return ValidationResult::valid(
+                std::time::Duration::ZERO,
+                std::time::Duration::ZERO,
+                data.len(),
+            );
+        };
+
+        let start_time = Instant::now();
+        let is_valid = ContentKey::from_data(data) == expected_key.content_key;
+        let validation_time = start_time.elapsed();
+
+        if is_valid {
+            self.md5_hooks
+                .metrics
+                .record_success(validation_time, data.len());
+            ValidationResult::valid(validation_time, validation_time, data.len())
+        } else {
+            self.md5_hooks
+                .metrics
+                .record_failure(validation_time, data.len());
+            ValidationResult::invalid(validation_time, validation_time, data.len())
+        }
+    }
 }
 
 /// No-op validation hooks for performance-critical scenarios
@@ -1058,4 +1137,48 @@ mod tests {
             .expect("Operation should succeed");
         assert!(result.is_valid);
     }
+
+    #[test]
+    fn test_ngdp_validation_hooks_check_content_puts() {
+        let data = b"content addressed";
+        let key = ContentCacheKey::new(ContentKey::from_data(data));
+        let other = ContentCacheKey::new(ContentKey::from_data(b"other content"));
+        let hooks = NgdpValidationHooks::new().with_put_validation();
+
+        // Each put is checked against its own key
+        assert!(hooks.before_put(key.as_cache_key(), data).is_valid);
+        assert!(
+            hooks
+                .before_put(other.as_cache_key(), b"other content")
+                .is_valid
+        );
+        let result = hooks.before_put(other.as_cache_key(), b"something else");
+        assert!(!result.is_valid);
+        assert_eq!(result.content_size, 14);
+        assert_eq!(
+            hooks
+                .md5_hooks
+                .metrics
+                .failed_validations
+                .load(Ordering::Relaxed),
+            1
+        );
+
+        // Keys that are not content addressed are not checked
+        assert!(hooks.before_put("ribbit:summary:us", b"anything").is_valid);
+        // Without put validation every put is accepted
+        assert!(
+            NgdpValidationHooks::new()
+                .before_put(key.as_cache_key(), b"anything")
+                .is_valid
+        );
+
+        let hooks: std::sync::Arc<dyn ValidationHooks> = std::sync::Arc::new(hooks);
+        assert!(check_before_put(Some(&hooks), key.as_cache_key(), data).is_ok());
+        assert!(matches!(
+            check_before_put(Some(&hooks), key.as_cache_key(), b"other"),
+            Err(CacheError::ContentValidationFailed(_))
+        ));
+        assert!(check_before_put(None, key.as_cache_key(), b"other").is_ok());
+    }
 }
@@ -0,0 +1,139 @@
+//! Write-path compression benchmarks.
+//!
+//! Compares the built-in [`ContentCompressor`] backends on content shaped
+//! like what game installations store: text-like assets, structured binary
+//! tables, and already-compressed (incompressible) media.
+//!
+//! Run with:
+//! ```bash
+//! cargo bench -p cascette-client-storage --bench compression
+//! ```
+//!
+//! The zlib numbers depend on the `flate2` backend linked into the build.
+
+#![allow(clippy::expect_used)]
+
+use cascette_client_storage::storage::{
+    ContentCompressor, Lz4Compressor, Passthrough, ZlibCompressor,
+};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+
+const SAMPLE_SIZE: usize = 256 * 1024;
+
+/// Lua/XML-like text with a small vocabulary.
+fn text_content() -> Vec<u8> {
+    let words = [
+        "local ",
+        "function ",
+        "frame",
+        ":SetPoint(",
+        "\"CENTER\"",
+        ", ",
+        "UIParent",
+        ")\n",
+        "end\n",
+        "<Frame name=\"",
+        "\"/>\n",
+        "self.",
+        "return ",
+        "if ",
+        " then\n",
+    ];
+    let mut data = Vec::with_capacity(SAMPLE_SIZE);
+    let mut state = 0x2545_f491_u32;
+    while data.len() < SAMPLE_SIZE {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        data.extend_from_slice(words[(state >> 16) as usize % words.len()].as_bytes());
+    }
+    data.truncate(SAMPLE_SIZE);
+    data
+}
+
+/// DB2-like fixed-width records with small, correlated integers.
+fn table_content() -> Vec<u8> {
+    let mut data = Vec::with_capacity(SAMPLE_SIZE);
+    let mut id = 0u32;
+    while data.len() < SAMPLE_SIZE {
+        id += 1;
+        data.extend_from_slice(&id.to_le_bytes());
+        data.extend_from_slice(&(id % 17).to_le_bytes());
+        data.extend_from_slice(&(id * 3 / 2).to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+    }
+    data.truncate(SAMPLE_SIZE);
+    data
+}
+
+/// Pseudo-random bytes standing in for BLP/OGG media.
+fn media_content() -> Vec<u8> {
+    let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+    (0..SAMPLE_SIZE)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state.to_le_bytes()[0]
+        })
+        .collect()
+}
+
+fn backends() -> Vec<(&'static str, Box<dyn ContentCompressor>)> {
+    vec![
+        ("none", Box::new(Passthrough)),
+        ("zlib-1", Box::new(ZlibCompressor::fast())),
+        ("zlib-6", Box::new(ZlibCompressor::default())),
+        ("zlib-9", Box::new(ZlibCompressor::new(9))),
+        ("lz4", Box::new(Lz4Compressor)),
+    ]
+}
+
+fn contents() -> [(&'static str, Vec<u8>); 3] {
+    [
+        ("text", text_content()),
+        ("table", table_content()),
+        ("media", media_content()),
+    ]
+}
+
+fn bench_compress(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compress");
+    group.throughput(Throughput::Bytes(SAMPLE_SIZE as u64));
+
+    for (content, data) in contents() {
+        for (name, backend) in backends() {
+            group.bench_with_input(BenchmarkId::new(name, content), &data, |b, data| {
+                b.iter(|| backend.compress(black_box(data)).expect("compress"));
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_decompress(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decompress");
+    group.throughput(Throughput::Bytes(SAMPLE_SIZE as u64));
+
+    for (content, data) in contents() {
+        for (name, backend) in backends() {
+            let compressed = backend.compress(&data).expect("compress");
+            group.bench_with_input(
+                BenchmarkId::new(name, content),
+                &compressed,
+                |b, compressed| {
+                    b.iter(|| {
+                        backend
+                            .decompress(black_box(compressed))
+                            .expect("decompress")
+                    });
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_compress, bench_decompress);
+criterion_main!(benches);
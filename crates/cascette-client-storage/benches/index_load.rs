@@ -0,0 +1,72 @@
+//! Index loading benchmarks.
+//!
+//! Compares a cold [`IndexManager::load_all`] from the 16 per-bucket `.idx`
+//! files with loading the same entries from a group index.
+//!
+//! Run with:
+//! ```bash
+//! cargo bench -p cascette-client-storage --bench index_load
+//! ```
+
+#![allow(clippy::expect_used)]
+
+use cascette_client_storage::index::IndexManager;
+use cascette_crypto::EncodingKey;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::path::Path;
+
+const ENTRY_COUNTS: [u32; 2] = [10_000, 100_000];
+
+/// Deterministic pseudo-random encoding key
+fn ekey(index: u32) -> EncodingKey {
+    let mut state = u64::from(index).wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+    let mut bytes = [0u8; 16];
+    for chunk in bytes.chunks_mut(8) {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        chunk.copy_from_slice(&state.to_le_bytes());
+    }
+    EncodingKey::from_bytes(bytes)
+}
+
+/// Save `count` entries to `dir`, optionally with a group index
+fn populate(dir: &Path, count: u32, group_index: bool) {
+    let mut manager = IndexManager::new(dir);
+    for i in 0..count {
+        manager
+            .add_entry(&ekey(i), (i % 64) as u16, i * 0x100, 0x100)
+            .expect("add entry");
+    }
+    manager.save_all().expect("save indices");
+    if group_index {
+        manager.write_group_index().expect("write group index");
+    }
+}
+
+fn bench_load_all(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().expect("runtime");
+    let mut group = c.benchmark_group("load_all");
+
+    for count in ENTRY_COUNTS {
+        group.throughput(Throughput::Elements(u64::from(count)));
+        for (name, group_index) in [("idx_files", false), ("group_index", true)] {
+            let dir = tempfile::tempdir().expect("temp dir");
+            populate(dir.path(), count, group_index);
+
+            group.bench_with_input(BenchmarkId::new(name, count), dir.path(), |b, path| {
+                b.iter(|| {
+                    let mut manager = IndexManager::new(path);
+                    runtime.block_on(manager.load_all()).expect("load_all");
+                    assert_eq!(manager.loaded_from_group_index(), group_index);
+                    manager
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_load_all);
+criterion_main!(benches);
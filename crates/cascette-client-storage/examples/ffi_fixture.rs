@@ -0,0 +1,41 @@
+#![allow(clippy::expect_used, clippy::panic)]
+
+//! Write a synthetic installation for the C FFI test program.
+//!
+//! Creates `<dir>/Data` holding one uncompressed file with the given
+//! contents and prints the file's encoding key as hex.
+//!
+//! Usage:
+//!   cargo run --example ffi_fixture -p cascette-client-storage \
+//!       --features ffi -- <dir> <contents>
+
+use cascette_client_storage::Installation;
+use cascette_crypto::EncodingKey;
+use cascette_formats::CascFormat;
+use cascette_formats::blte::{BlteFile, CompressionMode};
+
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let (Some(dir), Some(contents)) = (args.next(), args.next()) else {
+        panic!("usage: ffi_fixture <dir> <contents>");
+    };
+
+    let installation = Installation::open(std::path::Path::new(&dir).join("Data"))
+        .expect("failed to open installation");
+    installation
+        .write_file(contents.clone().into_bytes(), false)
+        .await
+        .expect("failed to write file");
+    // Flush the in-memory index entries to .idx files
+    installation
+        .compact_indices_if_needed(0.0)
+        .await
+        .expect("failed to write indices");
+
+    let blte = BlteFile::single_chunk(contents.into_bytes(), CompressionMode::None)
+        .expect("failed to create BLTE")
+        .build()
+        .expect("failed to build BLTE");
+    println!("{}", hex::encode(EncodingKey::from_data(&blte).as_bytes()));
+}
@@ -0,0 +1,94 @@
+#ifndef CASCETTE_CLIENT_STORAGE_H
+#define CASCETTE_CLIENT_STORAGE_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+// Success.
+#define CASCETTE_OK 0
+
+// A required pointer argument was NULL.
+#define CASCETTE_ERROR_NULL_ARGUMENT -1
+
+// The output buffer is too small; the required size was stored in `out_len`.
+#define CASCETTE_ERROR_BUFFER_TOO_SMALL -2
+
+// The library panicked; the handle should be closed.
+#define CASCETTE_ERROR_PANIC -3
+
+// The installation does not hold the requested file.
+#define CASCETTE_ERROR_NOT_FOUND -4
+
+// Length of the encoding keys passed to the read functions.
+#define CASCETTE_EKEY_LENGTH 16
+
+// Opaque handle to an opened installation.
+typedef struct CascetteInstall CascetteInstall;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Open the installation at `path`.
+//
+// `path` may be the game root, a launcher flavor directory, the `Data`
+// directory or `Data/data`. Returns NULL on failure.
+//
+// # Safety
+//
+// `path` must be NULL or a NUL-terminated string.
+CascetteInstall *cascette_install_open(const char *path);
+
+// Check whether the installation holds the file with encoding key `ekey`.
+//
+// Returns 1 if it does, 0 if it does not, or a negative error code.
+//
+// # Safety
+//
+// `handle` must be NULL or a live handle from [`cascette_install_open`],
+// and `ekey` must be NULL or point to [`CASCETTE_EKEY_LENGTH`] bytes.
+int32_t cascette_install_contains(const CascetteInstall *handle, const uint8_t *ekey);
+
+// Read the file with encoding key `ekey` into `out_buf`.
+//
+// `*out_len` holds the capacity of `out_buf` on input and is set to the
+// size of the file on return. Call with `out_buf` NULL to query the size,
+// then again with a buffer of at least that size. The size query reads it
+// from the encoding file or the BLTE chunk table without decoding the
+// file. A buffer that is too small returns
+// [`CASCETTE_ERROR_BUFFER_TOO_SMALL`] without writing to it. A key the
+// installation does not hold returns [`CASCETTE_ERROR_NOT_FOUND`].
+//
+// # Safety
+//
+// `handle` must be NULL or a live handle from [`cascette_install_open`],
+// `ekey` must be NULL or point to [`CASCETTE_EKEY_LENGTH`] bytes, `out_len`
+// must be NULL or valid for reads and writes, and `out_buf` must be NULL or
+// valid for writes of `*out_len` bytes.
+int32_t cascette_install_read(const CascetteInstall *handle,
+                              const uint8_t *ekey,
+                              uint8_t *out_buf,
+                              size_t *out_len);
+
+// Message of the last error on this thread, or NULL if the last call
+// succeeded.
+//
+// The string is owned by the library and valid until the next call into
+// the library on this thread.
+const char *cascette_install_last_error_message(void);
+
+// Close a handle from [`cascette_install_open`]. NULL is ignored.
+//
+// # Safety
+//
+// `handle` must be NULL or a live handle that no other thread is using,
+// and must not be used after this call.
+void cascette_install_close(CascetteInstall *handle);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CASCETTE_CLIENT_STORAGE_H */
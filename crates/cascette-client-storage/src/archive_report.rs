@@ -0,0 +1,210 @@
+//! Live and dead space in local data archives
+//!
+//! Replacing or removing a file drops its index entry but leaves its bytes
+//! in the `.data` archive. An archive report cross-references the index
+//! entries with the archive files to show how much of each archive is still
+//! referenced, so a tool can decide which archives are worth repacking and
+//! how much space that would reclaim.
+//!
+//! Live bytes are the union of the spans the index entries cover, each
+//! including the file's 30-byte local header. Spans shared by several
+//! entries are counted once, and spans past the end of an archive are cut
+//! at its size.
+
+use crate::index::IndexEntry;
+use crate::{Result, StorageError};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Space usage of one `.data` archive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ArchiveReport {
+    /// Archive number (`data.XXX`)
+    pub archive_id: u16,
+    /// Size of the archive file in bytes, 0 if it is not open
+    pub file_size: u64,
+    /// Index entries pointing into the archive
+    pub live_entries: u64,
+    /// Bytes covered by the live entries
+    pub live_bytes: u64,
+    /// Bytes no index entry references
+    pub dead_bytes: u64,
+}
+
+impl ArchiveReport {
+    /// Build a report per archive from archive sizes and index entries
+    ///
+    /// Every archive in `archive_sizes` is reported, as is every archive an
+    /// entry points into; archives missing from `archive_sizes` are reported
+    /// with a size of 0. Reports are sorted by archive number.
+    pub fn compute(
+        archive_sizes: &BTreeMap<u16, u64>,
+        entries: impl IntoIterator<Item = IndexEntry>,
+    ) -> Vec<Self> {
+        let mut spans: BTreeMap<u16, Vec<(u64, u64)>> = archive_sizes
+            .keys()
+            .map(|&archive_id| (archive_id, Vec::new()))
+            .collect();
+        for entry in entries {
+            let start = u64::from(entry.archive_location.archive_offset);
+            spans
+                .entry(entry.archive_location.archive_id)
+                .or_default()
+                .push((start, start + u64::from(entry.size)));
+        }
+
+        spans
+            .into_iter()
+            .map(|(archive_id, mut spans)| {
+                let file_size = archive_sizes.get(&archive_id).copied().unwrap_or(0);
+                let live_bytes = covered_bytes(&mut spans, file_size);
+                Self {
+                    archive_id,
+                    file_size,
+                    live_entries: spans.len() as u64,
+                    live_bytes,
+                    dead_bytes: file_size - live_bytes,
+                }
+            })
+            .collect()
+    }
+
+    /// Fraction of the archive no index entry references, from 0.0 to 1.0
+    pub fn dead_ratio(&self) -> f64 {
+        if self.file_size == 0 {
+            return 0.0;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let ratio = self.dead_bytes as f64 / self.file_size as f64;
+        ratio
+    }
+}
+
+/// Dead bytes summed over all archives, the space a full repack reclaims
+pub fn total_dead_space(reports: &[ArchiveReport]) -> u64 {
+    reports.iter().map(|report| report.dead_bytes).sum()
+}
+
+/// Render reports as pretty-printed JSON
+///
+/// # Errors
+///
+/// Returns an error if serialization fails.
+pub fn reports_to_json(reports: &[ArchiveReport]) -> Result<String> {
+    serde_json::to_string_pretty(reports)
+        .map_err(|e| StorageError::InvalidFormat(format!("failed to serialize report: {e}")))
+}
+
+impl fmt::Display for ArchiveReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "data.{:03}: {} bytes, {} entries, {} live, {} dead ({:.1}%)",
+            self.archive_id,
+            self.file_size,
+            self.live_entries,
+            self.live_bytes,
+            self.dead_bytes,
+            self.dead_ratio() * 100.0
+        )
+    }
+}
+
+/// Length of the union of `spans`, cut at `limit`
+fn covered_bytes(spans: &mut [(u64, u64)], limit: u64) -> u64 {
+    spans.sort_unstable();
+
+    let mut covered = 0;
+    let mut reached = 0;
+    for &(start, end) in spans.iter() {
+        let start = start.max(reached).min(limit);
+        let end = end.min(limit);
+        if end > start {
+            covered += end - start;
+            reached = end;
+        }
+    }
+    covered
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn entry(archive_id: u16, offset: u32, size: u32) -> IndexEntry {
+        IndexEntry::new([0; 9], archive_id, offset, size)
+    }
+
+    #[test]
+    fn test_compute_per_archive() {
+        let sizes = BTreeMap::from([(0, 1000), (1, 500)]);
+        let entries = [entry(0, 0, 100), entry(0, 300, 200), entry(1, 0, 500)];
+
+        let reports = ArchiveReport::compute(&sizes, entries);
+
+        assert_eq!(
+            reports,
+            [
+                ArchiveReport {
+                    archive_id: 0,
+                    file_size: 1000,
+                    live_entries: 2,
+                    live_bytes: 300,
+                    dead_bytes: 700,
+                },
+                ArchiveReport {
+                    archive_id: 1,
+                    file_size: 500,
+                    live_entries: 1,
+                    live_bytes: 500,
+                    dead_bytes: 0,
+                },
+            ]
+        );
+        assert_eq!(total_dead_space(&reports), 700);
+        assert!((reports[0].dead_ratio() - 0.7).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_compute_overlapping_and_out_of_range_spans() {
+        let sizes = BTreeMap::from([(0, 1000), (2, 64)]);
+        // The second entry shares bytes with the first and the last one
+        // runs past the end of the archive
+        let entries = [entry(0, 100, 200), entry(0, 150, 200), entry(0, 900, 400)];
+
+        let reports = ArchiveReport::compute(&sizes, entries);
+
+        assert_eq!(reports[0].live_entries, 3);
+        assert_eq!(reports[0].live_bytes, 250 + 100);
+        assert_eq!(reports[0].dead_bytes, 650);
+        // An archive without entries is all dead space
+        assert_eq!(reports[1].archive_id, 2);
+        assert_eq!(reports[1].dead_bytes, 64);
+    }
+
+    #[test]
+    fn test_compute_entries_in_missing_archive() {
+        let reports = ArchiveReport::compute(&BTreeMap::new(), [entry(7, 0, 100)]);
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].archive_id, 7);
+        assert_eq!(reports[0].live_entries, 1);
+        assert_eq!(reports[0].live_bytes, 0);
+        assert_eq!(reports[0].dead_bytes, 0);
+        assert!(reports[0].dead_ratio().abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_display_and_json() {
+        let reports = ArchiveReport::compute(&BTreeMap::from([(3, 200)]), [entry(3, 0, 50)]);
+
+        assert_eq!(
+            reports[0].to_string(),
+            "data.003: 200 bytes, 1 entries, 50 live, 150 dead (75.0%)"
+        );
+        let json = reports_to_json(&reports).expect("Report should serialize");
+        assert!(json.contains("\"dead_bytes\": 150"));
+    }
+}
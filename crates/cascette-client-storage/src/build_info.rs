@@ -1,4 +1,4 @@
-//! `.build.info` parser for installation metadata.
+//! `.build.info` parser and writer for installation metadata.
 //!
 //! The `.build.info` file is a BPSV file at the installation root
 //! that contains product identification and build configuration
@@ -20,22 +20,63 @@
 //! - `Last Activated!STRING:0` -- timestamp
 //! - `Version!STRING:0` -- build version string
 //! - `Product!STRING:0` -- product code (e.g., "wow", "wow_classic")
+//!
+//! [`BuildInfoFile::new`] starts a file with these columns and
+//! [`BuildInfoFile::set_active_build`] records a build, so an installation
+//! can write its current state back out with [`BuildInfoFile::to_bpsv`].
 
 use std::path::Path;
 
-use cascette_formats::bpsv::{BpsvDocument, parse};
+use cascette_formats::bpsv::{BpsvDocument, BpsvField, BpsvSchema, BpsvType, parse};
 
 use crate::{Result, StorageError};
 
 /// Parsed `.build.info` file.
 ///
 /// Wraps a BPSV document and provides typed access to known columns.
+#[derive(Debug, Clone)]
 pub struct BuildInfoFile {
     /// Underlying BPSV document.
     document: BpsvDocument,
 }
 
+/// Columns written by [`BuildInfoFile::new`], in the order real
+/// installations use.
+const STANDARD_COLUMNS: &[(&str, BpsvType)] = &[
+    ("Branch", BpsvType::String(0)),
+    ("Active", BpsvType::Dec(1)),
+    ("Build Key", BpsvType::Hex(16)),
+    ("CDN Key", BpsvType::Hex(16)),
+    ("Install Key", BpsvType::Hex(16)),
+    ("IM Size", BpsvType::Dec(4)),
+    ("CDN Path", BpsvType::String(0)),
+    ("CDN Hosts", BpsvType::String(0)),
+    ("CDN Servers", BpsvType::String(0)),
+    ("Tags", BpsvType::String(0)),
+    ("Armadillo", BpsvType::String(0)),
+    ("Last Activated", BpsvType::String(0)),
+    ("Version", BpsvType::String(0)),
+    ("Product", BpsvType::String(0)),
+];
+
+impl Default for BuildInfoFile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BuildInfoFile {
+    /// Create an empty file with the standard columns.
+    pub fn new() -> Self {
+        let fields = STANDARD_COLUMNS
+            .iter()
+            .map(|(name, field_type)| BpsvField::new(*name, *field_type))
+            .collect();
+        Self {
+            document: BpsvDocument::new(BpsvSchema::new(fields)),
+        }
+    }
+
     /// Parse a `.build.info` file from its contents.
     pub fn parse_str(content: &str) -> Result<Self> {
         let document = parse(content).map_err(|e| {
@@ -96,6 +137,83 @@ impl BuildInfoFile {
     pub fn has_column(&self, name: &str) -> bool {
         self.document.has_field(name)
     }
+
+    /// Record `build_key` and `cdn_key` as the active build of `branch`.
+    ///
+    /// Updates the branch's row, or appends one if the branch is new, and
+    /// clears `Active` on every other row. Other columns of an existing
+    /// row are kept. Keys are 32-character hex config hashes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::InvalidFormat`] if a key is not a valid
+    /// config hash or the file lacks one of the columns being set.
+    pub fn set_active_build(
+        &mut self,
+        branch: &str,
+        build_key: &str,
+        cdn_key: &str,
+        last_activated: &str,
+    ) -> Result<()> {
+        for key in [build_key, cdn_key] {
+            if key.len() != 32 || !key.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(StorageError::InvalidFormat(format!(
+                    "invalid config hash in .build.info: {key:?}"
+                )));
+            }
+        }
+
+        let schema = self.document.schema().clone();
+        let column = |name: &str| {
+            schema.get_field_index(name).ok_or_else(|| {
+                StorageError::InvalidFormat(format!(".build.info has no {name} column"))
+            })
+        };
+        let branch_col = column("Branch")?;
+        let active_col = column("Active")?;
+        let build_key_col = column("Build Key")?;
+        let cdn_key_col = column("CDN Key")?;
+        let last_activated_col = column("Last Activated")?;
+
+        let mut rows: Vec<Vec<String>> = self
+            .document
+            .rows()
+            .iter()
+            .map(|row| row.raw_values().to_vec())
+            .collect();
+        let index = if let Some(index) = rows.iter().position(|row| row[branch_col] == branch) {
+            index
+        } else {
+            let mut row = vec![String::new(); schema.field_count()];
+            row[branch_col] = branch.to_string();
+            rows.push(row);
+            rows.len() - 1
+        };
+
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[active_col] = if i == index { "1" } else { "0" }.to_string();
+        }
+        let row = &mut rows[index];
+        row[build_key_col] = build_key.to_ascii_lowercase();
+        row[cdn_key_col] = cdn_key.to_ascii_lowercase();
+        row[last_activated_col] = last_activated.to_string();
+
+        let mut document = BpsvDocument::new(schema);
+        for row in rows {
+            document.add_raw_row(row).map_err(|e| {
+                StorageError::InvalidFormat(format!("invalid .build.info row: {e}"))
+            })?;
+        }
+        self.document = document;
+        Ok(())
+    }
+
+    /// Convert to a BPSV document ready to be written out.
+    ///
+    /// Formatting the result with `to_string` gives the file contents.
+    pub fn to_bpsv(&self) -> BpsvDocument {
+        self.document.clone()
+    }
 }
 
 /// A single entry (row) in a `.build.info` file.
@@ -267,6 +385,99 @@ eu|0|abcdef1234567890abcdef1234567890|fedcba0987654321fedcba0987654321|111111111
         assert!(info.active_entry().is_none());
     }
 
+    #[test]
+    fn test_to_bpsv_round_trip() {
+        let info = BuildInfoFile::parse_str(SAMPLE_BUILD_INFO).expect("parse");
+        let written = info.to_bpsv().to_string();
+        let reparsed = BuildInfoFile::parse_str(&written).expect("parse");
+
+        assert_eq!(
+            reparsed.document().schema().to_header(),
+            info.document().schema().to_header()
+        );
+        for (original, reparsed) in info.entries().iter().zip(reparsed.entries()) {
+            assert_eq!(original.row.raw_values(), reparsed.row.raw_values());
+        }
+        assert_eq!(reparsed.to_bpsv().to_string(), written);
+    }
+
+    #[test]
+    fn test_set_active_build() {
+        let mut info = BuildInfoFile::new();
+        assert_eq!(info.entry_count(), 0);
+        assert!(info.has_column("Armadillo"));
+
+        info.set_active_build(
+            "us",
+            "abcdef1234567890abcdef1234567890",
+            "fedcba0987654321fedcba0987654321",
+            "2024-01-01T00:00:00Z",
+        )
+        .expect("set us");
+        info.set_active_build(
+            "eu",
+            "00000000000000000000000000000001",
+            "00000000000000000000000000000002",
+            "2024-02-01T00:00:00Z",
+        )
+        .expect("set eu");
+
+        let reparsed = BuildInfoFile::parse_str(&info.to_bpsv().to_string()).expect("parse");
+        let entries = reparsed.entries();
+        assert_eq!(entries.len(), 2);
+        assert!(!entries[0].is_active());
+        assert_eq!(
+            entries[0].build_key(),
+            Some("abcdef1234567890abcdef1234567890")
+        );
+
+        let active = reparsed.active_entry().expect("active entry");
+        assert_eq!(active.branch(), Some("eu"));
+        assert_eq!(active.build_key(), Some("00000000000000000000000000000001"));
+        assert_eq!(active.cdn_key(), Some("00000000000000000000000000000002"));
+        assert_eq!(active.last_activated(), Some("2024-02-01T00:00:00Z"));
+        assert_eq!(active.install_size(), None);
+    }
+
+    #[test]
+    fn test_set_active_build_keeps_other_columns() {
+        let mut info = BuildInfoFile::parse_str(SAMPLE_BUILD_INFO).expect("parse");
+        info.set_active_build(
+            "eu",
+            "22222222222222222222222222222222",
+            "33333333333333333333333333333333",
+            "2024-03-01T00:00:00Z",
+        )
+        .expect("set eu");
+
+        let active = info.active_entry().expect("active entry");
+        assert_eq!(active.branch(), Some("eu"));
+        assert_eq!(active.build_key(), Some("22222222222222222222222222222222"));
+        assert_eq!(active.cdn_path(), Some("tpr/wow"));
+        assert_eq!(active.version(), Some("11.0.7.12345"));
+        assert_eq!(info.entries()[0].cdn_hosts().len(), 2);
+        assert!(!info.entries()[0].is_active());
+
+        assert!(matches!(
+            info.set_active_build("eu", "abc", "33333333333333333333333333333333", ""),
+            Err(StorageError::InvalidFormat(_))
+        ));
+        let mut partial = BuildInfoFile::parse_str(
+            "Branch!STRING:0|Active!DEC:1
+",
+        )
+        .expect("parse");
+        assert!(matches!(
+            partial.set_active_build(
+                "us",
+                "22222222222222222222222222222222",
+                "33333333333333333333333333333333",
+                ""
+            ),
+            Err(StorageError::InvalidFormat(_))
+        ));
+    }
+
     #[test]
     fn test_missing_column() {
         let content = "Branch!STRING:0\nus\n";
@@ -0,0 +1,164 @@
+//! Bounded cache of decompressed BLTE chunks
+//!
+//! Range reads decode single chunks of a file. Keeping recently decoded
+//! chunks lets sequential reads of the same chunk skip decompression.
+//! The cache holds at most a fixed number of bytes and evicts the least
+//! recently used chunks first.
+
+use cascette_crypto::EncodingKey;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// Default byte capacity of the chunk cache used by range reads
+pub const DEFAULT_CHUNK_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Chunk of a file, by encoding key and chunk index
+type ChunkId = (EncodingKey, usize);
+
+/// LRU cache of decompressed chunks, capped by total bytes
+#[derive(Debug)]
+pub struct ChunkCache {
+    capacity: usize,
+    size: usize,
+    tick: u64,
+    entries: HashMap<ChunkId, (Arc<[u8]>, u64)>,
+    /// Chunks by last use, least recent first
+    order: BTreeMap<u64, ChunkId>,
+}
+
+impl ChunkCache {
+    /// Create a cache holding at most `capacity` bytes
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            size: 0,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    /// Look up a chunk, marking it as recently used
+    pub fn get(&mut self, encoding_key: &EncodingKey, index: usize) -> Option<Arc<[u8]>> {
+        self.tick += 1;
+        let (data, last_used) = self.entries.get_mut(&(*encoding_key, index))?;
+        self.order.remove(last_used);
+        *last_used = self.tick;
+        self.order.insert(self.tick, (*encoding_key, index));
+        Some(Arc::clone(data))
+    }
+
+    /// Add a chunk, evicting the least recently used chunks beyond capacity
+    ///
+    /// A chunk larger than the whole capacity is not cached.
+    pub fn insert(&mut self, encoding_key: EncodingKey, index: usize, data: Arc<[u8]>) {
+        if data.len() > self.capacity {
+            return;
+        }
+
+        self.tick += 1;
+        self.size += data.len();
+        if let Some((old, last_used)) = self
+            .entries
+            .insert((encoding_key, index), (data, self.tick))
+        {
+            self.size -= old.len();
+            self.order.remove(&last_used);
+        }
+        self.order.insert(self.tick, (encoding_key, index));
+
+        while self.size > self.capacity {
+            let Some((_, id)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some((evicted, _)) = self.entries.remove(&id) {
+                self.size -= evicted.len();
+            }
+        }
+    }
+
+    /// Drop every chunk of a file
+    pub fn remove_file(&mut self, encoding_key: &EncodingKey) {
+        let size = &mut self.size;
+        let order = &mut self.order;
+        self.entries.retain(|(key, _), (data, last_used)| {
+            if key == encoding_key {
+                *size -= data.len();
+                order.remove(last_used);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// Chunk indices of a file currently cached, in ascending order
+    #[cfg(test)]
+    pub fn cached_chunks(&self, encoding_key: &EncodingKey) -> Vec<usize> {
+        let mut chunks: Vec<_> = self
+            .entries
+            .keys()
+            .filter(|(key, _)| key == encoding_key)
+            .map(|&(_, index)| index)
+            .collect();
+        chunks.sort_unstable();
+        chunks
+    }
+
+    /// Total bytes of cached chunks
+    #[cfg(test)]
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(len: usize) -> Arc<[u8]> {
+        vec![0u8; len].into()
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used_beyond_capacity() {
+        let key = EncodingKey::from_bytes([1; 16]);
+        let mut cache = ChunkCache::new(100);
+
+        cache.insert(key, 0, chunk(40));
+        cache.insert(key, 1, chunk(40));
+        assert!(cache.get(&key, 0).is_some());
+        cache.insert(key, 2, chunk(40));
+
+        // Chunk 1 was used least recently
+        assert_eq!(cache.cached_chunks(&key), [0, 2]);
+        assert_eq!(cache.size(), 80);
+    }
+
+    #[test]
+    fn test_oversized_chunk_not_cached() {
+        let key = EncodingKey::from_bytes([1; 16]);
+        let mut cache = ChunkCache::new(10);
+
+        cache.insert(key, 0, chunk(11));
+        assert!(cache.get(&key, 0).is_none());
+        assert_eq!(cache.size(), 0);
+    }
+
+    #[test]
+    fn test_replace_and_remove_file_track_size() {
+        let key = EncodingKey::from_bytes([1; 16]);
+        let other = EncodingKey::from_bytes([2; 16]);
+        let mut cache = ChunkCache::new(100);
+
+        cache.insert(key, 0, chunk(30));
+        cache.insert(key, 0, chunk(20));
+        cache.insert(other, 0, chunk(10));
+        assert_eq!(cache.size(), 30);
+
+        cache.remove_file(&key);
+        assert_eq!(cache.size(), 10);
+        assert!(cache.cached_chunks(&key).is_empty());
+        assert_eq!(cache.cached_chunks(&other), [0]);
+    }
+}
@@ -1,5 +1,6 @@
 //! Configuration for the storage system
 
+use crate::storage::CompressionBackend;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
@@ -23,6 +24,10 @@ pub struct StorageConfig {
 
     /// Enable content verification
     pub verify_content: bool,
+
+    /// Compression applied to new writes
+    #[serde(default)]
+    pub compression: CompressionBackend,
 }
 
 impl Default for StorageConfig {
@@ -34,6 +39,7 @@ impl Default for StorageConfig {
             enable_mmap: true,
             read_threads: 4,
             verify_content: true,
+            compression: CompressionBackend::None,
         }
     }
 }
@@ -67,4 +73,11 @@ impl StorageConfig {
         self.max_index_cache_size = size;
         self
     }
+
+    /// Set the compression applied to new writes
+    #[must_use]
+    pub const fn with_compression(mut self, compression: CompressionBackend) -> Self {
+        self.compression = compression;
+        self
+    }
 }
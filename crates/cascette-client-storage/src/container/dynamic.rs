@@ -541,7 +541,7 @@ impl Container for DynamicContainer {
 
         // Persist the updated index to disk
         {
-            let index = self.index.read();
+            let mut index = self.index.write();
             index.save_all()?;
         }
 
@@ -566,7 +566,7 @@ impl Container for DynamicContainer {
         if removed {
             debug!("removed key {} from index", hex::encode(&key[..9]));
             // Persist the updated index
-            let index = self.index.read();
+            let mut index = self.index.write();
             index.save_all()?;
         }
 
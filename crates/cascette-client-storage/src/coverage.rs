@@ -0,0 +1,333 @@
+//! Listfile coverage against a root file and local storage
+//!
+//! A coverage report answers three questions about a build: how many of the
+//! root file's `FileDataID`s the community listfile names, how many are
+//! stored locally, and how many are both. Named files are also broken down
+//! by their top-level directory.
+//!
+//! The listfile is read line by line and only the top-level directory of
+//! each name is kept, so full listfiles with millions of entries do not
+//! need to fit in memory.
+
+use crate::listfile::parse_line;
+use crate::{Result, StorageError};
+use cascette_crypto::ContentKey;
+use cascette_formats::root::RootFile;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::BufRead;
+
+/// Coverage counts for one top-level directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PrefixCoverage {
+    /// Root `FileDataID`s named under this directory
+    pub named: u64,
+    /// Named `FileDataID`s stored locally
+    pub named_and_present: u64,
+}
+
+/// Listfile and local storage coverage of a root file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CoverageReport {
+    /// Distinct `FileDataID`s in the root file
+    pub total: u64,
+    /// Root `FileDataID`s with a listfile name
+    pub named: u64,
+    /// Root `FileDataID`s stored locally
+    pub present: u64,
+    /// Root `FileDataID`s that are both named and stored locally
+    pub named_and_present: u64,
+    /// Listfile entries for `FileDataID`s not in the root file
+    pub unknown_listfile_entries: u64,
+    /// Listfile lines that are not `<fdid>;<path>`
+    pub malformed_listfile_lines: u64,
+    /// Named coverage by lowercased top-level directory
+    ///
+    /// Files at the top level are counted under an empty prefix.
+    pub by_prefix: BTreeMap<String, PrefixCoverage>,
+}
+
+impl CoverageReport {
+    /// Compute coverage of `root` by a listfile and local storage
+    ///
+    /// `listfile` holds `<fdid>;<path>` lines, as published by the
+    /// community listfile project. Empty lines are ignored. `is_present`
+    /// reports whether the file with a content key is stored locally. A
+    /// `FileDataID` with several root entries (one per locale, say) counts
+    /// as present if any of them is.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading the listfile fails.
+    pub fn compute<R, F>(root: &RootFile, listfile: R, is_present: F) -> Result<Self>
+    where
+        R: BufRead,
+        F: Fn(&ContentKey) -> bool,
+    {
+        let names = ListfileNames::read(listfile, &root_file_data_ids(root))?;
+        Ok(Self::from_names(root, &names, is_present))
+    }
+
+    /// Compute coverage of `root` from an already read listfile
+    pub(crate) fn from_names<F>(root: &RootFile, names: &ListfileNames, is_present: F) -> Self
+    where
+        F: Fn(&ContentKey) -> bool,
+    {
+        let mut present: HashMap<u32, bool> = HashMap::new();
+        for record in root.iter_records() {
+            let state = present.entry(record.file_data_id.get()).or_default();
+            *state = *state || is_present(&record.content_key);
+        }
+
+        let mut report = Self {
+            total: present.len() as u64,
+            present: present.values().filter(|&&present| present).count() as u64,
+            unknown_listfile_entries: names.unknown,
+            malformed_listfile_lines: names.malformed,
+            ..Self::default()
+        };
+
+        for (fdid, &is_present) in &present {
+            let Some(&id) = names.prefix_of.get(fdid) else {
+                continue;
+            };
+            let coverage = report
+                .by_prefix
+                .entry(names.prefixes[id].clone())
+                .or_default();
+            coverage.named += 1;
+            report.named += 1;
+            if is_present {
+                coverage.named_and_present += 1;
+                report.named_and_present += 1;
+            }
+        }
+
+        report
+    }
+
+    /// Render the report as pretty-printed JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| StorageError::InvalidFormat(format!("failed to serialize report: {e}")))
+    }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "FileDataIDs in root:    {}", self.total)?;
+        writeln!(f, "Named by listfile:      {}", self.named)?;
+        writeln!(f, "Present locally:        {}", self.present)?;
+        writeln!(f, "Named and present:      {}", self.named_and_present)?;
+        writeln!(
+            f,
+            "Unknown listfile IDs:   {}",
+            self.unknown_listfile_entries
+        )?;
+        writeln!(
+            f,
+            "Malformed listfile:     {}",
+            self.malformed_listfile_lines
+        )?;
+
+        if !self.by_prefix.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "{:<24} {:>10} {:>10}", "Prefix", "Named", "Present")?;
+            for (prefix, coverage) in &self.by_prefix {
+                let prefix = if prefix.is_empty() {
+                    "(top level)"
+                } else {
+                    prefix
+                };
+                writeln!(
+                    f,
+                    "{prefix:<24} {:>10} {:>10}",
+                    coverage.named, coverage.named_and_present
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Top-level directories a listfile names for root `FileDataID`s
+///
+/// Reading the listfile is kept apart from the presence checks so that
+/// callers can do the blocking I/O without holding locks needed for them.
+pub(crate) struct ListfileNames {
+    /// Distinct top-level directories
+    prefixes: Vec<String>,
+    /// Index into `prefixes` by named `FileDataID`
+    prefix_of: HashMap<u32, usize>,
+    unknown: u64,
+    malformed: u64,
+}
+
+impl ListfileNames {
+    /// Read `<fdid>;<path>` lines, keeping the names of `known` IDs
+    pub(crate) fn read<R: BufRead>(listfile: R, known: &HashSet<u32>) -> Result<Self> {
+        let mut names = Self {
+            prefixes: Vec::new(),
+            prefix_of: HashMap::new(),
+            unknown: 0,
+            malformed: 0,
+        };
+        let mut prefix_ids: HashMap<String, usize> = HashMap::new();
+        for line in listfile.lines() {
+            let line = line.map_err(StorageError::Io)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Some((fdid, path)) = parse_line(&line) else {
+                names.malformed += 1;
+                continue;
+            };
+            if !known.contains(&fdid) {
+                names.unknown += 1;
+                continue;
+            }
+
+            let prefix = top_level_prefix(path);
+            let id = if let Some(&id) = prefix_ids.get(&prefix) {
+                id
+            } else {
+                names.prefixes.push(prefix.clone());
+                prefix_ids.insert(prefix, names.prefixes.len() - 1);
+                names.prefixes.len() - 1
+            };
+            // Later listfile entries for the same FileDataID replace earlier ones
+            names.prefix_of.insert(fdid, id);
+        }
+        Ok(names)
+    }
+}
+
+/// Distinct `FileDataID`s of a root file
+pub(crate) fn root_file_data_ids(root: &RootFile) -> HashSet<u32> {
+    root.iter_records()
+        .map(|record| record.file_data_id.get())
+        .collect()
+}
+
+/// Lowercased first path component, or empty for top-level files
+///
+/// Listfile paths use `/`, but `\` is accepted too.
+fn top_level_prefix(path: &str) -> String {
+    path.trim()
+        .split_once(['/', '\\'])
+        .map(|(prefix, _)| prefix.to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use cascette_crypto::FileDataId;
+    use cascette_formats::root::{ContentFlags, LocaleFlags, RootBuilder, RootVersion};
+    use std::io::Cursor;
+
+    fn ckey(byte: u8) -> ContentKey {
+        ContentKey::from_bytes([byte; 16])
+    }
+
+    /// Root with FDIDs 1-5, where FDID 5 has an entry per locale
+    fn build_root() -> RootFile {
+        let mut builder = RootBuilder::new(RootVersion::V2);
+        for fdid in 1..=4u8 {
+            builder.add_file(
+                FileDataId::new(u32::from(fdid)),
+                ckey(fdid),
+                None,
+                LocaleFlags::new(LocaleFlags::ALL),
+                ContentFlags::new(ContentFlags::INSTALL),
+            );
+        }
+        builder.add_file(
+            FileDataId::new(5),
+            ckey(50),
+            None,
+            LocaleFlags::new(LocaleFlags::ENUS),
+            ContentFlags::new(ContentFlags::INSTALL),
+        );
+        builder.add_file(
+            FileDataId::new(5),
+            ckey(51),
+            None,
+            LocaleFlags::new(LocaleFlags::DEDE),
+            ContentFlags::new(ContentFlags::INSTALL),
+        );
+        let data = builder.build().expect("Root should build");
+        RootFile::parse(&data).expect("Root should parse")
+    }
+
+    #[test]
+    fn test_coverage_counts_and_prefixes() {
+        let root = build_root();
+        let listfile = "1;Interface/Icons/a.blp\n\
+                        2;interface/glues/b.blp\n\
+                        3;World\\Maps\\c.wdt\n\
+                        5;readme.txt\n\
+                        \n\
+                        99;World/unknown.m2\n\
+                        not a line\n";
+        // FDIDs 1, 4 and the deDE entry of 5 are stored locally
+        let local = [ckey(1), ckey(4), ckey(51)];
+
+        let report =
+            CoverageReport::compute(&root, Cursor::new(listfile), |key| local.contains(key))
+                .expect("Coverage should compute");
+
+        assert_eq!(report.total, 5);
+        assert_eq!(report.named, 4);
+        assert_eq!(report.present, 3);
+        assert_eq!(report.named_and_present, 2);
+        assert_eq!(report.unknown_listfile_entries, 1);
+        assert_eq!(report.malformed_listfile_lines, 1);
+
+        assert_eq!(
+            report.by_prefix["interface"],
+            PrefixCoverage {
+                named: 2,
+                named_and_present: 1
+            }
+        );
+        assert_eq!(
+            report.by_prefix["world"],
+            PrefixCoverage {
+                named: 1,
+                named_and_present: 0
+            }
+        );
+        assert_eq!(
+            report.by_prefix[""],
+            PrefixCoverage {
+                named: 1,
+                named_and_present: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_coverage_renders_text_and_json() {
+        let root = build_root();
+        let report = CoverageReport::compute(&root, Cursor::new("1;Sound/a.ogg\n"), |_| true)
+            .expect("Coverage should compute");
+
+        let text = report.to_string();
+        assert!(text.contains("FileDataIDs in root:    5"));
+        assert!(text.lines().any(|line| line.starts_with("sound ")));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&report.to_json().expect("Report should serialize"))
+                .expect("Report should be valid JSON");
+        assert_eq!(json["total"], 5);
+        assert_eq!(json["present"], 5);
+        assert_eq!(json["by_prefix"]["sound"]["named"], 1);
+    }
+}
@@ -0,0 +1,465 @@
+//! C ABI for the [`Installation`] read path.
+//!
+//! Enabled with the `ffi` feature. The matching C header is
+//! `include/cascette_client_storage.h`, generated with cbindgen from this
+//! module:
+//!
+//! ```text
+//! cbindgen --config cbindgen.toml --output include/cascette_client_storage.h
+//! ```
+//!
+//! # Memory ownership
+//!
+//! - [`cascette_install_open`] returns a handle owned by the caller, which
+//!   must be released with exactly one call to [`cascette_install_close`].
+//! - Output buffers passed to [`cascette_install_read`] are owned by the
+//!   caller; the library only writes into them.
+//! - The string returned by [`cascette_install_last_error_message`] is owned
+//!   by the library and stays valid until the next call into the library on
+//!   the same thread. It must not be freed.
+//!
+//! # Threading
+//!
+//! A handle may be shared between threads and read from concurrently. It
+//! must not be closed while another thread is still using it.
+//!
+//! # Errors
+//!
+//! Functions returning `int32_t` return [`CASCETTE_OK`] on success, a
+//! negative `CASCETTE_ERROR_*` code for errors detected at the boundary or
+//! a missing file ([`CASCETTE_ERROR_NOT_FOUND`]), or the positive TACT error
+//! code of any other [`StorageError`] (see
+//! [`StorageError::tact_error_code`]). On failure the message is available
+//! from [`cascette_install_last_error_message`].
+
+#![allow(unsafe_code)]
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use cascette_crypto::EncodingKey;
+
+use crate::{Installation, StorageError, resolve_installation};
+
+/// Success.
+pub const CASCETTE_OK: i32 = 0;
+/// A required pointer argument was NULL.
+pub const CASCETTE_ERROR_NULL_ARGUMENT: i32 = -1;
+/// The output buffer is too small; the required size was stored in `out_len`.
+pub const CASCETTE_ERROR_BUFFER_TOO_SMALL: i32 = -2;
+/// The library panicked; the handle should be closed.
+pub const CASCETTE_ERROR_PANIC: i32 = -3;
+/// The installation does not hold the requested file.
+pub const CASCETTE_ERROR_NOT_FOUND: i32 = -4;
+
+/// Length of the encoding keys passed to the read functions.
+pub const CASCETTE_EKEY_LENGTH: usize = 16;
+
+/// Opaque handle to an opened installation.
+pub struct CascetteInstall {
+    /// Runtime driving the async read path.
+    runtime: tokio::runtime::Runtime,
+    /// The opened installation.
+    installation: Installation,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Remember `message` as this thread's last error.
+fn set_last_error(message: impl Into<String>) {
+    // Interior NULs would truncate the message in C, drop them instead
+    let message = CString::new(message.into().replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+/// Record `error` and return its status code.
+fn storage_error_status(error: &StorageError) -> i32 {
+    set_last_error(error.to_string());
+    if matches!(error, StorageError::NotFound(_)) {
+        return CASCETTE_ERROR_NOT_FOUND;
+    }
+    i32::try_from(error.tact_error_code()).unwrap_or(i32::MAX)
+}
+
+/// Run `f`, turning a panic into `on_panic` with the panic message recorded.
+fn guard<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    clear_last_error();
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| (*s).to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        set_last_error(format!("panic: {message}"));
+        on_panic
+    })
+}
+
+impl CascetteInstall {
+    /// Resolve `path` and load the local indices and archives.
+    fn open(path: &Path) -> crate::Result<Self> {
+        let resolved = resolve_installation(path)?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("cascette-ffi")
+            .build()?;
+        let installation = Installation::open(resolved.data_dir)?;
+        runtime.block_on(installation.initialize())?;
+        Ok(Self {
+            runtime,
+            installation,
+        })
+    }
+}
+
+/// Read the encoding key behind `ekey`.
+///
+/// # Safety
+///
+/// `ekey` must point to [`CASCETTE_EKEY_LENGTH`] readable bytes.
+unsafe fn read_ekey(ekey: *const u8) -> EncodingKey {
+    let mut bytes = [0u8; CASCETTE_EKEY_LENGTH];
+    // SAFETY: the caller guarantees `ekey` points to 16 readable bytes
+    bytes.copy_from_slice(unsafe { std::slice::from_raw_parts(ekey, CASCETTE_EKEY_LENGTH) });
+    EncodingKey::from_bytes(bytes)
+}
+
+/// Open the installation at `path`.
+///
+/// `path` may be the game root, a launcher flavor directory, the `Data`
+/// directory or `Data/data`. Returns NULL on failure.
+///
+/// # Safety
+///
+/// `path` must be NULL or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cascette_install_open(path: *const c_char) -> *mut CascetteInstall {
+    guard(std::ptr::null_mut(), || {
+        if path.is_null() {
+            set_last_error("path is NULL");
+            return std::ptr::null_mut();
+        }
+        // SAFETY: the caller guarantees a NUL-terminated string
+        let Ok(path) = unsafe { CStr::from_ptr(path) }.to_str() else {
+            set_last_error("path is not valid UTF-8");
+            return std::ptr::null_mut();
+        };
+
+        match CascetteInstall::open(Path::new(path)) {
+            Ok(install) => Box::into_raw(Box::new(install)),
+            Err(e) => {
+                set_last_error(e.to_string());
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Check whether the installation holds the file with encoding key `ekey`.
+///
+/// Returns 1 if it does, 0 if it does not, or a negative error code.
+///
+/// # Safety
+///
+/// `handle` must be NULL or a live handle from [`cascette_install_open`],
+/// and `ekey` must be NULL or point to [`CASCETTE_EKEY_LENGTH`] bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cascette_install_contains(
+    handle: *const CascetteInstall,
+    ekey: *const u8,
+) -> i32 {
+    guard(CASCETTE_ERROR_PANIC, || {
+        if handle.is_null() || ekey.is_null() {
+            set_last_error("handle or ekey is NULL");
+            return CASCETTE_ERROR_NULL_ARGUMENT;
+        }
+        // SAFETY: the caller guarantees a live handle and a 16-byte key
+        let (install, ekey) = unsafe { (&*handle, read_ekey(ekey)) };
+        let found = install
+            .runtime
+            .block_on(install.installation.has_encoding_key(&ekey));
+        i32::from(found)
+    })
+}
+
+/// Read the file with encoding key `ekey` into `out_buf`.
+///
+/// `*out_len` holds the capacity of `out_buf` on input and is set to the
+/// size of the file on return. Call with `out_buf` NULL to query the size,
+/// then again with a buffer of at least that size. The size query reads it
+/// from the encoding file or the BLTE chunk table without decoding the
+/// file. A buffer that is too small returns
+/// [`CASCETTE_ERROR_BUFFER_TOO_SMALL`] without writing to it. A key the
+/// installation does not hold returns [`CASCETTE_ERROR_NOT_FOUND`].
+///
+/// # Safety
+///
+/// `handle` must be NULL or a live handle from [`cascette_install_open`],
+/// `ekey` must be NULL or point to [`CASCETTE_EKEY_LENGTH`] bytes, `out_len`
+/// must be NULL or valid for reads and writes, and `out_buf` must be NULL or
+/// valid for writes of `*out_len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cascette_install_read(
+    handle: *const CascetteInstall,
+    ekey: *const u8,
+    out_buf: *mut u8,
+    out_len: *mut usize,
+) -> i32 {
+    guard(CASCETTE_ERROR_PANIC, || {
+        if handle.is_null() || ekey.is_null() || out_len.is_null() {
+            set_last_error("handle, ekey or out_len is NULL");
+            return CASCETTE_ERROR_NULL_ARGUMENT;
+        }
+        // SAFETY: the caller guarantees a live handle, a 16-byte key and a
+        // valid `out_len`
+        let (install, ekey, capacity) = unsafe { (&*handle, read_ekey(ekey), *out_len) };
+
+        if out_buf.is_null() {
+            let size = match install
+                .runtime
+                .block_on(install.installation.file_size_by_encoding_key(&ekey))
+            {
+                Ok(size) => size,
+                Err(e) => return storage_error_status(&e),
+            };
+            let Ok(size) = usize::try_from(size) else {
+                set_last_error(format!("file of {size} bytes does not fit in memory"));
+                return CASCETTE_ERROR_BUFFER_TOO_SMALL;
+            };
+            // SAFETY: `out_len` is valid for writes
+            unsafe { *out_len = size };
+            return CASCETTE_OK;
+        }
+
+        let data = match install
+            .runtime
+            .block_on(install.installation.read_file_by_encoding_key(&ekey))
+        {
+            Ok(data) => data,
+            Err(e) => return storage_error_status(&e),
+        };
+
+        // SAFETY: `out_len` is valid for writes
+        unsafe { *out_len = data.len() };
+        if capacity < data.len() {
+            set_last_error(format!(
+                "buffer holds {capacity} bytes, file needs {}",
+                data.len()
+            ));
+            return CASCETTE_ERROR_BUFFER_TOO_SMALL;
+        }
+
+        // SAFETY: `out_buf` is valid for `capacity >= data.len()` bytes
+        unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), out_buf, data.len()) };
+        CASCETTE_OK
+    })
+}
+
+/// Message of the last error on this thread, or NULL if the last call
+/// succeeded.
+///
+/// The string is owned by the library and valid until the next call into
+/// the library on this thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn cascette_install_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |m| m.as_ptr())
+    })
+}
+
+/// Close a handle from [`cascette_install_open`]. NULL is ignored.
+///
+/// # Safety
+///
+/// `handle` must be NULL or a live handle that no other thread is using,
+/// and must not be used after this call.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cascette_install_close(handle: *mut CascetteInstall) {
+    guard((), || {
+        if !handle.is_null() {
+            // SAFETY: the caller hands back ownership of a live handle
+            drop(unsafe { Box::from_raw(handle) });
+        }
+    });
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use cascette_formats::CascFormat;
+    use cascette_formats::blte::{BlteFile, CompressionMode};
+
+    /// Write `files` into a fresh installation and persist its indices.
+    ///
+    /// Returns the temp dir (the game root) and each file's encoding key.
+    fn synthetic_installation(files: &[&[u8]]) -> (tempfile::TempDir, Vec<[u8; 16]>) {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let runtime = tokio::runtime::Runtime::new().expect("runtime");
+        let installation =
+            Installation::open(dir.path().join("Data")).expect("installation should open");
+
+        let mut ekeys = Vec::new();
+        for data in files {
+            runtime
+                .block_on(installation.write_file(data.to_vec(), false))
+                .expect("file should be written");
+            let blte = BlteFile::single_chunk(data.to_vec(), CompressionMode::None)
+                .expect("BLTE should be created")
+                .build()
+                .expect("BLTE should build");
+            ekeys.push(*EncodingKey::from_data(&blte).as_bytes());
+        }
+        runtime
+            .block_on(installation.compact_indices_if_needed(0.0))
+            .expect("indices should be written");
+        (dir, ekeys)
+    }
+
+    fn open(path: &Path) -> *mut CascetteInstall {
+        let path = CString::new(path.to_str().expect("utf-8 path")).expect("no NUL");
+        // SAFETY: `path` is NUL-terminated
+        unsafe { cascette_install_open(path.as_ptr()) }
+    }
+
+    fn last_error() -> String {
+        let message = cascette_install_last_error_message();
+        assert!(!message.is_null());
+        // SAFETY: non-NULL messages are NUL-terminated
+        unsafe { CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_open_read_close() {
+        let (dir, ekeys) = synthetic_installation(&[b"hello from ffi"]);
+        let handle = open(dir.path());
+        assert!(!handle.is_null(), "{}", last_error());
+
+        // SAFETY: `handle` is live and every pointer outlives the calls
+        unsafe {
+            assert_eq!(cascette_install_contains(handle, ekeys[0].as_ptr()), 1);
+
+            let mut len = 0usize;
+            let status = cascette_install_read(
+                handle,
+                ekeys[0].as_ptr(),
+                std::ptr::null_mut(),
+                &raw mut len,
+            );
+            assert_eq!(status, CASCETTE_OK);
+            assert_eq!(len, 14);
+            assert!(cascette_install_last_error_message().is_null());
+
+            let mut small = [0u8; 4];
+            let mut small_len = small.len();
+            let status = cascette_install_read(
+                handle,
+                ekeys[0].as_ptr(),
+                small.as_mut_ptr(),
+                &raw mut small_len,
+            );
+            assert_eq!(status, CASCETTE_ERROR_BUFFER_TOO_SMALL);
+            assert_eq!(small_len, 14);
+            assert_eq!(small, [0; 4]);
+
+            let mut buf = vec![0u8; len];
+            let status =
+                cascette_install_read(handle, ekeys[0].as_ptr(), buf.as_mut_ptr(), &raw mut len);
+            assert_eq!(status, CASCETTE_OK);
+            assert_eq!(buf, b"hello from ffi");
+
+            let missing = [0xABu8; 16];
+            assert_eq!(cascette_install_contains(handle, missing.as_ptr()), 0);
+            let status =
+                cascette_install_read(handle, missing.as_ptr(), std::ptr::null_mut(), &raw mut len);
+            assert_eq!(status, CASCETTE_ERROR_NOT_FOUND);
+            assert!(last_error().contains("not found"), "{}", last_error());
+            let status =
+                cascette_install_read(handle, missing.as_ptr(), buf.as_mut_ptr(), &raw mut len);
+            assert_eq!(status, CASCETTE_ERROR_NOT_FOUND);
+
+            assert_eq!(
+                cascette_install_read(handle, std::ptr::null(), std::ptr::null_mut(), &raw mut len),
+                CASCETTE_ERROR_NULL_ARGUMENT
+            );
+
+            cascette_install_close(handle);
+            cascette_install_close(std::ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_open_failures() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        assert!(open(&dir.path().join("missing")).is_null());
+        assert!(last_error().contains("not a directory"), "{}", last_error());
+
+        // SAFETY: NULL is accepted
+        assert!(unsafe { cascette_install_open(std::ptr::null()) }.is_null());
+        assert_eq!(last_error(), "path is NULL");
+    }
+
+    #[test]
+    fn test_concurrent_reads_share_handle() {
+        let files: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 100 + usize::from(i)]).collect();
+        let slices: Vec<&[u8]> = files.iter().map(Vec::as_slice).collect();
+        let (dir, ekeys) = synthetic_installation(&slices);
+
+        let handle = open(dir.path());
+        assert!(!handle.is_null(), "{}", last_error());
+        // Raw pointers are not Send; share the address instead
+        let address = handle as usize;
+
+        std::thread::scope(|scope| {
+            for (ekey, expected) in ekeys.iter().zip(&files) {
+                scope.spawn(move || {
+                    let handle = address as *const CascetteInstall;
+                    let mut buf = vec![0u8; 256];
+                    let mut len = buf.len();
+                    // SAFETY: the handle stays live until the scope ends
+                    let status = unsafe {
+                        cascette_install_read(handle, ekey.as_ptr(), buf.as_mut_ptr(), &raw mut len)
+                    };
+                    assert_eq!(status, CASCETTE_OK);
+                    assert_eq!(&buf[..len], expected.as_slice());
+                });
+            }
+        });
+
+        // SAFETY: all readers have finished
+        unsafe { cascette_install_close(handle) };
+    }
+
+    #[test]
+    fn test_guard_catches_panics() {
+        #[allow(clippy::panic)]
+        let status = guard(CASCETTE_ERROR_PANIC, || panic!("boom"));
+        assert_eq!(status, CASCETTE_ERROR_PANIC);
+        assert_eq!(last_error(), "panic: boom");
+    }
+
+    #[test]
+    fn test_tact_error_codes() {
+        assert_eq!(
+            StorageError::ContainerLocked(String::new()).tact_error_code(),
+            11
+        );
+        assert_eq!(
+            StorageError::TruncatedRead(String::new()).tact_error_code(),
+            7
+        );
+        assert_eq!(StorageError::NotFound(String::new()).tact_error_code(), 1);
+    }
+}
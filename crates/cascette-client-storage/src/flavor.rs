@@ -0,0 +1,294 @@
+//! Launcher-compatible flavor layout for installations.
+//!
+//! The Battle.net launcher installs each product flavor into its own
+//! subdirectory of the installation root (`_retail_`, `_classic_`, ...).
+//! The shared `.build.info` and `Data` tree stay at the root, while the
+//! files from the install manifest go into the flavor directory next to a
+//! `.flavor.info` file naming the product:
+//!
+//! ```text
+//! World of Warcraft/
+//! ├── .build.info
+//! ├── Data/
+//! └── _retail_/
+//!     ├── .flavor.info
+//!     └── Wow.exe
+//! ```
+//!
+//! `.flavor.info` is a BPSV file with a single `Product Flavor!STRING:0`
+//! column holding the product code.
+
+use std::path::{Path, PathBuf};
+
+use cascette_formats::bpsv::{BpsvBuilder, BpsvDocument, BpsvField, BpsvType, BpsvValue, parse};
+
+use crate::{Result, StorageError};
+
+/// File name of the flavor marker inside a flavor directory.
+pub const FLAVOR_INFO_FILE: &str = ".flavor.info";
+
+/// Column holding the product code in `.flavor.info`.
+const PRODUCT_FLAVOR_COLUMN: &str = "Product Flavor";
+
+/// Flavor directory the launcher uses for a product code.
+///
+/// Returns `None` for products without a known flavor directory.
+pub fn flavor_directory(product: &str) -> Option<&'static str> {
+    match product {
+        "wow" => Some("_retail_"),
+        "wowt" => Some("_ptr_"),
+        "wowxptr" => Some("_xptr_"),
+        "wow_beta" => Some("_beta_"),
+        "wow_classic" => Some("_classic_"),
+        "wow_classic_ptr" => Some("_classic_ptr_"),
+        "wow_classic_beta" => Some("_classic_beta_"),
+        "wow_classic_era" => Some("_classic_era_"),
+        "wow_classic_era_ptr" => Some("_classic_era_ptr_"),
+        _ => None,
+    }
+}
+
+/// Parsed `.flavor.info` file.
+pub struct FlavorInfoFile {
+    /// Underlying BPSV document.
+    document: BpsvDocument,
+}
+
+impl FlavorInfoFile {
+    /// Create a `.flavor.info` naming `product`.
+    pub fn new(product: &str) -> Self {
+        let mut builder = BpsvBuilder::new();
+        builder.add_field(BpsvField::new(PRODUCT_FLAVOR_COLUMN, BpsvType::String(0)));
+        // A single string value always matches the single string field
+        let _ = builder.add_row(vec![BpsvValue::String(product.to_string())]);
+        Self {
+            document: builder.build(),
+        }
+    }
+
+    /// Parse a `.flavor.info` file from its contents.
+    pub fn parse_str(content: &str) -> Result<Self> {
+        let document = parse(content).map_err(|e| {
+            StorageError::InvalidFormat(format!("failed to parse .flavor.info: {e}"))
+        })?;
+
+        Ok(Self { document })
+    }
+
+    /// Read and parse a `.flavor.info` file from disk.
+    pub async fn from_path(path: &Path) -> Result<Self> {
+        let content = tokio::fs::read_to_string(path).await.map_err(|e| {
+            StorageError::Io(std::io::Error::other(format!(
+                "failed to read .flavor.info at {}: {e}",
+                path.display()
+            )))
+        })?;
+
+        Self::parse_str(&content)
+    }
+
+    /// Product code of the first row.
+    pub fn product_flavor(&self) -> Option<&str> {
+        let schema = self.document.schema();
+        self.document
+            .rows()
+            .first()?
+            .get_raw_by_name(PRODUCT_FLAVOR_COLUMN, schema)
+    }
+
+    /// Get the underlying BPSV document.
+    pub fn document(&self) -> &BpsvDocument {
+        &self.document
+    }
+}
+
+impl std::fmt::Display for FlavorInfoFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.document)
+    }
+}
+
+/// Where an installation puts the files from its install manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallLayout {
+    /// Install manifest files go directly into the installation root.
+    Flat,
+    /// Install manifest files go into a launcher flavor directory.
+    Flavored {
+        /// Product code written to `.flavor.info`.
+        product: String,
+        /// Flavor directory name relative to the root (e.g. `_retail_`).
+        directory: String,
+    },
+}
+
+impl InstallLayout {
+    /// Launcher-compatible layout for `product`.
+    ///
+    /// Fails for products without a known flavor directory.
+    pub fn flavored(product: &str) -> Result<Self> {
+        let directory = flavor_directory(product).ok_or_else(|| {
+            StorageError::InvalidFormat(format!("no flavor directory known for product {product}"))
+        })?;
+        Ok(Self::Flavored {
+            product: product.to_string(),
+            directory: directory.to_string(),
+        })
+    }
+
+    /// Detect the layout of an existing installation of `product`.
+    ///
+    /// A flavor directory with a `.flavor.info` naming `product` means a
+    /// launcher-compatible layout, and a `.build.info` without one means a
+    /// flat layout. Returns `None` when `root` holds no installation yet, so
+    /// a resumed install keeps whatever layout it started with.
+    pub async fn detect(root: &Path, product: &str) -> Result<Option<Self>> {
+        if let Some(directory) = flavor_directory(product) {
+            let flavor_info = root.join(directory).join(FLAVOR_INFO_FILE);
+            if tokio::fs::try_exists(&flavor_info).await? {
+                let info = FlavorInfoFile::from_path(&flavor_info).await?;
+                if info.product_flavor() != Some(product) {
+                    return Err(StorageError::InvalidFormat(format!(
+                        "{} names product {}, expected {product}",
+                        flavor_info.display(),
+                        info.product_flavor().unwrap_or("<none>")
+                    )));
+                }
+                return Ok(Some(Self::Flavored {
+                    product: product.to_string(),
+                    directory: directory.to_string(),
+                }));
+            }
+        }
+
+        if tokio::fs::try_exists(root.join(".build.info")).await? {
+            return Ok(Some(Self::Flat));
+        }
+        Ok(None)
+    }
+
+    /// Directory that receives the install manifest files.
+    pub fn install_dir(&self, root: &Path) -> PathBuf {
+        match self {
+            Self::Flat => root.to_path_buf(),
+            Self::Flavored { directory, .. } => root.join(directory),
+        }
+    }
+
+    /// Destination of an install manifest entry.
+    ///
+    /// Manifest paths use `\` or `/` separators; both are accepted. `.` and
+    /// `..` components are dropped so an entry cannot leave the install
+    /// directory.
+    pub fn install_path(&self, root: &Path, manifest_path: &str) -> PathBuf {
+        manifest_path
+            .split(['\\', '/'])
+            .filter(|part| !matches!(*part, "" | "." | ".."))
+            .fold(self.install_dir(root), |path, part| path.join(part))
+    }
+
+    /// Create the flavor directory and its `.flavor.info`.
+    ///
+    /// Does nothing for the flat layout.
+    pub async fn prepare(&self, root: &Path) -> Result<()> {
+        let Self::Flavored { product, .. } = self else {
+            return Ok(());
+        };
+
+        let dir = self.install_dir(root);
+        tokio::fs::create_dir_all(&dir).await?;
+        tokio::fs::write(
+            dir.join(FLAVOR_INFO_FILE),
+            FlavorInfoFile::new(product).to_string(),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flavor_info_round_trip() {
+        let info = FlavorInfoFile::new("wow_classic");
+        let content = info.to_string();
+        assert!(content.starts_with("Product Flavor!STRING:0\n"));
+
+        let parsed = FlavorInfoFile::parse_str(&content).expect("parse");
+        assert_eq!(parsed.product_flavor(), Some("wow_classic"));
+    }
+
+    #[test]
+    fn test_install_path() {
+        let root = Path::new("/games/wow");
+        let layout = InstallLayout::flavored("wow").expect("known product");
+        assert_eq!(
+            layout.install_path(root, "Utils\\WowVoiceProxy.exe"),
+            root.join("_retail_")
+                .join("Utils")
+                .join("WowVoiceProxy.exe")
+        );
+        assert_eq!(
+            InstallLayout::Flat.install_path(root, "Wow.exe"),
+            root.join("Wow.exe")
+        );
+        assert_eq!(
+            layout.install_path(root, "..\\..\\Wow.exe"),
+            root.join("_retail_").join("Wow.exe")
+        );
+        assert!(InstallLayout::flavored("unknown").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_prepare_and_detect() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        assert_eq!(
+            InstallLayout::detect(root, "wow_classic_era")
+                .await
+                .expect("detect"),
+            None
+        );
+
+        let layout = InstallLayout::flavored("wow_classic_era").expect("known product");
+        layout.prepare(root).await.expect("prepare");
+        let content = std::fs::read_to_string(root.join("_classic_era_").join(FLAVOR_INFO_FILE))
+            .expect("read .flavor.info");
+        assert_eq!(
+            FlavorInfoFile::parse_str(&content)
+                .expect("parse")
+                .product_flavor(),
+            Some("wow_classic_era")
+        );
+
+        // Even with a .build.info present, the flavor directory wins
+        std::fs::write(root.join(".build.info"), "Branch!STRING:0\n").expect("write");
+        assert_eq!(
+            InstallLayout::detect(root, "wow_classic_era")
+                .await
+                .expect("detect"),
+            Some(layout)
+        );
+        assert_eq!(
+            InstallLayout::detect(root, "wow").await.expect("detect"),
+            Some(InstallLayout::Flat)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detect_rejects_mismatched_flavor() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let flavor_dir = dir.path().join("_retail_");
+        std::fs::create_dir_all(&flavor_dir).expect("mkdir");
+        std::fs::write(
+            flavor_dir.join(FLAVOR_INFO_FILE),
+            FlavorInfoFile::new("wowt").to_string(),
+        )
+        .expect("write");
+
+        assert!(InstallLayout::detect(dir.path(), "wow").await.is_err());
+    }
+}
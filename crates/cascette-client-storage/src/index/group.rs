@@ -0,0 +1,476 @@
+//! Group index: all buckets in one file
+//!
+//! Loading an installation reads the newest `.idx` file of each of the 16
+//! buckets and merges its update section. A group index stores the merged
+//! entries of every bucket in a single file, sorted by encoding key with the
+//! bucket byte embedded, so a cold start reads one file and skips the merge.
+//!
+//! The group index records the `.idx` version each bucket had when it was
+//! built, and the build config hash of the installation. It is only used
+//! while those are still the newest versions on disk and the installation
+//! is on the same build; once any bucket is saved again, or the build is
+//! replaced, it is stale and loading falls back to the per-bucket files.
+//! The build config catches rebuilds that write the same `.idx` versions.
+//!
+//! File layout (little-endian):
+//! ```text
+//! [0x00] Magic "GIDX"
+//! [0x04] Format version (u8, currently 2)
+//! [0x05] Has build config (u8, 0 or 1)
+//! [0x06] Build config hash (16 bytes, zero without a build config)
+//! [0x16] Bucket count (u8)
+//!        Per bucket: bucket (u8), idx version (u32), key size (u8),
+//!                    entry count (u32)
+//!        Entry count (u32)
+//!        Per entry: bucket (u8), key (bucket key size), archive id (u16),
+//!                   archive offset (u32), encoded size (u32)
+//! [end]  Jenkins hashlittle of all preceding bytes (u32)
+//! ```
+
+use super::{IndexEntry, IndexManager, check_key_size, sync_directory};
+use crate::{Result, StorageError};
+use cascette_crypto::jenkins::hashlittle;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// File name of the group index in the data directory
+pub const GROUP_INDEX_FILE: &str = "index.group";
+
+/// Magic bytes at the start of a group index
+const MAGIC: &[u8; 4] = b"GIDX";
+
+/// Current group index format version
+const FORMAT_VERSION: u8 = 2;
+
+/// Seed of the trailing Jenkins hash
+const HASH_SEED: u32 = 0;
+
+/// One bucket of a group index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupBucket {
+    /// `.idx` file version the entries came from
+    pub version: u32,
+    /// Key width of the bucket (9 or 16)
+    pub key_size: u8,
+    /// Entries of the bucket, sorted by key
+    pub entries: Vec<IndexEntry>,
+}
+
+/// Merged entries of all saved buckets
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupIndex {
+    /// Buckets covered by the group index
+    buckets: BTreeMap<u8, GroupBucket>,
+    /// Build config hash of the installation the index was built for
+    build_config: Option<[u8; 16]>,
+}
+
+impl GroupIndex {
+    /// Collect the entries of every saved bucket of `manager`
+    ///
+    /// Only buckets with a file version, loaded or saved, are included,
+    /// since a bucket that was never written has nothing to compare against
+    /// when checking staleness. Build after saving so unsaved entries are
+    /// not recorded under an older version. The manager's
+    /// [build config](IndexManager::build_config) is recorded as well.
+    pub fn build_from(manager: &IndexManager) -> Self {
+        let buckets = manager
+            .indices
+            .iter()
+            .filter_map(|(&bucket, index)| {
+                let version = *manager.versions.get(&bucket)?;
+                Some((
+                    bucket,
+                    GroupBucket {
+                        version,
+                        key_size: index.header.key_size,
+                        entries: IndexManager::merged_entries(index),
+                    },
+                ))
+            })
+            .collect();
+        Self {
+            buckets,
+            build_config: manager.build_config,
+        }
+    }
+
+    /// Build config hash the group index was built for
+    pub const fn build_config(&self) -> Option<&[u8; 16]> {
+        self.build_config.as_ref()
+    }
+
+    /// Buckets covered by the group index
+    pub const fn buckets(&self) -> &BTreeMap<u8, GroupBucket> {
+        &self.buckets
+    }
+
+    /// Take the buckets out of the group index
+    pub fn into_buckets(self) -> BTreeMap<u8, GroupBucket> {
+        self.buckets
+    }
+
+    /// Total number of entries over all buckets
+    pub fn entry_count(&self) -> usize {
+        self.buckets.values().map(|info| info.entries.len()).sum()
+    }
+
+    /// All entries with their bucket, sorted by key across buckets
+    pub fn entries(&self) -> Vec<(u8, &IndexEntry)> {
+        let mut entries: Vec<(u8, &IndexEntry)> = self
+            .buckets
+            .iter()
+            .flat_map(|(&bucket, info)| info.entries.iter().map(move |entry| (bucket, entry)))
+            .collect();
+        entries.sort_unstable_by(|a, b| a.1.key_bytes().cmp(b.1.key_bytes()));
+        entries
+    }
+
+    /// Whether the group index was built for `build_config` from exactly
+    /// the `.idx` versions in `newest`, the newest version on disk per
+    /// bucket
+    pub fn is_current(&self, newest: &BTreeMap<u8, u32>, build_config: Option<&[u8; 16]>) -> bool {
+        self.build_config.as_ref() == build_config
+            && self.buckets.len() == newest.len()
+            && self
+                .buckets
+                .iter()
+                .all(|(bucket, info)| newest.get(bucket) == Some(&info.version))
+    }
+
+    /// Serialize to the group index format
+    ///
+    /// # Errors
+    ///
+    /// Returns error if there are more than 255 buckets or `u32::MAX`
+    /// entries
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let entries = self.entries();
+        let bucket_count = u8::try_from(self.buckets.len())
+            .map_err(|_| StorageError::Index("Too many buckets for group index".to_string()))?;
+        let entry_count = u32::try_from(entries.len())
+            .map_err(|_| StorageError::Index("Too many entries for group index".to_string()))?;
+
+        let mut data = Vec::with_capacity(31 + self.buckets.len() * 10 + entries.len() * 27);
+        data.extend_from_slice(MAGIC);
+        data.push(FORMAT_VERSION);
+        data.push(u8::from(self.build_config.is_some()));
+        data.extend_from_slice(&self.build_config.unwrap_or_default());
+        data.push(bucket_count);
+        for (&bucket, info) in &self.buckets {
+            data.push(bucket);
+            data.extend_from_slice(&info.version.to_le_bytes());
+            data.push(info.key_size);
+            // Fits, the total was checked above
+            data.extend_from_slice(&(info.entries.len() as u32).to_le_bytes());
+        }
+        data.extend_from_slice(&entry_count.to_le_bytes());
+        for (bucket, entry) in entries {
+            let key_size = self.buckets.get(&bucket).map_or(9, |info| info.key_size);
+            let mut key = [0u8; 16];
+            let key_bytes = entry.key_bytes();
+            let len = key_bytes.len().min(key.len());
+            key[..len].copy_from_slice(&key_bytes[..len]);
+            data.push(bucket);
+            data.extend_from_slice(&key[..usize::from(key_size)]);
+            data.extend_from_slice(&entry.archive_location.archive_id.to_le_bytes());
+            data.extend_from_slice(&entry.archive_location.archive_offset.to_le_bytes());
+            data.extend_from_slice(&entry.size.to_le_bytes());
+        }
+        let hash = hashlittle(&data, HASH_SEED);
+        data.extend_from_slice(&hash.to_le_bytes());
+        Ok(data)
+    }
+
+    /// Parse a group index
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the magic, version or hash do not match, the data
+    /// is truncated, or an entry is in the wrong bucket or out of order
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let Some((body, hash)) = data.split_last_chunk::<4>() else {
+            return Err(invalid("too short"));
+        };
+        if hashlittle(body, HASH_SEED) != u32::from_le_bytes(*hash) {
+            return Err(invalid("hash mismatch"));
+        }
+
+        let Some((magic, rest)) = body.split_first_chunk::<4>() else {
+            return Err(invalid("too short"));
+        };
+        if magic != MAGIC {
+            return Err(invalid("bad magic"));
+        }
+        let Some((&[version, has_build_config], rest)) = rest.split_first_chunk::<2>() else {
+            return Err(invalid("too short"));
+        };
+        if version != FORMAT_VERSION {
+            return Err(invalid(&format!("unsupported version {version}")));
+        }
+        let Some((&build_config, rest)) = rest.split_first_chunk::<16>() else {
+            return Err(invalid("too short"));
+        };
+        let build_config = match has_build_config {
+            0 => None,
+            1 => Some(build_config),
+            _ => return Err(invalid("bad build config flag")),
+        };
+        let Some((&bucket_count, mut rest)) = rest.split_first() else {
+            return Err(invalid("too short"));
+        };
+
+        // Key size and entries per bucket number, 0 for buckets not covered
+        let mut key_sizes = [0u8; 256];
+        let mut lists: Vec<Vec<IndexEntry>> = Vec::new();
+        lists.resize_with(256, Vec::new);
+        let mut versions = BTreeMap::new();
+        for _ in 0..bucket_count {
+            let Some((&[bucket, v0, v1, v2, v3, key_size, c0, c1, c2, c3], tail)) =
+                rest.split_first_chunk::<10>()
+            else {
+                return Err(invalid("truncated bucket table"));
+            };
+            check_key_size(usize::from(key_size))?;
+            // Capacity hint only, bounded by what the body can hold
+            let count = u32::from_le_bytes([c0, c1, c2, c3]) as usize;
+            key_sizes[usize::from(bucket)] = key_size;
+            lists[usize::from(bucket)] = Vec::with_capacity(count.min(body.len() / 19));
+            versions.insert(bucket, u32::from_le_bytes([v0, v1, v2, v3]));
+            rest = tail;
+        }
+
+        let Some((count, tail)) = rest.split_first_chunk::<4>() else {
+            return Err(invalid("too short"));
+        };
+        rest = tail;
+        for _ in 0..u32::from_le_bytes(*count) {
+            let Some((&bucket, tail)) = rest.split_first() else {
+                return Err(invalid("truncated entry"));
+            };
+            let key_size = usize::from(key_sizes[usize::from(bucket)]);
+            if key_size == 0 {
+                return Err(invalid(&format!("entry in unknown bucket {bucket:02x}")));
+            }
+            let Some((record, tail)) = tail.split_at_checked(key_size + 10) else {
+                return Err(invalid("truncated entry"));
+            };
+            rest = tail;
+
+            let (key, fields) = record.split_at(key_size);
+            if IndexManager::get_bucket_index(key) != bucket {
+                return Err(invalid(&format!("entry key not in bucket {bucket:02x}")));
+            }
+            let list = &mut lists[usize::from(bucket)];
+            if list.last().is_some_and(|last| last.key_bytes() >= key) {
+                return Err(invalid("entries out of order"));
+            }
+            let archive_id = u16::from_le_bytes([fields[0], fields[1]]);
+            let archive_offset = u32::from_le_bytes([fields[2], fields[3], fields[4], fields[5]]);
+            let size = u32::from_le_bytes([fields[6], fields[7], fields[8], fields[9]]);
+            let entry = if key_size == 16 {
+                let mut full_key = [0u8; 16];
+                full_key.copy_from_slice(key);
+                IndexEntry::new_full(full_key, archive_id, archive_offset, size)
+            } else {
+                let mut short_key = [0u8; 9];
+                short_key.copy_from_slice(key);
+                IndexEntry::new(short_key, archive_id, archive_offset, size)
+            };
+            list.push(entry);
+        }
+        if !rest.is_empty() {
+            return Err(invalid("trailing data"));
+        }
+
+        let buckets = versions
+            .into_iter()
+            .map(|(bucket, version)| {
+                let index = usize::from(bucket);
+                (
+                    bucket,
+                    GroupBucket {
+                        version,
+                        key_size: key_sizes[index],
+                        entries: std::mem::take(&mut lists[index]),
+                    },
+                )
+            })
+            .collect();
+        Ok(Self {
+            buckets,
+            build_config,
+        })
+    }
+
+    /// Read and parse a group index file
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be read or is invalid
+    pub fn read(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path).map_err(|e| {
+            StorageError::Index(format!(
+                "Failed to read group index {}: {e}",
+                path.display()
+            ))
+        })?;
+        Self::parse(&data)
+    }
+
+    /// Write the group index to `path`
+    ///
+    /// The file is written next to `path` and renamed into place, so a
+    /// crash leaves either the old or the new group index.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be written
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let data = self.to_bytes()?;
+        let temp_path = path.with_extension("group.tmp");
+        let io_error =
+            |e: std::io::Error| StorageError::Index(format!("Failed to write group index: {e}"));
+
+        let mut file = File::create(&temp_path).map_err(io_error)?;
+        file.write_all(&data).map_err(io_error)?;
+        file.sync_all().map_err(io_error)?;
+        drop(file);
+        std::fs::rename(&temp_path, path).map_err(io_error)?;
+        if let Some(dir) = path.parent() {
+            sync_directory(dir);
+        }
+        Ok(())
+    }
+}
+
+/// Error for a malformed group index
+fn invalid(msg: &str) -> StorageError {
+    StorageError::Index(format!("Invalid group index: {msg}"))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use cascette_crypto::EncodingKey;
+
+    fn ekey(seed: u8) -> EncodingKey {
+        EncodingKey::from_bytes([seed; 16])
+    }
+
+    fn saved_manager(dir: &Path, seeds: &[u8]) -> IndexManager {
+        let mut manager = IndexManager::new(dir);
+        for &seed in seeds {
+            manager
+                .add_entry(&ekey(seed), u16::from(seed), u32::from(seed) * 0x100, 64)
+                .expect("add_entry should succeed");
+        }
+        manager.save_all().expect("save_all should succeed");
+        manager
+    }
+
+    #[test]
+    fn test_group_index_round_trip() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let manager = saved_manager(temp_dir.path(), &[1, 2, 3, 0x40, 0x81]);
+
+        let group = GroupIndex::build_from(&manager);
+        assert_eq!(group.entry_count(), 5);
+        assert!(
+            group
+                .entries()
+                .windows(2)
+                .all(|w| w[0].1.key_bytes() < w[1].1.key_bytes())
+        );
+        for (bucket, entry) in group.entries() {
+            assert_eq!(IndexManager::get_bucket_index(entry.key_bytes()), bucket);
+        }
+
+        let parsed = GroupIndex::parse(&group.to_bytes().expect("serialize"))
+            .expect("Group index should parse");
+        assert_eq!(parsed, group);
+    }
+
+    #[test]
+    fn test_group_index_16_byte_keys_round_trip() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let mut manager = IndexManager::with_key_size(temp_dir.path(), 16).expect("valid key size");
+        manager
+            .add_entry(&ekey(9), 1, 0x200, 32)
+            .expect("add_entry should succeed");
+        manager.save_all().expect("save_all should succeed");
+
+        let group = GroupIndex::build_from(&manager);
+        let parsed = GroupIndex::parse(&group.to_bytes().expect("serialize"))
+            .expect("Group index should parse");
+        assert_eq!(parsed.entries()[0].1.full_key, Some([9; 16]));
+        assert_eq!(parsed, group);
+    }
+
+    #[test]
+    fn test_group_index_skips_unsaved_buckets() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let mut manager = IndexManager::new(temp_dir.path());
+        manager
+            .add_entry(&ekey(1), 1, 0, 64)
+            .expect("add_entry should succeed");
+
+        let group = GroupIndex::build_from(&manager);
+        assert!(group.buckets().is_empty());
+        assert_eq!(group.entry_count(), 0);
+    }
+
+    #[test]
+    fn test_group_index_rejects_damage() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let manager = saved_manager(temp_dir.path(), &[1, 2]);
+        let data = GroupIndex::build_from(&manager)
+            .to_bytes()
+            .expect("serialize");
+
+        let mut flipped = data.clone();
+        flipped[8] ^= 0xff;
+        assert!(GroupIndex::parse(&flipped).is_err());
+        assert!(GroupIndex::parse(&data[..data.len() - 1]).is_err());
+        assert!(GroupIndex::parse(&[]).is_err());
+    }
+
+    #[test]
+    fn test_is_current_compares_versions() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let manager = saved_manager(temp_dir.path(), &[1]);
+        let bucket = IndexManager::bucket_for_key(&ekey(1));
+        let group = GroupIndex::build_from(&manager);
+
+        assert!(group.is_current(&BTreeMap::from([(bucket, 1)]), None));
+        // A newer file for the bucket
+        assert!(!group.is_current(&BTreeMap::from([(bucket, 2)]), None));
+        // A bucket the group index does not cover
+        assert!(!group.is_current(&BTreeMap::from([(bucket, 1), (bucket ^ 1, 1)]), None));
+        assert!(!group.is_current(&BTreeMap::new(), None));
+    }
+
+    #[test]
+    fn test_is_current_compares_build_config() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let mut manager = saved_manager(temp_dir.path(), &[1]);
+        manager.set_build_config(Some([0xAB; 16]));
+        let bucket = IndexManager::bucket_for_key(&ekey(1));
+        let newest = BTreeMap::from([(bucket, 1)]);
+
+        let group = GroupIndex::parse(
+            &GroupIndex::build_from(&manager)
+                .to_bytes()
+                .expect("serialize"),
+        )
+        .expect("Group index should parse");
+        assert_eq!(group.build_config(), Some(&[0xAB; 16]));
+        assert!(group.is_current(&newest, Some(&[0xAB; 16])));
+        // A rebuild under the same idx versions
+        assert!(!group.is_current(&newest, Some(&[0xCD; 16])));
+        assert!(!group.is_current(&newest, None));
+    }
+}
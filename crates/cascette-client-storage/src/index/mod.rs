@@ -8,7 +8,12 @@
 //! Lookups search the update section first (linear), then the sorted
 //! section (binary search). When the update section fills, entries are
 //! merged into the sorted section via atomic file replacement.
+//!
+//! A [`group::GroupIndex`] written next to the `.idx` files holds the
+//! merged entries of all buckets and is preferred on load while it matches
+//! the newest file of every bucket.
 
+pub mod group;
 pub mod update;
 
 #[cfg(test)]
@@ -18,15 +23,15 @@ use crate::{Result, StorageError};
 use binrw::Endian;
 use binrw::{BinRead, BinReaderExt, BinResult, BinWrite, BinWriterExt};
 use cascette_crypto::{ContentKey, EncodingKey};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
-use tokio::fs;
 use tracing::{debug, info, warn};
 
+pub use group::{GROUP_INDEX_FILE, GroupIndex};
 pub use update::UpdateStatus;
-use update::{UPDATE_SECTION_ALIGNMENT, UpdateEntry, UpdateSection};
+use update::{ENTRIES_PER_PAGE, UPDATE_SECTION_ALIGNMENT, UpdateEntry, UpdateSection};
 
 /// Custom binrw parser for archive location (5 bytes: 1 high + 4 packed)
 fn parse_archive_location<R: std::io::Read + std::io::Seek>(
@@ -113,6 +118,9 @@ pub struct IndexHeaderV2 {
     pub segment_size: u64,
 }
 
+/// Serialized size of [`IndexHeaderV2`], the header guarded block size
+const INDEX_HEADER_V2_SIZE: u32 = 16;
+
 /// Legacy IndexHeader for compatibility (wraps V2)
 #[derive(Debug, Clone, BinRead, BinWrite)]
 #[brw(little)]
@@ -137,10 +145,14 @@ pub struct IndexHeader {
     pub segment_bits: u8,
 }
 
-/// Entry in an index file (18-byte IDX Journal format)
+/// Entry in an index file (IDX Journal format)
+///
+/// Buckets with 9-byte keys use an 18-byte entry, buckets with 16-byte keys
+/// (some Classic installations) a 25-byte entry. The fields after the key
+/// are the same in both layouts.
 ///
 /// Mixed endianness (verified against Agent.exe and CascLib):
-/// - Key: 9 bytes (endianness irrelevant)
+/// - Key: 9 or 16 bytes (endianness irrelevant)
 /// - StorageOffset: 5 bytes big-endian
 /// - EncodedSize: 4 bytes **little-endian**
 ///
@@ -161,9 +173,15 @@ pub struct IndexEntry {
     ///
     /// Agent.exe reads this with a backward byte loop (LE) and CascLib
     /// uses `ConvertBytesToInteger_4_LE`. This is the one field in the
-    /// 18-byte entry that is NOT big-endian.
+    /// entry that is NOT big-endian.
     #[brw(little)]
     pub size: u32,
+
+    /// Full 16-byte encoding key, for entries of buckets with 16-byte keys.
+    ///
+    /// `None` for 9-byte buckets. Not part of the 18-byte binrw layout.
+    #[brw(ignore)]
+    pub full_key: Option<[u8; 16]>,
 }
 
 /// Archive location data combining archive ID and offset
@@ -175,6 +193,17 @@ pub struct ArchiveLocation {
     pub archive_offset: u32,
 }
 
+/// Key width of a bucket, checked against the two supported sizes
+fn check_key_size(key_bytes: usize) -> Result<()> {
+    if key_bytes == 9 || key_bytes == 16 {
+        Ok(())
+    } else {
+        Err(StorageError::Index(format!(
+            "Invalid key size: {key_bytes}"
+        )))
+    }
+}
+
 impl IndexEntry {
     /// Create new `IndexEntry` with the given parameters
     pub const fn new(key: [u8; 9], archive_id: u16, archive_offset: u32, size: u32) -> Self {
@@ -185,9 +214,17 @@ impl IndexEntry {
                 archive_offset,
             },
             size,
+            full_key: None,
         }
     }
 
+    /// Create an entry for a bucket with 16-byte keys
+    pub fn new_full(key: [u8; 16], archive_id: u16, archive_offset: u32, size: u32) -> Self {
+        let mut entry = Self::new(truncate_key(&key), archive_id, archive_offset, size);
+        entry.full_key = Some(key);
+        entry
+    }
+
     /// Archive file number (data.XXX) from the packed location.
     pub const fn archive_id(&self) -> u16 {
         self.archive_location.archive_id
@@ -198,38 +235,54 @@ impl IndexEntry {
         self.archive_location.archive_offset
     }
 
-    /// Create from raw packed data (IDX Journal format) - legacy compatibility
+    /// Key bytes as stored in the index: 16 bytes if the full key is
+    /// known, the 9-byte truncated key otherwise.
+    pub fn key_bytes(&self) -> &[u8] {
+        self.full_key.as_ref().map_or(&self.key[..], |key| &key[..])
+    }
+
+    /// Create from raw packed data (IDX Journal format)
+    ///
+    /// `key_bytes` is the bucket's key width, 9 (18-byte entries) or 16
+    /// (25-byte entries). The offset and size widths are fixed for local
+    /// CASC indices.
     ///
     /// # Errors
     ///
     /// Returns error if data is malformed or insufficient
     pub fn from_packed(
         data: &[u8],
-        _key_bytes: usize,
+        key_bytes: usize,
         _offset_bits: usize,
         _size_bits: usize,
     ) -> Result<Self> {
-        // For local CASC IDX files, use fixed 18-byte format
-        if data.len() < 18 {
+        check_key_size(key_bytes)?;
+        if data.len() < key_bytes + 9 {
             return Err(StorageError::Index("Entry data too small".to_string()));
         }
+        if data[..key_bytes].iter().all(|&b| b == 0) {
+            return Err(StorageError::Index("Empty entry".to_string()));
+        }
 
-        // Parse using binrw - create a cursor and read
-        let mut cursor = Cursor::new(data);
-        match Self::read_be(&mut cursor) {
-            Ok(entry) => {
-                // Skip empty entries
-                if entry.key == [0u8; 9] {
-                    return Err(StorageError::Index("Empty entry".to_string()));
-                }
-                Ok(entry)
-            }
-            Err(e) => Err(StorageError::Index(format!("Failed to parse entry: {e}"))),
+        // The 18-byte layout is also the tail of the 25-byte one, with the
+        // last 9 key bytes in place of the truncated key
+        let mut cursor = Cursor::new(&data[key_bytes - 9..]);
+        let mut entry = Self::read_be(&mut cursor)
+            .map_err(|e| StorageError::Index(format!("Failed to parse entry: {e}")))?;
+        if key_bytes == 16 {
+            let mut key = [0u8; 16];
+            key.copy_from_slice(&data[..16]);
+            entry.key = truncate_key(&key);
+            entry.full_key = Some(key);
         }
+        Ok(entry)
     }
 
-    /// Pack entry to raw data - legacy compatibility
-    pub fn to_packed(&self, _key_bytes: usize, _offset_bits: usize, _size_bits: usize) -> Vec<u8> {
+    /// Pack entry to raw data (IDX Journal format)
+    ///
+    /// With `key_bytes` of 16 the full key is written; an entry that only
+    /// knows its truncated key is zero-padded.
+    pub fn to_packed(&self, key_bytes: usize, _offset_bits: usize, _size_bits: usize) -> Vec<u8> {
         // Use binrw to serialize
         let mut data = Vec::new();
         let mut cursor = Cursor::new(&mut data);
@@ -238,19 +291,59 @@ impl IndexEntry {
         if let Err(e) = self.write_be(&mut cursor) {
             eprintln!("Warning: Failed to serialize entry with binrw: {e}");
             // Fallback to ensure we return something
-            return vec![0; 18];
+            return vec![0; key_bytes.max(9) + 9];
+        }
+
+        if key_bytes == 16 {
+            let key = self.full_key.unwrap_or_else(|| {
+                let mut key = [0u8; 16];
+                key[..9].copy_from_slice(&self.key);
+                key
+            });
+            data.splice(..9, key);
         }
 
         data
     }
 }
 
+/// Flush directory metadata so a rename survives a crash
+///
+/// Best effort: a failure only weakens durability, and Windows offers no
+/// way to sync a directory handle.
+fn sync_directory(path: &Path) {
+    #[cfg(unix)]
+    if let Err(e) = File::open(path).and_then(|dir| dir.sync_all()) {
+        warn!("Failed to sync directory {}: {}", path.display(), e);
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// First 9 bytes of a key, zero-padded if shorter
+fn truncate_key(key_bytes: &[u8]) -> [u8; 9] {
+    let mut truncated = [0u8; 9];
+    let len = 9.min(key_bytes.len());
+    truncated[..len].copy_from_slice(&key_bytes[..len]);
+    truncated
+}
+
 /// Index file manager
 pub struct IndexManager {
     /// Map of index ID to loaded index data
     indices: BTreeMap<u8, IndexFile>,
     /// Directory containing index files
     base_path: PathBuf,
+    /// Key width (9 or 16) for buckets created by this manager
+    key_size: u8,
+    /// File version backing each bucket, from loading or the last save
+    versions: BTreeMap<u8, u32>,
+    /// Number of superseded versions kept per bucket after a save
+    retained_versions: usize,
+    /// Whether the last load used the group index
+    loaded_from_group: bool,
+    /// Build config hash of the installation, checked against the group index
+    build_config: Option<[u8; 16]>,
 }
 
 /// Individual index file data
@@ -269,47 +362,202 @@ impl IndexManager {
         Self {
             indices: BTreeMap::new(),
             base_path: base_path.as_ref().to_path_buf(),
+            key_size: 9,
+            versions: BTreeMap::new(),
+            retained_versions: 1,
+            loaded_from_group: false,
+            build_config: None,
         }
     }
 
+    /// Keep `count` superseded versions of each bucket when saving.
+    ///
+    /// The default of 1 leaves the previous file as a fallback if the
+    /// newest one turns out to be damaged.
+    #[must_use]
+    pub fn with_retained_versions(mut self, count: usize) -> Self {
+        self.retained_versions = count;
+        self
+    }
+
+    /// Set the build config hash of the installation
+    ///
+    /// A group index is only loaded if it was written for the same build
+    /// config, and [`write_group_index`](Self::write_group_index) records it.
+    pub const fn set_build_config(&mut self, build_config: Option<[u8; 16]>) {
+        self.build_config = build_config;
+    }
+
+    /// Build config hash of the installation, if set
+    pub const fn build_config(&self) -> Option<&[u8; 16]> {
+        self.build_config.as_ref()
+    }
+
+    /// File version currently backing `bucket`, if it was loaded or saved
+    pub fn bucket_version(&self, bucket: u8) -> Option<u32> {
+        self.versions.get(&bucket).copied()
+    }
+
+    /// Create an index manager whose new buckets use `key_size`-byte keys.
+    ///
+    /// Loaded buckets keep the key size from their header.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if `key_size` is not 9 or 16
+    pub fn with_key_size(base_path: impl AsRef<Path>, key_size: u8) -> Result<Self> {
+        check_key_size(usize::from(key_size))?;
+        Ok(Self {
+            key_size,
+            ..Self::new(base_path)
+        })
+    }
+
     /// Load all index files from the directory
     ///
+    /// A valid group index built for the current
+    /// [build config](Self::build_config) from the newest version of every
+    /// bucket is loaded instead of the `.idx` files. Otherwise each bucket loads
+    /// its newest version that passes the header Jenkins hash check; older
+    /// versions are only read if newer ones fail.
+    ///
     /// # Errors
     ///
-    /// Returns error if directory cannot be read or index files cannot be loaded
+    /// Returns error if directory cannot be read or no version of a bucket
+    /// can be loaded
+    #[allow(clippy::unused_async)] // Async for API compatibility with callers
     pub async fn load_all(&mut self) -> Result<()> {
         info!("Loading index files from {}", self.base_path.display());
 
-        // Find all .idx files
-        let mut entries = fs::read_dir(&self.base_path)
-            .await
-            .map_err(|e| StorageError::Index(format!("Failed to read directory: {e}")))?;
-
-        while let Some(entry) = entries
-            .next_entry()
-            .await
-            .map_err(|e| StorageError::Index(format!("Failed to read entry: {e}")))?
-        {
-            let path = entry.path();
+        let files = self.index_files_on_disk()?;
+        self.loaded_from_group = self.load_group_index(&files);
+        if self.loaded_from_group {
+            info!(
+                "Loaded {} index buckets from group index",
+                self.indices.len()
+            );
+            return Ok(());
+        }
 
-            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                // Parse index file names using official CASC format
-                if let Some((bucket, version)) = Self::parse_index_filename(name) {
-                    debug!(
-                        "Loading index file bucket {:02x} version {:06x} from {}",
-                        bucket,
-                        version,
-                        path.display()
-                    );
-                    self.load_index(bucket, &path)?;
+        for (bucket, mut candidates) in files {
+            // Newest first; older versions are fallbacks for a damaged file
+            candidates.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+            let mut first_error = None;
+            for (version, path) in &candidates {
+                debug!(
+                    "Loading index file bucket {:02x} version {:08x} from {}",
+                    bucket,
+                    version,
+                    path.display()
+                );
+                match self.load_index(bucket, path) {
+                    Ok(()) => {
+                        self.versions.insert(bucket, *version);
+                        first_error = None;
+                        break;
+                    }
+                    Err(e) => {
+                        warn!("Skipping index file {}: {}", path.display(), e);
+                        first_error.get_or_insert(e);
+                    }
                 }
             }
+            if let Some(e) = first_error {
+                return Err(e);
+            }
         }
 
         info!("Loaded {} index files", self.indices.len());
         Ok(())
     }
 
+    /// Load the group index if it matches the build config and the newest
+    /// file of every bucket
+    ///
+    /// Returns `false`, leaving the manager untouched, if there is no
+    /// group index or it is damaged or stale.
+    fn load_group_index(&mut self, files: &BTreeMap<u8, Vec<(u32, PathBuf)>>) -> bool {
+        let path = self.base_path.join(GROUP_INDEX_FILE);
+        if !path.exists() {
+            return false;
+        }
+        let group = match GroupIndex::read(&path) {
+            Ok(group) => group,
+            Err(e) => {
+                warn!("Ignoring group index {}: {}", path.display(), e);
+                return false;
+            }
+        };
+        let newest: BTreeMap<u8, u32> = files
+            .iter()
+            .filter_map(|(&bucket, candidates)| {
+                candidates
+                    .iter()
+                    .map(|(version, _)| *version)
+                    .max()
+                    .map(|version| (bucket, version))
+            })
+            .collect();
+        if !group.is_current(&newest, self.build_config.as_ref()) {
+            debug!("Group index {} is stale", path.display());
+            return false;
+        }
+
+        for (bucket, info) in group.into_buckets() {
+            let mut index = Self::empty_bucket(bucket, info.key_size);
+            index.entries = info.entries;
+            self.indices.insert(bucket, index);
+            self.versions.insert(bucket, info.version);
+        }
+        true
+    }
+
+    /// Whether the last [`load_all`](Self::load_all) used the group index
+    pub const fn loaded_from_group_index(&self) -> bool {
+        self.loaded_from_group
+    }
+
+    /// Write a group index of all saved buckets next to the `.idx` files
+    ///
+    /// See [`GroupIndex::build_from`]; call after saving so the group index
+    /// matches the files on disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the group index cannot be written
+    pub fn write_group_index(&self) -> Result<()> {
+        let group = GroupIndex::build_from(self);
+        group.write(&self.base_path.join(GROUP_INDEX_FILE))?;
+        info!(
+            "Wrote group index with {} entries from {} buckets",
+            group.entry_count(),
+            group.buckets().len()
+        );
+        Ok(())
+    }
+
+    /// Index files in the directory, grouped by bucket as `(version, path)`
+    fn index_files_on_disk(&self) -> Result<BTreeMap<u8, Vec<(u32, PathBuf)>>> {
+        let mut files: BTreeMap<u8, Vec<(u32, PathBuf)>> = BTreeMap::new();
+        let entries = std::fs::read_dir(&self.base_path)
+            .map_err(|e| StorageError::Index(format!("Failed to read directory: {e}")))?;
+
+        for entry in entries {
+            let path = entry
+                .map_err(|e| StorageError::Index(format!("Failed to read entry: {e}")))?
+                .path();
+            // Parse index file names using official CASC format
+            if let Some((bucket, version)) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(Self::parse_index_filename)
+            {
+                files.entry(bucket).or_default().push((version, path));
+            }
+        }
+        Ok(files)
+    }
+
     /// Read and validate the index header, returning a legacy-compatible header
     fn read_index_header(reader: &mut BufReader<File>) -> Result<(IndexHeader, usize)> {
         // Read header guarded block (size + hash)
@@ -317,8 +565,25 @@ impl IndexManager {
             .read_le()
             .map_err(|e| StorageError::Index(format!("Failed to read header block: {e}")))?;
 
-        // Read V2 header inside the guarded block
-        let header_v2: IndexHeaderV2 = reader
+        // Read V2 header inside the guarded block and check its Jenkins hash
+        if header_block.block_size != INDEX_HEADER_V2_SIZE {
+            return Err(StorageError::Index(format!(
+                "Unexpected index header size: {}",
+                header_block.block_size
+            )));
+        }
+        let mut header_data = [0u8; INDEX_HEADER_V2_SIZE as usize];
+        reader
+            .read_exact(&mut header_data)
+            .map_err(|e| StorageError::Index(format!("Failed to read header: {e}")))?;
+        let header_hash = cascette_crypto::jenkins::hashlittle(&header_data, 0);
+        if header_hash != header_block.block_hash {
+            return Err(StorageError::Index(format!(
+                "Index header hash mismatch: expected 0x{:08x}, got 0x{header_hash:08x}",
+                header_block.block_hash
+            )));
+        }
+        let header_v2: IndexHeaderV2 = Cursor::new(&header_data)
             .read_le()
             .map_err(|e| StorageError::Index(format!("Failed to read header: {e}")))?;
 
@@ -326,12 +591,7 @@ impl IndexManager {
         if header_v2.version != 7 {
             warn!("Unexpected index version: {}", header_v2.version);
         }
-        if header_v2.ekey_length != 9 && header_v2.ekey_length != 16 {
-            return Err(StorageError::Index(format!(
-                "Invalid key size: {}",
-                header_v2.ekey_length
-            )));
-        }
+        check_key_size(usize::from(header_v2.ekey_length))?;
 
         // Create legacy header for compatibility
         let header = IndexHeader {
@@ -386,12 +646,13 @@ impl IndexManager {
     ) -> Vec<IndexEntry> {
         let mut entries = Vec::new();
         let mut offset = 0;
+        let key_size = usize::from(header.key_size);
 
         while offset + entry_size <= entry_data.len() {
             let entry_bytes = &entry_data[offset..offset + entry_size];
 
             // Check if entry is valid (non-zero key)
-            if entry_bytes[..9].iter().any(|&b| b != 0) {
+            if entry_bytes[..key_size].iter().any(|&b| b != 0) {
                 // Use fixed parsing for IDX Journal format
                 if let Ok(entry) = IndexEntry::from_packed(
                     entry_bytes,
@@ -418,7 +679,7 @@ impl IndexManager {
                 eprintln!(
                     "  {}: key={}, archive={}, offset={}, size={}",
                     i,
-                    hex::encode(entry.key),
+                    hex::encode(entry.key_bytes()),
                     entry.archive_id(),
                     entry.archive_offset(),
                     entry.size
@@ -457,7 +718,7 @@ impl IndexManager {
         Self::debug_print_entries(&entries, id);
 
         // Sort entries by key for binary search
-        entries.sort_by_key(|e| e.key);
+        entries.sort_by(|a, b| a.key_bytes().cmp(b.key_bytes()));
 
         // Parse update section from 64KB-aligned boundary after sorted data
         // Layout: 8 (header block) + 16 (header) + 8 (padding) + 8 (entry block) + entry_data
@@ -520,13 +781,9 @@ impl IndexManager {
         let key_bytes = key.as_bytes();
         let index_id = Self::get_bucket_index(key_bytes);
 
-        self.indices.get(&index_id).and_then(|index| {
-            let mut search_key = [0u8; 9];
-            search_key[..9.min(key_bytes.len())]
-                .copy_from_slice(&key_bytes[..9.min(key_bytes.len())]);
-
-            Self::search_both_sections(index, &search_key)
-        })
+        self.indices
+            .get(&index_id)
+            .and_then(|index| Self::search_both_sections(index, key_bytes))
     }
 
     /// Look up a content key in the indices (backward compatibility).
@@ -537,22 +794,23 @@ impl IndexManager {
         let key_bytes = key.as_bytes();
         let index_id = Self::get_bucket_index(key_bytes);
 
-        self.indices.get(&index_id).and_then(|index| {
-            let mut search_key = [0u8; 9];
-            search_key[..9.min(key_bytes.len())]
-                .copy_from_slice(&key_bytes[..9.min(key_bytes.len())]);
-
-            Self::search_both_sections(index, &search_key)
-        })
+        self.indices
+            .get(&index_id)
+            .and_then(|index| Self::search_both_sections(index, key_bytes))
     }
 
     /// Search both sections of an index file.
     ///
     /// Agent's `SearchBothSections`: searches update section first (linear),
     /// then sorted section (binary search). Update entries take precedence.
-    fn search_both_sections(index: &IndexFile, search_key: &[u8; 9]) -> Option<IndexEntry> {
+    ///
+    /// `key` is truncated to the bucket's key width, so 16-byte buckets
+    /// compare the full key.
+    fn search_both_sections(index: &IndexFile, key: &[u8]) -> Option<IndexEntry> {
+        let search_key = &key[..usize::from(index.header.key_size).min(key.len())];
+
         // Search update section first (linear scan, newest first)
-        if let Some(update_entry) = index.update_section.search(search_key) {
+        if let Some(update_entry) = index.update_section.find(search_key) {
             if update_entry.status == UpdateStatus::Delete {
                 return None; // Deleted via tombstone
             }
@@ -562,11 +820,28 @@ impl IndexManager {
         // Binary search sorted section
         index
             .entries
-            .binary_search_by_key(search_key, |e| e.key)
+            .binary_search_by(|e| e.key_bytes().cmp(search_key))
             .ok()
             .map(|idx| index.entries[idx].clone())
     }
 
+    /// Build an update section entry for `key` in `index`.
+    ///
+    /// Entries of 16-byte buckets keep the full key for the merge.
+    fn new_update(
+        index: &IndexFile,
+        key: &[u8],
+        archive_location: ArchiveLocation,
+        size: u32,
+        status: UpdateStatus,
+    ) -> UpdateEntry {
+        let entry = UpdateEntry::new(truncate_key(key), archive_location, size, status);
+        match <[u8; 16]>::try_from(key) {
+            Ok(full_key) if index.header.key_size == 16 => entry.with_full_key(full_key),
+            _ => entry,
+        }
+    }
+
     /// Add a new entry to the appropriate index.
     ///
     /// Appends to the update section (LSM-tree L0). If the update section
@@ -584,31 +859,12 @@ impl IndexManager {
     ) -> Result<()> {
         let key_bytes = key.as_bytes();
         let index_id = Self::get_bucket_index(key_bytes);
+        self.ensure_bucket(index_id);
 
-        let mut truncated_key = [0u8; 9];
-        truncated_key[..9.min(key_bytes.len())]
-            .copy_from_slice(&key_bytes[..9.min(key_bytes.len())]);
-
-        // Ensure bucket exists
-        self.indices.entry(index_id).or_insert_with(|| IndexFile {
-            header: IndexHeader {
-                data_size: 16,
-                data_hash: 0,
-                version: 7,
-                bucket: index_id,
-                unused: 0,
-                length_size: 4,
-                location_size: 5,
-                key_size: 9,
-                segment_bits: 30,
-            },
-            entries: Vec::new(),
-            update_section: UpdateSection::new(),
-        });
-
-        let make_entry = || {
-            UpdateEntry::new(
-                truncated_key,
+        let make_entry = |index: &IndexFile| {
+            Self::new_update(
+                index,
+                key_bytes,
                 ArchiveLocation {
                     archive_id,
                     archive_offset,
@@ -624,7 +880,8 @@ impl IndexManager {
                 .indices
                 .get_mut(&index_id)
                 .unwrap_or_else(|| unreachable!("bucket was just created"));
-            if index.update_section.append(make_entry()) {
+            let entry = make_entry(index);
+            if index.update_section.append(entry) {
                 return Ok(());
             }
         }
@@ -636,7 +893,8 @@ impl IndexManager {
             .indices
             .get_mut(&index_id)
             .unwrap_or_else(|| unreachable!("bucket was just flushed"));
-        if !index.update_section.append(make_entry()) {
+        let entry = make_entry(index);
+        if !index.update_section.append(entry) {
             return Err(StorageError::Index(
                 "update section full after flush".to_string(),
             ));
@@ -645,17 +903,151 @@ impl IndexManager {
         Ok(())
     }
 
+    /// Create an empty bucket unless it is already loaded
+    fn ensure_bucket(&mut self, bucket: u8) {
+        let key_size = self.key_size;
+        self.indices
+            .entry(bucket)
+            .or_insert_with(|| Self::empty_bucket(bucket, key_size));
+    }
+
+    /// Bucket with a v7 header and no entries
+    fn empty_bucket(bucket: u8, key_size: u8) -> IndexFile {
+        IndexFile {
+            header: IndexHeader {
+                data_size: 16,
+                data_hash: 0,
+                version: 7,
+                bucket,
+                unused: 0,
+                length_size: 4,
+                location_size: 5,
+                key_size,
+                segment_bits: 30,
+            },
+            entries: Vec::new(),
+            update_section: UpdateSection::new(),
+        }
+    }
+
+    /// Add a batch of entries and save the buckets they touch.
+    ///
+    /// Each entry is `(key, archive_id, archive_offset, size)`. All entries
+    /// are in memory before anything is written, so a caller holding the
+    /// manager's lock publishes them to readers at once. A full update
+    /// section is merged in memory and each touched bucket is then saved
+    /// once, rather than on every merge as with [`add_entry`](Self::add_entry).
+    ///
+    /// Buckets are separate files, so a crash while saving can leave some
+    /// buckets with the new entries and others without.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a touched bucket cannot be saved
+    pub fn publish_entries(&mut self, entries: &[(EncodingKey, u16, u32, u32)]) -> Result<()> {
+        let mut touched = BTreeSet::new();
+        for &(key, archive_id, archive_offset, size) in entries {
+            let key_bytes = key.as_bytes();
+            let bucket = Self::get_bucket_index(key_bytes);
+            self.ensure_bucket(bucket);
+            touched.insert(bucket);
+
+            let index = self
+                .indices
+                .get_mut(&bucket)
+                .unwrap_or_else(|| unreachable!("bucket was just created"));
+            let location = ArchiveLocation {
+                archive_id,
+                archive_offset,
+            };
+            let entry = Self::new_update(
+                index,
+                key_bytes,
+                location.clone(),
+                size,
+                UpdateStatus::Normal,
+            );
+            if !index.update_section.append(entry) {
+                index.entries = Self::merged_entries(index);
+                index.update_section.clear();
+                let entry =
+                    Self::new_update(index, key_bytes, location, size, UpdateStatus::Normal);
+                if !index.update_section.append(entry) {
+                    return Err(StorageError::Index(
+                        "update section full after merge".to_string(),
+                    ));
+                }
+            }
+        }
+
+        let mut on_disk = self.index_files_on_disk()?;
+        for bucket in touched {
+            let existing = on_disk.remove(&bucket).unwrap_or_default();
+            self.save_bucket(bucket, &existing)?;
+        }
+        Ok(())
+    }
+
     /// Save all modified indices to disk
     ///
+    /// Each bucket is written as a new file one version above the newest
+    /// one on disk, so files written by the agent are never overwritten.
+    /// Once the new file is in place, superseded versions beyond
+    /// [`with_retained_versions`](Self::with_retained_versions) are removed.
+    ///
     /// # Errors
     ///
     /// Returns error if index files cannot be created or written
-    pub fn save_all(&self) -> Result<()> {
-        for (&id, index) in &self.indices {
-            // Use version 1 for new index files - in production this would be incremented
-            let filename = Self::generate_index_filename(id, 1);
-            let path = self.base_path.join(filename);
-            Self::save_index(id, index, &path)?;
+    pub fn save_all(&mut self) -> Result<()> {
+        let mut on_disk = self.index_files_on_disk()?;
+        let buckets: Vec<u8> = self.indices.keys().copied().collect();
+        for bucket in buckets {
+            let existing = on_disk.remove(&bucket).unwrap_or_default();
+            self.save_bucket(bucket, &existing)?;
+        }
+        Ok(())
+    }
+
+    /// Write `bucket` as the next version and remove superseded files
+    ///
+    /// `existing` lists the bucket's files on disk as `(version, path)`.
+    fn save_bucket(&mut self, bucket: u8, existing: &[(u32, PathBuf)]) -> Result<()> {
+        let Some(index) = self.indices.get(&bucket) else {
+            return Ok(());
+        };
+
+        let newest = existing
+            .iter()
+            .map(|(version, _)| *version)
+            .chain(self.versions.get(&bucket).copied())
+            .max()
+            .unwrap_or(0);
+        let version = newest.checked_add(1).ok_or_else(|| {
+            StorageError::Index(format!("Index version overflow for bucket {bucket:02x}"))
+        })?;
+        let path = self
+            .base_path
+            .join(Self::generate_index_filename(bucket, version));
+        Self::save_index(bucket, index, &path)?;
+        self.versions.insert(bucket, version);
+        sync_directory(&self.base_path);
+
+        // Keep the newest superseded versions as a fallback
+        let mut superseded: Vec<&(u32, PathBuf)> =
+            existing.iter().filter(|(v, _)| *v < version).collect();
+        superseded.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+        for (old_version, old_path) in superseded.into_iter().skip(self.retained_versions) {
+            match std::fs::remove_file(old_path) {
+                Ok(()) => debug!(
+                    "Removed superseded index bucket {:02x} version {:08x}",
+                    bucket, old_version
+                ),
+                Err(e) => warn!(
+                    "Failed to remove superseded index {}: {}",
+                    old_path.display(),
+                    e
+                ),
+            }
         }
         Ok(())
     }
@@ -737,9 +1129,20 @@ impl IndexManager {
             .write_le(&mut cursor)
             .map_err(|e| StorageError::Index(format!("Failed to serialize header: {e}")))?;
 
+        // The update section stores 9-byte keys, so 16-byte buckets are
+        // written merged to keep their full keys
+        let merge = index.header.key_size == 16 && index.update_section.entry_count() > 0;
+        let merged;
+        let entries = if merge {
+            merged = Self::merged_entries(index);
+            &merged
+        } else {
+            &index.entries
+        };
+
         // Build entry data
-        let mut entry_data = Vec::with_capacity(index.entries.len() * entry_size);
-        for entry in &index.entries {
+        let mut entry_data = Vec::with_capacity(entries.len() * entry_size);
+        for entry in entries {
             let packed = entry.to_packed(index.header.key_size as usize, 30, 32);
             entry_data.extend_from_slice(&packed);
         }
@@ -761,7 +1164,7 @@ impl IndexManager {
         };
 
         // Build update section bytes (only if there are pending updates)
-        let update_data = if index.update_section.entry_count() > 0 {
+        let update_data = if index.update_section.entry_count() > 0 && !merge {
             Some(index.update_section.to_bytes())
         } else {
             None
@@ -789,8 +1192,10 @@ impl IndexManager {
                         debug!(
                             "Saved index {:02x} with {} sorted + {} update entries (attempt {})",
                             id,
-                            index.entries.len(),
-                            index.update_section.entry_count(),
+                            entries.len(),
+                            update_data
+                                .as_ref()
+                                .map_or(0, |_| index.update_section.entry_count()),
                             attempt + 1
                         );
                         return Ok(());
@@ -899,24 +1304,8 @@ impl IndexManager {
     /// Delete tombstones suppress the corresponding sorted entry.
     pub fn iter_entries(&self) -> impl Iterator<Item = (u8, IndexEntry)> + '_ {
         self.indices.iter().flat_map(|(&bucket, index)| {
-            // Build merged view: start with sorted entries, apply updates
-            let mut merged: BTreeMap<[u8; 9], Option<IndexEntry>> = BTreeMap::new();
-
-            for entry in &index.entries {
-                merged.insert(entry.key, Some(entry.clone()));
-            }
-
-            for update in index.update_section.all_entries() {
-                if update.status == UpdateStatus::Delete {
-                    merged.insert(update.ekey, None);
-                } else {
-                    merged.insert(update.ekey, Some(update.to_index_entry()));
-                }
-            }
-
-            merged
-                .into_values()
-                .flatten()
+            Self::merged_entries(index)
+                .into_iter()
                 .map(move |entry| (bucket, entry))
         })
     }
@@ -948,13 +1337,10 @@ impl IndexManager {
         let key_bytes = key.as_bytes();
         let index_id = Self::get_bucket_index(key_bytes);
 
-        let mut truncated_key = [0u8; 9];
-        truncated_key[..9.min(key_bytes.len())]
-            .copy_from_slice(&key_bytes[..9.min(key_bytes.len())]);
-
         if let Some(index) = self.indices.get_mut(&index_id) {
-            let tombstone = UpdateEntry::new(
-                truncated_key,
+            let tombstone = Self::new_update(
+                index,
+                key_bytes,
                 entry.archive_location,
                 entry.size,
                 UpdateStatus::Delete,
@@ -990,13 +1376,10 @@ impl IndexManager {
         let key_bytes = key.as_bytes();
         let index_id = Self::get_bucket_index(key_bytes);
 
-        let mut truncated_key = [0u8; 9];
-        truncated_key[..9.min(key_bytes.len())]
-            .copy_from_slice(&key_bytes[..9.min(key_bytes.len())]);
-
         if let Some(index) = self.indices.get_mut(&index_id) {
-            let entry = UpdateEntry::new(
-                truncated_key,
+            let entry = Self::new_update(
+                index,
+                key_bytes,
                 ArchiveLocation {
                     archive_id,
                     archive_offset,
@@ -1024,13 +1407,9 @@ impl IndexManager {
         let key_bytes = key.as_bytes();
         let index_id = Self::get_bucket_index(key_bytes);
 
-        let mut truncated_key = [0u8; 9];
-        truncated_key[..9.min(key_bytes.len())]
-            .copy_from_slice(&key_bytes[..9.min(key_bytes.len())]);
-
         if let Some(index) = self.indices.get_mut(&index_id) {
             let update =
-                UpdateEntry::new(truncated_key, entry.archive_location, entry.size, status);
+                Self::new_update(index, key_bytes, entry.archive_location, entry.size, status);
             return index.update_section.append(update);
         }
 
@@ -1042,12 +1421,6 @@ impl IndexManager {
     /// Merge-sorts update entries into the sorted section, handling
     /// deletions and overwrites. Writes the result atomically to disk.
     pub fn flush_updates_for_bucket(&mut self, bucket: u8) -> Result<()> {
-        // Compute path before taking mutable borrow on indices
-        let path = {
-            let filename = Self::generate_index_filename(bucket, 1);
-            self.base_path.join(filename)
-        };
-
         let Some(index) = self.indices.get_mut(&bucket) else {
             return Ok(());
         };
@@ -1056,10 +1429,45 @@ impl IndexManager {
             return Ok(());
         }
 
+        let merged = Self::merged_entries(index);
+
+        debug!(
+            "Flushed bucket {:02x}: {} sorted + {} updates -> {} merged",
+            bucket,
+            index.entries.len(),
+            index.update_section.entry_count(),
+            merged.len()
+        );
+
+        index.entries = merged;
+        index.update_section.clear();
+
+        // Save to disk as a new version
+        let existing = self
+            .index_files_on_disk()?
+            .remove(&bucket)
+            .unwrap_or_default();
+        self.save_bucket(bucket, &existing)
+    }
+
+    /// Sorted section of `index` after applying its update section.
+    ///
+    /// Deduplicates updates (latest wins), drops tombstoned keys and keeps
+    /// the result sorted by key. In 16-byte buckets, update entries read
+    /// from disk only know 9 key bytes; they take the full key of the
+    /// sorted entry they replace.
+    fn merged_entries(index: &IndexFile) -> Vec<IndexEntry> {
+        let wide = index.header.key_size == 16;
+
         // Collect and deduplicate updates (latest entry wins via BTreeMap insert)
-        let mut updates: BTreeMap<[u8; 9], UpdateEntry> = BTreeMap::new();
+        let mut updates: BTreeMap<Vec<u8>, UpdateEntry> = BTreeMap::new();
         for entry in index.update_section.all_entries() {
-            updates.insert(entry.ekey, entry.clone());
+            let mut entry = entry.clone();
+            if wide && entry.full_key.is_none() {
+                entry.full_key = Self::resolve_full_key(index, &entry.ekey);
+            }
+            let key = entry.to_index_entry().key_bytes().to_vec();
+            updates.insert(key, entry);
         }
 
         // Merge-sort: walk sorted section and updates together
@@ -1068,13 +1476,17 @@ impl IndexManager {
 
         for (update_key, update_entry) in &updates {
             // Add sorted entries that come before this update key
-            while sorted_idx < index.entries.len() && index.entries[sorted_idx].key < *update_key {
+            while sorted_idx < index.entries.len()
+                && index.entries[sorted_idx].key_bytes() < update_key.as_slice()
+            {
                 merged.push(index.entries[sorted_idx].clone());
                 sorted_idx += 1;
             }
 
             // Skip matching sorted entry (update takes precedence)
-            if sorted_idx < index.entries.len() && index.entries[sorted_idx].key == *update_key {
+            if sorted_idx < index.entries.len()
+                && index.entries[sorted_idx].key_bytes() == update_key.as_slice()
+            {
                 sorted_idx += 1;
             }
 
@@ -1085,26 +1497,26 @@ impl IndexManager {
         }
 
         // Add remaining sorted entries
-        while sorted_idx < index.entries.len() {
-            merged.push(index.entries[sorted_idx].clone());
-            sorted_idx += 1;
-        }
-
-        debug!(
-            "Flushed bucket {:02x}: {} sorted + {} updates -> {} merged",
-            bucket,
-            index.entries.len(),
-            updates.len(),
-            merged.len()
-        );
-
-        index.entries = merged;
-        index.update_section.clear();
-
-        // Save to disk with atomic replacement
-        Self::save_index(bucket, index, &path)?;
+        merged.extend_from_slice(&index.entries[sorted_idx..]);
+        merged
+    }
 
-        Ok(())
+    /// Full key of the sorted entry with the 9-byte `prefix`, if any.
+    fn resolve_full_key(index: &IndexFile, prefix: &[u8; 9]) -> Option<[u8; 16]> {
+        let start = index.entries.partition_point(|e| e.key < *prefix);
+        let full_key = index
+            .entries
+            .get(start)
+            .filter(|e| e.key == *prefix)
+            .and_then(|e| e.full_key);
+        if full_key.is_none() {
+            warn!(
+                "Update entry {} in 16-byte index {:02x} has no full key; storing it zero-padded",
+                hex::encode(prefix),
+                index.header.bucket
+            );
+        }
+        full_key
     }
 
     /// Flush all update sections across all buckets.
@@ -1116,6 +1528,80 @@ impl IndexManager {
         Ok(())
     }
 
+    /// Report how full each bucket's update section is.
+    ///
+    /// `add_entry` flushes a bucket when its update section is full, so a
+    /// bucket near 100% will merge on the next write. Buckets are listed in
+    /// ascending order.
+    pub fn compaction_stats(&self) -> Vec<BucketCompactionStat> {
+        self.indices
+            .iter()
+            .map(|(&bucket, index)| {
+                let section = &index.update_section;
+                let capacity = section.capacity_pages() * ENTRIES_PER_PAGE;
+                let pending_updates = section.entry_count();
+
+                // The latest update per key decides the merge result
+                let mut latest: BTreeMap<[u8; 9], UpdateStatus> = BTreeMap::new();
+                for entry in section.all_entries() {
+                    latest.insert(entry.ekey, entry.status);
+                }
+                let mut added = 0;
+                let mut deleted = 0;
+                for (key, status) in &latest {
+                    let in_sorted = index.entries.binary_search_by_key(key, |e| e.key).is_ok();
+                    match (in_sorted, *status == UpdateStatus::Delete) {
+                        (true, true) => deleted += 1,
+                        (false, false) => added += 1,
+                        _ => {}
+                    }
+                }
+
+                #[allow(clippy::cast_precision_loss)]
+                let fill_percent = if capacity == 0 {
+                    0.0
+                } else {
+                    pending_updates as f32 * 100.0 / capacity as f32
+                };
+
+                BucketCompactionStat {
+                    bucket,
+                    sorted_count: index.entries.len(),
+                    pending_updates,
+                    pending_deletes: deleted,
+                    merged_count: index.entries.len() + added - deleted,
+                    update_capacity: capacity,
+                    fill_percent,
+                }
+            })
+            .collect()
+    }
+
+    /// Flush the buckets whose update section is more than `threshold`
+    /// percent full.
+    ///
+    /// `threshold` uses the same scale as
+    /// [`BucketCompactionStat::fill_percent`] (0 to 100). Returns the
+    /// flushed buckets. Calling this while idle keeps the merge off the
+    /// write path of [`add_entry`](Self::add_entry).
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a flushed index cannot be written; buckets flushed
+    /// before the failure stay flushed.
+    pub fn compact_if_needed(&mut self, threshold: f32) -> Result<Vec<u8>> {
+        let buckets: Vec<u8> = self
+            .compaction_stats()
+            .into_iter()
+            .filter(|stat| stat.pending_updates > 0 && stat.fill_percent > threshold)
+            .map(|stat| stat.bucket)
+            .collect();
+        for &bucket in &buckets {
+            self.flush_updates_for_bucket(bucket)?;
+        }
+        Ok(buckets)
+    }
+
     /// Clear all entries from all indices.
     ///
     /// Clears both sorted and update sections.
@@ -1177,6 +1663,26 @@ pub struct IndexStats {
     pub total_entries: usize,
 }
 
+/// Update section fill of one index bucket, see
+/// [`IndexManager::compaction_stats`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketCompactionStat {
+    /// Bucket ID (0x00-0x0F)
+    pub bucket: u8,
+    /// Entries in the sorted section
+    pub sorted_count: usize,
+    /// Entries in the update section, including overwrites and tombstones
+    pub pending_updates: usize,
+    /// Sorted entries that delete tombstones will remove on merge
+    pub pending_deletes: usize,
+    /// Sorted section size after merging the update section
+    pub merged_count: usize,
+    /// Update section capacity in entries
+    pub update_capacity: usize,
+    /// Update section fill, 0 to 100
+    pub fill_percent: f32,
+}
+
 #[cfg(test)]
 #[allow(clippy::expect_used)]
 mod tests {
@@ -1719,6 +2225,436 @@ mod tests {
         assert_eq!(entry2.archive_offset(), 0x2000);
         assert_eq!(entry2.size, 2048);
     }
+
+    #[test]
+    fn test_packed_entry_16_byte_keys() {
+        let key: [u8; 16] = std::array::from_fn(|i| 0xA0 + i as u8);
+        let entry = IndexEntry::new_full(key, 0x0123, 0x0345_6789, 0x0004_0000);
+
+        let packed = entry.to_packed(16, 30, 32);
+        assert_eq!(packed.len(), 25);
+        assert_eq!(packed[..16], key);
+        assert_eq!(packed[21..25], 0x0004_0000u32.to_le_bytes());
+
+        let parsed = IndexEntry::from_packed(&packed, 16, 30, 32).expect("16-byte entry");
+        assert_eq!(parsed, entry);
+        assert_eq!(parsed.key_bytes(), key);
+
+        // The 18-byte layout reads key bytes as the location
+        let misread = IndexEntry::from_packed(&packed, 9, 30, 32).expect("18-byte entry");
+        assert_ne!(misread.archive_location, entry.archive_location);
+
+        // 9-byte entries keep the 18-byte layout
+        let short = IndexEntry::new(entry.key, 1, 2, 3);
+        assert_eq!(short.to_packed(9, 30, 32).len(), 18);
+        assert_eq!(short.key_bytes(), entry.key);
+
+        assert!(IndexEntry::from_packed(&packed, 12, 30, 32).is_err());
+        assert!(IndexEntry::from_packed(&packed[..24], 16, 30, 32).is_err());
+    }
+
+    #[test]
+    fn test_idx_journal_16_byte_keys_round_trip() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        assert!(IndexManager::with_key_size(temp_dir.path(), 12).is_err());
+        let mut manager =
+            IndexManager::with_key_size(temp_dir.path(), 16).expect("16-byte keys are valid");
+
+        // Same 9-byte prefix, so the same bucket and update-section key
+        let ekey = |tail: u8| {
+            let mut bytes = [0x5Au8; 16];
+            bytes[15] = tail;
+            EncodingKey::from_bytes(bytes)
+        };
+        let bucket = IndexManager::bucket_for_key(&ekey(1));
+        manager
+            .add_entry(&ekey(1), 1, 0x1000, 1024)
+            .expect("add_entry should succeed");
+        manager
+            .add_entry(&ekey(2), 2, 0x2000, 2048)
+            .expect("add_entry should succeed");
+        assert_eq!(manager.lookup(&ekey(1)).expect("entry 1").archive_id(), 1);
+        assert_eq!(manager.lookup(&ekey(2)).expect("entry 2").archive_id(), 2);
+        assert!(manager.lookup(&ekey(3)).is_none());
+
+        manager.save_all().expect("save_all should succeed");
+
+        // The file holds two 25-byte entries and no update section
+        let path = temp_dir
+            .path()
+            .join(IndexManager::generate_index_filename(bucket, 1));
+        let data = std::fs::read(&path).expect("index file");
+        assert_eq!(data[0x08 + 6], 16, "ekey_length in header");
+        assert_eq!(
+            u32::from_le_bytes([data[0x20], data[0x21], data[0x22], data[0x23]]),
+            50
+        );
+        assert_eq!(data.len(), 0x28 + 50);
+
+        // A fresh 9-byte manager follows the header
+        let mut reader = IndexManager::new(temp_dir.path());
+        reader
+            .load_index(bucket, &path)
+            .expect("load_index should succeed");
+        assert_eq!(reader.entry_count(), 2);
+        for (tail, archive_id, size) in [(1, 1, 1024), (2, 2, 2048)] {
+            let entry = reader.lookup(&ekey(tail)).expect("entry should exist");
+            assert_eq!(entry.archive_id(), archive_id);
+            assert_eq!(entry.size, size);
+            assert_eq!(entry.key_bytes(), ekey(tail).as_bytes());
+        }
+        assert!(reader.lookup(&ekey(3)).is_none());
+
+        // Updates to a loaded bucket keep full keys through a flush
+        assert!(reader.update_entry(&ekey(2), 3, 0x3000, 4096));
+        reader
+            .flush_updates_for_bucket(bucket)
+            .expect("flush should succeed");
+        assert_eq!(reader.entry_count(), 2);
+        assert_eq!(reader.lookup(&ekey(1)).expect("entry 1").archive_id(), 1);
+        assert_eq!(reader.lookup(&ekey(2)).expect("entry 2").archive_id(), 3);
+    }
+
+    #[test]
+    fn test_compaction_stats_account_for_tombstones() {
+        let temp_dir = tempfile::tempdir().expect("Operation should succeed");
+        let mut manager = IndexManager::new(temp_dir.path());
+
+        // Keys in the same bucket: equal bytes cancel out in the bucket XOR
+        let ekey = |n: u8| {
+            let mut bytes = [0u8; 16];
+            bytes[0] = 0x11;
+            bytes[1] = n;
+            bytes[2] = n;
+            EncodingKey::from_bytes(bytes)
+        };
+        let bucket = IndexManager::bucket_for_key(&ekey(0));
+
+        for last in 0..3 {
+            manager
+                .add_entry(&ekey(last), 1, u32::from(last) * 0x100, 0x100)
+                .expect("Operation should succeed");
+        }
+        manager
+            .flush_updates_for_bucket(bucket)
+            .expect("Operation should succeed");
+
+        // One delete, one overwrite, one new key; the new key is then deleted
+        assert!(manager.remove_entry(&ekey(0)));
+        assert!(manager.update_entry(&ekey(1), 2, 0, 0x100));
+        manager
+            .add_entry(&ekey(3), 1, 0x300, 0x100)
+            .expect("Operation should succeed");
+        assert!(manager.remove_entry(&ekey(3)));
+
+        let stats = manager.compaction_stats();
+        assert_eq!(stats.len(), 1);
+        let stat = &stats[0];
+        assert_eq!(stat.bucket, bucket);
+        assert_eq!(stat.sorted_count, 3);
+        assert_eq!(stat.pending_updates, 4);
+        assert_eq!(stat.pending_deletes, 1);
+        assert_eq!(stat.merged_count, 2);
+        assert_eq!(stat.update_capacity, 60 * 21);
+        assert!((stat.fill_percent - 400.0 / 1260.0).abs() < 1e-4);
+
+        manager
+            .flush_updates_for_bucket(bucket)
+            .expect("Operation should succeed");
+        assert_eq!(manager.bucket_entry_count(bucket), stat.merged_count);
+    }
+
+    #[test]
+    fn test_compact_if_needed_flushes_full_buckets_only() {
+        let temp_dir = tempfile::tempdir().expect("Operation should succeed");
+        let mut manager = IndexManager::new(temp_dir.path());
+
+        let ekey = |first: u8| {
+            let mut bytes = [0u8; 16];
+            bytes[0] = first;
+            EncodingKey::from_bytes(bytes)
+        };
+        let busy = ekey(0x11);
+        let quiet = ekey(0x12);
+        let busy_bucket = IndexManager::bucket_for_key(&busy);
+        let quiet_bucket = IndexManager::bucket_for_key(&quiet);
+        assert_ne!(busy_bucket, quiet_bucket);
+
+        manager
+            .add_entry(&quiet, 1, 0, 0x100)
+            .expect("Operation should succeed");
+        // Fill half of the busy bucket's update section with overwrites
+        for offset in 0..630 {
+            manager
+                .add_entry(&busy, 1, offset, 0x100)
+                .expect("Operation should succeed");
+        }
+
+        let flushed = manager
+            .compact_if_needed(25.0)
+            .expect("Operation should succeed");
+        assert_eq!(flushed, [busy_bucket]);
+
+        let stats = manager.compaction_stats();
+        let stat = |bucket| {
+            stats
+                .iter()
+                .find(|stat| stat.bucket == bucket)
+                .expect("Operation should succeed")
+        };
+        assert_eq!(stat(busy_bucket).pending_updates, 0);
+        assert_eq!(stat(busy_bucket).sorted_count, 1);
+        assert_eq!(stat(quiet_bucket).pending_updates, 1);
+        assert!(manager.has_entry(&busy));
+
+        // Nothing left above the threshold
+        assert!(
+            manager
+                .compact_if_needed(25.0)
+                .expect("Operation should succeed")
+                .is_empty()
+        );
+    }
+
+    /// Versions of `bucket`'s index files in `dir`, ascending
+    fn versions_on_disk(dir: &Path, bucket: u8) -> Vec<u32> {
+        let mut versions: Vec<u32> = std::fs::read_dir(dir)
+            .expect("read_dir")
+            .filter_map(|entry| {
+                let name = entry.expect("dir entry").file_name();
+                IndexManager::parse_index_filename(name.to_str()?)
+            })
+            .filter(|&(b, _)| b == bucket)
+            .map(|(_, version)| version)
+            .collect();
+        versions.sort_unstable();
+        versions
+    }
+
+    #[tokio::test]
+    async fn test_save_all_increments_versions() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let ekey1 = create_test_ekey_1();
+        let bucket = IndexManager::bucket_for_key(&ekey1);
+        let mut manager = IndexManager::new(temp_dir.path());
+        manager
+            .add_entry(&ekey1, 1, 0x1000, 1024)
+            .expect("add_entry should succeed");
+
+        // Each save writes the next version and keeps one previous file
+        for version in 1..=4 {
+            manager.save_all().expect("save_all should succeed");
+            assert_eq!(manager.bucket_version(bucket), Some(version));
+        }
+        assert_eq!(versions_on_disk(temp_dir.path(), bucket), [3, 4]);
+
+        // Flushing the update section also writes a new version
+        assert!(manager.update_entry(&ekey1, 2, 0x2000, 2048));
+        manager
+            .flush_updates_for_bucket(bucket)
+            .expect("flush should succeed");
+        assert_eq!(versions_on_disk(temp_dir.path(), bucket), [4, 5]);
+
+        // A fresh load picks the newest version and continues from it
+        let mut reader = IndexManager::new(temp_dir.path());
+        reader.load_all().await.expect("load_all should succeed");
+        assert_eq!(reader.bucket_version(bucket), Some(5));
+        assert_eq!(reader.lookup(&ekey1).expect("entry").archive_id(), 2);
+        reader.save_all().expect("save_all should succeed");
+        assert_eq!(versions_on_disk(temp_dir.path(), bucket), [5, 6]);
+    }
+
+    #[tokio::test]
+    async fn test_publish_entries_saves_each_bucket_once() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let mut manager = IndexManager::new(temp_dir.path());
+
+        // More bucket 0 keys than one update section holds; the repeated
+        // bytes cancel out in the bucket hash, and all-zero keys mark
+        // empty entries
+        let keys: Vec<EncodingKey> = (1..=1500u16)
+            .map(|i| {
+                let [hi, lo] = i.to_be_bytes();
+                let mut key = [0u8; 16];
+                key[..4].copy_from_slice(&[hi, hi, lo, lo]);
+                EncodingKey::from_bytes(key)
+            })
+            .collect();
+        assert!(
+            keys.iter()
+                .all(|key| IndexManager::bucket_for_key(key) == 0)
+        );
+        let entries: Vec<_> = keys
+            .iter()
+            .zip(0u32..)
+            .map(|(&key, i)| (key, 0, i * 64, 64))
+            .collect();
+
+        manager
+            .publish_entries(&entries)
+            .expect("publish should succeed");
+        assert_eq!(versions_on_disk(temp_dir.path(), 0), [1]);
+
+        let mut reader = IndexManager::new(temp_dir.path());
+        reader.load_all().await.expect("load_all should succeed");
+        for (key, _, offset, _) in &entries {
+            let entry = reader.lookup(key).expect("published entry");
+            assert_eq!(entry.archive_offset(), *offset);
+        }
+    }
+
+    #[test]
+    fn test_save_all_retained_versions() {
+        let ekey1 = create_test_ekey_1();
+        let bucket = IndexManager::bucket_for_key(&ekey1);
+        for (retained, expected) in [(0, &[5][..]), (2, &[3, 4, 5][..])] {
+            let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+            let mut manager = IndexManager::new(temp_dir.path()).with_retained_versions(retained);
+            manager
+                .add_entry(&ekey1, 1, 0x1000, 1024)
+                .expect("add_entry should succeed");
+            for _ in 0..5 {
+                manager.save_all().expect("save_all should succeed");
+            }
+            assert_eq!(versions_on_disk(temp_dir.path(), bucket), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_all_prefers_newest_valid_version() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let ekey1 = create_test_ekey_1();
+        let bucket = IndexManager::bucket_for_key(&ekey1);
+        let mut manager = IndexManager::new(temp_dir.path()).with_retained_versions(5);
+        manager
+            .add_entry(&ekey1, 1, 0x1000, 1024)
+            .expect("add_entry should succeed");
+        manager.save_all().expect("save_all should succeed");
+
+        // A higher version written by another process is loaded and
+        // saves continue above it
+        let file = |version| {
+            temp_dir
+                .path()
+                .join(IndexManager::generate_index_filename(bucket, version))
+        };
+        std::fs::copy(file(1), file(0x10)).expect("copy index");
+        let mut reader = IndexManager::new(temp_dir.path());
+        reader.load_all().await.expect("load_all should succeed");
+        assert_eq!(reader.bucket_version(bucket), Some(0x10));
+
+        assert!(manager.update_entry(&ekey1, 7, 0x7000, 1024));
+        manager.save_all().expect("save_all should succeed");
+        assert_eq!(manager.bucket_version(bucket), Some(0x11));
+
+        // A newest file failing its header hash falls back to the previous one
+        let mut data = std::fs::read(file(0x11)).expect("index file");
+        data[0x08 + 4] ^= 0xff;
+        std::fs::write(file(0x12), &data).expect("write index");
+        let mut reader = IndexManager::new(temp_dir.path());
+        reader.load_all().await.expect("load_all should succeed");
+        assert_eq!(reader.bucket_version(bucket), Some(0x11));
+        assert_eq!(reader.lookup(&ekey1).expect("entry").archive_id(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_load_all_prefers_current_group_index() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let ekey1 = create_test_ekey_1();
+        let ekey2 = create_test_ekey_2();
+        let mut manager = IndexManager::new(temp_dir.path());
+        manager
+            .add_entry(&ekey1, 1, 0x1000, 1024)
+            .expect("add_entry should succeed");
+        manager
+            .add_entry(&ekey2, 2, 0x2000, 2048)
+            .expect("add_entry should succeed");
+        manager.save_all().expect("save_all should succeed");
+        manager
+            .write_group_index()
+            .expect("write_group_index should succeed");
+
+        let mut reader = IndexManager::new(temp_dir.path());
+        reader.load_all().await.expect("load_all should succeed");
+        assert!(reader.loaded_from_group_index());
+        assert_eq!(reader.entry_count(), 2);
+        assert_eq!(reader.lookup(&ekey2).expect("entry").archive_id(), 2);
+        for bucket in reader.loaded_buckets() {
+            assert_eq!(
+                reader.bucket_version(bucket),
+                manager.bucket_version(bucket)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_all_falls_back_from_stale_group_index() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let ekey1 = create_test_ekey_1();
+        let ekey3 = create_test_ekey_3();
+        let mut manager = IndexManager::new(temp_dir.path());
+        manager
+            .add_entry(&ekey1, 1, 0x1000, 1024)
+            .expect("add_entry should succeed");
+        manager.save_all().expect("save_all should succeed");
+        manager
+            .write_group_index()
+            .expect("write_group_index should succeed");
+
+        // Saving again bumps the idx versions past the group index
+        assert!(manager.update_entry(&ekey1, 7, 0x7000, 1024));
+        manager
+            .add_entry(&ekey3, 3, 0x3000, 512)
+            .expect("add_entry should succeed");
+        manager.save_all().expect("save_all should succeed");
+
+        let mut reader = IndexManager::new(temp_dir.path());
+        reader.load_all().await.expect("load_all should succeed");
+        assert!(!reader.loaded_from_group_index());
+        assert_eq!(reader.lookup(&ekey1).expect("entry").archive_id(), 7);
+        assert!(reader.has_entry(&ekey3));
+
+        // A damaged group index is ignored as well
+        manager
+            .write_group_index()
+            .expect("write_group_index should succeed");
+        let path = temp_dir.path().join(GROUP_INDEX_FILE);
+        let mut data = std::fs::read(&path).expect("group index");
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        std::fs::write(&path, &data).expect("write group index");
+        let mut reader = IndexManager::new(temp_dir.path());
+        reader.load_all().await.expect("load_all should succeed");
+        assert!(!reader.loaded_from_group_index());
+        assert_eq!(reader.entry_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_load_all_ignores_group_index_of_other_build() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let ekey1 = create_test_ekey_1();
+        let mut manager = IndexManager::new(temp_dir.path());
+        manager.set_build_config(Some([1; 16]));
+        manager
+            .add_entry(&ekey1, 1, 0x1000, 1024)
+            .expect("add_entry should succeed");
+        manager.save_all().expect("save_all should succeed");
+        manager
+            .write_group_index()
+            .expect("write_group_index should succeed");
+
+        let mut reader = IndexManager::new(temp_dir.path());
+        reader.set_build_config(Some([1; 16]));
+        reader.load_all().await.expect("load_all should succeed");
+        assert!(reader.loaded_from_group_index());
+
+        // Same idx versions, but a different build
+        let mut reader = IndexManager::new(temp_dir.path());
+        reader.set_build_config(Some([2; 16]));
+        reader.load_all().await.expect("load_all should succeed");
+        assert!(!reader.loaded_from_group_index());
+        assert!(reader.has_entry(&ekey1));
+    }
 }
 
 // Validation implementations for round-trip testing
@@ -1839,6 +2775,7 @@ mod validation_impls {
                     archive_offset: 0x1234_5678 & 0x3FFF_FFFF, // 30-bit max
                 },
                 size: 0x8765_4321,
+                full_key: None,
             }
         }
 
@@ -1852,6 +2789,7 @@ mod validation_impls {
                         archive_offset: 0,
                     },
                     size: 0,
+                    full_key: None,
                 },
                 // Maximum archive ID and offset (10-bit and 30-bit limits)
                 Self {
@@ -1861,6 +2799,7 @@ mod validation_impls {
                         archive_offset: 0x3FFF_FFFF, // Maximum 30-bit value
                     },
                     size: u32::MAX,
+                    full_key: None,
                 },
                 // Boundary values for archive ID (test bit packing)
                 Self {
@@ -1870,6 +2809,7 @@ mod validation_impls {
                         archive_offset: 0x1000_0000, // 2^28
                     },
                     size: 0x1234_5678,
+                    full_key: None,
                 },
                 // Test edge case for bit boundaries
                 Self {
@@ -1879,6 +2819,7 @@ mod validation_impls {
                         archive_offset: 0x2AAA_AAAA, // Pattern test
                     },
                     size: 0x5555_AAAA,
+                    full_key: None,
                 },
                 // Standard realistic values
                 Self {
@@ -1888,6 +2829,7 @@ mod validation_impls {
                         archive_offset: 0x0010_0000, // 1MB offset
                     },
                     size: 65536, // 64KB file
+                    full_key: None,
                 },
             ]
         }
@@ -74,6 +74,10 @@ impl UpdateStatus {
 /// [0x16] status      (1 byte)
 /// [0x17] padding     (1 byte)
 /// ```
+///
+/// The on-disk entry holds 9 key bytes even in buckets with 16-byte keys.
+/// Entries created for such a bucket carry the full key in memory so it
+/// survives the merge into the sorted section.
 #[derive(Debug, Clone)]
 pub struct UpdateEntry {
     /// Hash guard: `hashlittle(bytes[4..23], 0) | 0x80000000`.
@@ -86,6 +90,8 @@ pub struct UpdateEntry {
     pub encoded_size: u32,
     /// Status byte.
     pub status: UpdateStatus,
+    /// Full 16-byte key, if known. Not serialized.
+    pub full_key: Option<[u8; 16]>,
 }
 
 impl UpdateEntry {
@@ -102,6 +108,7 @@ impl UpdateEntry {
             archive_location,
             encoded_size,
             status,
+            full_key: None,
         };
         // Serialize with hash_guard=0, compute hash from bytes[4..23],
         // then set the correct hash_guard. Since hash_guard is at bytes[0..4],
@@ -111,6 +118,29 @@ impl UpdateEntry {
         entry
     }
 
+    /// Attach the full 16-byte key of an entry in a 16-byte bucket.
+    ///
+    /// The key must start with [`Self::ekey`]; the hash guard is unchanged
+    /// since only 9 key bytes are stored.
+    #[must_use]
+    pub const fn with_full_key(mut self, key: [u8; 16]) -> Self {
+        self.full_key = Some(key);
+        self
+    }
+
+    /// Check whether this entry is for `key`.
+    ///
+    /// Compares the 9-byte prefix, and the full key when both sides have
+    /// 16 bytes.
+    pub fn matches(&self, key: &[u8]) -> bool {
+        key.len() >= 9
+            && self.ekey == key[..9]
+            && match (self.full_key, key.len()) {
+                (Some(full_key), 16) => full_key == key,
+                _ => true,
+            }
+    }
+
     /// Compute the hash guard from serialized entry bytes.
     ///
     /// Hashes bytes 4 through 22 inclusive (19 bytes: ekey + offset + size + status).
@@ -164,17 +194,20 @@ impl UpdateEntry {
             },
             encoded_size,
             status,
+            full_key: None,
         }
     }
 
     /// Convert to an `IndexEntry` (for merge into sorted section).
     pub fn to_index_entry(&self) -> IndexEntry {
-        IndexEntry::new(
+        let mut entry = IndexEntry::new(
             self.ekey,
             self.archive_location.archive_id,
             self.archive_location.archive_offset,
             self.encoded_size,
-        )
+        );
+        entry.full_key = self.full_key;
+        entry
     }
 
     /// Check if the hash guard matches the entry contents.
@@ -388,6 +421,18 @@ impl UpdateSection {
         None
     }
 
+    /// Search for a key of any supported width (linear scan, newest first).
+    ///
+    /// Like [`Self::search`], but entries that know their full 16-byte key
+    /// only match a 16-byte `key` that is equal, see [`UpdateEntry::matches`].
+    pub fn find(&self, key: &[u8]) -> Option<&UpdateEntry> {
+        self.pages
+            .iter()
+            .rev()
+            .flat_map(|page| page.entries().iter().rev())
+            .find(|entry| entry.matches(key))
+    }
+
     /// Iterate all entries across all pages (oldest first).
     pub fn all_entries(&self) -> impl Iterator<Item = &UpdateEntry> {
         self.pages.iter().flat_map(UpdatePage::entries)
@@ -776,4 +821,35 @@ mod tests {
         assert_eq!(index_entry.archive_offset(), 0x1234);
         assert_eq!(index_entry.size, 9999);
     }
+
+    #[test]
+    fn test_find_compares_full_keys() {
+        let mut full = [0x11u8; 16];
+        let location = ArchiveLocation {
+            archive_id: 1,
+            archive_offset: 0x40,
+        };
+        let mut section = UpdateSection::new();
+        section.append(
+            UpdateEntry::new([0x11; 9], location, 100, UpdateStatus::Normal).with_full_key(full),
+        );
+
+        // Same 9-byte prefix, different tail
+        let mut other = full;
+        other[15] = 0x22;
+        assert!(section.find(&full).is_some());
+        assert!(section.find(&other).is_none());
+        assert!(section.find(&full[..9]).is_some());
+        assert!(section.search(&[0x11; 9]).is_some());
+
+        // Hash guard only covers the stored bytes
+        let entry = section.find(&full).expect("entry");
+        assert!(entry.validate_hash_guard());
+        assert_eq!(entry.to_index_entry().full_key, Some(full));
+
+        // Entries read from disk only know the prefix
+        let loaded = UpdateSection::from_bytes(&section.to_bytes());
+        full[15] = 0x33;
+        assert!(loaded.find(&full).is_some());
+    }
 }
@@ -7,15 +7,31 @@
 //! and should be handled separately where needed (e.g., browse commands).
 
 use crate::{
-    Result, StorageError, index::IndexManager, resolver::ContentResolver,
-    storage::archive_file::ArchiveManager,
+    Result, StorageError,
+    archive_report::ArchiveReport,
+    build_info::BuildInfoFile,
+    chunk_cache::{ChunkCache, DEFAULT_CHUNK_CACHE_BYTES},
+    coverage::CoverageReport,
+    index::{IndexEntry, IndexManager},
+    repair::{RepairFailure, RepairReport},
+    resolver::{ContentResolver, ResolutionMetrics, ResolutionSource},
+    resume::{ResumeFailure, ResumeReport, blte_is_intact},
+    storage::{
+        archive_file::ArchiveManager,
+        compression::{ContentCompressor, Passthrough},
+        local_header::{LOCAL_HEADER_SIZE, LocalHeader},
+    },
+    transaction::WriteTransaction,
 };
-use cascette_crypto::{ContentKey, EncodingKey};
+use binrw::BinRead;
+use cascette_crypto::{ContentKey, EncodingKey, TactKeyStore};
 use cascette_formats::CascFormat;
-use cascette_formats::blte::BlteFile;
-use std::path::PathBuf;
-use std::sync::Arc;
-use tokio::sync::RwLock as AsyncRwLock;
+use cascette_formats::blte::{BlteFile, BlteHeader, ChunkData, CompressionMode};
+use cascette_formats::download::DownloadManifest;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::{Mutex as AsyncMutex, RwLock as AsyncRwLock};
 use tracing::{debug, info, warn};
 
 /// Represents a game installation with its local CASC storage
@@ -35,6 +51,12 @@ pub struct Installation {
     resolver: Arc<ContentResolver>,
     /// Simple in-memory cache for performance optimization
     cache: Arc<AsyncRwLock<dashmap::DashMap<String, Vec<u8>>>>,
+    /// Decompressed chunks of range reads, capped by bytes
+    chunk_cache: Mutex<ChunkCache>,
+    /// Build metadata written out as `.build.info`
+    build_info: Arc<AsyncRwLock<Option<BuildInfoFile>>>,
+    /// Serializes writes, so an open transaction owns the archive tails
+    writer: Arc<AsyncMutex<()>>,
 }
 
 impl Installation {
@@ -44,6 +66,18 @@ impl Installation {
     ///
     /// Returns error if directory cannot be created or components cannot be initialized
     pub fn open(path: PathBuf) -> Result<Self> {
+        Self::open_with_compressor(path, Arc::new(Passthrough))
+    }
+
+    /// Open an installation whose writes are compressed with `compressor`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if directory cannot be created or components cannot be initialized
+    pub fn open_with_compressor(
+        path: PathBuf,
+        compressor: Arc<dyn ContentCompressor>,
+    ) -> Result<Self> {
         // Ensure installation directory exists
         if !path.exists() {
             info!("Creating installation directory: {}", path.display());
@@ -64,8 +98,10 @@ impl Installation {
         // Initialize managers for local CASC storage
         // Both .idx (index) and .data (archive) files live in Data/data/
         let index_manager = Arc::new(AsyncRwLock::new(IndexManager::new(&data_path)));
-        let archive_manager = Arc::new(AsyncRwLock::new(ArchiveManager::new(&data_path)));
-        let resolver = Arc::new(ContentResolver::new());
+        let archive_manager = Arc::new(AsyncRwLock::new(ArchiveManager::with_compressor(
+            &data_path, compressor,
+        )));
+        let resolver = Arc::new(ContentResolver::new().with_reverse_index_dir(&data_path));
 
         // Initialize simple in-memory cache for performance
         let cache = Arc::new(AsyncRwLock::new(dashmap::DashMap::new()));
@@ -78,9 +114,40 @@ impl Installation {
             archive_manager,
             resolver,
             cache,
+            chunk_cache: Mutex::new(ChunkCache::new(DEFAULT_CHUNK_CACHE_BYTES)),
+            build_info: Arc::new(AsyncRwLock::new(None)),
+            writer: Arc::new(AsyncMutex::new(())),
         })
     }
 
+    /// Limit the decompressed chunks kept for range reads to `bytes`
+    ///
+    /// Defaults to [`DEFAULT_CHUNK_CACHE_BYTES`]. Zero disables the chunk
+    /// cache.
+    #[must_use]
+    pub fn with_chunk_cache_bytes(mut self, bytes: usize) -> Self {
+        self.chunk_cache = Mutex::new(ChunkCache::new(bytes));
+        self
+    }
+
+    /// Replace the content resolver
+    ///
+    /// Use this to install a resolver built with
+    /// `ContentResolver::with_cdn_fallback`. The root and encoding files
+    /// are held by the resolver, so load them after replacing it. The
+    /// resolver persists its reverse index in the data directory.
+    #[must_use]
+    pub fn with_resolver(mut self, resolver: ContentResolver) -> Self {
+        let data_path = self.path.join(crate::DATA_DIR);
+        self.resolver = Arc::new(resolver.with_reverse_index_dir(data_path));
+        self
+    }
+
+    /// Get counts of file reads by where they were served from
+    pub fn resolution_metrics(&self) -> ResolutionMetrics {
+        self.resolver.metrics()
+    }
+
     /// Read a file by content key
     ///
     /// # Errors
@@ -169,6 +236,40 @@ impl Installation {
         Ok(data)
     }
 
+    /// Read a file by content key, resolving it through the encoding file
+    ///
+    /// Resolves the content key to its encoding key using the loaded
+    /// encoding file, then reads and BLTE-decodes the file from local
+    /// storage. Content key resolutions are cached by the resolver, so
+    /// repeated reads of the same key skip the encoding file search.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StorageError::Resolver` if no encoding file has been loaded
+    /// with `load_encoding_file()`, `StorageError::NotFound` if the content
+    /// key is not in the encoding file, or any error from
+    /// `read_file_by_encoding_key()`
+    pub async fn read_file_by_ckey(&self, content_key: &ContentKey) -> Result<Vec<u8>> {
+        if !self.resolver.has_encoding_file() {
+            return Err(StorageError::Resolver(
+                "Encoding file not loaded: call load_encoding_file() before reading by content key"
+                    .to_string(),
+            ));
+        }
+
+        let encoding_key = self
+            .resolver
+            .resolve_content_key(content_key)
+            .ok_or_else(|| {
+                StorageError::NotFound(format!(
+                    "Content key not found in encoding file: {}",
+                    hex::encode(content_key.as_bytes())
+                ))
+            })?;
+
+        self.read_file_by_encoding_key(&encoding_key).await
+    }
+
     /// Read a file by encoding key from local storage
     ///
     /// This is the CORRECT method for reading files from local CASC installations.
@@ -179,6 +280,9 @@ impl Installation {
     /// 2. Encoding file: ContentKey -> EncodingKey
     /// 3. Local .idx: EncodingKey -> archive location
     ///
+    /// If the key is not stored locally and the resolver has a CDN
+    /// fallback, the file is downloaded, stored and indexed.
+    ///
     /// # Errors
     ///
     /// Returns error if file cannot be found or read
@@ -191,19 +295,22 @@ impl Installation {
             let cache = self.cache.read().await;
             if let Some(cached_data) = cache.get(&cache_key) {
                 debug!("Cache hit for encoding key");
+                self.resolver.record_resolution(ResolutionSource::Local);
                 return Ok(cached_data.clone());
             }
         }
 
         // Look up encoding key in indices to get archive location
-        let index_entry = {
-            let index_manager = self.index_manager.read().await;
-            index_manager.lookup(encoding_key).ok_or_else(|| {
+        let index_entry = self.index_manager.read().await.lookup(encoding_key);
+        let Some(index_entry) = index_entry else {
+            let data = self.fetch_from_cdn(encoding_key).await?.ok_or_else(|| {
                 StorageError::NotFound(format!(
                     "Archive location not found for encoding key: {}",
                     hex::encode(encoding_key.as_bytes())
                 ))
-            })?
+            })?;
+            self.cache.read().await.insert(cache_key, data.clone());
+            return Ok(data);
         };
 
         debug!(
@@ -232,9 +339,358 @@ impl Installation {
             cache.insert(cache_key, data.clone());
         }
 
+        self.resolver.record_resolution(ResolutionSource::Local);
         Ok(data)
     }
 
+    /// Get the decoded size of a file by encoding key without reading it
+    ///
+    /// The size comes from the read cache, the encoding file, or the chunk
+    /// table in the BLTE header of the stored entry, so nothing is decoded
+    /// or cached. Only a single-chunk entry that the encoding file does not
+    /// list has to be decompressed to learn its size; the result is still
+    /// not cached.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NotFound` if the key is neither in the encoding file nor in
+    /// the local indices, or an error if the BLTE header cannot be read
+    pub async fn file_size_by_encoding_key(&self, encoding_key: &EncodingKey) -> Result<u64> {
+        let cache_key = format!("ekey:{}", hex::encode(encoding_key.as_bytes()));
+        if let Some(cached) = self.cache.read().await.get(&cache_key) {
+            return Ok(cached.len() as u64);
+        }
+        if let Some(size) = self.resolver.get_decoded_size(encoding_key) {
+            return Ok(size);
+        }
+
+        let index_entry = self
+            .index_manager
+            .read()
+            .await
+            .lookup(encoding_key)
+            .ok_or_else(|| {
+                StorageError::NotFound(format!(
+                    "Archive location not found for encoding key: {}",
+                    hex::encode(encoding_key.as_bytes())
+                ))
+            })?;
+        let archive_manager = self.archive_manager.read().await;
+        let chunks = Self::read_chunk_table(&archive_manager, &index_entry)?;
+        if let Some(size) = chunks
+            .iter()
+            .map(|chunk| chunk.decompressed_size)
+            .sum::<Option<u64>>()
+        {
+            return Ok(size);
+        }
+
+        let mut size = 0;
+        for (chunk_index, chunk) in chunks.iter().enumerate() {
+            size += match chunk.decompressed_size {
+                Some(chunk_size) => chunk_size,
+                None => {
+                    Self::decode_chunk(&archive_manager, &index_entry, chunk, chunk_index, None)?
+                        .len() as u64
+                }
+            };
+        }
+        drop(archive_manager);
+        Ok(size)
+    }
+
+    /// Download a file missing from local storage and store it
+    ///
+    /// Returns `None` if the resolver has no CDN fallback. The download is
+    /// checked against `encoding_key` and decoded before it is written, so
+    /// a bad response never reaches the archives.
+    async fn fetch_from_cdn(&self, encoding_key: &EncodingKey) -> Result<Option<Vec<u8>>> {
+        let Some(blte) = self.resolver.download_from_cdn(encoding_key).await? else {
+            return Ok(None);
+        };
+        if !blte_matches_key(&blte, encoding_key) {
+            return Err(StorageError::Verification(format!(
+                "CDN data does not match encoding key {}",
+                hex::encode(encoding_key.as_bytes())
+            )));
+        }
+        let data = Self::decode_blte(&blte)?;
+
+        // An open write transaction owns the archive tails until it ends;
+        // serve the download without storing it rather than wait
+        let Ok(_writer) = self.writer.try_lock() else {
+            debug!(
+                "Not storing {} from CDN during a write transaction",
+                hex::encode(encoding_key.as_bytes())
+            );
+            self.resolver.record_resolution(ResolutionSource::Cdn);
+            return Ok(Some(data));
+        };
+        let (archive_id, archive_offset, size, _) = self
+            .archive_manager
+            .write()
+            .await
+            .write_blte_with_key(&blte, encoding_key)?;
+        self.index_manager.write().await.add_entry(
+            encoding_key,
+            archive_id,
+            archive_offset,
+            size,
+        )?;
+
+        info!(
+            "Stored {} from CDN in archive {} at offset {}",
+            hex::encode(encoding_key.as_bytes()),
+            archive_id,
+            archive_offset
+        );
+        self.resolver.record_resolution(ResolutionSource::Cdn);
+        Ok(Some(data))
+    }
+
+    /// Read a byte range of a file by encoding key
+    ///
+    /// Only the BLTE chunks covering `offset..offset + length` are read from
+    /// the archive and decompressed, so large files can be streamed without
+    /// decoding the whole payload. Recently decompressed chunks are cached,
+    /// up to [`with_chunk_cache_bytes`](Self::with_chunk_cache_bytes), so
+    /// sequential range reads do not decode the same chunk twice. A range
+    /// extending past the end of the file is truncated; an offset at or past
+    /// the end returns no data.
+    ///
+    /// Encrypted chunks require [`Self::read_range_with_keys`].
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the encoding key is not in the local indices, the
+    /// archive data is not BLTE-encoded, or a chunk cannot be decoded
+    pub async fn read_range(
+        &self,
+        encoding_key: &EncodingKey,
+        offset: u64,
+        length: usize,
+    ) -> Result<Vec<u8>> {
+        self.read_range_inner(encoding_key, offset, length, None)
+            .await
+    }
+
+    /// Read a byte range of a file by encoding key, decrypting encrypted chunks
+    ///
+    /// Like [`Self::read_range`], with keys for encrypted chunks. Each chunk
+    /// is decrypted with the IV of its position in the file, so ranges that
+    /// start mid-file decode correctly.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the encoding key is not in the local indices, the
+    /// archive data is not BLTE-encoded, a chunk cannot be decoded, or a
+    /// decryption key is missing
+    pub async fn read_range_with_keys(
+        &self,
+        encoding_key: &EncodingKey,
+        offset: u64,
+        length: usize,
+        key_store: &TactKeyStore,
+    ) -> Result<Vec<u8>> {
+        self.read_range_inner(encoding_key, offset, length, Some(key_store))
+            .await
+    }
+
+    async fn read_range_inner(
+        &self,
+        encoding_key: &EncodingKey,
+        offset: u64,
+        length: usize,
+        key_store: Option<&TactKeyStore>,
+    ) -> Result<Vec<u8>> {
+        let ekey_hex = hex::encode(encoding_key.as_bytes());
+        debug!(
+            "Reading range {}+{} of encoding key: {}",
+            offset, length, ekey_hex
+        );
+
+        let index_entry = {
+            let index_manager = self.index_manager.read().await;
+            index_manager.lookup(encoding_key).ok_or_else(|| {
+                StorageError::NotFound(format!(
+                    "Archive location not found for encoding key: {ekey_hex}"
+                ))
+            })?
+        };
+
+        let archive_manager = self.archive_manager.read().await;
+        let chunks = Self::read_chunk_table(&archive_manager, &index_entry)?;
+
+        let end = offset.saturating_add(length as u64);
+        let mut result = Vec::with_capacity(length.min(16 * 1024 * 1024));
+
+        for (chunk_index, chunk) in chunks.iter().enumerate() {
+            if chunk
+                .decompressed_size
+                .is_some_and(|size| chunk.decompressed_offset + size <= offset)
+            {
+                continue;
+            }
+            if chunk.decompressed_offset >= end {
+                break;
+            }
+
+            let cached = self
+                .chunk_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(encoding_key, chunk_index);
+            let data = if let Some(data) = cached {
+                debug!("Cache hit for chunk {} of {}", chunk_index, ekey_hex);
+                data
+            } else {
+                let data: Arc<[u8]> = Self::decode_chunk(
+                    &archive_manager,
+                    &index_entry,
+                    chunk,
+                    chunk_index,
+                    key_store,
+                )?
+                .into();
+                self.chunk_cache
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .insert(*encoding_key, chunk_index, Arc::clone(&data));
+                data
+            };
+
+            // Slice the requested part out of the chunk; the bounds are
+            // below the chunk length, which fits in usize
+            #[allow(clippy::cast_possible_truncation)]
+            let start = offset.saturating_sub(chunk.decompressed_offset) as usize;
+            #[allow(clippy::cast_possible_truncation)]
+            let stop = (end - chunk.decompressed_offset).min(data.len() as u64) as usize;
+            if start < stop {
+                result.extend_from_slice(&data[start..stop]);
+            }
+        }
+        drop(archive_manager);
+
+        Ok(result)
+    }
+
+    /// Read the BLTE chunk table of an archive entry
+    ///
+    /// Only the BLTE header is read. Single-chunk files yield one chunk
+    /// whose decompressed size is unknown until it is decoded.
+    fn read_chunk_table(
+        archive_manager: &ArchiveManager,
+        entry: &IndexEntry,
+    ) -> Result<Vec<BlteChunkSpan>> {
+        let archive_id = entry.archive_id();
+        let archive_offset = entry.archive_offset();
+
+        let prefix_len = entry.size.min(LOCAL_HEADER_SIZE as u32 + 8);
+        let prefix = archive_manager.read_raw(archive_id, archive_offset, prefix_len)?;
+
+        let blte_start = if prefix.len() >= LOCAL_HEADER_SIZE + 8
+            && &prefix[LOCAL_HEADER_SIZE..LOCAL_HEADER_SIZE + 4] == b"BLTE"
+        {
+            LOCAL_HEADER_SIZE
+        } else if prefix.len() >= 8 && &prefix[0..4] == b"BLTE" {
+            0
+        } else {
+            return Err(StorageError::InvalidFormat(
+                "Archive entry is not BLTE-encoded".to_string(),
+            ));
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        let blte_start_u32 = blte_start as u32;
+
+        let header_size = u32::from_be_bytes([
+            prefix[blte_start + 4],
+            prefix[blte_start + 5],
+            prefix[blte_start + 6],
+            prefix[blte_start + 7],
+        ]);
+
+        // Single chunk: everything after the 8-byte preamble
+        if header_size == 0 {
+            let data_start = blte_start_u32 + 8;
+            return Ok(vec![BlteChunkSpan {
+                compressed_offset: data_start,
+                compressed_size: entry.size.saturating_sub(data_start),
+                decompressed_offset: 0,
+                decompressed_size: None,
+            }]);
+        }
+
+        let header_bytes =
+            archive_manager.read_raw(archive_id, archive_offset + blte_start_u32, header_size)?;
+        let header = BlteHeader::read_options(
+            &mut std::io::Cursor::new(&header_bytes),
+            binrw::Endian::Big,
+            (),
+        )
+        .map_err(|e| StorageError::Archive(format!("Failed to parse BLTE header: {e}")))?;
+
+        let chunk_infos = header
+            .extended
+            .map(|extended| extended.chunk_infos)
+            .unwrap_or_default();
+
+        let mut compressed_offset = blte_start_u32 + header_size;
+        let mut decompressed_offset = 0u64;
+        let mut chunks = Vec::with_capacity(chunk_infos.len());
+        for info in chunk_infos {
+            chunks.push(BlteChunkSpan {
+                compressed_offset,
+                compressed_size: info.compressed_size,
+                decompressed_offset,
+                decompressed_size: Some(u64::from(info.decompressed_size)),
+            });
+            compressed_offset += info.compressed_size;
+            decompressed_offset += u64::from(info.decompressed_size);
+        }
+
+        if compressed_offset > entry.size {
+            return Err(StorageError::Corruption(format!(
+                "BLTE chunk table covers {compressed_offset} bytes, entry has {}",
+                entry.size
+            )));
+        }
+
+        Ok(chunks)
+    }
+
+    /// Read and decode a single BLTE chunk of an archive entry
+    fn decode_chunk(
+        archive_manager: &ArchiveManager,
+        entry: &IndexEntry,
+        chunk: &BlteChunkSpan,
+        chunk_index: usize,
+        key_store: Option<&TactKeyStore>,
+    ) -> Result<Vec<u8>> {
+        let raw = archive_manager.read_raw(
+            entry.archive_id(),
+            entry.archive_offset() + chunk.compressed_offset,
+            chunk.compressed_size,
+        )?;
+
+        let (&mode_byte, data) = raw
+            .split_first()
+            .ok_or_else(|| StorageError::Archive(format!("BLTE chunk {chunk_index} is empty")))?;
+        let mode = CompressionMode::from_byte(mode_byte).ok_or_else(|| {
+            StorageError::Archive(format!(
+                "Unknown BLTE compression mode 0x{mode_byte:02X} in chunk {chunk_index}"
+            ))
+        })?;
+        let chunk_data = ChunkData::from_compressed(mode, data.to_vec(), None);
+
+        let decoded = match key_store {
+            Some(key_store) => chunk_data.decompress_with_keys(chunk_index, key_store),
+            None => chunk_data.decompress(chunk_index),
+        };
+        decoded.map_err(|e| {
+            StorageError::Archive(format!("Failed to decode BLTE chunk {chunk_index}: {e}"))
+        })
+    }
+
     /// Decode BLTE-encoded data to get the actual file content
     ///
     /// Local CASC archives have a 30-byte header before each BLTE entry:
@@ -420,6 +876,94 @@ impl Installation {
         results
     }
 
+    /// Read many files by encoding key in one pass
+    ///
+    /// All keys are looked up under a single index lock, then grouped by
+    /// archive and read in ascending offset order, so bulk extraction reads
+    /// each archive sequentially instead of seeking back and forth. Cached
+    /// files are not read again. Results are returned in the order of
+    /// `keys`; a key that cannot be found or decoded only fails its own
+    /// entry.
+    pub async fn read_many(&self, keys: &[EncodingKey]) -> Vec<Result<Vec<u8>>> {
+        let mut results: Vec<Option<Result<Vec<u8>>>> = Vec::with_capacity(keys.len());
+        let mut locations: BTreeMap<u16, Vec<(u32, u32, usize)>> = BTreeMap::new();
+
+        {
+            let cache = self.cache.read().await;
+            let index_manager = self.index_manager.read().await;
+
+            for (position, encoding_key) in keys.iter().enumerate() {
+                let cache_key = format!("ekey:{}", hex::encode(encoding_key.as_bytes()));
+                if let Some(cached_data) = cache.get(&cache_key) {
+                    results.push(Some(Ok(cached_data.clone())));
+                    continue;
+                }
+
+                match index_manager.lookup(encoding_key) {
+                    Some(entry) => {
+                        locations.entry(entry.archive_id()).or_default().push((
+                            entry.archive_offset(),
+                            entry.size,
+                            position,
+                        ));
+                        results.push(None);
+                    }
+                    None => results.push(Some(Err(StorageError::NotFound(format!(
+                        "Archive location not found for encoding key: {}",
+                        hex::encode(encoding_key.as_bytes())
+                    ))))),
+                }
+            }
+        }
+
+        debug!(
+            "Batch read of {} keys from {} archives",
+            keys.len(),
+            locations.len()
+        );
+
+        let mut raw_reads = Vec::new();
+        let archive_manager = self.archive_manager.read().await;
+        for (archive_id, mut entries) in locations {
+            entries.sort_unstable_by_key(|&(offset, _, _)| offset);
+
+            let spans: Vec<_> = entries
+                .iter()
+                .map(|&(offset, size, _)| (offset, size))
+                .collect();
+            let reads = archive_manager.read_raw_many(archive_id, &spans);
+            raw_reads.extend(
+                entries
+                    .into_iter()
+                    .map(|(_, _, position)| position)
+                    .zip(reads),
+            );
+        }
+        drop(archive_manager);
+
+        let cache = self.cache.read().await;
+        for (position, raw_data) in raw_reads {
+            let data = raw_data.and_then(|raw| Self::decode_blte(&raw));
+            if let Ok(data) = &data {
+                let cache_key = format!("ekey:{}", hex::encode(keys[position].as_bytes()));
+                cache.insert(cache_key, data.clone());
+            }
+            results[position] = Some(data);
+        }
+        drop(cache);
+
+        results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| {
+                    Err(StorageError::Archive(
+                        "Batch read produced no result".to_string(),
+                    ))
+                })
+            })
+            .collect()
+    }
+
     /// Write a file to storage.
     ///
     /// The data is BLTE-encoded, prepended with a 30-byte local header, and
@@ -436,16 +980,79 @@ impl Installation {
             compress
         );
 
-        // Calculate content key from uncompressed data
-        let content_key = ContentKey::from_data(&data);
-
+        let _writer = self.writer.lock().await;
         // Write to archive: BLTE-encodes, prepends 30-byte local header,
         // and computes encoding key as MD5(blte_data)
-        let (archive_id, archive_offset, size, encoding_key_bytes) = {
-            let mut archive_manager = self.archive_manager.write().await;
-            archive_manager.write_content(&data, compress)?
-        };
+        let location = self
+            .archive_manager
+            .write()
+            .await
+            .write_content(&data, compress)?;
+        self.index_written(&data, location).await
+    }
 
+    /// Write a file to storage, compressing it with `compressor` instead of
+    /// the installation's configured compressor.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if file cannot be written or compressed
+    pub async fn write_file_with(
+        &self,
+        data: Vec<u8>,
+        compressor: &dyn ContentCompressor,
+    ) -> Result<ContentKey> {
+        debug!(
+            "Writing file ({} bytes, compressor: {:?})",
+            data.len(),
+            compressor
+        );
+
+        let _writer = self.writer.lock().await;
+        let location = self
+            .archive_manager
+            .write()
+            .await
+            .write_content_with(&data, compressor)?;
+        self.index_written(&data, location).await
+    }
+
+    /// Start a transaction for writing several files at once
+    ///
+    /// Files written through the transaction become visible together when
+    /// it is committed; rolling it back or dropping it removes their data
+    /// from the archives again. Waits for other writes to finish, and
+    /// holds off [`write_file`](Self::write_file) until the transaction
+    /// ends. See [`WriteTransaction`] for the crash behavior.
+    pub async fn begin_write(&self) -> WriteTransaction {
+        let writer = Arc::clone(&self.writer).lock_owned().await;
+        WriteTransaction::begin(
+            Arc::clone(&self.index_manager),
+            Arc::clone(&self.archive_manager),
+            writer,
+        )
+        .await
+    }
+
+    /// Change the compressor applied to subsequent writes.
+    ///
+    /// Content already stored stays readable: reads decode whichever BLTE
+    /// mode each chunk was written with.
+    pub async fn set_compressor(&self, compressor: Arc<dyn ContentCompressor>) {
+        self.archive_manager
+            .write()
+            .await
+            .set_compressor(compressor);
+    }
+
+    /// Index content just written to an archive.
+    async fn index_written(
+        &self,
+        data: &[u8],
+        (archive_id, archive_offset, size, encoding_key_bytes): (u16, u32, u32, [u8; 16]),
+    ) -> Result<ContentKey> {
+        // Calculate content key from uncompressed data
+        let content_key = ContentKey::from_data(data);
         let encoding_key = EncodingKey::from_bytes(encoding_key_bytes);
 
         // Update indices with the content-addressable encoding key
@@ -492,6 +1099,18 @@ impl Installation {
         self.resolver.load_root_file(data)
     }
 
+    /// Load the root file with content key `root_key` from the build config
+    ///
+    /// Unlike [`Self::load_root_file`], this lets the reverse index built
+    /// from the file be saved and reused.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if root file cannot be loaded
+    pub fn load_root_file_with_key(&self, data: &[u8], root_key: ContentKey) -> Result<()> {
+        self.resolver.load_root_file_with_key(data, root_key)
+    }
+
     /// Load encoding file for content resolution
     ///
     /// # Errors
@@ -501,6 +1120,79 @@ impl Installation {
         self.resolver.load_encoding_file(data)
     }
 
+    /// Load the encoding file with content key `encoding_key` from the
+    /// build config
+    ///
+    /// Unlike [`Self::load_encoding_file`], this lets the reverse index
+    /// built from the file be saved and reused.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if encoding file cannot be loaded
+    pub fn load_encoding_file_with_key(&self, data: &[u8], encoding_key: ContentKey) -> Result<()> {
+        self.resolver
+            .load_encoding_file_with_key(data, encoding_key)
+    }
+
+    /// Load build metadata from a `.build.info` file
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be read or parsed
+    pub async fn load_build_info(&self, path: &Path) -> Result<()> {
+        let build_info = BuildInfoFile::from_path(path).await?;
+        self.set_build_info(build_info).await;
+        Ok(())
+    }
+
+    /// Replace the installation's build metadata
+    ///
+    /// The build config of the active build is recorded in group indices
+    /// and a group index written for another build is not loaded.
+    pub async fn set_build_info(&self, build_info: BuildInfoFile) {
+        let build_config = build_info
+            .active_entry()
+            .and_then(|entry| hex::decode(entry.build_key()?).ok())
+            .and_then(|bytes| <[u8; 16]>::try_from(bytes).ok());
+        self.index_manager
+            .write()
+            .await
+            .set_build_config(build_config);
+        *self.build_info.write().await = Some(build_info);
+    }
+
+    /// Get a copy of the installation's build metadata, if any is set
+    pub async fn build_info(&self) -> Option<BuildInfoFile> {
+        self.build_info.read().await.clone()
+    }
+
+    /// Write the installation's build metadata to `path` as `.build.info`
+    ///
+    /// The file is written next to `path` first and renamed into place, so
+    /// a client reading it never sees a partial file.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no build metadata is set or the file cannot be written
+    pub async fn export_build_info(&self, path: &Path) -> Result<()> {
+        let contents = self
+            .build_info
+            .read()
+            .await
+            .as_ref()
+            .map(|build_info| build_info.to_bpsv().to_string())
+            .ok_or_else(|| StorageError::Installation("no build info to export".to_string()))?;
+
+        let mut temp_name = path.as_os_str().to_os_string();
+        temp_name.push(".tmp");
+        let temp_path = PathBuf::from(temp_name);
+        tokio::fs::write(&temp_path, contents).await?;
+        tokio::fs::rename(&temp_path, path).await?;
+
+        info!("Exported build info to {}", path.display());
+        Ok(())
+    }
+
     /// Verify installation integrity
     ///
     /// # Errors
@@ -543,6 +1235,23 @@ impl Installation {
         Ok(result)
     }
 
+    /// Find every game file whose content encodes to `encoding_key`
+    ///
+    /// Builds the reverse index from the loaded root and encoding files on
+    /// first use. With files loaded by their build config keys, the index is
+    /// saved in the data directory and later opens with the same files read
+    /// it instead. FDIDs sharing deduplicated content are all returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the root or encoding file has not been loaded
+    pub fn files_for_ekey(
+        &self,
+        encoding_key: &EncodingKey,
+    ) -> Result<Vec<crate::reverse_index::FileOwner>> {
+        self.resolver.files_for_ekey(encoding_key)
+    }
+
     /// Get file information by path
     ///
     /// # Errors
@@ -577,6 +1286,56 @@ impl Installation {
         }
     }
 
+    /// Update section fill per index bucket, see
+    /// [`IndexManager::compaction_stats`]
+    pub async fn index_compaction_stats(&self) -> Vec<crate::index::BucketCompactionStat> {
+        self.index_manager.read().await.compaction_stats()
+    }
+
+    /// Flush index buckets whose update section is more than `threshold`
+    /// percent full, see [`IndexManager::compact_if_needed`]
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a flushed index cannot be written
+    pub async fn compact_indices_if_needed(&self, threshold: f32) -> Result<Vec<u8>> {
+        self.index_manager
+            .write()
+            .await
+            .compact_if_needed(threshold)
+    }
+
+    /// Write a group index of the saved index buckets, see
+    /// [`IndexManager::write_group_index`]
+    ///
+    /// The next [`initialize`](Self::initialize) loads it instead of the
+    /// per-bucket `.idx` files as long as no bucket was saved since and the
+    /// [build info](Self::set_build_info) names the same build config.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the group index cannot be written
+    pub async fn write_group_index(&self) -> Result<()> {
+        self.index_manager.read().await.write_group_index()
+    }
+
+    /// Live and dead space of every data archive
+    ///
+    /// Index entries are matched against the open archives; see the
+    /// [`archive_report`](crate::archive_report) module.
+    /// [`total_dead_space`](crate::archive_report::total_dead_space) sums
+    /// the space a repack would reclaim.
+    pub async fn archive_report(&self) -> Vec<ArchiveReport> {
+        let archive_sizes = self.archive_manager.read().await.archive_sizes();
+        let index_manager = self.index_manager.read().await;
+        let reports = ArchiveReport::compute(
+            &archive_sizes,
+            index_manager.iter_entries().map(|(_, entry)| entry),
+        );
+        drop(index_manager);
+        reports
+    }
+
     /// Get the installation path
     pub const fn path(&self) -> &PathBuf {
         &self.path
@@ -600,6 +1359,265 @@ impl Installation {
         index_manager.lookup(encoding_key).is_some()
     }
 
+    /// Report how much of `root` a listfile names and local storage holds
+    ///
+    /// A root file entry counts as stored locally when its content key
+    /// resolves through the loaded encoding file to an encoding key found
+    /// in the local indices. `listfile` is streamed on a blocking thread,
+    /// see [`CoverageReport::compute`], before the indices are locked.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no encoding file has been loaded or the listfile
+    /// cannot be read
+    pub async fn listfile_coverage<R: std::io::BufRead + Send + 'static>(
+        &self,
+        root: &cascette_formats::root::RootFile,
+        listfile: R,
+    ) -> Result<CoverageReport> {
+        if !self.resolver.has_encoding_file() {
+            return Err(StorageError::Resolver(
+                "encoding file must be loaded to check local storage".to_string(),
+            ));
+        }
+
+        // Read the listfile before locking, so blocking I/O neither stalls
+        // the runtime nor holds up writers to the indices
+        let known = crate::coverage::root_file_data_ids(root);
+        let names = tokio::task::spawn_blocking(move || {
+            crate::coverage::ListfileNames::read(listfile, &known)
+        })
+        .await
+        .map_err(|e| StorageError::Io(std::io::Error::other(e)))??;
+
+        let index_manager = self.index_manager.read().await;
+        let report = CoverageReport::from_names(root, &names, |content_key| {
+            self.resolver
+                .resolve_content_key(content_key)
+                .is_some_and(|encoding_key| index_manager.lookup(&encoding_key).is_some())
+        });
+        drop(index_manager);
+        Ok(report)
+    }
+
+    /// Resume a download of the files in `manifest`
+    ///
+    /// Stored files of the manifest's encoded size are skipped. With
+    /// `verify_content`, they are read back and checked against their
+    /// encoding key and chunk checksums instead. Missing, resized and
+    /// corrupt files are downloaded through the resolver's CDN fallback;
+    /// see the [`resume`](crate::resume) module.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a downloaded file cannot be stored. Files that
+    /// cannot be downloaded are listed in [`ResumeReport::failed`].
+    // The download batch is held on purpose until it is published
+    #[allow(clippy::significant_drop_tightening)]
+    pub async fn resume_download(
+        &self,
+        manifest: &DownloadManifest,
+        verify_content: bool,
+    ) -> Result<ResumeReport> {
+        let mut report = ResumeReport {
+            expected: manifest.entries.len() as u64,
+            ..ResumeReport::default()
+        };
+        let mut batch = DownloadBatch::default();
+
+        for entry in &manifest.entries {
+            let encoding_key = &entry.encoding_key;
+            let expected_size = entry.file_size.as_u64() + LOCAL_HEADER_SIZE as u64;
+            let stored = self.index_manager.read().await.lookup(encoding_key);
+
+            let corrupt = match stored {
+                None => false,
+                Some(stored) if u64::from(stored.size) != expected_size => {
+                    warn!(
+                        "Stored size of {} is {}, expected {}",
+                        hex::encode(encoding_key.as_bytes()),
+                        stored.size,
+                        expected_size
+                    );
+                    true
+                }
+                Some(_) if !verify_content => {
+                    report.skipped += 1;
+                    continue;
+                }
+                Some(stored) => {
+                    let raw = self.archive_manager.read().await.read_raw(
+                        stored.archive_id(),
+                        stored.archive_offset(),
+                        stored.size,
+                    );
+                    let intact = raw.is_ok_and(|raw| {
+                        raw.get(LOCAL_HEADER_SIZE..)
+                            .is_some_and(|blte| blte_is_intact(blte, encoding_key))
+                    });
+                    if intact {
+                        report.verified += 1;
+                        continue;
+                    }
+                    warn!(
+                        "Stored copy of {} is corrupt",
+                        hex::encode(encoding_key.as_bytes())
+                    );
+                    true
+                }
+            };
+
+            match batch.download(self, encoding_key).await? {
+                Ok(size) => {
+                    report.bytes_downloaded += size;
+                    if corrupt {
+                        report.redownloaded += 1;
+                    } else {
+                        report.downloaded += 1;
+                    }
+                }
+                Err(reason) => report.failed.push(ResumeFailure {
+                    encoding_key: hex::encode(encoding_key.as_bytes()),
+                    reason,
+                }),
+            }
+        }
+
+        batch.publish(self).await?;
+
+        info!(
+            "Resumed download: {} skipped, {} verified, {} redownloaded, {} downloaded, {} failed",
+            report.skipped,
+            report.verified,
+            report.redownloaded,
+            report.downloaded,
+            report.failed.len()
+        );
+        Ok(report)
+    }
+
+    /// Verify every stored file and optionally repair corrupt ones
+    ///
+    /// Each file the indices reference is checked against the encoding key
+    /// in its local header, see the [`repair`](crate::repair) module. Keys
+    /// in `expected`, such as those of a build's download manifest, that
+    /// the indices lack are reported as missing. With `fix`, corrupt and
+    /// missing files are downloaded through the resolver's CDN fallback,
+    /// which can be discovered from the installation's `.build.info`;
+    /// without one, they are reported as unrecoverable.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a downloaded file cannot be stored. Files that
+    /// cannot be repaired are listed in [`RepairReport::unrecoverable`].
+    // The download batch is held on purpose until it is published
+    #[allow(clippy::significant_drop_tightening)]
+    pub async fn verify_and_repair(
+        &self,
+        expected: &[EncodingKey],
+        fix: bool,
+    ) -> Result<RepairReport> {
+        let entries = self.get_all_index_entries().await;
+        let mut report = RepairReport {
+            checked: entries.len() as u64,
+            ..RepairReport::default()
+        };
+        let mut batch = DownloadBatch::default();
+
+        for entry in entries {
+            let raw = self.archive_manager.read().await.read_raw(
+                entry.archive_id(),
+                entry.archive_offset(),
+                entry.size,
+            );
+            let header_key = raw
+                .as_ref()
+                .ok()
+                .and_then(|raw| LocalHeader::from_bytes(raw))
+                .map(|header| header.original_encoding_key())
+                .filter(|key| key.starts_with(entry.key_bytes()));
+            let Some(key) = entry.full_key.or(header_key) else {
+                warn!(
+                    "Cannot verify index entry {}: local header is unreadable or names another key",
+                    hex::encode(entry.key_bytes())
+                );
+                report.unrecoverable.push(RepairFailure {
+                    encoding_key: hex::encode(entry.key_bytes()),
+                    reason: "full encoding key unknown".to_string(),
+                });
+                continue;
+            };
+            let encoding_key = EncodingKey::from_bytes(key);
+
+            let intact = raw.is_ok_and(|raw| {
+                raw.get(LOCAL_HEADER_SIZE..)
+                    .is_some_and(|blte| blte_is_intact(blte, &encoding_key))
+            });
+            if intact {
+                report.intact += 1;
+                continue;
+            }
+
+            let key_hex = hex::encode(key);
+            warn!("Stored copy of {} is corrupt", key_hex);
+            if !fix {
+                report.unrecoverable.push(RepairFailure {
+                    encoding_key: key_hex,
+                    reason: "corrupt, repair not requested".to_string(),
+                });
+                continue;
+            }
+            match batch.download(self, &encoding_key).await? {
+                Ok(_) => report.repaired.push(key_hex),
+                Err(reason) => report.unrecoverable.push(RepairFailure {
+                    encoding_key: key_hex,
+                    reason,
+                }),
+            }
+        }
+
+        let index_manager = self.index_manager.read().await;
+        let mut seen = std::collections::HashSet::new();
+        let missing: Vec<EncodingKey> = expected
+            .iter()
+            .filter(|key| seen.insert(**key) && index_manager.lookup(key).is_none())
+            .copied()
+            .collect();
+        drop(index_manager);
+        report.missing = missing.len() as u64;
+
+        for encoding_key in missing {
+            let key_hex = hex::encode(encoding_key.as_bytes());
+            warn!("Expected file {} is not stored", key_hex);
+            if !fix {
+                report.unrecoverable.push(RepairFailure {
+                    encoding_key: key_hex,
+                    reason: "missing, repair not requested".to_string(),
+                });
+                continue;
+            }
+            match batch.download(self, &encoding_key).await? {
+                Ok(_) => report.repaired.push(key_hex),
+                Err(reason) => report.unrecoverable.push(RepairFailure {
+                    encoding_key: key_hex,
+                    reason,
+                }),
+            }
+        }
+
+        batch.publish(self).await?;
+
+        info!(
+            "Verified {} entries: {} intact, {} missing, {} repaired, {} unrecoverable",
+            report.checked,
+            report.intact,
+            report.missing,
+            report.repaired.len(),
+            report.unrecoverable.len()
+        );
+        Ok(report)
+    }
+
     /// Get all index entries from the installation
     ///
     /// Returns a vector of all index entries with their encoding keys and archive locations.
@@ -631,6 +1649,86 @@ impl Installation {
     }
 }
 
+/// Files downloaded per published batch by resume and repair
+const DOWNLOAD_BATCH_FILES: usize = 256;
+
+/// Downloads from the CDN fallback, stored through one write transaction
+///
+/// Syncing the archives and saving the index buckets per file dominates
+/// repairs of many small files. Downloads are staged instead and
+/// published every [`DOWNLOAD_BATCH_FILES`] files, replacing any stored
+/// copies. A batch that is dropped without being published is rolled back.
+#[derive(Default)]
+struct DownloadBatch {
+    transaction: Option<WriteTransaction>,
+    /// Keys staged in the open transaction
+    keys: Vec<EncodingKey>,
+}
+
+impl DownloadBatch {
+    /// Download `encoding_key` and stage it
+    ///
+    /// Returns the downloaded size, or why the download failed.
+    // The transaction stays open across downloads until it is published
+    #[allow(clippy::significant_drop_tightening)]
+    async fn download(
+        &mut self,
+        installation: &Installation,
+        encoding_key: &EncodingKey,
+    ) -> Result<std::result::Result<u64, String>> {
+        let blte = match installation.resolver.download_from_cdn(encoding_key).await {
+            Ok(Some(blte)) => blte,
+            Ok(None) => return Ok(Err("no CDN fallback is configured".to_string())),
+            Err(e) => return Ok(Err(e.to_string())),
+        };
+        if !blte_is_intact(&blte, encoding_key) {
+            return Ok(Err("CDN data does not match encoding key".to_string()));
+        }
+
+        let transaction = match &mut self.transaction {
+            Some(transaction) => transaction,
+            None => self.transaction.insert(installation.begin_write().await),
+        };
+        transaction.write(encoding_key, &blte).await?;
+        self.keys.push(*encoding_key);
+        if self.keys.len() >= DOWNLOAD_BATCH_FILES {
+            self.publish(installation).await?;
+        }
+        Ok(Ok(blte.len() as u64))
+    }
+
+    /// Commit the staged downloads and drop stale cached copies
+    ///
+    /// Repairs must survive a restart, so the commit syncs the data and
+    /// saves the index buckets before the files count as stored.
+    async fn publish(&mut self, installation: &Installation) -> Result<()> {
+        let Some(transaction) = self.transaction.take() else {
+            return Ok(());
+        };
+        transaction.commit().await?;
+
+        let cache = installation.cache.read().await;
+        for encoding_key in &self.keys {
+            cache.remove(&format!("ekey:{}", hex::encode(encoding_key.as_bytes())));
+        }
+        drop(cache);
+        let mut chunk_cache = installation
+            .chunk_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for encoding_key in &self.keys {
+            chunk_cache.remove_file(encoding_key);
+        }
+        drop(chunk_cache);
+        for _ in self.keys.drain(..) {
+            installation
+                .resolver
+                .record_resolution(ResolutionSource::Cdn);
+        }
+        Ok(())
+    }
+}
+
 /// Result of installation verification
 #[derive(Debug, Clone)]
 pub struct VerificationResult {
@@ -662,3 +1760,594 @@ pub struct InstallationStats {
     /// Number of cached content resolutions
     pub cached_content: usize,
 }
+
+/// Location of one BLTE chunk within an archive entry
+struct BlteChunkSpan {
+    /// Offset of the chunk (mode byte included) from the start of the entry
+    compressed_offset: u32,
+    /// Size of the chunk including the mode byte
+    compressed_size: u32,
+    /// Offset of the chunk's data in the decoded file
+    decompressed_offset: u64,
+    /// Decoded size, unknown for single-chunk files
+    decompressed_size: Option<u64>,
+}
+
+/// Check that `blte` is the BLTE stream named by `encoding_key`
+///
+/// Files with a chunk table are keyed by the MD5 of the BLTE header and
+/// single-chunk files by the MD5 of the whole stream. Files written by
+/// this crate always use the whole stream.
+fn blte_matches_key(blte: &[u8], encoding_key: &EncodingKey) -> bool {
+    let Some((magic, rest)) = blte.split_first_chunk::<4>() else {
+        return false;
+    };
+    let Some(header_size) = rest.first_chunk::<4>() else {
+        return false;
+    };
+    if magic != b"BLTE" {
+        return false;
+    }
+
+    let header_size = u32::from_be_bytes(*header_size) as usize;
+    let header_matches = header_size > 0
+        && blte
+            .get(..header_size)
+            .is_some_and(|header| EncodingKey::from_data(header) == *encoding_key);
+    header_matches || EncodingKey::from_data(blte) == *encoding_key
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use cascette_crypto::TactKey;
+    use cascette_formats::blte::{BlteBuilder, EncryptionSpec};
+    use cascette_formats::encoding::{CKeyEntryData, EKeyEntryData, EncodingBuilder};
+
+    const KEY_NAME: u64 = 0x1122_3344_5566_7788;
+    const KEY: [u8; 16] = [
+        0xDE, 0xAD, 0xBE, 0xEF, 0xCA, 0xFE, 0xBA, 0xBE, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE,
+        0xF0,
+    ];
+
+    fn test_data() -> Vec<u8> {
+        (0..100u8).collect()
+    }
+
+    /// Store `blte` in the installation and index it
+    async fn store_blte(installation: &Installation, blte: BlteFile) -> EncodingKey {
+        let bytes = blte.build().expect("BLTE should build");
+        let (archive_id, offset, size, ekey) = installation
+            .archive_manager
+            .write()
+            .await
+            .write_blte(&bytes)
+            .expect("BLTE should be written");
+        let encoding_key = EncodingKey::from_bytes(ekey);
+        installation
+            .index_manager
+            .write()
+            .await
+            .add_entry(&encoding_key, archive_id, offset, size)
+            .expect("Index entry should be added");
+        encoding_key
+    }
+
+    /// 100 bytes split into 16-byte chunks (the last one holds 4 bytes)
+    async fn multi_chunk_file(installation: &Installation) -> EncodingKey {
+        let blte = BlteBuilder::new()
+            .with_compression(CompressionMode::ZLib)
+            .with_chunk_size_unchecked(16)
+            .add_data(&test_data())
+            .expect("Data should be added")
+            .build()
+            .expect("BLTE should build");
+        assert_eq!(blte.chunks.len(), 7);
+        store_blte(installation, blte).await
+    }
+
+    fn open_installation(dir: &tempfile::TempDir) -> Installation {
+        Installation::open(dir.path().to_path_buf()).expect("Installation should open")
+    }
+
+    fn encoding_file(content_key: ContentKey, encoding_key: EncodingKey, size: u64) -> Vec<u8> {
+        let mut builder = EncodingBuilder::new();
+        builder.add_ckey_entry(CKeyEntryData {
+            content_key,
+            file_size: size,
+            encoding_keys: vec![encoding_key],
+        });
+        builder.add_ekey_entry(EKeyEntryData {
+            encoding_key,
+            espec: "n".to_string(),
+            file_size: size,
+        });
+        builder
+            .build()
+            .expect("Encoding file should build")
+            .build()
+            .expect("Encoding file should serialize")
+    }
+
+    #[tokio::test]
+    async fn test_export_build_info_round_trip() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open_installation(&dir);
+        let path = dir.path().join(crate::BUILD_INFO_FILE);
+
+        assert!(matches!(
+            installation.export_build_info(&path).await,
+            Err(StorageError::Installation(_))
+        ));
+
+        let mut build_info = BuildInfoFile::new();
+        build_info
+            .set_active_build(
+                "us",
+                "abcdef1234567890abcdef1234567890",
+                "fedcba0987654321fedcba0987654321",
+                "2024-01-01T00:00:00Z",
+            )
+            .expect("Build should be recorded");
+        installation.set_build_info(build_info).await;
+        installation
+            .export_build_info(&path)
+            .await
+            .expect("Build info should be exported");
+
+        let reopened = open_installation(&dir);
+        reopened
+            .load_build_info(&path)
+            .await
+            .expect("Build info should load");
+        let loaded = reopened
+            .build_info()
+            .await
+            .expect("Build info should be set");
+        let active = loaded.active_entry().expect("Active entry should exist");
+        assert_eq!(active.branch(), Some("us"));
+        assert_eq!(active.build_key(), Some("abcdef1234567890abcdef1234567890"));
+        assert_eq!(active.cdn_key(), Some("fedcba0987654321fedcba0987654321"));
+        assert_eq!(active.last_activated(), Some("2024-01-01T00:00:00Z"));
+
+        let expected = installation
+            .build_info()
+            .await
+            .expect("Build info should be set")
+            .to_bpsv()
+            .to_string();
+        assert_eq!(loaded.to_bpsv().to_string(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_archive_report_counts_removed_entries_as_dead() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open_installation(&dir);
+        let kept = multi_chunk_file(&installation).await;
+        let removed = store_blte(
+            &installation,
+            BlteFile::single_chunk(vec![7; 64], CompressionMode::None).expect("BLTE should build"),
+        )
+        .await;
+
+        let before = installation.archive_report().await;
+        assert_eq!(before.len(), 1);
+        assert_eq!(before[0].live_entries, 2);
+        assert_eq!(before[0].dead_bytes, 0);
+
+        let mut index_manager = installation.index_manager.write().await;
+        let removed_size = index_manager
+            .lookup(&removed)
+            .expect("Entry should exist")
+            .size;
+        assert!(index_manager.remove_entry(&removed));
+        drop(index_manager);
+
+        let after = installation.archive_report().await;
+        let kept_size = installation
+            .index_manager
+            .read()
+            .await
+            .lookup(&kept)
+            .expect("Entry should exist")
+            .size;
+        assert_eq!(after[0].live_entries, 1);
+        assert_eq!(after[0].live_bytes, u64::from(kept_size));
+        assert_eq!(after[0].dead_bytes, u64::from(removed_size));
+        assert_eq!(
+            crate::archive_report::total_dead_space(&after),
+            u64::from(removed_size)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_file_by_ckey() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation =
+            Installation::open(dir.path().to_path_buf()).expect("Installation should open");
+
+        let data = b"content addressed by ckey".to_vec();
+        let content_key = installation
+            .write_file(data.clone(), false)
+            .await
+            .expect("File should be written");
+
+        // Without an encoding file the caller is told what to load
+        let result = installation.read_file_by_ckey(&content_key).await;
+        assert!(
+            matches!(result, Err(StorageError::Resolver(ref msg)) if msg.contains("load_encoding_file"))
+        );
+
+        let blte = BlteFile::single_chunk(data.clone(), CompressionMode::None)
+            .expect("BLTE should be created")
+            .build()
+            .expect("BLTE should build");
+        let encoding_key = EncodingKey::from_data(&blte);
+        installation
+            .load_encoding_file(&encoding_file(content_key, encoding_key, data.len() as u64))
+            .expect("Encoding file should load");
+
+        for _ in 0..2 {
+            let read = installation
+                .read_file_by_ckey(&content_key)
+                .await
+                .expect("File should be read");
+            assert_eq!(read, data);
+        }
+
+        let unknown = ContentKey::from_data(b"not stored");
+        assert!(matches!(
+            installation.read_file_by_ckey(&unknown).await,
+            Err(StorageError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_listfile_coverage_checks_local_storage() {
+        use cascette_crypto::FileDataId;
+        use cascette_formats::root::{ContentFlags, LocaleFlags, RootBuilder, RootVersion};
+
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open_installation(&dir);
+
+        let data = b"covered file".to_vec();
+        let content_key = ContentKey::from_data(&data);
+        let blte = BlteFile::single_chunk(data.clone(), CompressionMode::None)
+            .expect("BLTE should be created");
+        let encoding_key = store_blte(&installation, blte).await;
+
+        let mut builder = RootBuilder::new(RootVersion::V2);
+        for (fdid, key) in [(1, content_key), (2, ContentKey::from_data(b"absent"))] {
+            builder.add_file(
+                FileDataId::new(fdid),
+                key,
+                None,
+                LocaleFlags::new(LocaleFlags::ALL),
+                ContentFlags::new(ContentFlags::INSTALL),
+            );
+        }
+        let root =
+            cascette_formats::root::RootFile::parse(&builder.build().expect("Root should build"))
+                .expect("Root should parse");
+        let listfile = || std::io::Cursor::new("1;Interface/a.blp\n2;World/b.wdt\n");
+
+        assert!(matches!(
+            installation.listfile_coverage(&root, listfile()).await,
+            Err(StorageError::Resolver(_))
+        ));
+
+        installation
+            .load_encoding_file(&encoding_file(content_key, encoding_key, data.len() as u64))
+            .expect("Encoding file should load");
+        let report = installation
+            .listfile_coverage(&root, listfile())
+            .await
+            .expect("Coverage should compute");
+        assert_eq!((report.total, report.named, report.present), (2, 2, 1));
+        assert_eq!(report.by_prefix["interface"].named_and_present, 1);
+        assert_eq!(report.by_prefix["world"].named_and_present, 0);
+    }
+
+    #[tokio::test]
+    async fn test_file_size_by_encoding_key_does_not_decode() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open_installation(&dir);
+
+        // The chunk table of a multi-chunk file has every chunk size
+        let encoding_key = multi_chunk_file(&installation).await;
+        let size = installation
+            .file_size_by_encoding_key(&encoding_key)
+            .await
+            .expect("Size should be known");
+        assert_eq!(size, test_data().len() as u64);
+        assert!(installation.cache.read().await.is_empty());
+
+        // A single-chunk file listed in the encoding file
+        let data = b"sized by the encoding file".to_vec();
+        let content_key = ContentKey::from_data(&data);
+        let blte = BlteFile::single_chunk(data.clone(), CompressionMode::ZLib)
+            .expect("BLTE should be created");
+        let encoding_key = store_blte(&installation, blte).await;
+        assert_eq!(
+            installation
+                .file_size_by_encoding_key(&encoding_key)
+                .await
+                .expect("Size should be known"),
+            data.len() as u64
+        );
+        installation
+            .load_encoding_file(&encoding_file(content_key, encoding_key, 1234))
+            .expect("Encoding file should load");
+        assert_eq!(
+            installation
+                .file_size_by_encoding_key(&encoding_key)
+                .await
+                .expect("Size should be known"),
+            1234
+        );
+        assert!(installation.cache.read().await.is_empty());
+
+        let missing = EncodingKey::from_bytes([0xAB; 16]);
+        assert!(matches!(
+            installation.file_size_by_encoding_key(&missing).await,
+            Err(StorageError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_many_keeps_input_order() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open_installation(&dir);
+
+        let mut stored = Vec::new();
+        for i in 0..4u8 {
+            let data = vec![i; 64 + usize::from(i)];
+            let blte = BlteFile::single_chunk(data.clone(), CompressionMode::ZLib)
+                .expect("BLTE should be created");
+            stored.push((store_blte(&installation, blte).await, data));
+        }
+        let missing = EncodingKey::from_data(b"not stored");
+
+        // Warm the cache for one key so both paths are exercised
+        installation
+            .read_file_by_encoding_key(&stored[1].0)
+            .await
+            .expect("File should be read");
+
+        let keys = [stored[3].0, missing, stored[0].0, stored[1].0, stored[3].0];
+        let results = installation.read_many(&keys).await;
+
+        assert_eq!(results.len(), keys.len());
+        assert_eq!(
+            results[0].as_ref().expect("File should be read"),
+            &stored[3].1
+        );
+        assert!(matches!(results[1], Err(StorageError::NotFound(_))));
+        assert_eq!(
+            results[2].as_ref().expect("File should be read"),
+            &stored[0].1
+        );
+        assert_eq!(
+            results[3].as_ref().expect("File should be read"),
+            &stored[1].1
+        );
+        assert_eq!(
+            results[4].as_ref().expect("File should be read"),
+            &stored[3].1
+        );
+
+        assert!(installation.read_many(&[]).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_range_multi_chunk() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open_installation(&dir);
+        let encoding_key = multi_chunk_file(&installation).await;
+        let data = test_data();
+
+        for (offset, length) in [(0, 100), (0, 16), (16, 16), (10, 20), (15, 2), (40, 50)] {
+            let range = installation
+                .read_range(&encoding_key, offset, length)
+                .await
+                .expect("Range should be read");
+            let start = usize::try_from(offset).expect("Offset fits");
+            assert_eq!(
+                range,
+                data[start..start + length],
+                "range {offset}+{length}"
+            );
+        }
+
+        // Ranges past the end are truncated
+        let range = installation
+            .read_range(&encoding_key, 90, 50)
+            .await
+            .expect("Range should be read");
+        assert_eq!(range, data[90..]);
+        for offset in [100, 1000] {
+            let range = installation
+                .read_range(&encoding_key, offset, 10)
+                .await
+                .expect("Range should be read");
+            assert!(range.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_range_decodes_only_covering_chunks() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open_installation(&dir);
+        let encoding_key = multi_chunk_file(&installation).await;
+
+        installation
+            .read_range(&encoding_key, 20, 20)
+            .await
+            .expect("Range should be read");
+
+        let cached = installation
+            .chunk_cache
+            .lock()
+            .expect("Chunk cache lock should not be poisoned")
+            .cached_chunks(&encoding_key);
+        assert_eq!(cached, [1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_read_range_chunk_cache_is_bounded() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open_installation(&dir).with_chunk_cache_bytes(40);
+        let encoding_key = multi_chunk_file(&installation).await;
+        let data = installation
+            .read_file_by_encoding_key(&encoding_key)
+            .await
+            .expect("File should be read");
+
+        // Reading every chunk keeps only what fits in the capacity
+        let range = installation
+            .read_range(&encoding_key, 0, data.len())
+            .await
+            .expect("Range should be read");
+        assert_eq!(range, data);
+
+        let chunk_cache = installation
+            .chunk_cache
+            .lock()
+            .expect("Chunk cache lock should not be poisoned");
+        assert!(chunk_cache.size() <= 40);
+        assert_eq!(chunk_cache.cached_chunks(&encoding_key), [4, 5, 6]);
+        drop(chunk_cache);
+    }
+
+    #[tokio::test]
+    async fn test_read_range_single_chunk() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open_installation(&dir);
+        let data = test_data();
+
+        installation
+            .write_file(data.clone(), false)
+            .await
+            .expect("File should be written");
+        let blte = BlteFile::single_chunk(data.clone(), CompressionMode::None)
+            .expect("BLTE should be created")
+            .build()
+            .expect("BLTE should build");
+        let encoding_key = EncodingKey::from_data(&blte);
+
+        let range = installation
+            .read_range(&encoding_key, 30, 40)
+            .await
+            .expect("Range should be read");
+        assert_eq!(range, data[30..70]);
+    }
+
+    #[tokio::test]
+    async fn test_read_range_encrypted_mid_file() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open_installation(&dir);
+        let data = test_data();
+
+        let blte = BlteBuilder::new()
+            .with_chunk_size_unchecked(16)
+            .with_encryption(EncryptionSpec::salsa20(KEY_NAME, [1, 2, 3, 4]), KEY)
+            .add_data(&data)
+            .expect("Data should be added")
+            .build()
+            .expect("BLTE should build");
+        let encoding_key = store_blte(&installation, blte).await;
+
+        // Without keys the encrypted chunks cannot be decoded
+        assert!(
+            installation
+                .read_range(&encoding_key, 50, 20)
+                .await
+                .is_err()
+        );
+
+        let mut key_store = TactKeyStore::new();
+        key_store.add(TactKey::new(KEY_NAME, KEY));
+
+        // Starts in chunk 3, so chunks 0-2 are never decrypted
+        let range = installation
+            .read_range_with_keys(&encoding_key, 50, 20, &key_store)
+            .await
+            .expect("Range should be read");
+        assert_eq!(range, data[50..70]);
+    }
+
+    #[tokio::test]
+    async fn test_initialize_loads_group_index() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open_installation(&dir);
+        let stored = multi_chunk_file(&installation).await;
+        installation
+            .index_manager
+            .write()
+            .await
+            .save_all()
+            .expect("Indices should save");
+        installation
+            .write_group_index()
+            .await
+            .expect("Group index should be written");
+
+        let reopened = open_installation(&dir);
+        reopened
+            .initialize()
+            .await
+            .expect("Installation should initialize");
+        let index_manager = reopened.index_manager.read().await;
+        assert!(index_manager.loaded_from_group_index());
+        assert!(index_manager.has_entry(&stored));
+        drop(index_manager);
+    }
+
+    #[tokio::test]
+    async fn test_initialize_skips_group_index_of_other_build() {
+        fn build_info(build_key: &str) -> BuildInfoFile {
+            let mut build_info = BuildInfoFile::new();
+            build_info
+                .set_active_build(
+                    "us",
+                    build_key,
+                    "fedcba0987654321fedcba0987654321",
+                    "2024-01-01T00:00:00Z",
+                )
+                .expect("Build should be recorded");
+            build_info
+        }
+
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open_installation(&dir);
+        installation
+            .set_build_info(build_info("abcdef1234567890abcdef1234567890"))
+            .await;
+        let stored = multi_chunk_file(&installation).await;
+        installation
+            .index_manager
+            .write()
+            .await
+            .save_all()
+            .expect("Indices should save");
+        installation
+            .write_group_index()
+            .await
+            .expect("Group index should be written");
+
+        // Rebuilt in place under the same idx versions
+        let reopened = open_installation(&dir);
+        reopened
+            .set_build_info(build_info("0123456789abcdef0123456789abcdef"))
+            .await;
+        reopened
+            .initialize()
+            .await
+            .expect("Installation should initialize");
+        let index_manager = reopened.index_manager.read().await;
+        assert!(!index_manager.loaded_from_group_index());
+        assert!(index_manager.has_entry(&stored));
+        drop(index_manager);
+    }
+}
@@ -57,9 +57,30 @@ pub mod lru;
 // Content resolution pipeline
 pub mod resolver;
 
+// Reverse lookup (encoding key -> files)
+pub mod reverse_index;
+
 // Installation management
 pub mod installation;
 
+// Listfile coverage of root files and local storage
+pub mod coverage;
+
+// Live and dead space in data archives
+pub mod archive_report;
+
+// Listfile search by regex or glob
+pub mod listfile;
+
+// Transactional multi-file writes
+pub mod transaction;
+
+// Resuming partially downloaded builds
+pub mod resume;
+
+// Verifying stored files and repairing them from a CDN
+pub mod repair;
+
 // Configuration
 pub mod config;
 
@@ -69,15 +90,38 @@ pub mod validation;
 // Build info parser (.build.info BPSV file)
 pub mod build_info;
 
+// Launcher flavor layout (.flavor.info and flavor directories)
+pub mod flavor;
+
+// Data directory resolution from game roots and flavor directories
+pub mod locate;
+
+// C ABI for the installation read path
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
 // Top-level storage manager (manages installations)
 mod storage_manager;
 
+// Bounded cache of decompressed chunks for range reads
+mod chunk_cache;
+
+pub use archive_report::ArchiveReport;
 pub use build_info::BuildInfoFile;
+pub use chunk_cache::DEFAULT_CHUNK_CACHE_BYTES;
 pub use config::StorageConfig;
 pub use container::AccessMode;
+pub use coverage::{CoverageReport, PrefixCoverage};
+pub use flavor::{FlavorInfoFile, InstallLayout};
 pub use index::IndexEntry;
 pub use installation::Installation;
+pub use listfile::{ListfileEntry, ListfileQuery, PatternSyntax};
+pub use locate::{ResolvedInstallation, resolve_installation};
+pub use repair::{RepairFailure, RepairReport};
+pub use resolver::{ContentResolver, ResolutionMetrics, ResolutionSource};
+pub use resume::{ResumeFailure, ResumeReport};
 pub use storage_manager::Storage;
+pub use transaction::WriteTransaction;
 
 /// Result type for storage operations.
 pub type Result<T> = std::result::Result<T, StorageError>;
@@ -132,6 +176,10 @@ pub enum StorageError {
     #[error("Cache error: {0}")]
     Cache(String),
 
+    /// CDN download failed.
+    #[error("CDN error: {0}")]
+    Cdn(String),
+
     /// Corruption detected.
     #[error("Data corruption detected: {0}")]
     Corruption(String),
@@ -259,3 +307,24 @@ pub const fn translate_error_code(casc_code: CascErrorCode) -> TactErrorCode {
         _ => 1,
     }
 }
+
+impl StorageError {
+    /// CASC error code for this error.
+    ///
+    /// Only the variants documented with a TACT error code have one.
+    pub const fn casc_error_code(&self) -> Option<CascErrorCode> {
+        match self {
+            Self::ResourceExhausted(_) | Self::TruncatedRead(_) => Some(3),
+            Self::ContainerLocked(_) => Some(9),
+            _ => None,
+        }
+    }
+
+    /// TACT error code for this error, 1 (unknown) without a CASC code.
+    pub const fn tact_error_code(&self) -> TactErrorCode {
+        match self.casc_error_code() {
+            Some(code) => translate_error_code(code),
+            None => translate_error_code(CascErrorCode::MAX),
+        }
+    }
+}
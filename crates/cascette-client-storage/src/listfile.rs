@@ -0,0 +1,209 @@
+//! Searching the community listfile by regex or glob
+//!
+//! The listfile maps `FileDataID`s to paths, one `<fdid>;<path>` line per
+//! file. A [`ListfileQuery`] matches paths against a regular expression or a
+//! glob such as `interface/**/*.blp`:
+//!
+//! - a regex matches anywhere in the path, as with `grep`
+//! - a glob matches the whole path; `*` stays within one directory and
+//!   `**` spans any number of them
+//!
+//! Either can ignore case. Like coverage reports, searches read the
+//! listfile line by line and keep only the matches.
+
+use crate::{Result, StorageError};
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+use std::io::BufRead;
+
+/// How a search pattern is interpreted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PatternSyntax {
+    /// Regular expression matched anywhere in the path
+    #[default]
+    Regex,
+    /// Glob matched against the whole path
+    Glob,
+}
+
+/// A listfile line matched by a search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListfileEntry {
+    /// `FileDataID` of the file
+    pub file_data_id: u32,
+    /// Path as written in the listfile
+    pub path: String,
+}
+
+/// Compiled listfile search pattern
+#[derive(Debug, Clone)]
+pub struct ListfileQuery {
+    matcher: Matcher,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Regex(Regex),
+    Glob(GlobMatcher),
+}
+
+impl ListfileQuery {
+    /// Compile `pattern` with the given syntax
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pattern is not a valid regex or glob.
+    pub fn new(pattern: &str, syntax: PatternSyntax, ignore_case: bool) -> Result<Self> {
+        let matcher = match syntax {
+            PatternSyntax::Regex => RegexBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .build()
+                .map(Matcher::Regex)
+                .map_err(|e| StorageError::Config(format!("invalid regex {pattern:?}: {e}")))?,
+            PatternSyntax::Glob => GlobBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .literal_separator(true)
+                .build()
+                .map(|glob| Matcher::Glob(glob.compile_matcher()))
+                .map_err(|e| StorageError::Config(format!("invalid glob {pattern:?}: {e}")))?,
+        };
+        Ok(Self { matcher })
+    }
+
+    /// Check whether a listfile path matches
+    pub fn is_match(&self, path: &str) -> bool {
+        match &self.matcher {
+            Matcher::Regex(regex) => regex.is_match(path),
+            Matcher::Glob(glob) => glob.is_match(path),
+        }
+    }
+
+    /// Find matching entries of `listfile`, in listfile order
+    ///
+    /// At most `limit` entries are returned; reading stops once the limit
+    /// is reached. Empty and malformed lines are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading the listfile fails.
+    pub fn search<R: BufRead>(
+        &self,
+        listfile: R,
+        limit: Option<usize>,
+    ) -> Result<Vec<ListfileEntry>> {
+        let mut matches = Vec::new();
+        if limit == Some(0) {
+            return Ok(matches);
+        }
+        for line in listfile.lines() {
+            let line = line.map_err(StorageError::Io)?;
+            let Some((file_data_id, path)) = parse_line(&line) else {
+                continue;
+            };
+            if !self.is_match(path) {
+                continue;
+            }
+            matches.push(ListfileEntry {
+                file_data_id,
+                path: path.to_string(),
+            });
+            if limit.is_some_and(|limit| matches.len() >= limit) {
+                break;
+            }
+        }
+        Ok(matches)
+    }
+}
+
+/// Split a `<fdid>;<path>` line, trimming surrounding whitespace
+///
+/// Returns `None` for empty and malformed lines.
+pub(crate) fn parse_line(line: &str) -> Option<(u32, &str)> {
+    let (fdid, path) = line.trim().split_once(';')?;
+    Some((fdid.trim().parse().ok()?, path))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const LISTFILE: &str = "1;Interface/Icons/Ability_Ambush.blp\n\
+                            2;interface/glues/models/ui.m2\n\
+                            not a line\n\
+                            \n\
+                            3;Interface/Glues/Credits/Logo.BLP\n\
+                            4;World/Maps/Azeroth/Azeroth.wdt\n\
+                            5;interface.blp\n";
+
+    fn search(pattern: &str, syntax: PatternSyntax, ignore_case: bool) -> Vec<u32> {
+        ListfileQuery::new(pattern, syntax, ignore_case)
+            .expect("Pattern should compile")
+            .search(Cursor::new(LISTFILE), None)
+            .expect("Search should succeed")
+            .into_iter()
+            .map(|entry| entry.file_data_id)
+            .collect()
+    }
+
+    #[test]
+    fn test_glob_matches_whole_path_across_directories() {
+        assert_eq!(
+            search("Interface/**/*.blp", PatternSyntax::Glob, false),
+            [1]
+        );
+        assert_eq!(
+            search("interface/**/*.blp", PatternSyntax::Glob, true),
+            [1, 3]
+        );
+        // `*` does not cross directories
+        assert_eq!(
+            search("interface/*.blp", PatternSyntax::Glob, true),
+            &[] as &[u32]
+        );
+        assert_eq!(search("*.blp", PatternSyntax::Glob, true), [5]);
+    }
+
+    #[test]
+    fn test_regex_matches_anywhere_and_is_the_default() {
+        assert_eq!(PatternSyntax::default(), PatternSyntax::Regex);
+        assert_eq!(search(r"\.blp$", PatternSyntax::Regex, false), [1, 5]);
+        assert_eq!(search(r"\.blp$", PatternSyntax::Regex, true), [1, 3, 5]);
+        assert_eq!(search("glues", PatternSyntax::Regex, false), [2]);
+    }
+
+    #[test]
+    fn test_limit_stops_the_search() {
+        let query = ListfileQuery::new("interface", PatternSyntax::Regex, true)
+            .expect("Regex should compile");
+        let found = query
+            .search(Cursor::new(LISTFILE), Some(2))
+            .expect("Search should succeed");
+        assert_eq!(
+            found,
+            [
+                ListfileEntry {
+                    file_data_id: 1,
+                    path: "Interface/Icons/Ability_Ambush.blp".to_string(),
+                },
+                ListfileEntry {
+                    file_data_id: 2,
+                    path: "interface/glues/models/ui.m2".to_string(),
+                },
+            ]
+        );
+        assert!(
+            query
+                .search(Cursor::new(LISTFILE), Some(0))
+                .expect("Search should succeed")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_invalid_patterns_are_rejected() {
+        assert!(ListfileQuery::new("(", PatternSyntax::Regex, false).is_err());
+        assert!(ListfileQuery::new("[", PatternSyntax::Glob, false).is_err());
+    }
+}
@@ -0,0 +1,343 @@
+//! Locate the CASC `Data` directory from a user-supplied path.
+//!
+//! Users point tools at whatever directory is at hand: the game root
+//! (`World of Warcraft/`), a launcher flavor directory (`_retail_/`), the
+//! `Data` directory that [`Installation::open`](crate::Installation::open)
+//! expects, or the `Data/data` directory holding the `.idx` files.
+//! [`resolve_installation`] probes the path and its neighbours and
+//! returns the `Data` directory together with what it found along the way.
+
+use std::path::{Path, PathBuf};
+
+use crate::flavor::{FLAVOR_INFO_FILE, FlavorInfoFile};
+use crate::{BUILD_INFO_FILE, DATA_DIR, DEFAULT_DATA_DIR, Result, StorageError};
+
+/// Which kind of directory the caller passed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocationKind {
+    /// The game root, holding `Data/` and `.build.info`.
+    GameRoot,
+    /// A launcher flavor directory next to `Data/`.
+    Flavor,
+    /// The `Data` directory itself.
+    Data,
+    /// The `Data/data` directory holding `.idx` and `.data` files.
+    DataData,
+}
+
+/// A launcher flavor directory found in the game root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlavorCandidate {
+    /// Directory name relative to the root (e.g. `_retail_`).
+    pub directory: String,
+    /// Product code from its `.flavor.info`, if present and readable.
+    pub product: Option<String>,
+}
+
+/// Result of resolving a user-supplied path.
+#[derive(Debug, Clone)]
+pub struct ResolvedInstallation {
+    /// Kind of directory that was passed in.
+    pub kind: LocationKind,
+    /// Game root directory.
+    pub root: PathBuf,
+    /// `Data` directory to open with [`Installation::open`](crate::Installation::open).
+    pub data_dir: PathBuf,
+    /// `.build.info` in the root, if it exists.
+    pub build_info: Option<PathBuf>,
+    /// Flavor directories found in the root, sorted by name.
+    pub flavors: Vec<FlavorCandidate>,
+    /// The flavor the path points at: the flavor directory that was passed
+    /// in, or the only flavor in the root.
+    pub flavor: Option<FlavorCandidate>,
+}
+
+impl ResolvedInstallation {
+    /// The selected flavor, or an error listing the candidates.
+    pub fn require_flavor(&self) -> Result<&FlavorCandidate> {
+        if let Some(flavor) = &self.flavor {
+            return Ok(flavor);
+        }
+        if self.flavors.is_empty() {
+            return Err(StorageError::Installation(format!(
+                "no flavor directory found in {}",
+                self.root.display()
+            )));
+        }
+
+        let candidates: Vec<String> = self
+            .flavors
+            .iter()
+            .map(|flavor| match &flavor.product {
+                Some(product) => format!("{} ({product})", flavor.directory),
+                None => flavor.directory.clone(),
+            })
+            .collect();
+        Err(StorageError::Installation(format!(
+            "{} holds several flavors, pass one of them instead: {}",
+            self.root.display(),
+            candidates.join(", ")
+        )))
+    }
+}
+
+/// Check whether `path` is a CASC `Data` directory.
+///
+/// A `Data` directory has a `data/` subdirectory holding at least one
+/// `.idx` file.
+pub fn is_valid_data_dir(path: &Path) -> bool {
+    has_index_files(&path.join(DATA_DIR))
+}
+
+/// Check whether `dir` directly holds `.idx` files.
+fn has_index_files(dir: &Path) -> bool {
+    std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries.filter_map(std::result::Result::ok).any(|entry| {
+            entry
+                .path()
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("idx"))
+        })
+    })
+}
+
+/// Whether a directory name follows the launcher's `_flavor_` convention.
+fn is_flavor_name(name: &str) -> bool {
+    name.len() > 2 && name.starts_with('_') && name.ends_with('_')
+}
+
+/// Read the flavor candidate in `dir`, if it is a flavor directory.
+fn flavor_candidate(dir: &Path) -> Option<FlavorCandidate> {
+    let directory = dir.file_name()?.to_str()?.to_string();
+    let flavor_info = dir.join(FLAVOR_INFO_FILE);
+    if !is_flavor_name(&directory) && !flavor_info.is_file() {
+        return None;
+    }
+
+    let product = std::fs::read_to_string(&flavor_info)
+        .ok()
+        .and_then(|content| FlavorInfoFile::parse_str(&content).ok())
+        .and_then(|info| info.product_flavor().map(str::to_string));
+    Some(FlavorCandidate { directory, product })
+}
+
+/// Flavor directories in `root`, sorted by name.
+fn find_flavors(root: &Path) -> Vec<FlavorCandidate> {
+    let mut flavors: Vec<FlavorCandidate> = std::fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .filter_map(std::result::Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .filter_map(|path| flavor_candidate(&path))
+                .collect()
+        })
+        .unwrap_or_default();
+    flavors.sort_by(|a, b| a.directory.cmp(&b.directory));
+    flavors
+}
+
+/// Describe whether `dir` is a `Data` directory, for error messages.
+fn describe_data_dir(dir: &Path) -> String {
+    let data = dir.join(DATA_DIR);
+    if !dir.is_dir() {
+        format!("{}: missing", dir.display())
+    } else if !data.is_dir() {
+        format!("{}: missing", data.display())
+    } else {
+        format!("{}: no .idx files", data.display())
+    }
+}
+
+/// Find the `Data` directory for a game root, flavor directory, `Data`
+/// directory or `Data/data` directory.
+///
+/// The path is probed in that order of specificity: first as a `Data`
+/// directory, then as `Data/data`, then as a flavor directory whose parent
+/// holds `Data/`, and last as a game root. If nothing matches, the error
+/// lists every location that was probed and what was missing there.
+///
+/// The path is canonicalized first, so relative paths such as `Data`
+/// resolve against the current directory and the returned paths are
+/// absolute.
+pub fn resolve_installation(path: &Path) -> Result<ResolvedInstallation> {
+    if !path.is_dir() {
+        return Err(StorageError::Installation(format!(
+            "{} is not a directory",
+            path.display()
+        )));
+    }
+    let canonical = path.canonicalize().map_err(|e| {
+        StorageError::Installation(format!("cannot resolve {}: {e}", path.display()))
+    })?;
+    let path = canonical.as_path();
+
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+    let located = if is_valid_data_dir(path) {
+        parent.map(|root| (LocationKind::Data, root.to_path_buf(), path.to_path_buf()))
+    } else if has_index_files(path)
+        && let Some(data_dir) = parent
+        && is_valid_data_dir(data_dir)
+    {
+        data_dir.parent().map(|root| {
+            (
+                LocationKind::DataData,
+                root.to_path_buf(),
+                data_dir.to_path_buf(),
+            )
+        })
+    } else if let Some(root) = parent
+        && flavor_candidate(path).is_some()
+        && is_valid_data_dir(&root.join(DEFAULT_DATA_DIR))
+    {
+        Some((
+            LocationKind::Flavor,
+            root.to_path_buf(),
+            root.join(DEFAULT_DATA_DIR),
+        ))
+    } else if is_valid_data_dir(&path.join(DEFAULT_DATA_DIR)) {
+        Some((
+            LocationKind::GameRoot,
+            path.to_path_buf(),
+            path.join(DEFAULT_DATA_DIR),
+        ))
+    } else {
+        None
+    };
+
+    let Some((kind, root, data_dir)) = located else {
+        let mut probes = vec![
+            format!("as Data directory: {}", describe_data_dir(path)),
+            format!(
+                "as game root: {}",
+                describe_data_dir(&path.join(DEFAULT_DATA_DIR))
+            ),
+        ];
+        if let Some(root) = parent {
+            probes.push(format!(
+                "as flavor directory: {}",
+                describe_data_dir(&root.join(DEFAULT_DATA_DIR))
+            ));
+        }
+        let build_info = path.join(BUILD_INFO_FILE);
+        if !build_info.is_file() {
+            probes.push(format!("{}: missing", build_info.display()));
+        }
+        return Err(StorageError::Installation(format!(
+            "no CASC storage found at {}; probed {}",
+            path.display(),
+            probes.join("; ")
+        )));
+    };
+
+    let build_info = Some(root.join(BUILD_INFO_FILE)).filter(|p| p.is_file());
+    let flavors = find_flavors(&root);
+    let flavor = if kind == LocationKind::Flavor {
+        flavor_candidate(path)
+    } else if let [only] = flavors.as_slice() {
+        Some(only.clone())
+    } else {
+        None
+    };
+
+    Ok(ResolvedInstallation {
+        kind,
+        root,
+        data_dir,
+        build_info,
+        flavors,
+        flavor,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Game root with a `Data` directory and the given flavor directories.
+    fn game_root(flavors: &[(&str, &str)]) -> TempDir {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("Data/data")).expect("mkdir");
+        std::fs::create_dir_all(root.join("Data/config")).expect("mkdir");
+        std::fs::write(root.join("Data/data/0000000001.idx"), []).expect("write");
+        std::fs::write(root.join(BUILD_INFO_FILE), "Branch!STRING:0\n").expect("write");
+        for (directory, product) in flavors {
+            std::fs::create_dir_all(root.join(directory)).expect("mkdir");
+            std::fs::write(
+                root.join(directory).join(FLAVOR_INFO_FILE),
+                FlavorInfoFile::new(product).to_string(),
+            )
+            .expect("write");
+        }
+        dir
+    }
+
+    fn retail() -> FlavorCandidate {
+        FlavorCandidate {
+            directory: "_retail_".to_string(),
+            product: Some("wow".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_resolve_each_input_shape() {
+        let dir = game_root(&[("_retail_", "wow")]);
+        let root = &dir.path().canonicalize().expect("canonicalize");
+
+        for (input, kind) in [
+            (root.clone(), LocationKind::GameRoot),
+            (root.join("_retail_"), LocationKind::Flavor),
+            (root.join("Data"), LocationKind::Data),
+            (root.join("Data/data"), LocationKind::DataData),
+            // Non-canonical paths resolve to the same root
+            (root.join("Data/../Data"), LocationKind::Data),
+            (root.join("_retail_/../Data/data"), LocationKind::DataData),
+        ] {
+            let resolved = resolve_installation(&input).expect("should resolve");
+            assert_eq!(resolved.kind, kind, "{}", input.display());
+            assert_eq!(&resolved.root, root);
+            assert_eq!(resolved.data_dir, root.join("Data"));
+            assert_eq!(resolved.build_info, Some(root.join(BUILD_INFO_FILE)));
+            assert_eq!(resolved.flavors, [retail()]);
+            assert_eq!(resolved.require_flavor().expect("one flavor"), &retail());
+        }
+    }
+
+    #[test]
+    fn test_resolve_with_several_flavors() {
+        let dir = game_root(&[("_retail_", "wow"), ("_classic_", "wow_classic")]);
+        let root = &dir.path().canonicalize().expect("canonicalize");
+
+        // The data directory is shared, only the flavor is ambiguous
+        let resolved = resolve_installation(root).expect("should resolve");
+        assert_eq!(resolved.data_dir, root.join("Data"));
+        assert_eq!(resolved.flavors.len(), 2);
+        assert_eq!(resolved.flavor, None);
+        let err = resolved
+            .require_flavor()
+            .expect_err("ambiguous")
+            .to_string();
+        assert!(err.contains("_classic_ (wow_classic)"), "{err}");
+        assert!(err.contains("_retail_ (wow)"), "{err}");
+
+        let resolved = resolve_installation(&root.join("_retail_")).expect("should resolve");
+        assert_eq!(resolved.flavor, Some(retail()));
+    }
+
+    #[test]
+    fn test_resolve_invalid_directory() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir_all(dir.path().join("Data/data")).expect("mkdir");
+
+        let err = resolve_installation(dir.path())
+            .expect_err("no index files")
+            .to_string();
+        assert!(err.contains("no .idx files"), "{err}");
+        assert!(err.contains(BUILD_INFO_FILE), "{err}");
+
+        assert!(resolve_installation(&dir.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! Verifying stored files and repairing them from a CDN
+//!
+//! [`Installation::verify_and_repair`](crate::Installation::verify_and_repair)
+//! reads every file the local indices reference and checks its BLTE data
+//! against the encoding key in the file's 30-byte local header. Index
+//! entries only keep the first 9 bytes of most keys, so the header is also
+//! where the full key for a download comes from; an entry whose header is
+//! unreadable or names another key cannot be repaired.
+//!
+//! Files can also be missing altogether: keys the caller expects, such as
+//! those of a download manifest, are looked up in the indices too.
+//!
+//! Local storage alone cannot recreate corrupt or missing data. With
+//! repairs enabled, these files are downloaded through the resolver's CDN
+//! fallback, checked, and written back under a new index entry. Files that
+//! cannot be repaired are listed with the reason.
+
+use crate::{Result, StorageError};
+use serde::Serialize;
+use std::fmt;
+
+/// A stored file that failed verification and was not repaired
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RepairFailure {
+    /// Hex encoding key, truncated to the index key if the full key is unknown
+    pub encoding_key: String,
+    /// Why the file was not repaired
+    pub reason: String,
+}
+
+/// Outcome of verifying and repairing local storage
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RepairReport {
+    /// Index entries checked
+    pub checked: u64,
+    /// Entries whose data matched their encoding key
+    pub intact: u64,
+    /// Expected encoding keys without an index entry
+    pub missing: u64,
+    /// Hex encoding keys of corrupt or missing files downloaded again
+    pub repaired: Vec<String>,
+    /// Files that failed verification and were not repaired
+    pub unrecoverable: Vec<RepairFailure>,
+}
+
+impl RepairReport {
+    /// Check whether every checked file is now intact
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.unrecoverable.is_empty()
+    }
+
+    /// Render the report as pretty-printed JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| StorageError::InvalidFormat(format!("failed to serialize report: {e}")))
+    }
+}
+
+impl fmt::Display for RepairReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Entries checked:     {}", self.checked)?;
+        writeln!(f, "Intact:              {}", self.intact)?;
+        writeln!(f, "Missing:             {}", self.missing)?;
+        writeln!(f, "Repaired:            {}", self.repaired.len())?;
+        for key in &self.repaired {
+            writeln!(f, "  {key}")?;
+        }
+        writeln!(f, "Unrecoverable:       {}", self.unrecoverable.len())?;
+        for failure in &self.unrecoverable {
+            writeln!(f, "  {}: {}", failure.encoding_key, failure.reason)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_renders_text_and_json() {
+        let report = RepairReport {
+            checked: 3,
+            intact: 1,
+            missing: 1,
+            repaired: vec!["aa".to_string()],
+            unrecoverable: vec![RepairFailure {
+                encoding_key: "bb".to_string(),
+                reason: "no CDN fallback is configured".to_string(),
+            }],
+        };
+        assert!(!report.is_healthy());
+
+        let text = report.to_string();
+        assert!(text.contains("Missing:             1\n"));
+        assert!(text.contains("Repaired:            1\n  aa\n"));
+        assert!(text.contains("  bb: no CDN fallback is configured"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&report.to_json().expect("Report should serialize"))
+                .expect("Report should be valid JSON");
+        assert_eq!(json["intact"], 1);
+        assert_eq!(json["unrecoverable"][0]["encoding_key"], "bb");
+    }
+}
@@ -1,15 +1,88 @@
 //! Content resolution pipeline
 //!
 //! Resolves file paths to actual content through the CASC lookup chain.
-
+//! With the `cdn-fallback` feature, a resolver built with
+//! `ContentResolver::with_cdn_fallback` also lets an
+//! [`Installation`](crate::Installation) download files missing from local
+//! storage.
+
+#[cfg(feature = "cdn-fallback")]
+use crate::build_info::{BuildInfoEntry, BuildInfoFile};
+use crate::reverse_index::{FileOwner, REVERSE_INDEX_EXTENSION, ReverseIndex};
 use crate::{Result, StorageError};
 use cascette_crypto::Jenkins96;
 use cascette_crypto::{ContentKey, EncodingKey};
 use cascette_formats::{encoding::EncodingFile, root::RootFile};
+#[cfg(feature = "cdn-fallback")]
+use cascette_protocol::{ArchiveLocator, CdnClient, CdnEndpoint, ContentType};
 use dashmap::DashMap;
 use parking_lot::RwLock;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// Cached reverse index
+#[derive(Default)]
+struct ReverseIndexSlot {
+    index: Option<Arc<ReverseIndex>>,
+    /// Bumped whenever the index is replaced or dropped, so a build that
+    /// overlapped a reload does not cache a stale index
+    generation: u64,
+    /// Build config content key of the loaded root file, if known
+    root_key: Option<ContentKey>,
+    /// Build config content key of the loaded encoding file, if known
+    encoding_key: Option<ContentKey>,
+}
+
+impl ReverseIndexSlot {
+    /// Drop the index after the root or encoding file changed
+    fn invalidate(&mut self) {
+        self.index = None;
+        self.generation += 1;
+    }
+}
+
+/// CDN that files missing from local storage are downloaded from
+#[cfg(feature = "cdn-fallback")]
+struct CdnFallback {
+    client: CdnClient,
+    endpoint: CdnEndpoint,
+    /// Archives of the build's CDN config, searched before loose files
+    locator: ArchiveLocator,
+}
+
+/// CDN endpoint named by a `.build.info` entry
+#[cfg(feature = "cdn-fallback")]
+fn cdn_endpoint_of(build: &BuildInfoEntry<'_>) -> Result<CdnEndpoint> {
+    let no_cdn = || StorageError::Config(".build.info names no CDN for the active build".into());
+    let path = build
+        .cdn_path()
+        .filter(|path| !path.is_empty())
+        .ok_or_else(no_cdn)?;
+
+    // Servers are URLs such as `http://host/?maxhosts=4`, hosts bare names
+    let (scheme, host) = if let Some(server) = build.cdn_servers().first() {
+        let (scheme, rest) = server
+            .split_once("://")
+            .map_or((None, *server), |(scheme, rest)| (Some(scheme), rest));
+        (scheme, rest.split(['/', '?']).next().unwrap_or_default())
+    } else {
+        (None, build.cdn_hosts().first().copied().unwrap_or_default())
+    };
+    if host.is_empty() {
+        return Err(no_cdn());
+    }
+
+    Ok(CdnEndpoint {
+        host: host.to_string(),
+        path: path.to_string(),
+        product_path: None,
+        scheme: scheme.map(str::to_string),
+        is_fallback: false,
+        strict: false,
+        max_hosts: None,
+    })
+}
 
 /// Resolves file paths to content through the CASC lookup chain
 pub struct ContentResolver {
@@ -23,6 +96,15 @@ pub struct ContentResolver {
     content_cache: DashMap<ContentKey, EncodingKey>,
     /// `FileDataID` to content key mapping (for modern root files)
     file_data_id_map: DashMap<u32, ContentKey>,
+    /// Encoding key -> files reverse index, built on demand
+    reverse_index: RwLock<ReverseIndexSlot>,
+    /// Directory the reverse index is persisted in, if any
+    reverse_index_dir: Option<PathBuf>,
+    /// CDN to download files missing from local storage, if enabled
+    #[cfg(feature = "cdn-fallback")]
+    cdn_fallback: Option<CdnFallback>,
+    /// Where file reads were served from
+    metrics: RwLock<ResolutionMetrics>,
 }
 
 impl ContentResolver {
@@ -34,15 +116,209 @@ impl ContentResolver {
             path_cache: DashMap::new(),
             content_cache: DashMap::new(),
             file_data_id_map: DashMap::new(),
+            reverse_index: RwLock::new(ReverseIndexSlot::default()),
+            reverse_index_dir: None,
+            #[cfg(feature = "cdn-fallback")]
+            cdn_fallback: None,
+            metrics: RwLock::new(ResolutionMetrics::default()),
         }
     }
 
+    /// Fall back to downloading from `endpoint` when local storage misses
+    ///
+    /// Files are looked up in the archives set with
+    /// [`Self::with_cdn_archives`] and fetched with a range request; files
+    /// in no archive are fetched from the CDN's loose `data/<ekey>` path.
+    /// The installation stores each downloaded file locally, so later reads
+    /// are served from disk.
+    #[cfg(feature = "cdn-fallback")]
+    #[must_use]
+    pub fn with_cdn_fallback(mut self, cdn_client: CdnClient, endpoint: CdnEndpoint) -> Self {
+        let archives = self
+            .cdn_fallback
+            .take()
+            .map(|fallback| fallback.locator.archives().to_vec())
+            .unwrap_or_default();
+        self.cdn_fallback = Some(CdnFallback {
+            client: cdn_client,
+            endpoint,
+            locator: ArchiveLocator::new(archives),
+        });
+        self
+    }
+
+    /// Search `archives`, the hex archive keys of the build's CDN config,
+    /// for files downloaded by the CDN fallback
+    ///
+    /// Most files are only stored inside archives. Without archives the
+    /// fallback finds loose files only. Has no effect without
+    /// [`Self::with_cdn_fallback`].
+    #[cfg(feature = "cdn-fallback")]
+    #[must_use]
+    pub fn with_cdn_archives(mut self, archives: Vec<String>) -> Self {
+        if let Some(fallback) = &mut self.cdn_fallback {
+            fallback.locator = ArchiveLocator::new(archives);
+        }
+        self
+    }
+
+    /// Fall back to the CDN of the active build in a `.build.info`
+    ///
+    /// The host is the first of the build's `CDN Servers`, which carry
+    /// their scheme, or else the first of its `CDN Hosts` over HTTPS,
+    /// under the build's `CDN Path`. The build's CDN config is downloaded
+    /// from there and its archives are searched as with
+    /// [`Self::with_cdn_archives`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `StorageError::Config` if there is no active build or it
+    /// names no CDN, and `StorageError::Cdn` if the CDN config cannot be
+    /// downloaded or parsed.
+    #[cfg(feature = "cdn-fallback")]
+    pub async fn with_cdn_fallback_from_build_info(
+        self,
+        cdn_client: CdnClient,
+        build_info: &BuildInfoFile,
+    ) -> Result<Self> {
+        let build = build_info
+            .active_entry()
+            .ok_or_else(|| StorageError::Config(".build.info has no active build".to_string()))?;
+        let endpoint = cdn_endpoint_of(&build)?;
+
+        let archives = match build.cdn_key().filter(|key| !key.is_empty()) {
+            Some(cdn_key) => {
+                let key = hex::decode(cdn_key).map_err(|e| {
+                    StorageError::Config(format!("invalid CDN Key {cdn_key:?}: {e}"))
+                })?;
+                let data = cdn_client
+                    .download(&endpoint, ContentType::Config, &key)
+                    .await
+                    .map_err(|e| {
+                        StorageError::Cdn(format!(
+                            "failed to download CDN config {cdn_key} from {}: {e}",
+                            endpoint.host
+                        ))
+                    })?;
+                cascette_formats::config::CdnConfig::parse(data.as_slice())
+                    .map_err(|e| StorageError::Cdn(format!("invalid CDN config {cdn_key}: {e}")))?
+                    .archives()
+                    .into_iter()
+                    .map(|archive| archive.content_key)
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        info!(
+            "Using CDN fallback {} with {} archives from .build.info",
+            endpoint.host,
+            archives.len()
+        );
+        Ok(self
+            .with_cdn_fallback(cdn_client, endpoint)
+            .with_cdn_archives(archives))
+    }
+
+    /// Persist the reverse index in `dir`
+    ///
+    /// A built index is saved there under a name derived from the content
+    /// keys of the root and encoding files, and loaded instead of rebuilt
+    /// while the same files are loaded. Indices built from other files are
+    /// removed when a new one is saved.
+    ///
+    /// Only files loaded with [`Self::load_root_file_with_key`] and
+    /// [`Self::load_encoding_file_with_key`] are persisted; the keys come
+    /// from the build config rather than from hashing the files.
+    #[must_use]
+    pub fn with_reverse_index_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.reverse_index_dir = Some(dir.into());
+        self
+    }
+
+    /// Check if a CDN fallback is configured
+    pub const fn has_cdn_fallback(&self) -> bool {
+        #[cfg(feature = "cdn-fallback")]
+        return self.cdn_fallback.is_some();
+        #[cfg(not(feature = "cdn-fallback"))]
+        false
+    }
+
+    /// Download the BLTE-encoded file for `key` from the fallback CDN
+    ///
+    /// Returns `None` without a CDN fallback. The data is not checked
+    /// against `key`.
+    #[cfg(feature = "cdn-fallback")]
+    pub(crate) async fn download_from_cdn(&self, key: &EncodingKey) -> Result<Option<Vec<u8>>> {
+        let Some(fallback) = &self.cdn_fallback else {
+            return Ok(None);
+        };
+
+        debug!(
+            "Downloading {} from CDN {}",
+            hex::encode(key.as_bytes()),
+            fallback.endpoint.host
+        );
+        fallback
+            .locator
+            .download(&fallback.client, &fallback.endpoint, key.as_bytes())
+            .await
+            .map(Some)
+            .map_err(|e| {
+                StorageError::Cdn(format!(
+                    "failed to download {} from {}: {e}",
+                    hex::encode(key.as_bytes()),
+                    fallback.endpoint.host
+                ))
+            })
+    }
+
+    /// Download the BLTE-encoded file for `key` from the fallback CDN
+    ///
+    /// Always `None`: CDN downloads need the `cdn-fallback` feature.
+    #[cfg(not(feature = "cdn-fallback"))]
+    #[allow(clippy::unused_async)]
+    pub(crate) async fn download_from_cdn(&self, key: &EncodingKey) -> Result<Option<Vec<u8>>> {
+        let _ = key;
+        Ok(None)
+    }
+
+    /// Record where a file read was served from
+    pub(crate) fn record_resolution(&self, source: ResolutionSource) {
+        let mut metrics = self.metrics.write();
+        match source {
+            ResolutionSource::Local => metrics.local += 1,
+            ResolutionSource::Cdn => metrics.cdn += 1,
+        }
+        metrics.last_source = Some(source);
+    }
+
+    /// Get counts of file reads by where they were served from
+    pub fn metrics(&self) -> ResolutionMetrics {
+        *self.metrics.read()
+    }
+
     /// Load root file for path resolution
     ///
     /// # Errors
     ///
     /// Returns error if root file cannot be parsed
     pub fn load_root_file(&self, data: &[u8]) -> Result<()> {
+        self.load_root(data, None)
+    }
+
+    /// Load the root file with content key `root_key` from the build config
+    ///
+    /// The key identifies the persisted reverse index for this file.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if root file cannot be parsed
+    pub fn load_root_file_with_key(&self, data: &[u8], root_key: ContentKey) -> Result<()> {
+        self.load_root(data, Some(root_key))
+    }
+
+    fn load_root(&self, data: &[u8], root_key: Option<ContentKey>) -> Result<()> {
         info!("Loading root file ({} bytes)", data.len());
 
         // Parse root file
@@ -66,6 +342,11 @@ impl ContentResolver {
         }
 
         *self.root_file.write() = Some(root);
+        {
+            let mut slot = self.reverse_index.write();
+            slot.invalidate();
+            slot.root_key = root_key;
+        }
         Ok(())
     }
 
@@ -75,6 +356,22 @@ impl ContentResolver {
     ///
     /// Returns error if encoding file cannot be parsed
     pub fn load_encoding_file(&self, data: &[u8]) -> Result<()> {
+        self.load_encoding(data, None)
+    }
+
+    /// Load the encoding file with content key `encoding_key` from the
+    /// build config
+    ///
+    /// The key identifies the persisted reverse index for this file.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if encoding file cannot be parsed
+    pub fn load_encoding_file_with_key(&self, data: &[u8], encoding_key: ContentKey) -> Result<()> {
+        self.load_encoding(data, Some(encoding_key))
+    }
+
+    fn load_encoding(&self, data: &[u8], encoding_key: Option<ContentKey>) -> Result<()> {
         info!("Loading encoding file ({} bytes)", data.len());
 
         let encoding = EncodingFile::parse(data)
@@ -98,6 +395,11 @@ impl ContentResolver {
             cached_entries
         );
         *self.encoding_file.write() = Some(encoding);
+        {
+            let mut slot = self.reverse_index.write();
+            slot.invalidate();
+            slot.encoding_key = encoding_key;
+        }
         Ok(())
     }
 
@@ -133,6 +435,11 @@ impl ContentResolver {
         self.file_data_id_map.get(&fdid).map(|v| *v)
     }
 
+    /// Whether an encoding file has been loaded
+    pub fn has_encoding_file(&self) -> bool {
+        self.encoding_file.read().is_some()
+    }
+
     /// Resolve a content key to an encoding key
     pub fn resolve_content_key(&self, key: &ContentKey) -> Option<EncodingKey> {
         // Check cache first
@@ -192,6 +499,138 @@ impl ContentResolver {
         })
     }
 
+    /// Get the encoding key -> files reverse index, building it if needed
+    ///
+    /// The index is built from the loaded root and encoding files on first
+    /// use and dropped whenever either file is reloaded. An index whose
+    /// build overlapped a reload is returned but not cached. With
+    /// [`with_reverse_index_dir`](Self::with_reverse_index_dir) a saved
+    /// index for the loaded files is read instead of building one, and a
+    /// built index is saved.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the root or encoding file has not been loaded
+    pub fn reverse_index(&self) -> Result<Arc<ReverseIndex>> {
+        let (generation, persisted) = {
+            let slot = self.reverse_index.read();
+            if let Some(index) = &slot.index {
+                return Ok(Arc::clone(index));
+            }
+            (slot.generation, self.reverse_index_path(&slot))
+        };
+
+        if let Some(path) = persisted.as_deref().filter(|path| path.exists()) {
+            match ReverseIndex::load(path) {
+                Ok(index) => {
+                    debug!("Loaded reverse index from {}", path.display());
+                    let index = Arc::new(index);
+                    self.publish_reverse_index(&index, generation);
+                    return Ok(index);
+                }
+                Err(e) => warn!(
+                    "Rebuilding unreadable reverse index {}: {e}",
+                    path.display()
+                ),
+            }
+        }
+
+        let root_guard = self.root_file.read();
+        let encoding_guard = self.encoding_file.read();
+        let (Some(root), Some(encoding)) = (root_guard.as_ref(), encoding_guard.as_ref()) else {
+            return Err(StorageError::Resolver(
+                "Root and encoding files must be loaded to build the reverse index".to_string(),
+            ));
+        };
+        let index = Arc::new(ReverseIndex::build(root, encoding));
+        drop(encoding_guard);
+        drop(root_guard);
+
+        info!(
+            "Built reverse index: {} encoding keys, {} content keys",
+            index.ekey_count(),
+            index.ckey_count()
+        );
+        if self.publish_reverse_index(&index, generation)
+            && let Some(path) = &persisted
+        {
+            Self::save_reverse_index(&index, path);
+        }
+        Ok(index)
+    }
+
+    /// Where the index for the loaded root and encoding files is persisted
+    fn reverse_index_path(&self, slot: &ReverseIndexSlot) -> Option<PathBuf> {
+        let dir = self.reverse_index_dir.as_ref()?;
+        let name = ReverseIndex::file_name(slot.root_key.as_ref()?, slot.encoding_key.as_ref()?);
+        Some(dir.join(name))
+    }
+
+    /// Save `index` to `path` and remove indices built from other files
+    ///
+    /// Failures are logged; the index is rebuilt next time instead.
+    fn save_reverse_index(index: &ReverseIndex, path: &Path) {
+        let temp_path = path.with_extension("tmp");
+        let saved = index
+            .save(&temp_path)
+            .and_then(|()| std::fs::rename(&temp_path, path).map_err(Into::into));
+        if let Err(e) = saved {
+            warn!("Failed to save reverse index {}: {e}", path.display());
+            let _ = std::fs::remove_file(&temp_path);
+            return;
+        }
+        debug!("Saved reverse index to {}", path.display());
+
+        let Some(entries) = path.parent().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let stale = entry.path();
+            if stale != path
+                && stale
+                    .extension()
+                    .is_some_and(|ext| ext == REVERSE_INDEX_EXTENSION)
+                && let Err(e) = std::fs::remove_file(&stale)
+            {
+                warn!(
+                    "Failed to remove stale reverse index {}: {e}",
+                    stale.display()
+                );
+            }
+        }
+    }
+
+    /// Cache `index` unless the root or encoding file was reloaded since
+    /// `generation` was read
+    fn publish_reverse_index(&self, index: &Arc<ReverseIndex>, generation: u64) -> bool {
+        let mut slot = self.reverse_index.write();
+        if slot.generation != generation {
+            debug!("Root or encoding file reloaded during reverse index build, not caching it");
+            return false;
+        }
+        slot.index = Some(Arc::clone(index));
+        true
+    }
+
+    /// Install a previously persisted reverse index
+    ///
+    /// The caller is responsible for ensuring it matches the loaded
+    /// root and encoding files.
+    pub fn set_reverse_index(&self, index: ReverseIndex) {
+        let mut slot = self.reverse_index.write();
+        slot.index = Some(Arc::new(index));
+        slot.generation += 1;
+    }
+
+    /// Find every game file whose content encodes to `encoding_key`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the reverse index cannot be built
+    pub fn files_for_ekey(&self, encoding_key: &EncodingKey) -> Result<Vec<FileOwner>> {
+        Ok(self.reverse_index()?.files_for_ekey(encoding_key))
+    }
+
     /// Clear all caches
     pub fn clear_caches(&self) {
         self.path_cache.clear();
@@ -218,6 +657,28 @@ impl ContentResolver {
         None
     }
 
+    /// Get the decoded size of the file with encoding key `encoding_key`
+    ///
+    /// Looks the key up in the loaded encoding file without reading or
+    /// decoding the file. Returns `None` without an encoding file or if it
+    /// does not list the key.
+    pub fn get_decoded_size(&self, encoding_key: &EncodingKey) -> Option<u64> {
+        self.encoding_file.read().as_ref().and_then(|encoding| {
+            let content_key = *encoding.lookup_by_ekey(encoding_key).first()?;
+            let page = encoding
+                .ckey_index
+                .partition_point(|idx| idx.first_key <= *content_key.as_bytes())
+                .checked_sub(1)?;
+            encoding
+                .ckey_pages
+                .get(page)?
+                .entries
+                .iter()
+                .find(|entry| entry.content_key == content_key)
+                .map(|entry| entry.file_size)
+        })
+    }
+
     /// Get resolver statistics
     pub fn stats(&self) -> ResolverStats {
         let root_entries = {
@@ -260,6 +721,36 @@ pub struct FileInfo {
     pub size: u64,
 }
 
+/// Where a file read was served from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolutionSource {
+    /// Local CASC storage (or the installation's in-memory cache)
+    Local,
+    /// The resolver's CDN fallback
+    Cdn,
+}
+
+/// File reads by where they were served from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResolutionMetrics {
+    /// Reads served from local storage
+    pub local: u64,
+    /// Reads downloaded from the CDN fallback
+    pub cdn: u64,
+    /// Source of the most recent read
+    pub last_source: Option<ResolutionSource>,
+}
+
+impl ResolutionMetrics {
+    /// Number of reads served from `source`
+    pub const fn count(&self, source: ResolutionSource) -> u64 {
+        match source {
+            ResolutionSource::Local => self.local,
+            ResolutionSource::Cdn => self.cdn,
+        }
+    }
+}
+
 /// Resolver statistics
 #[derive(Debug, Clone)]
 pub struct ResolverStats {
@@ -272,3 +763,153 @@ pub struct ResolverStats {
     /// Number of cached content lookups
     pub content_cache_size: usize,
 }
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reverse_index_built_across_reload_is_not_cached() {
+        let resolver = ContentResolver::new();
+        let generation = resolver.reverse_index.read().generation;
+
+        // A root or encoding file loaded while the index was being built
+        resolver.reverse_index.write().invalidate();
+
+        let index = Arc::new(ReverseIndex::default());
+        assert!(!resolver.publish_reverse_index(&index, generation));
+        assert!(resolver.reverse_index.read().index.is_none());
+        assert!(resolver.reverse_index().is_err());
+    }
+
+    #[test]
+    fn test_reverse_index_is_cached_without_reload() {
+        let resolver = ContentResolver::new();
+        let generation = resolver.reverse_index.read().generation;
+
+        let index = Arc::new(ReverseIndex::default());
+        assert!(resolver.publish_reverse_index(&index, generation));
+        let cached = resolver
+            .reverse_index()
+            .expect("Cached index should be returned");
+        assert!(Arc::ptr_eq(&cached, &index));
+    }
+
+    #[test]
+    fn test_files_for_ekey_reuses_saved_reverse_index() {
+        use cascette_crypto::FileDataId;
+        use cascette_formats::encoding::{CKeyEntryData, EKeyEntryData, EncodingBuilder};
+        use cascette_formats::root::{ContentFlags, LocaleFlags, RootBuilder, RootVersion};
+
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let saved_indices = || {
+            std::fs::read_dir(dir.path())
+                .expect("Directory should be readable")
+                .map(|entry| entry.expect("Entry should be readable").path())
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|ext| ext == REVERSE_INDEX_EXTENSION)
+                })
+                .collect::<Vec<_>>()
+        };
+        let encoding_file = |content_key, encoding_key, size| {
+            let mut builder = EncodingBuilder::new();
+            builder.add_ckey_entry(CKeyEntryData {
+                content_key,
+                file_size: size,
+                encoding_keys: vec![encoding_key],
+            });
+            builder.add_ekey_entry(EKeyEntryData {
+                encoding_key,
+                espec: "n".to_string(),
+                file_size: size,
+            });
+            builder
+                .build()
+                .expect("Encoding file should build")
+                .build()
+                .expect("Encoding file should serialize")
+        };
+
+        let content_key = ContentKey::from_bytes([7; 16]);
+        let encoding_key = EncodingKey::from_bytes([8; 16]);
+        let mut builder = RootBuilder::new(RootVersion::V2);
+        builder.add_file(
+            FileDataId::new(100),
+            content_key,
+            None,
+            LocaleFlags::new(LocaleFlags::ALL),
+            ContentFlags::new(ContentFlags::INSTALL),
+        );
+        let root = builder.build().expect("Root should build");
+        let encoding = encoding_file(content_key, encoding_key, 10);
+        // Keys the build config would name the files by
+        let root_ckey = ContentKey::from_bytes([1; 16]);
+        let encoding_ckey = ContentKey::from_bytes([2; 16]);
+
+        let resolver = ContentResolver::new().with_reverse_index_dir(dir.path());
+        resolver
+            .load_root_file_with_key(&root, root_ckey)
+            .expect("Root should load");
+        resolver
+            .load_encoding_file_with_key(&encoding, encoding_ckey)
+            .expect("Encoding should load");
+        let owners = resolver
+            .files_for_ekey(&encoding_key)
+            .expect("Reverse index should build");
+        assert_eq!(owners.len(), 1);
+        assert_eq!(owners[0].file_data_id, 100);
+        let saved = saved_indices();
+        assert_eq!(saved.len(), 1);
+
+        // Replace the saved index so a new resolver can only answer with
+        // FDID 200 if it reads the file
+        ReverseIndex::from_mappings([(200, None, content_key)], [(content_key, encoding_key)])
+            .save(&saved[0])
+            .expect("Index should save");
+
+        let reopened = ContentResolver::new().with_reverse_index_dir(dir.path());
+        reopened
+            .load_root_file_with_key(&root, root_ckey)
+            .expect("Root should load");
+        reopened
+            .load_encoding_file_with_key(&encoding, encoding_ckey)
+            .expect("Encoding should load");
+        let owners = reopened
+            .files_for_ekey(&encoding_key)
+            .expect("Saved reverse index should load");
+        assert_eq!(owners.len(), 1);
+        assert_eq!(owners[0].file_data_id, 200);
+
+        // Other files build and save a new index and remove the old one
+        reopened
+            .load_encoding_file_with_key(
+                &encoding_file(content_key, encoding_key, 20),
+                ContentKey::from_bytes([3; 16]),
+            )
+            .expect("Encoding should load");
+        let owners = reopened
+            .files_for_ekey(&encoding_key)
+            .expect("Reverse index should build");
+        assert_eq!(owners[0].file_data_id, 100);
+        let resaved = saved_indices();
+        assert_eq!(resaved.len(), 1);
+        assert_ne!(resaved, saved);
+
+        // Files loaded without build config keys are never persisted
+        let unkeyed = ContentResolver::new().with_reverse_index_dir(dir.path());
+        unkeyed.load_root_file(&root).expect("Root should load");
+        unkeyed
+            .load_encoding_file(&encoding)
+            .expect("Encoding should load");
+        assert_eq!(
+            unkeyed
+                .files_for_ekey(&encoding_key)
+                .expect("Reverse index should build")[0]
+                .file_data_id,
+            100
+        );
+        assert_eq!(saved_indices(), resaved);
+    }
+}
@@ -0,0 +1,205 @@
+//! Resuming partially downloaded builds
+//!
+//! An interrupted download leaves some of a download manifest's files in
+//! local storage. [`Installation::resume_download`](crate::Installation::resume_download)
+//! walks the manifest and handles each file by what is stored:
+//!
+//! - nothing: the file is downloaded
+//! - an entry of the manifest's encoded size: the file is skipped, or with
+//!   content verification read back and checked against its encoding key
+//!   and BLTE chunk checksums
+//! - an entry of another size, or one failing verification: the file is
+//!   downloaded again and the new copy replaces the index entry
+//!
+//! Downloads that fail are listed in the report rather than aborting the
+//! resume, so a later run can pick them up.
+
+use crate::{Result, StorageError};
+use binrw::BinRead;
+use cascette_crypto::EncodingKey;
+use cascette_formats::blte::BlteHeader;
+use serde::Serialize;
+use std::fmt;
+
+/// A manifest file that could not be downloaded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResumeFailure {
+    /// Hex encoding key of the file
+    pub encoding_key: String,
+    /// Why the download failed
+    pub reason: String,
+}
+
+/// Outcome of resuming a download
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ResumeReport {
+    /// Files in the download manifest
+    pub expected: u64,
+    /// Stored files of the expected size whose content was not checked
+    pub skipped: u64,
+    /// Stored files whose content matched their encoding key
+    pub verified: u64,
+    /// Stored files that were corrupt and were downloaded again
+    pub redownloaded: u64,
+    /// Missing files that were downloaded
+    pub downloaded: u64,
+    /// Bytes of BLTE data downloaded
+    pub bytes_downloaded: u64,
+    /// Files that were missing or corrupt and could not be downloaded
+    pub failed: Vec<ResumeFailure>,
+}
+
+impl ResumeReport {
+    /// Check whether every manifest file is now stored intact
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Render the report as pretty-printed JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| StorageError::InvalidFormat(format!("failed to serialize report: {e}")))
+    }
+}
+
+impl fmt::Display for ResumeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Files in manifest:   {}", self.expected)?;
+        writeln!(f, "Skipped:             {}", self.skipped)?;
+        writeln!(f, "Verified:            {}", self.verified)?;
+        writeln!(f, "Redownloaded:        {}", self.redownloaded)?;
+        writeln!(f, "Downloaded:          {}", self.downloaded)?;
+        writeln!(f, "Bytes downloaded:    {}", self.bytes_downloaded)?;
+        writeln!(f, "Failed:              {}", self.failed.len())?;
+        for failure in &self.failed {
+            writeln!(f, "  {}: {}", failure.encoding_key, failure.reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check a BLTE stream against its encoding key and chunk checksums
+///
+/// Single-chunk streams are keyed by the MD5 of the whole stream. Chunked
+/// streams are keyed by the MD5 of their header, which holds an MD5 per
+/// chunk, so each chunk is checked as well. A zero chunk checksum is not
+/// checked.
+pub fn blte_is_intact(blte: &[u8], encoding_key: &EncodingKey) -> bool {
+    if EncodingKey::from_data(blte) == *encoding_key {
+        return true;
+    }
+
+    let Ok(header) =
+        BlteHeader::read_options(&mut std::io::Cursor::new(blte), binrw::Endian::Big, ())
+    else {
+        return false;
+    };
+    let Some(extended) = header.extended else {
+        return false;
+    };
+    let header_size = header.header_size as usize;
+    if !blte
+        .get(..header_size)
+        .is_some_and(|bytes| EncodingKey::from_data(bytes) == *encoding_key)
+    {
+        return false;
+    }
+
+    let mut offset = header_size;
+    for info in &extended.chunk_infos {
+        let end = offset + info.compressed_size as usize;
+        let Some(chunk) = blte.get(offset..end) else {
+            return false;
+        };
+        if info.checksum != [0; 16] && *EncodingKey::from_data(chunk).as_bytes() != info.checksum {
+            return false;
+        }
+        offset = end;
+    }
+    offset == blte.len()
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use cascette_formats::CascFormat;
+    use cascette_formats::blte::{BlteBuilder, CompressionMode};
+
+    fn chunked_blte(data: &[u8]) -> (Vec<u8>, EncodingKey) {
+        let blte = BlteBuilder::new()
+            .with_compression(CompressionMode::None)
+            .with_chunk_size_unchecked(256)
+            .add_data(data)
+            .expect("Data should be added")
+            .build()
+            .expect("BLTE should build")
+            .build()
+            .expect("BLTE should serialize");
+        let header_size = u32::from_be_bytes(blte[4..8].try_into().expect("BLTE header size"));
+        let key = EncodingKey::from_data(&blte[..header_size as usize]);
+        (blte, key)
+    }
+
+    #[test]
+    fn test_chunk_corruption_is_detected() {
+        let (mut blte, key) = chunked_blte(&[7; 1024]);
+        assert!(blte_is_intact(&blte, &key));
+
+        // The header still matches, the last chunk does not
+        let last = blte.len() - 1;
+        blte[last] ^= 0xFF;
+        assert!(!blte_is_intact(&blte, &key));
+
+        blte[last] ^= 0xFF;
+        assert!(!blte_is_intact(&blte[..blte.len() - 1], &key));
+        blte.push(0);
+        assert!(!blte_is_intact(&blte, &key));
+    }
+
+    #[test]
+    fn test_single_chunk_is_keyed_by_whole_stream() {
+        let blte = BlteBuilder::new()
+            .with_compression(CompressionMode::None)
+            .add_data(b"single")
+            .expect("Data should be added")
+            .build()
+            .expect("BLTE should build")
+            .build()
+            .expect("BLTE should serialize");
+        let key = EncodingKey::from_data(&blte);
+        assert!(blte_is_intact(&blte, &key));
+        assert!(!blte_is_intact(&blte, &EncodingKey::from_data(b"other")));
+        assert!(!blte_is_intact(b"not blte", &key));
+    }
+
+    #[test]
+    fn test_report_renders_text_and_json() {
+        let report = ResumeReport {
+            expected: 3,
+            verified: 1,
+            redownloaded: 1,
+            failed: vec![ResumeFailure {
+                encoding_key: "00ff".to_string(),
+                reason: "no CDN fallback".to_string(),
+            }],
+            ..ResumeReport::default()
+        };
+        assert!(!report.is_complete());
+
+        let text = report.to_string();
+        assert!(text.contains("Redownloaded:        1"));
+        assert!(text.contains("  00ff: no CDN fallback"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&report.to_json().expect("Report should serialize"))
+                .expect("Report should be valid JSON");
+        assert_eq!(json["verified"], 1);
+        assert_eq!(json["failed"][0]["encoding_key"], "00ff");
+    }
+}
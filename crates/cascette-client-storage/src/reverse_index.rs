@@ -0,0 +1,396 @@
+//! Reverse lookup from encoding keys to the files that reference them
+//!
+//! The forward chain (path/FDID -> content key -> encoding key) answers
+//! "where is this file". Verification and corruption reports go the other
+//! way: given an encoding key, which game files does it belong to? Answering
+//! that with a forward scan joins the whole root and encoding file for every
+//! query, so this index inverts the mapping once and keeps it around.
+//!
+//! Encoding key mappings are kept in one vector sorted by encoding key and
+//! found by binary search, so each mapping costs its two keys and nothing
+//! else. Lookups compare the full 16-byte key, so keys sharing a prefix never
+//! return files belonging to a different key.
+//!
+//! The index can be persisted with [`ReverseIndex::save`] and restored with
+//! [`ReverseIndex::load`] so it survives restarts without re-reading the
+//! root and encoding files. A resolver with
+//! [`with_reverse_index_dir`](crate::ContentResolver::with_reverse_index_dir)
+//! does this itself, naming each file after [`ReverseIndex::file_name`].
+
+use crate::{Result, StorageError};
+use cascette_crypto::{ContentKey, EncodingKey};
+use cascette_formats::{encoding::EncodingFile, root::RootFile};
+use std::collections::HashMap;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Magic bytes identifying a persisted reverse index.
+const MAGIC: [u8; 4] = *b"CRIX";
+
+/// Persisted format version.
+const VERSION: u8 = 1;
+
+/// Extension of reverse index files written by the resolver
+pub const REVERSE_INDEX_EXTENSION: &str = "reverse";
+
+/// A game file that references a given encoding key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileOwner {
+    /// `FileDataID` from the root file
+    pub file_data_id: u32,
+    /// Content key the FDID maps to
+    pub content_key: ContentKey,
+    /// Jenkins96 name hash from the root file, if the root carries names
+    pub name_hash: Option<u64>,
+}
+
+/// Encoding key to content key mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EkeyEntry {
+    encoding_key: EncodingKey,
+    content_key: ContentKey,
+}
+
+/// Root file record attached to a content key.
+#[derive(Debug, Clone, Copy)]
+struct RootRef {
+    file_data_id: u32,
+    name_hash: Option<u64>,
+}
+
+/// Inverted root + encoding mapping (encoding key -> FDIDs, content key -> FDIDs).
+#[derive(Debug, Default)]
+pub struct ReverseIndex {
+    /// Encoding key -> content key mappings, sorted and deduplicated
+    by_ekey: Vec<EkeyEntry>,
+    /// Content key -> every root record referencing it
+    by_content_key: HashMap<ContentKey, Vec<RootRef>>,
+}
+
+impl ReverseIndex {
+    /// Build from raw mappings.
+    ///
+    /// `root_records` yields `(fdid, name_hash, content_key)` and
+    /// `encoding_records` yields `(content_key, encoding_key)`. A content key
+    /// may appear with several encoding keys and several FDIDs; every
+    /// combination is preserved.
+    pub fn from_mappings<R, E>(root_records: R, encoding_records: E) -> Self
+    where
+        R: IntoIterator<Item = (u32, Option<u64>, ContentKey)>,
+        E: IntoIterator<Item = (ContentKey, EncodingKey)>,
+    {
+        let mut index = Self::default();
+
+        for (file_data_id, name_hash, content_key) in root_records {
+            index
+                .by_content_key
+                .entry(content_key)
+                .or_default()
+                .push(RootRef {
+                    file_data_id,
+                    name_hash,
+                });
+        }
+
+        index.by_ekey = encoding_records
+            .into_iter()
+            .map(|(content_key, encoding_key)| EkeyEntry {
+                encoding_key,
+                content_key,
+            })
+            .collect();
+        index.by_ekey.sort_unstable_by(|a, b| {
+            (a.encoding_key.as_bytes(), a.content_key.as_bytes())
+                .cmp(&(b.encoding_key.as_bytes(), b.content_key.as_bytes()))
+        });
+        index.by_ekey.dedup();
+        index.by_ekey.shrink_to_fit();
+
+        index
+    }
+
+    /// Build from parsed root and encoding files.
+    pub fn build(root: &RootFile, encoding: &EncodingFile) -> Self {
+        let root_records = root.blocks.iter().flat_map(|block| {
+            block
+                .records
+                .iter()
+                .map(|r| (r.file_data_id.get(), r.name_hash, r.content_key))
+        });
+        let encoding_records = encoding.ckey_pages.iter().flat_map(|page| {
+            page.entries.iter().flat_map(|entry| {
+                entry
+                    .encoding_keys
+                    .iter()
+                    .map(move |ekey| (entry.content_key, *ekey))
+            })
+        });
+        Self::from_mappings(root_records, encoding_records)
+    }
+
+    /// File name of the index built from the root and encoding files with
+    /// these content keys.
+    pub fn file_name(root_key: &ContentKey, encoding_key: &ContentKey) -> String {
+        format!(
+            "{}-{}.{REVERSE_INDEX_EXTENSION}",
+            root_key.to_hex(),
+            encoding_key.to_hex()
+        )
+    }
+
+    /// Content keys that encode to `encoding_key`.
+    pub fn content_keys_for_ekey(&self, encoding_key: &EncodingKey) -> Vec<ContentKey> {
+        let start = self
+            .by_ekey
+            .partition_point(|e| e.encoding_key.as_bytes() < encoding_key.as_bytes());
+        self.by_ekey[start..]
+            .iter()
+            .take_while(|e| e.encoding_key == *encoding_key)
+            .map(|e| e.content_key)
+            .collect()
+    }
+
+    /// All FDIDs referencing `content_key`, in root file order.
+    pub fn files_for_ckey(&self, content_key: &ContentKey) -> Vec<FileOwner> {
+        self.by_content_key
+            .get(content_key)
+            .map(|refs| {
+                refs.iter()
+                    .map(|r| FileOwner {
+                        file_data_id: r.file_data_id,
+                        content_key: *content_key,
+                        name_hash: r.name_hash,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// All game files whose content encodes to `encoding_key`.
+    ///
+    /// Deduplicated assets shared by many FDIDs return every one of them.
+    pub fn files_for_ekey(&self, encoding_key: &EncodingKey) -> Vec<FileOwner> {
+        self.content_keys_for_ekey(encoding_key)
+            .iter()
+            .flat_map(|ckey| self.files_for_ckey(ckey))
+            .collect()
+    }
+
+    /// Number of distinct (encoding key, content key) pairs.
+    pub fn ekey_count(&self) -> usize {
+        self.by_ekey.len()
+    }
+
+    /// Number of distinct content keys referenced by the root file.
+    pub fn ckey_count(&self) -> usize {
+        self.by_content_key.len()
+    }
+
+    /// Check whether the index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.by_ekey.is_empty() && self.by_content_key.is_empty()
+    }
+
+    /// Write the index to `path`.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read an index previously written with [`Self::save`].
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::read_from(&mut BufReader::new(file))
+    }
+
+    /// Serialize the index.
+    ///
+    /// Layout (little-endian): magic, version, ekey entry count, entries as
+    /// `ekey[16] ckey[16]`, root ref count, refs as
+    /// `ckey[16] fdid:u32 has_name:u8 name_hash:u64`.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[VERSION])?;
+
+        writer.write_all(&(self.ekey_count() as u64).to_le_bytes())?;
+        for entry in &self.by_ekey {
+            writer.write_all(entry.encoding_key.as_bytes())?;
+            writer.write_all(entry.content_key.as_bytes())?;
+        }
+
+        let ref_count: usize = self.by_content_key.values().map(Vec::len).sum();
+        writer.write_all(&(ref_count as u64).to_le_bytes())?;
+        for (content_key, refs) in &self.by_content_key {
+            for r in refs {
+                writer.write_all(content_key.as_bytes())?;
+                writer.write_all(&r.file_data_id.to_le_bytes())?;
+                writer.write_all(&[u8::from(r.name_hash.is_some())])?;
+                writer.write_all(&r.name_hash.unwrap_or(0).to_le_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deserialize an index written with [`Self::write_to`].
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(StorageError::InvalidFormat(
+                "reverse index: bad magic".to_string(),
+            ));
+        }
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != VERSION {
+            return Err(StorageError::InvalidFormat(format!(
+                "reverse index: unsupported version {}",
+                version[0]
+            )));
+        }
+
+        let ekey_count = read_u64(reader)?;
+        let mut encoding_records = Vec::new();
+        for _ in 0..ekey_count {
+            let encoding_key = EncodingKey::from_bytes(read_key(reader)?);
+            let content_key = ContentKey::from_bytes(read_key(reader)?);
+            encoding_records.push((content_key, encoding_key));
+        }
+
+        let ref_count = read_u64(reader)?;
+        let mut root_records = Vec::new();
+        for _ in 0..ref_count {
+            let content_key = ContentKey::from_bytes(read_key(reader)?);
+            let mut fdid = [0u8; 4];
+            reader.read_exact(&mut fdid)?;
+            let mut has_name = [0u8; 1];
+            reader.read_exact(&mut has_name)?;
+            let name_hash = read_u64(reader)?;
+            root_records.push((
+                u32::from_le_bytes(fdid),
+                (has_name[0] != 0).then_some(name_hash),
+                content_key,
+            ));
+        }
+
+        Ok(Self::from_mappings(root_records, encoding_records))
+    }
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_key<R: Read>(reader: &mut R) -> Result<[u8; 16]> {
+    let mut buf = [0u8; 16];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn key(first: u8, last: u8) -> [u8; 16] {
+        let mut k = [0u8; 16];
+        k[0] = first;
+        k[15] = last;
+        k
+    }
+
+    /// Two content keys; `shared` is referenced by three FDIDs, `single` by one.
+    /// The two encoding keys share their first 15 bytes.
+    fn sample_index() -> (ReverseIndex, EncodingKey, EncodingKey) {
+        let shared = ContentKey::from_bytes(key(0xAA, 1));
+        let single = ContentKey::from_bytes(key(0xBB, 2));
+        let ekey_shared = EncodingKey::from_bytes(key(0x11, 1));
+        let ekey_single = EncodingKey::from_bytes(key(0x11, 2));
+
+        let index = ReverseIndex::from_mappings(
+            vec![
+                (100, Some(0x1234), shared),
+                (101, None, shared),
+                (102, None, shared),
+                (200, Some(0x5678), single),
+            ],
+            vec![(shared, ekey_shared), (single, ekey_single)],
+        );
+        (index, ekey_shared, ekey_single)
+    }
+
+    #[test]
+    fn test_files_for_ekey_returns_all_shared_fdids() {
+        let (index, ekey_shared, _) = sample_index();
+
+        let mut fdids: Vec<u32> = index
+            .files_for_ekey(&ekey_shared)
+            .iter()
+            .map(|f| f.file_data_id)
+            .collect();
+        fdids.sort_unstable();
+        assert_eq!(fdids, vec![100, 101, 102]);
+    }
+
+    #[test]
+    fn test_shared_prefix_verifies_full_key() {
+        let (index, _, ekey_single) = sample_index();
+
+        let owners = index.files_for_ekey(&ekey_single);
+        assert_eq!(owners.len(), 1);
+        assert_eq!(owners[0].file_data_id, 200);
+        assert_eq!(owners[0].name_hash, Some(0x5678));
+
+        // Same prefix, unknown suffix
+        let unknown = EncodingKey::from_bytes(key(0x11, 3));
+        assert!(index.files_for_ekey(&unknown).is_empty());
+    }
+
+    #[test]
+    fn test_multiple_ekeys_per_ckey() {
+        let ckey = ContentKey::from_bytes(key(0xCC, 0));
+        let ekey_a = EncodingKey::from_bytes(key(0x21, 0));
+        let ekey_b = EncodingKey::from_bytes(key(0x22, 0));
+        let index = ReverseIndex::from_mappings(
+            vec![(7, None, ckey)],
+            vec![(ckey, ekey_a), (ckey, ekey_b), (ckey, ekey_a)],
+        );
+
+        assert_eq!(index.ekey_count(), 2);
+        assert_eq!(index.files_for_ekey(&ekey_a)[0].file_data_id, 7);
+        assert_eq!(index.files_for_ekey(&ekey_b)[0].file_data_id, 7);
+    }
+
+    #[test]
+    fn test_persistence_round_trip() {
+        let (index, ekey_shared, ekey_single) = sample_index();
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("reverse.idx");
+
+        index.save(&path).expect("save");
+        let loaded = ReverseIndex::load(&path).expect("load");
+
+        assert_eq!(loaded.ekey_count(), index.ekey_count());
+        assert_eq!(loaded.ckey_count(), index.ckey_count());
+        assert_eq!(loaded.files_for_ekey(&ekey_shared).len(), 3);
+        assert_eq!(
+            loaded.files_for_ekey(&ekey_single),
+            index.files_for_ekey(&ekey_single)
+        );
+    }
+
+    #[test]
+    fn test_load_rejects_bad_magic() {
+        let mut data: &[u8] = b"NOPE\x01";
+        assert!(matches!(
+            ReverseIndex::read_from(&mut data),
+            Err(StorageError::InvalidFormat(_))
+        ));
+    }
+}
@@ -2,11 +2,12 @@
 //!
 //! Data archives contain BLTE-encoded game content.
 
+use crate::storage::compression::{ContentCompressor, Passthrough, compressor_for_mode};
 use crate::storage::local_header::{LOCAL_HEADER_SIZE, LocalHeader};
 use crate::{Result, StorageError};
 use cascette_crypto::{ContentKey, EncodingKey};
 use cascette_formats::CascFormat;
-use cascette_formats::blte::{BlteFile, CompressionMode};
+use cascette_formats::blte::{BlteFile, BlteHeader, ChunkData, CompressionMode};
 use dashmap::DashMap;
 use memmap2::{Mmap, MmapOptions};
 use parking_lot::RwLock;
@@ -29,8 +30,8 @@ pub struct ArchiveManager {
     base_path: PathBuf,
     /// Next write position for each archive
     write_positions: Arc<RwLock<BTreeMap<u16, u64>>>,
-    /// Default compression mode for new data
-    default_compression: CompressionMode,
+    /// Compressor applied to new data
+    compressor: Arc<dyn ContentCompressor>,
 }
 
 /// Individual archive file with memory mapping
@@ -49,27 +50,45 @@ struct ArchiveFile {
 impl ArchiveManager {
     /// Creates an archive manager with no compression (pass-through).
     pub fn new(base_path: impl AsRef<Path>) -> Self {
-        Self::with_compression(base_path, CompressionMode::None)
+        Self::with_compressor(base_path, Arc::new(Passthrough))
     }
 
     /// Creates an archive manager that applies `compression` to new writes.
     pub fn with_compression(base_path: impl AsRef<Path>, compression: CompressionMode) -> Self {
+        Self::with_compressor(base_path, compressor_for_mode(compression))
+    }
+
+    /// Creates an archive manager that compresses new writes with `compressor`.
+    pub fn with_compressor(
+        base_path: impl AsRef<Path>,
+        compressor: Arc<dyn ContentCompressor>,
+    ) -> Self {
         Self {
             archives: DashMap::new(),
             base_path: base_path.as_ref().to_path_buf(),
             write_positions: Arc::new(RwLock::new(BTreeMap::new())),
-            default_compression: compression,
+            compressor,
         }
     }
 
     /// Changes the compression applied to subsequent writes.
-    pub const fn set_compression_mode(&mut self, mode: CompressionMode) {
-        self.default_compression = mode;
+    pub fn set_compression_mode(&mut self, mode: CompressionMode) {
+        self.compressor = compressor_for_mode(mode);
+    }
+
+    /// Changes the compressor applied to subsequent writes.
+    pub fn set_compressor(&mut self, compressor: Arc<dyn ContentCompressor>) {
+        self.compressor = compressor;
     }
 
     /// Compression mode applied to new writes.
-    pub const fn compression_mode(&self) -> CompressionMode {
-        self.default_compression
+    pub fn compression_mode(&self) -> CompressionMode {
+        self.compressor.mode()
+    }
+
+    /// Compressor applied to new writes.
+    pub fn compressor(&self) -> &Arc<dyn ContentCompressor> {
+        &self.compressor
     }
 
     /// Open all archive files from a directory
@@ -191,6 +210,45 @@ impl ArchiveManager {
         Ok(data)
     }
 
+    /// Read several raw entries from one archive.
+    ///
+    /// The archive is looked up once and the entries are copied in the given
+    /// order; pass them sorted by offset for sequential access. Each entry
+    /// fails on its own if it lies outside the archive.
+    pub fn read_raw_many(&self, archive_id: u16, spans: &[(u32, u32)]) -> Vec<Result<Vec<u8>>> {
+        let Some(archive) = self.archives.get(&archive_id) else {
+            return spans
+                .iter()
+                .map(|_| {
+                    Err(StorageError::Archive(format!(
+                        "Archive {archive_id} not found"
+                    )))
+                })
+                .collect();
+        };
+
+        let reads = spans
+            .iter()
+            .map(|&(offset, size)| {
+                let (offset, size) = (offset as usize, size as usize);
+                archive
+                    .mmap
+                    .get(offset..offset + size)
+                    .map(<[u8]>::to_vec)
+                    .ok_or_else(|| {
+                        StorageError::Archive(format!(
+                            "Read beyond archive bounds: {} + {} > {}",
+                            offset,
+                            size,
+                            archive.mmap.len()
+                        ))
+                    })
+            })
+            .collect();
+        drop(archive);
+        reads
+    }
+
     /// Read content from an archive at specified location.
     ///
     /// Handles the 30-byte local header if present, then decompresses
@@ -243,12 +301,12 @@ impl ArchiveManager {
         data: &[u8],
         compress: bool,
     ) -> Result<(u16, u32, u32, [u8; 16])> {
-        let mode = if compress {
-            self.default_compression
+        let blte_data = if compress {
+            Self::compress_blte(data, self.compressor.as_ref())?
         } else {
-            CompressionMode::None
+            Self::compress_blte(data, &Passthrough)?
         };
-        self.write_content_with_mode(data, mode)
+        self.write_blte(&blte_data)
     }
 
     /// Write content to an archive with specific compression mode.
@@ -268,14 +326,58 @@ impl ArchiveManager {
         data: &[u8],
         mode: CompressionMode,
     ) -> Result<(u16, u32, u32, [u8; 16])> {
-        // Select archive with space
-        let archive_id = self.select_archive_for_write();
-
         // BLTE-encode the data (even uncompressed data gets a BLTE wrapper)
         let blte_data = Self::compress_blte_with_mode(data, mode)?;
+        self.write_blte(&blte_data)
+    }
 
+    /// Write content to an archive using `compressor` instead of the
+    /// configured one.
+    ///
+    /// Returns the same tuple as [`Self::write_content_with_mode`].
+    ///
+    /// # Errors
+    ///
+    /// Returns error if compression fails, archive creation fails, write fails, or size limits exceeded
+    pub fn write_content_with(
+        &mut self,
+        data: &[u8],
+        compressor: &dyn ContentCompressor,
+    ) -> Result<(u16, u32, u32, [u8; 16])> {
+        let blte_data = Self::compress_blte(data, compressor)?;
+        self.write_blte(&blte_data)
+    }
+
+    /// Write already BLTE-encoded data to an archive.
+    ///
+    /// Prepends the 30-byte local header. Returns the same tuple as
+    /// [`Self::write_content_with_mode`].
+    ///
+    /// # Errors
+    ///
+    /// Returns error if archive creation fails, write fails, or size limits exceeded
+    pub fn write_blte(&mut self, blte_data: &[u8]) -> Result<(u16, u32, u32, [u8; 16])> {
         // Compute encoding key as MD5(blte_data) — content-addressable
-        let encoding_key = EncodingKey::from_data(&blte_data);
+        let encoding_key = EncodingKey::from_data(blte_data);
+        self.write_blte_with_key(blte_data, &encoding_key)
+    }
+
+    /// Write already BLTE-encoded data under a known encoding key.
+    ///
+    /// Used for data whose key was assigned elsewhere, such as files
+    /// downloaded from a CDN. The caller is responsible for the key
+    /// matching the data.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if archive creation fails, write fails, or size limits exceeded
+    pub fn write_blte_with_key(
+        &mut self,
+        blte_data: &[u8],
+        encoding_key: &EncodingKey,
+    ) -> Result<(u16, u32, u32, [u8; 16])> {
+        // Select archive with space
+        let archive_id = self.select_archive_for_write();
 
         // Build the 30-byte local header
         let blte_size = u32::try_from(blte_data.len())
@@ -315,7 +417,7 @@ impl ArchiveManager {
         // Write local header + BLTE data
         let mut combined = Vec::with_capacity(LOCAL_HEADER_SIZE + blte_data.len());
         combined.extend_from_slice(&header_bytes);
-        combined.extend_from_slice(&blte_data);
+        combined.extend_from_slice(blte_data);
         self.write_to_archive(archive_id, offset, &combined)?;
 
         // Update write position
@@ -396,7 +498,8 @@ impl ArchiveManager {
         file.flush()
             .map_err(|e| StorageError::Archive(format!("Failed to flush: {e}")))?;
 
-        // Check if file grew significantly and remap if needed
+        // The mapping only covers the file as it was when mapped, so any
+        // growth needs a remap before the new data can be read
         let new_size = self.get_file_size(&archive_path)?;
         let current_size = {
             let archive = self
@@ -407,17 +510,7 @@ impl ArchiveManager {
             archive.size
         };
 
-        // Remap if file grew by more than 64MB or doubled in size
-        let size_threshold = 64 * 1024 * 1024; // 64MB
-        let size_difference = new_size.saturating_sub(current_size);
-        #[allow(clippy::cast_precision_loss)]
-        let size_ratio = if current_size > 0 {
-            new_size as f64 / current_size as f64
-        } else {
-            f64::INFINITY
-        };
-
-        if size_difference > size_threshold || size_ratio > 2.0 {
+        if new_size > current_size {
             debug!(
                 "Remapping archive {} due to size change: {} -> {} bytes",
                 id, current_size, new_size
@@ -436,26 +529,17 @@ impl ArchiveManager {
 
     /// Compress data using BLTE with specific compression mode
     fn compress_blte_with_mode(data: &[u8], mode: CompressionMode) -> Result<Vec<u8>> {
-        // Validate compression mode is supported
-        match mode {
-            CompressionMode::None | CompressionMode::ZLib | CompressionMode::LZ4 => {}
-            CompressionMode::Encrypted => {
-                return Err(StorageError::Archive(
-                    "Encrypted compression not supported for storage".to_string(),
-                ));
-            }
-            #[allow(deprecated)]
-            CompressionMode::Frame => {
-                return Err(StorageError::Archive(
-                    "Frame compression is deprecated and not supported".to_string(),
-                ));
-            }
-        }
+        Self::compress_blte(data, compressor_for_mode(mode).as_ref())
+    }
 
-        // Create single-chunk BLTE file with specified compression
-        let blte_file = BlteFile::single_chunk(data.to_vec(), mode).map_err(|e| {
-            StorageError::Archive(format!("Failed to create BLTE with {mode:?}: {e}"))
-        })?;
+    /// Compress data into a single-chunk BLTE file with `compressor`
+    fn compress_blte(data: &[u8], compressor: &dyn ContentCompressor) -> Result<Vec<u8>> {
+        let mode = compressor.mode();
+        let chunk = ChunkData::from_compressed(mode, compressor.compress(data)?, Some(data.len()));
+        let blte_file = BlteFile {
+            header: BlteHeader::single_chunk(),
+            chunks: vec![chunk],
+        };
 
         // Build the BLTE data
         blte_file
@@ -483,6 +567,90 @@ impl ArchiveManager {
         Ok(hash.as_bytes() == expected_hash)
     }
 
+    /// Next write position of every archive
+    ///
+    /// The positions taken before a series of writes can be passed to
+    /// [`truncate_to`](Self::truncate_to) to undo them.
+    pub fn write_positions(&self) -> BTreeMap<u16, u64> {
+        self.write_positions.read().clone()
+    }
+
+    /// Size in bytes of every open archive file
+    pub fn archive_sizes(&self) -> BTreeMap<u16, u64> {
+        self.archives
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().size))
+            .collect()
+    }
+
+    /// Flush the data of the given archives to disk
+    ///
+    /// # Errors
+    ///
+    /// Returns error if an archive is not open or cannot be synced
+    pub fn sync_archives(&self, ids: impl IntoIterator<Item = u16>) -> Result<()> {
+        for id in ids {
+            let path = self
+                .archives
+                .get(&id)
+                .map(|archive| archive.path.clone())
+                .ok_or_else(|| StorageError::Archive(format!("Archive {id} not found")))?;
+            OpenOptions::new()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.sync_data())
+                .map_err(|e| StorageError::Archive(format!("Failed to sync archive {id}: {e}")))?;
+        }
+        Ok(())
+    }
+
+    /// Cut every archive back to the write position in `positions`
+    ///
+    /// Archives created since `positions` was taken are removed. Data
+    /// written after that point must not be referenced by any index entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if an archive cannot be truncated, removed or remapped
+    pub fn truncate_to(&mut self, positions: &BTreeMap<u16, u64>) -> Result<()> {
+        let current = self.write_positions();
+        for (id, position) in current {
+            let Some(path) = self.archives.get(&id).map(|archive| archive.path.clone()) else {
+                continue;
+            };
+            match positions.get(&id) {
+                Some(&target) if target < position => {
+                    OpenOptions::new()
+                        .write(true)
+                        .open(&path)
+                        .and_then(|file| {
+                            file.set_len(target)?;
+                            file.sync_all()
+                        })
+                        .map_err(|e| {
+                            StorageError::Archive(format!("Failed to truncate archive {id}: {e}"))
+                        })?;
+                    self.remap_archive(id, &path, target)?;
+                    self.write_positions.write().insert(id, target);
+                    debug!(
+                        "Truncated archive {} from {} to {} bytes",
+                        id, position, target
+                    );
+                }
+                Some(_) => {}
+                None => {
+                    self.archives.remove(&id);
+                    self.write_positions.write().remove(&id);
+                    std::fs::remove_file(&path).map_err(|e| {
+                        StorageError::Archive(format!("Failed to remove archive {id}: {e}"))
+                    })?;
+                    debug!("Removed archive {} created after the snapshot", id);
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Get current file size
     #[allow(clippy::unused_self)]
     fn get_file_size(&self, path: &Path) -> Result<u64> {
@@ -1059,4 +1227,64 @@ mod tests {
             "encoding key should be MD5 of the BLTE-encoded data"
         );
     }
+
+    #[test]
+    fn test_small_writes_are_readable() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let mut manager = ArchiveManager::new(temp_dir.path());
+
+        // Appends far below the old 64 MiB / 2x remap threshold
+        for i in 0..3u8 {
+            let (archive_id, offset, size, _) = manager
+                .write_content(&[i; 40], false)
+                .expect("write should succeed");
+            let read = manager
+                .read_raw(archive_id, offset, size)
+                .expect("read_raw should succeed");
+            assert_eq!(read.len(), size as usize);
+        }
+    }
+
+    #[test]
+    fn test_read_raw_many_after_small_writes() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let mut manager = ArchiveManager::new(temp_dir.path());
+
+        // Small appends must stay readable without a large growth in size
+        let writes: Vec<_> = (0..3u8)
+            .map(|i| {
+                manager
+                    .write_content(&[i; 40], false)
+                    .expect("write should succeed")
+            })
+            .collect();
+        let archive_id = writes[0].0;
+
+        let mut spans: Vec<_> = writes
+            .iter()
+            .map(|&(_, offset, size, _)| (offset, size))
+            .collect();
+        spans.push((u32::MAX - 1, 2));
+
+        let reads = manager.read_raw_many(archive_id, &spans);
+        assert_eq!(reads.len(), 4);
+        for (read, &(offset, size)) in reads.iter().zip(&spans[..3]) {
+            let read = read.as_ref().expect("read should succeed");
+            assert_eq!(
+                read,
+                &manager
+                    .read_raw(archive_id, offset, size)
+                    .expect("read_raw should succeed")
+            );
+        }
+        assert!(matches!(reads[3], Err(StorageError::Archive(_))));
+
+        let missing = manager.read_raw_many(archive_id + 1, &spans[..2]);
+        assert_eq!(missing.len(), 2);
+        assert!(
+            missing
+                .iter()
+                .all(|read| matches!(read, Err(StorageError::Archive(_))))
+        );
+    }
 }
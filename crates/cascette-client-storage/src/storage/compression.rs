@@ -0,0 +1,290 @@
+//! Pluggable compression for the archive write path.
+//!
+//! Writes go through a [`ContentCompressor`], which produces the payload of
+//! a single BLTE chunk and names the BLTE mode byte that goes in front of
+//! it. Reads do not use the configured compressor: BLTE data carries its
+//! mode per chunk, so content written with any backend stays readable by an
+//! installation configured with any other.
+//!
+//! The zlib backend uses `flate2`, which links miniz_oxide by default.
+//! Builds that can link a faster implementation select it by enabling the
+//! matching `flate2` feature (`zlib-ng`, `zlib-rs`) in the final binary;
+//! Cargo unifies the feature across the dependency graph, so no change is
+//! needed here. Where only miniz_oxide is available,
+//! [`ZlibCompressor::fast`] trades ratio for throughput.
+
+use crate::{Result, StorageError};
+use cascette_formats::blte::{CompressionMode, compress_chunk, decompress_chunk};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Compression backend used when BLTE-encoding new content.
+pub trait ContentCompressor: fmt::Debug + Send + Sync {
+    /// BLTE mode byte written in front of the compressed chunk.
+    fn mode(&self) -> CompressionMode;
+
+    /// Compress one chunk of content.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the backend fails to compress the data
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>>;
+
+    /// Decompress one chunk previously produced by [`Self::compress`].
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the data is corrupt or exceeds the decompression limit
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Mode 'N': stores content uncompressed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Passthrough;
+
+impl ContentCompressor for Passthrough {
+    fn mode(&self) -> CompressionMode {
+        CompressionMode::None
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(data.to_vec())
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(data.to_vec())
+    }
+}
+
+/// Mode 'Z': zlib through `flate2`.
+#[derive(Debug, Clone, Copy)]
+pub struct ZlibCompressor {
+    level: u32,
+}
+
+impl ZlibCompressor {
+    /// Highest zlib compression level.
+    pub const MAX_LEVEL: u32 = 9;
+
+    /// Creates a zlib compressor at `level` (0-9, clamped).
+    pub fn new(level: u32) -> Self {
+        Self {
+            level: level.min(Self::MAX_LEVEL),
+        }
+    }
+
+    /// Level 1: the fastest setting, for slow deflate implementations.
+    pub fn fast() -> Self {
+        Self::new(Compression::fast().level())
+    }
+
+    /// Compression level in use.
+    pub const fn level(&self) -> u32 {
+        self.level
+    }
+}
+
+impl Default for ZlibCompressor {
+    fn default() -> Self {
+        Self::new(Compression::default().level())
+    }
+}
+
+impl ContentCompressor for ZlibCompressor {
+    fn mode(&self) -> CompressionMode {
+        CompressionMode::ZLib
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut encoder = ZlibEncoder::new(
+            Vec::with_capacity(data.len() / 2),
+            Compression::new(self.level),
+        );
+        encoder
+            .write_all(data)
+            .and_then(|()| encoder.finish())
+            .map_err(|e| StorageError::Archive(format!("ZLib compression failed: {e}")))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        decompress_chunk(data, CompressionMode::ZLib)
+            .map_err(|e| StorageError::Archive(e.to_string()))
+    }
+}
+
+/// Mode '4': LZ4 block compression as used in BLTE.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lz4Compressor;
+
+impl ContentCompressor for Lz4Compressor {
+    fn mode(&self) -> CompressionMode {
+        CompressionMode::LZ4
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        compress_chunk(data, CompressionMode::LZ4).map_err(|e| StorageError::Archive(e.to_string()))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        decompress_chunk(data, CompressionMode::LZ4)
+            .map_err(|e| StorageError::Archive(e.to_string()))
+    }
+}
+
+/// Stand-in for modes that cannot be used to write local storage.
+///
+/// Keeps [`crate::storage::ArchiveManager::with_compression`] infallible
+/// while still rejecting the write itself.
+#[derive(Debug, Clone, Copy)]
+struct Unsupported(CompressionMode);
+
+impl Unsupported {
+    fn error(self) -> StorageError {
+        match self.0 {
+            CompressionMode::Encrypted => {
+                StorageError::Archive("Encrypted compression not supported for storage".to_string())
+            }
+            #[allow(deprecated)]
+            CompressionMode::Frame => StorageError::Archive(
+                "Frame compression is deprecated and not supported".to_string(),
+            ),
+            mode => {
+                StorageError::Archive(format!("{mode:?} compression not supported for storage"))
+            }
+        }
+    }
+}
+
+impl ContentCompressor for Unsupported {
+    fn mode(&self) -> CompressionMode {
+        self.0
+    }
+
+    fn compress(&self, _data: &[u8]) -> Result<Vec<u8>> {
+        Err(self.error())
+    }
+
+    fn decompress(&self, _data: &[u8]) -> Result<Vec<u8>> {
+        Err(self.error())
+    }
+}
+
+/// Returns the built-in compressor for a BLTE mode.
+///
+/// Encrypted, frame and zstd modes yield a compressor that fails on use.
+pub(crate) fn compressor_for_mode(mode: CompressionMode) -> Arc<dyn ContentCompressor> {
+    match mode {
+        CompressionMode::None => Arc::new(Passthrough),
+        CompressionMode::ZLib => Arc::new(ZlibCompressor::default()),
+        CompressionMode::LZ4 => Arc::new(Lz4Compressor),
+        mode => Arc::new(Unsupported(mode)),
+    }
+}
+
+/// Serializable choice of built-in write backend for [`crate::StorageConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionBackend {
+    /// Store writes uncompressed (mode 'N')
+    #[default]
+    None,
+    /// zlib at the given level (mode 'Z')
+    Zlib {
+        /// Compression level, 0-9
+        level: u32,
+    },
+    /// LZ4 (mode '4')
+    Lz4,
+}
+
+impl CompressionBackend {
+    /// Builds the compressor for this backend.
+    pub fn compressor(self) -> Arc<dyn ContentCompressor> {
+        match self {
+            Self::None => Arc::new(Passthrough),
+            Self::Zlib { level } => Arc::new(ZlibCompressor::new(level)),
+            Self::Lz4 => Arc::new(Lz4Compressor),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+
+    use super::*;
+
+    fn sample() -> Vec<u8> {
+        b"The quick brown fox jumps over the lazy dog. "
+            .repeat(64)
+            .into_iter()
+            .chain(0..=255u8)
+            .collect()
+    }
+
+    #[test]
+    fn test_backends_round_trip() {
+        let data = sample();
+        let backends: [Arc<dyn ContentCompressor>; 4] = [
+            Arc::new(Passthrough),
+            Arc::new(ZlibCompressor::default()),
+            Arc::new(ZlibCompressor::fast()),
+            Arc::new(Lz4Compressor),
+        ];
+        for backend in backends {
+            let compressed = backend.compress(&data).expect("compress");
+            let decompressed = backend.decompress(&compressed).expect("decompress");
+            assert_eq!(decompressed, data, "{backend:?}");
+        }
+    }
+
+    #[test]
+    fn test_zlib_output_readable_by_blte_decoder() {
+        let data = sample();
+        for level in 0..=ZlibCompressor::MAX_LEVEL {
+            let compressed = ZlibCompressor::new(level)
+                .compress(&data)
+                .expect("compress");
+            let decompressed =
+                decompress_chunk(&compressed, CompressionMode::ZLib).expect("decompress");
+            assert_eq!(decompressed, data, "level {level}");
+        }
+        assert_eq!(ZlibCompressor::new(42).level(), ZlibCompressor::MAX_LEVEL);
+    }
+
+    #[test]
+    fn test_backend_config() {
+        assert_eq!(CompressionBackend::default(), CompressionBackend::None);
+        assert_eq!(
+            CompressionBackend::Zlib { level: 1 }.compressor().mode(),
+            CompressionMode::ZLib
+        );
+        assert_eq!(
+            CompressionBackend::Lz4.compressor().mode(),
+            CompressionMode::LZ4
+        );
+
+        let json = serde_json::to_string(&CompressionBackend::Zlib { level: 3 })
+            .expect("serialize backend");
+        assert_eq!(json, r#"{"zlib":{"level":3}}"#);
+        let parsed: CompressionBackend = serde_json::from_str(r#""lz4""#).expect("parse backend");
+        assert_eq!(parsed, CompressionBackend::Lz4);
+    }
+
+    #[test]
+    fn test_unsupported_modes_fail_on_use() {
+        let encrypted = compressor_for_mode(CompressionMode::Encrypted);
+        assert_eq!(encrypted.mode(), CompressionMode::Encrypted);
+        assert!(
+            encrypted
+                .compress(b"data")
+                .expect_err("encrypted writes are rejected")
+                .to_string()
+                .contains("Encrypted compression not supported")
+        );
+    }
+}
@@ -53,7 +53,10 @@ impl Storage {
         }
 
         let installation_path = self.base_path.join(name);
-        let installation = Arc::new(Installation::open(installation_path)?);
+        let installation = Arc::new(Installation::open_with_compressor(
+            installation_path,
+            self.config.compression.compressor(),
+        )?);
 
         self.installations
             .insert(name.to_string(), installation.clone());
@@ -0,0 +1,365 @@
+//! Transactional multi-file writes
+//!
+//! Ingesting a build writes thousands of files. Written one at a time, a
+//! crash halfway through leaves archives with appended data that only some
+//! index entries reference. A [`WriteTransaction`] stages the index entries
+//! of all its writes and publishes them together on commit:
+//!
+//! 1. [`write`](WriteTransaction::write) appends BLTE data to the archives
+//!    and stages its index entry. Nothing references the data yet.
+//! 2. [`commit`](WriteTransaction::commit) syncs the touched archives, then
+//!    adds all staged entries under one index lock and saves each touched
+//!    bucket once.
+//! 3. [`rollback`](WriteTransaction::rollback), or dropping the
+//!    transaction, truncates the archives back to their length at
+//!    [`begin_write`](crate::Installation::begin_write) and discards the
+//!    staged entries.
+//!
+//! Readers look content up through the index, so they never see data of
+//! an uncommitted transaction. A crash before the index is saved leaves
+//! only unreferenced data at the end of the archives.
+
+use crate::index::IndexManager;
+use crate::storage::archive_file::ArchiveManager;
+use crate::{Result, StorageError};
+use cascette_crypto::EncodingKey;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use tokio::sync::{OwnedMutexGuard, RwLock as AsyncRwLock};
+use tracing::{debug, info, warn};
+
+/// Batch of archive writes whose index entries are published together
+///
+/// Created by [`Installation::begin_write`](crate::Installation::begin_write).
+/// The installation accepts no other writes while a transaction is open.
+#[must_use = "a transaction is rolled back when dropped"]
+pub struct WriteTransaction {
+    index_manager: Arc<AsyncRwLock<IndexManager>>,
+    archive_manager: Arc<AsyncRwLock<ArchiveManager>>,
+    /// Held until the transaction ends, serializing writers
+    _writer: OwnedMutexGuard<()>,
+    /// Archive write positions before the first write
+    start_positions: BTreeMap<u16, u64>,
+    /// `(key, archive_id, archive_offset, size)` of each write
+    staged: Vec<(EncodingKey, u16, u32, u32)>,
+    /// Set once committed or rolled back
+    finished: bool,
+}
+
+impl WriteTransaction {
+    pub(crate) async fn begin(
+        index_manager: Arc<AsyncRwLock<IndexManager>>,
+        archive_manager: Arc<AsyncRwLock<ArchiveManager>>,
+        writer: OwnedMutexGuard<()>,
+    ) -> Self {
+        let start_positions = archive_manager.read().await.write_positions();
+        Self {
+            index_manager,
+            archive_manager,
+            _writer: writer,
+            start_positions,
+            staged: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// Append BLTE-encoded `data` stored under `encoding_key`
+    ///
+    /// As with downloaded files, the caller is responsible for the key
+    /// matching the data. The entry becomes visible on
+    /// [`commit`](Self::commit).
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the data cannot be written to an archive
+    pub async fn write(&mut self, encoding_key: &EncodingKey, data: &[u8]) -> Result<()> {
+        let (archive_id, archive_offset, size, _) = self
+            .archive_manager
+            .write()
+            .await
+            .write_blte_with_key(data, encoding_key)?;
+        self.staged
+            .push((*encoding_key, archive_id, archive_offset, size));
+        Ok(())
+    }
+
+    /// Number of writes staged so far
+    pub fn len(&self) -> usize {
+        self.staged.len()
+    }
+
+    /// Whether nothing has been written yet
+    pub fn is_empty(&self) -> bool {
+        self.staged.is_empty()
+    }
+
+    /// Sync the written archives and publish all staged index entries
+    ///
+    /// Returns the number of entries published.
+    ///
+    /// # Errors
+    ///
+    /// If the archives cannot be synced, the transaction is rolled back and
+    /// the error returned. If saving an index bucket fails, the entries are
+    /// already visible in memory and point at synced data, so the archives
+    /// are kept; buckets saved before the failure keep the new entries.
+    pub async fn commit(mut self) -> Result<usize> {
+        let archives: BTreeSet<u16> = self.staged.iter().map(|&(_, id, _, _)| id).collect();
+        let synced = self.archive_manager.read().await.sync_archives(archives);
+        if let Err(e) = synced {
+            warn!("Failed to sync archives, rolling back transaction: {}", e);
+            self.undo().await?;
+            return Err(e);
+        }
+
+        // Nothing may be truncated from here on: entries reference the data
+        self.finished = true;
+        self.index_manager
+            .write()
+            .await
+            .publish_entries(&self.staged)?;
+
+        info!("Committed write transaction of {} files", self.staged.len());
+        Ok(self.staged.len())
+    }
+
+    /// Discard all writes of the transaction
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the archives cannot be truncated
+    pub async fn rollback(mut self) -> Result<()> {
+        self.undo().await
+    }
+
+    async fn undo(&mut self) -> Result<()> {
+        self.finished = true;
+        self.archive_manager
+            .write()
+            .await
+            .truncate_to(&self.start_positions)?;
+        debug!(
+            "Rolled back write transaction of {} files",
+            self.staged.len()
+        );
+        self.staged.clear();
+        Ok(())
+    }
+}
+
+impl Drop for WriteTransaction {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        // Drop cannot wait for the lock; if it is taken, the written data
+        // stays in the archives unreferenced, which is still consistent
+        let result = self.archive_manager.try_write().map_or_else(
+            |_| {
+                Err(StorageError::Archive(
+                    "archive manager is locked".to_string(),
+                ))
+            },
+            |mut archives| archives.truncate_to(&self.start_positions),
+        );
+        match result {
+            Ok(()) => debug!(
+                "Rolled back dropped write transaction of {} files",
+                self.staged.len()
+            ),
+            Err(e) => warn!(
+                "Failed to roll back dropped write transaction, leaving unreferenced data: {}",
+                e
+            ),
+        }
+    }
+}
+
+impl std::fmt::Debug for WriteTransaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WriteTransaction")
+            .field("staged", &self.staged.len())
+            .field("finished", &self.finished)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+// Transactions are held on purpose until commit or rollback
+#[allow(clippy::significant_drop_tightening)]
+mod tests {
+    use crate::Installation;
+    use cascette_crypto::EncodingKey;
+    use cascette_formats::CascFormat;
+    use cascette_formats::blte::{BlteBuilder, CompressionMode};
+
+    /// BLTE encoding of `data` and its encoding key
+    fn blte(data: &[u8]) -> (EncodingKey, Vec<u8>) {
+        let bytes = BlteBuilder::new()
+            .with_compression(CompressionMode::None)
+            .add_data(data)
+            .expect("Data should be added")
+            .build()
+            .expect("BLTE should build")
+            .build()
+            .expect("BLTE should serialize");
+        (EncodingKey::from_data(&bytes), bytes)
+    }
+
+    async fn open(dir: &tempfile::TempDir) -> Installation {
+        let installation =
+            Installation::open(dir.path().to_path_buf()).expect("Installation should open");
+        installation
+            .initialize()
+            .await
+            .expect("Installation should initialize");
+        installation
+    }
+
+    fn archive_len(dir: &tempfile::TempDir) -> u64 {
+        std::fs::metadata(dir.path().join(crate::DATA_DIR).join("data.000"))
+            .map_or(0, |metadata| metadata.len())
+    }
+
+    #[tokio::test]
+    async fn test_commit_publishes_all_entries() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open(&dir).await;
+        let files: Vec<_> = (0..3u8).map(|i| blte(&[i; 64])).collect();
+
+        let mut txn = installation.begin_write().await;
+        for (ekey, data) in &files {
+            txn.write(ekey, data).await.expect("Write should succeed");
+        }
+        assert_eq!(txn.len(), 3);
+        // Readers do not see staged entries, written data or not
+        for (ekey, _) in &files {
+            assert!(!installation.has_encoding_key(ekey).await);
+        }
+
+        assert_eq!(txn.commit().await.expect("Commit should succeed"), 3);
+        for (i, (ekey, _)) in files.iter().enumerate() {
+            let data = installation
+                .read_file_by_encoding_key(ekey)
+                .await
+                .expect("Committed file should be readable");
+            assert_eq!(data, vec![u8::try_from(i).expect("index fits"); 64]);
+        }
+
+        // Committed entries are on disk
+        drop(installation);
+        let reopened = open(&dir).await;
+        for (ekey, _) in &files {
+            assert!(reopened.has_encoding_key(ekey).await);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rollback_truncates_archives() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open(&dir).await;
+        installation
+            .write_file(vec![7; 32], false)
+            .await
+            .expect("Write should succeed");
+        let before = archive_len(&dir);
+
+        let (ekey, data) = blte(b"rolled back");
+        let mut txn = installation.begin_write().await;
+        txn.write(&ekey, &data).await.expect("Write should succeed");
+        assert!(archive_len(&dir) > before);
+        txn.rollback().await.expect("Rollback should succeed");
+
+        assert_eq!(archive_len(&dir), before);
+        assert!(!installation.has_encoding_key(&ekey).await);
+
+        // The writer lock is released and writes continue at the old end
+        let (ekey, data) = blte(b"after rollback");
+        let mut txn = installation.begin_write().await;
+        txn.write(&ekey, &data).await.expect("Write should succeed");
+        txn.commit().await.expect("Commit should succeed");
+        assert_eq!(
+            installation
+                .read_file_by_encoding_key(&ekey)
+                .await
+                .expect("File should be readable"),
+            b"after rollback"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dropped_transaction_rolls_back() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open(&dir).await;
+
+        let (ekey, data) = blte(b"dropped");
+        {
+            let mut txn = installation.begin_write().await;
+            txn.write(&ekey, &data).await.expect("Write should succeed");
+        }
+
+        // The transaction created the archive, so it is removed again
+        assert_eq!(archive_len(&dir), 0);
+        assert!(!installation.has_encoding_key(&ekey).await);
+        installation
+            .write_file(vec![1; 16], false)
+            .await
+            .expect("Write after drop should succeed");
+    }
+
+    #[tokio::test]
+    async fn test_crash_before_index_publish_leaves_storage_consistent() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let installation = open(&dir).await;
+
+        let committed = blte(b"committed before the crash");
+        let mut txn = installation.begin_write().await;
+        txn.write(&committed.0, &committed.1)
+            .await
+            .expect("Write should succeed");
+        txn.commit().await.expect("Commit should succeed");
+        let committed_len = archive_len(&dir);
+
+        // Simulate the process dying between archive write and index
+        // publish: neither commit nor rollback runs
+        let lost = blte(b"lost in the crash");
+        let mut txn = installation.begin_write().await;
+        txn.write(&lost.0, &lost.1)
+            .await
+            .expect("Write should succeed");
+        std::mem::forget(txn);
+        drop(installation);
+        assert!(archive_len(&dir) > committed_len);
+
+        let reopened = open(&dir).await;
+        assert_eq!(
+            reopened
+                .read_file_by_encoding_key(&committed.0)
+                .await
+                .expect("Committed file should survive"),
+            b"committed before the crash"
+        );
+        assert!(!reopened.has_encoding_key(&lost.0).await);
+
+        // New writes go after the unreferenced tail and stay readable
+        let later = blte(b"written after restart");
+        let mut txn = reopened.begin_write().await;
+        txn.write(&later.0, &later.1)
+            .await
+            .expect("Write should succeed");
+        txn.commit().await.expect("Commit should succeed");
+        for (ekey, expected) in [
+            (&committed.0, &b"committed before the crash"[..]),
+            (&later.0, &b"written after restart"[..]),
+        ] {
+            assert_eq!(
+                reopened
+                    .read_file_by_encoding_key(ekey)
+                    .await
+                    .expect("File should be readable"),
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,605 @@
+//! CDN fallback for files missing from local storage.
+//!
+//! An installation whose resolver has a CDN fallback downloads missing
+//! files from a mock CDN, stores them, and serves later reads from disk.
+
+#![allow(clippy::expect_used)]
+
+use cascette_client_storage::{
+    BuildInfoFile, ContentResolver, Installation, ResolutionMetrics, ResolutionSource, StorageError,
+};
+use cascette_crypto::EncodingKey;
+use cascette_formats::CascFormat;
+use cascette_formats::archive::ArchiveIndexBuilder;
+use cascette_formats::blte::{BlteBuilder, CompressionMode};
+use cascette_formats::download::DownloadManifestBuilder;
+use cascette_protocol::cache::ProtocolCache;
+use cascette_protocol::{CacheConfig, CdnClient, CdnConfig, CdnEndpoint, RetryPolicy};
+use std::io::Cursor;
+use std::sync::Arc;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn sample() -> Vec<u8> {
+    (0..4096u32).flat_map(u32::to_le_bytes).collect()
+}
+
+/// `data` as a chunked BLTE stream and its encoding key, the MD5 of the
+/// BLTE header.
+fn chunked_blte(data: &[u8]) -> (Vec<u8>, EncodingKey) {
+    let blte = BlteBuilder::new()
+        .with_compression(CompressionMode::ZLib)
+        .with_chunk_size_unchecked(1024)
+        .add_data(data)
+        .expect("Data should be added")
+        .build()
+        .expect("BLTE should build")
+        .build()
+        .expect("BLTE should serialize");
+    let header_size = u32::from_be_bytes(blte[4..8].try_into().expect("BLTE header size"));
+    let key = EncodingKey::from_data(&blte[..header_size as usize]);
+    (blte, key)
+}
+
+fn cdn_path(key: &EncodingKey) -> String {
+    let hex = hex::encode(key.as_bytes());
+    format!("/tpr/wow/data/{}/{}/{hex}", &hex[..2], &hex[2..4])
+}
+
+/// Open an installation in `dir` that falls back to `server`.
+fn open_with_fallback(dir: &tempfile::TempDir, server: &MockServer) -> Installation {
+    open_with_config(dir, server, CdnConfig::default())
+}
+
+fn open_with_config(
+    dir: &tempfile::TempDir,
+    server: &MockServer,
+    config: CdnConfig,
+) -> Installation {
+    open_with_resolver(dir, server, config, |resolver| resolver)
+}
+
+fn open_with_resolver(
+    dir: &tempfile::TempDir,
+    server: &MockServer,
+    config: CdnConfig,
+    configure: impl FnOnce(ContentResolver) -> ContentResolver,
+) -> Installation {
+    let cache = ProtocolCache::new(&CacheConfig {
+        cache_dir: Some(dir.path().join("cdn-cache")),
+        ..Default::default()
+    })
+    .expect("CDN cache should open");
+    let client = CdnClient::new(Arc::new(cache), config).expect("CDN client should build");
+    let endpoint = CdnEndpoint {
+        host: server.uri().replace("http://", ""),
+        path: "tpr/wow".to_string(),
+        product_path: None,
+        scheme: Some("http".to_string()),
+        is_fallback: false,
+        strict: false,
+        max_hosts: None,
+    };
+
+    Installation::open(dir.path().join("Data"))
+        .expect("Installation should open")
+        .with_resolver(configure(
+            ContentResolver::new().with_cdn_fallback(client, endpoint),
+        ))
+}
+
+#[tokio::test]
+async fn missing_file_is_downloaded_and_stored_locally() {
+    let server = MockServer::start().await;
+    let data = sample();
+    let (blte, key) = chunked_blte(&data);
+    Mock::given(method("GET"))
+        .and(path(cdn_path(&key)))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(blte))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().expect("Temp dir should be created");
+    let installation = open_with_fallback(&dir, &server);
+    assert!(!installation.has_encoding_key(&key).await);
+
+    let read = installation
+        .read_file_by_encoding_key(&key)
+        .await
+        .expect("File should be downloaded");
+    assert_eq!(read, data);
+    assert!(installation.has_encoding_key(&key).await);
+    assert_eq!(
+        installation.resolution_metrics().last_source,
+        Some(ResolutionSource::Cdn)
+    );
+
+    // A fresh read of the archives finds the stored copy without the CDN
+    let location = installation
+        .get_all_index_entries()
+        .await
+        .into_iter()
+        .next()
+        .expect("Downloaded file should be indexed");
+    let stored = installation
+        .read_from_archive(
+            location.archive_id(),
+            location.archive_offset(),
+            location.size,
+        )
+        .await
+        .expect("Stored copy should read back");
+    assert_eq!(stored, data);
+
+    installation
+        .read_file_by_encoding_key(&key)
+        .await
+        .expect("Second read should succeed");
+    assert_eq!(
+        installation.resolution_metrics(),
+        ResolutionMetrics {
+            local: 1,
+            cdn: 1,
+            last_source: Some(ResolutionSource::Local),
+        }
+    );
+}
+
+#[tokio::test]
+async fn archived_file_is_fetched_with_a_range_request() {
+    let server = MockServer::start().await;
+    let data = sample();
+    let (blte, key) = chunked_blte(&data);
+
+    // The file sits between other data in an archive, with no loose copy
+    let mut archive = vec![0xAA; 100];
+    let mut builder = ArchiveIndexBuilder::new();
+    builder.add_entry(
+        key.as_bytes().to_vec(),
+        blte.len() as u32,
+        archive.len() as u64,
+    );
+    archive.extend_from_slice(&blte);
+    archive.extend_from_slice(&[0xBB; 100]);
+    let mut index = Cursor::new(Vec::new());
+    let built = builder.build(&mut index).expect("Index should build");
+    let archive_key = hex::encode(built.footer.name_hash());
+    let archive_path = format!(
+        "/tpr/wow/data/{}/{}/{archive_key}",
+        &archive_key[..2],
+        &archive_key[2..4]
+    );
+    Mock::given(method("GET"))
+        .and(path(format!("{archive_path}.index")))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(index.into_inner()))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let range = format!("bytes=100-{}", 100 + blte.len() - 1);
+    Mock::given(method("GET"))
+        .and(path(archive_path))
+        .and(header("range", range.as_str()))
+        .respond_with(ResponseTemplate::new(206).set_body_bytes(blte))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().expect("Temp dir should be created");
+    let installation = open_with_resolver(&dir, &server, CdnConfig::default(), |resolver| {
+        resolver.with_cdn_archives(vec![archive_key])
+    });
+    let read = installation
+        .read_file_by_encoding_key(&key)
+        .await
+        .expect("File should be downloaded from its archive");
+    assert_eq!(read, data);
+    assert!(installation.has_encoding_key(&key).await);
+}
+
+#[tokio::test]
+async fn mismatched_download_is_not_stored() {
+    let server = MockServer::start().await;
+    let (_, key) = chunked_blte(&sample());
+    let (other, _) = chunked_blte(b"some other file");
+    Mock::given(method("GET"))
+        .and(path(cdn_path(&key)))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(other))
+        .mount(&server)
+        .await;
+
+    // The CDN client rejects the download itself
+    let without_retries = CdnConfig::default().with_retry_policy(RetryPolicy {
+        max_attempts: 0,
+        ..RetryPolicy::default()
+    });
+    let dir = tempfile::tempdir().expect("Temp dir should be created");
+    let installation = open_with_config(&dir, &server, without_retries.clone());
+    let result = installation.read_file_by_encoding_key(&key).await;
+    assert!(matches!(result, Err(StorageError::Cdn(_))));
+    assert!(!installation.has_encoding_key(&key).await);
+
+    // Without client verification, the installation still checks the key
+    let dir = tempfile::tempdir().expect("Temp dir should be created");
+    let installation = open_with_config(&dir, &server, without_retries.verify_checksums(false));
+    let result = installation.read_file_by_encoding_key(&key).await;
+    assert!(matches!(result, Err(StorageError::Verification(_))));
+    assert!(!installation.has_encoding_key(&key).await);
+    assert_eq!(installation.resolution_metrics().cdn, 0);
+}
+
+#[tokio::test]
+async fn fallback_is_opt_in() {
+    let (_, key) = chunked_blte(&sample());
+    let dir = tempfile::tempdir().expect("Temp dir should be created");
+    let installation =
+        Installation::open(dir.path().to_path_buf()).expect("Installation should open");
+
+    let result = installation.read_file_by_encoding_key(&key).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+#[tokio::test]
+// The transaction is held on purpose until commit
+#[allow(clippy::significant_drop_tightening)]
+async fn resume_skips_intact_files_and_refetches_corrupt_ones() {
+    let server = MockServer::start().await;
+    let files: Vec<_> = (0..5u32)
+        .map(|i| {
+            let data: Vec<u8> = (0..2048u32)
+                .flat_map(|n| (n * 7 + i).to_le_bytes())
+                .collect();
+            let (blte, key) = chunked_blte(&data);
+            (data, blte, key)
+        })
+        .collect();
+    let [intact, corrupt, resized, missing, unavailable] = &files[..] else {
+        unreachable!("five files");
+    };
+    for (_, blte, key) in [corrupt, resized, missing] {
+        Mock::given(method("GET"))
+            .and(path(cdn_path(key)))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(blte.clone()))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    let without_retries = CdnConfig::default().with_retry_policy(RetryPolicy {
+        max_attempts: 0,
+        ..RetryPolicy::default()
+    });
+    let dir = tempfile::tempdir().expect("Temp dir should be created");
+    let installation = open_with_config(&dir, &server, without_retries);
+    installation
+        .initialize()
+        .await
+        .expect("Installation should initialize");
+
+    // An interrupted download: one file intact, one with a flipped byte
+    // and one truncated
+    let mut flipped = corrupt.1.clone();
+    let last = flipped.len() - 1;
+    flipped[last] ^= 0xFF;
+    let mut txn = installation.begin_write().await;
+    txn.write(&intact.2, &intact.1)
+        .await
+        .expect("Write should succeed");
+    txn.write(&corrupt.2, &flipped)
+        .await
+        .expect("Write should succeed");
+    txn.write(&resized.2, &resized.1[..resized.1.len() - 16])
+        .await
+        .expect("Write should succeed");
+    txn.commit().await.expect("Commit should succeed");
+
+    let mut builder = DownloadManifestBuilder::new(1).expect("Builder should be created");
+    for (_, blte, key) in &files {
+        builder = builder
+            .add_file(*key, blte.len() as u64, 0)
+            .expect("File should be added");
+    }
+    let manifest = builder.build().expect("Manifest should build");
+
+    // Without verification, only the size gives the truncated file away
+    let report = installation
+        .resume_download(&manifest, false)
+        .await
+        .expect("Resume should succeed");
+    assert_eq!(report.expected, 5);
+    assert_eq!(report.skipped, 2);
+    assert_eq!(report.verified, 0);
+    assert_eq!(report.redownloaded, 1);
+    assert_eq!(report.downloaded, 1);
+    assert_eq!(
+        report.bytes_downloaded,
+        (resized.1.len() + missing.1.len()) as u64
+    );
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(
+        report.failed[0].encoding_key,
+        hex::encode(unavailable.2.as_bytes())
+    );
+
+    // Verification finds the flipped byte
+    let report = installation
+        .resume_download(&manifest, true)
+        .await
+        .expect("Resume should succeed");
+    assert_eq!(report.skipped, 0);
+    assert_eq!(report.verified, 3);
+    assert_eq!(report.redownloaded, 1);
+    assert_eq!(report.downloaded, 0);
+    assert!(!report.is_complete());
+
+    for (data, _, key) in [intact, corrupt, resized, missing] {
+        let read = installation
+            .read_file_by_encoding_key(key)
+            .await
+            .expect("File should be stored");
+        assert_eq!(&read, data);
+    }
+    let report = installation
+        .resume_download(&manifest, true)
+        .await
+        .expect("Resume should succeed");
+    assert_eq!(report.verified, 4);
+    assert_eq!(report.failed.len(), 1);
+}
+
+#[tokio::test]
+// The transaction is held on purpose until commit
+#[allow(clippy::significant_drop_tightening)]
+async fn verify_repairs_corrupt_files_from_cdn() {
+    let server = MockServer::start().await;
+    let files: Vec<_> = (0..3u32)
+        .map(|i| {
+            let data: Vec<u8> = (0..2048u32)
+                .flat_map(|n| (n * 3 + i).to_le_bytes())
+                .collect();
+            let (blte, key) = chunked_blte(&data);
+            (data, blte, key)
+        })
+        .collect();
+    let [intact, corrupt, unknown] = &files[..] else {
+        unreachable!("three files");
+    };
+    Mock::given(method("GET"))
+        .and(path(cdn_path(&corrupt.2)))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(corrupt.1.clone()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().expect("Temp dir should be created");
+    let locations = {
+        let installation = open_with_fallback(&dir, &server);
+        installation
+            .initialize()
+            .await
+            .expect("Installation should initialize");
+        let mut txn = installation.begin_write().await;
+        for (_, blte, key) in &files {
+            txn.write(key, blte).await.expect("Write should succeed");
+        }
+        txn.commit().await.expect("Commit should succeed");
+        let mut locations = Vec::new();
+        for (_, _, key) in &files {
+            let entry = installation
+                .get_all_index_entries()
+                .await
+                .into_iter()
+                .find(|entry| entry.key_bytes() == &key.as_bytes()[..9])
+                .expect("File should be indexed");
+            locations.push((entry.archive_id(), entry.archive_offset(), entry.size));
+        }
+        locations
+    };
+
+    // Flip the last BLTE byte of one file and a key byte in the local
+    // header of another, which stores the key reversed
+    let archive = dir.path().join("Data").join("data").join("data.000");
+    let mut bytes = std::fs::read(&archive).expect("Archive should read");
+    let (_, offset, size) = locations[1];
+    bytes[offset as usize + size as usize - 1] ^= 0xFF;
+    let (_, offset, _) = locations[2];
+    bytes[offset as usize + 15] ^= 0xFF;
+    std::fs::write(&archive, bytes).expect("Archive should write");
+
+    let installation = open_with_fallback(&dir, &server);
+    installation
+        .initialize()
+        .await
+        .expect("Installation should initialize");
+
+    let report = installation
+        .verify_and_repair(&[], false)
+        .await
+        .expect("Verification should succeed");
+    assert_eq!(report.checked, 3);
+    assert_eq!(report.intact, 1);
+    assert!(report.repaired.is_empty());
+    assert_eq!(report.unrecoverable.len(), 2);
+
+    let report = installation
+        .verify_and_repair(&[], true)
+        .await
+        .expect("Repair should succeed");
+    assert_eq!(report.intact, 1);
+    assert_eq!(report.repaired, [hex::encode(corrupt.2.as_bytes())]);
+    assert_eq!(report.unrecoverable.len(), 1);
+    assert_eq!(
+        report.unrecoverable[0].encoding_key,
+        hex::encode(&unknown.2.as_bytes()[..9])
+    );
+    assert_eq!(report.unrecoverable[0].reason, "full encoding key unknown");
+
+    for (data, _, key) in [intact, corrupt] {
+        let read = installation
+            .read_file_by_encoding_key(key)
+            .await
+            .expect("File should read");
+        assert_eq!(&read, data);
+    }
+    let report = installation
+        .verify_and_repair(&[], true)
+        .await
+        .expect("Verification should succeed");
+    assert_eq!(report.intact, 2);
+    assert!(report.repaired.is_empty());
+
+    // Without a CDN fallback, corrupt files cannot be fixed offline
+    drop(installation);
+    let mut bytes = std::fs::read(&archive).expect("Archive should read");
+    let (_, offset, size) = locations[0];
+    bytes[offset as usize + size as usize - 1] ^= 0xFF;
+    std::fs::write(&archive, bytes).expect("Archive should write");
+    let offline = Installation::open(dir.path().join("Data")).expect("Installation should open");
+    offline
+        .initialize()
+        .await
+        .expect("Installation should initialize");
+    let report = offline
+        .verify_and_repair(&[], true)
+        .await
+        .expect("Verification should succeed");
+    assert_eq!(report.intact, 1);
+    assert!(report.unrecoverable.iter().any(|failure| {
+        failure.encoding_key == hex::encode(intact.2.as_bytes())
+            && failure.reason == "no CDN fallback is configured"
+    }));
+}
+
+#[tokio::test]
+// The transaction is held on purpose until commit
+#[allow(clippy::significant_drop_tightening)]
+async fn verify_fetches_missing_files_from_cdn_in_build_info() {
+    let server = MockServer::start().await;
+    let files: Vec<_> = (0..2u32)
+        .map(|i| {
+            let data: Vec<u8> = (0..1024u32).flat_map(|n| (n ^ i).to_le_bytes()).collect();
+            let (blte, key) = chunked_blte(&data);
+            (data, blte, key)
+        })
+        .collect();
+    let [stored, missing] = &files[..] else {
+        unreachable!("two files");
+    };
+
+    // The missing file is only in an archive named by the CDN config
+    let mut builder = ArchiveIndexBuilder::new();
+    builder.add_entry(missing.2.as_bytes().to_vec(), missing.1.len() as u32, 0);
+    let mut index = Cursor::new(Vec::new());
+    let built = builder.build(&mut index).expect("Index should build");
+    let archive_key = hex::encode(built.footer.name_hash());
+    let archive_path = format!(
+        "/tpr/wow/data/{}/{}/{archive_key}",
+        &archive_key[..2],
+        &archive_key[2..4]
+    );
+    let cdn_config = format!("archives = {archive_key}\n");
+    let cdn_key = hex::encode(md5::compute(&cdn_config).0);
+    for (route, body) in [
+        (
+            format!(
+                "/tpr/wow/config/{}/{}/{cdn_key}",
+                &cdn_key[..2],
+                &cdn_key[2..4]
+            ),
+            cdn_config.into_bytes(),
+        ),
+        (format!("{archive_path}.index"), index.into_inner()),
+        (archive_path, missing.1.clone()),
+    ] {
+        Mock::given(method("GET"))
+            .and(path(route))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
+            .mount(&server)
+            .await;
+    }
+
+    let dir = tempfile::tempdir().expect("Temp dir should be created");
+    let build_info = BuildInfoFile::parse_str(&format!(
+        "Branch!STRING:0|Active!DEC:1|Build Key!HEX:16|CDN Key!HEX:16|CDN Path!STRING:0|CDN Hosts!STRING:0|CDN Servers!STRING:0\n\
+         us|1|{}|{cdn_key}|tpr/wow|cdn.invalid|{}/?maxhosts=4",
+        "ab".repeat(16),
+        server.uri()
+    ))
+    .expect("Build info should parse");
+    let cache = ProtocolCache::new(&CacheConfig {
+        cache_dir: Some(dir.path().join("cdn-cache")),
+        ..Default::default()
+    })
+    .expect("CDN cache should open");
+    let client =
+        CdnClient::new(Arc::new(cache), CdnConfig::default()).expect("CDN client should build");
+    let resolver = ContentResolver::new()
+        .with_cdn_fallback_from_build_info(client, &build_info)
+        .await
+        .expect("CDN should be discovered");
+
+    let installation = Installation::open(dir.path().join("Data"))
+        .expect("Installation should open")
+        .with_resolver(resolver);
+    installation
+        .initialize()
+        .await
+        .expect("Installation should initialize");
+    let mut txn = installation.begin_write().await;
+    txn.write(&stored.2, &stored.1)
+        .await
+        .expect("Write should succeed");
+    txn.commit().await.expect("Commit should succeed");
+
+    let expected = [stored.2, missing.2, missing.2];
+    let report = installation
+        .verify_and_repair(&expected, false)
+        .await
+        .expect("Verification should succeed");
+    assert_eq!((report.checked, report.intact, report.missing), (1, 1, 1));
+    assert_eq!(report.unrecoverable.len(), 1);
+    assert_eq!(
+        report.unrecoverable[0].encoding_key,
+        hex::encode(missing.2.as_bytes())
+    );
+
+    let report = installation
+        .verify_and_repair(&expected, true)
+        .await
+        .expect("Repair should succeed");
+    assert_eq!(report.missing, 1);
+    assert_eq!(report.repaired, [hex::encode(missing.2.as_bytes())]);
+    assert!(report.is_healthy());
+    let read = installation
+        .read_file_by_encoding_key(&missing.2)
+        .await
+        .expect("File should read");
+    assert_eq!(&read, &missing.0);
+
+    let report = installation
+        .verify_and_repair(&expected, true)
+        .await
+        .expect("Verification should succeed");
+    assert_eq!((report.checked, report.intact, report.missing), (2, 2, 0));
+}
+
+#[tokio::test]
+async fn build_info_without_cdn_is_rejected() {
+    let build_info = BuildInfoFile::parse_str(
+        "Branch!STRING:0|Active!DEC:1|CDN Path!STRING:0|CDN Hosts!STRING:0\nus|1|tpr/wow|",
+    )
+    .expect("Build info should parse");
+    let dir = tempfile::tempdir().expect("Temp dir should be created");
+    let cache = ProtocolCache::new(&CacheConfig {
+        cache_dir: Some(dir.path().to_path_buf()),
+        ..Default::default()
+    })
+    .expect("CDN cache should open");
+    let client =
+        CdnClient::new(Arc::new(cache), CdnConfig::default()).expect("CDN client should build");
+
+    let result = ContentResolver::new()
+        .with_cdn_fallback_from_build_info(client, &build_info)
+        .await;
+    assert!(matches!(result, Err(StorageError::Config(_))));
+}
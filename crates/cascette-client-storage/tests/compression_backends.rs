@@ -0,0 +1,170 @@
+//! Cross-backend readability of stored content.
+//!
+//! Content written with one compressor must read back from an installation
+//! configured with any other, since reads decode the BLTE mode stored with
+//! each chunk.
+
+#![allow(clippy::expect_used)]
+
+use cascette_client_storage::storage::{
+    CompressionBackend, ContentCompressor, Lz4Compressor, Passthrough, ZlibCompressor,
+};
+use cascette_client_storage::{Installation, Storage, StorageConfig};
+use cascette_crypto::EncodingKey;
+use cascette_formats::CascFormat;
+use cascette_formats::blte::{BlteFile, BlteHeader, ChunkData};
+use std::sync::Arc;
+
+fn sample() -> Vec<u8> {
+    b"<Ui><Frame name=\"CompressionTest\"/></Ui>\n"
+        .repeat(200)
+        .into_iter()
+        .chain((0..4096u32).flat_map(u32::to_le_bytes))
+        .collect()
+}
+
+fn backends() -> Vec<Arc<dyn ContentCompressor>> {
+    vec![
+        Arc::new(Passthrough),
+        Arc::new(ZlibCompressor::fast()),
+        Arc::new(ZlibCompressor::default()),
+        Arc::new(ZlibCompressor::new(ZlibCompressor::MAX_LEVEL)),
+        Arc::new(Lz4Compressor),
+    ]
+}
+
+/// Encoding key of `data` stored as a single chunk by `compressor`.
+fn encoding_key(data: &[u8], compressor: &dyn ContentCompressor) -> EncodingKey {
+    let chunk = ChunkData::from_compressed(
+        compressor.mode(),
+        compressor.compress(data).expect("Data should compress"),
+        Some(data.len()),
+    );
+    let blte = BlteFile {
+        header: BlteHeader::single_chunk(),
+        chunks: vec![chunk],
+    }
+    .build()
+    .expect("BLTE should build");
+    EncodingKey::from_data(&blte)
+}
+
+#[tokio::test]
+async fn test_content_readable_across_backends() {
+    let data = sample();
+
+    for writer in backends() {
+        for reader in backends() {
+            let dir = tempfile::tempdir().expect("Temp dir should be created");
+            let installation =
+                Installation::open_with_compressor(dir.path().to_path_buf(), writer.clone())
+                    .expect("Installation should open");
+            installation
+                .write_file(data.clone(), true)
+                .await
+                .expect("File should be written");
+            installation
+                .compact_indices_if_needed(0.0)
+                .await
+                .expect("Indices should be flushed");
+            drop(installation);
+
+            let installation =
+                Installation::open_with_compressor(dir.path().to_path_buf(), reader.clone())
+                    .expect("Installation should reopen");
+            installation
+                .initialize()
+                .await
+                .expect("Installation should initialize");
+            let read = installation
+                .read_file_by_encoding_key(&encoding_key(&data, writer.as_ref()))
+                .await
+                .expect("File should be read");
+            assert_eq!(read, data, "written with {writer:?}, read with {reader:?}");
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_per_write_override() {
+    let dir = tempfile::tempdir().expect("Temp dir should be created");
+    let installation = Installation::open_with_compressor(
+        dir.path().to_path_buf(),
+        Arc::new(ZlibCompressor::default()),
+    )
+    .expect("Installation should open");
+    let data = sample();
+
+    installation
+        .write_file_with(data.clone(), &Lz4Compressor)
+        .await
+        .expect("Override write should succeed");
+    let lz4_key = encoding_key(&data, &Lz4Compressor);
+    assert_eq!(
+        installation
+            .read_file_by_encoding_key(&lz4_key)
+            .await
+            .expect("LZ4 file should be read"),
+        data
+    );
+
+    // The configured compressor still applies to plain writes, and can be
+    // swapped without affecting what was already stored
+    let other = b"configured compressor".repeat(32);
+    installation
+        .write_file(other.clone(), true)
+        .await
+        .expect("Write should succeed");
+    installation.set_compressor(Arc::new(Passthrough)).await;
+    assert_eq!(
+        installation
+            .read_file_by_encoding_key(&encoding_key(&other, &ZlibCompressor::default()))
+            .await
+            .expect("ZLib file should be read"),
+        other
+    );
+    assert_eq!(
+        installation
+            .read_file_by_encoding_key(&lz4_key)
+            .await
+            .expect("LZ4 file should still be read"),
+        data
+    );
+}
+
+#[tokio::test]
+async fn test_storage_config_selects_backend() {
+    let dir = tempfile::tempdir().expect("Temp dir should be created");
+    let config = StorageConfig::new(dir.path()).with_compression(CompressionBackend::Lz4);
+    let storage = Storage::new(config).expect("Storage should be created");
+    let installation = storage
+        .open_installation("wow")
+        .expect("Installation should open");
+    let data = sample();
+
+    installation
+        .write_file(data.clone(), true)
+        .await
+        .expect("File should be written");
+    assert_eq!(
+        installation
+            .read_file_by_encoding_key(&encoding_key(&data, &Lz4Compressor))
+            .await
+            .expect("File should be stored as LZ4"),
+        data
+    );
+
+    // compress = false bypasses the configured backend
+    let raw = b"stored uncompressed".to_vec();
+    installation
+        .write_file(raw.clone(), false)
+        .await
+        .expect("File should be written");
+    assert_eq!(
+        installation
+            .read_file_by_encoding_key(&encoding_key(&raw, &Passthrough))
+            .await
+            .expect("File should be stored as mode N"),
+        raw
+    );
+}
@@ -0,0 +1,90 @@
+/*
+ * Reads a file from a synthetic installation through the C ABI.
+ *
+ * Usage: read_installation <install dir> <ekey hex> <expected contents>
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "cascette_client_storage.h"
+
+#define CHECK(cond)                                                        \
+    do {                                                                   \
+        if (!(cond)) {                                                     \
+            const char *message = cascette_install_last_error_message();   \
+            fprintf(stderr, "%s:%d: check failed: %s (%s)\n", __FILE__,    \
+                    __LINE__, #cond, message ? message : "no error");      \
+            return 1;                                                      \
+        }                                                                  \
+    } while (0)
+
+static int parse_ekey(const char *hex, uint8_t ekey[CASCETTE_EKEY_LENGTH]) {
+    if (strlen(hex) != CASCETTE_EKEY_LENGTH * 2) {
+        return -1;
+    }
+    for (size_t i = 0; i < CASCETTE_EKEY_LENGTH; i++) {
+        unsigned int byte;
+        if (sscanf(hex + i * 2, "%2x", &byte) != 1) {
+            return -1;
+        }
+        ekey[i] = (uint8_t)byte;
+    }
+    return 0;
+}
+
+int main(int argc, char **argv) {
+    if (argc != 4) {
+        fprintf(stderr, "usage: %s <install dir> <ekey hex> <expected>\n", argv[0]);
+        return 2;
+    }
+
+    uint8_t ekey[CASCETTE_EKEY_LENGTH];
+    CHECK(parse_ekey(argv[2], ekey) == 0);
+    const char *expected = argv[3];
+    size_t expected_len = strlen(expected);
+
+    CHECK(cascette_install_open(NULL) == NULL);
+    CHECK(cascette_install_last_error_message() != NULL);
+
+    CascetteInstall *install = cascette_install_open(argv[1]);
+    CHECK(install != NULL);
+    CHECK(cascette_install_contains(install, ekey) == 1);
+
+    /* Size query */
+    size_t len = 0;
+    CHECK(cascette_install_read(install, ekey, NULL, &len) == CASCETTE_OK);
+    CHECK(len == expected_len);
+    CHECK(cascette_install_last_error_message() == NULL);
+
+    /* Too small a buffer is rejected and reports the size */
+    uint8_t small[1];
+    size_t small_len = sizeof(small);
+    CHECK(cascette_install_read(install, ekey, small, &small_len) ==
+          CASCETTE_ERROR_BUFFER_TOO_SMALL);
+    CHECK(small_len == expected_len);
+
+    uint8_t *buf = malloc(len);
+    CHECK(buf != NULL);
+    CHECK(cascette_install_read(install, ekey, buf, &len) == CASCETTE_OK);
+    CHECK(memcmp(buf, expected, expected_len) == 0);
+    free(buf);
+
+    /* Unknown keys report a distinct not-found code */
+    uint8_t missing[CASCETTE_EKEY_LENGTH];
+    memset(missing, 0xAB, sizeof(missing));
+    CHECK(cascette_install_contains(install, missing) == 0);
+    CHECK(cascette_install_read(install, missing, NULL, &len) ==
+          CASCETTE_ERROR_NOT_FOUND);
+    CHECK(cascette_install_last_error_message() != NULL);
+
+    CHECK(cascette_install_read(install, NULL, NULL, &len) ==
+          CASCETTE_ERROR_NULL_ARGUMENT);
+
+    cascette_install_close(install);
+    cascette_install_close(NULL);
+
+    printf("ok\n");
+    return 0;
+}
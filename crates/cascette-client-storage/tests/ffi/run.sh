@@ -0,0 +1,21 @@
+#!/usr/bin/env bash
+# Build the cdylib, write a synthetic installation and run the C test
+# program against it.
+set -euo pipefail
+
+crate_dir="$(cd "$(dirname "$0")/../.." && pwd)"
+target_dir="$(cargo metadata --format-version 1 --no-deps | sed -n 's/.*"target_directory":"\([^"]*\)".*/\1/p')"
+work_dir="$(mktemp -d)"
+trap 'rm -rf "$work_dir"' EXIT
+
+cargo rustc -p cascette-client-storage --features ffi --lib --crate-type cdylib
+contents="hello from the C test"
+ekey="$(cargo run -q -p cascette-client-storage --features ffi --example ffi_fixture -- "$work_dir/install" "$contents")"
+
+cc -std=c99 -Wall -Wextra -Werror \
+  -I "$crate_dir/include" \
+  "$crate_dir/tests/ffi/read_installation.c" \
+  -L "$target_dir/debug" -lcascette_client_storage \
+  -o "$work_dir/read_installation"
+
+LD_LIBRARY_PATH="$target_dir/debug" "$work_dir/read_installation" "$work_dir/install" "$ekey" "$contents"
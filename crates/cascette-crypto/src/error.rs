@@ -30,4 +30,26 @@ pub enum CryptoError {
     /// Invalid key format
     #[error("Invalid key format: {0}")]
     InvalidKeyFormat(String),
+
+    /// Invalid named key entry in a keyring file
+    #[error("Invalid key '{name}': {reason}")]
+    InvalidKey {
+        /// Key name as written in the file
+        name: String,
+        /// Why the entry was rejected
+        reason: String,
+    },
+
+    /// Fetching a key list failed
+    #[error("Failed to fetch key list from {url}: {reason}")]
+    Fetch {
+        /// URL of the key list
+        url: String,
+        /// Error reported by the fetcher
+        reason: String,
+    },
+
+    /// I/O error while reading a key file or streaming data
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
 }
@@ -421,6 +421,157 @@ fn hashlittle2_impl(key: &[u8], pc: &mut u32, pb: &mut u32) {
     *pb = b;
 }
 
+/// Incremental `hashlittle()` over data fed in pieces
+///
+/// lookup3 seeds its state with the total input length, so the hash of
+/// data fed piece by piece can only be computed in constant memory when
+/// the length is known up front, as with [`with_len`](Self::with_len).
+/// A hasher from [`new`](Self::new) buffers its input until
+/// [`finalize`](Self::finalize) instead.
+///
+/// Either way the result equals [`hashlittle`] of the concatenated input,
+/// however it was split.
+///
+/// # Examples
+///
+/// ```
+/// use cascette_crypto::jenkins::{Jenkins96Hasher, hashlittle};
+///
+/// let mut hasher = Jenkins96Hasher::with_len(0, 9);
+/// hasher.update(b"test ");
+/// hasher.update(b"data");
+/// assert_eq!(hasher.finalize(), hashlittle(b"test data", 0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Jenkins96Hasher {
+    state: HasherState,
+}
+
+#[derive(Debug, Clone)]
+enum HasherState {
+    /// Length unknown; input is kept until it is
+    Buffered { initval: u32, data: Vec<u8> },
+    /// Length known; full blocks are mixed as soon as more input follows
+    Streaming {
+        a: u32,
+        b: u32,
+        c: u32,
+        /// Last block seen, not mixed until more input arrives
+        block: [u8; 12],
+        filled: usize,
+    },
+}
+
+impl Jenkins96Hasher {
+    /// Create a hasher for input of unknown length
+    ///
+    /// The input is buffered until [`finalize`](Self::finalize); use
+    /// [`with_len`](Self::with_len) to hash large data in constant memory.
+    pub fn new(init: u32) -> Self {
+        Self {
+            state: HasherState::Buffered {
+                initval: init,
+                data: Vec::new(),
+            },
+        }
+    }
+
+    /// Create a hasher for exactly `len` bytes of input
+    ///
+    /// The result only matches [`hashlittle`] if exactly `len` bytes are
+    /// fed before [`finalize`](Self::finalize).
+    pub fn with_len(init: u32, len: usize) -> Self {
+        let a = 0xdead_beef_u32
+            .wrapping_add(u32::try_from(len).unwrap_or(u32::MAX))
+            .wrapping_add(init);
+        Self {
+            state: HasherState::Streaming {
+                a,
+                b: a,
+                c: a,
+                block: [0; 12],
+                filled: 0,
+            },
+        }
+    }
+
+    /// Feed the next piece of input
+    pub fn update(&mut self, mut data: &[u8]) {
+        match &mut self.state {
+            HasherState::Buffered { data: buffer, .. } => buffer.extend_from_slice(data),
+            HasherState::Streaming {
+                a,
+                b,
+                c,
+                block,
+                filled,
+            } => {
+                while !data.is_empty() {
+                    // A full block is only mixed once input follows it, as
+                    // the final block is handled differently
+                    if *filled == 12 {
+                        *a = a.wrapping_add(u32::from_le_bytes([
+                            block[0], block[1], block[2], block[3],
+                        ]));
+                        *b = b.wrapping_add(u32::from_le_bytes([
+                            block[4], block[5], block[6], block[7],
+                        ]));
+                        *c = c.wrapping_add(u32::from_le_bytes([
+                            block[8], block[9], block[10], block[11],
+                        ]));
+                        mix(a, b, c);
+                        *filled = 0;
+                    }
+
+                    let take = (12 - *filled).min(data.len());
+                    block[*filled..*filled + take].copy_from_slice(&data[..take]);
+                    *filled += take;
+                    data = &data[take..];
+                }
+            }
+        }
+    }
+
+    /// Compute the hash of all input fed so far
+    pub fn finalize(self) -> u32 {
+        match self.state {
+            HasherState::Buffered { initval, data } => hashlittle(&data, initval),
+            HasherState::Streaming {
+                mut a,
+                mut b,
+                mut c,
+                mut block,
+                filled,
+            } => {
+                if filled == 0 {
+                    return c;
+                }
+
+                // The final block is zero-padded, as in `hashlittle`
+                block[filled..].fill(0);
+                a = a.wrapping_add(u32::from_le_bytes([block[0], block[1], block[2], block[3]]));
+                b = b.wrapping_add(u32::from_le_bytes([block[4], block[5], block[6], block[7]]));
+                c = c.wrapping_add(u32::from_le_bytes([
+                    block[8], block[9], block[10], block[11],
+                ]));
+                final_mix(&mut a, &mut b, &mut c);
+                c
+            }
+        }
+    }
+}
+
+impl std::io::Write for Jenkins96Hasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::expect_used, clippy::unwrap_used)]
 mod tests {
@@ -585,4 +736,111 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_hasher_block_boundaries() {
+        let data: Vec<u8> = (0..=48u8).collect();
+        for len in [0, 1, 11, 12, 13, 24, 25, 48] {
+            let data = &data[..len];
+            let expected = hashlittle(data, 0x1234_5678);
+
+            let mut buffered = Jenkins96Hasher::new(0x1234_5678);
+            buffered.update(data);
+            assert_eq!(buffered.finalize(), expected, "buffered, len {len}");
+
+            let mut streaming = Jenkins96Hasher::with_len(0x1234_5678, len);
+            for chunk in data.chunks(12) {
+                streaming.update(chunk);
+            }
+            assert_eq!(streaming.finalize(), expected, "streaming, len {len}");
+        }
+    }
+
+    #[test]
+    fn test_hasher_empty_updates() {
+        let mut hasher = Jenkins96Hasher::with_len(0, 4);
+        hasher.update(b"");
+        hasher.update(b"te");
+        hasher.update(b"");
+        hasher.update(b"st");
+        assert_eq!(hasher.finalize(), hashlittle(b"test", 0));
+    }
+
+    mod proptest_tests {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Feed `data` to `hasher` split at the (sorted, clamped) `cuts`
+        fn feed_split(mut hasher: Jenkins96Hasher, data: &[u8], cuts: &[usize]) -> u32 {
+            let mut cuts: Vec<usize> = cuts.iter().map(|cut| cut % (data.len() + 1)).collect();
+            cuts.sort_unstable();
+            let mut start = 0;
+            for cut in cuts {
+                hasher.update(&data[start..cut]);
+                start = cut;
+            }
+            hasher.update(&data[start..]);
+            hasher.finalize()
+        }
+
+        proptest! {
+            #[test]
+            fn split_input_matches_hashlittle(
+                data in prop::collection::vec(any::<u8>(), 0..256),
+                cuts in prop::collection::vec(any::<usize>(), 0..8),
+                init in any::<u32>(),
+            ) {
+                let expected = hashlittle(&data, init);
+                prop_assert_eq!(feed_split(Jenkins96Hasher::new(init), &data, &cuts), expected);
+                prop_assert_eq!(
+                    feed_split(Jenkins96Hasher::with_len(init, data.len()), &data, &cuts),
+                    expected
+                );
+            }
+
+            #[test]
+            fn byte_by_byte_matches_hashlittle(
+                data in prop::collection::vec(any::<u8>(), 0..64),
+                init in any::<u32>(),
+            ) {
+                let mut buffered = Jenkins96Hasher::new(init);
+                let mut streaming = Jenkins96Hasher::with_len(init, data.len());
+                for byte in &data {
+                    buffered.update(std::slice::from_ref(byte));
+                    streaming.update(std::slice::from_ref(byte));
+                }
+                let expected = hashlittle(&data, init);
+                prop_assert_eq!(buffered.finalize(), expected);
+                prop_assert_eq!(streaming.finalize(), expected);
+            }
+
+            #[test]
+            fn fixed_chunks_match_hashlittle(
+                data in prop::collection::vec(any::<u8>(), 0..256),
+                chunk_size in 1usize..40,
+                init in any::<u32>(),
+            ) {
+                let mut hasher = Jenkins96Hasher::with_len(init, data.len());
+                for chunk in data.chunks(chunk_size) {
+                    hasher.update(chunk);
+                }
+                prop_assert_eq!(hasher.finalize(), hashlittle(&data, init));
+            }
+
+            #[test]
+            fn io_write_matches_hashlittle(
+                data in prop::collection::vec(any::<u8>(), 0..256),
+                init in any::<u32>(),
+            ) {
+                let mut buffered = Jenkins96Hasher::new(init);
+                std::io::copy(&mut data.as_slice(), &mut buffered).expect("Write should succeed");
+                let mut streaming = Jenkins96Hasher::with_len(init, data.len());
+                std::io::copy(&mut data.as_slice(), &mut streaming).expect("Write should succeed");
+
+                let expected = hashlittle(&data, init);
+                prop_assert_eq!(buffered.finalize(), expected);
+                prop_assert_eq!(streaming.finalize(), expected);
+            }
+        }
+    }
 }
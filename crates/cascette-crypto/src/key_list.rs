@@ -0,0 +1,444 @@
+//! Published TACT key lists
+//!
+//! New encryption keys reach users in two ways: community key lists and
+//! hotfixes pushed to the client. [`KeyList::parse`] reads both:
+//!
+//! - [`KeyListFormat::Csv`]: `keyname;keyhex` per line
+//! - [`KeyListFormat::WowTools`]: `keyname keyhex [description]` per line, as
+//!   published by wow.tools and the `TACTKeys` repository
+//! - [`KeyListFormat::DbCache`]: the client's `DBCache.bin` hotfix cache,
+//!   joining `TactKey` rows (the key) with `TactKeyLookup` rows (the key name)
+//!
+//! Key names in text lists are 16 hex characters read as a big-endian `u64`,
+//! matching the TOML keyring. A key name listed twice with different values
+//! is a [`KeyConflict`]: the first value is kept and a warning is logged.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+use tracing::warn;
+
+use crate::error::CryptoError;
+use crate::keys::TactKey;
+use crate::store_trait::TactKeyProvider;
+
+/// `XFTH` magic of the `DBCache.bin` header and of each entry
+const DBCACHE_MAGIC: [u8; 4] = *b"XFTH";
+
+/// Size of the `DBCache.bin` header: magic, version, build and a 32-byte hash
+const DBCACHE_HEADER_SIZE: usize = 44;
+
+/// Size of a `DBCache.bin` entry header
+const DBCACHE_ENTRY_HEADER_SIZE: usize = 32;
+
+/// Entry status of a valid hotfix row
+const DBCACHE_STATUS_VALID: u8 = 1;
+
+/// Format of a key list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyListFormat {
+    /// `keyname;keyhex` per line
+    Csv,
+    /// `keyname keyhex [description]` per line
+    WowTools,
+    /// Binary `DBCache.bin` hotfix cache
+    DbCache(DbCacheTables),
+}
+
+impl KeyListFormat {
+    /// Detect the format of a text key list
+    ///
+    /// Returns `None` for binary data: a `DBCache.bin` cannot be read without
+    /// its [`DbCacheTables`].
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&DBCACHE_MAGIC) {
+            return None;
+        }
+        let content = std::str::from_utf8(data).ok()?;
+        let first = content
+            .lines()
+            .map(str::trim)
+            .find(|line| !is_comment(line))
+            .unwrap_or_default();
+        Some(if first.contains(';') {
+            Self::Csv
+        } else {
+            Self::WowTools
+        })
+    }
+}
+
+/// Table hashes identifying key rows in `DBCache.bin`
+///
+/// The hashes come from the client's DB2 definitions for the `TactKey` and
+/// `TactKeyLookup` tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DbCacheTables {
+    /// Table hash of `TactKey` (16-byte key per row)
+    pub tact_key: u32,
+    /// Table hash of `TactKeyLookup` (8-byte key name per row)
+    pub tact_key_lookup: u32,
+}
+
+/// A key name seen with two different values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyConflict {
+    /// Key name
+    pub id: u64,
+    /// Value that was kept
+    pub kept: [u8; 16],
+    /// Value that was discarded
+    pub rejected: [u8; 16],
+}
+
+/// Result of merging a key list into a key store
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyMerge {
+    /// Keys that were not in the store before
+    pub added: Vec<TactKey>,
+    /// Keys already in the store with the same value
+    pub unchanged: usize,
+    /// Keys already in the store with a different value, which was kept
+    pub conflicts: Vec<KeyConflict>,
+}
+
+/// Deduplicated keys read from a key list
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyList {
+    keys: Vec<TactKey>,
+    conflicts: Vec<KeyConflict>,
+}
+
+impl KeyList {
+    /// Parse a key list
+    ///
+    /// Malformed lines of text lists are logged and skipped, as are
+    /// `TactKey` rows without a matching `TactKeyLookup` row.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CryptoError::InvalidKeyFormat` if a text list is not valid
+    /// UTF-8, or a `DBCache.bin` has a bad magic or is truncated.
+    pub fn parse(data: &[u8], format: KeyListFormat) -> Result<Self, CryptoError> {
+        match format {
+            KeyListFormat::Csv => Self::parse_text(data, |line| line.split_once(';')),
+            KeyListFormat::WowTools => Self::parse_text(data, |line| {
+                let mut fields = line.split_whitespace();
+                Some((fields.next()?, fields.next()?))
+            }),
+            KeyListFormat::DbCache(tables) => Self::parse_dbcache(data, tables),
+        }
+    }
+
+    /// Keys in the order they were first listed
+    pub fn keys(&self) -> &[TactKey] {
+        &self.keys
+    }
+
+    /// Key names listed more than once with different values
+    pub fn conflicts(&self) -> &[KeyConflict] {
+        &self.conflicts
+    }
+
+    /// Number of distinct keys
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether the list holds no keys
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Add the keys missing from `store`
+    ///
+    /// Keys the store already holds with a different value are left alone
+    /// and reported as conflicts.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error of the store backend.
+    pub fn merge_into<P: TactKeyProvider + ?Sized>(
+        &self,
+        store: &mut P,
+    ) -> Result<KeyMerge, CryptoError> {
+        let mut merge = KeyMerge::default();
+        for key in &self.keys {
+            match store.get_key(key.id)? {
+                None => {
+                    store.add_key(*key)?;
+                    merge.added.push(*key);
+                }
+                Some(existing) if existing == key.key => merge.unchanged += 1,
+                Some(existing) => {
+                    warn!(
+                        "key {:016X} differs from the stored value, keeping the stored key",
+                        key.id
+                    );
+                    merge.conflicts.push(KeyConflict {
+                        id: key.id,
+                        kept: existing,
+                        rejected: key.key,
+                    });
+                }
+            }
+        }
+        Ok(merge)
+    }
+
+    fn parse_text(
+        data: &[u8],
+        split: impl Fn(&str) -> Option<(&str, &str)>,
+    ) -> Result<Self, CryptoError> {
+        let content = std::str::from_utf8(data)
+            .map_err(|e| CryptoError::InvalidKeyFormat(format!("key list is not UTF-8: {e}")))?;
+
+        let mut builder = Builder::default();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if is_comment(line) {
+                continue;
+            }
+
+            let Some((name, key)) = split(line) else {
+                warn!("key list line {}: expected a key name and key", index + 1);
+                continue;
+            };
+            match parse_text_key(name.trim(), key.trim()) {
+                Ok(key) => builder.push(key),
+                // Header rows such as `keyname;keyhex`
+                Err(_) if index == 0 => {}
+                Err(e) => warn!("key list line {}: skipping key: {}", index + 1, e),
+            }
+        }
+        Ok(builder.finish())
+    }
+
+    fn parse_dbcache(data: &[u8], tables: DbCacheTables) -> Result<Self, CryptoError> {
+        let invalid = |reason: &str| CryptoError::InvalidKeyFormat(format!("DBCache: {reason}"));
+
+        if data.len() < DBCACHE_HEADER_SIZE || !data.starts_with(&DBCACHE_MAGIC) {
+            return Err(invalid("missing XFTH header"));
+        }
+
+        let mut keys = Vec::new();
+        let mut names = HashMap::new();
+        let mut rest = &data[DBCACHE_HEADER_SIZE..];
+        while !rest.is_empty() {
+            let (header, tail) = rest
+                .split_at_checked(DBCACHE_ENTRY_HEADER_SIZE)
+                .ok_or_else(|| invalid("truncated entry header"))?;
+            if !header.starts_with(&DBCACHE_MAGIC) {
+                return Err(invalid("bad entry magic"));
+            }
+
+            let field = |offset: usize| {
+                u32::from_le_bytes([
+                    header[offset],
+                    header[offset + 1],
+                    header[offset + 2],
+                    header[offset + 3],
+                ])
+            };
+            let table_hash = field(16);
+            let record_id = field(20);
+            let data_size = field(24) as usize;
+            let status = header[28];
+
+            let (row, tail) = tail
+                .split_at_checked(data_size)
+                .ok_or_else(|| invalid("truncated entry data"))?;
+            rest = tail;
+
+            if status != DBCACHE_STATUS_VALID {
+                continue;
+            }
+            if table_hash == tables.tact_key
+                && let Ok(key) = <[u8; 16]>::try_from(row)
+            {
+                keys.push((record_id, key));
+            } else if table_hash == tables.tact_key_lookup
+                && let Ok(name) = <[u8; 8]>::try_from(row)
+            {
+                names.insert(record_id, u64::from_le_bytes(name));
+            }
+        }
+
+        let mut builder = Builder::default();
+        for (record_id, key) in keys {
+            if let Some(&id) = names.get(&record_id) {
+                builder.push(TactKey::new(id, key));
+            } else {
+                warn!("DBCache: TactKey row {record_id} has no TactKeyLookup row");
+            }
+        }
+        Ok(builder.finish())
+    }
+}
+
+/// Collects keys, keeping the first value of each key name
+#[derive(Default)]
+struct Builder {
+    list: KeyList,
+    index: HashMap<u64, usize>,
+}
+
+impl Builder {
+    fn push(&mut self, key: TactKey) {
+        match self.index.entry(key.id) {
+            Entry::Vacant(entry) => {
+                entry.insert(self.list.keys.len());
+                self.list.keys.push(key);
+            }
+            Entry::Occupied(entry) => {
+                let kept = self.list.keys[*entry.get()].key;
+                if kept != key.key {
+                    warn!(
+                        "key {:016X} is listed with two different values, keeping the first",
+                        key.id
+                    );
+                    self.list.conflicts.push(KeyConflict {
+                        id: key.id,
+                        kept,
+                        rejected: key.key,
+                    });
+                }
+            }
+        }
+    }
+
+    fn finish(self) -> KeyList {
+        self.list
+    }
+}
+
+fn is_comment(line: &str) -> bool {
+    line.is_empty() || line.starts_with('#') || line.starts_with("//")
+}
+
+fn parse_text_key(name: &str, key: &str) -> Result<TactKey, CryptoError> {
+    if name.len() != 16 {
+        return Err(CryptoError::InvalidKeyFormat(format!(
+            "key name must be 16 hex characters, got {}",
+            name.len()
+        )));
+    }
+    let id = u64::from_str_radix(name, 16)
+        .map_err(|e| CryptoError::InvalidKeyFormat(format!("invalid hex key name: {e}")))?;
+    TactKey::from_hex(id, key)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::keys::TactKeyStore;
+
+    /// Table hashes used by the `DBCache.bin` fixture
+    const TABLES: DbCacheTables = DbCacheTables {
+        tact_key: 0xDF2F_53CF,
+        tact_key_lookup: 0x1111_2222,
+    };
+
+    const FA50: [u8; 16] = [
+        0xBD, 0xC5, 0x18, 0x62, 0xAB, 0xED, 0x79, 0xB2, 0xDE, 0x48, 0xC8, 0xE7, 0xE6, 0x6C, 0x62,
+        0x00,
+    ];
+
+    fn fixture(name: &str) -> Vec<u8> {
+        let path = format!("{}/test_fixtures/keys/{name}", env!("CARGO_MANIFEST_DIR"));
+        std::fs::read(&path).expect("Fixture should be readable")
+    }
+
+    fn ids(list: &KeyList) -> Vec<u64> {
+        list.keys().iter().map(|key| key.id).collect()
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let data = fixture("keys.csv");
+        assert_eq!(KeyListFormat::detect(&data), Some(KeyListFormat::Csv));
+
+        let list = KeyList::parse(&data, KeyListFormat::Csv).expect("CSV should parse");
+        assert_eq!(
+            ids(&list),
+            [
+                0xFA50_5078_126A_CB3E,
+                0xFF81_3F7D_062A_C0BC,
+                0x1122_3344_5566_7788
+            ]
+        );
+        assert_eq!(list.keys()[0].key, FA50);
+        assert!(list.conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_parse_wowtools() {
+        let data = fixture("wowtools.txt");
+        assert_eq!(KeyListFormat::detect(&data), Some(KeyListFormat::WowTools));
+
+        let list = KeyList::parse(&data, KeyListFormat::WowTools).expect("List should parse");
+        // Unknown keys (`????`) are skipped
+        assert_eq!(ids(&list), [0xFA50_5078_126A_CB3E, 0xD1E9_B5ED_F928_3668]);
+        assert!(list.conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_parse_dbcache() {
+        let data = fixture("DBCache.bin");
+        assert_eq!(KeyListFormat::detect(&data), None);
+
+        let list =
+            KeyList::parse(&data, KeyListFormat::DbCache(TABLES)).expect("DBCache should parse");
+        // Rows without a lookup row, of other tables or not valid are skipped
+        assert_eq!(ids(&list), [0xFA50_5078_126A_CB3E, 0x0EBE_36B5_010D_FD7F]);
+        assert_eq!(list.keys()[0].key, FA50);
+
+        assert!(KeyList::parse(&data[..60], KeyListFormat::DbCache(TABLES)).is_err());
+        assert!(KeyList::parse(b"XFTX", KeyListFormat::DbCache(TABLES)).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_keys_are_merged() {
+        let list = KeyList::parse(
+            b"FA505078126ACB3E;BDC51862ABED79B2DE48C8E7E66C6200\n\
+              FA505078126ACB3E;bdc51862abed79b2de48c8e7e66c6200\n\
+              FA505078126ACB3E;00000000000000000000000000000000\n",
+            KeyListFormat::Csv,
+        )
+        .expect("CSV should parse");
+
+        assert_eq!(list.len(), 1);
+        assert_eq!(
+            list.conflicts(),
+            [KeyConflict {
+                id: 0xFA50_5078_126A_CB3E,
+                kept: FA50,
+                rejected: [0; 16],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_merge_into_keeps_stored_keys() {
+        let list = KeyList::parse(
+            b"FA505078126ACB3E BDC51862ABED79B2DE48C8E7E66C6200\n\
+              FF813F7D062AC0BC 00000000000000000000000000000000\n\
+              1122334455667788 00112233445566778899AABBCCDDEEFF\n",
+            KeyListFormat::WowTools,
+        )
+        .expect("List should parse");
+
+        let mut store = TactKeyStore::new();
+        let merge = list.merge_into(&mut store).expect("Merge should succeed");
+
+        assert_eq!(merge.unchanged, 1);
+        assert_eq!(
+            merge.added.iter().map(|key| key.id).collect::<Vec<_>>(),
+            [0x1122_3344_5566_7788]
+        );
+        assert_eq!(merge.conflicts.len(), 1);
+        assert_eq!(merge.conflicts[0].id, 0xFF81_3F7D_062A_C0BC);
+        assert_ne!(store.get(0xFF81_3F7D_062A_C0BC), Some(&[0; 16]));
+    }
+}
@@ -5,8 +5,12 @@
 
 use std::collections::HashMap;
 use std::fmt;
+use std::path::Path;
+
+use tracing::warn;
 
 use crate::error::CryptoError;
+use crate::store_trait::TactKeyProvider;
 
 /// A TACT encryption key
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -218,6 +222,70 @@ impl TactKeyStore {
         count
     }
 
+    /// Load keys from a `.keys` file (format: `key_id<TAB>key_hex` per line)
+    ///
+    /// The key ID is 16 hex characters holding the 8-byte key name, read as a
+    /// little-endian `u64`. The key is 32 hex characters. Empty lines and
+    /// lines starting with `#` are skipped; malformed lines are logged and
+    /// skipped without failing the load.
+    /// Returns the number of keys successfully loaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CryptoError::Io` if the file cannot be read.
+    pub fn load_from_file(&mut self, path: &Path) -> Result<usize, CryptoError> {
+        let content = std::fs::read_to_string(path)?;
+        let mut count = 0;
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match parse_keys_line(line) {
+                Ok(key) => {
+                    self.add_key(key)?;
+                    count += 1;
+                }
+                Err(e) => {
+                    warn!("{}:{}: skipping key line: {}", path.display(), index + 1, e);
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Load keys from a TOML keyring file
+    ///
+    /// The file holds a `[keys]` table mapping the 8-byte key name (16 hex
+    /// characters, optionally `0x`-prefixed) to the 16-byte key (32 hex
+    /// characters):
+    ///
+    /// ```toml
+    /// [keys]
+    /// FA505078126ACB3E = "BDC51862ABED79B2DE48C8E7E66C6200"
+    /// ```
+    ///
+    /// Keys are merged over the existing ones, replacing keys with the same
+    /// name. Nothing is added if any entry is invalid.
+    /// Returns the number of keys loaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CryptoError::Io` if the file cannot be read,
+    /// `CryptoError::InvalidKeyFormat` if it is not valid TOML and
+    /// `CryptoError::InvalidKey` naming the first malformed entry.
+    pub fn load_from_toml(&mut self, path: &Path) -> Result<usize, CryptoError> {
+        let keys = parse_toml_keys(&std::fs::read_to_string(path)?)?;
+        let count = keys.len();
+        for key in keys {
+            self.add_key(key)?;
+        }
+        Ok(count)
+    }
+
     /// Iterate over all keys
     pub fn iter(&self) -> impl Iterator<Item = TactKey> + '_ {
         self.keys.iter().map(|(&id, &key)| TactKey::new(id, key))
@@ -247,12 +315,85 @@ fn parse_key_id(s: &str) -> Result<u64, CryptoError> {
     }
 }
 
+/// Parse one `key_id<TAB>key_hex` line of a `.keys` file
+fn parse_keys_line(line: &str) -> Result<TactKey, CryptoError> {
+    let mut fields = line.split('\t');
+    let (Some(id_hex), Some(key_hex), None) = (fields.next(), fields.next(), fields.next()) else {
+        return Err(CryptoError::InvalidKeyFormat(
+            "expected exactly two tab-separated fields".to_string(),
+        ));
+    };
+    let (id_hex, key_hex) = (id_hex.trim(), key_hex.trim());
+
+    if id_hex.len() != 16 {
+        return Err(CryptoError::InvalidKeyFormat(format!(
+            "key ID must be 16 hex characters, got {}",
+            id_hex.len()
+        )));
+    }
+    if key_hex.len() != 32 {
+        return Err(CryptoError::InvalidKeyFormat(format!(
+            "key must be 32 hex characters, got {}",
+            key_hex.len()
+        )));
+    }
+
+    let mut id_bytes = [0u8; 8];
+    hex::decode_to_slice(id_hex, &mut id_bytes)
+        .map_err(|e| CryptoError::InvalidKeyFormat(format!("invalid hex key ID: {e}")))?;
+
+    TactKey::from_hex(u64::from_le_bytes(id_bytes), key_hex)
+}
+
+/// Parse the `[keys]` table of a TOML keyring file
+pub(crate) fn parse_toml_keys(content: &str) -> Result<Vec<TactKey>, CryptoError> {
+    let document: toml::Table = content
+        .parse()
+        .map_err(|e| CryptoError::InvalidKeyFormat(format!("invalid TOML: {e}")))?;
+
+    let Some(table) = document.get("keys") else {
+        return Ok(Vec::new());
+    };
+    let table = table
+        .as_table()
+        .ok_or_else(|| CryptoError::InvalidKeyFormat("`keys` must be a table".to_string()))?;
+
+    table
+        .iter()
+        .map(|(name, value)| {
+            let invalid = |reason: String| CryptoError::InvalidKey {
+                name: name.clone(),
+                reason,
+            };
+
+            let id_hex = name
+                .strip_prefix("0x")
+                .or_else(|| name.strip_prefix("0X"))
+                .unwrap_or(name);
+            if id_hex.len() != 16 {
+                return Err(invalid(format!(
+                    "key name must be 16 hex characters, got {}",
+                    id_hex.len()
+                )));
+            }
+            let id = u64::from_str_radix(id_hex, 16)
+                .map_err(|e| invalid(format!("invalid hex key name: {e}")))?;
+
+            let key_hex = value
+                .as_str()
+                .ok_or_else(|| invalid("key must be a hex string".to_string()))?;
+            TactKey::from_hex(id, key_hex).map_err(|e| invalid(e.to_string()))
+        })
+        .collect()
+}
+
 // TactKeyProvider implementation is in store_trait.rs to avoid circular dependency
 
 #[cfg(test)]
 #[allow(clippy::expect_used, clippy::unwrap_used)]
 mod tests {
     use super::*;
+    use std::io::Write;
 
     #[test]
     fn test_tact_key_from_hex() {
@@ -314,4 +455,111 @@ mod tests {
             1000
         );
     }
+
+    #[test]
+    fn test_load_from_file() {
+        let mut file = tempfile::NamedTempFile::new().expect("Temp file should be created");
+        write!(
+            file,
+            "# community keys\n\
+             3ECB6A12785050FA\tBDC51862ABED79B2DE48C8E7E66C6200\n\
+             \n\
+             BCC02A067D3F81FF\taa0b5c77f088ccc2d39049bd267f066d\r\n"
+        )
+        .expect("Key file should be written");
+
+        let mut store = TactKeyStore::empty();
+        let count = store
+            .load_from_file(file.path())
+            .expect("Key file should load");
+
+        assert_eq!(count, 2);
+        assert_eq!(store.len(), 2);
+        // IDs are stored little-endian in the file
+        assert!(store.get(0xFA50_5078_126A_CB3E).is_some());
+        assert_eq!(
+            store.get(0xFF81_3F7D_062A_C0BC).map(hex::encode_upper),
+            Some("AA0B5C77F088CCC2D39049BD267F066D".to_string())
+        );
+    }
+
+    #[test]
+    fn test_load_from_file_skips_malformed_lines() {
+        let mut file = tempfile::NamedTempFile::new().expect("Temp file should be created");
+        write!(
+            file,
+            "3ECB6A12785050FA\tBDC51862ABED79B2DE48C8E7E66C6200\n\
+             3ECB6A12785050\tBDC51862ABED79B2DE48C8E7E66C6200\n\
+             BCC02A067D3F81FF\tAA0B5C77F088CCC2D39049BD267F06\n\
+             ZZC02A067D3F81FF\tAA0B5C77F088CCC2D39049BD267F066D\n\
+             BCC02A067D3F81FF\tAA0B5C77F088CCC2D39049BD267F066Z\n\
+             BCC02A067D3F81FF AA0B5C77F088CCC2D39049BD267F066D\n\
+             BCC02A067D3F81FF\tAA0B5C77F088CCC2D39049BD267F066D\textra\n\
+             683628F9EDB5E9D1\t8E4A2579894E38B4AB9058BA5C7328EE\n"
+        )
+        .expect("Key file should be written");
+
+        let mut store = TactKeyStore::empty();
+        let count = store
+            .load_from_file(file.path())
+            .expect("Malformed lines should not fail the load");
+
+        assert_eq!(count, 2);
+        assert!(store.get(0xFA50_5078_126A_CB3E).is_some());
+        assert!(store.get(0xD1E9_B5ED_F928_3668).is_some());
+        assert!(store.get(0xFF81_3F7D_062A_C0BC).is_none());
+    }
+
+    #[test]
+    fn test_load_from_toml() {
+        let mut file = tempfile::NamedTempFile::new().expect("Temp file should be created");
+        write!(
+            file,
+            "# local keys\n\
+             [keys]\n\
+             FA505078126ACB3E = \"00112233445566778899AABBCCDDEEFF\"\n\
+             0xFF813F7D062AC0BC = \"AA0B5C77F088CCC2D39049BD267F066D\"\n"
+        )
+        .expect("Keyring should be written");
+
+        let mut store = TactKeyStore::new();
+        let before = store.len();
+        let count = store
+            .load_from_toml(file.path())
+            .expect("Keyring should load");
+
+        assert_eq!(count, 2);
+        assert_eq!(store.len(), before);
+        assert_eq!(
+            store.get(0xFA50_5078_126A_CB3E).map(hex::encode_upper),
+            Some("00112233445566778899AABBCCDDEEFF".to_string())
+        );
+    }
+
+    #[test]
+    fn test_load_from_toml_rejects_whole_file() {
+        let mut file = tempfile::NamedTempFile::new().expect("Temp file should be created");
+        write!(
+            file,
+            "[keys]\n\
+             1122334455667788 = \"00112233445566778899AABBCCDDEEFF\"\n\
+             8877665544332211 = \"00112233445566778899AABBCCDDEE\"\n"
+        )
+        .expect("Keyring should be written");
+
+        let mut store = TactKeyStore::empty();
+        let result = store.load_from_toml(file.path());
+        assert!(
+            matches!(result, Err(CryptoError::InvalidKey { ref name, .. }) if name == "8877665544332211")
+        );
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_load_from_missing_file() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let mut store = TactKeyStore::empty();
+        let result = store.load_from_file(&dir.path().join("missing.keys"));
+        assert!(matches!(result, Err(CryptoError::Io(_))));
+    }
 }
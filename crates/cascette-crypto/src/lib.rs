@@ -14,8 +14,12 @@
 //! This crate provides an in-memory key store and a trait for custom backends:
 //!
 //! - [`TactKeyStore`] - In-memory storage with hardcoded WoW keys
+//! - [`TomlKeyStore`] - Hardcoded keys merged with a reloadable TOML keyring file
 //! - [`TactKeyProvider`] - Trait for implementing custom storage backends
 //!
+//! [`KeyList`] reads published key lists (CSV, wow.tools, `DBCache.bin`) that
+//! can be merged into any backend, or synced into a [`TomlKeyStore`] file.
+//!
 //! Applications can implement `TactKeyProvider` for persistent storage (keyring,
 //! database, encrypted files, etc.).
 //!
@@ -79,17 +83,21 @@
 pub mod arc4;
 pub mod error;
 pub mod jenkins;
+pub mod key_list;
 pub mod keys;
 pub mod md5;
 pub mod salsa20;
 pub mod store_trait;
+pub mod toml_store;
 
 pub use error::CryptoError;
 
 // Re-export commonly used types
 pub use arc4::Arc4Cipher;
-pub use jenkins::{Jenkins96, hashlittle, hashlittle2};
+pub use jenkins::{Jenkins96, Jenkins96Hasher, hashlittle, hashlittle2};
+pub use key_list::{DbCacheTables, KeyConflict, KeyList, KeyListFormat, KeyMerge};
 pub use keys::{TactKey, TactKeyStore};
 pub use md5::{ContentKey, EncodingKey, FileDataId};
 pub use salsa20::Salsa20Cipher;
 pub use store_trait::{TactKeyIterator, TactKeyProvider, TactKeyStoreConfig, UnifiedKeyStore};
+pub use toml_store::TomlKeyStore;
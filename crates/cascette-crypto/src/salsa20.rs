@@ -5,6 +5,8 @@
 //! - 4-byte IV extended to 8 bytes by zero-padding
 //! - Block index `XORed` with first 4 bytes of IV
 
+use std::io::{self, Read, Write};
+
 use crate::error::CryptoError;
 
 /// Salsa20 cipher for CASC encryption
@@ -149,6 +151,52 @@ impl Salsa20Cipher {
             self.keystream_pos += 1;
         }
     }
+
+    /// Decrypt (or encrypt) a stream incrementally
+    ///
+    /// Reads `reader` in blocks of up to `chunk_size` bytes, applies the
+    /// keystream and writes the result to `writer`. The keystream position
+    /// carries over between blocks, so the output is identical to a one-shot
+    /// [`apply_keystream`](Self::apply_keystream) over the whole input while
+    /// only `chunk_size` bytes are held in memory.
+    /// Returns the number of bytes processed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CryptoError::Io` if `chunk_size` is zero or reading or
+    /// writing fails.
+    pub fn streaming_decrypt<R: Read, W: Write>(
+        &mut self,
+        mut reader: R,
+        mut writer: W,
+        chunk_size: usize,
+    ) -> Result<u64, CryptoError> {
+        if chunk_size == 0 {
+            return Err(
+                io::Error::new(io::ErrorKind::InvalidInput, "chunk size must be non-zero").into(),
+            );
+        }
+
+        let mut buffer = vec![0u8; chunk_size];
+        let mut total = 0u64;
+
+        loop {
+            let read = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            let block = &mut buffer[..read];
+            self.apply_keystream(block);
+            writer.write_all(block)?;
+            total += read as u64;
+        }
+
+        writer.flush()?;
+        Ok(total)
+    }
 }
 
 /// Decrypt data using CASC Salsa20 variant
@@ -265,4 +313,78 @@ mod tests {
         // Different upper 4 bytes should produce different ciphertext
         assert_ne!(ct4, ct8_diff);
     }
+
+    /// Reader that returns at most `limit` bytes per call
+    struct ShortReader<'a> {
+        data: &'a [u8],
+        limit: usize,
+    }
+
+    impl Read for ShortReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.limit).min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let key = [0x42u8; 16];
+        let iv = [0x11, 0x22, 0x33, 0x44];
+        let plaintext: Vec<u8> = (0..10_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let ciphertext =
+            encrypt_salsa20(&plaintext, &key, &iv, 3).expect("Operation should succeed");
+
+        // Chunk sizes below, at and across the 64-byte keystream block
+        for chunk_size in [1, 7, 63, 64, 65, 1000, 20_000] {
+            let mut cipher = Salsa20Cipher::new(&key, &iv, 3).expect("Operation should succeed");
+            let mut output = Vec::new();
+            let processed = cipher
+                .streaming_decrypt(ciphertext.as_slice(), &mut output, chunk_size)
+                .expect("Streaming decryption should succeed");
+
+            assert_eq!(processed, plaintext.len() as u64);
+            assert_eq!(output, plaintext, "chunk size {chunk_size}");
+        }
+    }
+
+    #[test]
+    fn test_streaming_with_short_reads() {
+        let key = [0x01u8; 16];
+        let iv = [0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09];
+        let plaintext = vec![0xA5u8; 1_000];
+        let ciphertext =
+            encrypt_salsa20(&plaintext, &key, &iv, 0).expect("Operation should succeed");
+
+        let reader = ShortReader {
+            data: &ciphertext,
+            limit: 13,
+        };
+        let mut cipher = Salsa20Cipher::new(&key, &iv, 0).expect("Operation should succeed");
+        let mut output = Vec::new();
+        cipher
+            .streaming_decrypt(reader, &mut output, 256)
+            .expect("Streaming decryption should succeed");
+
+        assert_eq!(output, plaintext);
+    }
+
+    #[test]
+    fn test_streaming_empty_and_zero_chunk() {
+        let key = [0x01u8; 16];
+        let iv = [0x02, 0x03, 0x04, 0x05];
+        let mut cipher = Salsa20Cipher::new(&key, &iv, 0).expect("Operation should succeed");
+
+        let mut output = Vec::new();
+        let processed = cipher
+            .streaming_decrypt(&[][..], &mut output, 64)
+            .expect("Empty input should succeed");
+        assert_eq!(processed, 0);
+        assert!(output.is_empty());
+
+        let result = cipher.streaming_decrypt(&b"data"[..], &mut output, 0);
+        assert!(matches!(result, Err(CryptoError::Io(_))));
+    }
 }
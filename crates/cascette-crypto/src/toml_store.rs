@@ -0,0 +1,409 @@
+//! TACT key backend backed by a TOML keyring file
+//!
+//! [`TomlKeyStore`] combines the hardcoded keys of [`TactKeyStore`] with keys
+//! read from a TOML file (see [`TactKeyStore::load_from_toml`]). File keys
+//! take precedence. The file can be re-read with [`TomlKeyStore::reload`], so
+//! long-running processes pick up new keys without restarting.
+//!
+//! Published key lists can be merged in with [`TomlKeyStore::load_from_url`],
+//! or written to the file with [`TomlKeyStore::sync`]. Fetching is left to
+//! the caller, which passes its own HTTP client as a closure.
+
+use std::fmt::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::CryptoError;
+use crate::key_list::{KeyList, KeyListFormat, KeyMerge};
+use crate::keys::{TactKey, TactKeyStore, parse_toml_keys};
+use crate::store_trait::TactKeyProvider;
+
+/// Key store that merges a TOML keyring file over the hardcoded keys
+#[derive(Debug, Clone)]
+pub struct TomlKeyStore {
+    path: PathBuf,
+    store: TactKeyStore,
+}
+
+impl TomlKeyStore {
+    /// Open a keyring file and merge its keys over the hardcoded set
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or contains an invalid
+    /// entry, see [`TactKeyStore::load_from_toml`].
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, CryptoError> {
+        let path = path.into();
+        let (store, _) = Self::read(&path)?;
+        Ok(Self { path, store })
+    }
+
+    /// Path of the keyring file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The merged key store
+    pub fn store(&self) -> &TactKeyStore {
+        &self.store
+    }
+
+    /// Re-read the keyring file
+    ///
+    /// Keys added at runtime and keys removed from the file are dropped; the
+    /// store again holds the hardcoded keys with the file merged over them.
+    /// If the file cannot be loaded, the current keys are kept.
+    /// Returns the number of keys read from the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or contains an invalid
+    /// entry.
+    pub fn reload(&mut self) -> Result<usize, CryptoError> {
+        let (store, count) = Self::read(&self.path)?;
+        self.store = store;
+        Ok(count)
+    }
+
+    /// Fetch a key list and merge its keys into the store
+    ///
+    /// `fetch` downloads `url`. With `format` of `None` a text format is
+    /// detected. Keys the store already holds keep their value; differing
+    /// values are reported in [`KeyMerge::conflicts`]. Merged keys live in
+    /// memory only and are dropped by [`reload`](Self::reload), see
+    /// [`sync`](Self::sync) to keep them.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CryptoError::Fetch` if `fetch` fails and
+    /// `CryptoError::InvalidKeyFormat` if the list cannot be parsed.
+    pub fn load_from_url<F, E>(
+        &mut self,
+        url: &str,
+        format: Option<KeyListFormat>,
+        fetch: F,
+    ) -> Result<KeyMerge, CryptoError>
+    where
+        F: FnOnce(&str) -> Result<Vec<u8>, E>,
+        E: fmt::Display,
+    {
+        let list = fetch_key_list(url, format, fetch)?;
+        list.merge_into(&mut self.store)
+    }
+
+    /// Fetch a key list and add its new keys to the keyring file
+    ///
+    /// Works like [`load_from_url`](Self::load_from_url), then rewrites the
+    /// file with the added keys and a `[sync]` table recording `url` and the
+    /// sync time in Unix seconds. Comments in the file are not preserved.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`load_from_url`](Self::load_from_url), and
+    /// `CryptoError::Io` if the file cannot be written.
+    pub fn sync<F, E>(
+        &mut self,
+        url: &str,
+        format: Option<KeyListFormat>,
+        fetch: F,
+    ) -> Result<KeyMerge, CryptoError>
+    where
+        F: FnOnce(&str) -> Result<Vec<u8>, E>,
+        E: fmt::Display,
+    {
+        let merge = self.load_from_url(url, format, fetch)?;
+
+        let mut keys = match std::fs::read_to_string(&self.path) {
+            Ok(content) => parse_toml_keys(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        keys.extend(&merge.added);
+        keys.sort_unstable_by_key(|key| key.id);
+
+        let updated = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let tmp = self.path.with_extension("toml.tmp");
+        std::fs::write(&tmp, format_keyring(&keys, url, updated))?;
+        std::fs::rename(&tmp, &self.path)?;
+
+        Ok(merge)
+    }
+
+    fn read(path: &Path) -> Result<(TactKeyStore, usize), CryptoError> {
+        let mut store = TactKeyStore::new();
+        let count = store.load_from_toml(path)?;
+        Ok((store, count))
+    }
+}
+
+fn fetch_key_list<F, E>(
+    url: &str,
+    format: Option<KeyListFormat>,
+    fetch: F,
+) -> Result<KeyList, CryptoError>
+where
+    F: FnOnce(&str) -> Result<Vec<u8>, E>,
+    E: fmt::Display,
+{
+    let data = fetch(url).map_err(|e| CryptoError::Fetch {
+        url: url.to_string(),
+        reason: e.to_string(),
+    })?;
+    let format = format
+        .or_else(|| KeyListFormat::detect(&data))
+        .ok_or_else(|| {
+            CryptoError::InvalidKeyFormat(format!(
+                "cannot detect the key list format of {url}; DBCache.bin needs an explicit format"
+            ))
+        })?;
+    KeyList::parse(&data, format)
+}
+
+/// Render a keyring file with a `[sync]` table
+fn format_keyring(keys: &[TactKey], source: &str, updated: u64) -> String {
+    let source = source.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut out = format!("[sync]\nsource = \"{source}\"\nupdated = {updated}\n\n[keys]\n");
+    for key in keys {
+        let _ = writeln!(out, "{:016X} = \"{}\"", key.id, hex::encode_upper(key.key));
+    }
+    out
+}
+
+impl TactKeyProvider for TomlKeyStore {
+    fn get_key(&self, id: u64) -> Result<Option<[u8; 16]>, CryptoError> {
+        self.store.get_key(id)
+    }
+
+    fn add_key(&mut self, key: TactKey) -> Result<(), CryptoError> {
+        self.store.add_key(key)
+    }
+
+    fn remove_key(&mut self, id: u64) -> Result<Option<[u8; 16]>, CryptoError> {
+        self.store.remove_key(id)
+    }
+
+    fn key_count(&self) -> Result<usize, CryptoError> {
+        self.store.key_count()
+    }
+
+    fn list_key_ids(&self) -> Result<Vec<u64>, CryptoError> {
+        self.store.list_key_ids()
+    }
+
+    fn load_keys(&mut self) -> Result<usize, CryptoError> {
+        self.reload()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    const HARDCODED_ID: u64 = 0xFA50_5078_126A_CB3E;
+
+    fn write_keyring(dir: &tempfile::TempDir, content: &str) -> PathBuf {
+        let path = dir.path().join("keys.toml");
+        std::fs::write(&path, content).expect("Keyring should be written");
+        path
+    }
+
+    #[test]
+    fn test_open_merges_over_hardcoded_keys() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let path = write_keyring(
+            &dir,
+            "[keys]\n\
+             FA505078126ACB3E = \"00112233445566778899AABBCCDDEEFF\"\n\
+             0x1122334455667788 = \"ffeeddccbbaa99887766554433221100\"\n",
+        );
+
+        let store = TomlKeyStore::open(&path).expect("Keyring should open");
+        let hardcoded = TactKeyStore::new().len();
+
+        assert_eq!(
+            store.key_count().expect("Key count should succeed"),
+            hardcoded + 1
+        );
+        // File keys replace hardcoded ones
+        assert_eq!(
+            store.get_key(HARDCODED_ID).expect("Lookup should succeed"),
+            Some([
+                0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD,
+                0xEE, 0xFF
+            ])
+        );
+        assert!(
+            store
+                .contains_key(0x1122_3344_5566_7788)
+                .expect("Lookup should succeed")
+        );
+    }
+
+    #[test]
+    fn test_invalid_entry_names_the_key() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+
+        let path = write_keyring(&dir, "[keys]\n1122334455667788 = \"0011\"\n");
+        let result = TomlKeyStore::open(&path);
+        assert!(
+            matches!(result, Err(CryptoError::InvalidKey { ref name, .. }) if name == "1122334455667788")
+        );
+
+        let path = write_keyring(
+            &dir,
+            "[keys]\nWRONGNAME = \"00112233445566778899AABBCCDDEEFF\"\n",
+        );
+        let result = TomlKeyStore::open(&path);
+        assert!(
+            matches!(result, Err(CryptoError::InvalidKey { ref name, .. }) if name == "WRONGNAME")
+        );
+
+        let path = write_keyring(
+            &dir,
+            "[keys]\n1122334455667788 = \"ZZ112233445566778899AABBCCDDEEFF\"\n",
+        );
+        assert!(matches!(
+            TomlKeyStore::open(&path),
+            Err(CryptoError::InvalidKey { .. })
+        ));
+
+        let path = write_keyring(&dir, "[keys\n");
+        assert!(matches!(
+            TomlKeyStore::open(&path),
+            Err(CryptoError::InvalidKeyFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_reload_picks_up_changes() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let path = write_keyring(
+            &dir,
+            "[keys]\n1122334455667788 = \"00112233445566778899AABBCCDDEEFF\"\n",
+        );
+        let mut store = TomlKeyStore::open(&path).expect("Keyring should open");
+
+        write_keyring(
+            &dir,
+            "[keys]\n8877665544332211 = \"00112233445566778899AABBCCDDEEFF\"\n",
+        );
+        assert_eq!(store.reload().expect("Reload should succeed"), 1);
+        assert!(
+            !store
+                .contains_key(0x1122_3344_5566_7788)
+                .expect("Lookup should succeed")
+        );
+        assert!(
+            store
+                .contains_key(0x8877_6655_4433_2211)
+                .expect("Lookup should succeed")
+        );
+        assert!(
+            store
+                .contains_key(HARDCODED_ID)
+                .expect("Lookup should succeed")
+        );
+
+        // A broken file keeps the previous keys
+        write_keyring(&dir, "[keys]\n8877665544332211 = 42\n");
+        assert!(store.reload().is_err());
+        assert!(
+            store
+                .contains_key(0x8877_6655_4433_2211)
+                .expect("Lookup should succeed")
+        );
+    }
+
+    #[test]
+    fn test_load_from_url_merges_in_memory() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let path = write_keyring(&dir, "[keys]\n");
+        let mut store = TomlKeyStore::open(&path).expect("Keyring should open");
+
+        let mut requested = String::new();
+        let merge = store
+            .load_from_url("https://keys.example/keys.txt", None, |url| {
+                requested = url.to_string();
+                Ok::<_, CryptoError>(
+                    b"FA505078126ACB3E BDC51862ABED79B2DE48C8E7E66C6200\n\
+                      1122334455667788 00112233445566778899AABBCCDDEEFF\n"
+                        .to_vec(),
+                )
+            })
+            .expect("Key list should load");
+
+        assert_eq!(requested, "https://keys.example/keys.txt");
+        assert_eq!(merge.added.len(), 1);
+        assert_eq!(merge.unchanged, 1);
+        assert!(
+            store
+                .contains_key(0x1122_3344_5566_7788)
+                .expect("Lookup should succeed")
+        );
+
+        let result = store.load_from_url("https://keys.example/", None, |_| Err("offline"));
+        assert!(
+            matches!(result, Err(CryptoError::Fetch { ref reason, .. }) if reason == "offline")
+        );
+        let result = store.load_from_url("https://keys.example/", None, |_| {
+            Ok::<_, CryptoError>(b"XFTH".to_vec())
+        });
+        assert!(matches!(result, Err(CryptoError::InvalidKeyFormat(_))));
+    }
+
+    #[test]
+    fn test_sync_writes_new_keys() {
+        let dir = tempfile::tempdir().expect("Temp dir should be created");
+        let path = write_keyring(
+            &dir,
+            "[keys]\n1122334455667788 = \"00112233445566778899AABBCCDDEEFF\"\n",
+        );
+        let mut store = TomlKeyStore::open(&path).expect("Keyring should open");
+
+        let merge = store
+            .sync("https://keys.example/keys.csv", None, |_| {
+                Ok::<_, CryptoError>(
+                    b"keyname;keyhex\n\
+                      1122334455667788;FFEEDDCCBBAA99887766554433221100\n\
+                      8877665544332211;00112233445566778899AABBCCDDEEFF\n"
+                        .to_vec(),
+                )
+            })
+            .expect("Sync should succeed");
+
+        // The local key wins over the remote one
+        assert_eq!(merge.conflicts.len(), 1);
+        assert_eq!(merge.added.len(), 1);
+
+        let content = std::fs::read_to_string(&path).expect("Keyring should be readable");
+        let document: toml::Table = content.parse().expect("Keyring should be valid TOML");
+        assert_eq!(
+            document["sync"]["source"].as_str(),
+            Some("https://keys.example/keys.csv")
+        );
+        assert!(
+            document["sync"]["updated"]
+                .as_integer()
+                .is_some_and(|t| t > 0)
+        );
+
+        let reopened = TomlKeyStore::open(&path).expect("Synced keyring should open");
+        assert_eq!(
+            reopened
+                .get_key(0x1122_3344_5566_7788)
+                .expect("Lookup should succeed"),
+            Some([
+                0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD,
+                0xEE, 0xFF
+            ])
+        );
+        assert!(
+            reopened
+                .contains_key(0x8877_6655_4433_2211)
+                .expect("Lookup should succeed")
+        );
+    }
+}
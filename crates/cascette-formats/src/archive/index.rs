@@ -155,7 +155,21 @@ impl IndexEntry {
     }
 
     /// Write entry to bytes
+    ///
+    /// Returns an error if the offset does not fit in `offset_bytes`.
     pub fn to_bytes(&self, _size_bytes: u8, offset_bytes: u8) -> ArchiveResult<Vec<u8>> {
+        let offset_bits = match (offset_bytes, self.archive_index) {
+            (6, Some(_)) => 32,
+            (4..=6, _) => u32::from(offset_bytes) * 8,
+            _ => 64,
+        };
+        if offset_bits < 64 && self.offset >> offset_bits != 0 {
+            return Err(ArchiveError::InvalidFormat(format!(
+                "Offset {} does not fit in {offset_bytes} offset bytes",
+                self.offset
+            )));
+        }
+
         let mut data = Vec::new();
 
         // Write key
@@ -406,6 +420,92 @@ impl IndexFooter {
     pub fn is_archive_group(&self) -> bool {
         self.offset_bytes == 6
     }
+
+    /// MD5 of the written footer
+    ///
+    /// CDN archives and their `.index` files are named by this hash.
+    pub fn name_hash(&self) -> [u8; 16] {
+        let mut data = Vec::with_capacity(MIN_FOOTER_SIZE + self.footer_hash.len());
+        // Writing to a Vec cannot fail
+        let _ = self.write(&mut data);
+        *cascette_crypto::md5::ContentKey::from_data(&data).as_bytes()
+    }
+}
+
+/// Entry count and size distribution of one or more archive indices
+///
+/// Statistics of several indices are combined with [`IndexStats::merge`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexStats {
+    /// Number of entries
+    pub entry_count: u64,
+    /// Sum of all entry sizes in bytes
+    pub total_size: u64,
+    /// Smallest entry size, `None` without entries
+    pub min_size: Option<u32>,
+    /// Largest entry size, `None` without entries
+    pub max_size: Option<u32>,
+}
+
+impl IndexStats {
+    fn record(&mut self, size: u32) {
+        self.entry_count += 1;
+        self.total_size += u64::from(size);
+        self.min_size = Some(self.min_size.map_or(size, |min| min.min(size)));
+        self.max_size = Some(self.max_size.map_or(size, |max| max.max(size)));
+    }
+
+    /// Add the statistics of another index
+    pub fn merge(&mut self, other: &Self) {
+        self.entry_count += other.entry_count;
+        self.total_size += other.total_size;
+        self.min_size = match (self.min_size, other.min_size) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.max_size = match (self.max_size, other.max_size) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    /// Average entry size in bytes, `None` without entries
+    pub fn mean_size(&self) -> Option<u64> {
+        self.total_size.checked_div(self.entry_count)
+    }
+}
+
+/// Borrowed view of one entry of an [`ArchiveIndex`]
+///
+/// Yielded by [`ArchiveIndex::iter_entries`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveIndexEntry<'a> {
+    /// Encoding key bytes (length from footer.ekey_length)
+    pub encoding_key: &'a [u8],
+    /// Byte offset within the archive
+    pub offset: u64,
+    /// Compressed size in bytes
+    pub size: u32,
+    /// Archive holding the entry (only for archive-groups)
+    pub archive_index: Option<u16>,
+}
+
+impl ArchiveIndexEntry<'_> {
+    /// Byte range of the entry within its archive
+    pub fn range(&self) -> std::ops::Range<u64> {
+        self.offset..self.offset + u64::from(self.size)
+    }
+}
+
+impl<'a> From<&'a IndexEntry> for ArchiveIndexEntry<'a> {
+    fn from(entry: &'a IndexEntry) -> Self {
+        Self {
+            encoding_key: &entry.encoding_key,
+            offset: entry.offset,
+            size: entry.size,
+            archive_index: entry.archive_index,
+        }
+    }
 }
 
 /// Complete archive index structure
@@ -531,11 +631,8 @@ impl ArchiveIndex {
             toc.push(key);
         }
 
-        // Skip per-block hashes. The correct TOC hash algorithm is
-        // `toc_hash = MD5(toc_keys || block_hashes)[:hash_bytes]`
-        // (from Agent.exe BuildMergedIndex at 0x7118c7).
-        // Validation is not enforced on parse because Blizzard-generated
-        // files may use a different generation path.
+        // Skip per-block hashes. `validate` recomputes them from the parsed
+        // entries and checks the TOC hash over them.
         let hash_section_size = chunk_count * footer.footer_hash_bytes as usize;
         reader.seek(SeekFrom::Current(hash_section_size as i64))?;
 
@@ -602,54 +699,13 @@ impl ArchiveIndex {
     }
 
     /// Build archive index to writer
+    ///
+    /// Pages and TOC are laid out as the footer specifies and the footer is
+    /// written as stored.
     pub fn build<W: Write + Seek>(&self, mut writer: W) -> ArchiveResult<()> {
-        let block_size = (self.footer.page_size_kb as usize) * 1024;
-        let record_size = self.footer.ekey_length as usize
-            + self.footer.size_bytes as usize
-            + self.footer.offset_bytes as usize;
-        let records_per_block = block_size / record_size;
-        let chunk_count = self.entries.len().div_ceil(records_per_block);
-        let hash_bytes = self.footer.footer_hash_bytes;
-
-        // Write entry chunks and compute block hashes
-        let mut entry_idx = 0;
-        let mut block_hashes = Vec::with_capacity(chunk_count);
-        for chunk_idx in 0..chunk_count {
-            let mut chunk_data = vec![0u8; block_size];
-
-            let entries_in_chunk = if chunk_idx == chunk_count - 1 {
-                self.entries.len() - entry_idx
-            } else {
-                records_per_block.min(self.entries.len() - entry_idx)
-            };
-
-            let mut pos = 0;
-            for _ in 0..entries_in_chunk {
-                if entry_idx < self.entries.len() {
-                    let entry_bytes = self.entries[entry_idx]
-                        .to_bytes(self.footer.size_bytes, self.footer.offset_bytes)?;
-                    chunk_data[pos..pos + entry_bytes.len()].copy_from_slice(&entry_bytes);
-                    pos += record_size;
-                    entry_idx += 1;
-                }
-            }
-
-            block_hashes.push(calculate_block_hash(&chunk_data, hash_bytes));
-            writer.write_all(&chunk_data)?;
-        }
-
-        // Write table of contents: keys then block hashes
-        for key in &self.toc {
-            writer.write_all(key)?;
-        }
-
-        for block_hash in &block_hashes {
-            writer.write_all(block_hash)?;
-        }
-
-        // Write footer
+        let pages = IndexPages::build(&self.entries, &self.footer)?;
+        pages.write(&mut writer)?;
         self.footer.write(&mut writer)?;
-
         Ok(())
     }
 
@@ -746,13 +802,6 @@ impl ArchiveIndex {
             return Err(ArchiveError::FooterChecksum);
         }
 
-        // TOC hash validation is intentionally disabled.
-        //
-        // Research shows no reference implementation (CascLib, TACT.Net, rustycasc) validates
-        // this field. Multiple algorithms were tested against WoW Classic 1.15.2.55140 files,
-        // none matched Blizzard's stored values. The field appears to be metadata only, not
-        // used for integrity verification.
-
         // Validate format
         self.footer.validate_format()?;
 
@@ -764,6 +813,13 @@ impl ArchiveIndex {
         // Validate TOC consistency
         self.validate_toc_consistency()?;
 
+        // Validate the TOC hash over the pages the entries occupy
+        let pages = IndexPages::build(&self.entries, &self.footer)?;
+        let hash_bytes = usize::from(self.footer.footer_hash_bytes);
+        if pages.toc_hash(self.footer.footer_hash_bytes) != self.footer.toc_hash[..hash_bytes] {
+            return Err(ArchiveError::TocChecksum);
+        }
+
         Ok(())
     }
 
@@ -805,56 +861,40 @@ impl ArchiveIndex {
         self.footer.is_archive_group()
     }
 
-    /// Write archive index to writer
-    pub fn write_to<W: Write + Seek>(&self, mut writer: W) -> ArchiveResult<()> {
-        let chunk_count = calculate_chunks(self.entries.len());
-        let block_size = CHUNK_SIZE;
-        let hash_bytes = self.footer.footer_hash_bytes;
-
-        // Write entry chunks and compute block hashes
-        let mut entry_idx = 0;
-        let mut block_hashes = Vec::with_capacity(chunk_count);
-        for chunk_idx in 0..chunk_count {
-            let mut chunk_data = vec![0u8; block_size];
-            let mut cursor = Cursor::new(&mut chunk_data);
-
-            let entries_in_chunk = if chunk_idx == chunk_count - 1 {
-                self.entries.len() - entry_idx
-            } else {
-                MAX_ENTRIES_PER_CHUNK.min(self.entries.len() - entry_idx)
-            };
-
-            for _ in 0..entries_in_chunk {
-                if entry_idx < self.entries.len() {
-                    let entry_bytes = self.entries[entry_idx].to_bytes(4, 4)?;
-                    cursor.write_all(&entry_bytes)?;
-                    entry_idx += 1;
-                }
-            }
-
-            block_hashes.push(calculate_block_hash(&chunk_data, hash_bytes));
-            writer.write_all(&chunk_data)?;
-        }
-
-        // Write table of contents: keys then block hashes
-        for key in &self.toc {
-            writer.write_all(key)?;
-        }
+    /// Iterate over all entries in on-disk order (sorted by encoding key)
+    pub fn iter_entries(&self) -> impl ExactSizeIterator<Item = ArchiveIndexEntry<'_>> {
+        self.entries.iter().map(ArchiveIndexEntry::from)
+    }
 
-        for block_hash in &block_hashes {
-            writer.write_all(block_hash)?;
+    /// Entry count and size distribution of this index
+    pub fn stats(&self) -> IndexStats {
+        let mut stats = IndexStats::default();
+        for entry in &self.entries {
+            stats.record(entry.size);
         }
+        stats
+    }
 
-        // Write footer
-        self.footer.write(&mut writer)?;
-
-        Ok(())
+    /// Write archive index to writer
+    ///
+    /// Same as [`ArchiveIndex::build`].
+    pub fn write_to<W: Write + Seek>(&self, writer: W) -> ArchiveResult<()> {
+        self.build(writer)
     }
 }
 
 /// Archive index builder
+///
+/// Produces the same bytes as Blizzard's CDN: entries sorted by key in
+/// zero-padded pages, a TOC of each page's last key followed by the
+/// truncated MD5 of each page, and a footer holding the truncated MD5 of
+/// the TOC and of the footer fields.
 pub struct ArchiveIndexBuilder {
     entries: Vec<IndexEntry>,
+    /// Footer version (default 1)
+    version: u8,
+    /// Page size in kilobytes (default 4)
+    page_size_kb: u8,
     /// Key size in bytes (default 16)
     key_size: u8,
     /// Offset field size in bytes (default 4)
@@ -868,19 +908,15 @@ pub struct ArchiveIndexBuilder {
 impl ArchiveIndexBuilder {
     /// Create new builder with default config (16-byte keys, 4-byte offset/size)
     pub fn new() -> Self {
-        Self {
-            entries: Vec::new(),
-            key_size: 16,
-            offset_bytes: 4,
-            size_bytes: 4,
-            hash_bytes: 8,
-        }
+        Self::with_config(16, 4, 4)
     }
 
     /// Create new builder with custom field sizes
     pub fn with_config(key_size: u8, offset_bytes: u8, size_bytes: u8) -> Self {
         Self {
             entries: Vec::new(),
+            version: 1,
+            page_size_kb: 4,
             key_size,
             offset_bytes,
             size_bytes,
@@ -888,6 +924,22 @@ impl ArchiveIndexBuilder {
         }
     }
 
+    /// Create new builder with the layout of an existing footer
+    ///
+    /// Takes the version, page size, field sizes and hash size from
+    /// `footer`; its hashes and element count are ignored.
+    pub fn with_footer_spec(footer: &IndexFooter) -> Self {
+        Self {
+            entries: Vec::new(),
+            version: footer.version,
+            page_size_kb: footer.page_size_kb,
+            key_size: footer.ekey_length,
+            offset_bytes: footer.offset_bytes,
+            size_bytes: footer.size_bytes,
+            hash_bytes: footer.footer_hash_bytes,
+        }
+    }
+
     /// Add entry to index with variable-length key
     pub fn add_entry(&mut self, encoding_key: Vec<u8>, size: u32, offset: u64) -> &mut Self {
         let entry = IndexEntry::new(encoding_key, size, offset);
@@ -910,75 +962,39 @@ impl ArchiveIndexBuilder {
     }
 
     /// Build index and write to writer
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the footer spec is not one the client accepts,
+    /// an entry key does not have the configured length, or an offset
+    /// does not fit the offset field.
     pub fn build<W: Write + Seek>(mut self, mut writer: W) -> ArchiveResult<ArchiveIndex> {
         // Sort entries by encoding key
         self.entries.sort();
 
-        let key_size = self.key_size as usize;
-        let entry_size = key_size + self.size_bytes as usize + self.offset_bytes as usize;
-        let max_entries_per_chunk = CHUNK_SIZE / entry_size;
-        let chunk_count = self.entries.len().div_ceil(max_entries_per_chunk);
-        let mut toc = Vec::with_capacity(chunk_count);
-
-        // Write entry chunks, build TOC, and compute per-block hashes
-        let hash_bytes = self.hash_bytes;
-        let mut entry_idx = 0;
-        let mut block_hashes = Vec::with_capacity(chunk_count);
-        for chunk_idx in 0..chunk_count {
-            let mut chunk_data = vec![0u8; CHUNK_SIZE];
-            let mut cursor = Cursor::new(&mut chunk_data);
-
-            let entries_in_chunk = if chunk_idx == chunk_count - 1 {
-                self.entries.len() - entry_idx
-            } else {
-                max_entries_per_chunk.min(self.entries.len() - entry_idx)
-            };
-
-            for _ in 0..entries_in_chunk {
-                if entry_idx < self.entries.len() {
-                    let entry_bytes =
-                        self.entries[entry_idx].to_bytes(self.size_bytes, self.offset_bytes)?;
-                    cursor.write_all(&entry_bytes)?;
-                    entry_idx += 1;
-                }
-            }
-
-            // Record last key for TOC
-            if entries_in_chunk > 0 {
-                let last_entry = &self.entries[entry_idx - 1];
-                // Pad or truncate key to key_size for TOC
-                let mut toc_key = vec![0u8; key_size];
-                let copy_len = last_entry.encoding_key.len().min(key_size);
-                toc_key[..copy_len].copy_from_slice(&last_entry.encoding_key[..copy_len]);
-                toc.push(toc_key);
-            }
-
-            block_hashes.push(calculate_block_hash(&chunk_data, hash_bytes));
-            writer.write_all(&chunk_data)?;
-        }
-
-        // Write table of contents: keys then block hashes
-        for key in &toc {
-            writer.write_all(key)?;
-        }
-
-        for block_hash in &block_hashes {
-            writer.write_all(block_hash)?;
-        }
-
-        // Compute TOC hash and create footer
-        let toc_hash = calculate_toc_hash(&toc, &block_hashes, hash_bytes);
-        let mut footer = IndexFooter::new(toc_hash, self.entries.len() as u32);
+        let element_count = u32::try_from(self.entries.len()).map_err(|_| {
+            ArchiveError::InvalidFormat(format!("Too many entries: {}", self.entries.len()))
+        })?;
+        let mut footer = IndexFooter::new(Vec::new(), element_count);
+        footer.version = self.version;
+        footer.page_size_kb = self.page_size_kb;
         footer.ekey_length = self.key_size;
         footer.offset_bytes = self.offset_bytes;
         footer.size_bytes = self.size_bytes;
-        footer.footer_hash_bytes = hash_bytes;
+        footer.footer_hash_bytes = self.hash_bytes;
+        footer.validate_format()?;
+
+        let pages = IndexPages::build(&self.entries, &footer)?;
+        let toc_hash = pages.toc_hash(self.hash_bytes);
+        footer.toc_hash[..toc_hash.len()].copy_from_slice(&toc_hash);
         footer.footer_hash = footer.calculate_footer_hash();
+
+        pages.write(&mut writer)?;
         footer.write(&mut writer)?;
 
         Ok(ArchiveIndex {
             entries: self.entries,
-            toc,
+            toc: pages.toc_keys,
             footer,
         })
     }
@@ -1030,11 +1046,7 @@ impl ArchiveIndexBuilder {
     /// # }
     /// ```
     pub fn from_archive_index(index: &ArchiveIndex) -> Self {
-        let mut builder = Self::with_config(
-            index.footer.ekey_length,
-            index.footer.offset_bytes,
-            index.footer.size_bytes,
-        );
+        let mut builder = Self::with_footer_spec(&index.footer);
 
         for entry in &index.entries {
             builder.entries.push(entry.clone());
@@ -1186,11 +1198,8 @@ impl ChunkedArchiveIndex {
             toc.push(key);
         }
 
-        // Skip per-block hashes. The correct TOC hash algorithm is
-        // `toc_hash = MD5(toc_keys || block_hashes)[:hash_bytes]`
-        // (from Agent.exe BuildMergedIndex at 0x7118c7).
-        // Validation is not enforced on parse because Blizzard-generated
-        // files may use a different generation path.
+        // Skip per-block hashes. The TOC hash covers the pages, which are
+        // loaded on demand, so it is not checked here.
         let hash_section_size = chunk_count * footer.footer_hash_bytes as usize;
         file.seek(SeekFrom::Current(hash_section_size as i64))?;
 
@@ -1270,6 +1279,77 @@ impl ChunkedArchiveIndex {
     }
 }
 
+/// Data pages and TOC of an archive index
+struct IndexPages {
+    /// Zero-padded pages holding the entries
+    data: Vec<u8>,
+    /// Last key of each page
+    toc_keys: Vec<Vec<u8>>,
+    /// Truncated MD5 of each page
+    block_hashes: Vec<Vec<u8>>,
+}
+
+impl IndexPages {
+    /// Lay out sorted `entries` in pages as `footer` specifies
+    fn build(entries: &[IndexEntry], footer: &IndexFooter) -> ArchiveResult<Self> {
+        let page_size = usize::from(footer.page_size_kb) * 1024;
+        let key_size = usize::from(footer.ekey_length);
+        let record_size =
+            key_size + usize::from(footer.size_bytes) + usize::from(footer.offset_bytes);
+        let records_per_page = page_size / record_size;
+        if records_per_page == 0 {
+            return Err(ArchiveError::InvalidFormat("Records per page is 0".into()));
+        }
+
+        let page_count = entries.len().div_ceil(records_per_page);
+        let mut data = vec![0u8; page_count * page_size];
+        let mut toc_keys = Vec::with_capacity(page_count);
+        let mut block_hashes = Vec::with_capacity(page_count);
+
+        for (page, chunk) in data
+            .chunks_exact_mut(page_size)
+            .zip(entries.chunks(records_per_page))
+        {
+            for (record, entry) in page.chunks_exact_mut(record_size).zip(chunk) {
+                if entry.encoding_key.len() != key_size {
+                    return Err(ArchiveError::InvalidFormat(format!(
+                        "Entry key is {} bytes, footer specifies {key_size}",
+                        entry.encoding_key.len()
+                    )));
+                }
+                record.copy_from_slice(&entry.to_bytes(footer.size_bytes, footer.offset_bytes)?);
+            }
+            if let Some(last) = chunk.last() {
+                toc_keys.push(last.encoding_key.clone());
+            }
+            block_hashes.push(calculate_block_hash(page, footer.footer_hash_bytes));
+        }
+
+        Ok(Self {
+            data,
+            toc_keys,
+            block_hashes,
+        })
+    }
+
+    /// TOC hash for the footer
+    fn toc_hash(&self, hash_bytes: u8) -> Vec<u8> {
+        calculate_toc_hash(&self.toc_keys, &self.block_hashes, hash_bytes)
+    }
+
+    /// Write the pages followed by the TOC keys and block hashes
+    fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.data)?;
+        for key in &self.toc_keys {
+            writer.write_all(key)?;
+        }
+        for block_hash in &self.block_hashes {
+            writer.write_all(block_hash)?;
+        }
+        Ok(())
+    }
+}
+
 /// Helper functions
 ///
 /// Calculate TOC hash from TOC keys and per-block hashes.
@@ -1489,6 +1569,70 @@ mod tests {
         assert!(index.entries[0].encoding_key <= index.entries[1].encoding_key);
     }
 
+    #[test]
+    fn test_index_stats() {
+        let mut builder = ArchiveIndexBuilder::new();
+        builder.add_entry_old([1u8; 16], 100, 0);
+        builder.add_entry_old([2u8; 16], 300, 100);
+        let index = builder
+            .build(&mut Cursor::new(Vec::new()))
+            .expect("Operation should succeed");
+
+        let mut stats = index.stats();
+        assert_eq!(stats.entry_count, 2);
+        assert_eq!(stats.total_size, 400);
+        assert_eq!(stats.min_size, Some(100));
+        assert_eq!(stats.max_size, Some(300));
+        assert_eq!(stats.mean_size(), Some(200));
+
+        stats.merge(&IndexStats::default());
+        assert_eq!(stats.entry_count, 2);
+        assert_eq!(IndexStats::default().mean_size(), None);
+
+        let mut builder = ArchiveIndexBuilder::new();
+        builder.add_entry_old([3u8; 16], 50, 0);
+        let other = builder
+            .build(&mut Cursor::new(Vec::new()))
+            .expect("Operation should succeed")
+            .stats();
+        stats.merge(&other);
+        assert_eq!(stats.entry_count, 3);
+        assert_eq!(stats.min_size, Some(50));
+        assert_eq!(stats.max_size, Some(300));
+    }
+
+    #[test]
+    fn test_iter_entries_round_trip() {
+        let mut builder = ArchiveIndexBuilder::new();
+        let entries_count = MAX_ENTRIES_PER_CHUNK + 10;
+        // Add in reverse so the builder has to sort
+        for i in (0..entries_count).rev() {
+            let mut key = [0u8; 16];
+            key[12..16].copy_from_slice(&(i as u32).to_be_bytes());
+            builder.add_entry_old(key, 100 + i as u32, (i * 4096) as u32);
+        }
+
+        let mut output = Vec::new();
+        builder
+            .build(&mut Cursor::new(&mut output))
+            .expect("Operation should succeed");
+        let index = ArchiveIndex::parse(Cursor::new(&output)).expect("Operation should succeed");
+        assert_eq!(index.chunk_count(), 2);
+
+        let entries: Vec<ArchiveIndexEntry<'_>> = index.iter_entries().collect();
+        assert_eq!(entries.len(), entries_count);
+        for (i, entry) in entries.iter().enumerate() {
+            assert_eq!(entry.encoding_key[12..16], (i as u32).to_be_bytes());
+            assert_eq!(entry.offset, (i * 4096) as u64);
+            assert_eq!(entry.size, 100 + i as u32);
+            assert_eq!(
+                entry.range(),
+                entry.offset..entry.offset + u64::from(entry.size)
+            );
+            assert_eq!(entry.archive_index, None);
+        }
+    }
+
     #[test]
     fn test_builder_multiple_chunks() {
         let mut builder = ArchiveIndexBuilder::new();
@@ -2419,6 +2563,99 @@ mod tests {
         assert!(builder.is_empty());
     }
 
+    #[test]
+    fn test_builder_footer_spec() {
+        // Version 0 archive-group layout with 26-byte records
+        let mut spec = IndexFooter::new(vec![0; 8], 0);
+        spec.version = 0;
+        spec.offset_bytes = 6;
+        let mut builder = ArchiveIndexBuilder::with_footer_spec(&spec);
+        for i in 0..400u32 {
+            let mut key = vec![0u8; 16];
+            key[..4].copy_from_slice(&i.to_be_bytes());
+            builder.entries.push(IndexEntry::new_archive_group(
+                key,
+                i + 1,
+                (i % 7) as u16,
+                i * 10,
+            ));
+        }
+
+        let mut output = Vec::new();
+        let built = builder
+            .build(&mut Cursor::new(&mut output))
+            .expect("Operation should succeed");
+        assert_eq!(built.footer.version, 0);
+        assert_eq!(built.chunk_count(), 3); // 157 records per page
+
+        let parsed =
+            ArchiveIndex::parse(&mut Cursor::new(&output)).expect("Operation should succeed");
+        assert_eq!(parsed.entries, built.entries);
+        assert_eq!(parsed.footer, built.footer);
+
+        // Rebuilding keeps the layout and the bytes
+        let mut rebuilt = Vec::new();
+        ArchiveIndexBuilder::from_archive_index(&parsed)
+            .build(&mut Cursor::new(&mut rebuilt))
+            .expect("Operation should succeed");
+        assert_eq!(rebuilt, output);
+    }
+
+    #[test]
+    fn test_builder_rejects_unrepresentable_entries() {
+        let mut builder = ArchiveIndexBuilder::new();
+        builder.add_entry(vec![1u8; 9], 100, 0);
+        assert!(matches!(
+            builder.build(&mut Cursor::new(Vec::new())),
+            Err(ArchiveError::InvalidFormat(_))
+        ));
+
+        let mut builder = ArchiveIndexBuilder::new();
+        builder.add_entry(vec![1u8; 16], 100, 1 << 32);
+        assert!(matches!(
+            builder.build(&mut Cursor::new(Vec::new())),
+            Err(ArchiveError::InvalidFormat(_))
+        ));
+
+        // Five offset bytes hold 40-bit offsets
+        let mut builder = ArchiveIndexBuilder::with_config(16, 5, 4);
+        builder.add_entry(vec![1u8; 16], 100, (1 << 40) - 1);
+        let index = builder
+            .build(&mut Cursor::new(Vec::new()))
+            .expect("Operation should succeed");
+        assert_eq!(index.entries[0].offset, (1 << 40) - 1);
+
+        // The client only accepts 4 KB pages
+        let mut spec = IndexFooter::new(vec![0; 8], 0);
+        spec.page_size_kb = 8;
+        assert!(
+            ArchiveIndexBuilder::with_footer_spec(&spec)
+                .build(&mut Cursor::new(Vec::new()))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_toc_hash_validated() {
+        let mut builder = ArchiveIndexBuilder::new();
+        for i in 0..200u8 {
+            builder.add_entry(vec![i; 16], u32::from(i) + 1, u64::from(i) * 100);
+        }
+        let mut output = Vec::new();
+        let index = builder
+            .build(&mut Cursor::new(&mut output))
+            .expect("Operation should succeed");
+        assert!(index.validate().is_ok());
+
+        // The footer hash does not cover the TOC hash, so only the TOC check fails
+        let toc_hash_pos = output.len() - 28;
+        output[toc_hash_pos] ^= 0xff;
+        assert!(matches!(
+            ArchiveIndex::parse(&mut Cursor::new(&output)),
+            Err(ArchiveError::TocChecksum)
+        ));
+    }
+
     #[cfg(test)]
     mod proptest_tests {
         use super::*;
@@ -2426,6 +2663,59 @@ mod tests {
         use proptest::test_runner::TestCaseError;
         use std::io::Cursor;
 
+        /// Unique keys of one length with sizes and offsets, spanning a few pages
+        fn index_entries() -> impl Strategy<Value = (u8, u8, Vec<IndexEntry>)> {
+            (9u8..=16, prop::sample::select(vec![4u8, 5]))
+                .prop_flat_map(|(key_size, offset_bytes)| {
+                    let key = prop::collection::vec(any::<u8>(), usize::from(key_size));
+                    let entry = (1u32..u32::MAX, 0u64..u64::from(u32::MAX));
+                    (
+                        Just(key_size),
+                        Just(offset_bytes),
+                        prop::collection::btree_map(key, entry, 1..600),
+                    )
+                })
+                .prop_map(|(key_size, offset_bytes, entries)| {
+                    let entries = entries
+                        .into_iter()
+                        .map(|(key, (size, offset))| IndexEntry::new(key, size, offset))
+                        .collect();
+                    (key_size, offset_bytes, entries)
+                })
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(64))]
+
+            #[test]
+            fn built_index_parses_to_same_entries((key_size, offset_bytes, entries) in index_entries()) {
+                let mut builder = ArchiveIndexBuilder::with_config(key_size, offset_bytes, 4);
+                for entry in entries.iter().rev() {
+                    builder.add_entry(entry.encoding_key.clone(), entry.size, entry.offset);
+                }
+                let mut output = Vec::new();
+                builder
+                    .build(&mut Cursor::new(&mut output))
+                    .map_err(|e| TestCaseError::fail(e.to_string()))?;
+
+                let parsed = ArchiveIndex::parse(&mut Cursor::new(&output))
+                    .map_err(|e| TestCaseError::fail(e.to_string()))?;
+                prop_assert_eq!(&parsed.entries, &entries);
+
+                // Every key is found, including the first and last of each page
+                for entry in &entries {
+                    prop_assert_eq!(parsed.find_entry(&entry.encoding_key), Some(entry));
+                }
+
+                // Keys between entries are not found
+                for pair in entries.windows(2) {
+                    let mut between = pair[0].encoding_key.clone();
+                    between.push(0);
+                    prop_assert!(parsed.find_entry(&between).is_none());
+                }
+            }
+        }
+
         /// Generate arbitrary encoding keys of various valid lengths
         #[allow(dead_code)]
         fn encoding_key() -> impl Strategy<Value = Vec<u8>> {
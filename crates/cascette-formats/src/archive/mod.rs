@@ -9,6 +9,7 @@
 //! - **Archive Building**: Create CDN archive data files with BLTE-encoded content
 //! - **Archive Index Parsing**: Binary format parsing with chunked structure
 //! - **Archive Index Building**: Create index files for archive data
+//! - **Archive Rebuilding**: Repack BLTE files into a new contiguous archive
 //! - **Variable-Length Key Support**: Full encoding key support based on footer specification
 //! - **Binary Search Operations**: Fast content location with O(log n) lookups
 //! - **HTTP Range Requests**: Efficient partial content downloads
@@ -124,6 +125,7 @@ mod builder;
 mod error;
 mod file;
 mod index;
+mod rebuild;
 
 #[cfg(test)]
 #[allow(clippy::expect_used, clippy::unwrap_used)]
@@ -136,9 +138,10 @@ pub use builder::{ArchiveBuilder, ArchiveEntry};
 pub use error::{ArchiveError, ArchiveResult};
 pub use file::{ArchiveFile, ArchiveLocation, ArchiveReader};
 pub use index::{
-    ArchiveIndex, ArchiveIndexBuilder, ChunkedArchiveIndex, IndexEntry, IndexFooter,
-    calculate_block_hash, calculate_chunks, calculate_toc_hash, is_sorted,
+    ArchiveIndex, ArchiveIndexBuilder, ArchiveIndexEntry, ChunkedArchiveIndex, IndexEntry,
+    IndexFooter, IndexStats, calculate_block_hash, calculate_chunks, calculate_toc_hash, is_sorted,
 };
+pub use rebuild::{RebuildConfig, rebuild_archive};
 
 /// Archive system constants
 pub mod constants {
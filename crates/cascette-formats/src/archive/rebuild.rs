@@ -0,0 +1,249 @@
+//! Rebuilding CDN archives from their BLTE files
+//!
+//! Archives that grew through many incremental patches carry entries in
+//! the order they were added. [`rebuild_archive`] packs a set of BLTE files
+//! into one new contiguous archive, largest first, and builds its index.
+//!
+//! With an alignment above one, each entry starts on a multiple of it and
+//! the gap to the previous entry is zero-filled. Placing the largest
+//! entries first keeps that padding to the small entries at the end.
+
+use std::collections::HashSet;
+use std::io::Cursor;
+
+use cascette_crypto::EncodingKey;
+
+use crate::archive::error::{ArchiveError, ArchiveResult};
+use crate::archive::index::{ArchiveIndex, ArchiveIndexBuilder};
+
+/// Settings for [`rebuild_archive`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RebuildConfig {
+    /// Maximum size of the rebuilt archive in bytes
+    pub target_size: u64,
+    /// Every entry starts at a multiple of this many bytes
+    pub alignment: u32,
+}
+
+impl RebuildConfig {
+    /// Size of Blizzard's CDN archives
+    pub const DEFAULT_TARGET_SIZE: u64 = 256 * 1024 * 1024;
+
+    /// Set the maximum size of the rebuilt archive
+    pub fn with_target_size(mut self, target_size: u64) -> Self {
+        self.target_size = target_size;
+        self
+    }
+
+    /// Set the alignment of entries
+    pub fn with_alignment(mut self, alignment: u32) -> Self {
+        self.alignment = alignment;
+        self
+    }
+}
+
+impl Default for RebuildConfig {
+    fn default() -> Self {
+        Self {
+            target_size: Self::DEFAULT_TARGET_SIZE,
+            alignment: 1,
+        }
+    }
+}
+
+/// Pack `files` into a new archive and build its index
+///
+/// Each file is a BLTE-encoded blob together with its encoding key. Files
+/// are written in decreasing size order; a key given more than once is
+/// only stored the first time.
+///
+/// # Errors
+///
+/// Returns [`ArchiveError::InvalidFormat`] if the alignment is zero, a
+/// file is not BLTE data or too large for an index entry, or the archive
+/// would exceed the target size (or the 4 GiB reach of index offsets).
+pub fn rebuild_archive(
+    files: &[(EncodingKey, &[u8])],
+    config: &RebuildConfig,
+) -> ArchiveResult<(Vec<u8>, ArchiveIndex)> {
+    if config.alignment == 0 {
+        return Err(ArchiveError::InvalidFormat(
+            "archive alignment must be at least 1".to_string(),
+        ));
+    }
+    let limit = config.target_size.min(u64::from(u32::MAX) + 1);
+
+    let mut seen = HashSet::new();
+    let mut ordered = Vec::with_capacity(files.len());
+    for (key, data) in files {
+        if !data.starts_with(b"BLTE") {
+            return Err(ArchiveError::InvalidFormat(format!(
+                "{} is not BLTE data",
+                key.to_hex()
+            )));
+        }
+        if u32::try_from(data.len()).is_err() {
+            return Err(ArchiveError::InvalidFormat(format!(
+                "{} is too large for an archive entry ({} bytes)",
+                key.to_hex(),
+                data.len()
+            )));
+        }
+        if seen.insert(*key.as_bytes()) {
+            ordered.push((key, *data));
+        }
+    }
+    // Largest first; the key keeps the layout deterministic
+    ordered.sort_by(|(a_key, a), (b_key, b)| {
+        b.len()
+            .cmp(&a.len())
+            .then_with(|| a_key.as_bytes().cmp(b_key.as_bytes()))
+    });
+
+    let alignment = u64::from(config.alignment);
+    let mut archive = Vec::new();
+    let mut index = ArchiveIndexBuilder::new();
+    for (key, data) in ordered {
+        let offset = (archive.len() as u64).div_ceil(alignment) * alignment;
+        let end = offset + data.len() as u64;
+        if end > limit {
+            return Err(ArchiveError::InvalidFormat(format!(
+                "rebuilt archive would exceed its target size of {limit} bytes"
+            )));
+        }
+
+        archive.resize(usize::try_from(offset).unwrap_or(usize::MAX), 0);
+        archive.extend_from_slice(data);
+        // Sizes were checked to fit in u32 above
+        index.add_entry(key.as_bytes().to_vec(), data.len() as u32, offset);
+    }
+
+    let index = index.build(Cursor::new(Vec::new()))?;
+    Ok((archive, index))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::CascFormat;
+    use crate::archive::{ArchiveLocation, ArchiveReader};
+    use crate::blte::{BlteBuilder, CompressionMode};
+
+    /// BLTE files of assorted sizes, with their keys and content
+    fn blte_files() -> Vec<(EncodingKey, Vec<u8>, Vec<u8>)> {
+        [17usize, 4096, 300, 1, 12_345, 999, 64, 2048, 5000, 77]
+            .iter()
+            .enumerate()
+            .map(|(i, &len)| {
+                let content: Vec<u8> = (0..len).map(|b| (b * 7 + i) as u8).collect();
+                let blte = BlteBuilder::new()
+                    .with_compression(CompressionMode::None)
+                    .add_data(&content)
+                    .unwrap()
+                    .build()
+                    .unwrap()
+                    .build()
+                    .unwrap();
+                (EncodingKey::from_data(&blte), blte, content)
+            })
+            .collect()
+    }
+
+    fn rebuild(
+        files: &[(EncodingKey, Vec<u8>, Vec<u8>)],
+        config: &RebuildConfig,
+    ) -> ArchiveResult<(Vec<u8>, ArchiveIndex)> {
+        let inputs: Vec<_> = files
+            .iter()
+            .map(|(key, blte, _)| (*key, blte.as_slice()))
+            .collect();
+        rebuild_archive(&inputs, config)
+    }
+
+    #[test]
+    fn test_all_files_extract_from_rebuilt_archive() {
+        let files = blte_files();
+        let config = RebuildConfig::default().with_alignment(16);
+        let (archive, index) = rebuild(&files, &config).expect("Archive should rebuild");
+        assert_eq!(index.entry_count(), files.len());
+
+        let mut reader = ArchiveReader::new(Cursor::new(archive.clone()));
+        for (key, blte, content) in &files {
+            let entry = index
+                .find_entry(key.as_bytes())
+                .expect("Every file should be indexed");
+            assert_eq!(entry.offset % 16, 0);
+
+            let start = usize::try_from(entry.offset).unwrap();
+            assert_eq!(
+                &archive[start..start + entry.size as usize],
+                blte.as_slice()
+            );
+
+            let location = ArchiveLocation::new(key.to_hex(), entry.offset, u64::from(entry.size));
+            assert_eq!(&reader.read_content(&location).unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn test_archive_size_is_close_to_optimum() {
+        let files = blte_files();
+        let optimum: usize = files.iter().map(|(_, blte, _)| blte.len()).sum();
+
+        let (packed, _) = rebuild(&files, &RebuildConfig::default()).unwrap();
+        assert_eq!(packed.len(), optimum);
+
+        let config = RebuildConfig::default().with_alignment(16);
+        let (aligned, _) = rebuild(&files, &config).unwrap();
+        assert!(aligned.len() >= optimum);
+        assert!(
+            aligned.len() * 100 <= optimum * 105,
+            "{} vs {optimum}",
+            aligned.len()
+        );
+    }
+
+    #[test]
+    fn test_entries_are_written_largest_first() {
+        let files = blte_files();
+        let (_, index) = rebuild(&files, &RebuildConfig::default()).unwrap();
+
+        let mut entries: Vec<_> = index.iter_entries().map(|entry| entry.range()).collect();
+        entries.sort_by_key(|range| range.start);
+        let sizes: Vec<_> = entries
+            .iter()
+            .map(|range| range.end - range.start)
+            .collect();
+        assert!(sizes.windows(2).all(|pair| pair[0] >= pair[1]));
+    }
+
+    #[test]
+    fn test_rebuild_rejects_invalid_input() {
+        let files = blte_files();
+        let optimum: u64 = files.iter().map(|(_, blte, _)| blte.len() as u64).sum();
+
+        let too_small = RebuildConfig::default().with_target_size(optimum - 1);
+        assert!(matches!(
+            rebuild(&files, &too_small),
+            Err(ArchiveError::InvalidFormat(_))
+        ));
+        assert!(rebuild(&files, &RebuildConfig::default().with_target_size(optimum)).is_ok());
+
+        let unaligned = RebuildConfig::default().with_alignment(0);
+        assert!(rebuild(&files, &unaligned).is_err());
+
+        let not_blte = [(EncodingKey::from_data(b"raw"), b"raw".as_slice())];
+        assert!(rebuild_archive(&not_blte, &RebuildConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_keys_are_stored_once() {
+        let files = blte_files();
+        let (key, blte, _) = &files[0];
+        let inputs = [(*key, blte.as_slice()), (*key, blte.as_slice())];
+        let (archive, index) = rebuild_archive(&inputs, &RebuildConfig::default()).unwrap();
+        assert_eq!(index.entry_count(), 1);
+        assert_eq!(archive.len(), blte.len());
+    }
+}
@@ -1,8 +1,12 @@
 //! BLTE file builder
 
-use super::compression::{EncryptionSpec, encrypt_chunk_with_key};
+use super::compression::{
+    EncryptionSpec, compress_chunk, compress_zlib, encrypt_chunk, encrypt_chunk_with_key,
+};
 use super::error::{BlteError, BlteResult};
-use super::{BlteFile, BlteHeader, ChunkData, CompressionMode};
+use super::{BlteFile, BlteHeader, BlteLayout, ChunkData, CompressionMode, HeaderFlags};
+use crate::espec::{BlockPlan, ESpec, ZLibVariant};
+use cascette_crypto::TactKeyStore;
 
 /// Minimum chunk size (1 KB) - smaller chunks create too much overhead
 const MIN_CHUNK_SIZE: usize = 1024;
@@ -13,6 +17,15 @@ const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
 /// Default chunk size (256 KB) - balanced for performance
 const DEFAULT_CHUNK_SIZE: usize = 256 * 1024;
 
+/// zlib level used when an `ESpec` does not name one
+const DEFAULT_ZLIB_LEVEL: u8 = 6;
+
+/// Window bits of a standard zlib stream, the only size `flate2` writes
+const DEFAULT_ZLIB_WINDOW_BITS: u8 = 15;
+
+/// Encryption type byte for `Salsa20`, the cipher `ESpec` encryption uses
+const SALSA20: u8 = 0x53;
+
 /// Encryption configuration for BLTE builder
 #[derive(Debug, Clone, Copy)]
 pub struct EncryptionConfig {
@@ -28,6 +41,7 @@ pub struct BlteBuilder {
     default_mode: CompressionMode,
     chunk_size: usize,
     encryption: Option<EncryptionConfig>,
+    layout: Option<BlteLayout>,
 }
 
 impl BlteBuilder {
@@ -38,9 +52,38 @@ impl BlteBuilder {
             default_mode: CompressionMode::None,
             chunk_size: DEFAULT_CHUNK_SIZE,
             encryption: None,
+            layout: None,
         }
     }
 
+    /// Create a builder for the chunkless form
+    ///
+    /// The file has header size 0 and its single chunk follows the preamble
+    /// directly. Added data is never split, and building fails if more than
+    /// one chunk was added or the chunk is encrypted.
+    pub fn chunkless() -> Self {
+        Self::new().with_layout(BlteLayout::Chunkless)
+    }
+
+    /// Create a builder holding `data` encoded as `espec` lays it out
+    ///
+    /// Each block of [`ESpec::plan`] becomes one chunk, and the file gets a
+    /// chunk table whenever the spec has one, so rebuilding content with the
+    /// `ESpec` recorded in its encoding file reproduces the original layout.
+    /// See [`Self::add_planned_blocks`] for how blocks are encoded.
+    pub fn from_espec(
+        espec: &ESpec,
+        data: &[u8],
+        key_store: Option<&TactKeyStore>,
+    ) -> BlteResult<Self> {
+        let builder = if espec.has_chunk_table() {
+            Self::new().with_chunk_table()
+        } else {
+            Self::new()
+        };
+        builder.add_planned_blocks(data, &espec.plan(data.len() as u64), key_store)
+    }
+
     /// Set the default compression mode
     #[must_use]
     pub fn with_compression(mut self, mode: CompressionMode) -> Self {
@@ -91,6 +134,70 @@ impl BlteBuilder {
         self
     }
 
+    /// Write a chunk table even when the file has a single chunk
+    #[must_use]
+    pub fn with_chunk_table(self) -> Self {
+        self.with_layout(BlteLayout::ChunkTable(HeaderFlags::Standard))
+    }
+
+    /// Build the file in `layout` instead of choosing one from the chunks
+    ///
+    /// Pass the [`BlteHeader::layout`] of a parsed file to rebuild it in its
+    /// original form, including the chunk table flags byte.
+    #[must_use]
+    pub fn with_layout(mut self, layout: BlteLayout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// Whether added data must stay in one chunk
+    fn is_chunkless(&self) -> bool {
+        self.layout == Some(BlteLayout::Chunkless)
+    }
+
+    /// Add one chunk per planned block of `data`
+    ///
+    /// Blocks are encoded with their own spec, not the builder's defaults:
+    /// - `n` is stored as is
+    /// - `z` is compressed with zlib at the spec's level; window sizes other
+    ///   than 15 bits cannot be written. The `lz4hc` variant is written as an
+    ///   LZ4 chunk.
+    /// - `e:{key,iv,spec}` encrypts the nested block with `Salsa20`, using
+    ///   the key named by the hex key name in `key_store`
+    ///
+    /// `BCPack` and `GDeflate` have no BLTE chunk mode and are rejected.
+    /// Compressed chunks match the original bytes only when the zlib
+    /// implementation does.
+    pub fn add_planned_blocks(
+        mut self,
+        data: &[u8],
+        plan: &[BlockPlan<'_>],
+        key_store: Option<&TactKeyStore>,
+    ) -> BlteResult<Self> {
+        for block in plan {
+            let range = usize::try_from(block.offset)
+                .ok()
+                .zip(usize::try_from(block.offset + block.size).ok())
+                .filter(|&(_, end)| end <= data.len())
+                .ok_or_else(|| {
+                    BlteError::InvalidChunk(format!(
+                        "planned block at {} ({} bytes) is outside {} bytes of data",
+                        block.offset,
+                        block.size,
+                        data.len()
+                    ))
+                })?;
+            let chunk = encode_block(
+                block.spec,
+                &data[range.0..range.1],
+                self.chunks.len(),
+                key_store,
+            )?;
+            self.chunks.push(chunk);
+        }
+        Ok(self)
+    }
+
     /// Add a pre-built chunk
     #[must_use]
     pub fn add_chunk(mut self, chunk: ChunkData) -> Self {
@@ -100,7 +207,7 @@ impl BlteBuilder {
 
     /// Add data that will be automatically chunked
     pub fn add_data(mut self, data: &[u8]) -> BlteResult<Self> {
-        if data.len() <= self.chunk_size {
+        if data.len() <= self.chunk_size || self.is_chunkless() {
             // Single chunk
             let chunk = if let Some(_encryption) = &self.encryption {
                 self.create_encrypted_chunk(data.to_vec(), 0)?
@@ -150,7 +257,7 @@ impl BlteBuilder {
         data: &[u8],
         encryption_per_chunk: Option<(EncryptionSpec, [u8; 16])>,
     ) -> BlteResult<Self> {
-        if data.len() <= self.chunk_size {
+        if data.len() <= self.chunk_size || self.is_chunkless() {
             // Single chunk - use current chunk count as block index for encryption
             let chunk_index = self.chunks.len();
             let chunk = if let Some((spec, key)) = encryption_per_chunk {
@@ -184,6 +291,8 @@ impl BlteBuilder {
     /// The encrypted payload always starts with an inner compression mode byte.
     /// After decryption, this byte indicates how to decompress the content.
     fn create_encrypted_chunk(&self, data: Vec<u8>, block_index: usize) -> BlteResult<ChunkData> {
+        // The chunk table records the size after decryption and decompression
+        let decompressed_size = data.len();
         let encryption = self.encryption.as_ref().ok_or_else(|| {
             super::error::BlteError::CompressionError("No encryption config set".to_string())
         })?;
@@ -197,7 +306,7 @@ impl BlteBuilder {
         Ok(ChunkData::from_compressed(
             CompressionMode::Encrypted,
             encrypted_data,
-            Some(inner.len()),
+            Some(decompressed_size),
         ))
     }
 
@@ -209,6 +318,8 @@ impl BlteBuilder {
         key: [u8; 16],
         block_index: usize,
     ) -> BlteResult<ChunkData> {
+        // The chunk table records the size after decryption and decompression
+        let decompressed_size = data.len();
         let inner = self.build_inner_payload(data)?;
 
         // Encrypt the payload (mode byte + compressed/raw data)
@@ -217,7 +328,7 @@ impl BlteBuilder {
         Ok(ChunkData::from_compressed(
             CompressionMode::Encrypted,
             encrypted_data,
-            Some(inner.len()),
+            Some(decompressed_size),
         ))
     }
 
@@ -251,7 +362,7 @@ impl BlteBuilder {
     ///
     /// Encrypted chunks always use the multi-chunk (extended header) format,
     /// even when there is only one chunk. The spec requires encrypted content
-    /// to have a chunk table.
+    /// to have a chunk table, so the chunkless layout rejects them.
     pub fn build(self) -> BlteResult<BlteFile> {
         if self.chunks.is_empty() {
             return Err(super::error::BlteError::InvalidChunkCount(0));
@@ -262,7 +373,16 @@ impl BlteBuilder {
             .iter()
             .any(|c| c.mode == CompressionMode::Encrypted);
 
-        if self.chunks.len() == 1 && !has_encrypted {
+        if let Some(layout) = self.layout {
+            if layout == BlteLayout::Chunkless && has_encrypted {
+                return Err(BlteError::SingleChunkEncrypted);
+            }
+            let header = BlteHeader::for_layout(&self.chunks, layout)?;
+            Ok(BlteFile {
+                header,
+                chunks: self.chunks,
+            })
+        } else if self.chunks.len() == 1 && !has_encrypted {
             // Single chunk file (non-encrypted only)
             Ok(BlteFile {
                 header: BlteHeader::single_chunk(),
@@ -279,6 +399,67 @@ impl BlteBuilder {
     }
 }
 
+/// Encode one planned block as a chunk
+fn encode_block(
+    spec: &ESpec,
+    data: &[u8],
+    block_index: usize,
+    key_store: Option<&TactKeyStore>,
+) -> BlteResult<ChunkData> {
+    let (mode, payload) = match spec {
+        ESpec::None => (CompressionMode::None, data.to_vec()),
+        ESpec::ZLib {
+            variant: Some(ZLibVariant::LZ4HC),
+            ..
+        } => (
+            CompressionMode::LZ4,
+            compress_chunk(data, CompressionMode::LZ4)?,
+        ),
+        ESpec::ZLib {
+            level, window_bits, ..
+        } => {
+            if let Some(bits) = window_bits
+                && *bits != DEFAULT_ZLIB_WINDOW_BITS
+            {
+                return Err(BlteError::CompressionError(format!(
+                    "ZLib window bits {bits} cannot be written, only {DEFAULT_ZLIB_WINDOW_BITS}"
+                )));
+            }
+            let level = level.unwrap_or(DEFAULT_ZLIB_LEVEL);
+            (
+                CompressionMode::ZLib,
+                compress_zlib(data, u32::from(level))?,
+            )
+        }
+        ESpec::Encrypted { key, iv, spec } => {
+            if spec.is_encrypted() {
+                return Err(BlteError::NestedEncryption);
+            }
+            let key_name = u64::from_str_radix(key, 16).map_err(|_| {
+                BlteError::CompressionError(format!("Invalid encryption key name: {key}"))
+            })?;
+            let key = key_store
+                .and_then(|store| store.get(key_name))
+                .ok_or(BlteError::KeyNotFound(key_name))?;
+
+            // The encrypted payload is the nested chunk with its mode byte
+            let inner = encode_block(spec, data, block_index, key_store)?.compressed_data();
+            (
+                CompressionMode::Encrypted,
+                encrypt_chunk(&inner, key_name, iv, SALSA20, key, block_index)?,
+            )
+        }
+        ESpec::BlockTable { .. } => {
+            return Err(BlteError::InvalidChunk(
+                "block tables must be planned before encoding".to_string(),
+            ));
+        }
+        ESpec::BCPack { .. } => return Err(BlteError::UnsupportedCompressionMode(b'c')),
+        ESpec::GDeflate { .. } => return Err(BlteError::UnsupportedCompressionMode(b'g')),
+    };
+    Ok(ChunkData::from_compressed(mode, payload, Some(data.len())))
+}
+
 impl Default for BlteBuilder {
     fn default() -> Self {
         Self::new()
@@ -644,4 +825,210 @@ mod tests {
         assert_eq!(decrypted2, data);
         assert_eq!(decrypted1, decrypted2);
     }
+
+    fn espec(input: &str) -> ESpec {
+        ESpec::parse(input).expect("ESpec should parse")
+    }
+
+    #[test]
+    fn test_from_espec_uncompressed_is_byte_identical() {
+        use crate::CascFormat;
+
+        let data: Vec<u8> = (0..40).collect();
+        let blte = BlteBuilder::from_espec(&espec("b:{16*2=n,*=n}"), &data, None)
+            .expect("Operation should succeed")
+            .build()
+            .expect("Test operation should succeed");
+
+        let expected = BlteFile::multi_chunk(vec![
+            ChunkData::new(data[..16].to_vec(), CompressionMode::None).expect("chunk"),
+            ChunkData::new(data[16..32].to_vec(), CompressionMode::None).expect("chunk"),
+            ChunkData::new(data[32..].to_vec(), CompressionMode::None).expect("chunk"),
+        ])
+        .expect("Test operation should succeed");
+        assert_eq!(
+            blte.build().expect("BLTE should serialize"),
+            expected.build().expect("BLTE should serialize")
+        );
+    }
+
+    #[test]
+    fn test_from_espec_chunk_table_follows_spec() {
+        let data = b"Hello, ESpec!".repeat(10);
+
+        // Plain specs are a single chunk without a table
+        let blte = BlteBuilder::from_espec(&espec("z:9"), &data, None)
+            .expect("Operation should succeed")
+            .build()
+            .expect("Test operation should succeed");
+        assert!(blte.header.is_single_chunk());
+        assert_eq!(blte.chunks[0].mode, CompressionMode::ZLib);
+        // zlib header for maximum compression
+        assert_eq!(blte.chunks[0].data[..2], [0x78, 0xDA]);
+        assert_eq!(blte.decompress().expect("Operation should succeed"), data);
+
+        // Block tables keep the table for a single chunk
+        let blte = BlteBuilder::from_espec(&espec("b:{*=z}"), &data, None)
+            .expect("Operation should succeed")
+            .build()
+            .expect("Test operation should succeed");
+        assert!(!blte.header.is_single_chunk());
+        assert_eq!(blte.chunks.len(), 1);
+        assert_eq!(blte.decompress().expect("Operation should succeed"), data);
+    }
+
+    #[test]
+    fn test_from_espec_encrypted_blocks() {
+        let data = b"Hello, encrypted ESpec block!".repeat(4);
+        let spec = espec("b:{16=z,*=e:{1234567890ABCDEF,0011223344556677,z}}");
+        let mut key_store = TactKeyStore::new();
+        key_store.add(TactKey::new(0x1234_5678_90AB_CDEF, [0x42; 16]));
+
+        let blte = BlteBuilder::from_espec(&spec, &data, Some(&key_store))
+            .expect("Operation should succeed")
+            .build()
+            .expect("Test operation should succeed");
+        assert_eq!(blte.chunks[1].mode, CompressionMode::Encrypted);
+        // Key name, then the 8-byte IV from the spec
+        assert_eq!(
+            blte.chunks[1].data[1..9],
+            0x1234_5678_90AB_CDEF_u64.to_le_bytes()
+        );
+        assert_eq!(blte.chunks[1].data[9], 8);
+        assert_eq!(
+            blte.chunks[1].data[10..18],
+            [0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77]
+        );
+        assert_eq!(
+            blte.decompress_with_keys(&key_store)
+                .expect("Operation should succeed"),
+            data
+        );
+
+        let missing = BlteBuilder::from_espec(&spec, &data, None);
+        assert!(matches!(
+            missing,
+            Err(BlteError::KeyNotFound(0x1234_5678_90AB_CDEF))
+        ));
+    }
+
+    #[test]
+    fn test_from_espec_rejects_unwritable_specs() {
+        let data = b"data";
+        for input in ["c", "g:{5}", "z:{9,12}"] {
+            assert!(
+                BlteBuilder::from_espec(&espec(input), data, None).is_err(),
+                "{input} should be rejected"
+            );
+        }
+
+        let spec = espec("n");
+        let plan = spec.plan(100);
+        assert!(matches!(
+            BlteBuilder::new().add_planned_blocks(data, &plan, None),
+            Err(BlteError::InvalidChunk(_))
+        ));
+    }
+
+    #[test]
+    fn test_chunkless_layout() {
+        use crate::CascFormat;
+
+        let data = b"Hello, chunkless BLTE!";
+        let blte = BlteBuilder::chunkless()
+            .with_chunk_size_unchecked(4)
+            .add_data(data)
+            .expect("Operation should succeed")
+            .build()
+            .expect("Test operation should succeed");
+
+        // Not split despite the chunk size, and no chunk table
+        assert_eq!(blte.header.layout(), BlteLayout::Chunkless);
+        let bytes = blte.build().expect("BLTE should serialize");
+        assert_eq!(bytes[..8], *b"BLTE\0\0\0\0");
+        assert_eq!(bytes[8], b'N');
+        assert_eq!(bytes[9..], data[..]);
+
+        let too_many = BlteBuilder::chunkless()
+            .add_chunk(ChunkData::new(data.to_vec(), CompressionMode::None).expect("chunk"))
+            .add_chunk(ChunkData::new(data.to_vec(), CompressionMode::None).expect("chunk"))
+            .build();
+        assert!(matches!(too_many, Err(BlteError::InvalidChunkCount(2))));
+
+        let encrypted = BlteBuilder::chunkless()
+            .with_encryption(EncryptionSpec::salsa20(0x1234, [1, 2, 3, 4]), [0x42; 16])
+            .add_data(data)
+            .expect("Operation should succeed")
+            .build();
+        assert!(matches!(encrypted, Err(BlteError::SingleChunkEncrypted)));
+    }
+
+    #[test]
+    fn test_layout_round_trip_is_byte_identical() {
+        use crate::CascFormat;
+
+        let data = b"layout round trip ".repeat(100);
+        for layout in [
+            BlteLayout::Chunkless,
+            BlteLayout::ChunkTable(HeaderFlags::Standard),
+            BlteLayout::ChunkTable(HeaderFlags::Extended),
+        ] {
+            let original = BlteBuilder::new()
+                .with_compression(CompressionMode::ZLib)
+                .with_layout(layout)
+                .add_data(&data)
+                .expect("Operation should succeed")
+                .build()
+                .expect("Test operation should succeed")
+                .build()
+                .expect("BLTE should serialize");
+
+            let parsed = <BlteFile as CascFormat>::parse(&original).expect("BLTE should parse");
+            assert_eq!(parsed.header.layout(), layout);
+
+            let rebuilt = parsed
+                .chunks
+                .iter()
+                .cloned()
+                .fold(
+                    BlteBuilder::new().with_layout(parsed.header.layout()),
+                    BlteBuilder::add_chunk,
+                )
+                .build()
+                .expect("Test operation should succeed");
+            assert_eq!(
+                rebuilt.build().expect("BLTE should serialize"),
+                original,
+                "{layout:?}"
+            );
+            assert_eq!(
+                rebuilt.decompress().expect("Operation should succeed"),
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn test_chunkless_decompresses_like_chunked() {
+        let data = b"same payload, different container ".repeat(50);
+        let chunkless = BlteBuilder::chunkless()
+            .with_compression(CompressionMode::ZLib)
+            .add_data(&data)
+            .expect("Operation should succeed")
+            .build()
+            .expect("Test operation should succeed");
+        let chunked = BlteBuilder::new()
+            .with_compression(CompressionMode::ZLib)
+            .with_chunk_size_unchecked(256)
+            .add_data(&data)
+            .expect("Operation should succeed")
+            .build()
+            .expect("Test operation should succeed");
+
+        assert!(chunked.chunks.len() > 1);
+        assert_eq!(
+            chunkless.decompress().expect("Operation should succeed"),
+            chunked.decompress().expect("Operation should succeed")
+        );
+    }
 }
@@ -3,7 +3,9 @@
 use binrw::io::{Read, Seek, Write};
 use binrw::{BinRead, BinResult, BinWrite};
 
+use super::compression::decrypt_chunk_with_keys;
 use super::error::{BlteError, BlteResult};
+use cascette_crypto::TactKeyStore;
 
 /// BLTE compression modes
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -17,6 +19,12 @@ pub enum CompressionMode {
     LZ4 = b'4',
     /// Encrypted (mode 'E')
     Encrypted = b'E',
+    /// Zstandard compression (mode 'S')
+    ///
+    /// Not used by Blizzard content. Meant for pipelines that re-encode
+    /// BLTE for their own distribution; encoding and decoding need the
+    /// `zstd` feature.
+    ZStd = b'S',
     /// Frame/Recursive BLTE (mode 'F') - deprecated
     #[deprecated(since = "0.1.0", note = "Recursive BLTE is deprecated")]
     Frame = b'F',
@@ -31,6 +39,7 @@ impl CompressionMode {
             b'Z' => Some(Self::ZLib),
             b'4' => Some(Self::LZ4),
             b'E' => Some(Self::Encrypted),
+            b'S' => Some(Self::ZStd),
             b'F' => Some(Self::Frame),
             _ => None,
         }
@@ -169,6 +178,23 @@ impl ChunkData {
         decompress_chunk(&self.data, self.mode)
     }
 
+    /// Decompress the chunk data, decrypting encrypted chunks
+    ///
+    /// `chunk_index` is the position of the chunk in its BLTE file. The
+    /// Salsa20/ARC4 IV is derived from it, so chunks decoded out of order
+    /// (e.g. when reading a byte range) must pass their real index.
+    pub fn decompress_with_keys(
+        &self,
+        chunk_index: usize,
+        key_store: &TactKeyStore,
+    ) -> BlteResult<Vec<u8>> {
+        if self.mode == CompressionMode::Encrypted {
+            decrypt_chunk_with_keys(&self.data, key_store, chunk_index)
+        } else {
+            self.decompress(chunk_index)
+        }
+    }
+
     /// Verify checksum if provided
     pub fn verify_checksum(&self, checksum: &[u8; 16]) -> bool {
         use cascette_crypto::md5::ContentKey;
@@ -195,6 +221,7 @@ mod tests {
             (b'Z', CompressionMode::ZLib),
             (b'4', CompressionMode::LZ4),
             (b'E', CompressionMode::Encrypted),
+            (b'S', CompressionMode::ZStd),
         ];
 
         for (byte, mode) in modes {
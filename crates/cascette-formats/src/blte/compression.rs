@@ -19,14 +19,7 @@ pub const MAX_DECOMPRESSION_SIZE: usize = 1024 * 1024 * 1024;
 pub fn compress_chunk(data: &[u8], mode: CompressionMode) -> BlteResult<Vec<u8>> {
     match mode {
         CompressionMode::None => Ok(data.to_vec()),
-        CompressionMode::ZLib => {
-            let mut encoder = ZlibEncoder::new(data, Compression::default());
-            let mut compressed = Vec::new();
-            encoder.read_to_end(&mut compressed).map_err(|e| {
-                BlteError::CompressionError(format!("ZLib compression failed: {e}"))
-            })?;
-            Ok(compressed)
-        }
+        CompressionMode::ZLib => compress_zlib(data, Compression::default().level()),
         CompressionMode::LZ4 => {
             // LZ4 compression: 8-byte LE decompressed size prefix + single LZ4 block.
             //
@@ -52,6 +45,7 @@ pub fn compress_chunk(data: &[u8], mode: CompressionMode) -> BlteResult<Vec<u8>>
             result.truncate(8 + compressed_len);
             Ok(result)
         }
+        CompressionMode::ZStd => compress_zstd(data),
         CompressionMode::Encrypted => {
             // Encryption mode requires special handling via encrypt_chunk_with_key
             Err(BlteError::CompressionError(
@@ -65,6 +59,134 @@ pub fn compress_chunk(data: &[u8], mode: CompressionMode) -> BlteResult<Vec<u8>>
     }
 }
 
+/// Compress data with zlib at `level` (0-9)
+pub fn compress_zlib(data: &[u8], level: u32) -> BlteResult<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(data, Compression::new(level));
+    let mut compressed = Vec::new();
+    encoder
+        .read_to_end(&mut compressed)
+        .map_err(|e| BlteError::CompressionError(format!("ZLib compression failed: {e}")))?;
+    Ok(compressed)
+}
+
+/// Compress data with zstd at its default level
+#[cfg(feature = "zstd")]
+fn compress_zstd(data: &[u8]) -> BlteResult<Vec<u8>> {
+    zstd::bulk::compress(data, zstd::DEFAULT_COMPRESSION_LEVEL)
+        .map_err(|e| BlteError::CompressionError(format!("zstd compression failed: {e}")))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn compress_zstd(_data: &[u8]) -> BlteResult<Vec<u8>> {
+    Err(zstd_disabled())
+}
+
+/// Decompress a zstd chunk, enforcing [`MAX_DECOMPRESSION_SIZE`]
+#[cfg(feature = "zstd")]
+fn decompress_zstd(data: &[u8]) -> BlteResult<Vec<u8>> {
+    let decoder = zstd::stream::read::Decoder::new(data)
+        .map_err(|e| BlteError::CompressionError(format!("zstd decompression failed: {e}")))?;
+
+    let mut decompressed = Vec::new();
+    decoder
+        .take(MAX_DECOMPRESSION_SIZE as u64 + 1)
+        .read_to_end(&mut decompressed)
+        .map_err(|e| BlteError::CompressionError(format!("zstd decompression failed: {e}")))?;
+
+    if decompressed.len() > MAX_DECOMPRESSION_SIZE {
+        return Err(BlteError::CompressionError(format!(
+            "Decompressed size exceeds limit of {MAX_DECOMPRESSION_SIZE} bytes"
+        )));
+    }
+    Ok(decompressed)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress_zstd(_data: &[u8]) -> BlteResult<Vec<u8>> {
+    Err(zstd_disabled())
+}
+
+#[cfg(not(feature = "zstd"))]
+const fn zstd_disabled() -> BlteError {
+    BlteError::FeatureDisabled {
+        mode: CompressionMode::ZStd as u8,
+        feature: "zstd",
+    }
+}
+
+/// Magic number at the start of every zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Size of the prefix sampled to estimate compressibility (64 KiB)
+const COMPRESSIBILITY_SAMPLE_SIZE: usize = 64 * 1024;
+
+/// Options for [`auto_select_compression_mode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionStrategy {
+    /// Inputs smaller than this are stored uncompressed
+    pub min_size: usize,
+    /// Allow zstd for large, highly compressible inputs
+    ///
+    /// Has no effect unless the `zstd` feature is enabled. Off by default,
+    /// since Blizzard clients cannot read zstd chunks.
+    pub allow_zstd: bool,
+    /// Inputs at least this large may use zstd
+    pub zstd_min_size: usize,
+}
+
+impl CompressionStrategy {
+    /// Opt in to zstd for inputs it suits
+    #[must_use]
+    pub const fn with_zstd(mut self) -> Self {
+        self.allow_zstd = true;
+        self
+    }
+}
+
+impl Default for CompressionStrategy {
+    fn default() -> Self {
+        Self {
+            min_size: 64,
+            allow_zstd: false,
+            zstd_min_size: 256 * 1024,
+        }
+    }
+}
+
+/// Pick a compression mode for `data`
+///
+/// Compressibility is estimated by LZ4-compressing the first 64 KiB.
+/// Inputs that are small or barely compressible are stored as
+/// [`CompressionMode::None`]. Large inputs that compress to half their size
+/// or less use [`CompressionMode::ZStd`] when `strategy` allows it and the
+/// `zstd` feature is enabled. Everything else uses [`CompressionMode::ZLib`],
+/// which every client can read.
+pub fn auto_select_compression_mode(
+    data: &[u8],
+    strategy: &CompressionStrategy,
+) -> CompressionMode {
+    if data.len() < strategy.min_size.max(1) {
+        return CompressionMode::None;
+    }
+
+    let sample = &data[..data.len().min(COMPRESSIBILITY_SAMPLE_SIZE)];
+    let estimate = lz4_flex::block::compress(sample).len();
+    // Less than 5% saved is not worth the decompression cost
+    if estimate * 100 >= sample.len() * 95 {
+        return CompressionMode::None;
+    }
+
+    if cfg!(feature = "zstd")
+        && strategy.allow_zstd
+        && data.len() >= strategy.zstd_min_size
+        && estimate * 2 <= sample.len()
+    {
+        return CompressionMode::ZStd;
+    }
+
+    CompressionMode::ZLib
+}
+
 /// Decompress chunk data
 pub fn decompress_chunk(data: &[u8], mode: CompressionMode) -> BlteResult<Vec<u8>> {
     match mode {
@@ -143,6 +265,7 @@ pub fn decompress_chunk(data: &[u8], mode: CompressionMode) -> BlteResult<Vec<u8
 
             Ok(decompressed)
         }
+        CompressionMode::ZStd => decompress_zstd(data),
         CompressionMode::Encrypted => {
             // Encryption mode requires special handling via decrypt_chunk_with_keys
             Err(BlteError::CompressionError(
@@ -289,6 +412,96 @@ mod tests {
         assert_eq!(decompressed, data);
     }
 
+    /// Deterministic incompressible bytes (xorshift)
+    fn noise(len: usize) -> Vec<u8> {
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_round_trip() {
+        let data = b"Hello, BLTE! This is a test of zstd compression. ".repeat(100);
+        let compressed =
+            compress_chunk(&data, CompressionMode::ZStd).expect("Test operation should succeed");
+        assert!(compressed.len() < data.len());
+
+        let decompressed = decompress_chunk(&compressed, CompressionMode::ZStd)
+            .expect("Test operation should succeed");
+        assert_eq!(decompressed, data);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_invalid_data() {
+        let result = decompress_chunk(b"not a zstd frame", CompressionMode::ZStd);
+        assert!(matches!(result, Err(BlteError::CompressionError(_))));
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn test_zstd_requires_feature() {
+        for result in [
+            compress_chunk(b"data", CompressionMode::ZStd),
+            decompress_chunk(b"data", CompressionMode::ZStd),
+        ] {
+            let error = result.expect_err("zstd should need the feature");
+            assert!(matches!(
+                error,
+                BlteError::FeatureDisabled {
+                    mode: b'S',
+                    feature: "zstd"
+                }
+            ));
+            assert!(error.to_string().contains("`zstd` feature"));
+        }
+    }
+
+    #[test]
+    fn test_auto_select_small_and_incompressible() {
+        let strategy = CompressionStrategy::default().with_zstd();
+        assert_eq!(
+            auto_select_compression_mode(b"tiny", &strategy),
+            CompressionMode::None
+        );
+        assert_eq!(
+            auto_select_compression_mode(&noise(1024 * 1024), &strategy),
+            CompressionMode::None
+        );
+    }
+
+    #[test]
+    fn test_auto_select_compressible() {
+        let large = b"<Ui><Frame name=\"Auto\"/></Ui>\n".repeat(32 * 1024);
+        let small = &large[..4096];
+
+        // zstd is only chosen when the caller opts in
+        let default = CompressionStrategy::default();
+        assert_eq!(
+            auto_select_compression_mode(&large, &default),
+            CompressionMode::ZLib
+        );
+
+        let zstd = default.with_zstd();
+        assert_eq!(
+            auto_select_compression_mode(small, &zstd),
+            CompressionMode::ZLib
+        );
+        let expected = if cfg!(feature = "zstd") {
+            CompressionMode::ZStd
+        } else {
+            CompressionMode::ZLib
+        };
+        assert_eq!(auto_select_compression_mode(&large, &zstd), expected);
+    }
+
     #[test]
     fn test_encryption_spec_creation() {
         // Test Salsa20 spec creation
@@ -694,11 +907,35 @@ pub fn encrypt_chunk_with_key(
     key: &[u8; 16],
     block_index: usize,
 ) -> BlteResult<Vec<u8>> {
+    encrypt_chunk(
+        data,
+        spec.key_name,
+        &spec.iv,
+        spec.encryption_type,
+        key,
+        block_index,
+    )
+}
+
+/// Encrypt chunk data with an IV of any length the cipher accepts
+///
+/// `Salsa20` takes 4 or 8 byte IVs; 8-byte IVs appear in `ESpec` strings.
+pub(super) fn encrypt_chunk(
+    data: &[u8],
+    key_name: u64,
+    iv: &[u8],
+    encryption_type: u8,
+    key: &[u8; 16],
+    block_index: usize,
+) -> BlteResult<Vec<u8>> {
+    let iv_size = u8::try_from(iv.len())
+        .map_err(|_| BlteError::CompressionError(format!("IV too long: {} bytes", iv.len())))?;
+
     // Encrypt the data based on encryption type
-    let encrypted_data = match spec.encryption_type {
+    let encrypted_data = match encryption_type {
         0x53 => {
             // Salsa20 encryption
-            encrypt_salsa20(data, key, &spec.iv, block_index).map_err(|e| {
+            encrypt_salsa20(data, key, iv, block_index).map_err(|e| {
                 BlteError::CompressionError(format!("Salsa20 encryption failed: {e}"))
             })?
         }
@@ -712,29 +949,26 @@ pub fn encrypt_chunk_with_key(
         }
         _ => {
             return Err(BlteError::CompressionError(format!(
-                "Unknown encryption type: 0x{:02X}",
-                spec.encryption_type
+                "Unknown encryption type: 0x{encryption_type:02X}"
             )));
         }
     };
 
     // Build encrypted chunk header
-    let mut result = Vec::new();
+    let mut result = Vec::with_capacity(11 + iv.len() + encrypted_data.len());
 
     // Key name size (always 8 for 64-bit keys)
     result.push(8);
 
     // Key name (64-bit little-endian)
-    result.extend_from_slice(&spec.key_name.to_le_bytes());
-
-    // IV size (always 4)
-    result.push(4);
+    result.extend_from_slice(&key_name.to_le_bytes());
 
-    // IV (4 bytes)
-    result.extend_from_slice(&spec.iv);
+    // IV size and IV
+    result.push(iv_size);
+    result.extend_from_slice(iv);
 
     // Encryption type
-    result.push(spec.encryption_type);
+    result.push(encryption_type);
 
     // Encrypted data
     result.extend_from_slice(&encrypted_data);
@@ -850,8 +1084,11 @@ pub fn decrypt_chunk_with_keys(
     };
 
     // Check if decrypted data has a compression mode marker
+    // Mode 'S' is not a Blizzard mode, so data starting with 'S' is only
+    // treated as zstd when a zstd frame follows
     if !decrypted_data.is_empty()
         && let Some(inner_mode) = CompressionMode::from_byte(decrypted_data[0])
+        && (inner_mode != CompressionMode::ZStd || decrypted_data[1..].starts_with(&ZSTD_MAGIC))
     {
         // Nested encryption (E inside E) is not valid
         if inner_mode == CompressionMode::Encrypted {
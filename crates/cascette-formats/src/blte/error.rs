@@ -48,6 +48,15 @@ pub enum BlteError {
     #[error("unsupported compression mode: 0x{0:02X}")]
     UnsupportedCompressionMode(u8),
 
+    /// Compression mode needs a cargo feature that was not compiled in
+    #[error("compression mode 0x{mode:02X} requires the `{feature}` feature")]
+    FeatureDisabled {
+        /// Mode byte
+        mode: u8,
+        /// Cargo feature of this crate that implements the mode
+        feature: &'static str,
+    },
+
     /// Checksum mismatch
     #[error("checksum mismatch: expected {expected}, got {actual}")]
     ChecksumMismatch {
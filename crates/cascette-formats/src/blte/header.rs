@@ -48,6 +48,20 @@ impl HeaderFlags {
     }
 }
 
+/// On-disk layout of a BLTE file
+///
+/// Recorded from a parsed header with [`BlteHeader::layout`] so the file can
+/// be rebuilt in the same form with
+/// [`BlteBuilder::with_layout`](super::BlteBuilder::with_layout).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlteLayout {
+    /// Header size 0: no chunk table, one implicit chunk follows the
+    /// 8-byte preamble
+    Chunkless,
+    /// Chunk table using the given flags byte
+    ChunkTable(HeaderFlags),
+}
+
 /// BLTE file header
 #[derive(Debug, Clone)]
 pub struct BlteHeader {
@@ -143,6 +157,19 @@ impl BlteHeader {
         Self::multi_chunk_with_flags(chunks, HeaderFlags::Extended)
     }
 
+    /// Create a header laying `chunks` out as `layout` describes
+    ///
+    /// The chunkless layout holds exactly one chunk.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn for_layout(chunks: &[ChunkData], layout: BlteLayout) -> BlteResult<Self> {
+        match layout {
+            BlteLayout::Chunkless if chunks.len() == 1 => Ok(Self::single_chunk()),
+            // Safe cast: any count that fits in memory is reported, not used
+            BlteLayout::Chunkless => Err(BlteError::InvalidChunkCount(chunks.len() as u32)),
+            BlteLayout::ChunkTable(flags) => Self::multi_chunk_with_flags(chunks, flags),
+        }
+    }
+
     /// Create a header for a multi-chunk file with specified flags
     #[allow(clippy::cast_possible_truncation)]
     fn multi_chunk_with_flags(chunks: &[ChunkData], flags: HeaderFlags) -> BlteResult<Self> {
@@ -196,6 +223,15 @@ impl BlteHeader {
         self.header_size == 0
     }
 
+    /// Layout of this header, for rebuilding the file in the same form
+    pub fn layout(&self) -> BlteLayout {
+        self.extended
+            .as_ref()
+            .map_or(BlteLayout::Chunkless, |extended| {
+                BlteLayout::ChunkTable(extended.flags)
+            })
+    }
+
     /// Get the data offset (where chunk data starts)
     pub fn data_offset(&self) -> usize {
         if self.is_single_chunk() {
@@ -223,7 +259,8 @@ impl BlteHeader {
 #[allow(clippy::cast_possible_truncation)]
 pub struct ExtendedHeader {
     /// Flags indicating chunk info format
-    #[br(map = |x: u8| HeaderFlags::from_byte(x).expect("valid header flags byte"))]
+    #[br(try_map = |x: u8| HeaderFlags::from_byte(x)
+        .ok_or_else(|| BlteError::InvalidHeader(format!("unknown header flags byte 0x{x:02X}"))))]
     pub flags: HeaderFlags,
 
     /// 24-bit chunk count (big-endian)
@@ -406,6 +443,43 @@ mod tests {
         assert_eq!(HeaderFlags::Extended.chunk_info_size(), 40);
     }
 
+    #[test]
+    fn test_unknown_header_flags_rejected() {
+        let mut data = Vec::from(BLTE_MAGIC);
+        data.extend_from_slice(&36u32.to_be_bytes());
+        data.extend_from_slice(&[0x11, 0, 0, 1]);
+        data.resize(36, 0);
+
+        let result =
+            BlteHeader::read_options(&mut std::io::Cursor::new(&data), binrw::Endian::Big, ());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_header_layout() {
+        use super::super::chunk::{ChunkData, CompressionMode};
+
+        let chunks = vec![
+            ChunkData::new(vec![1, 2, 3, 4], CompressionMode::None)
+                .expect("Test operation should succeed"),
+        ];
+        for layout in [
+            BlteLayout::Chunkless,
+            BlteLayout::ChunkTable(HeaderFlags::Standard),
+            BlteLayout::ChunkTable(HeaderFlags::Extended),
+        ] {
+            let header =
+                BlteHeader::for_layout(&chunks, layout).expect("Test operation should succeed");
+            assert_eq!(header.layout(), layout);
+        }
+
+        let two = vec![chunks[0].clone(), chunks[0].clone()];
+        assert!(matches!(
+            BlteHeader::for_layout(&two, BlteLayout::Chunkless),
+            Err(BlteError::InvalidChunkCount(2))
+        ));
+    }
+
     #[test]
     fn test_single_chunk_header() {
         let header = BlteHeader::single_chunk();
@@ -8,7 +8,8 @@
 //!
 //! - Parser and builder for all BLTE modes
 //! - Support for single and multi-chunk files
-//! - Compression modes: None, `ZLib`, LZ4
+//! - Compression modes: None, `ZLib`, LZ4, and zstd behind the `zstd` feature
+//! - Per-chunk compression mode selection ([`auto_select_compression_mode`])
 //! - Encryption support: Salsa20, ARC4
 //! - Round-trip validation
 
@@ -22,12 +23,12 @@ mod header;
 pub use builder::BlteBuilder;
 pub use chunk::{ChunkData, CompressionMode};
 pub use compression::{
-    EncryptionSpec, compress_chunk, decompress_chunk, decrypt_chunk_with_keys,
-    encrypt_chunk_with_key,
+    CompressionStrategy, EncryptionSpec, auto_select_compression_mode, compress_chunk,
+    compress_zlib, decompress_chunk, decrypt_chunk_with_keys, encrypt_chunk_with_key,
 };
 pub use encryption::{EncryptedHeader, EncryptionType};
 pub use error::{BlteError, BlteResult};
-pub use header::{BlteHeader, ChunkInfo, HeaderFlags};
+pub use header::{BlteHeader, BlteLayout, ChunkInfo, HeaderFlags};
 
 use binrw::io::{Read, Seek, SeekFrom, Write};
 use binrw::{BinRead, BinResult, BinWrite};
@@ -76,7 +77,12 @@ impl BinRead for BlteFile {
                 for info in &extended.chunk_infos {
                     let chunk =
                         ChunkData::read_options(reader, endian, (info.compressed_size as usize,))?;
-                    chunks.push(chunk);
+                    // Keep the table's size so a rebuilt table matches
+                    chunks.push(ChunkData::from_compressed(
+                        chunk.mode,
+                        chunk.data,
+                        Some(info.decompressed_size as usize),
+                    ));
                 }
             }
         }
@@ -161,13 +167,7 @@ impl BlteFile {
         let mut result = Vec::with_capacity(total_size);
 
         for (index, chunk) in self.chunks.iter().enumerate() {
-            let decompressed = if chunk.mode == CompressionMode::Encrypted {
-                // Decrypt encrypted chunks
-                decrypt_chunk_with_keys(&chunk.data, key_store, index)?
-            } else {
-                // Regular decompression for non-encrypted chunks
-                chunk.decompress(index)?
-            };
+            let decompressed = chunk.decompress_with_keys(index, key_store)?;
             result.extend_from_slice(&decompressed);
         }
         Ok(result)
@@ -190,6 +190,32 @@ impl BlteFile {
         self.chunks.iter().map(|c| c.decompressed_size()).sum()
     }
 
+    /// Compress data with automatic chunking, picking a mode per chunk
+    ///
+    /// Each chunk's mode comes from [`auto_select_compression_mode`], so
+    /// a file can mix stored, zlib and zstd chunks.
+    pub fn compress_with_strategy(
+        data: &[u8],
+        chunk_size: usize,
+        strategy: &CompressionStrategy,
+    ) -> BlteResult<Self> {
+        if data.len() <= chunk_size {
+            let mode = auto_select_compression_mode(data, strategy);
+            return Self::single_chunk(data.to_vec(), mode);
+        }
+
+        let chunks = data
+            .chunks(chunk_size)
+            .map(|chunk| {
+                ChunkData::new(
+                    chunk.to_vec(),
+                    auto_select_compression_mode(chunk, strategy),
+                )
+            })
+            .collect::<BlteResult<Vec<_>>>()?;
+        Self::multi_chunk(chunks)
+    }
+
     /// Compress data with automatic chunking
     pub fn compress(data: &[u8], chunk_size: usize, mode: CompressionMode) -> BlteResult<Self> {
         if data.len() <= chunk_size {
@@ -261,6 +287,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_compress_with_strategy_mixes_modes() {
+        let text = b"Interface\\AddOns\\Blizzard_Auto\\Auto.lua\n".repeat(8 * 1024);
+        let mut state = 1u32;
+        let noise: Vec<u8> = (0..64 * 1024)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 24) as u8
+            })
+            .collect();
+        let chunk_size = 256 * 1024;
+        let mut data = text[..chunk_size].to_vec();
+        data.extend_from_slice(&noise);
+        data.extend_from_slice(&text[..1024]);
+
+        let strategy = CompressionStrategy {
+            zstd_min_size: chunk_size,
+            ..CompressionStrategy::default()
+        }
+        .with_zstd();
+        let blte = BlteFile::compress_with_strategy(&data, chunk_size, &strategy)
+            .expect("Test operation should succeed");
+
+        let modes: Vec<_> = blte.chunks.iter().map(|chunk| chunk.mode).collect();
+        let large_mode = if cfg!(feature = "zstd") {
+            CompressionMode::ZStd
+        } else {
+            CompressionMode::ZLib
+        };
+        assert_eq!(modes[0], large_mode);
+        assert_eq!(modes[1], CompressionMode::None);
+
+        let built = blte.build().expect("Build should succeed");
+        let parsed = BlteFile::parse(&built).expect("Parse should succeed");
+        let decompressed = parsed.decompress().expect("Decompress should succeed");
+        assert_eq!(decompressed, data);
+    }
+
     #[test]
     fn test_single_chunk_round_trip() {
         let data = b"Hello, BLTE!";
@@ -285,11 +349,15 @@ mod tests {
 
         /// Generate arbitrary compression modes (excluding deprecated Frame mode)
         fn compression_mode() -> impl Strategy<Value = CompressionMode> {
-            prop_oneof![
-                Just(CompressionMode::None),
-                Just(CompressionMode::ZLib),
-                Just(CompressionMode::LZ4),
-            ]
+            let mut modes = vec![
+                CompressionMode::None,
+                CompressionMode::ZLib,
+                CompressionMode::LZ4,
+            ];
+            if cfg!(feature = "zstd") {
+                modes.push(CompressionMode::ZStd);
+            }
+            prop::sample::select(modes)
         }
 
         /// Generate arbitrary data chunks (reasonable sizes for testing)
@@ -376,7 +444,7 @@ mod tests {
                     fn invalid_compression_modes_rejected(
                         invalid_mode in any::<u8>().prop_filter(
                             "Not a valid compression mode",
-                            |&b| !matches!(b, b'N' | b'Z' | b'4' | b'E' | b'F')
+                            |&b| !matches!(b, b'N' | b'Z' | b'4' | b'E' | b'F' | b'S')
                         )
                     ) {
                         prop_assert!(CompressionMode::from_byte(invalid_mode).is_none());
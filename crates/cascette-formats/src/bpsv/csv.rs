@@ -0,0 +1,282 @@
+//! CSV conversion for spreadsheet tools
+//!
+//! [`BpsvDocument::to_csv`] writes RFC 4180 CSV with a header row of field
+//! names, and [`BpsvDocument::from_csv`] reads it back. CSV carries neither
+//! field types nor the sequence number, so reading needs the schema and the
+//! sequence number is not preserved.
+
+use crate::bpsv::document::BpsvDocument;
+use crate::bpsv::schema::BpsvSchema;
+use crate::bpsv::types::BpsvError;
+
+impl BpsvDocument {
+    /// Format the document as CSV
+    ///
+    /// The header row holds the field names and each data row the raw
+    /// values, so hex values keep their case. Values containing commas,
+    /// quotes or line breaks are quoted, and lines end with CRLF.
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut output = String::new();
+        let names = self.schema().fields().iter().map(|f| f.name.as_str());
+        write_record(&mut output, names);
+        for row in self.rows() {
+            write_record(&mut output, row.raw_values().iter().map(String::as_str));
+        }
+        output
+    }
+
+    /// Parse CSV into a document with `schema`
+    ///
+    /// The header row must list the schema's field names in order. Each
+    /// cell is converted to its field's type, with empty cells becoming
+    /// [`BpsvValue::Empty`](crate::bpsv::BpsvValue::Empty). Lines may end
+    /// with CRLF or LF, blank lines are skipped and a leading UTF-8 byte
+    /// order mark is ignored.
+    pub fn from_csv(input: &str, schema: &BpsvSchema) -> Result<Self, BpsvError> {
+        let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+        let mut records = parse_records(input)?.into_iter();
+
+        let header = records.next().ok_or(BpsvError::MissingHeader)?;
+        if !header.iter().map(String::as_str).eq(schema.field_names()) {
+            return Err(BpsvError::InvalidHeader(format!(
+                "CSV columns {} do not match schema fields {}",
+                header.join(","),
+                schema.field_names().join(",")
+            )));
+        }
+
+        let mut document = Self::new(schema.clone());
+        for record in records {
+            document.add_raw_row(record)?;
+        }
+        Ok(document)
+    }
+}
+
+/// Append one CSV record to `output`.
+fn write_record<'a>(output: &mut String, fields: impl ExactSizeIterator<Item = &'a str>) {
+    // A lone empty field would read back as a blank line, so quote it
+    let quote_empty = fields.len() == 1;
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            output.push(',');
+        }
+        if field.contains([',', '"', '\r', '\n']) || (quote_empty && field.is_empty()) {
+            output.push('"');
+            output.push_str(&field.replace('"', "\"\""));
+            output.push('"');
+        } else {
+            output.push_str(field);
+        }
+    }
+    output.push_str("\r\n");
+}
+
+/// Split CSV input into records of unquoted fields.
+fn parse_records(input: &str) -> Result<Vec<Vec<String>>, BpsvError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut line = 1;
+    // Inside a quoted field, and the line it started on
+    let mut quoted_since = None;
+    // The current field was quoted and its closing quote has been read
+    let mut closed = false;
+
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            line += 1;
+        }
+
+        if quoted_since.is_some() {
+            if c == '"' {
+                if chars.next_if_eq(&'"').is_some() {
+                    field.push('"');
+                } else {
+                    quoted_since = None;
+                    closed = true;
+                }
+            } else {
+                field.push(c);
+            }
+            continue;
+        }
+
+        match c {
+            ',' => {
+                record.push(std::mem::take(&mut field));
+                closed = false;
+            }
+            '\r' if matches!(chars.peek(), Some('\n') | None) => {}
+            '\n' => {
+                // Blank lines hold no record
+                if !record.is_empty() || !field.is_empty() || closed {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                }
+                closed = false;
+            }
+            '"' if field.is_empty() && !closed => quoted_since = Some(line),
+            _ if closed => {
+                return Err(BpsvError::InvalidCsv(format!(
+                    "line {line}: unexpected {c:?} after closing quote"
+                )));
+            }
+            '"' => {
+                return Err(BpsvError::InvalidCsv(format!(
+                    "line {line}: quote inside unquoted field"
+                )));
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if let Some(start) = quoted_since {
+        return Err(BpsvError::InvalidCsv(format!(
+            "line {start}: unterminated quoted field"
+        )));
+    }
+    if !record.is_empty() || !field.is_empty() || closed {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::bpsv::types::{BpsvField, BpsvType, BpsvValue};
+
+    fn create_test_schema() -> BpsvSchema {
+        BpsvSchema::new(vec![
+            BpsvField::new("Region", BpsvType::String(0)),
+            BpsvField::new("BuildConfig", BpsvType::Hex(16)),
+            BpsvField::new("BuildId", BpsvType::Dec(4)),
+            BpsvField::new("VersionsName", BpsvType::String(0)),
+        ])
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let schema = create_test_schema();
+        let mut doc = BpsvDocument::new(schema.clone());
+        for row in [
+            [
+                "us",
+                "be2bb98dc28aee05bbee519393696cdb",
+                "61491",
+                "11.1.7.61491",
+            ],
+            ["eu", "", "-1", "a, \"quoted\"\r\nname"],
+            ["", "00ff", "", ""],
+        ] {
+            doc.add_raw_row(row.iter().map(ToString::to_string).collect())
+                .expect("Test operation should succeed");
+        }
+
+        let csv = doc.to_csv();
+        assert!(csv.starts_with("Region,BuildConfig,BuildId,VersionsName\r\n"));
+        assert!(csv.contains("eu,,-1,\"a, \"\"quoted\"\"\r\nname\"\r\n"));
+
+        let parsed = BpsvDocument::from_csv(&csv, &schema).expect("Test operation should succeed");
+        assert_eq!(parsed.row_count(), 3);
+        for (original, parsed) in doc.rows().iter().zip(parsed.rows()) {
+            assert_eq!(original.raw_values(), parsed.raw_values());
+            assert_eq!(original.values(), parsed.values());
+        }
+        assert_eq!(parsed.to_csv(), csv);
+    }
+
+    #[test]
+    fn test_csv_converts_hex_and_dec_cells() {
+        let schema = create_test_schema();
+        let csv = "Region,BuildConfig,BuildId,VersionsName\nus,ABCD1234,-42,\"1,2\"\n";
+        let doc = BpsvDocument::from_csv(csv, &schema).expect("Test operation should succeed");
+
+        let row = doc.get_row(0).expect("Operation should succeed");
+        assert_eq!(
+            row.get_by_name("BuildConfig", &schema),
+            Some(&BpsvValue::Hex(vec![0xab, 0xcd, 0x12, 0x34]))
+        );
+        assert_eq!(
+            row.get_by_name("BuildId", &schema),
+            Some(&BpsvValue::Dec(-42))
+        );
+        assert_eq!(
+            row.get_by_name("VersionsName", &schema),
+            Some(&BpsvValue::String("1,2".to_string()))
+        );
+
+        let header = "Region,BuildConfig,BuildId,VersionsName\n";
+        assert!(matches!(
+            BpsvDocument::from_csv(&format!("{header}us,abc,1,x\n"), &schema),
+            Err(BpsvError::InvalidHexLength(_))
+        ));
+        assert!(matches!(
+            BpsvDocument::from_csv(&format!("{header}us,zz,1,x\n"), &schema),
+            Err(BpsvError::InvalidHexValue(_))
+        ));
+        assert!(matches!(
+            BpsvDocument::from_csv(&format!("{header}us,ab,1.5,x\n"), &schema),
+            Err(BpsvError::InvalidDecValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_csv_input_variants() {
+        let schema = BpsvSchema::new(vec![BpsvField::new("Name", BpsvType::String(0))]);
+
+        // BOM, CRLF, blank lines and no trailing newline
+        let doc = BpsvDocument::from_csv("\u{feff}Name\r\nfirst\r\n\r\n\"\"\nlast", &schema)
+            .expect("Test operation should succeed");
+        let names: Vec<&str> = doc.iter().filter_map(|row| row.get_raw(0)).collect();
+        assert_eq!(names, ["first", "", "last"]);
+
+        // A lone empty value survives the round trip
+        let reparsed =
+            BpsvDocument::from_csv(&doc.to_csv(), &schema).expect("Test operation should succeed");
+        assert_eq!(reparsed.row_count(), 3);
+        assert!(doc.to_csv().contains("\r\n\"\"\r\n"));
+    }
+
+    #[test]
+    fn test_csv_errors() {
+        let schema = create_test_schema();
+        assert!(matches!(
+            BpsvDocument::from_csv("", &schema),
+            Err(BpsvError::MissingHeader)
+        ));
+        assert!(matches!(
+            BpsvDocument::from_csv("Region,BuildId\n", &schema),
+            Err(BpsvError::InvalidHeader(_))
+        ));
+
+        let header = "Region,BuildConfig,BuildId,VersionsName\n";
+        assert!(matches!(
+            BpsvDocument::from_csv(&format!("{header}us,,1\n"), &schema),
+            Err(BpsvError::FieldCountMismatch {
+                expected: 4,
+                actual: 3
+            })
+        ));
+
+        let err = BpsvDocument::from_csv(&format!("{header}us,,1,\"open\n"), &schema)
+            .expect_err("Unterminated quote should fail");
+        assert_eq!(
+            err.to_string(),
+            "Invalid CSV: line 2: unterminated quoted field"
+        );
+        assert!(matches!(
+            BpsvDocument::from_csv(&format!("{header}us,,1,\"x\"y\n"), &schema),
+            Err(BpsvError::InvalidCsv(_))
+        ));
+        assert!(matches!(
+            BpsvDocument::from_csv(&format!("{header}us,,1,x\"y\n"), &schema),
+            Err(BpsvError::InvalidCsv(_))
+        ));
+    }
+}
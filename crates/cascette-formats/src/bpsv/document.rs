@@ -1,6 +1,7 @@
 use crate::bpsv::row::BpsvRow;
 use crate::bpsv::schema::BpsvSchema;
-use crate::bpsv::types::BpsvError;
+use crate::bpsv::sequence::SequenceNumber;
+use crate::bpsv::types::{BpsvError, BpsvWarning};
 use std::fmt;
 
 /// A complete BPSV document with schema and data rows
@@ -10,8 +11,10 @@ pub struct BpsvDocument {
     schema: BpsvSchema,
     /// Data rows
     rows: Vec<BpsvRow>,
-    /// Optional sequence number from "## seqn = N" line
-    sequence_number: Option<u32>,
+    /// Sequence number from the "## seqn = N" line
+    sequence: SequenceNumber,
+    /// Problems recovered from while parsing leniently
+    warnings: Vec<BpsvWarning>,
 }
 
 impl BpsvDocument {
@@ -21,7 +24,8 @@ impl BpsvDocument {
         Self {
             schema,
             rows: Vec::new(),
-            sequence_number: None,
+            sequence: SequenceNumber::Absent,
+            warnings: Vec::new(),
         }
     }
 
@@ -31,7 +35,8 @@ impl BpsvDocument {
         Self {
             schema,
             rows,
-            sequence_number: None,
+            sequence: SequenceNumber::Absent,
+            warnings: Vec::new(),
         }
     }
 
@@ -85,19 +90,44 @@ impl BpsvDocument {
     }
 
     /// Get the sequence number if present
+    ///
+    /// `None` if the document has no seqn line or, when parsed leniently, a
+    /// malformed one; see [`Self::sequence`] to tell these apart.
     #[must_use]
-    pub fn sequence_number(&self) -> Option<u32> {
-        self.sequence_number
+    pub const fn sequence_number(&self) -> Option<u64> {
+        self.sequence.value()
     }
 
-    /// Set the sequence number
-    pub fn set_sequence_number(&mut self, seqn: u32) {
-        self.sequence_number = Some(seqn);
+    /// Get the sequence number line as parsed
+    #[must_use]
+    pub const fn sequence(&self) -> &SequenceNumber {
+        &self.sequence
+    }
+
+    /// Set the sequence number, written as `## seqn = N`
+    pub fn set_sequence_number(&mut self, seqn: u64) {
+        self.sequence = SequenceNumber::new(seqn);
+    }
+
+    /// Replace the sequence number line, keeping its formatting
+    pub fn set_sequence(&mut self, sequence: SequenceNumber) {
+        self.sequence = sequence;
     }
 
     /// Clear the sequence number
     pub fn clear_sequence_number(&mut self) {
-        self.sequence_number = None;
+        self.sequence = SequenceNumber::Absent;
+    }
+
+    /// Problems recovered from while parsing leniently
+    #[must_use]
+    pub fn warnings(&self) -> &[BpsvWarning] {
+        &self.warnings
+    }
+
+    /// Record a problem recovered from while parsing
+    pub(crate) fn push_warning(&mut self, warning: BpsvWarning) {
+        self.warnings.push(warning);
     }
 
     /// Check if document has a field with given name
@@ -129,8 +159,8 @@ impl fmt::Display for BpsvDocument {
         writeln!(f, "{}", self.schema.to_header())?;
 
         // Write sequence number if present
-        if let Some(seqn) = self.sequence_number {
-            writeln!(f, "## seqn = {seqn}")?;
+        if let Some(line) = self.sequence.to_line() {
+            writeln!(f, "{line}")?;
         }
 
         // Write data rows
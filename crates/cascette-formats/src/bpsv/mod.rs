@@ -7,9 +7,12 @@
 //!
 //! BPSV files consist of:
 //! - A header line with field definitions (name!type:size)
-//! - An optional sequence number line (## seqn = N)
+//! - An optional sequence number line (## seqn = N), see [`SequenceNumber`]
 //! - Data rows with pipe-separated values
 //!
+//! Documents convert to and from CSV with [`BpsvDocument::to_csv`] and
+//! [`BpsvDocument::from_csv`] for use in spreadsheet tools.
+//!
 //! # Example
 //!
 //! ```
@@ -39,10 +42,12 @@
 //! assert!(output.contains("## seqn = 99999"));
 //! ```
 
+mod csv;
 mod document;
 mod reader;
 mod row;
 mod schema;
+mod sequence;
 mod types;
 mod writer;
 
@@ -51,10 +56,11 @@ mod writer;
 
 // Re-export main types
 pub use document::BpsvDocument;
-pub use reader::{BpsvReader, parse, parse_schema};
+pub use reader::{BpsvReader, ParseOptions, parse, parse_schema, parse_with_options};
 pub use row::BpsvRow;
 pub use schema::BpsvSchema;
-pub use types::{BpsvError, BpsvField, BpsvType, BpsvValue};
+pub use sequence::SequenceNumber;
+pub use types::{BpsvError, BpsvField, BpsvType, BpsvValue, BpsvWarning};
 pub use writer::{BpsvBuilder, BpsvWriter, format, write_to_file};
 
 // #[cfg(feature = "serde")]
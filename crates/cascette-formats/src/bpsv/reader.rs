@@ -1,18 +1,48 @@
 use crate::bpsv::document::BpsvDocument;
 use crate::bpsv::schema::BpsvSchema;
-use crate::bpsv::types::BpsvError;
+use crate::bpsv::sequence::SequenceNumber;
+use crate::bpsv::types::{BpsvError, BpsvWarning};
 use std::io::{BufRead, BufReader, Read};
 
+/// Options controlling how strictly documents are parsed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Record recoverable problems as [`BpsvWarning`]s on the document
+    /// instead of failing. Currently covers malformed `## seqn` lines.
+    pub lenient: bool,
+}
+
+impl ParseOptions {
+    /// Fail on any malformed line (the default)
+    #[must_use]
+    pub const fn strict() -> Self {
+        Self { lenient: false }
+    }
+
+    /// Recover from malformed lines where the rest of the document is usable
+    #[must_use]
+    pub const fn lenient() -> Self {
+        Self { lenient: true }
+    }
+}
+
 /// BPSV document reader
 pub struct BpsvReader<R> {
     reader: BufReader<R>,
+    options: ParseOptions,
 }
 
 impl<R: Read> BpsvReader<R> {
     /// Create a new reader from any `Read` source
     pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParseOptions::default())
+    }
+
+    /// Create a reader with parse options
+    pub fn with_options(reader: R, options: ParseOptions) -> Self {
         Self {
             reader: BufReader::new(reader),
+            options,
         }
     }
 
@@ -51,11 +81,21 @@ impl<R: Read> BpsvReader<R> {
                 continue;
             }
 
-            // Check for sequence number
+            // Check for sequence number, keeping the line for round trips
             if trimmed.starts_with("## seqn") {
-                let seqn = parse_sequence_line(trimmed)?;
-                if let Some(n) = seqn {
-                    document.set_sequence_number(n);
+                match parse_sequence_line(trimmed) {
+                    Ok(seqn) => document.set_sequence(match seqn {
+                        Some(value) => SequenceNumber::Value {
+                            value,
+                            line: Some(trimmed.to_string()),
+                        },
+                        None => SequenceNumber::Absent,
+                    }),
+                    Err(BpsvError::InvalidSequenceNumber(line)) if self.options.lenient => {
+                        document.set_sequence(SequenceNumber::Malformed { line: line.clone() });
+                        document.push_warning(BpsvWarning::InvalidSequenceNumber(line));
+                    }
+                    Err(e) => return Err(e),
                 }
                 continue;
             }
@@ -119,6 +159,12 @@ pub fn parse(content: &str) -> Result<BpsvDocument, BpsvError> {
     reader.read_document()
 }
 
+/// Parse a BPSV document from a string with parse options
+pub fn parse_with_options(content: &str, options: ParseOptions) -> Result<BpsvDocument, BpsvError> {
+    let mut reader = BpsvReader::with_options(content.as_bytes(), options);
+    reader.read_document()
+}
+
 /// Parse only the schema from a string
 pub fn parse_schema(content: &str) -> Result<BpsvSchema, BpsvError> {
     let mut reader = BpsvReader::from_bytes(content.as_bytes());
@@ -126,7 +172,7 @@ pub fn parse_schema(content: &str) -> Result<BpsvSchema, BpsvError> {
 }
 
 /// Parse a sequence number line
-fn parse_sequence_line(line: &str) -> Result<Option<u32>, BpsvError> {
+fn parse_sequence_line(line: &str) -> Result<Option<u64>, BpsvError> {
     // Handle "## seqn = 12345" format and variations
     let after_seqn = line
         .strip_prefix("## seqn")
@@ -147,7 +193,7 @@ fn parse_sequence_line(line: &str) -> Result<Option<u32>, BpsvError> {
     };
 
     let seqn = number_str
-        .parse::<u32>()
+        .parse::<u64>()
         .map_err(|_| BpsvError::InvalidSequenceNumber(line.to_string()))?;
 
     Ok(Some(seqn))
@@ -264,6 +310,68 @@ cn|9999";
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_sequence_number_edge_values() {
+        let doc = parse("A!DEC:4\n## seqn = 0\n1").expect("seqn 0 should parse");
+        assert_eq!(doc.sequence_number(), Some(0));
+        assert!(doc.sequence().is_present());
+
+        let doc = parse("A!DEC:4\n1").expect("document without seqn should parse");
+        assert_eq!(doc.sequence(), &SequenceNumber::Absent);
+
+        let large = u64::from(u32::MAX) + 1;
+        let doc = parse(&format!("A!DEC:4\n## seqn = {large}\n1")).expect("u64 seqn");
+        assert_eq!(doc.sequence_number(), Some(large));
+
+        let line = format!("## seqn = {}0", u64::MAX);
+        assert!(parse(&format!("A!DEC:4\n{line}\n1")).is_err());
+    }
+
+    #[test]
+    fn test_lenient_malformed_sequence() {
+        let content = "A!DEC:4\n## seqn = 12x\n1\n2";
+
+        assert!(matches!(
+            parse(content),
+            Err(BpsvError::InvalidSequenceNumber(_))
+        ));
+
+        let doc = parse_with_options(content, ParseOptions::lenient())
+            .expect("lenient parse should recover");
+        assert_eq!(doc.row_count(), 2);
+        assert_eq!(doc.sequence_number(), None);
+        assert_eq!(
+            doc.sequence(),
+            &SequenceNumber::Malformed {
+                line: "## seqn = 12x".to_string()
+            }
+        );
+        assert_eq!(
+            doc.warnings(),
+            &[BpsvWarning::InvalidSequenceNumber(
+                "## seqn = 12x".to_string()
+            )]
+        );
+
+        // Strict parsing of a well-formed document records no warnings
+        let doc = parse("A!DEC:4\n## seqn = 1\n1").expect("parse");
+        assert!(doc.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_sequence_line_round_trip() {
+        for line in ["## seqn = 12345", "## seqn: 0", "## seqn   =   4294967296"] {
+            let content = format!("A!DEC:4\n{line}\n1\n");
+            let doc = parse(&content).expect("parse");
+            assert_eq!(doc.to_string(), content);
+        }
+
+        // Replacing the value switches to the canonical form
+        let mut doc = parse("A!DEC:4\n## seqn: 5\n1").expect("parse");
+        doc.set_sequence_number(6);
+        assert_eq!(doc.to_string(), "A!DEC:4\n## seqn = 6\n1\n");
+    }
+
     #[test]
     fn test_parse_schema_only() {
         let content = "Region!STRING:0|BuildConfig!HEX:16|BuildId!DEC:4
@@ -0,0 +1,119 @@
+use std::borrow::Cow;
+
+/// Sequence number of a BPSV document, from its `## seqn` line
+///
+/// Absent and zero are distinct: some TACT HTTP responses carry no seqn
+/// line at all, while some products publish seqn 0. Values are `u64`
+/// because timestamp-derived sequence numbers exceed `u32::MAX`.
+///
+/// Parsed lines are kept verbatim so a document writes back the seqn line
+/// it was read with, including its spacing and separator.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum SequenceNumber {
+    /// The document has no seqn line
+    #[default]
+    Absent,
+    /// A seqn line with a value
+    Value {
+        /// Sequence number
+        value: u64,
+        /// Original line, or `None` to write `## seqn = N`
+        line: Option<String>,
+    },
+    /// A seqn line whose value did not parse (lenient parsing only)
+    Malformed {
+        /// Original line
+        line: String,
+    },
+}
+
+impl SequenceNumber {
+    /// A sequence number written in the canonical `## seqn = N` form
+    #[must_use]
+    pub const fn new(value: u64) -> Self {
+        Self::Value { value, line: None }
+    }
+
+    /// The numeric value, if the document has a well-formed seqn line
+    #[must_use]
+    pub const fn value(&self) -> Option<u64> {
+        match self {
+            Self::Value { value, .. } => Some(*value),
+            Self::Absent | Self::Malformed { .. } => None,
+        }
+    }
+
+    /// Check whether the document has a seqn line, well-formed or not
+    #[must_use]
+    pub const fn is_present(&self) -> bool {
+        !matches!(self, Self::Absent)
+    }
+
+    /// The seqn line to write, without line ending
+    #[must_use]
+    pub fn to_line(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Absent => None,
+            Self::Value {
+                line: Some(line), ..
+            }
+            | Self::Malformed { line } => Some(Cow::Borrowed(line)),
+            Self::Value { value, line: None } => Some(Cow::Owned(format!("## seqn = {value}"))),
+        }
+    }
+}
+
+impl From<u64> for SequenceNumber {
+    fn from(value: u64) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<Option<u64>> for SequenceNumber {
+    fn from(value: Option<u64>) -> Self {
+        value.map_or(Self::Absent, Self::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_absent_and_zero_differ() {
+        let absent = SequenceNumber::Absent;
+        let zero = SequenceNumber::new(0);
+
+        assert_ne!(absent, zero);
+        assert_eq!(absent.value(), None);
+        assert_eq!(zero.value(), Some(0));
+        assert!(!absent.is_present());
+        assert!(zero.is_present());
+        assert_eq!(absent.to_line(), None);
+        assert_eq!(zero.to_line().as_deref(), Some("## seqn = 0"));
+    }
+
+    #[test]
+    fn test_line_formatting_is_kept() {
+        let parsed = SequenceNumber::Value {
+            value: 20_251_014_123_045,
+            line: Some("## seqn:20251014123045".to_string()),
+        };
+        assert_eq!(parsed.value(), Some(20_251_014_123_045));
+        assert_eq!(parsed.to_line().as_deref(), Some("## seqn:20251014123045"));
+
+        let malformed = SequenceNumber::Malformed {
+            line: "## seqn = 12x".to_string(),
+        };
+        assert_eq!(malformed.value(), None);
+        assert!(malformed.is_present());
+        assert_eq!(malformed.to_line().as_deref(), Some("## seqn = 12x"));
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(SequenceNumber::from(u64::MAX).value(), Some(u64::MAX));
+        assert_eq!(SequenceNumber::from(None), SequenceNumber::Absent);
+        assert_eq!(SequenceNumber::from(Some(7)), SequenceNumber::new(7));
+    }
+}
@@ -239,6 +239,19 @@ pub enum BpsvError {
     /// Column index is out of bounds
     #[error("Column index out of bounds: {0}")]
     ColumnIndexOutOfBounds(usize),
+
+    /// Malformed CSV input
+    #[error("Invalid CSV: {0}")]
+    InvalidCsv(String),
+}
+
+/// Recoverable problem found while parsing leniently
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BpsvWarning {
+    /// A `## seqn` line with an unparseable value; the document parsed
+    /// without a sequence number
+    #[error("Invalid sequence number: {0}")]
+    InvalidSequenceNumber(String),
 }
 
 #[cfg(test)]
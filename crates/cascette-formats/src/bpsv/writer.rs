@@ -1,6 +1,7 @@
 use crate::bpsv::document::BpsvDocument;
 use crate::bpsv::row::BpsvRow;
 use crate::bpsv::schema::BpsvSchema;
+use crate::bpsv::sequence::SequenceNumber;
 use crate::bpsv::types::{BpsvError, BpsvField, BpsvValue};
 use std::io::{BufWriter, Write};
 
@@ -23,8 +24,8 @@ impl<W: Write> BpsvWriter<W> {
         writeln!(self.writer, "{}", document.schema().to_header())?;
 
         // Write sequence number if present
-        if let Some(seqn) = document.sequence_number() {
-            writeln!(self.writer, "## seqn = {seqn}")?;
+        if let Some(line) = document.sequence().to_line() {
+            writeln!(self.writer, "{line}")?;
         }
 
         // Write data rows
@@ -51,7 +52,7 @@ impl<W: Write> BpsvWriter<W> {
     }
 
     /// Write a sequence number line
-    pub fn write_sequence(&mut self, seqn: u32) -> Result<(), std::io::Error> {
+    pub fn write_sequence(&mut self, seqn: u64) -> Result<(), std::io::Error> {
         writeln!(self.writer, "## seqn = {seqn}")?;
         self.writer.flush()?;
         Ok(())
@@ -76,7 +77,7 @@ impl<W: Write> BpsvWriter<W> {
 pub struct BpsvBuilder {
     fields: Vec<BpsvField>,
     rows: Vec<Vec<BpsvValue>>,
-    sequence_number: Option<u32>,
+    sequence: SequenceNumber,
 }
 
 impl BpsvBuilder {
@@ -86,7 +87,7 @@ impl BpsvBuilder {
         Self {
             fields: Vec::new(),
             rows: Vec::new(),
-            sequence_number: None,
+            sequence: SequenceNumber::Absent,
         }
     }
 
@@ -103,8 +104,20 @@ impl BpsvBuilder {
     }
 
     /// Set the sequence number
-    pub fn set_sequence(&mut self, seqn: u32) -> &mut Self {
-        self.sequence_number = Some(seqn);
+    ///
+    /// Without a call the document has no seqn line; `0` writes
+    /// `## seqn = 0`.
+    pub fn set_sequence(&mut self, seqn: u64) -> &mut Self {
+        self.sequence = SequenceNumber::new(seqn);
+        self
+    }
+
+    /// Use the sequence number line of another document as is
+    ///
+    /// Keeps an absent, malformed or unusually formatted seqn line when
+    /// rebuilding a parsed document.
+    pub fn preserve_sequence(&mut self, sequence: SequenceNumber) -> &mut Self {
+        self.sequence = sequence;
         self
     }
 
@@ -127,9 +140,7 @@ impl BpsvBuilder {
         let schema = BpsvSchema::new(self.fields);
         let mut document = BpsvDocument::new(schema);
 
-        if let Some(seqn) = self.sequence_number {
-            document.set_sequence_number(seqn);
-        }
+        document.set_sequence(self.sequence);
 
         for row_values in self.rows {
             let row = BpsvRow::from_values(row_values);
@@ -311,4 +322,30 @@ mod tests {
 
         assert!(output.contains("deadbeef|1024"));
     }
+
+    #[test]
+    fn test_builder_sequence_representations() {
+        let build = |configure: &dyn Fn(&mut BpsvBuilder)| {
+            let mut builder = BpsvBuilder::new();
+            builder.add_field(BpsvField::new("A", BpsvType::Dec(4)));
+            configure(&mut builder);
+            format(&builder.build())
+        };
+
+        assert_eq!(build(&|_| {}), "A!DEC:4\n");
+        assert_eq!(
+            build(&|b| {
+                b.set_sequence(0);
+            }),
+            "A!DEC:4\n## seqn = 0\n"
+        );
+
+        let parsed = crate::bpsv::parse("A!DEC:4\n## seqn:7\n").expect("parse");
+        assert_eq!(
+            build(&|b| {
+                b.preserve_sequence(parsed.sequence().clone());
+            }),
+            "A!DEC:4\n## seqn:7\n"
+        );
+    }
 }
@@ -5,7 +5,7 @@
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Read, Write};
 
-use super::{is_valid_md5_hex, parse_line};
+use super::{ConfigValidationError, is_valid_md5_hex, parse_line};
 
 /// Build Configuration containing system file references
 #[derive(Debug, Clone)]
@@ -620,29 +620,37 @@ impl BuildConfig {
     }
 
     /// Validate the configuration
-    pub fn validate(&self) -> Result<(), ValidationError> {
-        // Must have root
-        if !self.entries.contains_key("root") {
-            return Err(ValidationError::MissingRoot);
-        }
-
-        // Must have encoding with two hashes
-        if let Some(encoding) = self.entries.get("encoding") {
-            if encoding.len() < 2 {
-                return Err(ValidationError::InvalidEncoding);
+    ///
+    /// Checks that `root` and `encoding` are present, that `encoding` holds
+    /// exactly two hashes, that `build-key` (where present) and every other
+    /// hash field hold 32-character hex MD5s, and that `build-uid` (where
+    /// present) is a product code: lowercase letters, digits and
+    /// underscores, starting with a letter.
+    ///
+    /// # Errors
+    ///
+    /// Returns every violation found, not just the first.
+    pub fn validate(&self) -> Result<(), Vec<ConfigValidationError>> {
+        let mut errors = Vec::new();
+        let mut check_hash = |key: &str, value: &String| {
+            if !is_valid_md5_hex(value) {
+                errors.push(ConfigValidationError::InvalidHash {
+                    key: key.to_string(),
+                    value: value.clone(),
+                });
             }
-            // Validate hash formats
-            for hash in encoding {
-                if !is_valid_md5_hex(hash) {
-                    return Err(ValidationError::InvalidHash(hash.clone()));
-                }
+        };
+
+        if let Some(values) = self.entries.get("build-key") {
+            for value in values {
+                check_hash("build-key", value);
             }
-        } else {
-            return Err(ValidationError::MissingEncoding);
         }
 
-        // Validate all hash values
-        for (key, values) in &self.entries {
+        // Validate all hash values, in key order so the errors are stable
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_unstable_by_key(|(key, _)| *key);
+        for (key, values) in entries {
             // Skip non-hash fields
             if key.starts_with("build-")
                 || key.starts_with("feature-")
@@ -662,13 +670,35 @@ impl BuildConfig {
                 }
 
                 // Must be valid MD5 hash
-                if !is_valid_md5_hex(value) {
-                    return Err(ValidationError::InvalidHash(value.clone()));
-                }
+                check_hash(key, value);
             }
         }
 
-        Ok(())
+        // Must have root
+        if !self.entries.contains_key("root") {
+            errors.push(ConfigValidationError::MissingField("root"));
+        }
+
+        // Must have encoding with two hashes
+        match self.entries.get("encoding") {
+            Some(encoding) if encoding.len() != 2 => {
+                errors.push(ConfigValidationError::InvalidEncoding(encoding.len()));
+            }
+            Some(_) => {}
+            None => errors.push(ConfigValidationError::MissingField("encoding")),
+        }
+
+        if let Some(uid) = self.build_uid()
+            && !is_product_code(uid)
+        {
+            errors.push(ConfigValidationError::InvalidBuildUid(uid.to_string()));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
     }
 
     /// Get raw entry by key
@@ -688,17 +718,12 @@ impl Default for BuildConfig {
     }
 }
 
-/// Build config validation errors
-#[derive(Debug, thiserror::Error)]
-pub enum ValidationError {
-    #[error("missing root field")]
-    MissingRoot,
-    #[error("missing encoding field")]
-    MissingEncoding,
-    #[error("encoding field must have two hashes")]
-    InvalidEncoding,
-    #[error("invalid hash format: {0}")]
-    InvalidHash(String),
+/// Check whether `uid` looks like a product code (`wow`, `wow_classic_era`, `s2`)
+fn is_product_code(uid: &str) -> bool {
+    uid.starts_with(|c: char| c.is_ascii_lowercase())
+        && uid
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
 }
 
 impl crate::CascFormat for BuildConfig {
@@ -1064,4 +1089,74 @@ mod tests {
         // Should not fail validation despite non-hash values
         assert!(config.validate().is_ok());
     }
+
+    #[test]
+    fn test_validate_reports_every_violation() {
+        let mut config = BuildConfig::new();
+        config.set("build-key", vec!["not-a-hash".into()]);
+        config.set("encoding", vec![hash(2)]);
+        config.set("install", vec![hash(4), "XYZ".into()]);
+        config.set("build-uid", vec!["WoW Classic".into()]);
+
+        let errors = config.validate().expect_err("Config should be invalid");
+        assert_eq!(
+            errors,
+            [
+                ConfigValidationError::InvalidHash {
+                    key: "build-key".into(),
+                    value: "not-a-hash".into(),
+                },
+                ConfigValidationError::InvalidHash {
+                    key: "install".into(),
+                    value: "XYZ".into(),
+                },
+                ConfigValidationError::MissingField("root"),
+                ConfigValidationError::InvalidEncoding(1),
+                ConfigValidationError::InvalidBuildUid("WoW Classic".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_encoding_and_build_uid() {
+        let mut config = BuildConfig::new();
+        config.set("root", vec![hash(1)]);
+        config.set("build-key", vec![hash(9)]);
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigValidationError::MissingField("encoding")])
+        );
+
+        config.set("encoding", vec![hash(2), hash(3), hash(4)]);
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigValidationError::InvalidEncoding(3)])
+        );
+
+        config.set(
+            "encoding",
+            vec![hash(2), "0ca3da3df6680c6d6eec149c1be7500".into()],
+        );
+        let errors = config
+            .validate()
+            .expect_err("Short hash should be rejected");
+        assert!(matches!(
+            errors.as_slice(),
+            [ConfigValidationError::InvalidHash { key, .. }] if key == "encoding"
+        ));
+
+        config.set("encoding", vec![hash(2), hash(3)]);
+        for uid in ["wow", "wow_classic_era", "s2", "d3cn"] {
+            config.set("build-uid", vec![uid.into()]);
+            assert_eq!(config.validate(), Ok(()), "{uid}");
+        }
+        for uid in ["", "_wow", "2wow", "wow-beta"] {
+            config.set("build-uid", vec![uid.into()]);
+            assert_eq!(
+                config.validate(),
+                Err(vec![ConfigValidationError::InvalidBuildUid(uid.into())]),
+                "{uid}"
+            );
+        }
+    }
 }
@@ -0,0 +1,28 @@
+//! Error types for config file validation
+
+use thiserror::Error;
+
+/// A problem found by [`BuildConfig::validate`](super::BuildConfig::validate)
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ConfigValidationError {
+    /// A field every build config needs is absent
+    #[error("missing {0} field")]
+    MissingField(&'static str),
+
+    /// The encoding field does not hold a content key and an encoding key
+    #[error("encoding field must have exactly two hashes, found {0}")]
+    InvalidEncoding(usize),
+
+    /// A field value is not a 32-character hex MD5
+    #[error("invalid hash in {key}: {value}")]
+    InvalidHash {
+        /// Field the value belongs to
+        key: String,
+        /// The offending value
+        value: String,
+    },
+
+    /// The build UID is not a product code such as `wow_classic`
+    #[error("invalid build-uid: {0:?}")]
+    InvalidBuildUid(String),
+}
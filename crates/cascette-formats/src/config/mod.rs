@@ -5,12 +5,14 @@
 
 mod build_config;
 mod cdn_config;
+pub mod error;
 mod keyring_config;
 mod patch_config;
 mod product_config;
 
 pub use build_config::{BuildConfig, BuildInfo, PartialPriority};
 pub use cdn_config::{ArchiveInfo, CdnConfig};
+pub use error::ConfigValidationError;
 pub use keyring_config::{KeyringConfig, KeyringEntry};
 pub use patch_config::{PatchConfig, PatchEntry};
 pub use product_config::{
@@ -3,7 +3,7 @@
 use crate::download::entry::DownloadFileEntry;
 use crate::download::error::{DownloadError, Result};
 use crate::download::header::DownloadHeader;
-use crate::download::priority::{PriorityAnalysis, analyze_priorities};
+use crate::download::priority::{PriorityAnalysis, StreamingPlan, analyze_priorities};
 use crate::download::tag::DownloadTag;
 use binrw::{BinRead, BinWrite};
 use std::io::Cursor;
@@ -170,6 +170,20 @@ impl DownloadManifest {
         analyze_priorities(&self.entries, &self.header)
     }
 
+    /// Plan a streaming installation of the entries matching all `tag_names`
+    ///
+    /// Entries are ordered by effective priority (with the v3 base priority
+    /// applied), highest first, and by file size, largest first, within a
+    /// priority. An empty tag list selects every entry.
+    pub fn streaming_plan(&self, tag_names: &[&str]) -> StreamingPlan<'_> {
+        StreamingPlan::create(
+            self.entries_by_tags(tag_names)
+                .into_iter()
+                .map(|(_, entry)| entry),
+            &self.header,
+        )
+    }
+
     /// Find entries by tag name
     pub fn entries_by_tag(&self, tag_name: &str) -> Vec<(usize, &DownloadFileEntry)> {
         let Some(tag) = self.tags.iter().find(|t| t.name == tag_name) else {
@@ -658,6 +672,87 @@ mod tests {
         assert_eq!(original, parsed);
     }
 
+    /// Manifest with one untagged file and four "Windows" files
+    fn create_streaming_manifest(version: u8, base_priority: i8) -> DownloadManifest {
+        let ekey = |byte: u8| EncodingKey::from_bytes([byte; 16]);
+        let mut builder = DownloadManifestBuilder::new(version)
+            .expect("Operation should succeed")
+            .with_base_priority(base_priority)
+            .expect("Operation should succeed")
+            .add_tag("Windows".to_string(), TagType::Platform);
+        for (byte, size, priority) in [
+            (1, 100, 2),
+            (2, 50, 1),
+            (3, 300, 2),
+            (4, 1000, 8),
+            (5, 1, -5),
+        ] {
+            builder = builder
+                .add_file(ekey(byte), size, priority)
+                .expect("Operation should succeed");
+        }
+        for index in 0..4 {
+            builder = builder
+                .associate_file_with_tag(index, "Windows")
+                .expect("Operation should succeed");
+        }
+        let manifest = builder.build().expect("Operation should succeed");
+
+        // Plan from the parsed layout, not the builder output
+        DownloadManifest::parse(&manifest.build().expect("Operation should succeed"))
+            .expect("Operation should succeed")
+    }
+
+    #[test]
+    fn test_streaming_plan_order() {
+        for (version, base_priority) in [(1, 0), (2, 0), (3, 2)] {
+            let manifest = create_streaming_manifest(version, base_priority);
+            let plan = manifest.streaming_plan(&["Windows"]);
+
+            // Priority 1 first, then the two priority 2 files largest first
+            let sizes: Vec<u64> = plan.entries.iter().map(|e| e.file_size.as_u64()).collect();
+            assert_eq!(sizes, [50, 300, 100, 1000], "v{version}");
+            assert_eq!(plan.analysis.total_files, 4);
+            assert_eq!(plan.analysis.total_size, 1450);
+
+            let all = manifest.streaming_plan(&[]);
+            assert_eq!(all.entries[0].file_size.as_u64(), 1, "v{version}");
+            assert!(manifest.streaming_plan(&["Missing"]).entries.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_streaming_plan_applies_base_priority() {
+        // Without a base priority, priorities 1 and 2 are High
+        let manifest = create_streaming_manifest(2, 0);
+        let v2 = manifest.streaming_plan(&["Windows"]);
+        assert_eq!(v2.analysis.essential_size, 0);
+        assert_eq!(v2.analysis.priority_range, (1, 8));
+        assert_eq!(
+            v2.analysis.categories[&PriorityCategory::High].total_size,
+            450
+        );
+
+        // A v3 base priority of 2 shifts them to Critical and Essential
+        let manifest = create_streaming_manifest(3, 2);
+        let v3 = manifest.streaming_plan(&["Windows"]);
+        assert_eq!(v3.analysis.essential_size, 450);
+        assert_eq!(v3.analysis.streamable_size, 1000);
+        assert_eq!(v3.analysis.priority_range, (-1, 6));
+        assert_eq!(
+            v3.analysis.categories[&PriorityCategory::Critical].total_size,
+            50
+        );
+        assert_eq!(
+            v3.analysis.categories[&PriorityCategory::Essential].total_size,
+            400
+        );
+        assert_eq!(
+            v3.analysis.categories[&PriorityCategory::Low].total_size,
+            1000
+        );
+    }
+
     #[test]
     fn test_version_differences() {
         let ekey = EncodingKey::from_hex("0123456789abcdef0123456789abcdef")
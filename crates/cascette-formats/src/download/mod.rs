@@ -136,21 +136,18 @@
 //! ## Streaming Installation
 //!
 //! ```rust,no_run
-//! # use cascette_formats::download::{DownloadManifest, PriorityCategory};
-//! # fn calculate_streaming_plan(manifest: &DownloadManifest) -> (u64, u64) {
-//! let mut essential_size = 0u64;
-//! let mut total_size = 0u64;
-//!
-//! for entry in &manifest.entries {
-//!     let size = entry.file_size.as_u64();
-//!     total_size += size;
-//!
-//!     if entry.is_essential(&manifest.header) {
-//!         essential_size += size;
-//!     }
+//! # use cascette_formats::download::DownloadManifest;
+//! # fn calculate_streaming_plan(manifest: &DownloadManifest) {
+//! let plan = manifest.streaming_plan(&["Windows", "enUS"]);
+//!
+//! println!(
+//!     "{} MB before playable",
+//!     plan.analysis.essential_size / (1024 * 1024)
+//! );
+//! for entry in &plan.entries {
+//!     // Download in this order, highest priority first
+//!     let _ = entry.encoding_key;
 //! }
-//!
-//! (essential_size, total_size)
 //! # }
 //! ```
 
@@ -168,7 +165,7 @@ pub use entry::{DownloadFileEntry, FileSize40};
 pub use error::{DownloadError, Result};
 pub use header::{DownloadHeader, DownloadHeaderBase, DownloadHeaderV2, DownloadHeaderV3};
 pub use manifest::DownloadManifest;
-pub use priority::{CategoryStats, PriorityAnalysis, PriorityCategory};
+pub use priority::{CategoryStats, PriorityAnalysis, PriorityCategory, StreamingPlan};
 pub use tag::DownloadTag;
 
 // Re-export TagType from install module for convenience
@@ -401,8 +401,8 @@ impl PriorityAnalysis {
 }
 
 /// Analyze the priority distribution of entries in a download manifest
-pub fn analyze_priorities(
-    entries: &[DownloadFileEntry],
+pub fn analyze_priorities<'a>(
+    entries: impl IntoIterator<Item = &'a DownloadFileEntry>,
     header: &DownloadHeader,
 ) -> PriorityAnalysis {
     let mut analysis = PriorityAnalysis::new(header.base_priority());
@@ -419,6 +419,40 @@ pub fn analyze_priorities(
     analysis
 }
 
+/// Tag-filtered download order for streaming installation
+///
+/// Created by [`DownloadManifest::streaming_plan`](crate::download::DownloadManifest::streaming_plan).
+#[derive(Debug, Clone)]
+pub struct StreamingPlan<'a> {
+    /// Selected entries, highest effective priority first, then largest first
+    pub entries: Vec<&'a DownloadFileEntry>,
+    /// Priority distribution of the selected entries
+    ///
+    /// `essential_size` is the amount to download before the game is playable.
+    pub analysis: PriorityAnalysis,
+}
+
+impl<'a> StreamingPlan<'a> {
+    /// Order `entries` for streaming and analyze them
+    pub fn create(
+        entries: impl IntoIterator<Item = &'a DownloadFileEntry>,
+        header: &DownloadHeader,
+    ) -> Self {
+        let mut entries: Vec<&DownloadFileEntry> = entries.into_iter().collect();
+
+        // Lower values = higher priority; the sort is stable, so equal
+        // priorities and sizes keep their manifest order
+        entries.sort_by(|a, b| {
+            a.effective_priority(header)
+                .cmp(&b.effective_priority(header))
+                .then_with(|| b.file_size.as_u64().cmp(&a.file_size.as_u64()))
+        });
+
+        let analysis = analyze_priorities(entries.iter().copied(), header);
+        Self { entries, analysis }
+    }
+}
+
 /// Create a download plan ordered by priority
 #[derive(Debug, Clone)]
 pub struct DownloadPlan {
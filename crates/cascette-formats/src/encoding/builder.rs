@@ -357,15 +357,15 @@ impl EncodingBuilder {
             espec_block_size: espec_data.len() as u32,
         };
 
-        Ok(EncodingFile {
+        Ok(EncodingFile::from_parts(
             header,
             espec_table,
             ckey_index,
             ckey_pages,
             ekey_index,
             ekey_pages,
-            trailing_espec: self.trailing_espec,
-        })
+            self.trailing_espec,
+        ))
     }
 
     /// Generate trailing `ESpec` for the encoding file itself
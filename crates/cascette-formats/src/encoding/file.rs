@@ -7,6 +7,7 @@ use crate::encoding::{
 use binrw::{BinRead, BinWrite};
 use cascette_crypto::{ContentKey, EncodingKey};
 use std::io::{Cursor, Read};
+use std::sync::OnceLock;
 
 /// Page data with entries
 #[derive(Debug, Clone)]
@@ -34,9 +35,33 @@ pub struct EncodingFile {
     pub ekey_pages: Vec<Page<EKeyPageEntry>>,
     /// Self-describing `ESpec` at end of file
     pub trailing_espec: Option<String>,
+    /// `(EKey, CKey)` pairs sorted by `EKey`, built on first use
+    reverse_index: OnceLock<Vec<(EncodingKey, ContentKey)>>,
 }
 
 impl EncodingFile {
+    /// Assemble an encoding file from its parsed or built parts
+    pub(super) fn from_parts(
+        header: EncodingHeader,
+        espec_table: ESpecTable,
+        ckey_index: Vec<IndexEntry>,
+        ckey_pages: Vec<Page<CKeyPageEntry>>,
+        ekey_index: Vec<IndexEntry>,
+        ekey_pages: Vec<Page<EKeyPageEntry>>,
+        trailing_espec: Option<String>,
+    ) -> Self {
+        Self {
+            header,
+            espec_table,
+            ckey_index,
+            ckey_pages,
+            ekey_index,
+            ekey_pages,
+            trailing_espec,
+            reverse_index: OnceLock::new(),
+        }
+    }
+
     /// Parse `CKey` pages from cursor
     fn parse_ckey_pages(
         cursor: &mut Cursor<&[u8]>,
@@ -258,7 +283,7 @@ impl EncodingFile {
             Some(String::from_utf8_lossy(&trailing_data).to_string())
         };
 
-        Ok(Self {
+        Ok(Self::from_parts(
             header,
             espec_table,
             ckey_index,
@@ -266,7 +291,7 @@ impl EncodingFile {
             ekey_index,
             ekey_pages,
             trailing_espec,
-        })
+        ))
     }
 
     /// Build encoding file into raw bytes
@@ -413,6 +438,58 @@ impl EncodingFile {
         Vec::new()
     }
 
+    /// `(EKey, CKey)` pairs of every `CKey` page, sorted by `EKey`
+    ///
+    /// The first call walks every `CKey` page and builds the list, which
+    /// later calls reuse. It holds another copy of every key pair, 32 bytes
+    /// per encoding key. Changes to `ckey_pages` after the first call are
+    /// not reflected. Pairs sharing an `EKey` keep their page order.
+    pub fn reverse_index(&self) -> &[(EncodingKey, ContentKey)] {
+        self.reverse_index.get_or_init(|| {
+            let mut index: Vec<_> = self
+                .ckey_pages
+                .iter()
+                .flat_map(|page| &page.entries)
+                .flat_map(|entry| {
+                    entry
+                        .encoding_keys
+                        .iter()
+                        .map(|encoding_key| (*encoding_key, entry.content_key))
+                })
+                .collect();
+            index.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+            index
+        })
+    }
+
+    /// Find the content keys an encoding key was produced from
+    ///
+    /// Builds the [`reverse_index`](Self::reverse_index) on first use.
+    /// Content keys are returned in page order. The result is empty if no
+    /// content key uses `ekey`.
+    pub fn lookup_by_ekey(&self, ekey: &EncodingKey) -> Vec<ContentKey> {
+        self.lookup_by_ekey_prefix(ekey.as_bytes())
+            .into_iter()
+            .map(|(_, content_key)| content_key)
+            .collect()
+    }
+
+    /// Find the `(EKey, CKey)` pairs whose encoding key starts with `prefix`
+    ///
+    /// Archive indices and TVFS store truncated encoding keys, usually the
+    /// first 9 bytes. Builds the [`reverse_index`](Self::reverse_index) on
+    /// first use. Pairs are returned sorted by encoding key, then in page
+    /// order. A prefix longer than 16 bytes matches nothing.
+    pub fn lookup_by_ekey_prefix(&self, prefix: &[u8]) -> Vec<(EncodingKey, ContentKey)> {
+        let index = self.reverse_index();
+        let start = index.partition_point(|(ekey, _)| ekey.as_bytes().as_slice() < prefix);
+        index[start..]
+            .iter()
+            .take_while(|(ekey, _)| ekey.as_bytes().starts_with(prefix))
+            .copied()
+            .collect()
+    }
+
     /// Find `ESpec` for an encoding key
     ///
     /// Uses binary search on the page index to find the candidate page,
@@ -623,3 +700,103 @@ impl crate::CascFormat for EncodingFile {
             .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use crate::encoding::{CKeyEntryData, EKeyEntryData, EncodingBuilder};
+    use cascette_crypto::{ContentKey, EncodingKey};
+
+    #[test]
+    fn test_lookup_by_ekey() {
+        let shared = EncodingKey::from_bytes([0xEE; 16]);
+        let unique = EncodingKey::from_bytes([0xDD; 16]);
+        let mut builder = EncodingBuilder::new();
+        for (ckey, ekeys) in [
+            ([1u8; 16], vec![shared]),
+            ([2u8; 16], vec![unique, shared]),
+            ([3u8; 16], vec![unique]),
+        ] {
+            builder.add_ckey_entry(CKeyEntryData {
+                content_key: ContentKey::from_bytes(ckey),
+                file_size: 100,
+                encoding_keys: ekeys,
+            });
+        }
+        for encoding_key in [shared, unique] {
+            builder.add_ekey_entry(EKeyEntryData {
+                encoding_key,
+                espec: "n".to_string(),
+                file_size: 100,
+            });
+        }
+        let encoding = builder.build().expect("Failed to build encoding file");
+
+        // Survives a round trip through the binary form
+        let parsed = super::EncodingFile::parse(&encoding.build().expect("Failed to serialize"))
+            .expect("Failed to parse encoding file");
+        for file in [&encoding, &parsed] {
+            assert_eq!(
+                file.lookup_by_ekey(&shared),
+                [
+                    ContentKey::from_bytes([1; 16]),
+                    ContentKey::from_bytes([2; 16])
+                ]
+            );
+            assert_eq!(
+                file.lookup_by_ekey(&unique),
+                [
+                    ContentKey::from_bytes([2; 16]),
+                    ContentKey::from_bytes([3; 16])
+                ]
+            );
+            assert!(
+                file.lookup_by_ekey(&EncodingKey::from_bytes([0; 16]))
+                    .is_empty()
+            );
+        }
+    }
+
+    #[test]
+    fn test_lookup_by_ekey_prefix() {
+        let mut first = [0xAB; 16];
+        first[15] = 1;
+        let mut second = [0xAB; 16];
+        second[15] = 2;
+        let other = [0xAC; 16];
+        let mut builder = EncodingBuilder::new();
+        for (ckey, ekey) in [([1u8; 16], second), ([2u8; 16], first), ([3u8; 16], other)] {
+            builder.add_ckey_entry(CKeyEntryData {
+                content_key: ContentKey::from_bytes(ckey),
+                file_size: 100,
+                encoding_keys: vec![EncodingKey::from_bytes(ekey)],
+            });
+        }
+        let encoding = builder.build().expect("Failed to build encoding file");
+
+        // Both keys share the 9 byte prefix and come back sorted by EKey
+        assert_eq!(
+            encoding.lookup_by_ekey_prefix(&[0xAB; 9]),
+            [
+                (
+                    EncodingKey::from_bytes(first),
+                    ContentKey::from_bytes([2; 16])
+                ),
+                (
+                    EncodingKey::from_bytes(second),
+                    ContentKey::from_bytes([1; 16])
+                ),
+            ]
+        );
+        assert_eq!(
+            encoding.lookup_by_ekey_prefix(&other[..9]),
+            [(
+                EncodingKey::from_bytes(other),
+                ContentKey::from_bytes([3; 16])
+            )]
+        );
+        assert!(encoding.lookup_by_ekey_prefix(&[0xAA; 9]).is_empty());
+        assert!(encoding.lookup_by_ekey_prefix(&[0xAB; 17]).is_empty());
+        assert_eq!(encoding.reverse_index().len(), 3);
+    }
+}
@@ -0,0 +1,596 @@
+//! Encoded size estimation for `ESpec` pipelines
+//!
+//! [`ESpec::estimate_encoded_size`] predicts the size of the BLTE file that
+//! encoding content of a given size with a spec produces. The structural
+//! parts are computed exactly from the block layout:
+//!
+//! - BLTE header: magic and header size (8 bytes), plus flags and chunk count
+//!   (4 bytes) and 24 bytes per chunk when a chunk table is written
+//! - Per-chunk framing: the mode byte, and for encrypted blocks the key name,
+//!   IV and cipher type followed by the inner mode byte
+//!
+//! Uncompressed (`n`) data is stored as is, so specs without compression are
+//! estimated exactly. Compressed blocks are estimated with a [`RatioModel`]
+//! and clamped to the hard limits of the codec. The default model covers
+//! typical game data; [`RatioModel::fit`] narrows it from observed sizes.
+
+use super::plan::split_blocks;
+use super::types::{ESpec, ZLibVariant};
+use std::collections::BTreeMap;
+
+/// BLTE magic and header size field
+const BLTE_PREAMBLE_SIZE: u64 = 8;
+/// Flags byte and 24-bit chunk count preceding the chunk table
+const CHUNK_TABLE_PREFIX_SIZE: u64 = 4;
+/// Compressed size, decompressed size and MD5 checksum
+const CHUNK_TABLE_ENTRY_SIZE: u64 = 24;
+/// Compression mode byte at the start of every chunk
+const MODE_BYTE_SIZE: u64 = 1;
+/// Key name size, 8-byte key name, IV size and cipher type of an
+/// encrypted chunk, excluding the IV itself
+const ENCRYPTION_HEADER_SIZE: u64 = 1 + 8 + 1 + 1;
+/// zlib level used when the spec does not name one
+const DEFAULT_ZLIB_LEVEL: u8 = 6;
+
+/// Codec whose output size depends on the content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CompressionKind {
+    /// zlib at the given level (`z`, including the `mpq` variant)
+    ZLib {
+        /// Compression level (1-9)
+        level: u8,
+    },
+    /// LZ4HC (`z` with the `lz4hc` variant)
+    Lz4Hc,
+    /// `BCPack` (`c`)
+    BCPack,
+    /// `GDeflate` (`g`)
+    GDeflate,
+}
+
+impl CompressionKind {
+    /// Codec used by a leaf spec, `None` for uncompressed or container specs
+    #[must_use]
+    pub fn of(spec: &ESpec) -> Option<Self> {
+        match spec {
+            ESpec::ZLib {
+                variant: Some(ZLibVariant::LZ4HC),
+                ..
+            } => Some(Self::Lz4Hc),
+            ESpec::ZLib { level, .. } => Some(Self::ZLib {
+                level: level.unwrap_or(DEFAULT_ZLIB_LEVEL),
+            }),
+            ESpec::BCPack { .. } => Some(Self::BCPack),
+            ESpec::GDeflate { .. } => Some(Self::GDeflate),
+            ESpec::None | ESpec::Encrypted { .. } | ESpec::BlockTable { .. } => None,
+        }
+    }
+
+    /// Smallest and largest output the codec can produce for `raw_size` bytes
+    const fn hard_limits(self, raw_size: u64) -> (u64, u64) {
+        match self {
+            // zlib header and Adler-32 trailer around a deflate stream, which
+            // spends at least one bit per 258-byte match; the upper limit is
+            // zlib's compressBound()
+            Self::ZLib { .. } => (
+                2 + 4 + raw_size / 2064,
+                raw_size + (raw_size >> 12) + (raw_size >> 14) + (raw_size >> 25) + 13,
+            ),
+            // 8-byte size prefix and a single LZ4 block
+            Self::Lz4Hc => (8, 8 + raw_size + raw_size / 255 + 16),
+            Self::BCPack | Self::GDeflate => (0, u64::MAX),
+        }
+    }
+}
+
+/// Range of compressed-to-raw size ratios for one codec
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RatioRange {
+    /// Smallest expected ratio
+    pub low: f64,
+    /// Typical ratio
+    pub expected: f64,
+    /// Largest expected ratio
+    pub high: f64,
+}
+
+impl RatioRange {
+    /// Create a ratio range
+    #[must_use]
+    pub const fn new(low: f64, expected: f64, high: f64) -> Self {
+        Self {
+            low,
+            expected,
+            high,
+        }
+    }
+}
+
+/// Compression ratios used to estimate compressed blocks
+///
+/// Codecs without an entry use the fallback range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatioModel {
+    ratios: BTreeMap<CompressionKind, RatioRange>,
+    fallback: RatioRange,
+}
+
+impl Default for RatioModel {
+    /// Ratios of typical game data
+    ///
+    /// The high ends allow for incompressible blocks, which the codecs
+    /// store with a few bytes of framing. The low ends are the best ratios
+    /// seen on real data; highly redundant content, such as zero-filled
+    /// blocks, compresses further and needs a fitted model.
+    fn default() -> Self {
+        let mut model = Self::new(RatioRange::new(0.05, 0.75, 1.05));
+        for level in 1..=9 {
+            let expected = match level {
+                1..=3 => 0.68,
+                4..=6 => 0.66,
+                _ => 0.65,
+            };
+            model = model.with_ratio(
+                CompressionKind::ZLib { level },
+                RatioRange::new(0.05, expected, 1.01),
+            );
+        }
+        model
+            .with_ratio(CompressionKind::Lz4Hc, RatioRange::new(0.08, 0.72, 1.01))
+            .with_ratio(CompressionKind::BCPack, RatioRange::new(0.2, 0.75, 1.05))
+            .with_ratio(CompressionKind::GDeflate, RatioRange::new(0.1, 0.66, 1.05))
+    }
+}
+
+impl RatioModel {
+    /// Create a model that uses `fallback` for every codec
+    #[must_use]
+    pub const fn new(fallback: RatioRange) -> Self {
+        Self {
+            ratios: BTreeMap::new(),
+            fallback,
+        }
+    }
+
+    /// Set the ratio range of a codec
+    #[must_use]
+    pub fn with_ratio(mut self, kind: CompressionKind, range: RatioRange) -> Self {
+        self.ratios.insert(kind, range);
+        self
+    }
+
+    /// Ratio range used for a codec
+    #[must_use]
+    pub fn ratio(&self, kind: CompressionKind) -> RatioRange {
+        self.ratios.get(&kind).copied().unwrap_or(self.fallback)
+    }
+
+    /// Calibrate the default model from observed sizes
+    ///
+    /// For each codec, the low and high ratios become the smallest and
+    /// largest ratio observed and the expected ratio the byte-weighted mean.
+    /// Samples whose compressed blocks use more than one codec cannot be
+    /// attributed and are ignored, as are samples smaller than their
+    /// structural overhead. Codecs without usable samples keep the default
+    /// range.
+    #[must_use]
+    pub fn fit(samples: &[EncodedSizeSample]) -> Self {
+        #[derive(Default)]
+        struct Observed {
+            low: f64,
+            high: f64,
+            raw: u64,
+            compressed: u64,
+        }
+
+        let mut observed: BTreeMap<CompressionKind, Observed> = BTreeMap::new();
+
+        for sample in samples {
+            let layout = Layout::of(&sample.espec, sample.raw_size);
+            let fixed = layout.overhead.total() + layout.passthrough;
+            let Some(compressed) = sample.encoded_size.checked_sub(fixed) else {
+                continue;
+            };
+            let Some(kind) = layout.single_kind() else {
+                continue;
+            };
+            let raw = layout.compressed.iter().map(|(_, size)| size).sum::<u64>();
+            if raw == 0 {
+                continue;
+            }
+
+            let ratio = to_f64(compressed) / to_f64(raw);
+            let entry = observed.entry(kind).or_insert_with(|| Observed {
+                low: ratio,
+                high: ratio,
+                ..Observed::default()
+            });
+            entry.low = entry.low.min(ratio);
+            entry.high = entry.high.max(ratio);
+            entry.raw += raw;
+            entry.compressed += compressed;
+        }
+
+        observed
+            .into_iter()
+            .fold(Self::default(), |model, (kind, observed)| {
+                let expected = to_f64(observed.compressed) / to_f64(observed.raw);
+                model.with_ratio(kind, RatioRange::new(observed.low, expected, observed.high))
+            })
+    }
+}
+
+/// Observed encoded size of content encoded with a spec
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedSizeSample {
+    /// Spec the content was encoded with
+    pub espec: ESpec,
+    /// Size of the content before encoding
+    pub raw_size: u64,
+    /// Size of the resulting BLTE file
+    pub encoded_size: u64,
+}
+
+/// Structural bytes of a BLTE file, independent of the content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EncodingOverhead {
+    /// Number of chunks the content is split into
+    pub chunk_count: u64,
+    /// BLTE magic and header size field
+    pub header: u64,
+    /// Chunk table including its flags and count prefix, 0 if absent
+    pub chunk_table: u64,
+    /// Mode bytes and encryption headers of all chunks
+    pub chunk_framing: u64,
+}
+
+impl EncodingOverhead {
+    /// Total structural bytes
+    #[must_use]
+    pub const fn total(&self) -> u64 {
+        self.header + self.chunk_table + self.chunk_framing
+    }
+}
+
+/// Estimated size of a BLTE file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeEstimate {
+    /// Lower bound of the encoded size
+    pub low: u64,
+    /// Expected encoded size
+    pub expected: u64,
+    /// Upper bound of the encoded size
+    pub high: u64,
+    /// Structural part of the estimate
+    pub overhead: EncodingOverhead,
+}
+
+impl SizeEstimate {
+    /// Whether the estimate has no uncertainty
+    #[must_use]
+    pub const fn is_exact(&self) -> bool {
+        self.low == self.high
+    }
+
+    /// Whether `size` lies within the estimated range
+    #[must_use]
+    pub const fn contains(&self, size: u64) -> bool {
+        self.low <= size && size <= self.high
+    }
+}
+
+impl ESpec {
+    /// Estimate the BLTE size of `raw_size` bytes encoded with this spec
+    ///
+    /// Block tables are laid out the way encoders split content: sized
+    /// blocks are taken in order, a sized block without a count repeats when
+    /// it is the last block (`256K*=z`), `*=` takes the rest, and content
+    /// beyond the declared blocks is encoded with the last block's spec.
+    /// Files without a block table are a single chunk and only get a chunk
+    /// table when encrypted.
+    #[must_use]
+    pub fn estimate_encoded_size(&self, raw_size: u64, model: &RatioModel) -> SizeEstimate {
+        let layout = Layout::of(self, raw_size);
+        let fixed = layout.overhead.total() + layout.passthrough;
+
+        let (mut low, mut expected, mut high) = (fixed, fixed, fixed);
+        for &(kind, size) in &layout.compressed {
+            let ratio = model.ratio(kind);
+            let (min, max) = kind.hard_limits(size);
+            let block_low = scale(size, ratio.low, false).clamp(min, max);
+            let block_high = scale(size, ratio.high, true).clamp(min, max);
+            let block_expected = scale(size, ratio.expected, false).clamp(block_low, block_high);
+
+            low = low.saturating_add(block_low);
+            expected = expected.saturating_add(block_expected);
+            high = high.saturating_add(block_high);
+        }
+
+        SizeEstimate {
+            low,
+            expected,
+            high,
+            overhead: layout.overhead,
+        }
+    }
+}
+
+/// Block layout of content encoded with a spec
+#[derive(Debug, Default)]
+struct Layout {
+    overhead: EncodingOverhead,
+    /// Bytes stored uncompressed
+    passthrough: u64,
+    /// Raw size of each compressed block
+    compressed: Vec<(CompressionKind, u64)>,
+}
+
+impl Layout {
+    fn of(spec: &ESpec, raw_size: u64) -> Self {
+        let blocks = split_blocks(spec, raw_size);
+        let has_table = spec.has_chunk_table();
+        let chunk_count = blocks.len() as u64;
+
+        let mut layout = Self {
+            overhead: EncodingOverhead {
+                chunk_count,
+                header: BLTE_PREAMBLE_SIZE,
+                chunk_table: if has_table {
+                    CHUNK_TABLE_PREFIX_SIZE + CHUNK_TABLE_ENTRY_SIZE * chunk_count
+                } else {
+                    0
+                },
+                chunk_framing: MODE_BYTE_SIZE * chunk_count,
+            },
+            ..Self::default()
+        };
+
+        for (block_spec, size) in blocks {
+            layout.add_payload(block_spec, size);
+        }
+        layout
+    }
+
+    /// Account for the chunk payload of a block, excluding its mode byte
+    fn add_payload(&mut self, spec: &ESpec, size: u64) {
+        match spec {
+            ESpec::None => self.passthrough += size,
+            ESpec::Encrypted { iv, spec, .. } => {
+                // The encrypted payload starts with the inner mode byte
+                self.overhead.chunk_framing +=
+                    ENCRYPTION_HEADER_SIZE + iv.len() as u64 + MODE_BYTE_SIZE;
+                self.add_payload(spec, size);
+            }
+            ESpec::BlockTable { .. } => {
+                for (block_spec, block_size) in split_blocks(spec, size) {
+                    self.add_payload(block_spec, block_size);
+                }
+            }
+            ESpec::ZLib { .. } | ESpec::BCPack { .. } | ESpec::GDeflate { .. } => {
+                if let Some(kind) = CompressionKind::of(spec) {
+                    self.compressed.push((kind, size));
+                }
+            }
+        }
+    }
+
+    /// The codec of all compressed blocks, if there is exactly one
+    fn single_kind(&self) -> Option<CompressionKind> {
+        let (first, _) = self.compressed.first()?;
+        self.compressed
+            .iter()
+            .all(|(kind, _)| kind == first)
+            .then_some(*first)
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+const fn to_f64(value: u64) -> f64 {
+    value as f64
+}
+
+/// Scale a size by a ratio, saturating at `u64::MAX`
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn scale(size: u64, ratio: f64, round_up: bool) -> u64 {
+    let scaled = to_f64(size) * ratio.max(0.0);
+    let scaled = if round_up {
+        scaled.ceil()
+    } else {
+        scaled.floor()
+    };
+    // Float-to-int casts saturate
+    scaled as u64
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::CascFormat;
+    use crate::blte::{BlteBuilder, BlteFile, ChunkData, CompressionMode, EncryptionSpec};
+
+    fn encoded_size(blte: &BlteFile) -> u64 {
+        blte.build().expect("BLTE should serialize").len() as u64
+    }
+
+    fn estimate(spec: &str, raw_size: u64) -> SizeEstimate {
+        ESpec::parse(spec)
+            .expect("ESpec should parse")
+            .estimate_encoded_size(raw_size, &RatioModel::default())
+    }
+
+    fn test_data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    /// Pseudo-random text over 16 letters, which zlib compresses to about
+    /// 57% like typical game data
+    fn text_data(len: usize) -> Vec<u8> {
+        let mut state = 0x2545_F491_u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                b"abcdefghijklmnop"[(state % 16) as usize]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_uncompressed_single_chunk_is_exact() {
+        let data = test_data(1000);
+        let blte = BlteFile::single_chunk(data, CompressionMode::None).expect("BLTE should build");
+
+        let estimate = estimate("n", 1000);
+        assert!(estimate.is_exact());
+        assert_eq!(estimate.expected, encoded_size(&blte));
+        assert_eq!(estimate.overhead.chunk_table, 0);
+        assert_eq!(estimate.overhead.total(), 9);
+    }
+
+    #[test]
+    fn test_uncompressed_block_table_is_exact() {
+        let data = test_data(600 * 1024 + 17);
+        let blte = BlteBuilder::new()
+            .with_compression(CompressionMode::None)
+            .with_chunk_size(256 * 1024)
+            .expect("Chunk size should be valid")
+            .add_data(&data)
+            .expect("Data should be added")
+            .build()
+            .expect("BLTE should build");
+
+        let estimate = estimate("b:{256K*=n}", data.len() as u64);
+        assert!(estimate.is_exact());
+        assert_eq!(estimate.expected, encoded_size(&blte));
+        assert_eq!(estimate.overhead.chunk_count, 3);
+        assert_eq!(estimate.overhead.chunk_table, 4 + 3 * 24);
+    }
+
+    #[test]
+    fn test_mixed_block_sizes_are_exact() {
+        let data = test_data(10_000);
+        let chunks = [1024, 2048, 2048, 2048, 2832]
+            .iter()
+            .scan(0, |offset, &len| {
+                let chunk = data[*offset..*offset + len].to_vec();
+                *offset += len;
+                Some(ChunkData::new(chunk, CompressionMode::None).expect("Chunk should build"))
+            })
+            .collect();
+        let blte = BlteFile::multi_chunk(chunks).expect("BLTE should build");
+
+        let estimate = estimate("b:{1K=n,2K*3=n,*=n}", 10_000);
+        assert!(estimate.is_exact());
+        assert_eq!(estimate.overhead.chunk_count, 5);
+        assert_eq!(estimate.expected, encoded_size(&blte));
+    }
+
+    #[test]
+    fn test_encrypted_uncompressed_is_exact() {
+        let data = test_data(300 * 1024);
+        let spec = EncryptionSpec::salsa20(0x1122_3344_5566_7788, [1, 2, 3, 4]);
+        let blte = BlteBuilder::new()
+            .with_compression(CompressionMode::None)
+            .with_chunk_size(256 * 1024)
+            .expect("Chunk size should be valid")
+            .with_encryption(spec, [0x42; 16])
+            .add_data(&data)
+            .expect("Data should be added")
+            .build()
+            .expect("BLTE should build");
+
+        let estimate = estimate(
+            "b:{256K*=e:{1122334455667788,01020304,n}}",
+            data.len() as u64,
+        );
+        assert!(estimate.is_exact());
+        assert_eq!(estimate.expected, encoded_size(&blte));
+        // Mode byte, encryption header, 4-byte IV and inner mode byte
+        assert_eq!(estimate.overhead.chunk_framing, 2 * (1 + 11 + 4 + 1));
+    }
+
+    #[test]
+    fn test_compressed_range_contains_actual_size() {
+        let data = text_data(700 * 1024);
+        let raw_size = data.len() as u64;
+        let blte = BlteFile::compress(&data, 256 * 1024, CompressionMode::ZLib)
+            .expect("BLTE should build");
+        let actual = encoded_size(&blte);
+
+        let estimate = estimate("b:{256K*=z}", raw_size);
+        assert!(!estimate.is_exact());
+        assert!(estimate.contains(actual));
+        assert!(estimate.low <= estimate.expected && estimate.expected <= estimate.high);
+        assert_eq!(estimate.overhead.chunk_count, 3);
+
+        // The range is bounded by the default ratios, not the codec limits
+        let overhead = estimate.overhead.total();
+        // Each of the three blocks rounds its lower bound down
+        assert!(estimate.low + 3 >= overhead + raw_size / 20);
+        assert!(estimate.high <= overhead + raw_size + raw_size / 100 + 3);
+        assert!(!estimate.contains(actual / 20));
+        // The expected size is within 20% of the actual size
+        assert!(estimate.expected.abs_diff(actual) * 5 < actual);
+    }
+
+    #[test]
+    fn test_redundant_content_needs_fitted_model() {
+        let data = vec![0u8; 256 * 1024];
+        let blte = BlteFile::compress(&data, 256 * 1024, CompressionMode::ZLib)
+            .expect("BLTE should build");
+        let actual = encoded_size(&blte);
+
+        let espec = ESpec::parse("b:{256K*=z}").expect("ESpec should parse");
+        let default = espec.estimate_encoded_size(256 * 1024, &RatioModel::default());
+        assert!(actual < default.low);
+
+        let model = RatioModel::fit(&[EncodedSizeSample {
+            espec: espec.clone(),
+            raw_size: 256 * 1024,
+            encoded_size: actual,
+        }]);
+        assert!(
+            espec
+                .estimate_encoded_size(256 * 1024, &model)
+                .contains(actual)
+        );
+    }
+
+    #[test]
+    fn test_fit_narrows_range() {
+        let espec = ESpec::parse("z").expect("ESpec should parse");
+        let samples = [(1000, 412), (2000, 903)].map(|(raw_size, payload)| EncodedSizeSample {
+            espec: espec.clone(),
+            raw_size,
+            encoded_size: 9 + payload,
+        });
+
+        let model = RatioModel::fit(&samples);
+        let range = model.ratio(CompressionKind::ZLib { level: 6 });
+        assert!((range.low - 0.412).abs() < 1e-9);
+        assert!((range.high - 0.4515).abs() < 1e-9);
+        assert!((range.expected - 1315.0 / 3000.0).abs() < 1e-9);
+
+        for sample in &samples {
+            assert!(
+                espec
+                    .estimate_encoded_size(sample.raw_size, &model)
+                    .contains(sample.encoded_size)
+            );
+        }
+        // Other codecs keep the default range
+        assert_eq!(
+            model.ratio(CompressionKind::Lz4Hc),
+            RatioModel::default().ratio(CompressionKind::Lz4Hc)
+        );
+    }
+
+    #[test]
+    fn test_empty_content() {
+        let estimate = estimate("b:{256K*=n}", 0);
+        assert_eq!(estimate.overhead.chunk_count, 0);
+        assert_eq!(estimate.expected, 12);
+    }
+}
@@ -103,11 +103,56 @@
 //! - **Small files**: `z:9` - Simple maximum compression
 //! - **Large assets**: `b:{22=n,31943=z,211_232=n,*=z}` - Mixed strategies for different sections
 //! - **MPQ compat**: `b:{16K*=z:{6,mpq}}` - Backward compatibility with older tools
+//!
+//! # Re-encoding
+//!
+//! [`ESpec::plan`] lays content out into the blocks a spec encodes, and
+//! [`BlteBuilder::from_espec`](crate::blte::BlteBuilder::from_espec) encodes
+//! them, so content can be rebuilt with the spec recorded in its encoding
+//! file:
+//!
+//! ```
+//! use cascette_formats::blte::BlteBuilder;
+//! use cascette_formats::espec::ESpec;
+//!
+//! let spec = ESpec::parse("b:{164=z,16K*2=z,*=n}").expect("Test operation should succeed");
+//! let data = vec![0u8; 40_000];
+//!
+//! let sizes: Vec<u64> = spec.plan(data.len() as u64).iter().map(|b| b.size).collect();
+//! assert_eq!(sizes, [164, 16_384, 16_384, 7068]);
+//!
+//! let blte = BlteBuilder::from_espec(&spec, &data, None)
+//!     .expect("Test operation should succeed")
+//!     .build()
+//!     .expect("Test operation should succeed");
+//! assert_eq!(blte.chunks.len(), 4);
+//! ```
+//!
+//! # Size Estimation
+//!
+//! [`ESpec::estimate_encoded_size`] predicts the BLTE size of content before
+//! encoding it. Structural overhead is exact; compressed blocks are bounded
+//! by a [`RatioModel`]:
+//!
+//! ```
+//! use cascette_formats::espec::{ESpec, RatioModel};
+//!
+//! let spec = ESpec::parse("b:{256K*=n}").expect("Test operation should succeed");
+//! let estimate = spec.estimate_encoded_size(600 * 1024, &RatioModel::default());
+//! assert!(estimate.is_exact());
+//! assert_eq!(estimate.expected, 600 * 1024 + 12 + 3 * 24 + 3);
+//! ```
 
+mod estimate;
 mod parser;
+mod plan;
 mod types;
 
+pub use estimate::{
+    CompressionKind, EncodedSizeSample, EncodingOverhead, RatioModel, RatioRange, SizeEstimate,
+};
 pub use parser::Parser;
+pub use plan::BlockPlan;
 pub use types::{BlockChunk, BlockSizeSpec, ESpec, ESpecError, ZLibVariant};
 
 // Re-export the main parse function
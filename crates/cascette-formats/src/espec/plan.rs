@@ -0,0 +1,203 @@
+//! Block layout planning for `ESpec` pipelines
+//!
+//! [`ESpec::plan`] splits content into the BLTE chunks an encoder produces
+//! for a spec, with the leaf spec each chunk is encoded with. The plan is
+//! what [`crate::blte::BlteBuilder::from_espec`] consumes to rebuild BLTE
+//! files with the same layout as the originals, and the layout the size
+//! estimator charges overhead for.
+
+use super::types::ESpec;
+
+/// One BLTE chunk of content encoded with a spec
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockPlan<'a> {
+    /// Offset of the block in the raw content
+    pub offset: u64,
+    /// Raw size of the block
+    pub size: u64,
+    /// Spec the block is encoded with, never a block table
+    pub spec: &'a ESpec,
+}
+
+impl ESpec {
+    /// Split `data_len` bytes of content into the chunks this spec encodes
+    ///
+    /// Blocks follow the layout described at [`ESpec::estimate_encoded_size`].
+    /// Block tables nested in a block are flattened into their own blocks.
+    /// Empty content has no blocks under a block table and a single empty
+    /// block otherwise.
+    #[must_use]
+    pub fn plan(&self, data_len: u64) -> Vec<BlockPlan<'_>> {
+        let mut plan = Vec::new();
+        plan_into(self, 0, data_len, &mut plan);
+        plan
+    }
+
+    /// Whether content encoded with this spec carries a chunk table
+    ///
+    /// Block tables always write one, even for a single block, and
+    /// encrypted content requires one.
+    #[must_use]
+    pub const fn has_chunk_table(&self) -> bool {
+        matches!(self, Self::BlockTable { .. } | Self::Encrypted { .. })
+    }
+}
+
+fn plan_into<'a>(spec: &'a ESpec, offset: u64, size: u64, plan: &mut Vec<BlockPlan<'a>>) {
+    let mut offset = offset;
+    for (block_spec, block_size) in split_blocks(spec, size) {
+        if matches!(block_spec, ESpec::BlockTable { .. }) {
+            plan_into(block_spec, offset, block_size, plan);
+        } else {
+            plan.push(BlockPlan {
+                offset,
+                size: block_size,
+                spec: block_spec,
+            });
+        }
+        offset += block_size;
+    }
+}
+
+/// Split content into blocks as a block table lays it out
+pub(super) fn split_blocks(spec: &ESpec, raw_size: u64) -> Vec<(&ESpec, u64)> {
+    let ESpec::BlockTable { chunks } = spec else {
+        return vec![(spec, raw_size)];
+    };
+
+    let mut blocks = Vec::new();
+    let mut remaining = raw_size;
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        if remaining == 0 {
+            break;
+        }
+        let is_last = index + 1 == chunks.len();
+
+        match &chunk.size_spec {
+            // `*=spec` takes the rest of the content
+            None => {
+                blocks.push((&chunk.spec, remaining));
+                remaining = 0;
+            }
+            // `*N=spec` spreads the rest over N blocks
+            Some(size_spec) if size_spec.size == 0 => {
+                let count = u64::from(size_spec.count.unwrap_or(1).max(1));
+                let block_size = remaining.div_ceil(count);
+                while remaining > 0 {
+                    let size = block_size.min(remaining);
+                    blocks.push((&chunk.spec, size));
+                    remaining -= size;
+                }
+            }
+            Some(size_spec) => {
+                let repeat = match size_spec.count {
+                    Some(count) => u64::from(count),
+                    None if is_last => u64::MAX,
+                    None => 1,
+                };
+                let mut emitted = 0;
+                while emitted < repeat && remaining > 0 {
+                    let size = size_spec.size.min(remaining);
+                    blocks.push((&chunk.spec, size));
+                    remaining -= size;
+                    emitted += 1;
+                }
+            }
+        }
+    }
+
+    if remaining > 0
+        && let Some(last) = chunks.last()
+    {
+        blocks.push((&last.spec, remaining));
+    }
+
+    blocks
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn layout(spec: &str, data_len: u64) -> Vec<(u64, u64, String)> {
+        ESpec::parse(spec)
+            .expect("ESpec should parse")
+            .plan(data_len)
+            .into_iter()
+            .map(|block| (block.offset, block.size, block.spec.to_string()))
+            .collect()
+    }
+
+    fn sizes(spec: &str, data_len: u64) -> Vec<u64> {
+        layout(spec, data_len)
+            .into_iter()
+            .map(|(_, size, _)| size)
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_encoding_file_espec() {
+        let data_len = 164 + 16 * 1024 * 565 + 1656;
+        let plan = layout("b:{164=z,16K*565=z,1656=z}", data_len);
+        assert_eq!(plan.len(), 567);
+        assert_eq!(plan[0], (0, 164, "z".to_string()));
+        assert_eq!(plan[1], (164, 16 * 1024, "z".to_string()));
+        assert_eq!(plan[566], (data_len - 1656, 1656, "z".to_string()));
+
+        // Blocks are contiguous and cover the content
+        let mut offset = 0;
+        for (block_offset, size, _) in &plan {
+            assert_eq!(*block_offset, offset);
+            offset += size;
+        }
+        assert_eq!(offset, data_len);
+    }
+
+    #[test]
+    fn test_plan_repeats_and_remainder() {
+        assert_eq!(
+            sizes("b:{256K*=z}", 600 * 1024),
+            [256 * 1024, 256 * 1024, 88 * 1024]
+        );
+        assert_eq!(sizes("b:{1K=n,*=z:9}", 5000), [1024, 3976]);
+        assert_eq!(sizes("b:{*3=z}", 10), [4, 4, 2]);
+        // A sized last block repeats
+        assert_eq!(sizes("b:{100=n,200=z}", 600), [100, 200, 200, 100]);
+        // Content past the declared blocks uses the last block's spec
+        assert_eq!(sizes("b:{100=n,200*1=z}", 1000), [100, 200, 700]);
+        // Content shorter than the declared blocks ends early
+        assert_eq!(sizes("b:{100=n,200=z}", 150), [100, 50]);
+    }
+
+    #[test]
+    fn test_plan_encrypted_blocks_keep_spec() {
+        let plan = layout("b:{1008=z,59=e:{57A612DDA061E38E,b0ea3333,z}}", 1067);
+        assert_eq!(
+            plan,
+            [
+                (0, 1008, "z".to_string()),
+                (1008, 59, "e:{57A612DDA061E38E,b0ea3333,z}".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_without_block_table() {
+        assert_eq!(layout("z:9", 1000), [(0, 1000, "z:9".to_string())]);
+        assert_eq!(layout("n", 0), [(0, 0, "n".to_string())]);
+        assert!(layout("b:{256K*=n}", 0).is_empty());
+
+        assert!(
+            !ESpec::parse("z")
+                .expect("ESpec should parse")
+                .has_chunk_table()
+        );
+        assert!(
+            ESpec::parse("b:{*=z}")
+                .expect("ESpec should parse")
+                .has_chunk_table()
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Differences between the install manifests of two builds
+//!
+//! Upgrading an installation from one build to another only needs the
+//! installed files that changed. [`diff_manifests`] matches the entries of
+//! both manifests by path and sorts them into added, removed and changed
+//! files. Entries are matched by path with `\` and `/` treated alike; a
+//! file is changed when its content key differs.
+
+use crate::install::{entry::InstallFileEntry, manifest::InstallManifest};
+use cascette_crypto::ContentKey;
+use std::collections::{HashMap, HashSet};
+
+/// A file installed by both builds with different content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangedFile<'a> {
+    /// Entry in the old manifest
+    pub old: &'a InstallFileEntry,
+    /// Entry in the new manifest
+    pub new: &'a InstallFileEntry,
+}
+
+/// Installed files that differ between two builds
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstallDiff<'a> {
+    /// Files only the new build installs, in new manifest order
+    pub added: Vec<&'a InstallFileEntry>,
+    /// Files only the old build installs, in old manifest order
+    pub removed: Vec<&'a InstallFileEntry>,
+    /// Files whose content key changed, in new manifest order
+    pub changed: Vec<ChangedFile<'a>>,
+}
+
+impl<'a> InstallDiff<'a> {
+    /// Check whether both builds install the same files
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Entries of the new build that an upgrade has to download
+    ///
+    /// Added files come first, then changed ones.
+    pub fn files_to_download(&self) -> impl Iterator<Item = &'a InstallFileEntry> + '_ {
+        self.added
+            .iter()
+            .copied()
+            .chain(self.changed.iter().map(|change| change.new))
+    }
+
+    /// Total download size of the added and changed files
+    ///
+    /// `encoded_size` looks up the size of a file on the CDN by content
+    /// key, typically through the build's encoding file. Files it does not
+    /// know are counted with their installed size.
+    pub fn download_size<F>(&self, encoded_size: F) -> u64
+    where
+        F: Fn(&ContentKey) -> Option<u64>,
+    {
+        self.files_to_download()
+            .map(|entry| {
+                encoded_size(&entry.content_key).unwrap_or_else(|| u64::from(entry.file_size))
+            })
+            .sum()
+    }
+
+    /// Installed size of the added and changed files
+    pub fn install_size(&self) -> u64 {
+        self.files_to_download()
+            .map(|entry| u64::from(entry.file_size))
+            .sum()
+    }
+}
+
+/// Compare the files two install manifests install for `tags`
+///
+/// Only files carrying every tag in `tags` are compared, as with
+/// [`InstallManifest::get_files_for_tags`]; a tag missing from one
+/// manifest selects none of its files. An empty tag list compares all
+/// files. When a manifest lists a path twice, its first entry is used.
+pub fn diff_manifests<'a>(
+    old: &'a InstallManifest,
+    new: &'a InstallManifest,
+    tags: &[&str],
+) -> InstallDiff<'a> {
+    let old_files = select(old, tags);
+    let new_files = select(new, tags);
+
+    let mut old_by_path: HashMap<String, &InstallFileEntry> = HashMap::new();
+    for entry in &old_files {
+        old_by_path.entry(entry.normalized_path()).or_insert(entry);
+    }
+
+    let mut diff = InstallDiff::default();
+    let mut seen: HashSet<String> = HashSet::new();
+    for entry in &new_files {
+        let path = entry.normalized_path();
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+        match old_by_path.get(&path) {
+            None => diff.added.push(entry),
+            Some(old_entry) if old_entry.content_key != entry.content_key => {
+                diff.changed.push(ChangedFile {
+                    old: old_entry,
+                    new: entry,
+                });
+            }
+            Some(_) => {}
+        }
+    }
+
+    for entry in &old_files {
+        let path = entry.normalized_path();
+        if !seen.contains(&path) && old_by_path.remove(&path).is_some() {
+            diff.removed.push(entry);
+        }
+    }
+
+    diff
+}
+
+/// Entries of `manifest` carrying every tag in `tags`
+fn select<'a>(manifest: &'a InstallManifest, tags: &[&str]) -> Vec<&'a InstallFileEntry> {
+    if tags.is_empty() {
+        return manifest.entries.iter().collect();
+    }
+    manifest
+        .get_files_for_tags(tags)
+        .into_iter()
+        .map(|(_, entry)| entry)
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::install::{InstallManifestBuilder, TagType};
+
+    fn ckey(byte: u8) -> ContentKey {
+        ContentKey::from_bytes([byte; 16])
+    }
+
+    /// Manifest with Windows/OSX and enUS/deDE tags and `(path, key, tags)` files
+    fn manifest(files: &[(&str, u8, &[&str])]) -> InstallManifest {
+        let mut builder = InstallManifestBuilder::new()
+            .add_tag("Windows".to_string(), TagType::Platform)
+            .add_tag("OSX".to_string(), TagType::Platform)
+            .add_tag("enUS".to_string(), TagType::Locale)
+            .add_tag("deDE".to_string(), TagType::Locale);
+        for &(path, key, tags) in files {
+            builder = builder
+                .add_file_with_tags(path.to_string(), ckey(key), 100 + u32::from(key), tags)
+                .expect("File should be added");
+        }
+        builder.build().expect("Manifest should build")
+    }
+
+    fn paths<'a>(entries: impl IntoIterator<Item = &'a InstallFileEntry>) -> Vec<&'a str> {
+        entries
+            .into_iter()
+            .map(|entry| entry.path.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_added_removed_and_changed() {
+        let old = manifest(&[
+            ("Wow.exe", 1, &["Windows", "enUS"]),
+            ("Data\\old.dat", 2, &["Windows", "enUS"]),
+            ("Utils/tool.dll", 3, &["Windows", "enUS"]),
+        ]);
+        let new = manifest(&[
+            ("Wow.exe", 11, &["Windows", "enUS"]),
+            ("Utils\\tool.dll", 3, &["Windows", "enUS"]),
+            ("Data/new.dat", 4, &["Windows", "enUS"]),
+        ]);
+
+        let diff = diff_manifests(&old, &new, &[]);
+        assert_eq!(paths(diff.added.iter().copied()), ["Data/new.dat"]);
+        assert_eq!(paths(diff.removed.iter().copied()), ["Data\\old.dat"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].old.content_key, ckey(1));
+        assert_eq!(diff.changed[0].new.content_key, ckey(11));
+        assert_eq!(paths(diff.files_to_download()), ["Data/new.dat", "Wow.exe"]);
+
+        assert_eq!(diff.install_size(), 104 + 111);
+        // Known encoded sizes replace installed sizes
+        assert_eq!(
+            diff.download_size(|key| (*key == ckey(11)).then_some(50)),
+            104 + 50
+        );
+        assert!(diff_manifests(&new, &new, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_tag_filter_restricts_compared_files() {
+        let old = manifest(&[
+            ("Wow.exe", 1, &["Windows", "enUS"]),
+            ("World of Warcraft.app", 2, &["OSX", "enUS"]),
+            ("Fonts/de.ttf", 3, &["Windows", "deDE"]),
+        ]);
+        let new = manifest(&[
+            ("Wow.exe", 11, &["Windows", "enUS"]),
+            ("World of Warcraft.app", 12, &["OSX", "enUS"]),
+            ("Fonts/de.ttf", 13, &["Windows", "deDE"]),
+            ("Fonts/en.ttf", 14, &["Windows", "enUS"]),
+        ]);
+
+        let diff = diff_manifests(&old, &new, &["Windows", "enUS"]);
+        assert_eq!(paths(diff.files_to_download()), ["Fonts/en.ttf", "Wow.exe"]);
+        assert!(diff.removed.is_empty());
+
+        let diff = diff_manifests(&old, &new, &["OSX", "deDE"]);
+        assert!(diff.is_empty());
+
+        // A file that gains a tag the filter needs counts as added
+        let retagged = manifest(&[("Fonts/de.ttf", 3, &["Windows", "deDE", "enUS"])]);
+        let diff = diff_manifests(&old, &retagged, &["enUS"]);
+        assert_eq!(paths(diff.added.iter().copied()), ["Fonts/de.ttf"]);
+        assert_eq!(
+            paths(diff.removed.iter().copied()),
+            ["Wow.exe", "World of Warcraft.app"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_tag_selects_nothing_from_that_manifest() {
+        let old = manifest(&[("Wow.exe", 1, &["Windows"])]);
+        let new = InstallManifestBuilder::new()
+            .add_tag("Windows".to_string(), TagType::Platform)
+            .add_tag("frFR".to_string(), TagType::Locale)
+            .add_file_with_tags("Wow.exe".to_string(), ckey(1), 101, &["Windows", "frFR"])
+            .expect("File should be added")
+            .build()
+            .expect("Manifest should build");
+
+        let diff = diff_manifests(&old, &new, &["Windows", "frFR"]);
+        assert_eq!(paths(diff.added.iter().copied()), ["Wow.exe"]);
+        assert!(diff.removed.is_empty());
+    }
+}
@@ -7,6 +7,7 @@ use crate::install::{
     tag::InstallTag,
 };
 use binrw::{BinRead, BinWrite, io::Cursor};
+use cascette_crypto::ContentKey;
 
 /// Complete install manifest containing header, tags, and file entries
 ///
@@ -209,6 +210,67 @@ impl InstallManifest {
             .sum()
     }
 
+    /// Select the files matching a set of tags, with their total size
+    ///
+    /// [`TagMatch::All`] keeps files whose bitmask has every tag's bit set,
+    /// [`TagMatch::Any`] keeps files with at least one of them. As with
+    /// [`get_files_for_tags`](Self::get_files_for_tags), an unknown tag
+    /// makes `All` select nothing, while `Any` ignores it. An empty tag list
+    /// selects nothing in either mode.
+    pub fn files_for_tags(&self, tag_names: &[&str], mode: TagMatch) -> TagSelection<'_> {
+        let files = match mode {
+            TagMatch::All => self.get_files_for_tags(tag_names),
+            TagMatch::Any => self.get_files_for_any_tag(tag_names),
+        };
+        let total_size = files
+            .iter()
+            .map(|(_, entry)| u64::from(entry.file_size))
+            .sum();
+
+        TagSelection { files, total_size }
+    }
+
+    /// Estimate the size of installing the files carrying every tag in `tags`
+    ///
+    /// Files are selected as with
+    /// [`get_files_for_tags`](Self::get_files_for_tags), except that an
+    /// empty tag list selects every file. Install manifests only record
+    /// installed sizes, so the compressed total assumes each file is stored
+    /// unencoded; use [`size_estimate_with`](Self::size_estimate_with) to
+    /// supply encoded sizes from the encoding file.
+    pub fn size_estimate(&self, tags: &[&str]) -> SizeEstimate {
+        self.size_estimate_with(tags, |_| None)
+    }
+
+    /// Estimate an install size, looking up compressed sizes by content key
+    ///
+    /// `encoded_size` returns the size of a file on the CDN, typically from
+    /// the build's encoding file. Files it does not know are counted with
+    /// their installed size.
+    pub fn size_estimate_with<F>(&self, tags: &[&str], encoded_size: F) -> SizeEstimate
+    where
+        F: Fn(&ContentKey) -> Option<u64>,
+    {
+        let files: Vec<&InstallFileEntry> = if tags.is_empty() {
+            self.entries.iter().collect()
+        } else {
+            self.get_files_for_tags(tags)
+                .into_iter()
+                .map(|(_, entry)| entry)
+                .collect()
+        };
+
+        let mut estimate = SizeEstimate::default();
+        for entry in files {
+            let installed = u64::from(entry.file_size);
+            estimate.file_count = estimate.file_count.saturating_add(1);
+            estimate.total_uncompressed_bytes += installed;
+            estimate.total_compressed_bytes +=
+                encoded_size(&entry.content_key).unwrap_or(installed);
+        }
+        estimate
+    }
+
     /// Get statistics about the manifest
     pub fn stats(&self) -> InstallStats {
         let total_size = self.total_install_size();
@@ -280,6 +342,76 @@ impl InstallManifest {
     }
 }
 
+/// How [`InstallManifest::files_for_tags`] combines several tags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagMatch {
+    /// File must have every tag (e.g. "Windows" and "enUS")
+    All,
+    /// File must have at least one tag (e.g. "enUS" or "deDE")
+    Any,
+}
+
+/// Files selected by [`InstallManifest::files_for_tags`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagSelection<'a> {
+    /// Selected files with their index in the manifest
+    pub files: Vec<(usize, &'a InstallFileEntry)>,
+    /// Total size of the selected files in bytes
+    pub total_size: u64,
+}
+
+/// Download and disk space needed for a set of install files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SizeEstimate {
+    /// Number of files selected
+    pub file_count: u32,
+    /// Bytes to download from the CDN
+    pub total_compressed_bytes: u64,
+    /// Bytes the files take once installed
+    pub total_uncompressed_bytes: u64,
+}
+
+impl SizeEstimate {
+    /// Format the estimate for display, e.g. "3 files, 1.2 GiB download, 2.5 GiB installed"
+    pub fn display_human(&self) -> String {
+        format!(
+            "{} files, {} download, {} installed",
+            self.file_count,
+            format_bytes(self.total_compressed_bytes),
+            format_bytes(self.total_uncompressed_bytes)
+        )
+    }
+}
+
+impl std::ops::Add for SizeEstimate {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            file_count: self.file_count.saturating_add(other.file_count),
+            total_compressed_bytes: self.total_compressed_bytes + other.total_compressed_bytes,
+            total_uncompressed_bytes: self.total_uncompressed_bytes
+                + other.total_uncompressed_bytes,
+        }
+    }
+}
+
+/// Format a byte count with binary units and one decimal, e.g. "1.2 GiB"
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
 /// Statistics about an install manifest
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct InstallStats {
@@ -309,7 +441,6 @@ impl crate::CascFormat for InstallManifest {
 mod tests {
     use super::*;
     use crate::install::{builder::InstallManifestBuilder, tag::TagType};
-    use cascette_crypto::ContentKey;
 
     fn create_test_manifest() -> InstallManifest {
         InstallManifestBuilder::new()
@@ -458,6 +589,130 @@ mod tests {
         assert_eq!(intersection_size, 1024); // Only file 0 has both
     }
 
+    #[test]
+    fn test_files_for_tags_match_modes() {
+        let manifest = create_test_manifest();
+
+        let all = manifest.files_for_tags(&["Windows", "x86_64"], TagMatch::All);
+        assert_eq!(all.files.len(), 1);
+        assert_eq!(all.files[0].0, 0);
+        assert_eq!(all.total_size, 1024);
+
+        let any = manifest.files_for_tags(&["x86_64", "enUS"], TagMatch::Any);
+        let indices: Vec<usize> = any.files.iter().map(|(index, _)| *index).collect();
+        assert_eq!(indices, [0, 2]);
+        assert_eq!(any.total_size, 1024 + 4096);
+
+        // Unknown tags empty an intersection but are ignored in a union
+        let all = manifest.files_for_tags(&["Windows", "NonExistent"], TagMatch::All);
+        assert!(all.files.is_empty());
+        let any = manifest.files_for_tags(&["Windows", "NonExistent"], TagMatch::Any);
+        assert_eq!(any.total_size, 1024 + 2048);
+
+        assert!(manifest.files_for_tags(&[], TagMatch::Any).files.is_empty());
+    }
+
+    #[test]
+    fn test_files_for_tags_with_empty_tag() {
+        let manifest = InstallManifestBuilder::new()
+            .add_tag("Windows".to_string(), TagType::Platform)
+            .add_tag("deDE".to_string(), TagType::Locale)
+            .add_file(
+                "Wow.exe".to_string(),
+                ContentKey::from_hex("0123456789abcdef0123456789abcdef")
+                    .expect("Operation should succeed"),
+                512,
+            )
+            .associate_file_with_tag(0, "Windows")
+            .expect("Operation should succeed")
+            .build()
+            .expect("Operation should succeed");
+        assert!(manifest.find_tag("deDE").is_some());
+
+        // The tag exists but no file has its bit set
+        let all = manifest.files_for_tags(&["Windows", "deDE"], TagMatch::All);
+        assert!(all.files.is_empty());
+        assert_eq!(all.total_size, 0);
+
+        let any = manifest.files_for_tags(&["Windows", "deDE"], TagMatch::Any);
+        assert_eq!(any.files.len(), 1);
+        assert_eq!(any.total_size, 512);
+
+        let only = manifest.files_for_tags(&["deDE"], TagMatch::Any);
+        assert!(only.files.is_empty());
+        assert_eq!(only.total_size, 0);
+    }
+
+    #[test]
+    fn test_size_estimate() {
+        let manifest = create_test_manifest();
+
+        let all = manifest.size_estimate(&[]);
+        assert_eq!(all.file_count, 3);
+        assert_eq!(all.total_uncompressed_bytes, 1024 + 2048 + 4096);
+        // Without encoded sizes every file counts as stored unencoded
+        assert_eq!(all.total_compressed_bytes, all.total_uncompressed_bytes);
+
+        let windows = manifest.size_estimate(&["Windows", "x86_64"]);
+        assert_eq!(windows.file_count, 1);
+        assert_eq!(windows.total_uncompressed_bytes, 1024);
+
+        assert_eq!(
+            manifest.size_estimate(&["Windows", "NonExistent"]),
+            SizeEstimate::default()
+        );
+    }
+
+    #[test]
+    fn test_size_estimate_disjoint_tags_sum_to_total() {
+        let manifest = InstallManifestBuilder::new()
+            .add_tag("Windows".to_string(), TagType::Platform)
+            .add_tag("OSX".to_string(), TagType::Platform)
+            .add_file("Wow.exe".to_string(), ContentKey::from_bytes([1; 16]), 3000)
+            .add_file("Wow.app".to_string(), ContentKey::from_bytes([2; 16]), 5000)
+            .add_file(
+                "Wow-64.exe".to_string(),
+                ContentKey::from_bytes([3; 16]),
+                7000,
+            )
+            .associate_file_with_tag(0, "Windows")
+            .expect("Operation should succeed")
+            .associate_file_with_tag(1, "OSX")
+            .expect("Operation should succeed")
+            .associate_file_with_tag(2, "Windows")
+            .expect("Operation should succeed")
+            .build()
+            .expect("Operation should succeed");
+        let encoded = |key: &ContentKey| (key.as_bytes()[0] != 3).then_some(1000);
+
+        let windows = manifest.size_estimate_with(&["Windows"], encoded);
+        let osx = manifest.size_estimate_with(&["OSX"], encoded);
+        let all = manifest.size_estimate_with(&[], encoded);
+
+        assert_eq!(windows.file_count, 2);
+        assert_eq!(windows.total_compressed_bytes, 1000 + 7000);
+        assert_eq!(windows + osx, all);
+        assert_eq!(all.total_compressed_bytes, 1000 + 1000 + 7000);
+        assert_eq!(all.total_uncompressed_bytes, 15000);
+    }
+
+    #[test]
+    fn test_size_estimate_display_human() {
+        let estimate = SizeEstimate {
+            file_count: 3,
+            total_compressed_bytes: 1_288_490_189,
+            total_uncompressed_bytes: 512,
+        };
+        assert_eq!(
+            estimate.display_human(),
+            "3 files, 1.2 GiB download, 512 B installed"
+        );
+
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(1536 * 1024), "1.5 MiB");
+        assert_eq!(format_bytes(5 * 1024_u64.pow(4)), "5.0 TiB");
+    }
+
     #[test]
     fn test_manifest_stats() {
         let manifest = create_test_manifest();
@@ -129,6 +129,7 @@
 //! ```
 
 pub mod builder;
+pub mod diff;
 pub mod entry;
 pub mod error;
 pub mod header;
@@ -137,10 +138,11 @@ pub mod tag;
 
 // Re-export main types
 pub use builder::InstallManifestBuilder;
+pub use diff::{ChangedFile, InstallDiff, diff_manifests};
 pub use entry::InstallFileEntry;
 pub use error::{InstallError, Result};
 pub use header::InstallHeader;
-pub use manifest::InstallManifest;
+pub use manifest::{InstallManifest, SizeEstimate, TagMatch, TagSelection};
 pub use tag::{InstallTag, TagType};
 
 #[cfg(test)]
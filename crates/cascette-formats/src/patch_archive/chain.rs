@@ -0,0 +1,429 @@
+//! Patch chain resolution and application
+//!
+//! A patch archive only describes single steps: a patch from one source
+//! file to one target file. Moving a file across several builds means
+//! applying a sequence of those steps, each one's target being the next
+//! one's source. [`PatchChainResolver`] collects the steps of one or more
+//! archives and finds the shortest such sequence, and [`apply_chain`]
+//! applies it with each intermediate result checked against the content
+//! key the archive records for it.
+//!
+//! Sources are recorded by encoding key and targets by content key. For a
+//! chain to continue past a target, the resolver needs to know the
+//! encoding key of that target, given with
+//! [`add_key_alias`](PatchChainResolver::add_key_alias), unless the
+//! archives use the same key for both.
+
+use crate::patch_archive::{PatchArchive, PatchArchiveError, PatchArchiveResult};
+use crate::zbsdiff;
+use cascette_crypto::EncodingKey;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// One patch step of a chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatchStep {
+    /// Key of the file the patch applies to
+    pub source_key: [u8; 16],
+    /// Content key of the patched file
+    pub target_ckey: [u8; 16],
+    /// Decoded size of the patched file
+    pub target_size: u64,
+    /// Encoding key of the patch data on CDN
+    pub patch_ekey: [u8; 16],
+    /// Size of the patch data in bytes
+    pub patch_size: u32,
+}
+
+/// A sequence of patch steps from a source file to a target file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchChain {
+    steps: Vec<PatchStep>,
+}
+
+impl PatchChain {
+    /// Steps in the order they are applied
+    pub fn steps(&self) -> &[PatchStep] {
+        &self.steps
+    }
+
+    /// Number of steps
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Whether the chain has no steps, i.e. the source is the target
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Total size of the patch data to fetch
+    pub fn total_patch_size(&self) -> u64 {
+        self.steps
+            .iter()
+            .map(|step| u64::from(step.patch_size))
+            .sum()
+    }
+}
+
+/// Graph of the patch steps recorded in patch archives
+#[derive(Debug, Clone, Default)]
+pub struct PatchChainResolver {
+    /// Steps by source key, in the order they were added
+    by_source: HashMap<[u8; 16], Vec<PatchStep>>,
+    /// Encoding keys of target files, by content key
+    aliases: HashMap<[u8; 16], [u8; 16]>,
+}
+
+impl PatchChainResolver {
+    /// Create an empty resolver
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a resolver from the steps of several archives
+    pub fn from_archives<'a>(archives: impl IntoIterator<Item = &'a PatchArchive>) -> Self {
+        let mut resolver = Self::new();
+        for archive in archives {
+            resolver.add_archive(archive);
+        }
+        resolver
+    }
+
+    /// Add every patch step of `archive`
+    pub fn add_archive(&mut self, archive: &PatchArchive) {
+        for entry in archive.all_file_entries() {
+            for patch in &entry.patches {
+                self.add_step(PatchStep {
+                    source_key: patch.source_ekey,
+                    target_ckey: entry.target_ckey,
+                    target_size: entry.decoded_size,
+                    patch_ekey: patch.patch_ekey,
+                    patch_size: patch.patch_size,
+                });
+            }
+        }
+    }
+
+    /// Add a single patch step
+    pub fn add_step(&mut self, step: PatchStep) {
+        let steps = self.by_source.entry(step.source_key).or_default();
+        if !steps.contains(&step) {
+            steps.push(step);
+        }
+    }
+
+    /// Record that the file with content key `ckey` has encoding key `ekey`
+    ///
+    /// Steps whose source is `ekey` then continue chains that reach `ckey`.
+    pub fn add_key_alias(&mut self, ckey: [u8; 16], ekey: [u8; 16]) {
+        self.aliases.insert(ckey, ekey);
+    }
+
+    /// Number of patch steps known to the resolver
+    pub fn step_count(&self) -> usize {
+        self.by_source.values().map(Vec::len).sum()
+    }
+
+    /// Find the chain with the fewest steps from `source` to `target_ckey`
+    ///
+    /// `source` is the key of the file to start from, as recorded in the
+    /// archives' source keys. Returns `None` if no chain exists.
+    pub fn find_chain(&self, source: &[u8; 16], target_ckey: &[u8; 16]) -> Option<PatchChain> {
+        if source == target_ckey {
+            return Some(PatchChain { steps: Vec::new() });
+        }
+
+        // Breadth-first search, remembering the file and step that reached
+        // each file
+        let mut reached_by: HashMap<[u8; 16], ([u8; 16], PatchStep)> = HashMap::new();
+        let mut visited = HashSet::from([*source]);
+        let mut queue = VecDeque::from([*source]);
+
+        while let Some(node) = queue.pop_front() {
+            for step in self.steps_from(&node) {
+                if !visited.insert(step.target_ckey) {
+                    continue;
+                }
+                reached_by.insert(step.target_ckey, (node, *step));
+                if &step.target_ckey == target_ckey {
+                    return Some(Self::unwind(&reached_by, target_ckey));
+                }
+                queue.push_back(step.target_ckey);
+            }
+        }
+        None
+    }
+
+    /// Steps that apply to the file reached as `node`
+    fn steps_from(&self, node: &[u8; 16]) -> impl Iterator<Item = &PatchStep> {
+        let alias = self.aliases.get(node).filter(|alias| *alias != node);
+        self.by_source
+            .get(node)
+            .into_iter()
+            .chain(alias.and_then(|alias| self.by_source.get(alias)))
+            .flatten()
+    }
+
+    /// Walk back from `target` to the search's source
+    fn unwind(
+        reached_by: &HashMap<[u8; 16], ([u8; 16], PatchStep)>,
+        target: &[u8; 16],
+    ) -> PatchChain {
+        let mut steps = Vec::new();
+        let mut node = *target;
+        while let Some((parent, step)) = reached_by.get(&node) {
+            steps.push(*step);
+            node = *parent;
+        }
+        steps.reverse();
+        PatchChain { steps }
+    }
+}
+
+/// Apply `chain` to `old_data`, fetching each step's patch with `fetch_patch`
+///
+/// `fetch_patch` returns the decoded ZBSDIFF1 patch data for a patch
+/// encoding key. The output of every step is checked against the content
+/// key the archive records for it before the next step runs.
+///
+/// # Errors
+///
+/// Returns [`PatchArchiveError::ChainStepFailed`] naming the step that
+/// failed, wrapping the fetch, patch or checksum error.
+pub fn apply_chain<F, E>(
+    old_data: &[u8],
+    chain: &PatchChain,
+    mut fetch_patch: F,
+) -> PatchArchiveResult<Vec<u8>>
+where
+    F: FnMut(&EncodingKey) -> Result<Vec<u8>, E>,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let mut data = old_data.to_vec();
+    for (index, step) in chain.steps().iter().enumerate() {
+        data = apply_step(&data, step, &mut fetch_patch).map_err(|err| {
+            PatchArchiveError::ChainStepFailed {
+                step: index,
+                source_key: hex::encode(step.source_key),
+                target_ckey: hex::encode(step.target_ckey),
+                reason: Box::new(err),
+            }
+        })?;
+    }
+    Ok(data)
+}
+
+fn apply_step<F, E>(
+    data: &[u8],
+    step: &PatchStep,
+    fetch_patch: &mut F,
+) -> PatchArchiveResult<Vec<u8>>
+where
+    F: FnMut(&EncodingKey) -> Result<Vec<u8>, E>,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let patch = fetch_patch(&EncodingKey::from_bytes(step.patch_ekey))
+        .map_err(|err| PatchArchiveError::PatchFetchFailed(err.into()))?;
+    let patched = zbsdiff::apply_patch_memory(data, &patch)
+        .map_err(|err| PatchArchiveError::ZbsdiffError(err.to_string()))?;
+
+    let actual: [u8; 16] = md5::compute(&patched).into();
+    if actual != step.target_ckey {
+        return Err(PatchArchiveError::PatchVerificationFailed {
+            expected: step.target_ckey,
+            actual,
+        });
+    }
+    Ok(patched)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::patch_archive::PatchArchiveBuilder;
+
+    /// Four builds of a file, each a small edit of the previous one
+    fn versions() -> Vec<Vec<u8>> {
+        let mut data: Vec<u8> = (0..2048u32).map(|i| (i % 251) as u8).collect();
+        let mut versions = vec![data.clone()];
+        for build in 1..4u8 {
+            data[usize::from(build) * 100..usize::from(build) * 100 + 16].fill(build);
+            data.extend_from_slice(&[build; 32]);
+            versions.push(data.clone());
+        }
+        versions
+    }
+
+    fn ckey(data: &[u8]) -> [u8; 16] {
+        md5::compute(data).into()
+    }
+
+    fn patch_ekey(step: u8) -> [u8; 16] {
+        [0xe0 | step; 16]
+    }
+
+    /// An archive with the patches from `versions[from]` to `versions[from + 1]`
+    fn archive(versions: &[Vec<u8>], steps: &[usize]) -> PatchArchive {
+        let mut builder = PatchArchiveBuilder::new();
+        for &from in steps {
+            let (old, new) = (&versions[from], &versions[from + 1]);
+            builder.add_file_entry(
+                ckey(new),
+                new.len() as u64,
+                vec![(ckey(old), old.len() as u64, patch_ekey(from as u8), 100, 0)],
+            );
+        }
+        builder.build_archive().expect("Archive should build")
+    }
+
+    /// Patch data by patch encoding key for each step
+    fn patches(versions: &[Vec<u8>]) -> HashMap<[u8; 16], Vec<u8>> {
+        versions
+            .windows(2)
+            .enumerate()
+            .map(|(from, pair)| {
+                let patch = zbsdiff::ZbsdiffBuilder::new(pair[0].clone(), pair[1].clone())
+                    .build()
+                    .expect("Patch should build");
+                (patch_ekey(from as u8), patch)
+            })
+            .collect()
+    }
+
+    fn fetch_from(
+        patches: &HashMap<[u8; 16], Vec<u8>>,
+    ) -> impl FnMut(&EncodingKey) -> Result<Vec<u8>, &'static str> + '_ {
+        |key| {
+            patches
+                .get(key.as_bytes())
+                .cloned()
+                .ok_or("patch not on CDN")
+        }
+    }
+
+    #[test]
+    fn test_three_step_chain_across_archives() {
+        let versions = versions();
+        let resolver = PatchChainResolver::from_archives(&[
+            archive(&versions, &[0, 1]),
+            archive(&versions, &[2]),
+        ]);
+        assert_eq!(resolver.step_count(), 3);
+
+        let chain = resolver
+            .find_chain(&ckey(&versions[0]), &ckey(&versions[3]))
+            .expect("Chain should exist");
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain.total_patch_size(), 300);
+        assert_eq!(chain.steps()[1].target_ckey, ckey(&versions[2]));
+
+        let patches = patches(&versions);
+        let patched =
+            apply_chain(&versions[0], &chain, fetch_from(&patches)).expect("Chain should apply");
+        assert_eq!(patched, versions[3]);
+
+        // There is no way back to an older build
+        assert!(
+            resolver
+                .find_chain(&ckey(&versions[3]), &ckey(&versions[0]))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_shortest_chain_is_chosen() {
+        let versions = versions();
+        let mut resolver = PatchChainResolver::from_archives(&[archive(&versions, &[0, 1, 2])]);
+        resolver.add_step(PatchStep {
+            source_key: ckey(&versions[1]),
+            target_ckey: ckey(&versions[3]),
+            target_size: versions[3].len() as u64,
+            patch_ekey: [0xaa; 16],
+            patch_size: 100,
+        });
+
+        let chain = resolver
+            .find_chain(&ckey(&versions[0]), &ckey(&versions[3]))
+            .expect("Chain should exist");
+        let targets: Vec<_> = chain.steps().iter().map(|step| step.target_ckey).collect();
+        assert_eq!(targets, [ckey(&versions[1]), ckey(&versions[3])]);
+    }
+
+    #[test]
+    fn test_chain_continues_through_key_alias() {
+        // Sources recorded by encoding keys that differ from content keys
+        let ekey = |index: u8| [0x10 | index; 16];
+        let mut resolver = PatchChainResolver::new();
+        for from in 0..2u8 {
+            resolver.add_step(PatchStep {
+                source_key: ekey(from),
+                target_ckey: [from + 1; 16],
+                target_size: 0,
+                patch_ekey: patch_ekey(from),
+                patch_size: 100,
+            });
+        }
+        assert!(resolver.find_chain(&ekey(0), &[2; 16]).is_none());
+
+        resolver.add_key_alias([1; 16], ekey(1));
+        let chain = resolver
+            .find_chain(&ekey(0), &[2; 16])
+            .expect("Chain should continue through the alias");
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain.steps()[1].source_key, ekey(1));
+    }
+
+    #[test]
+    fn test_corrupted_middle_patch_names_its_step() {
+        let versions = versions();
+        let resolver = PatchChainResolver::from_archives(&[archive(&versions, &[0, 1, 2])]);
+        let chain = resolver
+            .find_chain(&ckey(&versions[0]), &ckey(&versions[3]))
+            .expect("Chain should exist");
+        let middle = ckey(&versions[2]);
+
+        // Patch data that is not a ZBSDIFF1 patch
+        let mut patches = patches(&versions);
+        patches
+            .get_mut(&patch_ekey(1))
+            .expect("Middle patch")
+            .truncate(10);
+        let err = apply_chain(&versions[0], &chain, fetch_from(&patches))
+            .expect_err("Corrupted patch should fail");
+        assert!(matches!(
+            &err,
+            PatchArchiveError::ChainStepFailed { step: 1, target_ckey, reason, .. }
+                if *target_ckey == hex::encode(middle)
+                    && matches!(**reason, PatchArchiveError::ZbsdiffError(_))
+        ));
+        assert!(err.to_string().contains("step 1"));
+
+        // A valid patch that produces the wrong file
+        let mut patches = self::patches(&versions);
+        patches.insert(
+            patch_ekey(1),
+            zbsdiff::ZbsdiffBuilder::new(versions[1].clone(), b"something else".to_vec())
+                .build()
+                .expect("Patch should build"),
+        );
+        let err = apply_chain(&versions[0], &chain, fetch_from(&patches))
+            .expect_err("Wrong output should fail");
+        assert!(matches!(
+            err,
+            PatchArchiveError::ChainStepFailed { step: 1, reason, .. }
+                if matches!(*reason, PatchArchiveError::PatchVerificationFailed { expected, .. } if expected == middle)
+        ));
+
+        // A patch missing from the CDN
+        let mut patches = self::patches(&versions);
+        patches.remove(&patch_ekey(1));
+        let err = apply_chain(&versions[0], &chain, fetch_from(&patches))
+            .expect_err("Missing patch should fail");
+        assert!(matches!(
+            err,
+            PatchArchiveError::ChainStepFailed { step: 1, reason, .. }
+                if matches!(*reason, PatchArchiveError::PatchFetchFailed(_))
+        ));
+    }
+}
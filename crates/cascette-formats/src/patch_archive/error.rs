@@ -72,6 +72,24 @@ pub enum PatchArchiveError {
     #[error("ZBSDIFF error: {0}")]
     ZbsdiffError(String),
 
+    /// Patch data could not be fetched
+    #[error("patch fetch failed: {0}")]
+    PatchFetchFailed(Box<dyn std::error::Error + Send + Sync>),
+
+    /// A step of a patch chain failed
+    #[error("patch chain step {step} ({source_key} -> {target_ckey}) failed: {reason}")]
+    ChainStepFailed {
+        /// Index of the failed step in the chain
+        step: usize,
+        /// Source key of the step (hex string)
+        source_key: String,
+        /// Target content key of the step (hex string)
+        target_ckey: String,
+        /// Error of the step
+        #[source]
+        reason: Box<PatchArchiveError>,
+    },
+
     /// Header hash mismatch
     ///
     /// Agent.exe computes MD5 of the header region (fixed header + extended
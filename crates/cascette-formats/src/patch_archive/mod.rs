@@ -93,9 +93,30 @@
 //! let archive_data = builder.build()?;
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
+//!
+//! ## Applying a Patch Chain
+//!
+//! ```rust,no_run
+//! use cascette_formats::patch_archive::{PatchArchive, PatchChainResolver, apply_chain};
+//! use cascette_formats::CascFormat;
+//!
+//! let archive = PatchArchive::parse(&std::fs::read("patch_manifest.pa")?)?;
+//! let resolver = PatchChainResolver::from_archives([&archive]);
+//!
+//! let (old_key, new_ckey) = ([0x01; 16], [0x02; 16]);
+//! if let Some(chain) = resolver.find_chain(&old_key, &new_ckey) {
+//!     let old_data = std::fs::read("old_file")?;
+//!     let new_data = apply_chain(&old_data, &chain, |patch_ekey| {
+//!         std::fs::read(format!("patches/{}", patch_ekey.to_hex()))
+//!     })?;
+//!     println!("Patched in {} steps to {} bytes", chain.len(), new_data.len());
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
 
 pub(crate) mod block;
 mod builder;
+mod chain;
 mod compression;
 mod entry;
 mod error;
@@ -105,6 +126,7 @@ mod utils;
 
 pub use block::{FilePatch, PatchArchiveEncodingInfo, PatchBlock, PatchFileEntry};
 pub use builder::PatchArchiveBuilder;
+pub use chain::{PatchChain, PatchChainResolver, PatchStep, apply_chain};
 pub use compression::{
     decompress_patch_data, format_compression_spec, get_compression_at_offset,
     parse_compression_spec,
@@ -94,6 +94,8 @@ pub struct RootLookupTables {
     pub fdid_map: HashMap<FileDataId, Vec<RootEntry>>,
     /// Name hash to entries mapping
     pub name_map: HashMap<u64, Vec<RootEntry>>,
+    /// Name hash to `FileDataID` mapping (first record with the hash wins)
+    pub name_fdid_map: HashMap<u64, FileDataId>,
 }
 
 impl RootLookupTables {
@@ -107,6 +109,7 @@ impl RootLookupTables {
         Self {
             fdid_map: HashMap::with_capacity(fdid_capacity),
             name_map: HashMap::with_capacity(name_capacity),
+            name_fdid_map: HashMap::with_capacity(name_capacity),
         }
     }
 
@@ -118,6 +121,7 @@ impl RootLookupTables {
         // Add to name hash lookup if present
         if let Some(hash) = name_hash {
             self.name_map.entry(hash).or_default().push(entry);
+            self.name_fdid_map.entry(hash).or_insert(fdid);
         }
     }
 
@@ -125,6 +129,7 @@ impl RootLookupTables {
     pub fn clear(&mut self) {
         self.fdid_map.clear();
         self.name_map.clear();
+        self.name_fdid_map.clear();
     }
 
     /// Get total number of `FileDataID` entries
@@ -176,6 +181,16 @@ impl RootLookupTables {
         self.resolve_by_hash(name_hash, locale, content)
     }
 
+    /// Get the `FileDataID` of the record with a name hash
+    pub fn file_data_id_by_hash(&self, name_hash: u64) -> Option<FileDataId> {
+        self.name_fdid_map.get(&name_hash).copied()
+    }
+
+    /// Get the `FileDataID` of the record with a path
+    pub fn file_data_id_by_path(&self, path: &str) -> Option<FileDataId> {
+        self.file_data_id_by_hash(calculate_name_hash(path))
+    }
+
     /// Get all entries for a `FileDataID`
     pub fn get_entries_by_id(&self, fdid: FileDataId) -> Option<&Vec<RootEntry>> {
         self.fdid_map.get(&fdid)
@@ -313,6 +328,40 @@ mod tests {
         assert_ne!(hash1, 0);
     }
 
+    #[test]
+    fn test_name_hash_known_vectors() {
+        // Computed with Bob Jenkins' reference lookup3 hashlittle2 over the
+        // normalized path, as (pc << 32) | pb
+        let vectors = [
+            (
+                "Interface\\Icons\\INV_Misc_QuestionMark.blp",
+                0x9eb5_9e3c_7612_4837,
+            ),
+            ("world/maps/azeroth/azeroth.wdt", 0x19a3_c4cb_c40e_9fa2),
+            ("DBFilesClient/Map.db2", 0x76a4_6b27_7ae9_d377),
+            (
+                "Sound\\Music\\ZoneMusic\\DMF_L70ETC01.mp3",
+                0x0629_dca0_3821_69f3,
+            ),
+            ("Wow.exe", 0x2ad0_65b6_223e_89f5),
+            ("", 0xdead_beef_dead_beef),
+        ];
+
+        for (path, expected) in vectors {
+            assert_eq!(calculate_name_hash(path), expected, "{path}");
+        }
+
+        // Only case and separators are normalized
+        assert_eq!(
+            calculate_name_hash("WORLD\\MAPS\\AZEROTH\\AZEROTH.WDT"),
+            calculate_name_hash("World/Maps/Azeroth/Azeroth.wdt")
+        );
+        assert_ne!(
+            calculate_name_hash("World/Maps/Azeroth/Azeroth.wdt"),
+            calculate_name_hash("/World/Maps/Azeroth/Azeroth.wdt")
+        );
+    }
+
     #[test]
     fn test_file_data_id_delta_encoding() {
         let original_ids = vec![
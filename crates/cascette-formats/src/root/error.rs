@@ -48,6 +48,10 @@ pub enum RootError {
         description: String,
     },
 
+    /// Path lookup on a root that stores no name hashes
+    #[error("Root file has no name hashes, files can only be resolved by FileDataID")]
+    NoNameHashes,
+
     /// I/O operation failed
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
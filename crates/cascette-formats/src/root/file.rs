@@ -2,7 +2,7 @@
 
 use crate::root::{
     block::RootBlock,
-    entry::{RootEntry, RootLookupTables},
+    entry::{RootEntry, RootLookupTables, calculate_name_hash},
     error::{Result, RootError},
     flags::{ContentFlags, LocaleFlags},
     header::RootHeader,
@@ -33,61 +33,11 @@ impl RootFile {
 
     /// Parse root file from reader
     pub fn parse_from_reader<R: Read + Seek>(reader: &mut R) -> Result<Self> {
-        // Detect version (preliminary - may be updated from header)
-        let detected_version = RootVersion::detect(reader)?;
-
-        // Parse header if present
-        let header = if detected_version.has_header() {
-            Some(RootHeader::read(reader, detected_version)?)
-        } else {
-            None
-        };
-
-        // Use header's version if available (handles extended header cases)
-        let version = match &header {
-            Some(h) => h.version(),
-            None => detected_version,
-        };
-
-        // Determine if file has named files
-        let has_named_files = match &header {
-            Some(h) => h.named_files() > 0,
-            None => true, // V1 always has named files
-        };
-
-        // Parse blocks
-        let mut blocks = Vec::new();
-
-        loop {
-            // Check if we've reached end of file
-            let current_pos = reader.stream_position()?;
-            reader.seek(SeekFrom::End(0))?;
-            let end_pos = reader.stream_position()?;
-            reader.seek(SeekFrom::Start(current_pos))?;
-
-            if current_pos >= end_pos {
-                break;
-            }
-
-            // Try to parse next block
-            match RootBlock::parse(reader, version, has_named_files) {
-                Ok(block) => {
-                    // Skip empty blocks -- they can appear between valid blocks.
-                    // EOF termination is handled by the position check above.
-                    if block.num_records() > 0 {
-                        blocks.push(block);
-                    }
-                }
-                Err(e) => {
-                    // If we haven't parsed any blocks yet, this is a real error
-                    if blocks.is_empty() {
-                        return Err(e);
-                    }
-                    // Otherwise assume we've reached the end
-                    break;
-                }
-            }
-        }
+        let mut block_reader = RootBlockReader::new(reader)?;
+        let blocks = block_reader.by_ref().collect::<Result<Vec<_>>>()?;
+        let RootBlockReader {
+            version, header, ..
+        } = block_reader;
 
         // Build lookup tables
         let mut root_file = Self {
@@ -169,6 +119,47 @@ impl RootFile {
         self.lookups.resolve_by_hash(name_hash, locale, content)
     }
 
+    /// Look up the `FileDataID` of a path
+    ///
+    /// The path is normalized to uppercase with backslashes and matched by
+    /// its Jenkins96 name hash, see
+    /// [`calculate_name_hash`].
+    /// Returns `Ok(None)` if no record has that name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RootError::NoNameHashes` if the root stores no name hashes
+    /// at all, as is the case for roots that key files on `FileDataID` only.
+    pub fn lookup_by_path(&self, path: &str) -> Result<Option<FileDataId>> {
+        if self.lookups.name_count() == 0 {
+            return Err(RootError::NoNameHashes);
+        }
+        Ok(self.lookups.file_data_id_by_path(path))
+    }
+
+    /// Look up the content key of a file by its precomputed name hash
+    ///
+    /// Use this with Jenkins96 hashes computed elsewhere, such as from a
+    /// community listfile. The hash table built at parse time makes this an
+    /// O(1) lookup. Without locale or content filtering, the first record
+    /// with the hash in block order wins; use [`Self::resolve_by_hash`] to
+    /// filter.
+    pub fn lookup_by_filename_hash(&self, hash: u64) -> Option<&ContentKey> {
+        self.lookups
+            .get_entries_by_hash(hash)?
+            .first()
+            .map(|entry| &entry.content_key)
+    }
+
+    /// Look up the content key of a file by its path
+    ///
+    /// The path is hashed with
+    /// [`calculate_name_hash`] and
+    /// passed to [`Self::lookup_by_filename_hash`].
+    pub fn lookup_by_filename(&self, path: &str) -> Option<&ContentKey> {
+        self.lookup_by_filename_hash(calculate_name_hash(path))
+    }
+
     /// Get all entries for a `FileDataID`
     pub fn get_entries_by_id(&self, fdid: FileDataId) -> Option<&Vec<RootEntry>> {
         self.lookups.get_entries_by_id(fdid)
@@ -179,6 +170,34 @@ impl RootFile {
         self.lookups.get_entries_by_path(path)
     }
 
+    /// Find all files using a content key
+    ///
+    /// Returns the `FileDataID` and lookup entry of every record whose
+    /// content key matches, in block order.
+    pub fn find_by_content_key(&self, content_key: &ContentKey) -> Vec<(FileDataId, RootEntry)> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .flat_map(|(block_idx, block)| {
+                block
+                    .records
+                    .iter()
+                    .filter(|record| record.content_key == *content_key)
+                    .map(move |record| {
+                        (
+                            record.file_data_id,
+                            RootEntry::new(
+                                block_idx,
+                                record.content_key,
+                                block.locale_flags(),
+                                block.content_flags(),
+                            ),
+                        )
+                    })
+            })
+            .collect()
+    }
+
     /// Iterate over all file records
     pub fn iter_records(&self) -> impl Iterator<Item = &crate::root::entry::RootRecord> {
         self.blocks.iter().flat_map(|block| block.records.iter())
@@ -344,6 +363,142 @@ impl crate::CascFormat for RootFile {
     }
 }
 
+/// Block-by-block root file reader
+///
+/// Parses the header up front and then yields one [`RootBlock`] at a time,
+/// so lookups can stop as soon as they find what they need instead of
+/// loading the whole root. Empty blocks are skipped, matching the block
+/// indices of [`RootFile`].
+pub struct RootBlockReader<'a, R> {
+    reader: &'a mut R,
+    version: RootVersion,
+    header: Option<RootHeader>,
+    has_named_files: bool,
+    blocks_read: usize,
+    done: bool,
+}
+
+impl<'a, R: Read + Seek> RootBlockReader<'a, R> {
+    /// Read the root header and position the reader at the first block
+    pub fn new(reader: &'a mut R) -> Result<Self> {
+        // Detect version (preliminary - may be updated from header)
+        let detected_version = RootVersion::detect(reader)?;
+
+        // Parse header if present
+        let header = if detected_version.has_header() {
+            Some(RootHeader::read(reader, detected_version)?)
+        } else {
+            None
+        };
+
+        // Use header's version if available (handles extended header cases)
+        let version = match &header {
+            Some(h) => h.version(),
+            None => detected_version,
+        };
+
+        // Determine if file has named files
+        let has_named_files = match &header {
+            Some(h) => h.named_files() > 0,
+            None => true, // V1 always has named files
+        };
+
+        Ok(Self {
+            reader,
+            version,
+            header,
+            has_named_files,
+            blocks_read: 0,
+            done: false,
+        })
+    }
+
+    /// File format version
+    pub const fn version(&self) -> RootVersion {
+        self.version
+    }
+
+    /// File header (None for V1)
+    pub const fn header(&self) -> Option<&RootHeader> {
+        self.header.as_ref()
+    }
+
+    /// Find the first entry for a `FileDataID`, stopping at the matching block
+    ///
+    /// With `locale`, blocks that do not match any of the given locales are
+    /// skipped.
+    pub fn find_by_id(
+        &mut self,
+        fdid: FileDataId,
+        locale: Option<LocaleFlags>,
+    ) -> Result<Option<RootEntry>> {
+        while let Some(block) = self.next() {
+            let block = block?;
+            if locale.is_some_and(|locale| !block.locale_flags().matches(locale)) {
+                continue;
+            }
+
+            if let Some(record) = block.records.iter().find(|r| r.file_data_id == fdid) {
+                return Ok(Some(RootEntry::new(
+                    self.blocks_read - 1,
+                    record.content_key,
+                    block.locale_flags(),
+                    block.content_flags(),
+                )));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn at_end(&mut self) -> Result<bool> {
+        let current_pos = self.reader.stream_position()?;
+        let end_pos = self.reader.seek(SeekFrom::End(0))?;
+        self.reader.seek(SeekFrom::Start(current_pos))?;
+        Ok(current_pos >= end_pos)
+    }
+}
+
+impl<R: Read + Seek> Iterator for RootBlockReader<'_, R> {
+    type Item = Result<RootBlock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.at_end() {
+                Ok(false) => {}
+                Ok(true) => {
+                    self.done = true;
+                    break;
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+
+            match RootBlock::parse(self.reader, self.version, self.has_named_files) {
+                // Skip empty blocks -- they can appear between valid blocks.
+                // EOF termination is handled by the position check above.
+                Ok(block) if block.num_records() == 0 => {}
+                Ok(block) => {
+                    self.blocks_read += 1;
+                    return Some(Ok(block));
+                }
+                Err(e) => {
+                    self.done = true;
+                    // If we haven't parsed any blocks yet, this is a real error
+                    if self.blocks_read == 0 {
+                        return Some(Err(e));
+                    }
+                    // Otherwise assume we've reached the end
+                }
+            }
+        }
+
+        None
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::expect_used, clippy::unwrap_used)]
 mod tests {
@@ -351,6 +506,10 @@ mod tests {
     use crate::{CascFormat, root::builder::RootBuilder};
 
     fn create_test_root(version: RootVersion) -> RootFile {
+        RootFile::parse(&create_test_root_data(version)).expect("Operation should succeed")
+    }
+
+    fn create_test_root_data(version: RootVersion) -> Vec<u8> {
         let mut builder = RootBuilder::new(version);
 
         // Add some test files
@@ -382,8 +541,7 @@ mod tests {
             ContentFlags::new(ContentFlags::INSTALL | ContentFlags::NO_NAME_HASH),
         );
 
-        let data = builder.build().expect("Operation should succeed");
-        RootFile::parse(&data).expect("Operation should succeed")
+        builder.build().expect("Operation should succeed")
     }
 
     #[test]
@@ -484,6 +642,77 @@ mod tests {
         assert!(resolved.is_none());
     }
 
+    #[test]
+    fn test_lookup_by_path() {
+        for version in [
+            RootVersion::V1,
+            RootVersion::V2,
+            RootVersion::V3,
+            RootVersion::V4,
+        ] {
+            let root = create_test_root(version);
+
+            assert_eq!(
+                root.lookup_by_path("interface/icons/inv_misc_questionmark.blp")
+                    .expect("Root has name hashes"),
+                Some(FileDataId::new(100))
+            );
+            assert_eq!(
+                root.lookup_by_path("WORLD\\MAPS\\TESTMAP\\TESTMAP.WDT")
+                    .expect("Root has name hashes"),
+                Some(FileDataId::new(200))
+            );
+            assert_eq!(
+                root.lookup_by_path("NonExistent\\Path\\File.blp")
+                    .expect("Root has name hashes"),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn test_lookup_by_filename_hash() {
+        let paths = [
+            "Interface\\Icons\\INV_Misc_QuestionMark.blp",
+            "World\\Maps\\TestMap\\TestMap.wdt",
+        ];
+        for version in [
+            RootVersion::V1,
+            RootVersion::V2,
+            RootVersion::V3,
+            RootVersion::V4,
+        ] {
+            let root = create_test_root(version);
+            for path in paths {
+                let by_hash = root.lookup_by_filename_hash(calculate_name_hash(path));
+                assert!(by_hash.is_some(), "{path}");
+                assert_eq!(by_hash, root.lookup_by_filename(path));
+                assert_eq!(by_hash, root.lookup_by_filename(&path.to_lowercase()));
+            }
+            assert_eq!(root.lookup_by_filename("NonExistent\\Path\\File.blp"), None);
+        }
+    }
+
+    #[test]
+    fn test_lookup_by_path_without_name_hashes() {
+        let mut builder = RootBuilder::new(RootVersion::V2);
+        builder.add_file(
+            FileDataId::new(300),
+            ContentKey::from_hex("abcdefabcdefabcdefabcdefabcdefab")
+                .expect("Operation should succeed"),
+            None,
+            LocaleFlags::new(LocaleFlags::ALL),
+            ContentFlags::new(ContentFlags::INSTALL | ContentFlags::NO_NAME_HASH),
+        );
+        let root = RootFile::parse(&builder.build().expect("Operation should succeed"))
+            .expect("Operation should succeed");
+
+        assert!(matches!(
+            root.lookup_by_path("Interface\\Icons\\INV_Misc_QuestionMark.blp"),
+            Err(RootError::NoNameHashes)
+        ));
+    }
+
     #[test]
     fn test_multi_locale_resolution() {
         let root = create_test_root(RootVersion::V2);
@@ -577,4 +806,70 @@ mod tests {
         assert!(summary.contains("blocks"));
         assert!(summary.contains("files"));
     }
+
+    #[test]
+    fn test_block_reader_matches_parse() {
+        for version in [
+            RootVersion::V1,
+            RootVersion::V2,
+            RootVersion::V3,
+            RootVersion::V4,
+        ] {
+            let data = create_test_root_data(version);
+            let root = RootFile::parse(&data).expect("Operation should succeed");
+
+            let mut cursor = Cursor::new(&data);
+            let reader = RootBlockReader::new(&mut cursor).expect("Operation should succeed");
+            assert_eq!(reader.version(), root.version);
+            let blocks = reader
+                .collect::<Result<Vec<_>>>()
+                .expect("Operation should succeed");
+            assert_eq!(blocks, root.blocks);
+        }
+    }
+
+    #[test]
+    fn test_block_reader_find_by_id() {
+        let data = create_test_root_data(RootVersion::V4);
+        let mut cursor = Cursor::new(&data);
+        let mut reader = RootBlockReader::new(&mut cursor).expect("Operation should succeed");
+
+        let entry = reader
+            .find_by_id(FileDataId::new(100), None)
+            .expect("Operation should succeed")
+            .expect("File should be found");
+        assert_eq!(
+            entry.content_key,
+            ContentKey::from_hex("0123456789abcdef0123456789abcdef")
+                .expect("Operation should succeed")
+        );
+        // The lookup stops at the matching block
+        assert!(reader.next().is_some());
+
+        let mut cursor = Cursor::new(&data);
+        let mut reader = RootBlockReader::new(&mut cursor).expect("Operation should succeed");
+        let entry = reader
+            .find_by_id(
+                FileDataId::new(100),
+                Some(LocaleFlags::new(LocaleFlags::FRFR)),
+            )
+            .expect("Operation should succeed");
+        assert!(entry.is_none());
+    }
+
+    #[test]
+    fn test_find_by_content_key() {
+        let root = create_test_root(RootVersion::V2);
+        let content_key = ContentKey::from_hex("fedcba9876543210fedcba9876543210")
+            .expect("Operation should succeed");
+
+        let matches = root.find_by_content_key(&content_key);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].0, FileDataId::new(200));
+        assert!(matches[0].1.locale_flags.has(LocaleFlags::DEDE));
+
+        let missing = ContentKey::from_hex("00000000000000000000000000000000")
+            .expect("Operation should succeed");
+        assert!(root.find_by_content_key(&missing).is_empty());
+    }
 }
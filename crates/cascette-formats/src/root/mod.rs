@@ -193,7 +193,7 @@ pub use entry::{
     encode_file_data_ids,
 };
 pub use error::{Result, RootError};
-pub use file::RootFile;
+pub use file::{RootBlockReader, RootFile};
 pub use flags::{ContentFlags, LocaleFlags};
 pub use header::{RootHeader, RootHeaderInfo, RootMagic};
 pub use version::RootVersion;
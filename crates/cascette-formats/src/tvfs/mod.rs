@@ -22,6 +22,7 @@ mod error;
 mod est_table;
 mod header;
 mod path_table;
+mod paths;
 #[allow(dead_code)]
 mod utils;
 mod vfs_table;
@@ -36,10 +37,13 @@ pub use header::{
     TVFS_FLAG_WRITE_SUPPORT, TvfsHeader,
 };
 pub use path_table::{PathFileEntry, PathTable, PathTreeNode};
+pub use paths::TvfsPathIter;
 pub use vfs_table::{VfsEntry, VfsSpan, VfsTable};
 
 use crate::CascFormat;
+use crate::encoding::EncodingFile;
 use binrw::{BinRead, BinWrite};
+use cascette_crypto::md5::ContentKey;
 use std::io::Cursor;
 
 /// Complete TVFS file structure
@@ -221,6 +225,40 @@ impl TvfsFile {
             .find(|e| e.offset == span.cft_offset)
     }
 
+    /// Resolve a virtual path to its content key.
+    ///
+    /// The path is found by descending the prefix tree, then resolved
+    /// through the VFS and container tables like [`Self::resolve_path`].
+    /// The CFT only stores a truncated EKey, so the content key comes from
+    /// the encoding file entries whose EKey starts with it. Returns `None`
+    /// if the path does not exist, names a folder, has no encoding entry,
+    /// or its EKey prefix matches entries of different content.
+    pub fn resolve_content_key(&self, path: &str, encoding: &EncodingFile) -> Option<ContentKey> {
+        let ekey = &self.resolve_path(path)?.ekey;
+        let matches = encoding.lookup_by_ekey_prefix(ekey);
+        let (_, content_key) = *matches.first()?;
+        matches
+            .iter()
+            .all(|(_, other)| *other == content_key)
+            .then_some(content_key)
+    }
+
+    /// List the names of the files and folders directly in a virtual
+    /// directory.
+    ///
+    /// `prefix` is a folder path like `"data/sub"`, with or without a
+    /// trailing `/`; the empty string lists the root. Names are returned in
+    /// table order. A path that does not exist or names a file yields an
+    /// empty list.
+    pub fn list_directory(&self, prefix: &str) -> Vec<String> {
+        let prefix = prefix.strip_suffix('/').unwrap_or(prefix);
+        self.path_table
+            .find_node(prefix)
+            .filter(|node| node.is_folder())
+            .map(|node| node.child_names().into_iter().map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+
     /// Enumerate all files in the TVFS.
     pub fn enumerate_files(&self) -> impl Iterator<Item = (&PathFileEntry, Option<&VfsEntry>)> {
         self.path_table.files.iter().map(move |file| {
@@ -232,6 +270,18 @@ impl TvfsFile {
             (file, vfs_entry)
         })
     }
+
+    /// Iterate every virtual path together with its EKey prefix.
+    ///
+    /// Paths are rebuilt incrementally while walking the prefix tree, so large
+    /// manifests are not materialized up front. An EKey shared by several
+    /// paths is yielded once per path.
+    ///
+    /// The CFT only stores the first `ekey_size` bytes of each EKey; resolve
+    /// the full key through the encoding file's EKey prefix lookup.
+    pub fn iter_paths(&self) -> TvfsPathIter<'_> {
+        TvfsPathIter::new(self)
+    }
 }
 
 impl crate::CascFormat for TvfsFile {
@@ -249,6 +299,21 @@ impl crate::CascFormat for TvfsFile {
 #[allow(clippy::expect_used, clippy::unwrap_used)]
 mod tests {
     use super::*;
+    use crate::encoding::{CKeyEntryData, EncodingBuilder};
+    use cascette_crypto::EncodingKey;
+
+    /// Encoding file mapping each content key to its full encoding key
+    fn encoding_of(entries: &[([u8; 16], [u8; 16])]) -> EncodingFile {
+        let mut builder = EncodingBuilder::new();
+        for &(ckey, ekey) in entries {
+            builder.add_ckey_entry(CKeyEntryData {
+                content_key: ContentKey::from_bytes(ckey),
+                file_size: 10,
+                encoding_keys: vec![EncodingKey::from_bytes(ekey)],
+            });
+        }
+        builder.build().expect("encoding should build")
+    }
 
     #[test]
     fn test_tvfs_header_parsing() {
@@ -321,6 +386,236 @@ mod tests {
         assert!(result.is_err());
     }
 
+    fn iter_paths_fixture() -> TvfsFile {
+        let mut builder = TvfsBuilder::new();
+        builder.add_file("data/a.bin".to_string(), [1; 9], 10, 10, Some([0xAA; 16]));
+        builder.add_file(
+            "data/sub/b.bin".to_string(),
+            [2; 9],
+            20,
+            20,
+            Some([0xBB; 16]),
+        );
+        builder.add_file(
+            "data/sub/c.bin".to_string(),
+            [2; 9],
+            30,
+            30,
+            Some([0xBB; 16]),
+        );
+        builder.add_file("z.txt".to_string(), [4; 9], 40, 40, Some([0xCC; 16]));
+        let data = builder.build().expect("build should succeed");
+        TvfsFile::parse(&data).expect("parse should succeed")
+    }
+
+    #[test]
+    fn test_iter_paths_yields_every_path() {
+        let tvfs = iter_paths_fixture();
+        let mut paths: Vec<(String, &[u8])> = tvfs.iter_paths().collect();
+        paths.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let names: Vec<&str> = paths.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(
+            names,
+            ["data/a.bin", "data/sub/b.bin", "data/sub/c.bin", "z.txt"]
+        );
+
+        // Deduplicated content is reported for each path
+        assert_eq!(paths[1].1, paths[2].1);
+        assert_ne!(paths[0].1, paths[1].1);
+
+        for (path, ekey) in &paths {
+            let entry = tvfs.resolve_path(path).expect("path should resolve");
+            assert_eq!(*ekey, entry.ekey.as_slice());
+            assert_eq!(ekey.len(), usize::from(tvfs.header.ekey_size));
+        }
+    }
+
+    #[test]
+    fn test_iter_paths_matches_enumerate_files() {
+        let tvfs = iter_paths_fixture();
+        let mut from_iter: Vec<String> = tvfs.iter_paths().map(|(p, _)| p).collect();
+        let mut from_table: Vec<String> = tvfs
+            .path_table
+            .files
+            .iter()
+            .map(|f| f.path.clone())
+            .collect();
+        from_iter.sort();
+        from_table.sort();
+        assert_eq!(from_iter, from_table);
+    }
+
+    #[test]
+    fn test_iter_paths_yields_files_without_ckey() {
+        let mut builder = TvfsBuilder::with_flags(0);
+        builder.add_file("a.bin".to_string(), [1; 9], 10, 10, None);
+        let data = builder.build().expect("build should succeed");
+        let tvfs = TvfsFile::parse(&data).expect("parse should succeed");
+        let paths: Vec<_> = tvfs.iter_paths().collect();
+        assert_eq!(paths, [("a.bin".to_string(), &[1u8; 9][..])]);
+        assert!(tvfs.resolve_path("a.bin").is_some());
+
+        // The content key comes from the encoding file, not the CFT
+        let encoding = encoding_of(&[([7; 16], [1; 16])]);
+        assert_eq!(
+            tvfs.resolve_content_key("a.bin", &encoding),
+            Some(ContentKey::from_bytes([7; 16]))
+        );
+    }
+
+    fn deep_fixture() -> TvfsFile {
+        let mut builder = TvfsBuilder::new();
+        for (i, path) in [
+            "base/a/b/c/d/e/deep.bin",
+            "base/a/b/c/d/e/other.bin",
+            "base/a/b/side.bin",
+            "base/ab.bin",
+            "base/readme.txt",
+            "top.bin",
+        ]
+        .iter()
+        .enumerate()
+        {
+            let i = i as u8 + 1;
+            builder.add_file(path.to_string(), [i; 9], 10, 10, Some([i; 16]));
+        }
+        let data = builder.build().expect("build should succeed");
+        TvfsFile::parse(&data).expect("parse should succeed")
+    }
+
+    /// Encoding file for [`deep_fixture`], content key `[i; 16]` stored
+    /// under encoding key `[i; 16]`
+    fn deep_encoding() -> EncodingFile {
+        let entries: Vec<_> = (1..=6u8).map(|i| ([i; 16], [i; 16])).collect();
+        encoding_of(&entries)
+    }
+
+    #[test]
+    fn test_resolve_content_key_deep_paths() {
+        let tvfs = deep_fixture();
+        let encoding = deep_encoding();
+
+        for (path, byte) in [
+            ("base/a/b/c/d/e/deep.bin", 1),
+            ("base/a/b/c/d/e/other.bin", 2),
+            ("base/a/b/side.bin", 3),
+            ("base/ab.bin", 4),
+            ("top.bin", 6),
+        ] {
+            let ckey = tvfs
+                .resolve_content_key(path, &encoding)
+                .expect("path should resolve");
+            assert_eq!(ckey.as_bytes(), &[byte; 16], "{path}");
+        }
+    }
+
+    #[test]
+    fn test_resolve_content_key_without_unique_encoding_entry() {
+        let tvfs = deep_fixture();
+
+        // No encoding entry for the EKey
+        let encoding = encoding_of(&[([9; 16], [9; 16])]);
+        assert!(tvfs.resolve_content_key("top.bin", &encoding).is_none());
+
+        // Two files whose encoding keys share the truncated prefix
+        let mut second = [6; 16];
+        second[15] = 0;
+        let encoding = encoding_of(&[([6; 16], [6; 16]), ([8; 16], second)]);
+        assert!(tvfs.resolve_content_key("top.bin", &encoding).is_none());
+    }
+
+    #[test]
+    fn test_resolve_content_key_missing_paths() {
+        let tvfs = deep_fixture();
+        let encoding = deep_encoding();
+        for path in [
+            "",
+            "base",
+            "base/a/b",
+            "base/a/b/c/d/e/missing.bin",
+            "base/a/b/c/d/e/deep.bin/x",
+            "base/a/b/c/d/e/deep",
+            "base/ab",
+            "base/a/ab.bin",
+            "top.bin/",
+            "/top.bin",
+            "missing/top.bin",
+        ] {
+            assert!(
+                tvfs.resolve_content_key(path, &encoding).is_none(),
+                "{path:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_content_key_matches_iter_paths() {
+        let tvfs = deep_fixture();
+        let encoding = deep_encoding();
+        for (path, ekey) in tvfs.iter_paths() {
+            let entry = tvfs.resolve_path(&path).expect("path should resolve");
+            assert_eq!(entry.ekey, ekey);
+            assert!(tvfs.resolve_content_key(&path, &encoding).is_some());
+            assert_eq!(
+                tvfs.path_table.resolve_path(&path),
+                tvfs.path_table
+                    .files
+                    .iter()
+                    .find(|f| f.path == path)
+                    .map(|f| f.vfs_offset)
+            );
+        }
+    }
+
+    #[test]
+    fn test_list_directory() {
+        let tvfs = deep_fixture();
+        assert_eq!(tvfs.list_directory(""), ["base", "top.bin"]);
+        assert_eq!(tvfs.list_directory("base"), ["a", "ab.bin", "readme.txt"]);
+        assert_eq!(tvfs.list_directory("base/a/"), ["b"]);
+        assert_eq!(tvfs.list_directory("base/a/b"), ["c", "side.bin"]);
+        assert_eq!(
+            tvfs.list_directory("base/a/b/c/d/e"),
+            ["deep.bin", "other.bin"]
+        );
+
+        // Missing directories and files have no children
+        assert!(tvfs.list_directory("base/missing").is_empty());
+        assert!(tvfs.list_directory("base/a/b/side.bin").is_empty());
+        assert!(tvfs.list_directory("bas").is_empty());
+    }
+
+    #[test]
+    fn test_empty_named_folders_are_transparent() {
+        let leaf = |name: &str, vfs_offset| PathTreeNode {
+            name: name.to_string(),
+            children: Vec::new(),
+            vfs_offset: Some(vfs_offset),
+        };
+        let folder = |name: &str, children| PathTreeNode {
+            name: name.to_string(),
+            children,
+            vfs_offset: None,
+        };
+        let root = folder(
+            "",
+            vec![folder(
+                "dir",
+                vec![folder("", vec![leaf("x.bin", 7)]), leaf("y.bin", 8)],
+            )],
+        );
+        let table = PathTable::parse(&PathTable::build(&root)).expect("parse should succeed");
+
+        assert_eq!(table.resolve_path("dir/x.bin"), Some(7));
+        assert_eq!(table.resolve_path("dir/y.bin"), Some(8));
+        assert_eq!(table.resolve_path("dir"), None);
+        assert_eq!(
+            table.find_node("dir").map(PathTreeNode::child_names),
+            Some(vec!["x.bin", "y.bin"])
+        );
+    }
+
     #[test]
     fn test_cft_offs_size() {
         let header = TvfsHeader::new(TVFS_FLAG_INCLUDE_CKEY);
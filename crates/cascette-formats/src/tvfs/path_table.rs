@@ -51,6 +51,34 @@ pub struct PathTreeNode {
     pub vfs_offset: Option<u32>,
 }
 
+impl PathTreeNode {
+    /// Check whether this is a folder node.
+    pub fn is_folder(&self) -> bool {
+        self.vfs_offset.is_none()
+    }
+
+    /// Names of the files and folders directly in this folder.
+    ///
+    /// Children of empty-named folders are listed as if they were in this
+    /// folder. A file node has no children.
+    pub fn child_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        let mut stack = vec![self.children.iter()];
+        while let Some(children) = stack.last_mut() {
+            let Some(child) = children.next() else {
+                stack.pop();
+                continue;
+            };
+            if child.name.is_empty() && child.is_folder() {
+                stack.push(child.children.iter());
+            } else {
+                names.push(child.name.as_str());
+            }
+        }
+        names
+    }
+}
+
 impl PathTable {
     /// Parse the path table from raw bytes.
     pub fn parse(data: &[u8]) -> TvfsResult<Self> {
@@ -86,10 +114,44 @@ impl PathTable {
 
     /// Resolve a path to a VFS offset.
     pub fn resolve_path(&self, path: &str) -> Option<u32> {
-        self.files
-            .iter()
-            .find(|f| f.path == path)
-            .map(|f| f.vfs_offset)
+        self.find_node(path)?.vfs_offset
+    }
+
+    /// Find the file or folder node for a path by descending the tree.
+    ///
+    /// Components are joined by `/` as in [`PathFileEntry::path`]; the empty
+    /// path is the root. Folders with an empty name are transparent, as
+    /// they are when paths are built during parsing.
+    pub fn find_node(&self, path: &str) -> Option<&PathTreeNode> {
+        if path.is_empty() {
+            return Some(&self.root);
+        }
+
+        // Several children can match one component through empty-named
+        // folders, so candidates are kept on a stack
+        let mut stack = vec![(&self.root, path)];
+        while let Some((node, remaining)) = stack.pop() {
+            for child in node.children.iter().rev() {
+                if child.name.is_empty() {
+                    if child.vfs_offset.is_none() {
+                        stack.push((child, remaining));
+                    }
+                    continue;
+                }
+                let Some(rest) = remaining.strip_prefix(child.name.as_str()) else {
+                    continue;
+                };
+                if rest.is_empty() {
+                    return Some(child);
+                }
+                if let Some(rest) = rest.strip_prefix('/')
+                    && child.vfs_offset.is_none()
+                {
+                    stack.push((child, rest));
+                }
+            }
+        }
+        None
     }
 
     /// Build path table bytes from a tree structure.
@@ -0,0 +1,91 @@
+//! Streaming enumeration of TVFS virtual paths
+//!
+//! [`TvfsPathIter`] walks the parsed prefix tree depth-first and rebuilds each
+//! full path in a single reusable buffer, so only the path currently being
+//! yielded is allocated.
+
+use std::collections::HashMap;
+
+use super::{ContainerEntry, PathTreeNode, TvfsFile, VfsEntry};
+
+/// Iterator over `(path, EKey prefix)` pairs of a [`TvfsFile`].
+///
+/// The prefix is the `ekey_size` bytes of the encoding key stored in the
+/// container file table, which every file has.
+///
+/// Created by [`TvfsFile::iter_paths`].
+pub struct TvfsPathIter<'a> {
+    /// Children still to visit at each depth, with the path length to
+    /// truncate back to before appending a child name.
+    stack: Vec<(std::slice::Iter<'a, PathTreeNode>, usize)>,
+    /// Path of the directory currently being walked.
+    path: String,
+    /// VFS entries by byte offset.
+    vfs_entries: HashMap<u32, &'a VfsEntry>,
+    /// Container entries by byte offset.
+    container_entries: HashMap<u32, &'a ContainerEntry>,
+}
+
+impl<'a> TvfsPathIter<'a> {
+    pub(super) fn new(tvfs: &'a TvfsFile) -> Self {
+        Self {
+            stack: vec![(tvfs.path_table.root.children.iter(), 0)],
+            path: String::new(),
+            vfs_entries: tvfs
+                .vfs_table
+                .entries
+                .iter()
+                .map(|e| (e.offset, e))
+                .collect(),
+            container_entries: tvfs
+                .container_table
+                .entries
+                .iter()
+                .map(|e| (e.offset, e))
+                .collect(),
+        }
+    }
+
+    /// Resolve a VFS offset to the EKey prefix of its first span.
+    fn ekey_prefix(&self, vfs_offset: u32) -> Option<&'a [u8]> {
+        let span = self.vfs_entries.get(&vfs_offset)?.spans.first()?;
+        let entry: &'a ContainerEntry = self.container_entries.get(&span.cft_offset)?;
+        Some(&entry.ekey)
+    }
+}
+
+/// Append `name` to `path` as a new segment, using the same joining rules as
+/// the path table parser.
+fn push_segment(path: &mut String, name: &str) {
+    if !path.is_empty() && !name.is_empty() {
+        path.push('/');
+    }
+    path.push_str(name);
+}
+
+impl<'a> Iterator for TvfsPathIter<'a> {
+    type Item = (String, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (children, base_len) = self.stack.last_mut()?;
+            let base_len = *base_len;
+            let Some(node) = children.next() else {
+                self.stack.pop();
+                continue;
+            };
+
+            self.path.truncate(base_len);
+            push_segment(&mut self.path, &node.name);
+
+            if let Some(vfs_offset) = node.vfs_offset {
+                if let Some(ekey) = self.ekey_prefix(vfs_offset) {
+                    return Some((self.path.clone(), ekey));
+                }
+            } else {
+                let len = self.path.len();
+                self.stack.push((node.children.iter(), len));
+            }
+        }
+    }
+}
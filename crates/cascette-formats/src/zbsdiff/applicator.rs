@@ -0,0 +1,673 @@
+//! Incremental ZBSDIFF1 patch application
+//!
+//! [`PatchApplicator`] produces the patched file through [`Read`], a buffer
+//! at a time. Unlike [`apply_patch_memory`](crate::zbsdiff::apply_patch_memory)
+//! and [`ZbsdiffPatcher`](crate::zbsdiff::ZbsdiffPatcher), it never holds the
+//! old file, the patch blocks or the output in memory.
+//! [`apply_patch_streaming`] copies that output into a writer.
+
+use crate::zbsdiff::{
+    ControlEntry, ZbsdiffHeader,
+    error::{ZbsdiffError, ZbsdiffResult},
+    utils::{apply_diff_byte, offtin},
+};
+use binrw::BinRead;
+use flate2::read::ZlibDecoder;
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Default size of the window over the old file (64 KiB)
+const DEFAULT_WINDOW_SIZE: usize = 64 * 1024;
+
+/// Size of a control block entry: three sign-magnitude 64-bit integers
+const CONTROL_ENTRY_SIZE: usize = 24;
+
+/// Streaming ZBSDIFF1 patch application
+///
+/// The patched output is read from the applicator like any other reader.
+/// Patch blocks are decompressed as the output is produced, and the old
+/// file is read through a fixed-size window that is refilled when the
+/// patch seeks outside it.
+///
+/// The control, diff and extra blocks are consumed in parallel, each by its
+/// own decoder reading the patch from that block's offset. The patch reader
+/// is seeked back and forth between the blocks, so no compressed block is
+/// buffered. Wrap files in a [`BufReader`](std::io::BufReader) to keep the
+/// reads large.
+///
+/// Read errors caused by the patch wrap a [`ZbsdiffError`], which can be
+/// recovered with [`io::Error::into_inner`] and downcasting.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::fs::File;
+/// use std::io::BufReader;
+/// use cascette_formats::zbsdiff::PatchApplicator;
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let old_file = File::open("large_old_file.bin")?;
+/// let patch_file = BufReader::new(File::open("patch.zbsdiff")?);
+///
+/// let mut applicator = PatchApplicator::new(old_file, patch_file)?;
+/// let mut new_file = File::create("new_file.bin")?;
+/// std::io::copy(&mut applicator, &mut new_file)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct PatchApplicator<O, P> {
+    header: ZbsdiffHeader,
+    old: OldWindow<O>,
+    control: ZlibDecoder<PatchBlock<P>>,
+    diff: ZlibDecoder<PatchBlock<P>>,
+    extra: ZlibDecoder<PatchBlock<P>>,
+    /// Position in the old file
+    old_pos: u64,
+    /// Bytes of output produced so far
+    produced: u64,
+    /// Control entries read so far
+    entries_read: usize,
+    /// Diff bytes left in the current control entry
+    diff_remaining: u64,
+    /// Extra bytes left in the current control entry
+    extra_remaining: u64,
+    /// Seek to apply once the current control entry is done
+    pending_seek: i64,
+    /// Old file bytes for the diff chunk being applied
+    scratch: Vec<u8>,
+    finished: bool,
+}
+
+impl<O: Read + Seek, P: Read + Seek> PatchApplicator<O, P> {
+    /// Prepare to apply `patch` to `old`
+    ///
+    /// The patch starts at the current position of `patch`. Only its header
+    /// is read here; nothing is decompressed until the output is read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header is invalid, the patch is shorter than
+    /// its header declares, or the size of `old` or `patch` cannot be
+    /// determined.
+    pub fn new(mut old: O, mut patch: P) -> ZbsdiffResult<Self> {
+        let start = patch.stream_position().map_err(ZbsdiffError::SeekError)?;
+        let end = patch
+            .seek(SeekFrom::End(0))
+            .map_err(ZbsdiffError::SeekError)?;
+        patch
+            .seek(SeekFrom::Start(start))
+            .map_err(ZbsdiffError::SeekError)?;
+
+        let mut header_bytes = [0u8; 32];
+        read_patch_exact(&mut patch, &mut header_bytes)?;
+        let header =
+            ZbsdiffHeader::read_options(&mut Cursor::new(header_bytes), binrw::Endian::Little, ())?;
+        header.validate()?;
+
+        let control_start = start + header_bytes.len() as u64;
+        let diff_start = control_start + header.control_size as u64;
+        let extra_start = diff_start + header.diff_size as u64;
+        if extra_start > end {
+            return Err(ZbsdiffError::insufficient_data(
+                (extra_start - start) as usize,
+                (end - start) as usize,
+            ));
+        }
+
+        let patch = Arc::new(Mutex::new(patch));
+        let block = |from: u64, to: u64| {
+            ZlibDecoder::new(PatchBlock {
+                patch: Arc::clone(&patch),
+                pos: from,
+                end: to,
+            })
+        };
+        let control = block(control_start, diff_start);
+        let diff = block(diff_start, extra_start);
+        let extra = block(extra_start, end);
+
+        let old_size = old
+            .seek(SeekFrom::End(0))
+            .map_err(ZbsdiffError::SeekError)?;
+
+        Ok(Self {
+            header,
+            old: OldWindow::new(old, old_size, DEFAULT_WINDOW_SIZE),
+            control,
+            diff,
+            extra,
+            old_pos: 0,
+            produced: 0,
+            entries_read: 0,
+            diff_remaining: 0,
+            extra_remaining: 0,
+            pending_seek: 0,
+            scratch: Vec::new(),
+            finished: false,
+        })
+    }
+
+    /// Set the size of the window over the old file (default: 64KB)
+    ///
+    /// Larger windows mean fewer reads from the old file when the patch
+    /// seeks around in it.
+    #[must_use]
+    pub fn with_window_size(mut self, window_size: usize) -> Self {
+        self.old.capacity = window_size.max(1024); // Minimum 1KB
+        self
+    }
+
+    /// The patch header
+    pub fn header(&self) -> &ZbsdiffHeader {
+        &self.header
+    }
+
+    /// Size of the patched output in bytes
+    pub fn output_size(&self) -> u64 {
+        self.header.output_size as u64
+    }
+
+    /// Produce the next piece of output into `buf`
+    fn fill(&mut self, buf: &mut [u8]) -> ZbsdiffResult<usize> {
+        if buf.is_empty() || self.finished {
+            return Ok(0);
+        }
+
+        loop {
+            if self.diff_remaining > 0 {
+                let n = self.chunk_len(buf.len(), self.diff_remaining)?;
+                let out = &mut buf[..n];
+                read_block_exact(&mut self.diff, out, "diff")?;
+
+                self.scratch.resize(n, 0);
+                self.old
+                    .read_at(self.old_pos, &mut self.scratch)
+                    .map_err(ZbsdiffError::old_file_read_error)?;
+                for (new_byte, old_byte) in out.iter_mut().zip(&self.scratch) {
+                    *new_byte = apply_diff_byte(*old_byte, *new_byte);
+                }
+
+                self.old_pos += n as u64;
+                self.diff_remaining -= n as u64;
+                self.produced += n as u64;
+                return Ok(n);
+            }
+
+            if self.extra_remaining > 0 {
+                let n = self.chunk_len(buf.len(), self.extra_remaining)?;
+                read_block_exact(&mut self.extra, &mut buf[..n], "extra")?;
+
+                self.extra_remaining -= n as u64;
+                self.produced += n as u64;
+                return Ok(n);
+            }
+
+            // The current entry is done; seek and move on to the next one
+            self.old_pos = if self.pending_seek < 0 {
+                self.old_pos
+                    .saturating_sub(self.pending_seek.unsigned_abs())
+            } else {
+                self.old_pos.saturating_add(self.pending_seek as u64)
+            };
+            self.pending_seek = 0;
+
+            let Some(entry) = self.next_entry()? else {
+                self.finished = true;
+                if self.produced != self.output_size() {
+                    return Err(ZbsdiffError::SizeMismatch {
+                        expected: self.output_size() as usize,
+                        actual: self.produced as usize,
+                    });
+                }
+                return Ok(0);
+            };
+            self.diff_remaining = entry.diff_size as u64;
+            self.extra_remaining = entry.extra_size as u64;
+            self.pending_seek = entry.seek_offset;
+        }
+    }
+
+    /// Length of the next chunk, bounded by the window size
+    ///
+    /// Fails if the chunk would take the output past its declared size.
+    fn chunk_len(&self, buf_len: usize, remaining: u64) -> ZbsdiffResult<usize> {
+        let n = (buf_len as u64)
+            .min(remaining)
+            .min(self.old.capacity as u64);
+        if self.produced + n > self.output_size() {
+            return Err(ZbsdiffError::SizeMismatch {
+                expected: self.output_size() as usize,
+                actual: (self.produced + remaining) as usize,
+            });
+        }
+        Ok(n as usize)
+    }
+
+    /// Decode the next control entry, or `None` at the end of the block
+    fn next_entry(&mut self) -> ZbsdiffResult<Option<ControlEntry>> {
+        let mut bytes = [0u8; CONTROL_ENTRY_SIZE];
+        let mut filled = 0;
+        while filled < bytes.len() {
+            match self.control.read(&mut bytes[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(ZbsdiffError::decompression_error(e)),
+            }
+        }
+
+        match filled {
+            0 if self.entries_read == 0 => Err(ZbsdiffError::EmptyControlBlock),
+            0 => Ok(None),
+            CONTROL_ENTRY_SIZE => {
+                let field = |i: usize| {
+                    let mut buf = [0u8; 8];
+                    buf.copy_from_slice(&bytes[i * 8..(i + 1) * 8]);
+                    offtin(buf)
+                };
+                let entry = ControlEntry::new(field(0), field(1), field(2));
+                entry.validate().map_err(|e| {
+                    ZbsdiffError::invalid_control_entry(self.entries_read, e.to_string())
+                })?;
+                self.entries_read += 1;
+                Ok(Some(entry))
+            }
+            remaining => Err(ZbsdiffError::corrupt_patch(format!(
+                "Incomplete control entry: {remaining} bytes remaining"
+            ))),
+        }
+    }
+}
+
+impl<O: Read + Seek, P: Read + Seek> Read for PatchApplicator<O, P> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.fill(buf).map_err(io::Error::other)
+    }
+}
+
+/// Apply a patch read from `patch` to `old`, writing the result to `out`
+///
+/// Memory use is bounded by the state of three zlib decoders, a 64 KiB
+/// window over `old` and a 64 KiB copy buffer, whatever the size of the
+/// patch; see [`PatchApplicator`]. Returns the number of bytes written.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::fs::File;
+/// use std::io::{BufReader, BufWriter};
+/// use cascette_formats::zbsdiff::apply_patch_streaming;
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let old_file = File::open("large_old_file.bin")?;
+/// let patch_file = BufReader::new(File::open("patch.zbsdiff")?);
+/// let new_file = BufWriter::new(File::create("new_file.bin")?);
+///
+/// let written = apply_patch_streaming(old_file, patch_file, new_file)?;
+/// println!("Wrote {written} bytes");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the patch is invalid or does not fit `old`, or if
+/// reading `old` or writing `out` fails.
+pub fn apply_patch_streaming<O, P, W>(old: O, patch: P, mut out: W) -> ZbsdiffResult<u64>
+where
+    O: Read + Seek,
+    P: Read + Seek,
+    W: Write,
+{
+    let mut applicator = PatchApplicator::new(old, patch)?;
+    let mut buf = vec![0u8; DEFAULT_WINDOW_SIZE];
+    let mut written = 0u64;
+    loop {
+        let n = applicator.fill(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        out.write_all(&buf[..n]).map_err(write_failed)?;
+        written += n as u64;
+    }
+    out.flush().map_err(write_failed)?;
+    Ok(written)
+}
+
+fn write_failed(error: io::Error) -> ZbsdiffError {
+    ZbsdiffError::application_failed(format!("failed to write output: {error}"))
+}
+
+/// Fixed-size window over the old file
+///
+/// Bytes past the end of the old file read as zeros, as in bsdiff.
+struct OldWindow<R> {
+    reader: R,
+    /// Size of the old file
+    size: u64,
+    /// Position in the old file of the first buffered byte
+    start: u64,
+    buf: Vec<u8>,
+    /// Bytes to buffer per refill
+    capacity: usize,
+}
+
+impl<R: Read + Seek> OldWindow<R> {
+    fn new(reader: R, size: u64, capacity: usize) -> Self {
+        Self {
+            reader,
+            size,
+            start: 0,
+            buf: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Fill `out` with old file bytes starting at `pos`
+    fn read_at(&mut self, mut pos: u64, mut out: &mut [u8]) -> io::Result<()> {
+        while !out.is_empty() {
+            if pos >= self.size {
+                out.fill(0);
+                return Ok(());
+            }
+
+            let end = self.start + self.buf.len() as u64;
+            if pos < self.start || pos >= end {
+                self.refill(pos)?;
+            }
+
+            let offset = (pos - self.start) as usize;
+            let n = out.len().min(self.buf.len() - offset);
+            out[..n].copy_from_slice(&self.buf[offset..offset + n]);
+            out = &mut out[n..];
+            pos += n as u64;
+        }
+        Ok(())
+    }
+
+    /// Buffer the old file from `pos` onwards
+    fn refill(&mut self, pos: u64) -> io::Result<()> {
+        let len = (self.size - pos).min(self.capacity as u64) as usize;
+        self.buf.resize(len, 0);
+        self.reader.seek(SeekFrom::Start(pos))?;
+        self.reader.read_exact(&mut self.buf)?;
+        self.start = pos;
+        Ok(())
+    }
+}
+
+/// One compressed block of a patch shared with the other blocks
+///
+/// Each read seeks the shared patch reader to where this block left off.
+struct PatchBlock<P> {
+    patch: Arc<Mutex<P>>,
+    /// Position in the patch of the next unread byte
+    pos: u64,
+    /// Position in the patch where the block ends
+    end: u64,
+}
+
+impl<P: Read + Seek> Read for PatchBlock<P> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = (self.end - self.pos).min(buf.len() as u64) as usize;
+        if len == 0 {
+            return Ok(0);
+        }
+        let mut patch = self.patch.lock().unwrap_or_else(PoisonError::into_inner);
+        patch.seek(SeekFrom::Start(self.pos))?;
+        let n = patch.read(&mut buf[..len])?;
+        drop(patch);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+/// Read part of the patch, reporting truncation as insufficient data
+fn read_patch_exact<P: Read>(patch: &mut P, buf: &mut [u8]) -> ZbsdiffResult<()> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match patch.read(&mut buf[filled..]) {
+            Ok(0) => return Err(ZbsdiffError::insufficient_data(buf.len(), filled)),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Read from a decompressed block, reporting early ends as corruption
+fn read_block_exact<R: Read>(block: &mut R, buf: &mut [u8], name: &str) -> ZbsdiffResult<()> {
+    block.read_exact(buf).map_err(|e| {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            ZbsdiffError::corrupt_patch(format!("{name} block ended early"))
+        } else {
+            ZbsdiffError::decompression_error(e)
+        }
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::zbsdiff::{
+        ControlBlock, ZbsDiff, ZbsdiffBuilder, apply_patch_memory, compress_zlib,
+    };
+
+    /// Deterministic pseudo-random bytes (xorshift)
+    fn pseudo_random(len: usize, mut state: u64) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    /// Build a patch from control entries, diff bytes and extra bytes
+    fn build_patch(entries: Vec<ControlEntry>, diff: &[u8], extra: &[u8]) -> Vec<u8> {
+        let control = ControlBlock::with_entries(entries).unwrap();
+        let output_size = control.total_output_size();
+        let control_data = control.to_compressed().unwrap();
+        let diff_data = compress_zlib(diff).unwrap();
+        let patch = ZbsDiff {
+            header: ZbsdiffHeader::new(
+                control_data.len() as i64,
+                diff_data.len() as i64,
+                output_size,
+            )
+            .unwrap(),
+            control_data,
+            diff_data,
+            extra_data: compress_zlib(extra).unwrap(),
+        };
+        patch.build().unwrap()
+    }
+
+    fn apply_streaming(old: &[u8], patch: &[u8], window_size: usize) -> ZbsdiffResult<Vec<u8>> {
+        let mut applicator = PatchApplicator::new(Cursor::new(old), Cursor::new(patch))?
+            .with_window_size(window_size);
+        let mut output = Vec::new();
+        applicator.read_to_end(&mut output).map_err(|e| {
+            *e.into_inner()
+                .and_then(|inner| inner.downcast::<ZbsdiffError>().ok())
+                .expect("Read errors should wrap a ZbsdiffError")
+        })?;
+        Ok(output)
+    }
+
+    #[test]
+    fn test_streaming_matches_memory_for_large_file() {
+        const MB: usize = 1_000_000;
+        let old = pseudo_random(10 * MB, 0x2545_f491_4f6c_dd1d);
+
+        // Walk the old file out of order: forward, back past the start of
+        // the previous chunk, then beyond its end
+        let mut entries = Vec::new();
+        let mut diff = Vec::new();
+        let mut extra = Vec::new();
+        for (i, seek) in [(0, 2 * MB as i64), (1, -4 * MB as i64), (2, 3 * MB as i64)] {
+            let diff_len = 3 * MB;
+            let mut chunk = pseudo_random(diff_len, i + 1);
+            // Mostly unchanged bytes, like a real patch
+            for byte in chunk.iter_mut().skip(1) {
+                if *byte % 16 != 0 {
+                    *byte = 0;
+                }
+            }
+            diff.extend_from_slice(&chunk);
+            let extra_chunk = pseudo_random(MB / 2, i + 100);
+            extra.extend_from_slice(&extra_chunk);
+            entries.push(ControlEntry::new(
+                diff_len as i64,
+                extra_chunk.len() as i64,
+                seek,
+            ));
+        }
+        let patch = build_patch(entries, &diff, &extra);
+
+        let expected = apply_patch_memory(&old, &patch).unwrap();
+        let streamed = apply_streaming(&old, &patch, DEFAULT_WINDOW_SIZE).unwrap();
+        assert_eq!(streamed.len(), expected.len());
+        assert!(
+            streamed == expected,
+            "Streaming output differs from in-memory output"
+        );
+    }
+
+    #[test]
+    fn test_streaming_matches_memory_for_built_patch() {
+        let old = pseudo_random(20_000, 7);
+        let mut new = old.clone();
+        new[5000..5100].copy_from_slice(&[0xAB; 100]);
+        new.drain(12_000..13_000);
+        new.extend_from_slice(b"appended");
+
+        let patch = ZbsdiffBuilder::new(old.clone(), new.clone())
+            .build()
+            .unwrap();
+        let streamed = apply_streaming(&old, &patch, 1024).unwrap();
+        assert_eq!(streamed, new);
+        assert_eq!(streamed, apply_patch_memory(&old, &patch).unwrap());
+    }
+
+    #[test]
+    fn test_small_reads_and_reads_past_old_eof() {
+        let old = b"short".to_vec();
+        // Diff 10 bytes from position 3, with 8 bytes past the end of old
+        let entries = vec![ControlEntry::new(0, 0, 3), ControlEntry::new(10, 2, 0)];
+        let patch = build_patch(entries, &[1; 10], b"!!");
+
+        let mut applicator = PatchApplicator::new(Cursor::new(&old), Cursor::new(&patch)).unwrap();
+        assert_eq!(applicator.output_size(), 12);
+        let mut output = Vec::new();
+        let mut byte = [0u8; 1];
+        while applicator.read(&mut byte).unwrap() == 1 {
+            output.push(byte[0]);
+        }
+        assert_eq!(output, b"su\x01\x01\x01\x01\x01\x01\x01\x01!!");
+    }
+
+    #[test]
+    fn test_truncated_patch() {
+        let patch = build_patch(vec![ControlEntry::new(4, 4, 0)], b"diff", b"xtra");
+
+        let header_only = &patch[..20];
+        assert!(matches!(
+            PatchApplicator::new(Cursor::new(b"old!"), Cursor::new(header_only)),
+            Err(ZbsdiffError::InsufficientData {
+                needed: 32,
+                available: 20
+            })
+        ));
+
+        // The extra block is only read while producing output
+        let extra_len = compress_zlib(b"xtra").unwrap().len();
+        let truncated = &patch[..patch.len() - extra_len + 2];
+        let result = apply_streaming(b"old!", truncated, 1024);
+        assert!(result.is_err(), "Truncated extra block should fail");
+    }
+
+    #[test]
+    fn test_output_size_mismatch() {
+        let mut patch = build_patch(vec![ControlEntry::new(4, 0, 0)], b"diff", b"");
+        // Declare one byte more output than the control block produces
+        patch[24..32].copy_from_slice(&5i64.to_le_bytes());
+        assert!(matches!(
+            apply_streaming(b"old!", &patch, 1024),
+            Err(ZbsdiffError::SizeMismatch {
+                expected: 5,
+                actual: 4
+            })
+        ));
+
+        // And one byte less
+        patch[24..32].copy_from_slice(&3i64.to_le_bytes());
+        assert!(matches!(
+            apply_streaming(b"old!", &patch, 1024),
+            Err(ZbsdiffError::SizeMismatch { expected: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_apply_patch_streaming_matches_memory() {
+        let old = pseudo_random(300_000, 11);
+        let mut new = old.clone();
+        new[1000..2000].copy_from_slice(&pseudo_random(1000, 12));
+        new.drain(150_000..160_000);
+        new.extend_from_slice(&pseudo_random(200_000, 13));
+        let patch = ZbsdiffBuilder::new(old.clone(), new.clone())
+            .build()
+            .unwrap();
+
+        let mut output = Vec::new();
+        let written =
+            apply_patch_streaming(Cursor::new(&old), Cursor::new(&patch), &mut output).unwrap();
+        assert_eq!(written, new.len() as u64);
+        assert_eq!(
+            md5::compute(&output),
+            md5::compute(apply_patch_memory(&old, &patch).unwrap())
+        );
+        assert_eq!(md5::compute(&output), md5::compute(&new));
+    }
+
+    #[test]
+    fn test_patch_starts_at_reader_position() {
+        let patch = build_patch(
+            vec![ControlEntry::new(4, 4, 0)],
+            b"\x01\x01\x01\x01",
+            b"xtra",
+        );
+        let mut stored = b"archive header".to_vec();
+        stored.extend_from_slice(&patch);
+        let mut reader = Cursor::new(stored);
+        reader.set_position(14);
+
+        let mut output = Vec::new();
+        apply_patch_streaming(Cursor::new(b"old!"), reader, &mut output).unwrap();
+        assert_eq!(output, b"pme\"xtra");
+
+        // A control block reaching past the end of the patch
+        let mut truncated = patch.clone();
+        truncated.truncate(34);
+        assert!(matches!(
+            PatchApplicator::new(Cursor::new(b"old!"), Cursor::new(truncated)),
+            Err(ZbsdiffError::InsufficientData { available: 34, .. })
+        ));
+    }
+
+    #[test]
+    fn test_apply_patch_streaming_reports_write_errors() {
+        let patch = build_patch(vec![ControlEntry::new(4, 4, 0)], b"diff", b"xtra");
+        let mut full = [0u8; 3];
+        let result =
+            apply_patch_streaming(Cursor::new(b"old!"), Cursor::new(&patch), &mut full[..]);
+        assert!(matches!(
+            result,
+            Err(ZbsdiffError::ApplicationFailed { .. })
+        ));
+    }
+}
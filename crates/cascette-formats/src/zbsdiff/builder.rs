@@ -20,7 +20,8 @@
 use crate::zbsdiff::{
     ZBSDIFF1_SIGNATURE, ZbsdiffHeader,
     error::ZbsdiffResult,
-    utils::{ControlBlock, ControlEntry, compress_zlib},
+    suffix::{self, DiffResult},
+    utils::{ControlBlock, ControlEntry, compress_zlib_with_level},
 };
 use binrw::BinWrite;
 use std::io::{Cursor, Write};
@@ -51,6 +52,44 @@ pub struct ZbsdiffBuilder {
     old_data: Vec<u8>,
     new_data: Vec<u8>,
     max_diff_block_size: usize,
+    options: PatchOptions,
+}
+
+/// Bytes of suffix array per byte of old data (one `i32` index)
+const SUFFIX_ARRAY_BYTES_PER_BYTE: usize = 4;
+
+/// Tuning for [`ZbsdiffBuilder::build`] and [`create_patch_with_options`]
+///
+/// The options trade patch size against time and memory:
+///
+/// - `zlib_level` sets the compression of the three data blocks. Level 1
+///   compresses several times faster than level 9, for patches typically a
+///   few percent larger. Decompression speed barely depends on the level,
+///   so patches for distribution should use 9, while throwaway test
+///   patches can use 1.
+/// - `max_memory` caps the suffix array, which takes 4 bytes per byte of
+///   old data. Above the cap, new and old data are split into windows of
+///   `max_memory / 4` bytes at the same offsets and diffed window by
+///   window. Each window only matches against the same region of the old
+///   file, so content that moved further than a window ends up as extra
+///   data and the patch grows. Smaller windows also sort faster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatchOptions {
+    /// Maximum suffix array size in bytes, `None` for no limit
+    ///
+    /// Above the limit the old and new data are diffed in windows.
+    pub max_memory: Option<usize>,
+    /// Zlib level for the control, diff and extra blocks (0-9, default 6)
+    pub zlib_level: u32,
+}
+
+impl Default for PatchOptions {
+    fn default() -> Self {
+        Self {
+            max_memory: None,
+            zlib_level: 6,
+        }
+    }
 }
 
 impl ZbsdiffBuilder {
@@ -64,9 +103,17 @@ impl ZbsdiffBuilder {
             old_data,
             new_data,
             max_diff_block_size: 1024 * 1024, // 1MB chunks for diff operations
+            options: PatchOptions::default(),
         }
     }
 
+    /// Set the memory cap and zlib level (default: no cap, level 6)
+    #[must_use]
+    pub fn with_options(mut self, options: PatchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
     /// Set the maximum size for diff blocks (default: 1MB)
     ///
     /// Smaller blocks use less memory but may result in larger patches.
@@ -162,6 +209,8 @@ impl ZbsdiffBuilder {
     /// Memory usage is approximately 5x the old data size (1 byte + 4 bytes
     /// per byte for the suffix array). The i32 suffix array index limits
     /// old data to ~2 GiB, which is within the header's 1 GiB validation cap.
+    /// [`PatchOptions::max_memory`] bounds the suffix array by diffing in
+    /// windows instead.
     pub fn build(&self) -> ZbsdiffResult<Vec<u8>> {
         self.build_optimized_patch()
     }
@@ -171,9 +220,15 @@ impl ZbsdiffBuilder {
     /// Equivalent to `build()`. Kept for API symmetry with `build_simple_patch()`
     /// and `build_chunked_patch()`.
     pub fn build_optimized_patch(&self) -> ZbsdiffResult<Vec<u8>> {
-        use super::suffix;
-
-        let result = suffix::compute_diff(&self.old_data, &self.new_data);
+        let window = self
+            .options
+            .max_memory
+            .map(|max_memory| (max_memory / SUFFIX_ARRAY_BYTES_PER_BYTE).max(1))
+            .filter(|&window| window < self.old_data.len());
+        let result = match window {
+            Some(window) => compute_windowed_diff(&self.old_data, &self.new_data, window),
+            None => suffix::compute_diff(&self.old_data, &self.new_data),
+        };
 
         // Handle empty new data: compute_diff returns no control entries,
         // but build_patch_internal requires at least one entry.
@@ -193,9 +248,10 @@ impl ZbsdiffBuilder {
         extra_data: Vec<u8>,
     ) -> ZbsdiffResult<Vec<u8>> {
         // Compress all blocks
-        let control_compressed = control_block.to_compressed()?;
-        let diff_compressed = compress_zlib(&diff_data)?;
-        let extra_compressed = compress_zlib(&extra_data)?;
+        let level = self.options.zlib_level;
+        let control_compressed = control_block.to_compressed_with_level(level)?;
+        let diff_compressed = compress_zlib_with_level(&diff_data, level)?;
+        let extra_compressed = compress_zlib_with_level(&extra_data, level)?;
 
         // Create header
         let header = ZbsdiffHeader {
@@ -324,6 +380,79 @@ impl PatchAnalysis {
     }
 }
 
+/// Diff `new` against `old` one window of `window` bytes at a time
+///
+/// Window `i` of the new data is diffed against the old data at the same
+/// offset, so the suffix array never covers more than `window` bytes. The
+/// diffs of a window only read old bytes inside it; the last seek of each
+/// window is adjusted to land on the start of the next.
+fn compute_windowed_diff(old: &[u8], new: &[u8], window: usize) -> DiffResult {
+    let mut result = DiffResult {
+        control: Vec::new(),
+        diff_data: Vec::new(),
+        extra_data: Vec::new(),
+    };
+    // Position in the old data the control entries so far end at
+    let mut old_pos = 0i64;
+
+    for (index, new_window) in new.chunks(window).enumerate() {
+        let start = (index * window).min(old.len());
+        let old_window = &old[start..(start + window).min(old.len())];
+        let part = suffix::compute_diff(old_window, new_window);
+
+        // Move from where the previous window ended to this window's start
+        if let Some(last) = result.control.last_mut() {
+            last.seek_offset += start as i64 - old_pos;
+        }
+        old_pos = start as i64
+            + part
+                .control
+                .iter()
+                .map(|entry| entry.diff_size + entry.seek_offset)
+                .sum::<i64>();
+
+        result.control.extend(part.control);
+        result.diff_data.extend(part.diff_data);
+        result.extra_data.extend(part.extra_data);
+    }
+    result
+}
+
+/// Create an optimized patch between two byte slices with `options`
+///
+/// The output is a regular ZBSDIFF1 patch whatever the options; see
+/// [`PatchOptions`] for their effect on size and speed.
+///
+/// # Examples
+///
+/// ```rust
+/// use cascette_formats::zbsdiff::{PatchOptions, apply_patch_memory, create_patch_with_options};
+///
+/// let old = b"The quick brown fox jumps over the lazy dog".repeat(100);
+/// let mut new = old.clone();
+/// new[10..15].copy_from_slice(b"green");
+///
+/// let options = PatchOptions {
+///     max_memory: Some(16 * 1024),
+///     zlib_level: 1,
+/// };
+/// let patch = create_patch_with_options(&old, &new, options).expect("Patch should build");
+/// assert_eq!(apply_patch_memory(&old, &patch).expect("Patch should apply"), new);
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the data is too large for the ZBSDIFF1 header.
+pub fn create_patch_with_options(
+    old_data: &[u8],
+    new_data: &[u8],
+    options: PatchOptions,
+) -> ZbsdiffResult<Vec<u8>> {
+    ZbsdiffBuilder::new(old_data.to_vec(), new_data.to_vec())
+        .with_options(options)
+        .build()
+}
+
 /// Convenience function to create a patch between two byte slices
 #[cfg(test)]
 #[allow(clippy::expect_used, clippy::unwrap_used)]
@@ -679,4 +808,96 @@ mod tests {
         let result = apply_patch_memory(&old_data, &patch).expect("apply should succeed");
         assert_eq!(result, new_data);
     }
+
+    /// Old data with regions of repeated text and noise, and a new version
+    /// with edits, a moved block and appended data
+    fn edited_pair() -> (Vec<u8>, Vec<u8>) {
+        use rand::{RngExt, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let mut old = b"0123456789abcdef".repeat(4096);
+        for byte in old.iter_mut().step_by(7) {
+            *byte = rng.random();
+        }
+        let mut new = old.clone();
+        for _ in 0..200 {
+            let pos = rng.random_range(0..new.len());
+            new[pos] = rng.random();
+        }
+        let moved: Vec<u8> = new.drain(1000..3000).collect();
+        new.extend_from_slice(&moved);
+        new.extend_from_slice(b"appended at the end");
+        (old, new)
+    }
+
+    #[test]
+    fn test_windowed_patch_round_trips() {
+        let (old, new) = edited_pair();
+        let unlimited = create_patch_with_options(&old, &new, PatchOptions::default()).unwrap();
+        assert_eq!(apply_patch_memory(&old, &unlimited).unwrap(), new);
+
+        for max_memory in [1, 4 * 1024, 64 * 1024, old.len() * 4] {
+            let options = PatchOptions {
+                max_memory: Some(max_memory),
+                ..PatchOptions::default()
+            };
+            let patch = create_patch_with_options(&old, &new, options).unwrap();
+            assert_eq!(
+                apply_patch_memory(&old, &patch).unwrap(),
+                new,
+                "max_memory {max_memory}"
+            );
+        }
+
+        // A cap the suffix array fits in changes nothing
+        let fits = PatchOptions {
+            max_memory: Some(old.len() * 4),
+            ..PatchOptions::default()
+        };
+        assert_eq!(
+            create_patch_with_options(&old, &new, fits).unwrap(),
+            unlimited
+        );
+    }
+
+    #[test]
+    fn test_windowed_patch_with_shorter_or_empty_old() {
+        let (old, new) = edited_pair();
+        let options = PatchOptions {
+            max_memory: Some(16 * 1024),
+            ..PatchOptions::default()
+        };
+        for old in [&old[..5000], &old[..1]] {
+            let patch = create_patch_with_options(old, &new, options).unwrap();
+            assert_eq!(apply_patch_memory(old, &patch).unwrap(), new);
+        }
+        let patch = create_patch_with_options(&old, b"", options).unwrap();
+        assert!(apply_patch_memory(&old, &patch).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_zlib_level_trades_size() {
+        let (old, new) = edited_pair();
+        let fast = create_patch_with_options(
+            &old,
+            &new,
+            PatchOptions {
+                zlib_level: 0,
+                ..PatchOptions::default()
+            },
+        )
+        .unwrap();
+        let best = create_patch_with_options(
+            &old,
+            &new,
+            PatchOptions {
+                zlib_level: 9,
+                ..PatchOptions::default()
+            },
+        )
+        .unwrap();
+        assert!(best.len() < fast.len());
+        assert_eq!(apply_patch_memory(&old, &fast).unwrap(), new);
+        assert_eq!(apply_patch_memory(&old, &best).unwrap(), new);
+    }
 }
@@ -50,6 +50,25 @@
 //! # }
 //! ```
 //!
+//! ## Streaming Patch Application
+//!
+//! Large files can be patched without holding them in memory:
+//!
+//! ```rust
+//! use std::fs::File;
+//! use std::io::{BufReader, BufWriter};
+//! use cascette_formats::zbsdiff;
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let old_file = File::open("old_file.bin")?;
+//! let patch_file = BufReader::new(File::open("file.zbsdiff")?);
+//! let new_file = BufWriter::new(File::create("new_file.bin")?);
+//!
+//! zbsdiff::apply_patch_streaming(old_file, patch_file, new_file)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ## Creating Patches
 //!
 //! ```rust
@@ -77,11 +96,15 @@
 //! - ✅ Zlib compression/decompression
 //! - ✅ Memory-based patch application
 //! - ✅ Streaming patch application for large files
+//!   ([`apply_patch_streaming`](crate::zbsdiff::apply_patch_streaming))
+//! - ✅ Incremental patch output through `Read`
+//!   ([`PatchApplicator`](crate::zbsdiff::PatchApplicator))
 //! - ✅ Suffix array-based patch creation (bsdiff algorithm)
 //! - ✅ Basic patch creation (simple and chunked, for testing)
 //! - ✅ Error handling
 //! - ✅ Round-trip validation
 
+mod applicator;
 mod builder;
 mod error;
 mod header;
@@ -90,11 +113,14 @@ mod suffix;
 mod utils;
 
 // Re-export public API
-pub use builder::ZbsdiffBuilder;
+pub use applicator::{PatchApplicator, apply_patch_streaming};
+pub use builder::{PatchOptions, ZbsdiffBuilder, create_patch_with_options};
 pub use error::{ZbsdiffError, ZbsdiffResult};
 pub use header::{ZBSDIFF1_SIGNATURE, ZbsdiffHeader};
 pub use patcher::{ZbsdiffPatcher, apply_patch_memory};
-pub use utils::{ControlBlock, ControlEntry, compress_zlib, decompress_zlib};
+pub use utils::{
+    ControlBlock, ControlEntry, compress_zlib, compress_zlib_with_level, decompress_zlib,
+};
 
 /// Main ZBSDIFF1 patch structure
 #[derive(Debug, Clone)]
@@ -13,7 +13,7 @@ use std::io::{Cursor, Read, Write};
 /// encoding: bit 63 is the sign bit, bits 0-62 hold the absolute value,
 /// stored in little-endian byte order. This differs from two's complement
 /// which Rust's `i64::from_le_bytes` assumes.
-fn offtin(buf: [u8; 8]) -> i64 {
+pub fn offtin(buf: [u8; 8]) -> i64 {
     let magnitude = i64::from_le_bytes([
         buf[0],
         buf[1],
@@ -47,7 +47,14 @@ fn offtout(value: i64) -> [u8; 8] {
 
 /// Compress data using zlib compression
 pub fn compress_zlib(data: &[u8]) -> ZbsdiffResult<Vec<u8>> {
-    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    compress_zlib_with_level(data, Compression::default().level())
+}
+
+/// Compress data using zlib compression at `level` (0-9, higher is smaller)
+///
+/// Levels above 9 are treated as 9.
+pub fn compress_zlib_with_level(data: &[u8], level: u32) -> ZbsdiffResult<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(level.min(9)));
     encoder.write_all(data)?;
     Ok(encoder.finish()?)
 }
@@ -196,6 +203,11 @@ impl ControlBlock {
 
     /// Compress the control block to bytes
     pub fn to_compressed(&self) -> ZbsdiffResult<Vec<u8>> {
+        self.to_compressed_with_level(Compression::default().level())
+    }
+
+    /// Compress the control block to bytes at zlib `level` (0-9)
+    pub fn to_compressed_with_level(&self, level: u32) -> ZbsdiffResult<Vec<u8>> {
         let mut uncompressed = Vec::new();
         let mut cursor = Cursor::new(&mut uncompressed);
 
@@ -205,7 +217,7 @@ impl ControlBlock {
             cursor.write_all(&offtout(entry.seek_offset))?;
         }
 
-        compress_zlib(&uncompressed)
+        compress_zlib_with_level(&uncompressed, level)
     }
 
     /// Add a control entry to this block
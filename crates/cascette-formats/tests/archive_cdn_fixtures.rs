@@ -5,6 +5,7 @@
 //! for WoW Classic Era and StarCraft 2. Validates parsing, footer
 //! integrity, entry sorting, and round-trip building.
 
+use cascette_formats::CascFormat;
 use cascette_formats::archive::{ArchiveIndex, ArchiveIndexBuilder};
 use std::io::Cursor;
 use std::path::Path;
@@ -236,6 +237,51 @@ fn archive_cdn_round_trip() {
     }
 }
 
+// --- Rebuilding reproduces the CDN bytes ---
+
+#[test]
+fn archive_cdn_rebuild_is_byte_identical() {
+    for (name, data) in &fixture_files() {
+        ArchiveIndex::verify_round_trip(data)
+            .unwrap_or_else(|e| panic!("{name}: verify_round_trip failed: {e}"));
+
+        let original = ArchiveIndex::parse(&mut Cursor::new(data))
+            .unwrap_or_else(|e| panic!("Parse failed for {name}: {e}"));
+        let mut builder = ArchiveIndexBuilder::new();
+        for entry in original.entries.iter().rev() {
+            builder.add_entry(entry.encoding_key.clone(), entry.size, entry.offset);
+        }
+        let mut output = Cursor::new(Vec::new());
+        let built = builder
+            .build(&mut output)
+            .unwrap_or_else(|e| panic!("Build failed for {name}: {e}"));
+        assert_eq!(built.footer, original.footer, "{name}: footer mismatch");
+        assert!(
+            output.into_inner() == *data,
+            "{name}: rebuilt bytes should match the CDN file"
+        );
+    }
+}
+
+// --- Index files are named by the MD5 of their footer ---
+
+#[test]
+fn archive_cdn_name_hash() {
+    let dir = fixtures_dir();
+    for filename in [
+        "0017a402f556fbece46c38dc431a2c9b.index",
+        "00b79cc0eebdd26437c7e92e57ac7f5c.index",
+        "s2_00872b40344ef1a3dac4aff09588603c.index",
+    ] {
+        let data = std::fs::read(dir.join(filename)).unwrap();
+        let index = ArchiveIndex::parse(&mut Cursor::new(&data)).unwrap();
+        let expected = filename
+            .trim_start_matches("s2_")
+            .trim_end_matches(".index");
+        assert_eq!(hex::encode(index.footer.name_hash()), expected);
+    }
+}
+
 // --- Binary search works on CDN data ---
 
 #[test]
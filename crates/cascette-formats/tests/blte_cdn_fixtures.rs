@@ -0,0 +1,99 @@
+#![allow(clippy::expect_used, clippy::unwrap_used, clippy::panic)]
+//! Integration tests for BLTE layout round trips using real CDN data
+//!
+//! The fixtures are the BLTE-encoded TVFS manifests downloaded from Blizzard
+//! CDN for WoW Retail, WoW Classic, and WoW Classic Era. All of them use a
+//! chunk table with the standard 0x0F flags byte.
+
+use cascette_formats::CascFormat;
+use cascette_formats::blte::{BlteBuilder, BlteFile, BlteLayout, HeaderFlags};
+use std::path::Path;
+
+fn fixtures_dir() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("test_fixtures/tvfs")
+        .leak()
+}
+
+/// Load the BLTE fixtures with their decompressed counterparts
+fn fixture_files() -> Vec<(&'static str, Vec<u8>, Vec<u8>)> {
+    let dir = fixtures_dir();
+    let files = [
+        ("wow_retail", "wow_dbd6a1911a9dd025"),
+        ("wow_classic", "wow_classic_cbd15a9f67c4d28d"),
+        ("wow_classic_era", "wow_classic_era_04ca19154f0c48b1"),
+    ];
+    files
+        .iter()
+        .map(|(name, stem)| {
+            let read = |ext: &str| {
+                let path = dir.join(format!("{stem}.{ext}"));
+                std::fs::read(&path)
+                    .unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e))
+            };
+            (*name, read("blte"), read("bin"))
+        })
+        .collect()
+}
+
+/// Rebuild a parsed file from its chunks in the given layout
+fn rebuild(blte: &BlteFile, layout: BlteLayout) -> BlteFile {
+    blte.chunks
+        .iter()
+        .cloned()
+        .fold(
+            BlteBuilder::new().with_layout(layout),
+            BlteBuilder::add_chunk,
+        )
+        .build()
+        .expect("Rebuild should succeed")
+}
+
+#[test]
+fn blte_cdn_rebuild_is_byte_identical() {
+    for (name, data, _) in &fixture_files() {
+        let blte = <BlteFile as CascFormat>::parse(data)
+            .unwrap_or_else(|e| panic!("Parse failed for {name}: {e}"));
+        assert_eq!(
+            blte.header.layout(),
+            BlteLayout::ChunkTable(HeaderFlags::Standard),
+            "{name}"
+        );
+
+        let rebuilt = rebuild(&blte, blte.header.layout());
+        assert_eq!(
+            &rebuilt.build().expect("BLTE should serialize"),
+            data,
+            "{name}: rebuilt bytes differ"
+        );
+    }
+}
+
+#[test]
+fn blte_cdn_chunkless_decompresses_identically() {
+    for (name, data, expected) in &fixture_files() {
+        let blte = <BlteFile as CascFormat>::parse(data)
+            .unwrap_or_else(|e| panic!("Parse failed for {name}: {e}"));
+        assert_eq!(blte.chunks.len(), 1, "{name}");
+
+        // The same chunk without a table decodes to the same content
+        let chunkless = rebuild(&blte, BlteLayout::Chunkless);
+        let bytes = chunkless.build().expect("BLTE should serialize");
+        assert_eq!(bytes[4..8], [0, 0, 0, 0], "{name}");
+        assert_eq!(bytes.len(), data.len() - 28, "{name}");
+
+        let reparsed =
+            <BlteFile as CascFormat>::parse(&bytes).expect("Chunkless BLTE should parse");
+        assert_eq!(reparsed.header.layout(), BlteLayout::Chunkless, "{name}");
+        assert_eq!(
+            &reparsed.decompress().expect("Decompression should succeed"),
+            expected,
+            "{name}"
+        );
+        assert_eq!(
+            &blte.decompress().expect("Decompression should succeed"),
+            expected,
+            "{name}"
+        );
+    }
+}
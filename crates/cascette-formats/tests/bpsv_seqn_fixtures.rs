@@ -0,0 +1,75 @@
+#![allow(clippy::expect_used, clippy::unwrap_used, clippy::panic)]
+//! Integration tests for BPSV sequence number handling
+//!
+//! Each fixture is a versions document exercising one sequence number case:
+//! no seqn line, seqn 0, a value above `u32::MAX`, and a malformed value.
+
+use cascette_formats::bpsv::{
+    BpsvWarning, ParseOptions, SequenceNumber, format, parse, parse_with_options,
+};
+use std::path::Path;
+
+fn fixtures_dir() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("test_fixtures/bpsv")
+        .leak()
+}
+
+fn read_fixture(name: &str) -> String {
+    let path = fixtures_dir().join(name);
+    std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Failed to read fixture {}: {}", path.display(), e))
+}
+
+#[test]
+fn bpsv_seqn_absent() {
+    let content = read_fixture("tact_versions_no_seqn.bpsv");
+    let doc = parse(&content).expect("Document without seqn should parse");
+
+    assert_eq!(doc.sequence(), &SequenceNumber::Absent);
+    assert_eq!(doc.sequence_number(), None);
+    assert_eq!(doc.row_count(), 3);
+    assert_eq!(format(&doc), content);
+}
+
+#[test]
+fn bpsv_seqn_zero() {
+    let content = read_fixture("versions_seqn_zero.bpsv");
+    let doc = parse(&content).expect("seqn 0 should parse");
+
+    assert_eq!(doc.sequence_number(), Some(0));
+    assert_ne!(doc.sequence(), &SequenceNumber::Absent);
+    assert_eq!(format(&doc), content);
+}
+
+#[test]
+fn bpsv_seqn_above_u32() {
+    let content = read_fixture("versions_seqn_timestamp.bpsv");
+    let doc = parse(&content).expect("u64 seqn should parse");
+
+    let seqn = doc.sequence_number().expect("seqn should be present");
+    assert_eq!(seqn, 20_261_014_093_015_221);
+    assert!(seqn > u64::from(u32::MAX));
+    assert_eq!(format(&doc), content);
+}
+
+#[test]
+fn bpsv_seqn_malformed() {
+    let content = read_fixture("versions_seqn_malformed.bpsv");
+    assert!(parse(&content).is_err(), "strict parsing rejects the line");
+
+    let doc = parse_with_options(&content, ParseOptions::lenient())
+        .expect("Lenient parsing should recover");
+    assert_eq!(doc.row_count(), 3);
+    assert_eq!(doc.sequence_number(), None);
+    assert!(doc.sequence().is_present());
+    assert_eq!(
+        doc.warnings(),
+        &[BpsvWarning::InvalidSequenceNumber(
+            "## seqn = 3016450-".to_string()
+        )]
+    );
+
+    // The malformed line is written back unchanged
+    assert_eq!(format(&doc), content);
+}
@@ -291,7 +291,7 @@ fn build_config_cdn_validate() {
         let result = config.validate();
         assert!(
             result.is_ok(),
-            "{name}: validation failed: {}",
+            "{name}: validation failed: {:?}",
             result.unwrap_err()
         );
     }
@@ -5,7 +5,10 @@
 //! via cascette-py. Covers Classic Era, Classic, and Retail patterns
 //! including 4-byte and 8-byte IVs in encrypted blocks.
 
-use cascette_formats::espec::{ESpec, ESpecError};
+use cascette_crypto::{TactKey, TactKeyStore};
+use cascette_formats::CascFormat;
+use cascette_formats::blte::{BlteBuilder, BlteFile};
+use cascette_formats::espec::{ESpec, ESpecError, EncodedSizeSample, RatioModel};
 use std::path::Path;
 
 fn fixtures_dir() -> &'static Path {
@@ -52,6 +55,39 @@ fn load_classic_era_especs() -> Vec<String> {
         .collect()
 }
 
+/// Load the encoded size samples, checking them against the BLTE fixtures
+fn load_size_samples() -> Vec<EncodedSizeSample> {
+    let path = fixtures_dir().join("size_samples.json");
+    let data = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Failed to read fixture {}: {}", path.display(), e));
+    let json: serde_json::Value =
+        serde_json::from_str(&data).expect("Failed to parse fixture JSON");
+    json["samples"]
+        .as_array()
+        .expect("samples should be an array")
+        .iter()
+        .map(|sample| {
+            let blte_path = fixtures_dir().join("..").join(
+                sample["blte_file"]
+                    .as_str()
+                    .expect("blte_file should be a string"),
+            );
+            let blte_size = std::fs::metadata(&blte_path)
+                .unwrap_or_else(|e| panic!("Failed to stat {}: {}", blte_path.display(), e))
+                .len();
+            let encoded_size = sample["encoded_size"].as_u64().expect("encoded_size");
+            assert_eq!(blte_size, encoded_size, "{}", blte_path.display());
+
+            EncodedSizeSample {
+                espec: ESpec::parse(sample["espec"].as_str().expect("espec should be a string"))
+                    .expect("Sample ESpec should parse"),
+                raw_size: sample["raw_size"].as_u64().expect("raw_size"),
+                encoded_size,
+            }
+        })
+        .collect()
+}
+
 // --- Parse all representative ESpecs ---
 
 #[test]
@@ -371,3 +407,217 @@ fn espec_cdn_retail_mixed_iv8() {
         other => panic!("Expected BlockTable, got {other:?}"),
     }
 }
+
+// --- Encoded size estimation ---
+
+#[test]
+fn espec_cdn_estimate_contains_real_sizes() {
+    let samples = load_size_samples();
+    assert!(!samples.is_empty(), "Should have size samples");
+
+    let default_model = RatioModel::default();
+    let fitted_model = RatioModel::fit(&samples);
+
+    for sample in &samples {
+        for model in [&default_model, &fitted_model] {
+            let estimate = sample.espec.estimate_encoded_size(sample.raw_size, model);
+            assert!(
+                estimate.contains(sample.encoded_size),
+                "{} bytes as {} encoded to {}, estimated {}..={}",
+                sample.raw_size,
+                sample.espec,
+                sample.encoded_size,
+                estimate.low,
+                estimate.high
+            );
+            // One chunk with a chunk table: 8 + 4 + 24 header bytes, mode byte
+            assert_eq!(estimate.overhead.total(), 37);
+            // Sizes far outside typical ratios are rejected
+            assert!(!estimate.contains(sample.raw_size * 2));
+            assert!(!estimate.contains(sample.raw_size / 25));
+        }
+    }
+}
+
+#[test]
+fn espec_cdn_fitted_model_predicts_held_out_sizes() {
+    let samples = load_size_samples();
+    assert!(samples.len() >= 3, "Should have samples to hold out");
+
+    for (held_out, sample) in samples.iter().enumerate() {
+        let training: Vec<_> = samples
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != held_out)
+            .map(|(_, sample)| sample.clone())
+            .collect();
+        let model = RatioModel::fit(&training);
+
+        // The model never saw this sample, yet predicts it within 10%
+        let estimate = sample.espec.estimate_encoded_size(sample.raw_size, &model);
+        let error = estimate.expected.abs_diff(sample.encoded_size);
+        assert!(
+            error * 10 < sample.encoded_size,
+            "{} bytes as {} encoded to {}, fitted expectation {}",
+            sample.raw_size,
+            sample.espec,
+            sample.encoded_size,
+            estimate.expected
+        );
+    }
+}
+
+#[test]
+fn espec_cdn_estimate_uncompressed_representative() {
+    // Every uncompressed pattern has no uncertainty
+    for input in load_representative_especs() {
+        let spec = ESpec::parse(&input).expect("ESpec should parse");
+        if spec.is_compressed() {
+            continue;
+        }
+        let raw_size = 3 * 1024 * 1024 + 7;
+        let estimate = spec.estimate_encoded_size(raw_size, &RatioModel::default());
+        assert!(estimate.is_exact(), "{input} should be estimated exactly");
+        assert_eq!(estimate.low, raw_size + estimate.overhead.total());
+    }
+}
+
+// --- Block planning and rebuilding ---
+
+/// Content length covering every declared block, capped to keep tests fast
+fn declared_len(spec: &ESpec) -> u64 {
+    let ESpec::BlockTable { chunks } = spec else {
+        return 4096;
+    };
+    let declared: u64 = chunks
+        .iter()
+        .map(|chunk| {
+            chunk.size_spec.as_ref().map_or(1000, |size| {
+                size.size.max(1) * u64::from(size.count.unwrap_or(1))
+            })
+        })
+        .sum();
+    declared.min(4 * 1024 * 1024)
+}
+
+fn test_content(len: u64) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+/// Key store holding a test key for every key named in `espec`
+fn keys_for(espec: &str) -> TactKeyStore {
+    let mut store = TactKeyStore::new();
+    for (index, part) in espec.split("e:{").skip(1).enumerate() {
+        let name = u64::from_str_radix(&part[..16], 16).expect("Key name should be hex");
+        store.add(TactKey::new(name, [index as u8; 16]));
+    }
+    store
+}
+
+#[test]
+fn espec_cdn_plan_covers_representative() {
+    for input in load_representative_especs() {
+        let spec = ESpec::parse(&input).expect("ESpec should parse");
+        let data_len = declared_len(&spec) + 12_345;
+        let plan = spec.plan(data_len);
+
+        let mut offset = 0;
+        for block in &plan {
+            assert_eq!(block.offset, offset, "{input}: blocks should be contiguous");
+            assert!(
+                !matches!(block.spec, ESpec::BlockTable { .. }),
+                "{input}: blocks should be leaf specs"
+            );
+            offset += block.size;
+        }
+        assert_eq!(offset, data_len, "{input}: plan should cover the content");
+    }
+}
+
+#[test]
+fn espec_cdn_rebuild_representative() {
+    for input in load_representative_especs() {
+        let spec = ESpec::parse(&input).expect("ESpec should parse");
+        let keys = keys_for(&input);
+        let data = test_content(declared_len(&spec));
+
+        let blte = BlteBuilder::from_espec(&spec, &data, Some(&keys))
+            .unwrap_or_else(|e| panic!("{input} should encode: {e}"))
+            .build()
+            .expect("BLTE should build");
+        assert_eq!(
+            blte.chunks.len(),
+            spec.plan(data.len() as u64).len(),
+            "{input}"
+        );
+        assert_eq!(
+            blte.header.extended.is_some(),
+            spec.has_chunk_table(),
+            "{input}"
+        );
+
+        let bytes = blte.build().expect("BLTE should serialize");
+        let parsed = BlteFile::parse(&bytes).expect("Rebuilt BLTE should parse");
+        assert_eq!(
+            parsed
+                .decompress_with_keys(&keys)
+                .unwrap_or_else(|e| panic!("{input} should decode: {e}")),
+            data,
+            "{input}"
+        );
+    }
+}
+
+#[test]
+fn espec_cdn_rebuild_matches_blte_layout() {
+    let path = fixtures_dir().join("size_samples.json");
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).expect("Failed to read size samples"))
+            .expect("Failed to parse fixture JSON");
+
+    for sample in json["samples"]
+        .as_array()
+        .expect("samples should be an array")
+    {
+        let blte_path = fixtures_dir()
+            .join("..")
+            .join(sample["blte_file"].as_str().expect("blte_file"));
+        let original_bytes = std::fs::read(&blte_path).expect("Failed to read BLTE fixture");
+        let original = BlteFile::parse(&original_bytes).expect("Fixture should parse");
+        let data = original.decompress().expect("Fixture should decompress");
+        let spec = ESpec::parse(sample["espec"].as_str().expect("espec")).expect("ESpec");
+
+        let rebuilt = BlteBuilder::from_espec(&spec, &data, None)
+            .expect("Content should encode")
+            .build()
+            .expect("BLTE should build");
+
+        // Same chunk table layout and chunk modes. The compressed bytes
+        // differ because flate2's default backend is not zlib.
+        let layout = |blte: &BlteFile| {
+            let extended = blte.header.extended.as_ref().expect("chunk table");
+            (
+                extended.flags,
+                extended
+                    .chunk_infos
+                    .iter()
+                    .map(|info| info.decompressed_size)
+                    .collect::<Vec<_>>(),
+                blte.chunks
+                    .iter()
+                    .map(|chunk| (chunk.mode, chunk.data[..2].to_vec()))
+                    .collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(
+            layout(&rebuilt),
+            layout(&original),
+            "{}",
+            blte_path.display()
+        );
+        assert_eq!(
+            rebuilt.decompress().expect("Rebuilt BLTE should decode"),
+            data
+        );
+    }
+}
@@ -113,6 +113,22 @@ fn root_cdn_v1_lookup_tables() {
     assert!(fdid_count <= expected_total);
 }
 
+#[test]
+fn root_cdn_v1_lookup_by_filename_hash() {
+    let data = read_fixture("classic_era_v1_2blocks.root");
+    let root = root::file::RootFile::parse(&data).expect("V1 root parse should succeed");
+
+    // Every name hash resolves to the first record carrying it
+    let mut first_by_hash = std::collections::HashMap::new();
+    for record in root.iter_records() {
+        let hash = record.name_hash.expect("V1 records have name hashes");
+        first_by_hash.entry(hash).or_insert(record.content_key);
+    }
+    for (hash, content_key) in &first_by_hash {
+        assert_eq!(root.lookup_by_filename_hash(*hash), Some(content_key));
+    }
+}
+
 // --- V2 Retail root file tests ---
 
 #[test]
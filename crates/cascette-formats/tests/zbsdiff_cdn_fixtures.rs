@@ -22,10 +22,12 @@
 //!
 //! If no fixture files are present, the tests are skipped.
 
+use std::io::Read;
 use std::path::PathBuf;
 
 use cascette_formats::zbsdiff::{
-    self, ControlBlock, ZBSDIFF1_SIGNATURE, ZbsDiff, ZbsdiffBuilder, ZbsdiffHeader, ZbsdiffPatcher,
+    self, ControlBlock, PatchApplicator, ZBSDIFF1_SIGNATURE, ZbsDiff, ZbsdiffBuilder,
+    ZbsdiffHeader, ZbsdiffPatcher,
 };
 
 fn fixture_dir() -> PathBuf {
@@ -361,6 +363,48 @@ fn zbsdiff_cdn_apply_patch_streaming_small_buffer() {
     );
 }
 
+#[test]
+fn zbsdiff_cdn_apply_patch_applicator() {
+    let triplets = collect_triplets();
+    if triplets.is_empty() {
+        eprintln!(
+            "SKIPPED: no .old/.new/.zbsdiff triplets in {}",
+            fixture_dir().display()
+        );
+        return;
+    }
+
+    for triplet in &triplets {
+        let old_cursor = std::io::Cursor::new(&triplet.old_data);
+        let mut applicator =
+            PatchApplicator::new(old_cursor, std::io::Cursor::new(&triplet.patch_data))
+                .unwrap_or_else(|e| panic!("{}: applicator setup failed: {e}", triplet.name));
+
+        let mut result = Vec::new();
+        applicator
+            .read_to_end(&mut result)
+            .unwrap_or_else(|e| panic!("{}: incremental apply failed: {e}", triplet.name));
+
+        assert_eq!(
+            result, triplet.new_data,
+            "{}: incremental output does not match expected new file",
+            triplet.name,
+        );
+
+        eprintln!(
+            "  {}: incremental {} -> {} bytes OK",
+            triplet.name,
+            triplet.old_data.len(),
+            triplet.new_data.len(),
+        );
+    }
+
+    eprintln!(
+        "Incrementally applied {} CDN patches with correct output",
+        triplets.len()
+    );
+}
+
 #[test]
 fn zbsdiff_cdn_builder_round_trip() {
     let triplets = collect_triplets();
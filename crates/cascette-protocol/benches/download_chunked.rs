@@ -0,0 +1,131 @@
+//! Benchmark of a chunked CDN download against a single full download.
+//!
+//! A local mock CDN delays each response in proportion to its size,
+//! standing in for a per-connection bandwidth limit. Each iteration uses
+//! a fresh cache so that the file is downloaded.
+//!
+//! Run with:
+//! ```bash
+//! cargo bench -p cascette-protocol --bench download_chunked
+//! ```
+
+#![allow(clippy::expect_used)]
+
+use cascette_protocol::cache::ProtocolCache;
+use cascette_protocol::{CacheConfig, CdnClient, CdnConfig, CdnEndpoint, ContentType};
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::runtime::Runtime;
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+const FILE_SIZE: usize = 4 * 1024 * 1024;
+const CHUNKS: usize = 4;
+/// Simulated transfer time per MiB on one connection
+const LATENCY_PER_MIB: Duration = Duration::from_millis(20);
+
+/// Serves the file, honoring `Range` headers, at the simulated bandwidth
+struct ThrottledFile(Vec<u8>);
+
+impl Respond for ThrottledFile {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let range = request
+            .headers
+            .get("range")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("bytes="))
+            .and_then(|v| v.split_once('-'))
+            .and_then(|(start, end)| Some((start.parse().ok()?, end.parse::<usize>().ok()?)));
+
+        let (status, body) = match range {
+            Some((start, end)) => (206, &self.0[start..=end.min(self.0.len() - 1)]),
+            None => (200, &self.0[..]),
+        };
+        // HEAD responses carry no body, so they are not throttled
+        let delay = if request.method.as_str() == "HEAD" {
+            Duration::ZERO
+        } else {
+            let kib = u32::try_from(body.len() / 1024).expect("Body should fit in u32 KiB");
+            LATENCY_PER_MIB * kib / 1024
+        };
+        ResponseTemplate::new(status)
+            .set_body_bytes(body.to_vec())
+            .set_delay(delay)
+    }
+}
+
+/// Client with an empty cache; the directory must outlive the client
+fn fresh_client() -> (CdnClient, TempDir) {
+    let temp_dir = TempDir::new().expect("Failed to create cache directory");
+    let config = CacheConfig {
+        cache_dir: Some(temp_dir.path().to_path_buf()),
+        ..Default::default()
+    };
+    let cache = Arc::new(ProtocolCache::new(&config).expect("Failed to create cache"));
+    let client = CdnClient::new(cache, CdnConfig::default()).expect("Failed to create client");
+    (client, temp_dir)
+}
+
+fn bench_downloads(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("Failed to create runtime");
+    let body: Vec<u8> = (0..FILE_SIZE).map(|i| (i % 251) as u8).collect();
+    let key = md5::compute(&body).0;
+    let server = runtime.block_on(async {
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::any())
+            .respond_with(ThrottledFile(body))
+            .mount(&server)
+            .await;
+        server
+    });
+    let endpoint = CdnEndpoint {
+        host: server.uri().replace("http://", ""),
+        path: "tpr/wow".to_string(),
+        product_path: None,
+        scheme: Some("http".to_string()),
+        is_fallback: false,
+        strict: false,
+        max_hosts: None,
+    };
+
+    let mut group = c.benchmark_group("cdn_download_chunked");
+    group.sample_size(10);
+
+    group.bench_function("single", |b| {
+        b.iter_batched(
+            fresh_client,
+            |(client, _temp_dir)| {
+                let data = runtime
+                    .block_on(client.download(&endpoint, ContentType::Data, &key))
+                    .expect("Download failed");
+                black_box(data);
+            },
+            BatchSize::PerIteration,
+        );
+    });
+
+    group.bench_function("chunked_4", |b| {
+        b.iter_batched(
+            fresh_client,
+            |(client, _temp_dir)| {
+                let data = runtime
+                    .block_on(client.download_chunked(
+                        &endpoint,
+                        ContentType::Data,
+                        &key,
+                        FILE_SIZE / CHUNKS,
+                    ))
+                    .expect("Download failed");
+                black_box(data);
+            },
+            BatchSize::PerIteration,
+        );
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_downloads);
+criterion_main!(benches);
@@ -0,0 +1,118 @@
+//! Benchmark of concurrent CDN downloads against sequential ones.
+//!
+//! A local mock CDN answers every request after a fixed delay, standing in
+//! for network latency. Each iteration uses a fresh cache so that every
+//! file is downloaded.
+//!
+//! Run with:
+//! ```bash
+//! cargo bench -p cascette-protocol --bench download_parallel
+//! ```
+
+#![allow(clippy::expect_used)]
+
+use cascette_protocol::cache::ProtocolCache;
+use cascette_protocol::{CacheConfig, CdnClient, CdnConfig, CdnEndpoint, ContentType};
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::runtime::Runtime;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const FILES: u8 = 32;
+const LATENCY: Duration = Duration::from_millis(10);
+
+fn requests() -> Vec<(ContentType, Vec<u8>)> {
+    (0..FILES)
+        .map(|i| (ContentType::Data, vec![i, i, 0xcd, 0xef, 0x12, 0x34]))
+        .collect()
+}
+
+/// Client with an empty cache; the directory must outlive the client
+fn fresh_client() -> (CdnClient, TempDir) {
+    let temp_dir = TempDir::new().expect("Failed to create cache directory");
+    let config = CacheConfig {
+        cache_dir: Some(temp_dir.path().to_path_buf()),
+        ..Default::default()
+    };
+    let cache = Arc::new(ProtocolCache::new(&config).expect("Failed to create cache"));
+    let client = CdnClient::new(cache, CdnConfig::default()).expect("Failed to create client");
+    (client, temp_dir)
+}
+
+fn bench_downloads(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("Failed to create runtime");
+    let server = runtime.block_on(async {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_bytes(vec![0_u8; 64 * 1024])
+                    .set_delay(LATENCY),
+            )
+            .mount(&server)
+            .await;
+        server
+    });
+    let endpoint = CdnEndpoint {
+        host: server.uri().replace("http://", ""),
+        path: "tpr/wow".to_string(),
+        product_path: None,
+        scheme: Some("http".to_string()),
+        is_fallback: false,
+        strict: false,
+        max_hosts: None,
+    };
+
+    let mut group = c.benchmark_group("cdn_download");
+    group.sample_size(10);
+
+    group.bench_function("sequential", |b| {
+        b.iter_batched(
+            fresh_client,
+            |(client, _temp_dir)| {
+                runtime.block_on(async {
+                    for (content_type, key) in requests() {
+                        black_box(
+                            client
+                                .download(&endpoint, content_type, &key)
+                                .await
+                                .expect("Download failed"),
+                        );
+                    }
+                });
+            },
+            BatchSize::PerIteration,
+        );
+    });
+
+    for concurrency in [4, 16] {
+        group.bench_with_input(
+            BenchmarkId::new("parallel", concurrency),
+            &concurrency,
+            |b, &concurrency| {
+                b.iter_batched(
+                    fresh_client,
+                    |(client, _temp_dir)| {
+                        let results = runtime.block_on(client.download_parallel(
+                            &endpoint,
+                            requests(),
+                            concurrency,
+                        ));
+                        assert!(results.iter().all(Result::is_ok));
+                        black_box(results);
+                    },
+                    BatchSize::PerIteration,
+                );
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_downloads);
+criterion_main!(benches);
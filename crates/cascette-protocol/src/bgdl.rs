@@ -0,0 +1,204 @@
+//! Typed view of the `v1/products/{product}/bgdl` endpoint
+//!
+//! Background download (BGDL) lists the builds the launcher may pre-fetch
+//! before they go live. Rows share the versions schema:
+//!
+//! ```text
+//! Region!STRING:0|BuildConfig!HEX:16|CDNConfig!HEX:16|KeyRing!HEX:16|BuildId!DEC:4|VersionsName!String:0|ProductConfig!HEX:16
+//! ## seqn = 2950123
+//! us|e359107662e72559b4e1ab721b157cb0|48c0f2cc2681a23758a3b4fc6f6e1f3a||61491|11.1.0.61491|53020d32e1a25648c8e1eafd5771935f
+//! ```
+//!
+//! Products without a pending background download serve the header and
+//! seqn line only, which parses to an empty list.
+
+use crate::error::{ProtocolError, Result};
+use cascette_formats::bpsv::{BpsvDocument, BpsvRow, BpsvSchema, BpsvValue};
+
+/// One region row of a BGDL response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BgdlEntry {
+    /// Region code (e.g. `us`)
+    pub region: String,
+    /// Build config hash as lowercase hex
+    pub build_config: String,
+    /// CDN config hash as lowercase hex
+    pub cdn_config: String,
+    /// Key ring hash as lowercase hex, if the build has one
+    pub key_ring: Option<String>,
+    /// Build number
+    pub build_id: u32,
+    /// Version string (e.g. `11.1.0.61491`)
+    pub versions_name: String,
+    /// Product config hash as lowercase hex, if the build has one
+    ///
+    /// Fetch it with [`CdnClient::get_product_config`](crate::CdnClient::get_product_config).
+    pub product_config: Option<String>,
+}
+
+/// Parse a BGDL response into typed entries
+///
+/// # Errors
+///
+/// Returns `ProtocolError::Parse` if a row lacks a required field or its
+/// build number is out of range.
+pub fn parse_bgdl(document: &BpsvDocument) -> Result<Vec<BgdlEntry>> {
+    let schema = document.schema();
+
+    document
+        .rows()
+        .iter()
+        .map(|row| {
+            let build_id = row
+                .get_by_name("BuildId", schema)
+                .and_then(BpsvValue::as_dec)
+                .ok_or_else(|| ProtocolError::Parse("Missing BuildId field".to_string()))?;
+            let build_id = u32::try_from(build_id)
+                .map_err(|_| ProtocolError::Parse(format!("Invalid BuildId value: {build_id}")))?;
+
+            Ok(BgdlEntry {
+                region: required(row, schema, "Region")?,
+                build_config: required(row, schema, "BuildConfig")?,
+                cdn_config: required(row, schema, "CDNConfig")?,
+                key_ring: optional(row, schema, "KeyRing"),
+                build_id,
+                versions_name: required(row, schema, "VersionsName")?,
+                product_config: optional(row, schema, "ProductConfig"),
+            })
+        })
+        .collect()
+}
+
+fn required(row: &BpsvRow, schema: &BpsvSchema, name: &str) -> Result<String> {
+    optional(row, schema, name).ok_or_else(|| ProtocolError::Parse(format!("Missing {name} field")))
+}
+
+fn optional(row: &BpsvRow, schema: &BpsvSchema, name: &str) -> Option<String> {
+    row.get_raw_by_name(name, schema)
+        .filter(|value| !value.is_empty())
+        .map(str::to_ascii_lowercase)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::{CacheConfig, ClientConfig, RibbitTactClient};
+    use cascette_formats::CascFormat;
+    use tempfile::TempDir;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const BGDL: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/bgdl_wow.bpsv"
+    ));
+
+    fn parse(bpsv: &str) -> BpsvDocument {
+        <BpsvDocument as CascFormat>::parse(bpsv.as_bytes()).expect("Fixture should parse")
+    }
+
+    #[test]
+    fn test_parse_bgdl_fixture() {
+        let entries = parse_bgdl(&parse(BGDL)).expect("Fixture should convert");
+        assert_eq!(entries.len(), 5);
+
+        let us = &entries[0];
+        assert_eq!(us.region, "us");
+        assert_eq!(us.build_config, "e359107662e72559b4e1ab721b157cb0");
+        assert_eq!(us.cdn_config, "48c0f2cc2681a23758a3b4fc6f6e1f3a");
+        assert_eq!(us.key_ring, None);
+        assert_eq!(us.build_id, 61491);
+        assert_eq!(us.versions_name, "11.1.0.61491");
+        assert_eq!(
+            us.product_config.as_deref(),
+            Some("53020d32e1a25648c8e1eafd5771935f")
+        );
+
+        let cn = entries
+            .iter()
+            .find(|entry| entry.region == "cn")
+            .expect("Fixture has a cn row");
+        assert_eq!(
+            cn.key_ring.as_deref(),
+            Some("3ca57fe7319a297346440e4d2a03a0cd")
+        );
+    }
+
+    #[test]
+    fn test_parse_bgdl_without_rows() {
+        let document = parse(
+            "Region!STRING:0|BuildConfig!HEX:16|CDNConfig!HEX:16|KeyRing!HEX:16|BuildId!DEC:4|VersionsName!String:0|ProductConfig!HEX:16\n\
+             ## seqn = 2950123\n",
+        );
+        assert!(
+            parse_bgdl(&document)
+                .expect("Empty BGDL is valid")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_parse_bgdl_missing_field() {
+        let document = parse(
+            "Region!STRING:0|CDNConfig!HEX:16|BuildId!DEC:4|VersionsName!String:0\n\
+             us|48c0f2cc2681a23758a3b4fc6f6e1f3a|61491|11.1.0.61491\n",
+        );
+        let err = parse_bgdl(&document).expect_err("BuildConfig is required");
+        assert!(matches!(err, ProtocolError::Parse(msg) if msg.contains("BuildConfig")));
+    }
+
+    fn client_for(server: &MockServer, temp_dir: &TempDir) -> RibbitTactClient {
+        let config = ClientConfig {
+            tact_https_url: String::new(),
+            tact_http_url: server.uri(),
+            ribbit_url: String::new(),
+            cache_config: CacheConfig {
+                cache_dir: Some(temp_dir.path().to_path_buf()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        RibbitTactClient::new(config).expect("Operation should succeed")
+    }
+
+    #[tokio::test]
+    async fn test_query_bgdl() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/wow/bgdl"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(BGDL))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let entries = client_for(&server, &temp_dir)
+            .query_bgdl("wow")
+            .await
+            .expect("Query should succeed");
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[0].versions_name, "11.1.0.61491");
+    }
+
+    #[tokio::test]
+    async fn test_query_bgdl_not_found() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/nonexistent/bgdl"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let err = client_for(&server, &temp_dir)
+            .query_bgdl("nonexistent")
+            .await
+            .expect_err("Query should fail");
+        assert!(
+            matches!(&err, ProtocolError::NotFound(endpoint) if endpoint == "v1/products/nonexistent/bgdl"),
+            "unexpected error: {err}"
+        );
+    }
+}
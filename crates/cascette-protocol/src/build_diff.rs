@@ -0,0 +1,732 @@
+//! Comparison of two builds of a product
+//!
+//! A patch shows up as a new versions row: the build and CDN config hashes
+//! change, and the root file behind the build config lists which
+//! `FileDataID`s were added, removed or given new content.
+//! [`CdnClient::diff_builds`] compares two rows, which may come from
+//! different regions:
+//!
+//! ```text
+//! us 11.1.0.61491 (build 61491) -> us 11.1.5.62000 (build 62000)
+//!   build config: f731... -> 1c2d...
+//!     build_name:   WOW-61491patch11.1.0_Retail -> WOW-62000patch11.1.5_Retail
+//!     root:         0f3b... -> 8e9f...
+//!   cdn config:   unchanged
+//!   files:        12 added, 3 removed, 40 changed
+//! ```
+//!
+//! The file comparison needs the encoding and root files of both builds.
+//! When one of them cannot be fetched or decoded, for example because the
+//! root file is encrypted with a key that is not in the key store, the
+//! diff still reports the config changes and gives the reason in
+//! [`FileDiff::Unavailable`].
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use cascette_crypto::{ContentKey, TactKeyStore};
+use cascette_formats::CascFormat;
+use cascette_formats::blte::BlteFile;
+use cascette_formats::encoding::EncodingFile;
+use cascette_formats::root::RootFile;
+use serde::Serialize;
+
+use crate::bgdl::BgdlEntry;
+use crate::cdn::{CdnClient, CdnEndpoint, ContentType};
+use crate::error::{ProtocolError, Result};
+use crate::version_configs::{BuildConfigSummary, config_key};
+
+/// Identity of one side of a diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuildRef {
+    /// Region code of the row
+    pub region: String,
+    /// Version string of the row
+    pub versions_name: String,
+    /// Build number of the row
+    pub build_id: u32,
+    /// Build config hash
+    pub build_config: String,
+    /// CDN config hash
+    pub cdn_config: String,
+}
+
+impl BuildRef {
+    fn from_entry(entry: &BgdlEntry) -> Self {
+        Self {
+            region: entry.region.clone(),
+            versions_name: entry.versions_name.clone(),
+            build_id: entry.build_id,
+            build_config: entry.build_config.clone(),
+            cdn_config: entry.cdn_config.clone(),
+        }
+    }
+}
+
+/// A build config field whose value differs between the builds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldChange {
+    /// Field name, as in [`BuildConfigSummary`]
+    pub field: &'static str,
+    /// Value in the first build
+    pub from: Option<String>,
+    /// Value in the second build
+    pub to: Option<String>,
+}
+
+/// `FileDataID`s that differ between two root files, each list ascending
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FileDataIdDiff {
+    /// Only in the second root file
+    pub added: Vec<u32>,
+    /// Only in the first root file
+    pub removed: Vec<u32>,
+    /// In both, with different content keys
+    pub changed: Vec<u32>,
+}
+
+impl FileDataIdDiff {
+    /// Compare the `FileDataID`s of two root files
+    ///
+    /// A `FileDataID` with several entries (one per locale, say) counts as
+    /// changed if its set of content keys differs.
+    pub fn between(from: &RootFile, to: &RootFile) -> Self {
+        let from = content_keys_by_id(from);
+        let to = content_keys_by_id(to);
+
+        let mut diff = Self::default();
+        for (fdid, keys) in &from {
+            match to.get(fdid) {
+                None => diff.removed.push(*fdid),
+                Some(other) if other != keys => diff.changed.push(*fdid),
+                Some(_) => {}
+            }
+        }
+        diff.added = to
+            .keys()
+            .filter(|fdid| !from.contains_key(fdid))
+            .copied()
+            .collect();
+        diff
+    }
+
+    /// Whether the root files list the same content
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Sorted, deduplicated content keys of each `FileDataID`
+fn content_keys_by_id(root: &RootFile) -> BTreeMap<u32, Vec<ContentKey>> {
+    let mut files: BTreeMap<u32, Vec<ContentKey>> = BTreeMap::new();
+    for record in root.iter_records() {
+        files
+            .entry(record.file_data_id.get())
+            .or_default()
+            .push(record.content_key);
+    }
+    for keys in files.values_mut() {
+        keys.sort_unstable_by_key(|key| *key.as_bytes());
+        keys.dedup();
+    }
+    files
+}
+
+/// Outcome of the file comparison
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum FileDiff {
+    /// Both root files were compared
+    Compared(FileDataIdDiff),
+    /// The root files could not be compared; only configs were diffed
+    Unavailable {
+        /// Why the comparison was skipped
+        reason: String,
+    },
+}
+
+/// Differences between two builds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuildDiff {
+    /// First build
+    pub from: BuildRef,
+    /// Second build
+    pub to: BuildRef,
+    /// Whether the build config hashes differ
+    pub build_config_changed: bool,
+    /// Whether the CDN config hashes differ
+    pub cdn_config_changed: bool,
+    /// Build config fields that differ, if both build configs were fetched
+    pub build_config_fields: Vec<FieldChange>,
+    /// `FileDataID` changes, if both root files could be compared
+    pub files: FileDiff,
+}
+
+impl fmt::Display for BuildDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} {} (build {}) -> {} {} (build {})",
+            self.from.region,
+            self.from.versions_name,
+            self.from.build_id,
+            self.to.region,
+            self.to.versions_name,
+            self.to.build_id
+        )?;
+
+        write!(f, "  build config: ")?;
+        write_hash_change(
+            f,
+            self.build_config_changed,
+            &self.from.build_config,
+            &self.to.build_config,
+        )?;
+        for change in &self.build_config_fields {
+            let width = 14usize.saturating_sub(change.field.len() + 1);
+            writeln!(
+                f,
+                "    {}:{:width$}{} -> {}",
+                change.field,
+                "",
+                abbreviate_field(change.field, change.from.as_deref()),
+                abbreviate_field(change.field, change.to.as_deref())
+            )?;
+        }
+
+        write!(f, "  cdn config:   ")?;
+        write_hash_change(
+            f,
+            self.cdn_config_changed,
+            &self.from.cdn_config,
+            &self.to.cdn_config,
+        )?;
+
+        write!(f, "  files:        ")?;
+        match &self.files {
+            FileDiff::Compared(diff) if diff.is_empty() => writeln!(f, "unchanged"),
+            FileDiff::Compared(diff) => writeln!(
+                f,
+                "{} added, {} removed, {} changed",
+                diff.added.len(),
+                diff.removed.len(),
+                diff.changed.len()
+            ),
+            FileDiff::Unavailable { reason } => writeln!(f, "not compared ({reason})"),
+        }
+    }
+}
+
+fn write_hash_change(
+    f: &mut fmt::Formatter<'_>,
+    changed: bool,
+    from: &str,
+    to: &str,
+) -> fmt::Result {
+    if changed {
+        writeln!(f, "{} -> {}", abbreviate(from), abbreviate(to))
+    } else {
+        writeln!(f, "unchanged")
+    }
+}
+
+/// First four hex characters of a hash, for the text summary.
+fn abbreviate(hash: &str) -> String {
+    format!("{}...", hash.get(..4).unwrap_or(hash))
+}
+
+/// Field value for the text summary; only hashes are abbreviated
+fn abbreviate_field(field: &str, value: Option<&str>) -> String {
+    match value {
+        None => "none".to_string(),
+        Some(value) if field.starts_with("build_") => value.to_string(),
+        Some(value) => abbreviate(value),
+    }
+}
+
+/// Build config fields that differ between two summaries
+fn changed_fields(from: &BuildConfigSummary, to: &BuildConfigSummary) -> Vec<FieldChange> {
+    let fields: [(&'static str, &Option<String>, &Option<String>); 9] = [
+        ("build_name", &from.build_name, &to.build_name),
+        ("build_uid", &from.build_uid, &to.build_uid),
+        ("build_product", &from.build_product, &to.build_product),
+        ("root", &from.root, &to.root),
+        ("encoding", &from.encoding, &to.encoding),
+        ("encoding_key", &from.encoding_key, &to.encoding_key),
+        ("install", &from.install, &to.install),
+        ("download", &from.download, &to.download),
+        ("patch_config", &from.patch_config, &to.patch_config),
+    ];
+    fields
+        .into_iter()
+        .filter(|(_, from, to)| from != to)
+        .map(|(field, from, to)| FieldChange {
+            field,
+            from: from.clone(),
+            to: to.clone(),
+        })
+        .collect()
+}
+
+impl CdnClient {
+    /// Compare the builds referenced by two versions rows
+    ///
+    /// Each row comes with the CDN endpoint to fetch it from, so builds of
+    /// different regions can be compared. The config hashes are compared
+    /// directly. The build configs are fetched to list changed fields and
+    /// to locate the root files, which are compared when both can be
+    /// fetched; encrypted root files are decoded with `key_store`. Failure
+    /// to fetch a document degrades the diff instead of failing it: see
+    /// [`FileDiff::Unavailable`].
+    pub async fn diff_builds(
+        &self,
+        (from_endpoint, from): (&CdnEndpoint, &BgdlEntry),
+        (to_endpoint, to): (&CdnEndpoint, &BgdlEntry),
+        key_store: Option<&TactKeyStore>,
+    ) -> BuildDiff {
+        let (from_build, to_build) = futures::join!(
+            self.fetch_build_config_summary(from_endpoint, &from.build_config),
+            self.fetch_build_config_summary(to_endpoint, &to.build_config)
+        );
+
+        let mut diff = BuildDiff {
+            from: BuildRef::from_entry(from),
+            to: BuildRef::from_entry(to),
+            build_config_changed: !from.build_config.eq_ignore_ascii_case(&to.build_config),
+            cdn_config_changed: !from.cdn_config.eq_ignore_ascii_case(&to.cdn_config),
+            build_config_fields: Vec::new(),
+            files: FileDiff::Unavailable {
+                reason: String::new(),
+            },
+        };
+
+        let (from_build, to_build) = match (from_build, to_build) {
+            (Ok(from_build), Ok(to_build)) => (from_build, to_build),
+            (Err(e), _) | (_, Err(e)) => {
+                diff.files = FileDiff::Unavailable {
+                    reason: format!("build config unavailable: {e}"),
+                };
+                return diff;
+            }
+        };
+        diff.build_config_fields = changed_fields(&from_build, &to_build);
+
+        // Same root content key, same FileDataIDs: nothing to download
+        if from_build.root.is_some() && from_build.root == to_build.root {
+            diff.files = FileDiff::Compared(FileDataIdDiff::default());
+            return diff;
+        }
+
+        let (from_root, to_root) = futures::join!(
+            self.fetch_root_file(from_endpoint, &from_build, key_store),
+            self.fetch_root_file(to_endpoint, &to_build, key_store)
+        );
+        diff.files = match (from_root, to_root) {
+            (Ok(from_root), Ok(to_root)) => {
+                FileDiff::Compared(FileDataIdDiff::between(&from_root, &to_root))
+            }
+            (Err(e), _) | (_, Err(e)) => FileDiff::Unavailable {
+                reason: e.to_string(),
+            },
+        };
+        diff
+    }
+
+    /// Download and parse the root file of a build
+    ///
+    /// The root file is located through the build's encoding file.
+    async fn fetch_root_file(
+        &self,
+        endpoint: &CdnEndpoint,
+        build: &BuildConfigSummary,
+        key_store: Option<&TactKeyStore>,
+    ) -> Result<RootFile> {
+        let missing =
+            |field: &str| ProtocolError::Parse(format!("Build config has no {field} entry"));
+        let encoding_key = build
+            .encoding_key
+            .as_deref()
+            .ok_or_else(|| missing("encoding key"))?;
+        let root = build.root.as_deref().ok_or_else(|| missing("root"))?;
+
+        let data = self
+            .download(endpoint, ContentType::Data, &config_key(encoding_key)?)
+            .await?;
+        let encoding = EncodingFile::parse_blte(&data)
+            .map_err(|e| ProtocolError::Parse(format!("Invalid encoding file: {e}")))?;
+
+        let root_ckey = ContentKey::from_bytes(config_key(root)?);
+        let root_ekey = encoding.find_encoding(&root_ckey).ok_or_else(|| {
+            ProtocolError::NotFound(format!("Root file {root} is not in the encoding file"))
+        })?;
+        let data = self
+            .download(endpoint, ContentType::Data, root_ekey.as_bytes())
+            .await?;
+        let blte = BlteFile::parse(&data)
+            .map_err(|e| ProtocolError::Parse(format!("Invalid root BLTE: {e}")))?;
+        let decoded = match key_store {
+            Some(keys) => blte.decompress_with_keys(keys),
+            None => blte.decompress(),
+        }
+        .map_err(|e| ProtocolError::Parse(format!("Cannot decode root file: {e}")))?;
+        RootFile::parse(&decoded)
+            .map_err(|e| ProtocolError::Parse(format!("Invalid root file: {e}")))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::cache::ProtocolCache;
+    use crate::config::{CacheConfig, CdnConfig};
+    use crate::retry::RetryPolicy;
+    use cascette_crypto::{EncodingKey, FileDataId, TactKey};
+    use cascette_formats::blte::{BlteBuilder, CompressionMode, EncryptionSpec};
+    use cascette_formats::encoding::{CKeyEntryData, EKeyEntryData, EncodingBuilder};
+    use cascette_formats::root::{ContentFlags, LocaleFlags, RootBuilder, RootVersion};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const KEY_NAME: u64 = 0xFA50_5078_126A_CB3E;
+    const KEY: [u8; 16] = [0x42; 16];
+
+    fn hash_path(kind: &str, hash: &str) -> String {
+        format!("/tpr/wow/{kind}/{}/{}/{hash}", &hash[..2], &hash[2..4])
+    }
+
+    async fn mount(server: &MockServer, request_path: String, body: Vec<u8>) {
+        Mock::given(method("GET"))
+            .and(path(request_path))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
+            .mount(server)
+            .await;
+    }
+
+    // Checksums are not verified, so documents can be served under any key
+    fn client(temp_dir: &TempDir) -> CdnClient {
+        let cache = ProtocolCache::new(&CacheConfig {
+            cache_dir: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .expect("Operation should succeed");
+        let config = CdnConfig::default()
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 1,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(1),
+                multiplier: 1.0,
+                jitter: false,
+                ..RetryPolicy::default()
+            })
+            .verify_checksums(false);
+        CdnClient::new(Arc::new(cache), config).expect("Operation should succeed")
+    }
+
+    fn endpoint(server: &MockServer) -> CdnEndpoint {
+        CdnEndpoint {
+            host: server.uri().replace("http://", ""),
+            path: "tpr/wow".to_string(),
+            product_path: None,
+            scheme: Some("http".to_string()),
+            is_fallback: false,
+            strict: false,
+            max_hosts: None,
+        }
+    }
+
+    fn entry(region: &str, build_id: u32, build_config: &str, cdn_config: &str) -> BgdlEntry {
+        BgdlEntry {
+            region: region.to_string(),
+            build_config: build_config.to_string(),
+            cdn_config: cdn_config.to_string(),
+            key_ring: None,
+            build_id,
+            versions_name: format!("11.1.0.{build_id}"),
+            product_config: None,
+        }
+    }
+
+    fn build_config_body(build_id: u32, root: &str, encoding_key: &str) -> Vec<u8> {
+        format!(
+            "# Build Configuration\n\
+             root = {root}\n\
+             encoding = 28ac27d6d7b5fb4ea5e54b3bd1ab8a8c {encoding_key}\n\
+             build-name = WOW-{build_id}patch11.1.0_Retail\n\
+             build-uid = wow\n"
+        )
+        .into_bytes()
+    }
+
+    /// Root file listing `(fdid, content key byte)` pairs
+    fn root(files: &[(u32, u8)]) -> Vec<u8> {
+        let mut builder = RootBuilder::new(RootVersion::V2);
+        for &(fdid, key) in files {
+            builder.add_file(
+                FileDataId::new(fdid),
+                ContentKey::from_bytes([key; 16]),
+                None,
+                LocaleFlags::new(LocaleFlags::ALL),
+                ContentFlags::new(ContentFlags::INSTALL),
+            );
+        }
+        builder.build().expect("Root should build")
+    }
+
+    fn blte(data: &[u8], encrypted: bool) -> Vec<u8> {
+        let mut builder = BlteBuilder::new().with_compression(CompressionMode::None);
+        if encrypted {
+            builder = builder
+                .with_chunk_table()
+                .with_encryption(EncryptionSpec::salsa20(KEY_NAME, [1, 2, 3, 4]), KEY);
+        }
+        builder
+            .add_data(data)
+            .expect("Data should be added")
+            .build()
+            .expect("BLTE should build")
+            .build()
+            .expect("BLTE should serialize")
+    }
+
+    /// BLTE encoding file mapping `ckey` to `ekey`
+    fn encoding(ckey: [u8; 16], ekey: [u8; 16]) -> Vec<u8> {
+        let mut builder = EncodingBuilder::new();
+        builder.add_ckey_entry(CKeyEntryData {
+            content_key: ContentKey::from_bytes(ckey),
+            file_size: 100,
+            encoding_keys: vec![EncodingKey::from_bytes(ekey)],
+        });
+        builder.add_ekey_entry(EKeyEntryData {
+            encoding_key: EncodingKey::from_bytes(ekey),
+            espec: "n".to_string(),
+            file_size: 100,
+        });
+        let data = builder
+            .build()
+            .expect("Encoding should build")
+            .build()
+            .expect("Encoding should serialize");
+        blte(&data, false)
+    }
+
+    /// Serve a build whose root file lists `files`; returns its build config hash
+    async fn mount_build(
+        server: &MockServer,
+        id: u8,
+        files: &[(u32, u8)],
+        root_available: bool,
+        encrypted: bool,
+    ) -> String {
+        let root_ckey = [id; 16];
+        let root_ekey = [id.wrapping_add(0x10); 16];
+        let encoding_key = [id.wrapping_add(0x20); 16];
+        let build_config = hex::encode([id.wrapping_add(0x30); 16]);
+
+        mount(
+            server,
+            hash_path("config", &build_config),
+            build_config_body(
+                61000 + u32::from(id),
+                &hex::encode(root_ckey),
+                &hex::encode(encoding_key),
+            ),
+        )
+        .await;
+        mount(
+            server,
+            hash_path("data", &hex::encode(encoding_key)),
+            encoding(root_ckey, root_ekey),
+        )
+        .await;
+        if root_available {
+            mount(
+                server,
+                hash_path("data", &hex::encode(root_ekey)),
+                blte(&root(files), encrypted),
+            )
+            .await;
+        }
+        build_config
+    }
+
+    const CDN_CONFIG: &str = "9baea98f8aca42e616f083816a9fd66d";
+
+    #[tokio::test]
+    async fn test_diff_reports_configs_and_file_data_ids() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let client = client(&temp_dir);
+        let endpoint = endpoint(&server);
+
+        let from_config = mount_build(&server, 1, &[(1, 1), (2, 2), (3, 3)], true, false).await;
+        let to_config = mount_build(&server, 2, &[(1, 1), (2, 9), (4, 4)], true, false).await;
+        let from = entry("us", 61001, &from_config, CDN_CONFIG);
+        let to = entry("eu", 61002, &to_config, CDN_CONFIG);
+
+        let diff = client
+            .diff_builds((&endpoint, &from), (&endpoint, &to), None)
+            .await;
+
+        assert!(diff.build_config_changed);
+        assert!(!diff.cdn_config_changed);
+        assert_eq!(diff.from.region, "us");
+        assert_eq!(diff.to.region, "eu");
+        let fields: Vec<_> = diff
+            .build_config_fields
+            .iter()
+            .map(|change| change.field)
+            .collect();
+        assert_eq!(fields, ["build_name", "root", "encoding_key"]);
+        assert_eq!(
+            diff.files,
+            FileDiff::Compared(FileDataIdDiff {
+                added: vec![4],
+                removed: vec![3],
+                changed: vec![2],
+            })
+        );
+
+        let text = diff.to_string();
+        assert!(
+            text.starts_with("us 11.1.0.61001 (build 61001) -> eu 11.1.0.61002 (build 61002)\n")
+        );
+        assert!(text.contains(
+            "    build_name:   WOW-61001patch11.1.0_Retail -> WOW-61002patch11.1.0_Retail\n"
+        ));
+        assert!(text.contains("    root:         0101... -> 0202...\n"));
+        assert!(text.contains("  cdn config:   unchanged\n"));
+        assert!(text.contains("  files:        1 added, 1 removed, 1 changed\n"));
+
+        let json = serde_json::to_value(&diff).expect("Diff should serialize");
+        assert_eq!(json["files"]["status"], "compared");
+        assert_eq!(json["files"]["added"][0], 4);
+        assert_eq!(json["build_config_fields"][1]["field"], "root");
+    }
+
+    #[tokio::test]
+    async fn test_diff_degrades_without_root_file() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let client = client(&temp_dir);
+        let endpoint = endpoint(&server);
+
+        let from_config = mount_build(&server, 1, &[(1, 1)], true, false).await;
+        let to_config = mount_build(&server, 2, &[(1, 2)], false, false).await;
+        let from = entry("us", 61001, &from_config, CDN_CONFIG);
+        let to = entry("us", 61002, &to_config, "0123456789abcdef0123456789abcdef");
+
+        let diff = client
+            .diff_builds((&endpoint, &from), (&endpoint, &to), None)
+            .await;
+
+        assert!(diff.build_config_changed);
+        assert!(diff.cdn_config_changed);
+        assert!(!diff.build_config_fields.is_empty());
+        assert!(matches!(diff.files, FileDiff::Unavailable { .. }));
+        assert!(diff.to_string().contains("  files:        not compared ("));
+        let json = serde_json::to_value(&diff).expect("Diff should serialize");
+        assert_eq!(json["files"]["status"], "unavailable");
+    }
+
+    #[tokio::test]
+    async fn test_diff_encrypted_root_needs_key() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let client = client(&temp_dir);
+        let endpoint = endpoint(&server);
+
+        let from_config = mount_build(&server, 1, &[(1, 1)], true, false).await;
+        let to_config = mount_build(&server, 2, &[(1, 1), (2, 2)], true, true).await;
+        let from = entry("us", 61001, &from_config, CDN_CONFIG);
+        let to = entry("us", 61002, &to_config, CDN_CONFIG);
+
+        let diff = client
+            .diff_builds((&endpoint, &from), (&endpoint, &to), None)
+            .await;
+        assert!(
+            matches!(&diff.files, FileDiff::Unavailable { reason } if reason.contains("decode")),
+            "Encrypted root should not be compared without a key: {:?}",
+            diff.files
+        );
+        assert_eq!(diff.build_config_fields.len(), 3);
+
+        let mut keys = TactKeyStore::empty();
+        keys.add(TactKey::new(KEY_NAME, KEY));
+        let diff = client
+            .diff_builds((&endpoint, &from), (&endpoint, &to), Some(&keys))
+            .await;
+        assert_eq!(
+            diff.files,
+            FileDiff::Compared(FileDataIdDiff {
+                added: vec![2],
+                ..FileDataIdDiff::default()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_diff_same_root_skips_downloads() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let client = client(&temp_dir);
+        let endpoint = endpoint(&server);
+
+        // Only the build config is served: data downloads would fail
+        let build_config = hex::encode([0x31; 16]);
+        mount(
+            &server,
+            hash_path("config", &build_config),
+            build_config_body(61001, &hex::encode([1; 16]), &hex::encode([0x21; 16])),
+        )
+        .await;
+        let from = entry("us", 61001, &build_config, CDN_CONFIG);
+        let to = entry("kr", 61001, &build_config, CDN_CONFIG);
+
+        let diff = client
+            .diff_builds((&endpoint, &from), (&endpoint, &to), None)
+            .await;
+        assert!(!diff.build_config_changed);
+        assert!(diff.build_config_fields.is_empty());
+        assert_eq!(diff.files, FileDiff::Compared(FileDataIdDiff::default()));
+        assert!(diff.to_string().contains("  build config: unchanged\n"));
+        assert!(diff.to_string().contains("  files:        unchanged\n"));
+    }
+
+    #[tokio::test]
+    async fn test_diff_without_build_config() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let client = client(&temp_dir);
+        let endpoint = endpoint(&server);
+
+        let from = entry("us", 61001, "f731b0ceedb2b575dbf3b8ff5b3af1f0", CDN_CONFIG);
+        let to = entry("us", 61002, "1c2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f", CDN_CONFIG);
+        let diff = client
+            .diff_builds((&endpoint, &from), (&endpoint, &to), None)
+            .await;
+
+        assert!(diff.build_config_changed);
+        assert!(diff.build_config_fields.is_empty());
+        assert!(
+            matches!(&diff.files, FileDiff::Unavailable { reason } if reason.starts_with("build config unavailable"))
+        );
+        assert!(
+            diff.to_string()
+                .contains("  build config: f731... -> 1c2d...\n")
+        );
+    }
+
+    #[test]
+    fn test_file_data_id_diff_ignores_entry_order() {
+        let from = RootFile::parse(&root(&[(1, 1), (1, 2), (2, 3)])).expect("Root should parse");
+        let to = RootFile::parse(&root(&[(2, 3), (1, 2), (1, 1)])).expect("Root should parse");
+        assert!(FileDataIdDiff::between(&from, &to).is_empty());
+
+        let to = RootFile::parse(&root(&[(1, 1), (2, 3)])).expect("Root should parse");
+        assert_eq!(FileDataIdDiff::between(&from, &to).changed, vec![1]);
+    }
+}
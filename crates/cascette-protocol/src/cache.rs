@@ -29,6 +29,7 @@ mod native {
         memory_cache::MemoryCache,
         traits::AsyncCache,
     };
+    use std::sync::atomic::{AtomicU64, Ordering};
     use std::sync::{Arc, OnceLock};
     use tokio::runtime::{Handle, Runtime};
 
@@ -49,12 +50,22 @@ mod native {
     }
 
     /// Simple string-based cache key compatible with cascette-cache
-    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[derive(Debug, Clone)]
     pub struct ProtocolCacheKey {
         key: String,
         cached_key: OnceLock<String>,
     }
 
+    // Equality must ignore `cached_key`: a stored key has it initialized by
+    // the time a fresh lookup key is compared against it
+    impl PartialEq for ProtocolCacheKey {
+        fn eq(&self, other: &Self) -> bool {
+            self.key == other.key
+        }
+    }
+
+    impl Eq for ProtocolCacheKey {}
+
     impl std::hash::Hash for ProtocolCacheKey {
         fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
             self.key.hash(state);
@@ -79,25 +90,34 @@ mod native {
     /// High-performance protocol cache backed by cascette-cache
     pub struct ProtocolCache {
         cache: Arc<dyn AsyncCache<ProtocolCacheKey> + Send + Sync>,
+        /// Disk backend, when configured, for size and age enforcement
+        disk: Option<Arc<DiskCache<ProtocolCacheKey>>>,
         config: CacheConfig,
+        /// Expired responses served while they were revalidated
+        stale_served: AtomicU64,
     }
 
     impl ProtocolCache {
         /// Create a new high-performance protocol cache
         pub fn new(config: &CacheConfig) -> Result<Self> {
+            let mut disk = None;
             let cache: Arc<dyn AsyncCache<ProtocolCacheKey> + Send + Sync> =
                 if let Some(ref cache_dir) = config.cache_dir {
-                    let disk_config = DiskCacheConfig::new(cache_dir)
-                        .with_max_disk_usage(config.disk_max_size_bytes)
-                        .with_max_files(100_000)
-                        .with_default_ttl(config.cdn_ttl)
-                        .with_subdirectories(false, 0);
-
-                    let disk_cache = DiskCache::new(disk_config).map_err(|e| {
+                    let disk_config = config.max_age_by_kind.iter().fold(
+                        DiskCacheConfig::new(cache_dir)
+                            .with_max_disk_usage(config.disk_max_size_bytes)
+                            .with_max_files(100_000)
+                            .with_default_ttl(config.cdn_ttl)
+                            .with_subdirectories(false, 0),
+                        |disk_config, (&kind, &max_age)| disk_config.with_max_age(kind, max_age),
+                    );
+
+                    let disk_cache = Arc::new(DiskCache::new(disk_config).map_err(|e| {
                         crate::error::ProtocolError::Cache(CacheError::Backend(e.to_string()))
-                    })?;
+                    })?);
 
-                    Arc::new(disk_cache)
+                    disk = Some(Arc::clone(&disk_cache));
+                    disk_cache
                 } else {
                     let memory_config = MemoryCacheConfig::new()
                         .with_max_entries(config.memory_max_items)
@@ -113,7 +133,9 @@ mod native {
 
             Ok(Self {
                 cache,
+                disk,
                 config: config.clone(),
+                stale_served: AtomicU64::new(0),
             })
         }
 
@@ -151,6 +173,13 @@ mod native {
         fn get_ttl_for_key(&self, key: &ProtocolCacheKey) -> Duration {
             if key.key.starts_with("ribbit:") {
                 self.config.ribbit_ttl
+            } else if key.key.starts_with("cdn/") {
+                // CDN keys mirror the URL layout: cdn/{path}/{config|data|patch}/..
+                if key.key.contains("/config/") {
+                    self.config.config_ttl
+                } else {
+                    self.config.cdn_ttl
+                }
             } else if key.key.starts_with("cdn:") {
                 self.config.cdn_ttl
             } else {
@@ -170,6 +199,15 @@ mod native {
         }
 
         pub fn store_with_ttl(&self, key: &str, data: &[u8], ttl: Duration) -> Result<()> {
+            // An entry larger than the whole disk cap can never be stored;
+            // skip caching it rather than failing the caller's download
+            if self.disk.is_some() && data.len() > self.config.disk_max_size_bytes {
+                tracing::debug!(
+                    "Not caching {key}: {} bytes exceeds disk cache limit",
+                    data.len()
+                );
+                return Ok(());
+            }
             let cache_key = Self::parse_legacy_key(key);
             let bytes = Bytes::copy_from_slice(data);
             let cache = self.cache.clone();
@@ -182,8 +220,22 @@ mod native {
             self.store_with_ttl(key, data, ttl)
         }
 
+        /// Remove expired entries and evict least-recently-accessed files
+        /// until the disk cache is within `disk_max_size_bytes`
+        ///
+        /// Writes enforce the limits automatically once the cap is exceeded;
+        /// this is for explicit cleanup (e.g. at startup or from a CLI).
+        /// Returns the number of files removed. Memory-only caches return 0.
+        pub fn enforce_limits(&self) -> Result<usize> {
+            let Some(disk) = &self.disk else {
+                return Ok(0);
+            };
+            disk.enforce_limits()
+                .map_err(|e| crate::error::ProtocolError::Cache(CacheError::Backend(e.to_string())))
+        }
+
         pub fn cleanup_expired(&self) -> Result<usize> {
-            Ok(0)
+            self.enforce_limits()
         }
 
         pub fn stats(&self) -> Result<CacheStats> {
@@ -195,9 +247,15 @@ mod native {
                 entries: stats.entry_count as u64,
                 memory_usage: stats.memory_usage_bytes as u64,
                 disk_usage: 0,
+                stale_served: self.stale_served.load(Ordering::Relaxed),
             })
         }
 
+        /// Count a response served past its TTL while it is revalidated
+        pub fn record_stale_served(&self) {
+            self.stale_served.fetch_add(1, Ordering::Relaxed);
+        }
+
         #[allow(clippy::unused_async)]
         pub async fn warm_cache(&self, keys: Vec<String>) -> Result<usize> {
             let mut warmed = 0;
@@ -408,6 +466,11 @@ mod wasm {
             self.store_with_ttl(key, data, ttl)
         }
 
+        /// Enforce cache limits (localStorage has no size cap of its own; removes expired entries)
+        pub fn enforce_limits(&self) -> Result<usize> {
+            self.cleanup_expired()
+        }
+
         /// Clean up expired entries
         pub fn cleanup_expired(&self) -> Result<usize> {
             let storage = Self::get_storage()
@@ -478,6 +541,8 @@ mod wasm {
                 entries,
                 memory_usage,
                 disk_usage: 0,
+                // WASM clients do not revalidate in the background
+                stale_served: 0,
             })
         }
 
@@ -553,6 +618,9 @@ pub struct CacheStats {
     pub entries: u64,
     pub memory_usage: u64,
     pub disk_usage: u64,
+    /// Responses served past their TTL within
+    /// [`CacheConfig::revalidate_grace`] while being refreshed
+    pub stale_served: u64,
 }
 
 impl CacheStats {
@@ -587,3 +655,65 @@ pub enum CacheError {
     #[error("Cache error: {0}")]
     Other(String),
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::native::{ProtocolCache, ProtocolCacheKey};
+    use crate::config::CacheConfig;
+    use cascette_cache::key::{CacheKey, CacheKeyKind};
+    use std::hash::{BuildHasher, RandomState};
+    use std::time::Duration;
+
+    #[test]
+    fn test_cache_key_equality_ignores_cached_string() {
+        let stored = ProtocolCacheKey::new("ribbit:us:versions:wow".to_string());
+        // Storing a key in the cache initializes its key string
+        assert_eq!(stored.as_cache_key(), "ribbit:us:versions:wow");
+
+        let lookup = ProtocolCacheKey::new("ribbit:us:versions:wow".to_string());
+        assert_eq!(stored, lookup);
+        let state = RandomState::new();
+        assert_eq!(state.hash_one(&stored), state.hash_one(&lookup));
+
+        assert_ne!(
+            lookup,
+            ProtocolCacheKey::new("ribbit:eu:versions:wow".to_string())
+        );
+    }
+
+    #[test]
+    fn test_max_age_applies_to_cdn_content_types() {
+        let temp_dir = tempfile::tempdir().expect("Operation should succeed");
+        let config = CacheConfig {
+            cache_dir: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        }
+        .with_max_age(CacheKeyKind::Config, Duration::from_millis(10));
+        let cache = ProtocolCache::new(&config).expect("Operation should succeed");
+
+        let config_key = "cdn/tpr/wow/config/ab/cd/abcdef";
+        let data_key = "cdn/tpr/wow/data/12/34/123456";
+        cache
+            .store_bytes(config_key, b"build config")
+            .expect("Operation should succeed");
+        cache
+            .store_bytes(data_key, b"BLTE")
+            .expect("Operation should succeed");
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert_eq!(cache.enforce_limits().expect("Operation should succeed"), 1);
+        assert!(
+            cache
+                .get(config_key)
+                .expect("Operation should succeed")
+                .is_none()
+        );
+        assert!(
+            cache
+                .get(data_key)
+                .expect("Operation should succeed")
+                .is_some()
+        );
+    }
+}
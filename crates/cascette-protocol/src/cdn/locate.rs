@@ -0,0 +1,272 @@
+//! Finding files in CDN archives
+//!
+//! Most CDN files are stored inside archives rather than under their own
+//! `data/<ekey>` path. [`ArchiveLocator`] searches the archive indices of a
+//! CDN config for an encoding key and fetches the file with one range
+//! request. Indices are downloaded on first use and kept for later lookups.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use cascette_formats::archive::ArchiveIndex;
+use futures::StreamExt;
+
+use super::{CdnClient, CdnEndpoint, ContentType};
+use crate::error::{ProtocolError, Result};
+
+/// Where a file is stored in a CDN archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveSpan {
+    /// Archive key, as 16 bytes
+    pub archive: Vec<u8>,
+    /// Offset of the file in the archive
+    pub offset: u64,
+    /// Size of the BLTE-encoded file
+    pub size: u64,
+}
+
+/// Finds files in the archives of a CDN config
+pub struct ArchiveLocator {
+    archives: Vec<String>,
+    indices: Mutex<HashMap<String, Arc<ArchiveIndex>>>,
+}
+
+impl std::fmt::Debug for ArchiveLocator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ArchiveLocator")
+            .field("archives", &self.archives.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ArchiveLocator {
+    /// Create a locator searching `archives`, the hex archive keys of a
+    /// CDN config, in order
+    pub fn new(archives: Vec<String>) -> Self {
+        Self {
+            archives,
+            indices: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Hex keys of the searched archives
+    pub fn archives(&self) -> &[String] {
+        &self.archives
+    }
+
+    /// Find `ekey` in the archive indices
+    ///
+    /// Indices already loaded are searched first; the others are then
+    /// downloaded, at most [`CdnConfig::max_concurrent`](crate::CdnConfig)
+    /// at once, stopping at the first one containing `ekey`. Returns `None`
+    /// if no archive holds the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::Parse` if an index is invalid and download
+    /// errors as they occur.
+    pub async fn locate(
+        &self,
+        cdn: &CdnClient,
+        endpoint: &CdnEndpoint,
+        ekey: &[u8],
+    ) -> Result<Option<ArchiveSpan>> {
+        let found = |archive: &str, index: &ArchiveIndex| -> Result<Option<ArchiveSpan>> {
+            match index.find_entry(ekey) {
+                Some(entry) => Ok(Some(ArchiveSpan {
+                    archive: hex::decode(archive).map_err(|_| ProtocolError::InvalidKey)?,
+                    offset: entry.offset,
+                    size: u64::from(entry.size),
+                })),
+                None => Ok(None),
+            }
+        };
+
+        let mut missing = Vec::new();
+        {
+            let indices = self.indices.lock().unwrap_or_else(PoisonError::into_inner);
+            for archive in &self.archives {
+                match indices.get(archive) {
+                    Some(index) => {
+                        if let Some(location) = found(archive, index)? {
+                            return Ok(Some(location));
+                        }
+                    }
+                    None => missing.push(archive.as_str()),
+                }
+            }
+        }
+
+        let mut loads = futures::stream::iter(missing)
+            .map(|archive| async move {
+                let data = cdn.download_archive_index(endpoint, archive).await?;
+                let index = ArchiveIndex::parse(&mut Cursor::new(&data)).map_err(|e| {
+                    ProtocolError::Parse(format!("Invalid index for {archive}: {e}"))
+                })?;
+                Ok::<_, ProtocolError>((archive, Arc::new(index)))
+            })
+            .buffered(cdn.config().max_concurrent.max(1));
+
+        while let Some(loaded) = loads.next().await {
+            let (archive, index) = loaded?;
+            self.indices
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(archive.to_string(), Arc::clone(&index));
+            if let Some(location) = found(archive, &index)? {
+                return Ok(Some(location));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Download the BLTE-encoded file `ekey`
+    ///
+    /// A file found in an archive is fetched with a range request; any
+    /// other file is downloaded from its loose `data/<ekey>` path. The
+    /// ranged bytes are not checked against `ekey`, so callers should
+    /// verify them.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::NotFound` if a loose download finds nothing,
+    /// and index and download errors as they occur.
+    pub async fn download(
+        &self,
+        cdn: &CdnClient,
+        endpoint: &CdnEndpoint,
+        ekey: &[u8],
+    ) -> Result<Vec<u8>> {
+        let Some(span) = self.locate(cdn, endpoint, ekey).await? else {
+            return cdn.download(endpoint, ContentType::Data, ekey).await;
+        };
+
+        let mut data = cdn
+            .download_range(
+                endpoint,
+                ContentType::Data,
+                &span.archive,
+                span.offset,
+                span.size,
+            )
+            .await?;
+        // A server ignoring the range sends the whole archive
+        if data.len() as u64 != span.size {
+            let range = usize::try_from(span.offset)
+                .ok()
+                .zip(usize::try_from(span.offset + span.size).ok())
+                .filter(|&(_, end)| end <= data.len())
+                .ok_or_else(|| {
+                    ProtocolError::Parse(format!(
+                        "archive {} is shorter than the range of {}",
+                        hex::encode(&span.archive),
+                        hex::encode(ekey)
+                    ))
+                })?;
+            data = data[range.0..range.1].to_vec();
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::cache::ProtocolCache;
+    use crate::config::{CacheConfig, CdnConfig};
+    use cascette_formats::archive::ArchiveIndexBuilder;
+    use tempfile::TempDir;
+    use wiremock::matchers::{header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn data_path(key: &str) -> String {
+        format!("/tpr/wow/data/{}/{}/{key}", &key[..2], &key[2..4])
+    }
+
+    fn endpoint(server: &MockServer) -> CdnEndpoint {
+        CdnEndpoint {
+            host: server.uri().replace("http://", ""),
+            path: "tpr/wow".to_string(),
+            product_path: None,
+            scheme: Some("http".to_string()),
+            is_fallback: false,
+            strict: false,
+            max_hosts: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_from_archive_and_loose() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let cache = ProtocolCache::new(&CacheConfig {
+            cache_dir: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .expect("Operation should succeed");
+        let cdn = CdnClient::new(
+            Arc::new(cache),
+            CdnConfig::default().verify_checksums(false),
+        )
+        .expect("Operation should succeed");
+
+        let archived = b"file stored in an archive".to_vec();
+        let archived_key = md5::compute(&archived).0;
+        let mut archive = vec![0xAA; 64];
+        let mut builder = ArchiveIndexBuilder::new();
+        builder.add_entry(
+            archived_key.to_vec(),
+            archived.len() as u32,
+            archive.len() as u64,
+        );
+        archive.extend_from_slice(&archived);
+        archive.extend_from_slice(&[0xBB; 32]);
+        let mut index = Cursor::new(Vec::new());
+        let built = builder.build(&mut index).expect("Operation should succeed");
+        let archive_key = hex::encode(built.footer.name_hash());
+
+        Mock::given(method("GET"))
+            .and(path(format!("{}.index", data_path(&archive_key))))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(index.into_inner()))
+            .mount(&server)
+            .await;
+        // Ignores the range and sends the whole archive
+        Mock::given(method("GET"))
+            .and(path(data_path(&archive_key)))
+            .and(header_exists("range"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(archive))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let loose = b"loose file".to_vec();
+        let loose_key = md5::compute(&loose).0;
+        Mock::given(method("GET"))
+            .and(path(data_path(&hex::encode(loose_key))))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(loose.clone()))
+            .mount(&server)
+            .await;
+
+        let locator = ArchiveLocator::new(vec![archive_key.clone()]);
+        let endpoint = endpoint(&server);
+        let span = locator
+            .locate(&cdn, &endpoint, &archived_key)
+            .await
+            .expect("Operation should succeed")
+            .expect("File should be archived");
+        assert_eq!(hex::encode(&span.archive), archive_key);
+        assert_eq!((span.offset, span.size), (64, archived.len() as u64));
+
+        let data = locator
+            .download(&cdn, &endpoint, &archived_key)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(data, archived);
+        let data = locator
+            .download(&cdn, &endpoint, &loose_key)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(data, loose);
+    }
+}
@@ -0,0 +1,666 @@
+//! Mirror complete CDN archives to disk
+//!
+//! [`CdnClient::mirror_archives`] downloads the `.index` and data file of
+//! each archive into the CDN directory layout, so the output can be served
+//! as a CDN or read like one:
+//!
+//! ```text
+//! <output>/config/48/c0/48c0f2cc2681a23758a3b4fc6f6e1f3a
+//! <output>/data/00/17/0017a402f556fbece46c38dc431a2c9b
+//! <output>/data/00/17/0017a402f556fbece46c38dc431a2c9b.index
+//! <output>/archive-manifest.json
+//! ```
+//!
+//! Each archive is verified before it is kept: its index must parse with
+//! valid checksums, the MD5 of the index footer must equal the archive key
+//! (CDN archives are named by their index footer, not by their data), and
+//! every index entry must point to a BLTE header inside the data file.
+//!
+//! Data files are written to `<key>.partial` and resumed with a `Range`
+//! request after interruptions. Completed archives are recorded in the
+//! manifest as soon as they are verified, so an interrupted mirror skips
+//! them when it is run again.
+
+use std::collections::BTreeMap;
+use std::io::{Cursor, ErrorKind, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use cascette_formats::archive::ArchiveIndex;
+use cascette_formats::config::CdnConfig as CdnConfigFile;
+use futures::StreamExt;
+use futures::stream;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+use super::{CdnClient, CdnEndpoint, ContentType, error_for_status};
+use crate::error::{ProtocolError, Result};
+
+/// File name of the manifest in the output directory
+pub const MANIFEST_FILE: &str = "archive-manifest.json";
+
+/// Magic bytes at the start of every file stored in an archive
+const BLTE_MAGIC: &[u8; 4] = b"BLTE";
+
+/// Options for [`CdnClient::mirror_archives`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MirrorOptions {
+    /// Archives downloaded at once (at least one)
+    pub max_concurrent: usize,
+}
+
+impl Default for MirrorOptions {
+    fn default() -> Self {
+        Self { max_concurrent: 4 }
+    }
+}
+
+/// A verified archive recorded in the manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirroredArchive {
+    /// Size of the `.index` file in bytes
+    pub index_size: u64,
+    /// Size of the data file in bytes
+    pub data_size: u64,
+    /// Number of files in the archive
+    pub entries: usize,
+}
+
+/// Archives completed by earlier mirror runs, keyed by archive key
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// Completed archives
+    pub archives: BTreeMap<String, MirroredArchive>,
+}
+
+impl ArchiveManifest {
+    /// Load the manifest of `output`, or an empty one if there is none
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::Network` if the file cannot be read and
+    /// `ProtocolError::Parse` if it is not a valid manifest.
+    pub async fn load(output: &Path) -> Result<Self> {
+        match fs::read(output.join(MANIFEST_FILE)).await {
+            Ok(data) => serde_json::from_slice(&data)
+                .map_err(|e| ProtocolError::Parse(format!("Invalid archive manifest: {e}"))),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the manifest to `output`, replacing the previous one atomically
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::Network` if the file cannot be written.
+    pub async fn save(&self, output: &Path) -> Result<()> {
+        let data = serde_json::to_vec_pretty(self)
+            .map_err(|e| ProtocolError::Other(format!("Failed to encode manifest: {e}")))?;
+        let path = output.join(MANIFEST_FILE);
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, data).await?;
+        fs::rename(&temp, &path).await?;
+        Ok(())
+    }
+}
+
+/// Outcome of a mirror run
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MirrorReport {
+    /// Archives downloaded and verified by this run
+    pub mirrored: Vec<String>,
+    /// Archives skipped because the manifest already had them
+    pub skipped: Vec<String>,
+    /// Archives that failed, with the error of their last attempt
+    pub failed: Vec<(String, String)>,
+}
+
+impl MirrorReport {
+    /// Check whether every archive is now mirrored
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Local paths of one archive
+struct ArchivePaths {
+    index: PathBuf,
+    data: PathBuf,
+    partial: PathBuf,
+}
+
+impl ArchivePaths {
+    fn new(output: &Path, key: &str) -> Self {
+        let dir = output.join("data").join(&key[..2]).join(&key[2..4]);
+        Self {
+            index: dir.join(format!("{key}.index")),
+            data: dir.join(key),
+            partial: dir.join(format!("{key}.partial")),
+        }
+    }
+
+    /// Check whether both files exist with the sizes recorded for `archive`
+    async fn matches(&self, archive: &MirroredArchive) -> bool {
+        let size = |path: &Path| {
+            let path = path.to_path_buf();
+            async move { fs::metadata(path).await.map(|m| m.len()).ok() }
+        };
+        size(&self.index).await == Some(archive.index_size)
+            && size(&self.data).await == Some(archive.data_size)
+    }
+}
+
+/// Lowercase a 32 digit hex archive key
+fn archive_key(key: &str) -> Result<(String, [u8; 16])> {
+    let mut bytes = [0u8; 16];
+    hex::decode_to_slice(key, &mut bytes).map_err(|_| ProtocolError::InvalidKey)?;
+    Ok((hex::encode(bytes), bytes))
+}
+
+impl CdnClient {
+    /// Mirror the archives listed in a CDN config
+    ///
+    /// Downloads the CDN config `cdn_config_hash`, writes it to the output
+    /// directory and mirrors its `archives` with
+    /// [`mirror_archives`](Self::mirror_archives). The hash of a build is
+    /// the `CDNConfig` field of its versions row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the CDN config cannot be downloaded or parsed,
+    /// or if the output directory or manifest cannot be written.
+    pub async fn mirror_cdn_config_archives(
+        &self,
+        endpoint: &CdnEndpoint,
+        cdn_config_hash: &str,
+        output: &Path,
+        options: &MirrorOptions,
+    ) -> Result<MirrorReport> {
+        let (hash, key) = archive_key(cdn_config_hash)?;
+        let data = self.download(endpoint, ContentType::Config, &key).await?;
+        let config = CdnConfigFile::parse(data.as_slice())
+            .map_err(|e| ProtocolError::Parse(format!("Invalid CDN config: {e}")))?;
+
+        let dir = output.join("config").join(&hash[..2]).join(&hash[2..4]);
+        fs::create_dir_all(&dir).await?;
+        fs::write(dir.join(&hash), &data).await?;
+
+        let archives: Vec<String> = config
+            .archives()
+            .into_iter()
+            .map(|archive| archive.content_key)
+            .collect();
+        self.mirror_archives(endpoint, &archives, output, options)
+            .await
+    }
+
+    /// Mirror complete archives into `output`
+    ///
+    /// Archives recorded in the manifest whose files are still present are
+    /// skipped. The others are downloaded, at most
+    /// [`MirrorOptions::max_concurrent`] at once, and verified as described
+    /// in the [module documentation](crate::cdn::mirror). Transient failures are retried
+    /// with the client's retry policy, resuming partial data files. An
+    /// archive that still fails is reported in [`MirrorReport::failed`]
+    /// without stopping the others.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidKey` if an archive key is not 32 hex
+    /// characters, or an I/O error if the manifest cannot be read or
+    /// written.
+    pub async fn mirror_archives(
+        &self,
+        endpoint: &CdnEndpoint,
+        archives: &[String],
+        output: &Path,
+        options: &MirrorOptions,
+    ) -> Result<MirrorReport> {
+        let keys = archives
+            .iter()
+            .map(|key| archive_key(key))
+            .collect::<Result<Vec<_>>>()?;
+
+        fs::create_dir_all(output).await?;
+        let manifest = ArchiveManifest::load(output).await?;
+
+        let mut report = MirrorReport::default();
+        let mut pending = Vec::new();
+        for (key, bytes) in keys {
+            let paths = ArchivePaths::new(output, &key);
+            match manifest.archives.get(&key) {
+                Some(archive) if paths.matches(archive).await => report.skipped.push(key),
+                _ => pending.push((key, bytes, paths)),
+            }
+        }
+
+        let manifest = Mutex::new(manifest);
+        let manifest = &manifest;
+        let mut results = stream::iter(pending)
+            .map(|(key, bytes, paths)| async move {
+                let result = self.mirror_archive(endpoint, &key, &bytes, &paths).await;
+                let result = match result {
+                    Ok(archive) => {
+                        let mut manifest = manifest.lock().await;
+                        manifest.archives.insert(key.clone(), archive);
+                        manifest.save(output).await
+                    }
+                    Err(e) => Err(e),
+                };
+                (key, result)
+            })
+            .buffer_unordered(options.max_concurrent.max(1));
+
+        while let Some((key, result)) = results.next().await {
+            match result {
+                Ok(()) => {
+                    tracing::info!("Mirrored archive {}", key);
+                    report.mirrored.push(key);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to mirror archive {}: {}", key, e);
+                    report.failed.push((key, e.to_string()));
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Download and verify one archive
+    async fn mirror_archive(
+        &self,
+        endpoint: &CdnEndpoint,
+        key: &str,
+        bytes: &[u8; 16],
+        paths: &ArchivePaths,
+    ) -> Result<MirroredArchive> {
+        let index_data = self.download_archive_index(endpoint, key).await?;
+        let index = ArchiveIndex::parse(&mut Cursor::new(&index_data))
+            .map_err(|e| ProtocolError::Parse(format!("Invalid index for {key}: {e}")))?;
+        let name = hex::encode(index.footer.name_hash());
+        if name != key {
+            return Err(ProtocolError::Parse(format!(
+                "Index footer of {key} hashes to {name}"
+            )));
+        }
+
+        if let Some(dir) = paths.data.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        let url = Self::build_url(endpoint, ContentType::Data, bytes);
+        let data_size = self
+            .config
+            .retry_policy
+            .execute_with_budget_for_host(
+                super::url_host(&url),
+                &self.retry_budget,
+                &self.rate_limit_budget,
+                || self.download_to_file(&url, &paths.partial),
+            )
+            .await?;
+
+        if let Err(e) = verify_entries(&index, &paths.partial, data_size).await {
+            // A complete download with bad content cannot be resumed
+            let _ = fs::remove_file(&paths.partial).await;
+            return Err(e);
+        }
+
+        fs::write(&paths.index, &index_data).await?;
+        fs::rename(&paths.partial, &paths.data).await?;
+
+        Ok(MirroredArchive {
+            index_size: index_data.len() as u64,
+            data_size,
+            entries: index.entries.len(),
+        })
+    }
+
+    /// Download `url` into `path`, resuming after the bytes already there
+    ///
+    /// Returns the size of the complete file.
+    async fn download_to_file(&self, url: &str, path: &Path) -> Result<u64> {
+        let mut size = match fs::metadata(path).await {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == ErrorKind::NotFound => 0,
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut request = self.http_client.inner().get(url);
+        if size > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={size}-"));
+        }
+        let response = request.send().await?;
+
+        let mut file = match response.status() {
+            reqwest::StatusCode::PARTIAL_CONTENT => {
+                fs::OpenOptions::new().append(true).open(path).await?
+            }
+            // The partial file already holds everything
+            reqwest::StatusCode::RANGE_NOT_SATISFIABLE if size > 0 => return Ok(size),
+            status if status.is_success() => {
+                size = 0;
+                fs::File::create(path).await?
+            }
+            _ => return Err(error_for_status(&response)),
+        };
+
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            // A dropped connection is transient, so the retry resumes here
+            let chunk = chunk.map_err(|e| ProtocolError::Network(std::io::Error::other(e)))?;
+            file.write_all(&chunk).await?;
+            size += chunk.len() as u64;
+        }
+        file.sync_all().await?;
+
+        Ok(size)
+    }
+}
+
+/// Check that every entry of `index` starts a BLTE file inside the data
+async fn verify_entries(index: &ArchiveIndex, path: &Path, data_size: u64) -> Result<()> {
+    let mut file = fs::File::open(path).await?;
+    let mut magic = [0u8; 4];
+    for entry in &index.entries {
+        let key = hex::encode(&entry.encoding_key);
+        let end = entry.offset + u64::from(entry.size);
+        if end > data_size || entry.size < 4 {
+            return Err(ProtocolError::Parse(format!(
+                "Entry {key} at {}..{end} is outside the {data_size} byte archive",
+                entry.offset
+            )));
+        }
+        file.seek(SeekFrom::Start(entry.offset)).await?;
+        file.read_exact(&mut magic).await?;
+        if &magic != BLTE_MAGIC {
+            return Err(ProtocolError::Parse(format!(
+                "Entry {key} at {} is not BLTE data",
+                entry.offset
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::cache::ProtocolCache;
+    use crate::config::{CacheConfig, CdnConfig};
+    use crate::retry::RetryPolicy;
+    use cascette_formats::archive::ArchiveIndexBuilder;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// An archive of `count` small BLTE files, returning (key, index, data)
+    fn archive(seed: u8, count: u8) -> (String, Vec<u8>, Vec<u8>) {
+        let mut data = Vec::new();
+        let mut builder = ArchiveIndexBuilder::new();
+        for i in 0..count {
+            let file = [BLTE_MAGIC.as_slice(), &[seed, i, 0, 0, 0, 0, 0, 0]].concat();
+            let mut key = vec![seed; 16];
+            key[15] = i;
+            builder.add_entry(key, file.len() as u32, data.len() as u64);
+            data.extend_from_slice(&file);
+        }
+        let mut index = Cursor::new(Vec::new());
+        let built = builder.build(&mut index).expect("Operation should succeed");
+        (
+            hex::encode(built.footer.name_hash()),
+            index.into_inner(),
+            data,
+        )
+    }
+
+    fn data_path(key: &str) -> String {
+        format!("/tpr/wow/data/{}/{}/{key}", &key[..2], &key[2..4])
+    }
+
+    async fn serve_archive(server: &MockServer, key: &str, index: &[u8], data: &[u8]) {
+        Mock::given(method("GET"))
+            .and(path(format!("{}.index", data_path(key))))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(index))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(data_path(key)))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(data))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    fn client(temp_dir: &TempDir) -> CdnClient {
+        let cache = ProtocolCache::new(&CacheConfig {
+            cache_dir: Some(temp_dir.path().join("cache")),
+            ..Default::default()
+        })
+        .expect("Operation should succeed");
+        let config = CdnConfig::default().with_retry_policy(RetryPolicy {
+            max_attempts: 2,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            multiplier: 1.0,
+            jitter: false,
+            ..RetryPolicy::default()
+        });
+        CdnClient::new(Arc::new(cache), config).expect("Operation should succeed")
+    }
+
+    fn endpoint(server: &MockServer) -> CdnEndpoint {
+        CdnEndpoint {
+            host: server.uri().replace("http://", ""),
+            path: "tpr/wow".to_string(),
+            product_path: None,
+            scheme: Some("http".to_string()),
+            is_fallback: false,
+            strict: false,
+            max_hosts: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mirror_archives_and_skip_on_rerun() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let output = temp_dir.path().join("mirror");
+        let client = client(&temp_dir);
+
+        let mut keys = Vec::new();
+        for seed in 1..=3 {
+            let (key, index, data) = archive(seed, 10 * seed);
+            serve_archive(&server, &key, &index, &data).await;
+            keys.push((key, index, data));
+        }
+        let archives: Vec<String> = keys.iter().map(|(key, _, _)| key.clone()).collect();
+
+        let options = MirrorOptions { max_concurrent: 2 };
+        let report = client
+            .mirror_archives(&endpoint(&server), &archives, &output, &options)
+            .await
+            .expect("Operation should succeed");
+        assert!(report.is_complete(), "{:?}", report.failed);
+        assert_eq!(report.mirrored.len(), 3);
+
+        for (key, index, data) in &keys {
+            let paths = ArchivePaths::new(&output, key);
+            assert_eq!(&std::fs::read(&paths.index).expect("index"), index);
+            assert_eq!(&std::fs::read(&paths.data).expect("data"), data);
+            assert!(!paths.partial.exists());
+        }
+        let manifest = ArchiveManifest::load(&output)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(manifest.archives.len(), 3);
+        assert_eq!(manifest.archives[&keys[1].0].entries, 20);
+
+        // Data files are requested once; the second run only reads the manifest
+        let report = client
+            .mirror_archives(&endpoint(&server), &archives, &output, &options)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(report.skipped.len(), 3);
+        assert!(report.mirrored.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mirror_resumes_partial_data() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let output = temp_dir.path().join("mirror");
+        let client = client(&temp_dir);
+
+        let (key, index, data) = archive(7, 40);
+        let paths = ArchivePaths::new(&output, &key);
+        std::fs::create_dir_all(paths.partial.parent().expect("parent"))
+            .expect("Operation should succeed");
+        std::fs::write(&paths.partial, &data[..100]).expect("Operation should succeed");
+
+        Mock::given(method("GET"))
+            .and(path(format!("{}.index", data_path(&key))))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(index))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(data_path(&key)))
+            .and(header("Range", "bytes=100-"))
+            .respond_with(ResponseTemplate::new(206).set_body_bytes(&data[100..]))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let report = client
+            .mirror_archives(
+                &endpoint(&server),
+                std::slice::from_ref(&key),
+                &output,
+                &MirrorOptions::default(),
+            )
+            .await
+            .expect("Operation should succeed");
+        assert!(report.is_complete(), "{:?}", report.failed);
+        assert_eq!(std::fs::read(&paths.data).expect("data"), data);
+    }
+
+    #[tokio::test]
+    async fn test_mirror_rejects_bad_archives() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let output = temp_dir.path().join("mirror");
+        let client = client(&temp_dir);
+
+        // Served under a key its footer does not hash to
+        let (_, index, _) = archive(1, 5);
+        let wrong_key = "0123456789abcdef0123456789abcdef".to_string();
+        Mock::given(method("GET"))
+            .and(path(format!("{}.index", data_path(&wrong_key))))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(index))
+            .mount(&server)
+            .await;
+
+        // Data shorter than its index says
+        let (truncated_key, index, data_full) = archive(2, 5);
+        serve_archive(&server, &truncated_key, &index, &data_full[..30]).await;
+
+        // Data that is not BLTE at an entry's offset
+        let (garbled_key, index, mut garbled) = archive(3, 5);
+        garbled[12] = b'X';
+        serve_archive(&server, &garbled_key, &index, &garbled).await;
+
+        // Retried, then reported
+        let (unavailable_key, index, _) = archive(4, 5);
+        Mock::given(method("GET"))
+            .and(path(format!("{}.index", data_path(&unavailable_key))))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(index))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(data_path(&unavailable_key)))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let archives = vec![
+            wrong_key,
+            truncated_key.clone(),
+            garbled_key.clone(),
+            unavailable_key,
+        ];
+        let report = client
+            .mirror_archives(
+                &endpoint(&server),
+                &archives,
+                &output,
+                &MirrorOptions::default(),
+            )
+            .await
+            .expect("Operation should succeed");
+        assert!(report.mirrored.is_empty());
+        assert_eq!(report.failed.len(), 4);
+
+        // Nothing is recorded or left behind for rejected data
+        let manifest = ArchiveManifest::load(&output)
+            .await
+            .expect("Operation should succeed");
+        assert!(manifest.archives.is_empty());
+        for key in [&truncated_key, &garbled_key] {
+            let paths = ArchivePaths::new(&output, key);
+            assert!(!paths.data.exists() && !paths.partial.exists());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mirror_cdn_config_archives() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let output = temp_dir.path().join("mirror");
+        let client = client(&temp_dir);
+
+        let (first, index, data) = archive(1, 3);
+        serve_archive(&server, &first, &index, &data).await;
+        let (second, index, data) = archive(2, 3);
+        serve_archive(&server, &second, &index, &data).await;
+
+        let config = format!("# CDN Configuration\narchives = {first} {second}\n");
+        let config_hash = &hex::encode(md5::compute(&config).0);
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/tpr/wow/config/{}/{}/{config_hash}",
+                &config_hash[..2],
+                &config_hash[2..4]
+            )))
+            .respond_with(ResponseTemplate::new(200).set_body_string(config.clone()))
+            .mount(&server)
+            .await;
+
+        let report = client
+            .mirror_cdn_config_archives(
+                &endpoint(&server),
+                config_hash,
+                &output,
+                &MirrorOptions::default(),
+            )
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(report.mirrored.len(), 2);
+        assert_eq!(
+            std::fs::read_to_string(
+                output
+                    .join("config")
+                    .join(&config_hash[..2])
+                    .join(&config_hash[2..4])
+                    .join(config_hash)
+            )
+            .expect("config"),
+            config
+        );
+    }
+}
@@ -1,6 +1,15 @@
 //! CDN client for content delivery with dependency injection
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod locate;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod mirror;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod progressive;
 pub mod range;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod resume;
+mod verify;
 
 #[cfg(all(not(target_arch = "wasm32"), feature = "streaming"))]
 pub mod streaming;
@@ -9,17 +18,22 @@ pub mod streaming;
 #[cfg(all(not(target_arch = "wasm32"), feature = "streaming"))]
 pub use cascette_formats::archive::{ArchiveError, ArchiveIndex};
 
-#[cfg(not(target_arch = "wasm32"))]
 use futures::StreamExt;
+use futures::stream::FuturesUnordered;
 use std::fmt;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::Semaphore;
 
 use std::time::Duration;
 
 use crate::config::CdnConfig;
 use crate::error::{ProtocolError, Result};
-use crate::retry::RetryPolicy;
+use crate::retry::RetryBudget;
 use crate::transport::HttpClient;
+#[cfg(not(target_arch = "wasm32"))]
+use verify::ContentVerifier;
+use verify::verify_content;
 
 pub use range::{RangeDownloader, RangeError};
 
@@ -43,6 +57,30 @@ fn parse_retry_after(response: &reqwest::Response) -> Option<Duration> {
         .map(Duration::from_secs)
 }
 
+/// Host and port of `url`, the key of the retry policy's circuit breaker
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', '?', '#']).next().unwrap_or(rest)
+}
+
+/// Map an unsuccessful response to the error its status is retried as
+///
+/// 404 and other 4xx responses (except 408 and 429) become
+/// [`ProtocolError::ClientError`], which is never retried.
+fn error_for_status(response: &reqwest::Response) -> ProtocolError {
+    let status = response.status();
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = parse_retry_after(response);
+        ProtocolError::RateLimited { retry_after }
+    } else if status.is_server_error() {
+        ProtocolError::ServerError(status)
+    } else if status.is_client_error() && status != reqwest::StatusCode::REQUEST_TIMEOUT {
+        ProtocolError::ClientError(status)
+    } else {
+        ProtocolError::HttpStatus(status)
+    }
+}
+
 /// CDN endpoint configuration injected from external source
 #[derive(Debug, Clone)]
 pub struct CdnEndpoint {
@@ -121,6 +159,12 @@ pub struct CdnClient {
     http_client: HttpClient,
     cache: Arc<crate::cache::ProtocolCache>,
     config: CdnConfig,
+    /// Shared budget for retries of transient failures
+    retry_budget: RetryBudget,
+    /// Shared budget for retries of rate-limited (429) responses
+    rate_limit_budget: RetryBudget,
+    /// Rotates the first host tried by [`download_any`](Self::download_any)
+    next_host: AtomicUsize,
 }
 
 impl CdnClient {
@@ -129,7 +173,10 @@ impl CdnClient {
         Ok(Self {
             http_client: HttpClient::new()?,
             cache,
+            retry_budget: RetryBudget::per_minute(config.retry_budget_per_minute),
+            rate_limit_budget: RetryBudget::per_minute(config.rate_limit_budget_per_minute),
             config,
+            next_host: AtomicUsize::new(0),
         })
     }
 
@@ -157,30 +204,39 @@ impl CdnClient {
         )
     }
 
-    /// Download content using injected CDN endpoint
-    pub async fn download(
-        &self,
-        endpoint: &CdnEndpoint,
-        content_type: ContentType,
-        key: &[u8],
-    ) -> Result<Vec<u8>> {
+    /// Cache key of a file, mirroring its CDN path
+    fn cache_key(endpoint: &CdnEndpoint, content_type: ContentType, key: &[u8]) -> String {
         let hex_key = hex::encode(key);
 
         // Use full CDN path structure for cache key to match actual CDN organization
         // This allows direct correlation between cache files and CDN URLs
         // Always use path field for ALL game content (config, data, patch)
-        let cache_key = format!(
+        format!(
             "cdn/{}/{}/{}/{}/{}",
             normalize_cdn_path(&endpoint.path),
             content_type,
             &hex_key[..2],
             &hex_key[2..4],
             hex_key
-        );
+        )
+    }
+
+    /// Download content using injected CDN endpoint
+    ///
+    /// Unless disabled with [`CdnConfig::verify_checksums`], the content is
+    /// checked against `key` and a mismatching download is retried like a
+    /// server error.
+    pub async fn download(
+        &self,
+        endpoint: &CdnEndpoint,
+        content_type: ContentType,
+        key: &[u8],
+    ) -> Result<Vec<u8>> {
+        let cache_key = Self::cache_key(endpoint, content_type, key);
 
         // Check cache first
         if let Some(cached) = self.cache.get_bytes(&cache_key)? {
-            tracing::debug!("CDN cache hit for {}", hex_key);
+            tracing::debug!("CDN cache hit for {}", hex::encode(key));
             return Ok(cached);
         }
 
@@ -188,7 +244,7 @@ impl CdnClient {
         let url = Self::build_url(endpoint, content_type, key);
 
         // Download with retry logic
-        let data = self.download_with_retry(&url).await?;
+        let data = self.download_with_retry(&url, Some(key)).await?;
 
         // Store in cache
         self.cache.store_bytes(&cache_key, &data)?;
@@ -196,6 +252,127 @@ impl CdnClient {
         Ok(data)
     }
 
+    /// Download content from any of several mirrors of the same CDN
+    ///
+    /// Successive calls start at successive hosts, so load is spread
+    /// round-robin across `endpoints`. When a host fails after its retries,
+    /// the next one is tried; hosts marked as fallback (`?fallback=1`) are
+    /// only tried after all others. A failing host marked strict
+    /// (`?strict=1`) ends the download without failing over, and a
+    /// `?maxhosts=N` on any endpoint caps the number of hosts tried.
+    ///
+    /// The cache is keyed by the CDN path, not the host, so cached content
+    /// is served whichever mirror would have been chosen. `endpoints`
+    /// should all come from one CDN entry, as returned by
+    /// [`endpoints_from_bpsv_row`](Self::endpoints_from_bpsv_row).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProtocolError::InvalidEndpoint`] if `endpoints` is empty
+    /// and [`ProtocolError::AllHostsFailed`] if every host tried failed.
+    /// If only one host was tried, its error is returned instead.
+    pub async fn download_any(
+        &self,
+        endpoints: &[CdnEndpoint],
+        content_type: ContentType,
+        key: &[u8],
+    ) -> Result<Vec<u8>> {
+        let first = endpoints
+            .first()
+            .ok_or_else(|| ProtocolError::InvalidEndpoint("no CDN hosts".to_string()))?;
+
+        let cache_key = Self::cache_key(first, content_type, key);
+        if let Some(cached) = self.cache.get_bytes(&cache_key)? {
+            tracing::debug!("CDN cache hit for {}", hex::encode(key));
+            return Ok(cached);
+        }
+
+        let order = self.host_order(endpoints);
+        let attempts = order.len();
+        let mut last_error = None;
+        for endpoint in order {
+            match self.download(endpoint, content_type, key).await {
+                Ok(data) => return Ok(data),
+                Err(e) if endpoint.strict => return Err(e),
+                Err(e) => {
+                    tracing::warn!(
+                        "CDN host {} failed for {}: {e}",
+                        endpoint.host,
+                        hex::encode(key)
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if attempts == 1 => Err(e),
+            _ => Err(ProtocolError::AllHostsFailed),
+        }
+    }
+
+    /// Hosts to try for one [`download_any`](Self::download_any) call
+    ///
+    /// Primary hosts come first, rotated by one position per call, then
+    /// fallback hosts in their listed order.
+    fn host_order<'a>(&self, endpoints: &'a [CdnEndpoint]) -> Vec<&'a CdnEndpoint> {
+        let (fallback, primary): (Vec<_>, Vec<_>) =
+            endpoints.iter().partition(|endpoint| endpoint.is_fallback);
+
+        let mut order = Vec::with_capacity(endpoints.len());
+        if !primary.is_empty() {
+            let start = self.next_host.fetch_add(1, Ordering::Relaxed) % primary.len();
+            order.extend(primary[start..].iter().chain(&primary[..start]));
+        }
+        order.extend(fallback);
+
+        let max_hosts = endpoints
+            .iter()
+            .filter_map(|endpoint| endpoint.max_hosts)
+            .min()
+            .map_or(usize::MAX, |n| n as usize);
+        order.truncate(max_hosts);
+        order
+    }
+
+    /// Download several files concurrently from the same endpoint
+    ///
+    /// At most `concurrency` downloads run at once (at least one). Each
+    /// download is a [`download`](Self::download), so cached files are not
+    /// fetched again and failures are retried per file. The results are in
+    /// the order of `requests`; one failed download does not affect the
+    /// others.
+    pub async fn download_parallel(
+        &self,
+        endpoint: &CdnEndpoint,
+        requests: Vec<(ContentType, Vec<u8>)>,
+        concurrency: usize,
+    ) -> Vec<Result<Vec<u8>>> {
+        let semaphore = Semaphore::new(concurrency.max(1));
+        let semaphore = &semaphore;
+
+        let mut downloads: FuturesUnordered<_> = requests
+            .iter()
+            .enumerate()
+            .map(|(index, (content_type, key))| async move {
+                let result = match semaphore.acquire().await {
+                    Ok(_permit) => self.download(endpoint, *content_type, key).await,
+                    Err(e) => Err(ProtocolError::Other(format!(
+                        "Download limiter closed: {e}"
+                    ))),
+                };
+                (index, result)
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(requests.len());
+        while let Some(result) = downloads.next().await {
+            results.push(result);
+        }
+        results.sort_unstable_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
     /// Download with resume support using HTTP Range headers
     ///
     /// If `resume_from` is Some(offset), sends a Range header to resume from that byte offset.
@@ -214,6 +391,8 @@ impl CdnClient {
     /// * If `resume_from` is Some(offset), returns bytes from offset onwards
     /// * Falls back to full download if server doesn't support Range header
     ///
+    /// Only complete downloads are verified against `key`.
+    ///
     /// # Errors
     ///
     /// Returns error if download fails or server returns non-success status
@@ -228,7 +407,7 @@ impl CdnClient {
 
         // If no resume point, use regular download
         let Some(offset) = resume_from else {
-            return self.download_with_retry(&url).await;
+            return self.download_with_retry(&url, Some(key)).await;
         };
 
         // Try to download with Range header
@@ -296,10 +475,126 @@ impl CdnClient {
         }
     }
 
+    /// Download a file as concurrent range requests of `chunk_size` bytes
+    ///
+    /// A HEAD request gives the file size; the chunks are then requested
+    /// with at most [`CdnConfig::max_concurrent`] in flight, each retried
+    /// like [`download`](Self::download), and reassembled in order. Files
+    /// no larger than one chunk, files of unknown size and servers that
+    /// answer a range request with `200 OK` get a single full download.
+    ///
+    /// The reassembled content is cached and verified against `key` like
+    /// [`download`](Self::download).
+    pub async fn download_chunked(
+        &self,
+        endpoint: &CdnEndpoint,
+        content_type: ContentType,
+        key: &[u8],
+        chunk_size: usize,
+    ) -> Result<Vec<u8>> {
+        let cache_key = Self::cache_key(endpoint, content_type, key);
+        if let Some(cached) = self.cache.get_bytes(&cache_key)? {
+            tracing::debug!("CDN cache hit for {}", hex::encode(key));
+            return Ok(cached);
+        }
+
+        let url = Self::build_url(endpoint, content_type, key);
+        let chunk_size = chunk_size.max(1) as u64;
+        let size = self.get_file_size(endpoint, content_type, key).await?;
+
+        let chunked = match size {
+            Some(size) if size > chunk_size => self.download_chunks(&url, size, chunk_size).await?,
+            _ => None,
+        };
+        let data = if let Some(data) = chunked {
+            if self.config.verify_checksums {
+                verify_content(key, &data)?;
+            }
+            data
+        } else {
+            self.download_with_retry(&url, Some(key)).await?
+        };
+
+        self.cache.store_bytes(&cache_key, &data)?;
+        Ok(data)
+    }
+
+    /// Request `size` bytes of `url` in chunks and reassemble them
+    ///
+    /// Returns `None` as soon as the server answers a range request with
+    /// the full file.
+    async fn download_chunks(
+        &self,
+        url: &str,
+        size: u64,
+        chunk_size: u64,
+    ) -> Result<Option<Vec<u8>>> {
+        let mut chunks = futures::stream::iter((0..size).step_by(chunk_size as usize))
+            .map(|start| self.download_chunk(url, start, (start + chunk_size).min(size)))
+            .buffered(self.config.max_concurrent.max(1));
+
+        let mut data = Vec::with_capacity(size as usize);
+        while let Some(chunk) = chunks.next().await {
+            match chunk? {
+                Some(chunk) => data.extend_from_slice(&chunk),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(data))
+    }
+
+    /// Request bytes `start..end` of `url`, retrying like a full download
+    ///
+    /// Returns `None` if the server ignores the range and answers `200 OK`.
+    async fn download_chunk(&self, url: &str, start: u64, end: u64) -> Result<Option<Vec<u8>>> {
+        self.config
+            .retry_policy
+            .execute_with_budget_for_host(
+                url_host(url),
+                &self.retry_budget,
+                &self.rate_limit_budget,
+                || async {
+                    let response = self
+                        .http_client
+                        .inner()
+                        .get(url)
+                        .header(reqwest::header::RANGE, format!("bytes={start}-{}", end - 1))
+                        .send()
+                        .await?;
+
+                    match response.status() {
+                        reqwest::StatusCode::PARTIAL_CONTENT => {
+                            let chunk = response.bytes().await?.to_vec();
+                            if chunk.len() as u64 == end - start {
+                                Ok(Some(chunk))
+                            } else {
+                                Err(ProtocolError::IncompleteRange {
+                                    expected: end - start,
+                                    received: chunk.len() as u64,
+                                })
+                            }
+                        }
+                        reqwest::StatusCode::OK => {
+                            tracing::debug!(
+                                "Range request ignored by CDN, downloading {url} whole"
+                            );
+                            Ok(None)
+                        }
+                        _ => Err(error_for_status(&response)),
+                    }
+                },
+            )
+            .await
+    }
+
     /// Download with progress callback
     ///
     /// On native platforms, this uses streaming to report progress incrementally.
     /// On WASM, streaming is not available, so progress is reported only at completion.
+    ///
+    /// The content is hashed as it arrives and checked against `key` once
+    /// the download completes, unless [`CdnConfig::verify_checksums`] is
+    /// disabled. Mismatches are not retried.
     #[cfg(not(target_arch = "wasm32"))]
     pub async fn download_with_progress<F>(
         &self,
@@ -322,10 +617,18 @@ impl CdnClient {
         } else {
             Vec::new()
         };
+        let mut verifier = self
+            .config
+            .verify_checksums
+            .then(|| ContentVerifier::new(key))
+            .flatten();
         let mut stream = response.bytes_stream();
 
         while let Some(chunk) = stream.next().await {
             let chunk = chunk?;
+            if let Some(verifier) = &mut verifier {
+                verifier.update(&chunk);
+            }
             data.extend_from_slice(&chunk);
             downloaded += chunk.len() as u64;
             if self.config.enable_progress {
@@ -333,6 +636,9 @@ impl CdnClient {
             }
         }
 
+        if let Some(verifier) = verifier {
+            verifier.finish()?;
+        }
         Ok(data)
     }
 
@@ -340,6 +646,8 @@ impl CdnClient {
     ///
     /// On WASM, streaming is not available (`bytes_stream()` not supported by reqwest-wasm).
     /// This version downloads the entire content first, then reports progress at 100%.
+    /// The content is checked against `key` unless
+    /// [`CdnConfig::verify_checksums`] is disabled.
     #[cfg(target_arch = "wasm32")]
     pub async fn download_with_progress<F>(
         &self,
@@ -358,6 +666,9 @@ impl CdnClient {
 
         // On WASM, we can't stream - download all at once
         let data = response.bytes().await?.to_vec();
+        if self.config.verify_checksums {
+            verify_content(key, &data)?;
+        }
 
         // Report progress at 100% completion
         if self.config.enable_progress {
@@ -406,7 +717,7 @@ impl CdnClient {
         );
 
         // Download with retry logic
-        let data = self.download_with_retry(&url).await?;
+        let data = self.download_with_retry(&url, None).await?;
 
         // Store in cache
         self.cache.store_bytes(&cache_key, &data)?;
@@ -414,11 +725,80 @@ impl CdnClient {
         Ok(data)
     }
 
+    /// Download and parse a product config
+    ///
+    /// Product configs are JSON documents referenced by the `ProductConfig`
+    /// field of versions and BGDL responses. Unlike game content they live
+    /// under the endpoint's `product_path` (default `tpr/configs`), in its
+    /// `data` directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidKey` if `hash` is not 32 hex characters,
+    /// `ProtocolError::NotFound` if the CDN has no such config and
+    /// `ProtocolError::Parse` if the document is not valid JSON.
+    pub async fn get_product_config(
+        &self,
+        endpoint: &CdnEndpoint,
+        hash: &str,
+    ) -> Result<crate::product_config::ProductConfig> {
+        if hash.len() != 32 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ProtocolError::InvalidKey);
+        }
+        let hash = hash.to_ascii_lowercase();
+        let base_path =
+            normalize_cdn_path(endpoint.product_path.as_deref().unwrap_or("tpr/configs"));
+        let cache_key = format!(
+            "cdn/{}/data/{}/{}/{}",
+            base_path,
+            &hash[..2],
+            &hash[2..4],
+            hash
+        );
+
+        let data = if let Some(cached) = self.cache.get_bytes(&cache_key)? {
+            tracing::debug!("CDN cache hit for product config {}", hash);
+            cached
+        } else {
+            let scheme = endpoint.scheme.as_deref().unwrap_or("https");
+            let url = format!(
+                "{}://{}/{}/data/{}/{}/{}",
+                scheme,
+                endpoint.host,
+                base_path,
+                &hash[..2],
+                &hash[2..4],
+                hash
+            );
+
+            let data = match self.download_with_retry(&url, None).await {
+                Err(ProtocolError::ClientError(reqwest::StatusCode::NOT_FOUND)) => {
+                    return Err(ProtocolError::NotFound(url));
+                }
+                result => result?,
+            };
+            self.cache.store_bytes(&cache_key, &data)?;
+            data
+        };
+
+        crate::product_config::ProductConfig::from_json(&data)
+    }
+
     /// Get the CDN configuration
     pub fn config(&self) -> &CdnConfig {
         &self.config
     }
 
+    /// Retries left in the current minute for transient failures
+    pub fn retry_budget_remaining(&self) -> u32 {
+        self.retry_budget.remaining()
+    }
+
+    /// Retries left in the current minute for rate-limited responses
+    pub fn rate_limit_budget_remaining(&self) -> u32 {
+        self.rate_limit_budget.remaining()
+    }
+
     /// Get file size without downloading using HEAD request
     pub async fn get_file_size(
         &self,
@@ -485,33 +865,72 @@ impl CdnClient {
         }
     }
 
-    async fn download_with_retry(&self, url: &str) -> Result<Vec<u8>> {
-        let retry_policy = RetryPolicy::default();
-
-        retry_policy
-            .execute(|| async {
-                let response = self.http_client.inner().get(url).send().await?;
-
-                if response.status().is_success() {
-                    Ok(response.bytes().await?.to_vec())
-                } else if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
-                    let retry_after = parse_retry_after(&response);
-                    Err(ProtocolError::RateLimited { retry_after })
-                } else if response.status().is_server_error() {
-                    Err(ProtocolError::ServerError(response.status()))
-                } else {
-                    Err(ProtocolError::HttpStatus(response.status()))
-                }
-            })
+    /// Download a URL, retrying according to the configured policy and budgets
+    ///
+    /// 404 and other 4xx responses (except 408 and 429) fail immediately with
+    /// [`ProtocolError::ClientError`]. 429 responses honor `Retry-After` and are
+    /// charged against the rate-limit budget; 5xx, 408 and transport errors
+    /// are charged against the general retry budget.
+    ///
+    /// If `key` is given and checksums are verified, content that does not
+    /// hash to it fails with [`ProtocolError::ChecksumMismatch`] and is
+    /// retried against the general budget as well.
+    async fn download_with_retry(&self, url: &str, key: Option<&[u8]>) -> Result<Vec<u8>> {
+        let key = key.filter(|_| self.config.verify_checksums);
+        self.config
+            .retry_policy
+            .execute_with_budget_for_host(
+                url_host(url),
+                &self.retry_budget,
+                &self.rate_limit_budget,
+                || async {
+                    let response = self.http_client.inner().get(url).send().await?;
+
+                    if response.status().is_success() {
+                        let data = response.bytes().await?.to_vec();
+                        if let Some(key) = key {
+                            verify_content(key, &data)?;
+                        }
+                        Ok(data)
+                    } else {
+                        Err(error_for_status(&response))
+                    }
+                },
+            )
             .await
     }
 
     /// Create CDN endpoint from BPSV query results
     /// This is a convenience method to help users build `CdnEndpoint` from Ribbit responses
+    ///
+    /// Only the first of the row's hosts is used; see
+    /// [`endpoints_from_bpsv_row`](Self::endpoints_from_bpsv_row) for all
+    /// of them.
     pub fn endpoint_from_bpsv_row(
         row: &cascette_formats::bpsv::BpsvRow,
         schema: &cascette_formats::bpsv::BpsvSchema,
     ) -> Result<CdnEndpoint> {
+        Self::endpoints_from_bpsv_row(row, schema)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ProtocolError::Parse("Hosts field has no hosts".to_string()))
+    }
+
+    /// Create one CDN endpoint per host of a BPSV CDNs row
+    ///
+    /// The `Hosts` field is a space-separated list of mirrors serving the
+    /// same `Path`. The endpoints are in the listed order, each with the
+    /// query parameters of its own host URL. Use them with
+    /// [`download_any`](Self::download_any).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProtocolError::Parse`] if the `Hosts` or `Path` field is
+    /// missing or `Hosts` lists no hosts.
+    pub fn endpoints_from_bpsv_row(
+        row: &cascette_formats::bpsv::BpsvRow,
+        schema: &cascette_formats::bpsv::BpsvSchema,
+    ) -> Result<Vec<CdnEndpoint>> {
         let hosts_raw = row
             .get_by_name("Hosts", schema)
             .and_then(|v| v.as_string())
@@ -521,6 +940,7 @@ impl CdnClient {
             .get_by_name("Path", schema)
             .and_then(|v| v.as_string())
             .ok_or_else(|| ProtocolError::Parse("Missing Path field".to_string()))?;
+        let path = normalize_cdn_path(path);
 
         // ProductPath is optional (newer products)
         let product_path = row
@@ -528,20 +948,28 @@ impl CdnClient {
             .and_then(|v| v.as_string())
             .map(std::string::ToString::to_string);
 
-        // Hosts field can contain space-separated multiple hosts; use the first one.
-        // Parse query parameters from the host URL.
-        let first_host = hosts_raw.split_whitespace().next().unwrap_or(hosts_raw);
-        let (host, is_fallback, strict, max_hosts) = parse_cdn_server_url(first_host);
+        // Hosts can be empty or whitespace-only for regions without a CDN
+        let endpoints: Vec<_> = hosts_raw
+            .split_whitespace()
+            .map(|raw_host| {
+                // Parse query parameters from the host URL
+                let (host, is_fallback, strict, max_hosts) = parse_cdn_server_url(raw_host);
+                CdnEndpoint {
+                    host,
+                    path: path.to_string(),
+                    product_path: product_path.clone(),
+                    scheme: None, // Defaults to https in production
+                    is_fallback,
+                    strict,
+                    max_hosts,
+                }
+            })
+            .collect();
 
-        Ok(CdnEndpoint {
-            host,
-            path: normalize_cdn_path(path).to_string(),
-            product_path,
-            scheme: None, // Defaults to https in production
-            is_fallback,
-            strict,
-            max_hosts,
-        })
+        if endpoints.is_empty() {
+            return Err(ProtocolError::Parse("Hosts field has no hosts".to_string()));
+        }
+        Ok(endpoints)
     }
 }
 
@@ -721,6 +1149,101 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_download_parallel_preserves_order() {
+        let mock_server = MockServer::start().await;
+        let keys: Vec<String> = (0..8_u8)
+            .map(|i| format!("{i:02x}{i:02x}cdef1234567890"))
+            .collect();
+
+        // Each file contains its own key, the first one takes longest
+        for (i, key) in keys.iter().enumerate() {
+            let delay = Duration::from_millis(if i == 0 { 200 } else { 20 });
+            Mock::given(method("GET"))
+                .and(path(format!(
+                    "/tpr/wow/data/{}/{}/{key}",
+                    &key[..2],
+                    &key[2..4]
+                )))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_bytes(key.clone().into_bytes())
+                        .set_delay(delay),
+                )
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+        }
+        // Not on the CDN
+        Mock::given(method("GET"))
+            .and(path("/tpr/wow/data/ff/ff/ffffcdef1234567890"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+
+        let cache = create_test_cache();
+        let client = CdnClient::new(cache, CdnConfig::default()).expect("Operation should succeed");
+        let endpoint = CdnEndpoint {
+            host: mock_server.uri().replace("http://", ""),
+            path: "tpr/wow".to_string(),
+            product_path: None,
+            scheme: Some("http".to_string()),
+            is_fallback: false,
+            strict: false,
+            max_hosts: None,
+        };
+
+        let mut requests: Vec<_> = keys
+            .iter()
+            .map(|key| {
+                (
+                    ContentType::Data,
+                    hex::decode(key).expect("Operation should succeed"),
+                )
+            })
+            .collect();
+        requests.insert(
+            3,
+            (
+                ContentType::Data,
+                hex::decode("ffffcdef1234567890").expect("Operation should succeed"),
+            ),
+        );
+
+        let results = client.download_parallel(&endpoint, requests, 4).await;
+        assert_eq!(results.len(), 9);
+        assert!(results[3].is_err());
+        let data: Vec<_> = results
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| *i != 3)
+            .map(|(_, result)| result.expect("Download should succeed"))
+            .collect();
+        for (key, data) in keys.iter().zip(&data) {
+            assert_eq!(data, key.as_bytes());
+        }
+
+        // Cached files are not requested again
+        let again = client
+            .download_parallel(
+                &endpoint,
+                vec![(
+                    ContentType::Data,
+                    hex::decode(&keys[0]).expect("Operation should succeed"),
+                )],
+                0,
+            )
+            .await;
+        assert_eq!(
+            again
+                .into_iter()
+                .next()
+                .expect("One result")
+                .expect("Cached download should succeed"),
+            keys[0].as_bytes()
+        );
+    }
+
     #[tokio::test]
     async fn test_download_range_request() {
         let mock_server = MockServer::start().await;
@@ -1177,6 +1700,182 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_endpoints_from_bpsv_row_multiple_hosts() {
+        let schema = BpsvSchema::new(vec![
+            BpsvField::new("Name", BpsvType::String(0)),
+            BpsvField::new("Hosts", BpsvType::String(0)),
+            BpsvField::new("Path", BpsvType::String(0)),
+        ]);
+
+        let row = BpsvRow::from_values(vec![
+            BpsvValue::String("us".to_string()),
+            BpsvValue::String(
+                " level3.blizzard.com  us.cdn.blizzard.com cdn.arctium.tools?fallback=1 "
+                    .to_string(),
+            ),
+            BpsvValue::String("tpr/wow/".to_string()),
+        ]);
+
+        let endpoints =
+            CdnClient::endpoints_from_bpsv_row(&row, &schema).expect("Operation should succeed");
+        let hosts: Vec<_> = endpoints.iter().map(|e| e.host.as_str()).collect();
+        assert_eq!(
+            hosts,
+            [
+                "level3.blizzard.com",
+                "us.cdn.blizzard.com",
+                "cdn.arctium.tools"
+            ]
+        );
+        assert!(endpoints.iter().all(|e| e.path == "tpr/wow"));
+        assert_eq!(
+            endpoints.iter().map(|e| e.is_fallback).collect::<Vec<_>>(),
+            [false, false, true]
+        );
+
+        // The single-endpoint form keeps using the first host
+        let endpoint =
+            CdnClient::endpoint_from_bpsv_row(&row, &schema).expect("Operation should succeed");
+        assert_eq!(endpoint.host, "level3.blizzard.com");
+    }
+
+    #[test]
+    fn test_endpoints_from_bpsv_row_empty_hosts() {
+        let schema = BpsvSchema::new(vec![
+            BpsvField::new("Name", BpsvType::String(0)),
+            BpsvField::new("Hosts", BpsvType::String(0)),
+            BpsvField::new("Path", BpsvType::String(0)),
+        ]);
+
+        for hosts in ["", "   "] {
+            let row = BpsvRow::from_values(vec![
+                BpsvValue::String("xx".to_string()),
+                BpsvValue::String(hosts.to_string()),
+                BpsvValue::String("tpr/wow".to_string()),
+            ]);
+
+            assert!(matches!(
+                CdnClient::endpoints_from_bpsv_row(&row, &schema),
+                Err(ProtocolError::Parse(_))
+            ));
+            assert!(matches!(
+                CdnClient::endpoint_from_bpsv_row(&row, &schema),
+                Err(ProtocolError::Parse(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_any_fails_over_and_caches_by_path() {
+        let broken = MockServer::start().await;
+        let working = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&broken)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/tpr/wow/data/ab/cd/abcdef1234567890"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"mirrored".to_vec()))
+            .expect(1)
+            .mount(&working)
+            .await;
+
+        let client = CdnClient::new(create_test_cache(), CdnConfig::default())
+            .expect("Operation should succeed");
+        let endpoints = [mock_endpoint(&broken), mock_endpoint(&working)];
+        let key = hex::decode("abcdef1234567890").expect("Operation should succeed");
+
+        // Whichever host is tried first, the download succeeds
+        for _ in 0..2 {
+            let data = client
+                .download_any(&endpoints, ContentType::Data, &key)
+                .await
+                .expect("Download should fail over to the working host");
+            assert_eq!(data, b"mirrored");
+        }
+
+        // Served from cache even when only the broken mirror is offered
+        let data = client
+            .download_any(&endpoints[..1], ContentType::Data, &key)
+            .await
+            .expect("Cached content should be served");
+        assert_eq!(data, b"mirrored");
+    }
+
+    #[tokio::test]
+    async fn test_download_any_round_robin() {
+        let first = MockServer::start().await;
+        let second = MockServer::start().await;
+
+        for server in [&first, &second] {
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(b"content".to_vec()))
+                .expect(1)
+                .mount(server)
+                .await;
+        }
+
+        let client = CdnClient::new(create_test_cache(), CdnConfig::default())
+            .expect("Operation should succeed");
+        let endpoints = [mock_endpoint(&first), mock_endpoint(&second)];
+
+        // Two different files, so neither is a cache hit
+        for key in ["0011223344556677", "8899aabbccddeeff"] {
+            let key = hex::decode(key).expect("Operation should succeed");
+            client
+                .download_any(&endpoints, ContentType::Data, &key)
+                .await
+                .expect("Download should succeed");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_any_strict_and_fallback_hosts() {
+        let strict = MockServer::start().await;
+        let fallback = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&strict)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"fallback".to_vec()))
+            .expect(0)
+            .mount(&fallback)
+            .await;
+
+        let client = CdnClient::new(create_test_cache(), CdnConfig::default())
+            .expect("Operation should succeed");
+        // The fallback host is listed first but only tried after the others
+        let endpoints = [
+            CdnEndpoint {
+                is_fallback: true,
+                ..mock_endpoint(&fallback)
+            },
+            CdnEndpoint {
+                strict: true,
+                ..mock_endpoint(&strict)
+            },
+        ];
+        let key = hex::decode("abcdef1234567890").expect("Operation should succeed");
+
+        let result = client
+            .download_any(&endpoints, ContentType::Data, &key)
+            .await;
+        assert!(matches!(
+            result,
+            Err(ProtocolError::ClientError(reqwest::StatusCode::NOT_FOUND))
+        ));
+
+        assert!(matches!(
+            client.download_any(&[], ContentType::Data, &key).await,
+            Err(ProtocolError::InvalidEndpoint(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_download_rate_limited_with_retry_after() {
         use std::sync::Arc;
@@ -1241,6 +1940,7 @@ mod tests {
             chunk_size: 8 * 1024 * 1024,
             enable_progress: true,
             pool_size: 50,
+            ..Default::default()
         };
 
         let client = CdnClient::new(cache, config).expect("Operation should succeed");
@@ -1251,4 +1951,578 @@ mod tests {
         assert!(client_config.enable_progress);
         assert_eq!(client_config.pool_size, 50);
     }
+
+    fn mock_endpoint(mock_server: &MockServer) -> CdnEndpoint {
+        CdnEndpoint {
+            host: mock_server.uri().replace("http://", ""),
+            path: "tpr/wow".to_string(),
+            product_path: None,
+            scheme: Some("http".to_string()),
+            is_fallback: false,
+            strict: false,
+            max_hosts: None,
+        }
+    }
+
+    fn fast_retry_config() -> CdnConfig {
+        CdnConfig::default().with_retry_policy(crate::retry::RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+            multiplier: 2.0,
+            jitter: false,
+            ..crate::retry::RetryPolicy::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_download_client_errors_not_retried() {
+        use crate::error::ErrorClass;
+
+        let mock_server = MockServer::start().await;
+        for (status, key) in [(404, "abcdef1234567890"), (403, "abcdef1234567891")] {
+            Mock::given(method("GET"))
+                .and(path(format!("/tpr/wow/data/ab/cd/{key}")))
+                .respond_with(ResponseTemplate::new(status))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+        }
+
+        let client = CdnClient::new(create_test_cache(), fast_retry_config())
+            .expect("Operation should succeed");
+        let endpoint = mock_endpoint(&mock_server);
+
+        for key in ["abcdef1234567890", "abcdef1234567891"] {
+            let key = hex::decode(key).expect("Operation should succeed");
+            let err = client
+                .download(&endpoint, ContentType::Data, &key)
+                .await
+                .expect_err("Test operation should fail");
+            assert!(matches!(err, ProtocolError::ClientError(_)));
+            assert_eq!(err.classification(), ErrorClass::Permanent);
+        }
+        // No retries means no budget spent
+        assert_eq!(client.retry_budget_remaining(), 120);
+    }
+
+    #[tokio::test]
+    async fn test_download_request_timeout_retried() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let mock_server = MockServer::start().await;
+        let counter = Arc::new(AtomicUsize::new(0));
+        let counter_clone = Arc::clone(&counter);
+
+        Mock::given(method("GET"))
+            .and(path("/tpr/wow/data/ab/cd/abcdef1234567890"))
+            .respond_with(move |_req: &wiremock::Request| {
+                if counter_clone.fetch_add(1, Ordering::SeqCst) == 0 {
+                    ResponseTemplate::new(408)
+                } else {
+                    ResponseTemplate::new(200).set_body_bytes(b"ok".to_vec())
+                }
+            })
+            .mount(&mock_server)
+            .await;
+
+        let client = CdnClient::new(create_test_cache(), fast_retry_config())
+            .expect("Operation should succeed");
+        let key = hex::decode("abcdef1234567890").expect("Operation should succeed");
+        let data = client
+            .download(&mock_endpoint(&mock_server), ContentType::Data, &key)
+            .await
+            .expect("Operation should succeed");
+
+        assert_eq!(data, b"ok");
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+        assert_eq!(client.retry_budget_remaining(), 119);
+    }
+
+    #[tokio::test]
+    async fn test_download_server_error_exhausts_attempts() {
+        use crate::error::ErrorClass;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/tpr/wow/data/ab/cd/abcdef1234567890"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(4) // initial + 3 retries
+            .mount(&mock_server)
+            .await;
+
+        let client = CdnClient::new(create_test_cache(), fast_retry_config())
+            .expect("Operation should succeed");
+        let key = hex::decode("abcdef1234567890").expect("Operation should succeed");
+        let err = client
+            .download(&mock_endpoint(&mock_server), ContentType::Data, &key)
+            .await
+            .expect_err("Test operation should fail");
+
+        assert!(matches!(err, ProtocolError::ServerError(_)));
+        assert_eq!(err.classification(), ErrorClass::Transient);
+    }
+
+    #[tokio::test]
+    async fn test_download_rate_limited_without_retry_after_uses_backoff() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let mock_server = MockServer::start().await;
+        let counter = Arc::new(AtomicUsize::new(0));
+        let counter_clone = Arc::clone(&counter);
+
+        Mock::given(method("GET"))
+            .and(path("/tpr/wow/data/ab/cd/abcdef1234567890"))
+            .respond_with(move |_req: &wiremock::Request| {
+                if counter_clone.fetch_add(1, Ordering::SeqCst) == 0 {
+                    ResponseTemplate::new(429)
+                } else {
+                    ResponseTemplate::new(200).set_body_bytes(b"ok".to_vec())
+                }
+            })
+            .mount(&mock_server)
+            .await;
+
+        let client = CdnClient::new(create_test_cache(), fast_retry_config())
+            .expect("Operation should succeed");
+        let key = hex::decode("abcdef1234567890").expect("Operation should succeed");
+        let start = std::time::Instant::now();
+        let data = client
+            .download(&mock_endpoint(&mock_server), ContentType::Data, &key)
+            .await
+            .expect("Operation should succeed");
+
+        assert_eq!(data, b"ok");
+        // Fell back to the 1ms backoff rather than waiting for a Retry-After
+        assert!(start.elapsed() < Duration::from_millis(500));
+        // Charged against the rate-limit budget, not the general one
+        assert_eq!(client.rate_limit_budget_remaining(), 9);
+        assert_eq!(client.retry_budget_remaining(), 120);
+    }
+
+    #[tokio::test]
+    async fn test_download_rate_limit_budget_exhausted() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/tpr/wow/data/ab/cd/abcdef1234567890"))
+            .respond_with(ResponseTemplate::new(429).append_header("Retry-After", "30"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = CdnClient::new(
+            create_test_cache(),
+            fast_retry_config().with_rate_limit_budget(0),
+        )
+        .expect("Operation should succeed");
+        let key = hex::decode("abcdef1234567890").expect("Operation should succeed");
+        let start = std::time::Instant::now();
+        let err = client
+            .download(&mock_endpoint(&mock_server), ContentType::Data, &key)
+            .await
+            .expect_err("Test operation should fail");
+
+        assert!(matches!(
+            err,
+            ProtocolError::RetryBudgetExhausted(inner)
+                if matches!(*inner, ProtocolError::RateLimited { .. })
+        ));
+        // Did not sleep for the 30s Retry-After
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_download_retry_budget_shared_across_downloads() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+
+        let client = CdnClient::new(
+            create_test_cache(),
+            fast_retry_config().with_retry_budget(2),
+        )
+        .expect("Operation should succeed");
+        let endpoint = mock_endpoint(&mock_server);
+
+        // First download spends the whole budget on its retries
+        let key = hex::decode("abcdef1234567890").expect("Operation should succeed");
+        let err = client
+            .download(&endpoint, ContentType::Data, &key)
+            .await
+            .expect_err("Test operation should fail");
+        assert!(matches!(err, ProtocolError::RetryBudgetExhausted(_)));
+
+        // Second download gets no retries at all
+        let key = hex::decode("abcdef1234567891").expect("Operation should succeed");
+        let err = client
+            .download(&endpoint, ContentType::Data, &key)
+            .await
+            .expect_err("Test operation should fail");
+        assert!(matches!(err, ProtocolError::RetryBudgetExhausted(_)));
+
+        let requests = mock_server
+            .received_requests()
+            .await
+            .expect("Operation should succeed");
+        // 1 + 2 retries for the first download, 1 for the second
+        assert_eq!(requests.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_download_circuit_breaker_stops_requests_to_failing_host() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+
+        let policy = fast_retry_config()
+            .retry_policy
+            .with_circuit_breaker(2, Duration::from_secs(60));
+        let client = CdnClient::new(
+            create_test_cache(),
+            CdnConfig::default().with_retry_policy(policy),
+        )
+        .expect("Operation should succeed");
+        let endpoint = mock_endpoint(&mock_server);
+
+        for key in ["abcdef1234567890", "abcdef1234567891"] {
+            let key = hex::decode(key).expect("Operation should succeed");
+            let err = client
+                .download(&endpoint, ContentType::Data, &key)
+                .await
+                .expect_err("Test operation should fail");
+            assert!(matches!(err, ProtocolError::CircuitOpen(ref host) if *host == endpoint.host));
+        }
+
+        let requests = mock_server
+            .received_requests()
+            .await
+            .expect("Operation should succeed");
+        // Two failures open the circuit; the second download sends nothing
+        assert_eq!(requests.len(), 2);
+    }
+
+    #[test]
+    fn test_url_host() {
+        assert_eq!(url_host("http://127.0.0.1:8080/tpr/wow"), "127.0.0.1:8080");
+        assert_eq!(url_host("https://cdn.example?x=1"), "cdn.example");
+        assert_eq!(url_host("cdn.example/path"), "cdn.example");
+    }
+
+    #[tokio::test]
+    async fn test_get_product_config() {
+        let mock_server = MockServer::start().await;
+        let body = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/product_config_wow.json"
+        ));
+        Mock::given(method("GET"))
+            .and(path(
+                "/tpr/configs/data/53/02/53020d32e1a25648c8e1eafd5771935f",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(body.to_vec()))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = CdnClient::new(create_test_cache(), fast_retry_config())
+            .expect("Operation should succeed");
+        let endpoint = CdnEndpoint {
+            product_path: Some("tpr/configs/".to_string()),
+            ..mock_endpoint(&mock_server)
+        };
+
+        // The second call is served from cache
+        for _ in 0..2 {
+            let config = client
+                .get_product_config(&endpoint, "53020D32E1A25648C8E1EAFD5771935F")
+                .await
+                .expect("Product config should download");
+            assert_eq!(config.uid("win"), Some("wow"));
+            assert_eq!(
+                config.launch_arguments("win"),
+                ["-launcherlogin", "-uid", "wow"]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_product_config_not_found() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/tpr/configs/data/00/11/00112233445566778899aabbccddeeff",
+            ))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = CdnClient::new(create_test_cache(), fast_retry_config())
+            .expect("Operation should succeed");
+        let endpoint = mock_endpoint(&mock_server);
+
+        let err = client
+            .get_product_config(&endpoint, "00112233445566778899aabbccddeeff")
+            .await
+            .expect_err("Missing config should fail");
+        assert!(
+            matches!(&err, ProtocolError::NotFound(url) if url.ends_with("/00112233445566778899aabbccddeeff"))
+        );
+
+        let err = client
+            .get_product_config(&endpoint, "not-a-hash")
+            .await
+            .expect_err("Invalid hash should fail");
+        assert!(matches!(err, ProtocolError::InvalidKey));
+    }
+
+    /// Serve `wrong` for the first `failures` requests of `key`, then `right`
+    async fn serve_corrupted(
+        mock_server: &MockServer,
+        key: &[u8],
+        failures: usize,
+        wrong: &'static [u8],
+        right: &'static [u8],
+    ) -> Arc<std::sync::atomic::AtomicUsize> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let hex_key = hex::encode(key);
+        let counter = Arc::new(AtomicUsize::new(0));
+        let counter_clone = Arc::clone(&counter);
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/tpr/wow/config/{}/{}/{hex_key}",
+                &hex_key[..2],
+                &hex_key[2..4]
+            )))
+            .respond_with(move |_req: &wiremock::Request| {
+                let body = if counter_clone.fetch_add(1, Ordering::SeqCst) < failures {
+                    wrong
+                } else {
+                    right
+                };
+                ResponseTemplate::new(200).set_body_bytes(body)
+            })
+            .mount(mock_server)
+            .await;
+        counter
+    }
+
+    #[tokio::test]
+    async fn test_download_retries_checksum_mismatch() {
+        use std::sync::atomic::Ordering;
+
+        let mock_server = MockServer::start().await;
+        let content: &'static [u8] = b"build-name = verified\n";
+        let key = md5::compute(content).0;
+        let requests = serve_corrupted(&mock_server, &key, 1, b"truncated", content).await;
+
+        let client = CdnClient::new(create_test_cache(), fast_retry_config())
+            .expect("Operation should succeed");
+        let data = client
+            .download(&mock_endpoint(&mock_server), ContentType::Config, &key)
+            .await
+            .expect("Download should succeed after retry");
+
+        assert_eq!(data, content);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_download_checksum_mismatch_exhausts_attempts() {
+        use std::sync::atomic::Ordering;
+
+        let mock_server = MockServer::start().await;
+        let key = md5::compute(b"expected content").0;
+        let requests = serve_corrupted(&mock_server, &key, usize::MAX, b"wrong", b"").await;
+
+        let client = CdnClient::new(create_test_cache(), fast_retry_config())
+            .expect("Operation should succeed");
+        let err = client
+            .download(&mock_endpoint(&mock_server), ContentType::Config, &key)
+            .await
+            .expect_err("Corrupted download should fail");
+
+        assert!(matches!(
+            err,
+            ProtocolError::ChecksumMismatch { expected, actual }
+                if expected == hex::encode(key) && actual == hex::encode(md5::compute(b"wrong").0)
+        ));
+        assert_eq!(requests.load(Ordering::SeqCst), 4); // initial + 3 retries
+    }
+
+    #[tokio::test]
+    async fn test_download_checksum_verification_can_be_disabled() {
+        let mock_server = MockServer::start().await;
+        let key = md5::compute(b"expected content").0;
+        serve_corrupted(&mock_server, &key, usize::MAX, b"unverified", b"").await;
+
+        let client = CdnClient::new(
+            create_test_cache(),
+            fast_retry_config().verify_checksums(false),
+        )
+        .expect("Operation should succeed");
+        let data = client
+            .download(&mock_endpoint(&mock_server), ContentType::Config, &key)
+            .await
+            .expect("Unverified download should succeed");
+
+        assert_eq!(data, b"unverified");
+    }
+
+    #[tokio::test]
+    async fn test_download_with_progress_verifies_checksum() {
+        let mock_server = MockServer::start().await;
+        let content: &'static [u8] = b"streamed config";
+        let key = md5::compute(content).0;
+        serve_corrupted(&mock_server, &key, 1, b"streamed garbage", content).await;
+
+        let client = CdnClient::new(create_test_cache(), CdnConfig::default())
+            .expect("Operation should succeed");
+        let endpoint = mock_endpoint(&mock_server);
+
+        let err = client
+            .download_with_progress(&endpoint, ContentType::Config, &key, |_, _| {})
+            .await
+            .expect_err("Corrupted download should fail");
+        assert!(matches!(err, ProtocolError::ChecksumMismatch { .. }));
+
+        let data = client
+            .download_with_progress(&endpoint, ContentType::Config, &key, |_, _| {})
+            .await
+            .expect("Intact download should succeed");
+        assert_eq!(data, content);
+    }
+
+    /// Mock CDN file that answers HEAD and honors (or ignores) `Range`
+    struct RangedFile {
+        body: Vec<u8>,
+        honor_ranges: bool,
+        ranged_requests: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl wiremock::Respond for RangedFile {
+        fn respond(&self, request: &wiremock::Request) -> ResponseTemplate {
+            let range = request
+                .headers
+                .get("range")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("bytes="))
+                .and_then(|v| v.split_once('-'))
+                .and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)));
+
+            match range {
+                Some((start, end)) if self.honor_ranges => {
+                    let end = usize::min(end, self.body.len() - 1);
+                    self.ranged_requests
+                        .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    ResponseTemplate::new(206).set_body_bytes(self.body[start..=end].to_vec())
+                }
+                _ => ResponseTemplate::new(200).set_body_bytes(self.body.clone()),
+            }
+        }
+    }
+
+    /// Serve `body` under its MD5 key, returning the key and range request count
+    async fn serve_ranged(
+        mock_server: &MockServer,
+        body: Vec<u8>,
+        honor_ranges: bool,
+    ) -> ([u8; 16], Arc<std::sync::atomic::AtomicUsize>) {
+        let key = md5::compute(&body).0;
+        let hex_key = hex::encode(key);
+        let ranged_requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        Mock::given(path(format!(
+            "/tpr/wow/data/{}/{}/{hex_key}",
+            &hex_key[..2],
+            &hex_key[2..4]
+        )))
+        .respond_with(RangedFile {
+            body,
+            honor_ranges,
+            ranged_requests: Arc::clone(&ranged_requests),
+        })
+        .mount(mock_server)
+        .await;
+        (key, ranged_requests)
+    }
+
+    fn ranged_body() -> Vec<u8> {
+        (0..10_000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn test_download_chunked_reassembles_in_order() {
+        use std::sync::atomic::Ordering;
+
+        let mock_server = MockServer::start().await;
+        let body = ranged_body();
+        let (key, ranged_requests) = serve_ranged(&mock_server, body.clone(), true).await;
+
+        let client = CdnClient::new(create_test_cache(), fast_retry_config())
+            .expect("Operation should succeed");
+        let data = client
+            .download_chunked(&mock_endpoint(&mock_server), ContentType::Data, &key, 1024)
+            .await
+            .expect("Chunked download should succeed");
+
+        assert_eq!(data, body);
+        // Nine full chunks and a shorter last one
+        assert_eq!(ranged_requests.load(Ordering::SeqCst), 10);
+
+        // The reassembled file is cached
+        let again = client
+            .download(&mock_endpoint(&mock_server), ContentType::Data, &key)
+            .await
+            .expect("Cached download should succeed");
+        assert_eq!(again, body);
+        assert_eq!(ranged_requests.load(Ordering::SeqCst), 10);
+    }
+
+    #[tokio::test]
+    async fn test_download_chunked_falls_back_without_range_support() {
+        use std::sync::atomic::Ordering;
+
+        let mock_server = MockServer::start().await;
+        let body = ranged_body();
+        let (key, ranged_requests) = serve_ranged(&mock_server, body.clone(), false).await;
+
+        let client = CdnClient::new(create_test_cache(), fast_retry_config())
+            .expect("Operation should succeed");
+        let data = client
+            .download_chunked(&mock_endpoint(&mock_server), ContentType::Data, &key, 1024)
+            .await
+            .expect("Fallback download should succeed");
+
+        assert_eq!(data, body);
+        assert_eq!(ranged_requests.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_download_chunked_small_file_downloads_whole() {
+        use std::sync::atomic::Ordering;
+
+        let mock_server = MockServer::start().await;
+        let body = ranged_body();
+        let (key, ranged_requests) = serve_ranged(&mock_server, body.clone(), true).await;
+
+        let client = CdnClient::new(create_test_cache(), fast_retry_config())
+            .expect("Operation should succeed");
+        let data = client
+            .download_chunked(
+                &mock_endpoint(&mock_server),
+                ContentType::Data,
+                &key,
+                body.len(),
+            )
+            .await
+            .expect("Download should succeed");
+
+        assert_eq!(data, body);
+        assert_eq!(ranged_requests.load(Ordering::SeqCst), 0);
+    }
 }
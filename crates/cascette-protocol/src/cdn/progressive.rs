@@ -0,0 +1,926 @@
+//! Progressive reads of CDN files while their chunks download
+//!
+//! [`ProgressiveFileManager::open_streaming`] finds a file's encoding key in
+//! the archive indices, reads its BLTE chunk table with one range request and
+//! returns a [`ProgressiveReader`]. The reader downloads and decompresses each
+//! chunk as it is reached, so the first bytes of a large file are available
+//! long before the last chunk arrives:
+//!
+//! ```text
+//! archive data:  ... | BLTE header | chunk 0 | chunk 1 | chunk 2 | ... |
+//!                       ^ 1 request   ^ fetched on demand, up to
+//!                                       `prefetch_depth` at once
+//! ```
+//!
+//! Up to `prefetch_depth` chunks starting at the read position are fetched
+//! at once; chunks behind the position are not prefetched. Decompressed
+//! chunks are kept in a cache shared by all readers of a manager, so seeking
+//! back or opening the same file again does not download them a second time.
+//!
+//! Files not found in any archive are read as loose files from
+//! `data/<ekey>`. Chunkless BLTE files (header size 0) have no chunk table
+//! to stream by and are downloaded whole when opened.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::future::Future;
+use std::io::{self, SeekFrom};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
+
+use cascette_crypto::TactKeyStore;
+use cascette_formats::blte::{ChunkData, CompressionMode, HeaderFlags};
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+use tokio::task::JoinHandle;
+
+use super::locate::ArchiveLocator;
+use super::{CdnClient, CdnEndpoint, ContentType};
+use crate::error::{ProtocolError, Result};
+
+/// Bytes requested for the BLTE header before the chunk table size is known
+const HEADER_PROBE: u64 = 4096;
+
+/// Magic bytes and header size field preceding every BLTE chunk table
+const PREAMBLE_SIZE: usize = 8;
+
+/// Opens CDN files for reading while they download
+pub struct ProgressiveFileManager {
+    endpoint: CdnEndpoint,
+    locator: ArchiveLocator,
+    prefetch_depth: usize,
+    key_store: Option<Arc<TactKeyStore>>,
+    cache: Arc<ChunkCache>,
+}
+
+impl std::fmt::Debug for ProgressiveFileManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressiveFileManager")
+            .field("endpoint", &self.endpoint)
+            .field("archives", &self.locator.archives().len())
+            .field("prefetch_depth", &self.prefetch_depth)
+            .field("cached_bytes", &self.cached_bytes())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProgressiveFileManager {
+    /// Create a manager reading from `archives` on `endpoint`
+    ///
+    /// `archives` are the hex archive keys of the CDN config, searched in
+    /// order. Their indices are downloaded the first time a file is opened.
+    /// Readers prefetch 4 chunks and the manager caches up to 64 MiB of
+    /// decompressed chunks by default.
+    pub fn new(endpoint: CdnEndpoint, archives: Vec<String>) -> Self {
+        Self {
+            endpoint,
+            locator: ArchiveLocator::new(archives),
+            prefetch_depth: 4,
+            key_store: None,
+            cache: Arc::new(ChunkCache::new(64 * 1024 * 1024)),
+        }
+    }
+
+    /// Set the number of chunks a reader downloads at once (at least one)
+    #[must_use]
+    pub fn with_prefetch_depth(mut self, depth: usize) -> Self {
+        self.prefetch_depth = depth.max(1);
+        self
+    }
+
+    /// Set the decompressed bytes kept for seeking back and reopening files
+    ///
+    /// The oldest chunks are evicted first. A capacity of zero disables the
+    /// cache.
+    #[must_use]
+    pub fn with_cache_capacity(mut self, bytes: usize) -> Self {
+        self.cache = Arc::new(ChunkCache::new(bytes));
+        self
+    }
+
+    /// Decrypt encrypted chunks with keys from `key_store`
+    #[must_use]
+    pub fn with_key_store(mut self, key_store: Arc<TactKeyStore>) -> Self {
+        self.key_store = Some(key_store);
+        self
+    }
+
+    /// Decompressed bytes currently cached
+    pub fn cached_bytes(&self) -> usize {
+        self.cache.size()
+    }
+
+    /// Open the file with encoding key `ekey` for reading
+    ///
+    /// Downloads the archive indices not loaded yet, stopping at the first
+    /// one containing `ekey`, and the file's BLTE header. Chunk data is only
+    /// downloaded as the returned reader advances.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::NotFound` if `ekey` is in no archive and not
+    /// on the CDN as a loose file, `ProtocolError::Parse` if an index or the
+    /// BLTE header is invalid, and download errors as they occur.
+    pub async fn open_streaming(
+        &self,
+        ekey: &[u8],
+        cdn_client: Arc<CdnClient>,
+    ) -> Result<ProgressiveReader> {
+        let located = self
+            .locator
+            .locate(&cdn_client, &self.endpoint, ekey)
+            .await?;
+        let (file_key, offset, size) = if let Some(span) = located {
+            (span.archive, span.offset, span.size)
+        } else {
+            let size = cdn_client
+                .get_file_size(&self.endpoint, ContentType::Data, ekey)
+                .await?
+                .ok_or_else(|| ProtocolError::NotFound(hex::encode(ekey)))?;
+            (ekey.to_vec(), 0, size)
+        };
+
+        let url = CdnClient::build_url(&self.endpoint, ContentType::Data, &file_key);
+        let mut source = ChunkSource {
+            verify: cdn_client.config().verify_checksums,
+            cdn: cdn_client,
+            url,
+            ekey: ekey.to_vec(),
+            spans: Vec::new(),
+            key_store: self.key_store.clone(),
+            cache: Arc::clone(&self.cache),
+        };
+
+        let mut current = None;
+        let mut header = source.fetch_range(offset, size.min(HEADER_PROBE)).await?;
+        if let ChunkTable::TooShort(header_size) = parse_chunk_table(&header, offset, size)? {
+            header = source.fetch_range(offset, header_size).await?;
+        }
+        source.spans = match parse_chunk_table(&header, offset, size)? {
+            ChunkTable::Spans(spans) => spans,
+            ChunkTable::TooShort(_) => return Err(invalid_header("chunk table truncated")),
+            ChunkTable::Chunkless => {
+                let raw = source
+                    .fetch_range(offset, size)
+                    .await?
+                    .split_off(PREAMBLE_SIZE);
+                let data = decode_chunk(&raw, 0, None, source.key_store.as_deref())?;
+                let span = ChunkSpan {
+                    offset: offset + PREAMBLE_SIZE as u64,
+                    compressed_size: raw.len() as u64,
+                    start: 0,
+                    decompressed_size: data.len() as u64,
+                    checksum: [0; 16],
+                };
+                let data = Arc::new(data);
+                self.cache.insert(ekey, 0, Arc::clone(&data));
+                current = Some((0, data));
+                vec![span]
+            }
+        };
+
+        let len = source
+            .spans
+            .last()
+            .map_or(0, |span| span.start + span.decompressed_size);
+        Ok(ProgressiveReader {
+            source: Arc::new(source),
+            prefetch_depth: self.prefetch_depth,
+            position: 0,
+            len,
+            current,
+            in_flight: BTreeMap::new(),
+        })
+    }
+}
+
+/// A chunk's place in the CDN file and in the decompressed content
+#[derive(Debug, Clone, Copy)]
+struct ChunkSpan {
+    /// Offset of the chunk in the archive or loose file
+    offset: u64,
+    /// Size of the chunk including its mode byte
+    compressed_size: u64,
+    /// Offset of the chunk's first byte in the decompressed content
+    start: u64,
+    /// Size of the chunk after decompression
+    decompressed_size: u64,
+    /// MD5 of the compressed chunk, all zeros if absent
+    checksum: [u8; 16],
+}
+
+/// Result of parsing a BLTE header prefix
+enum ChunkTable {
+    /// The chunk table was complete
+    Spans(Vec<ChunkSpan>),
+    /// The header is larger than the bytes given; carries its size
+    TooShort(u64),
+    /// Header size 0: one chunk without a table
+    Chunkless,
+}
+
+/// Parse the BLTE header at the start of `data` into chunk spans
+///
+/// `offset` and `size` locate the file in its archive, so the spans can be
+/// checked against the file's bounds.
+fn parse_chunk_table(data: &[u8], offset: u64, size: u64) -> Result<ChunkTable> {
+    if data.len() < PREAMBLE_SIZE || &data[..4] != b"BLTE" {
+        return Err(invalid_header("missing BLTE magic"));
+    }
+    let header_size = u64::from(u32::from_be_bytes([data[4], data[5], data[6], data[7]]));
+    if header_size == 0 {
+        return Ok(ChunkTable::Chunkless);
+    }
+    if header_size > size {
+        return Err(invalid_header("header larger than file"));
+    }
+    if (data.len() as u64) < header_size {
+        return Ok(ChunkTable::TooShort(header_size));
+    }
+
+    let table = &data[PREAMBLE_SIZE..header_size as usize];
+    if table.len() < 4 {
+        return Err(invalid_header("chunk table truncated"));
+    }
+    let flags = HeaderFlags::from_byte(table[0])
+        .ok_or_else(|| invalid_header(&format!("unknown flags byte 0x{:02X}", table[0])))?;
+    let count = u32::from_be_bytes([0, table[1], table[2], table[3]]) as usize;
+    let entries = &table[4..];
+    if count == 0 || entries.len() != count * flags.chunk_info_size() {
+        return Err(invalid_header("chunk count does not match header size"));
+    }
+
+    let mut spans = Vec::with_capacity(count);
+    let mut chunk_offset = offset + header_size;
+    let mut start = 0;
+    for entry in entries.chunks_exact(flags.chunk_info_size()) {
+        let compressed_size =
+            u64::from(u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]));
+        let decompressed_size =
+            u64::from(u32::from_be_bytes([entry[4], entry[5], entry[6], entry[7]]));
+        let mut checksum = [0; 16];
+        checksum.copy_from_slice(&entry[8..24]);
+        if compressed_size == 0 {
+            return Err(invalid_header("empty chunk"));
+        }
+        spans.push(ChunkSpan {
+            offset: chunk_offset,
+            compressed_size,
+            start,
+            decompressed_size,
+            checksum,
+        });
+        chunk_offset += compressed_size;
+        start += decompressed_size;
+    }
+    if chunk_offset > offset + size {
+        return Err(invalid_header("chunks extend past the end of the file"));
+    }
+    Ok(ChunkTable::Spans(spans))
+}
+
+fn invalid_header(reason: &str) -> ProtocolError {
+    ProtocolError::Parse(format!("Invalid BLTE header: {reason}"))
+}
+
+/// Decompress one chunk, mode byte included
+///
+/// `expected_size` is checked when the chunk table gives one.
+fn decode_chunk(
+    raw: &[u8],
+    index: usize,
+    expected_size: Option<u64>,
+    key_store: Option<&TactKeyStore>,
+) -> Result<Vec<u8>> {
+    let (&mode_byte, data) = raw
+        .split_first()
+        .ok_or_else(|| invalid_header("empty chunk"))?;
+    let mode = CompressionMode::from_byte(mode_byte).ok_or_else(|| {
+        ProtocolError::Parse(format!("Unknown BLTE compression mode 0x{mode_byte:02X}"))
+    })?;
+    let chunk = ChunkData::from_compressed(mode, data.to_vec(), None);
+    let decoded = match key_store {
+        Some(key_store) => chunk.decompress_with_keys(index, key_store),
+        None => chunk.decompress(index),
+    }
+    .map_err(|e| ProtocolError::Parse(format!("Failed to decode BLTE chunk {index}: {e}")))?;
+
+    if let Some(expected) = expected_size
+        && decoded.len() as u64 != expected
+    {
+        return Err(ProtocolError::Parse(format!(
+            "BLTE chunk {index} decoded to {} bytes, expected {expected}",
+            decoded.len()
+        )));
+    }
+    Ok(decoded)
+}
+
+/// Everything a chunk download task needs, shared by a reader and its tasks
+struct ChunkSource {
+    cdn: Arc<CdnClient>,
+    /// URL of the archive or loose file holding the chunks
+    url: String,
+    ekey: Vec<u8>,
+    spans: Vec<ChunkSpan>,
+    /// Check chunks against the MD5s in the chunk table
+    verify: bool,
+    key_store: Option<Arc<TactKeyStore>>,
+    cache: Arc<ChunkCache>,
+}
+
+impl ChunkSource {
+    /// Download `len` bytes at `offset`, retrying like a full download
+    ///
+    /// A server ignoring the range is answered by downloading the whole file
+    /// and cutting the range out of it.
+    async fn fetch_range(&self, offset: u64, len: u64) -> Result<Vec<u8>> {
+        if let Some(data) = self
+            .cdn
+            .download_chunk(&self.url, offset, offset + len)
+            .await?
+        {
+            return Ok(data);
+        }
+        let data = self.cdn.download_with_retry(&self.url, None).await?;
+        let end = offset + len;
+        if (data.len() as u64) < end {
+            return Err(ProtocolError::IncompleteRange {
+                expected: len,
+                received: (data.len() as u64).saturating_sub(offset),
+            });
+        }
+        Ok(data[offset as usize..end as usize].to_vec())
+    }
+
+    /// Download, verify and decompress chunk `index`, caching the result
+    async fn fetch_chunk(self: Arc<Self>, index: usize) -> Result<Arc<Vec<u8>>> {
+        let span = self.spans[index];
+        let raw = self.fetch_range(span.offset, span.compressed_size).await?;
+        if self.verify && span.checksum != [0; 16] {
+            let actual = md5::compute(&raw).0;
+            if actual != span.checksum {
+                return Err(ProtocolError::ChecksumMismatch {
+                    expected: hex::encode(span.checksum),
+                    actual: hex::encode(actual),
+                });
+            }
+        }
+
+        let data = Arc::new(decode_chunk(
+            &raw,
+            index,
+            Some(span.decompressed_size),
+            self.key_store.as_deref(),
+        )?);
+        self.cache.insert(&self.ekey, index, Arc::clone(&data));
+        Ok(data)
+    }
+
+    /// Index of the chunk holding decompressed byte `position`
+    fn chunk_at(&self, position: u64) -> usize {
+        self.spans
+            .partition_point(|span| span.start + span.decompressed_size <= position)
+    }
+}
+
+/// Decompressed chunks by encoding key and chunk index, evicted oldest first
+struct ChunkCache {
+    capacity: usize,
+    state: Mutex<ChunkCacheState>,
+}
+
+#[derive(Default)]
+struct ChunkCacheState {
+    chunks: HashMap<(Vec<u8>, usize), Arc<Vec<u8>>>,
+    order: VecDeque<(Vec<u8>, usize)>,
+    size: usize,
+}
+
+impl ChunkCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(ChunkCacheState::default()),
+        }
+    }
+
+    fn get(&self, ekey: &[u8], index: usize) -> Option<Arc<Vec<u8>>> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.chunks.get(&(ekey.to_vec(), index)).cloned()
+    }
+
+    fn insert(&self, ekey: &[u8], index: usize, data: Arc<Vec<u8>>) {
+        if data.len() > self.capacity {
+            return;
+        }
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let key = (ekey.to_vec(), index);
+        if state.chunks.contains_key(&key) {
+            return;
+        }
+        while state.size + data.len() > self.capacity {
+            let Some(oldest) = state.order.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = state.chunks.remove(&oldest) {
+                state.size -= evicted.len();
+            }
+        }
+        state.size += data.len();
+        state.order.push_back(key.clone());
+        state.chunks.insert(key, data);
+    }
+
+    fn size(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .size
+    }
+}
+
+/// Reader over a CDN file that downloads its chunks as it advances
+///
+/// Implements [`AsyncRead`] and [`AsyncSeek`] over the decompressed
+/// content. Reading needs a Tokio runtime, since chunk downloads run as
+/// spawned tasks; they are aborted when the reader is dropped or seeks away
+/// from them.
+pub struct ProgressiveReader {
+    source: Arc<ChunkSource>,
+    prefetch_depth: usize,
+    position: u64,
+    len: u64,
+    /// The chunk under `position`, once it has arrived
+    current: Option<(usize, Arc<Vec<u8>>)>,
+    /// Download tasks by chunk index
+    in_flight: BTreeMap<usize, JoinHandle<Result<Arc<Vec<u8>>>>>,
+}
+
+impl std::fmt::Debug for ProgressiveReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressiveReader")
+            .field("ekey", &hex::encode(&self.source.ekey))
+            .field("position", &self.position)
+            .field("len", &self.len)
+            .field("chunks", &self.source.spans.len())
+            .field("in_flight", &self.in_flight.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProgressiveReader {
+    /// Size of the decompressed content
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the decompressed content is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of BLTE chunks in the file
+    pub fn chunk_count(&self) -> usize {
+        self.source.spans.len()
+    }
+
+    /// Current read position in the decompressed content
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Make sure chunks `first..first + prefetch_depth` are cached or
+    /// downloading, aborting downloads outside that window
+    fn schedule(&mut self, first: usize) {
+        let window = first..(first + self.prefetch_depth).min(self.source.spans.len());
+        self.in_flight.retain(|index, handle| {
+            let keep = window.contains(index);
+            if !keep {
+                handle.abort();
+            }
+            keep
+        });
+        for index in window {
+            if !self.in_flight.contains_key(&index)
+                && self.source.cache.get(&self.source.ekey, index).is_none()
+            {
+                let source = Arc::clone(&self.source);
+                self.in_flight
+                    .insert(index, tokio::spawn(source.fetch_chunk(index)));
+            }
+        }
+    }
+
+    /// Poll for the decompressed data of chunk `index`
+    fn poll_chunk(&mut self, index: usize, cx: &mut Context<'_>) -> Poll<io::Result<Arc<Vec<u8>>>> {
+        if let Some(data) = self.source.cache.get(&self.source.ekey, index) {
+            self.in_flight.remove(&index);
+            self.schedule(index);
+            return Poll::Ready(Ok(data));
+        }
+
+        self.schedule(index);
+        let Some(handle) = self.in_flight.get_mut(&index) else {
+            // Scheduling found the chunk cached after all
+            return self.poll_chunk(index, cx);
+        };
+        match Pin::new(handle).poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(result) => {
+                self.in_flight.remove(&index);
+                Poll::Ready(match result {
+                    Ok(Ok(data)) => Ok(data),
+                    Ok(Err(e)) => Err(io::Error::other(e)),
+                    Err(e) => Err(io::Error::other(e)),
+                })
+            }
+        }
+    }
+}
+
+impl AsyncRead for ProgressiveReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        if this.position >= this.len || buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let index = this.source.chunk_at(this.position);
+        let data = match &this.current {
+            Some((current, data)) if *current == index => Arc::clone(data),
+            _ => match this.poll_chunk(index, cx) {
+                Poll::Ready(Ok(data)) => {
+                    this.current = Some((index, Arc::clone(&data)));
+                    data
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            },
+        };
+
+        let start = (this.position - this.source.spans[index].start) as usize;
+        let n = buf.remaining().min(data.len() - start);
+        buf.put_slice(&data[start..start + n]);
+        this.position += n as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for ProgressiveReader {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let target = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+        self.position = target.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.position))
+    }
+}
+
+impl Drop for ProgressiveReader {
+    fn drop(&mut self) {
+        for handle in self.in_flight.values() {
+            handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::cache::ProtocolCache;
+    use crate::config::{CacheConfig, CdnConfig};
+    use crate::retry::RetryPolicy;
+    use cascette_formats::CascFormat;
+    use cascette_formats::archive::ArchiveIndexBuilder;
+    use cascette_formats::blte::BlteFile;
+    use std::io::Cursor;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+    /// Mock CDN file honoring `Range`, recording each requested range
+    struct RangedFile {
+        body: Vec<u8>,
+        ranges: Arc<Mutex<Vec<(usize, usize)>>>,
+    }
+
+    impl Respond for RangedFile {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let range = request
+                .headers
+                .get("range")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("bytes="))
+                .and_then(|v| v.split_once('-'))
+                .and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)));
+            match range {
+                Some((start, end)) => {
+                    self.ranges.lock().expect("ranges").push((start, end));
+                    ResponseTemplate::new(206).set_body_bytes(self.body[start..=end].to_vec())
+                }
+                None => ResponseTemplate::new(200).set_body_bytes(self.body.clone()),
+            }
+        }
+    }
+
+    fn content(seed: u8, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| ((i / 7) as u8).wrapping_mul(13) ^ seed)
+            .collect()
+    }
+
+    fn blte(data: &[u8], chunk_size: usize) -> Vec<u8> {
+        BlteFile::compress(data, chunk_size, CompressionMode::ZLib)
+            .expect("Operation should succeed")
+            .build()
+            .expect("Operation should succeed")
+    }
+
+    fn data_path(key: &str) -> String {
+        format!("/tpr/wow/data/{}/{}/{key}", &key[..2], &key[2..4])
+    }
+
+    /// Serve an archive of `files`, returning its key, the files' encoding
+    /// keys and the recorded range requests
+    async fn serve_archive(
+        server: &MockServer,
+        files: &[Vec<u8>],
+    ) -> (String, Vec<[u8; 16]>, Arc<Mutex<Vec<(usize, usize)>>>) {
+        // Padding in front of the first file keeps offsets non-zero
+        let mut data = vec![0xAA; 100];
+        let mut ekeys = Vec::new();
+        let mut builder = ArchiveIndexBuilder::new();
+        for file in files {
+            let ekey = md5::compute(file).0;
+            builder.add_entry(ekey.to_vec(), file.len() as u32, data.len() as u64);
+            data.extend_from_slice(file);
+            ekeys.push(ekey);
+        }
+        let mut index = Cursor::new(Vec::new());
+        let built = builder.build(&mut index).expect("Operation should succeed");
+        let key = hex::encode(built.footer.name_hash());
+
+        Mock::given(method("GET"))
+            .and(path(format!("{}.index", data_path(&key))))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(index.into_inner()))
+            .mount(server)
+            .await;
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        Mock::given(method("GET"))
+            .and(path(data_path(&key)))
+            .respond_with(RangedFile {
+                body: data,
+                ranges: Arc::clone(&ranges),
+            })
+            .mount(server)
+            .await;
+        (key, ekeys, ranges)
+    }
+
+    fn client(temp_dir: &TempDir) -> Arc<CdnClient> {
+        let cache = ProtocolCache::new(&CacheConfig {
+            cache_dir: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .expect("Operation should succeed");
+        let config = CdnConfig::default().with_retry_policy(RetryPolicy {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            multiplier: 1.0,
+            jitter: false,
+            ..RetryPolicy::default()
+        });
+        Arc::new(CdnClient::new(Arc::new(cache), config).expect("Operation should succeed"))
+    }
+
+    fn endpoint(server: &MockServer) -> CdnEndpoint {
+        CdnEndpoint {
+            host: server.uri().replace("http://", ""),
+            path: "tpr/wow".to_string(),
+            product_path: None,
+            scheme: Some("http".to_string()),
+            is_fallback: false,
+            strict: false,
+            max_hosts: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streams_chunks_on_demand() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let other = content(1, 3000);
+        let file = content(2, 16_000);
+        let (key, ekeys, ranges) =
+            serve_archive(&server, &[blte(&other, 1000), blte(&file, 1000)]).await;
+
+        let manager =
+            ProgressiveFileManager::new(endpoint(&server), vec![key]).with_prefetch_depth(2);
+        let mut reader = manager
+            .open_streaming(&ekeys[1], client(&temp_dir))
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(reader.len(), 16_000);
+        assert_eq!(reader.chunk_count(), 16);
+        assert_eq!(ranges.lock().expect("ranges").len(), 1, "header only");
+
+        let mut start = [0; 100];
+        reader
+            .read_exact(&mut start)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(start.as_slice(), &file[..100]);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        // The header and the prefetch window of two chunks
+        assert_eq!(ranges.lock().expect("ranges").len(), 3);
+
+        let mut rest = Vec::new();
+        reader
+            .read_to_end(&mut rest)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(rest, file[100..]);
+        assert_eq!(ranges.lock().expect("ranges").len(), 17);
+    }
+
+    #[tokio::test]
+    async fn test_seeking_back_reuses_cached_chunks() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let file = content(3, 10_000);
+        let (key, ekeys, ranges) = serve_archive(&server, &[blte(&file, 1024)]).await;
+        let cdn = client(&temp_dir);
+
+        let manager = ProgressiveFileManager::new(endpoint(&server), vec![key]);
+        let mut reader = manager
+            .open_streaming(&ekeys[0], Arc::clone(&cdn))
+            .await
+            .expect("Operation should succeed");
+        let mut data = Vec::new();
+        reader
+            .read_to_end(&mut data)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(data, file);
+        assert_eq!(manager.cached_bytes(), file.len());
+        let requests = ranges.lock().expect("ranges").len();
+
+        assert_eq!(
+            reader
+                .seek(SeekFrom::Start(2500))
+                .await
+                .expect("Operation should succeed"),
+            2500
+        );
+        let mut middle = vec![0; 3000];
+        reader
+            .read_exact(&mut middle)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(middle, file[2500..5500]);
+
+        assert_eq!(
+            reader
+                .seek(SeekFrom::End(-10))
+                .await
+                .expect("Operation should succeed"),
+            9990
+        );
+        let mut tail = Vec::new();
+        reader
+            .read_to_end(&mut tail)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(tail, file[9990..]);
+        assert!(reader.seek(SeekFrom::Current(-20_000)).await.is_err());
+
+        // A second reader of the same file only downloads the header again
+        let mut again = manager
+            .open_streaming(&ekeys[0], cdn)
+            .await
+            .expect("Operation should succeed");
+        let mut data = Vec::new();
+        again
+            .read_to_end(&mut data)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(data, file);
+        assert_eq!(ranges.lock().expect("ranges").len(), requests + 1);
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_chunk_fails_read() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let file = content(4, 4000);
+        let mut encoded = blte(&file, 1000);
+        let len = encoded.len();
+        encoded[len - 3] ^= 0xFF;
+        let (key, ekeys, _) = serve_archive(&server, &[encoded]).await;
+
+        let manager = ProgressiveFileManager::new(endpoint(&server), vec![key]);
+        let mut reader = manager
+            .open_streaming(&ekeys[0], client(&temp_dir))
+            .await
+            .expect("Operation should succeed");
+
+        let mut start = vec![0; 3000];
+        reader
+            .read_exact(&mut start)
+            .await
+            .expect("Intact chunks should be readable");
+        assert_eq!(start, file[..3000]);
+        let err = reader
+            .read_to_end(&mut Vec::new())
+            .await
+            .expect_err("Corrupt chunk should fail");
+        assert!(err.to_string().contains("Checksum mismatch"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_loose_chunkless_file() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let (key, _, _) = serve_archive(&server, &[blte(&content(5, 100), 1000)]).await;
+
+        let file = content(6, 500);
+        let encoded = blte(&file, 1000);
+        let ekey = md5::compute(&encoded).0;
+        let loose_path = data_path(&hex::encode(ekey));
+        Mock::given(method("HEAD"))
+            .and(path(loose_path.as_str()))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-length", encoded.len().to_string().as_str()),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(loose_path.as_str()))
+            .respond_with(RangedFile {
+                body: encoded,
+                ranges: Arc::default(),
+            })
+            .mount(&server)
+            .await;
+
+        let manager = ProgressiveFileManager::new(endpoint(&server), vec![key]);
+        let mut reader = manager
+            .open_streaming(&ekey, client(&temp_dir))
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(reader.chunk_count(), 1);
+        let mut data = Vec::new();
+        reader
+            .read_to_end(&mut data)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(data, file);
+
+        let err = manager
+            .open_streaming(&[0x55; 16], client(&temp_dir))
+            .await
+            .expect_err("Unknown key should fail");
+        assert!(matches!(err, ProtocolError::NotFound(_)), "{err}");
+    }
+
+    #[test]
+    fn test_parse_chunk_table_rejects_bad_headers() {
+        let encoded = blte(&content(7, 3000), 1000);
+        let ChunkTable::Spans(spans) =
+            parse_chunk_table(&encoded, 50, encoded.len() as u64).expect("valid header")
+        else {
+            unreachable!("Multi-chunk file should have a chunk table");
+        };
+        assert_eq!(spans.len(), 3);
+        assert_eq!(spans[0].offset, 50 + 12 + 3 * 24);
+        assert_eq!(spans[2].start, 2000);
+
+        assert!(matches!(
+            parse_chunk_table(&encoded[..20], 0, encoded.len() as u64),
+            Ok(ChunkTable::TooShort(84))
+        ));
+        assert!(parse_chunk_table(b"BLTX\0\0\0\0", 0, 8).is_err());
+        // Chunks extending past the archive entry
+        assert!(parse_chunk_table(&encoded, 0, 100).is_err());
+    }
+}
@@ -0,0 +1,908 @@
+//! Resumable downloads with persistent session files
+//!
+//! A [`ResumableDownload`] lists the files of a download: the URLs each one
+//! can be fetched from (tried in order), its output path and its expected
+//! MD5. [`DownloadManager`] runs the downloads and keeps the session file
+//! up to date, so an interrupted run can be picked up where it stopped:
+//!
+//! ```text
+//! {
+//!   "chunk_size": 1048576,
+//!   "files": [
+//!     {
+//!       "urls": ["http://cdn.example.com/tpr/wow/data/00/17/0017a402..."],
+//!       "output": "downloads/0017a402f556fbece46c38dc431a2c9b",
+//!       "md5": "0017a402f556fbece46c38dc431a2c9b",
+//!       "size": null,
+//!       "chunks": ["5d41402abc4b2a76b9719d911017c592"],
+//!       "complete": false
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Progress is recorded per chunk of `chunk_size` bytes: each chunk's MD5
+//! is added to the session once it is written. The session file is saved
+//! at most once a second while chunks come in, and whenever a file starts
+//! over or completes; each save replaces it atomically and syncs it to
+//! disk. [`ResumableDownload::load`] checks the chunks of unfinished files
+//! against their output and truncates each file to its last verified chunk,
+//! so a corrupted or half-written tail is downloaded again instead of ending
+//! up in the result. Resumed files continue with a `Range` request; servers
+//! answering it with `200 OK` restart the file.
+//!
+//! Finished files are verified against their MD5 before they are marked
+//! complete, and again when a session is resumed, so complete files are
+//! only skipped while they are intact.
+
+use std::io::{ErrorKind, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use futures::stream;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+use super::{error_for_status, url_host};
+use crate::error::{ProtocolError, Result};
+use crate::retry::RetryPolicy;
+use crate::transport::HttpClient;
+
+/// Default chunk size at which progress is recorded (1 MiB)
+pub const DEFAULT_CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// Minimum time between saves that only record new chunks
+const CHUNK_SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// One file of a [`ResumableDownload`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionFile {
+    /// URLs serving the file, tried in order
+    pub urls: Vec<String>,
+    /// Where the file is written
+    pub output: PathBuf,
+    /// Expected MD5 of the whole file as lowercase hex
+    pub md5: String,
+    /// Size of the file, once it is complete
+    pub size: Option<u64>,
+    /// MD5 of each chunk written so far, as lowercase hex
+    pub chunks: Vec<String>,
+    /// Whether the file was downloaded and verified
+    pub complete: bool,
+}
+
+/// Files of a download and their progress, saved as a JSON session file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumableDownload {
+    /// Bytes per recorded chunk
+    pub chunk_size: u64,
+    /// Files to download
+    pub files: Vec<SessionFile>,
+}
+
+impl Default for ResumableDownload {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHUNK_SIZE)
+    }
+}
+
+impl ResumableDownload {
+    /// Create an empty session recording progress every `chunk_size` bytes
+    ///
+    /// A chunk size of zero is treated as one byte.
+    pub fn new(chunk_size: u64) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+            files: Vec::new(),
+        }
+    }
+
+    /// Add a file served at `urls`, written to `output`, with MD5 `md5`
+    pub fn add_file(&mut self, urls: Vec<String>, output: impl Into<PathBuf>, md5: [u8; 16]) {
+        self.files.push(SessionFile {
+            urls,
+            output: output.into(),
+            md5: hex::encode(md5),
+            size: None,
+            chunks: Vec::new(),
+            complete: false,
+        });
+    }
+
+    /// Check whether every file is complete
+    pub fn is_complete(&self) -> bool {
+        self.files.iter().all(|file| file.complete)
+    }
+
+    /// Load a session file and validate the partial files it lists
+    ///
+    /// Each unfinished file keeps the leading chunks whose data still
+    /// matches the recorded MD5 and is truncated after the last of them.
+    /// Complete files are left alone; [`DownloadManager`] verifies them
+    /// when it resumes the session.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::Network` if a file cannot be read or
+    /// truncated and `ProtocolError::Parse` if the session is invalid.
+    pub async fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path).await?;
+        let mut session: Self = serde_json::from_slice(&data)
+            .map_err(|e| ProtocolError::Parse(format!("Invalid download session: {e}")))?;
+        if session.chunk_size == 0 {
+            return Err(ProtocolError::Parse(
+                "Invalid download session: chunk size is zero".to_string(),
+            ));
+        }
+
+        let chunk_size = session.chunk_size;
+        for file in session.files.iter_mut().filter(|file| !file.complete) {
+            let verified = verified_chunks(file, chunk_size).await?;
+            if verified < file.chunks.len() {
+                tracing::warn!(
+                    "{}: chunk {} does not match the session, resuming from there",
+                    file.output.display(),
+                    verified
+                );
+                file.chunks.truncate(verified);
+            }
+        }
+        Ok(session)
+    }
+
+    /// Write the session to `path`, replacing the previous file atomically
+    ///
+    /// The new file is synced before it replaces the old one, and its
+    /// directory after, so a crash leaves either session intact.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::Network` if the file cannot be written.
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_vec_pretty(self)
+            .map_err(|e| ProtocolError::Other(format!("Failed to encode session: {e}")))?;
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        let mut file = fs::File::create(&temp).await?;
+        file.write_all(&data).await?;
+        file.sync_all().await?;
+        drop(file);
+        fs::rename(&temp, path).await?;
+        sync_directory(path).await;
+        Ok(())
+    }
+}
+
+/// Flush the directory holding `path` so a rename survives a crash
+///
+/// Best effort: a failure only weakens durability, and Windows offers no
+/// way to sync a directory handle.
+async fn sync_directory(path: &Path) {
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let synced = match fs::File::open(dir).await {
+            Ok(dir) => dir.sync_all().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = synced {
+            tracing::warn!("Failed to sync directory {}: {}", dir.display(), e);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// The session of a run, shared by its downloads, and its session file
+struct SessionStore<'a> {
+    path: &'a Path,
+    state: Mutex<SessionState>,
+    /// Version of the session last saved, held while saving
+    saved: Mutex<u64>,
+}
+
+struct SessionState {
+    session: ResumableDownload,
+    /// Incremented on every change
+    version: u64,
+    last_save: Instant,
+}
+
+impl<'a> SessionStore<'a> {
+    /// Share `session`, already saved to `path`
+    fn new(session: ResumableDownload, path: &'a Path) -> Self {
+        Self {
+            path,
+            state: Mutex::new(SessionState {
+                session,
+                version: 0,
+                last_save: Instant::now(),
+            }),
+            saved: Mutex::new(0),
+        }
+    }
+
+    /// Current state of one file
+    async fn file(&self, index: usize) -> SessionFile {
+        self.state.lock().await.session.files[index].clone()
+    }
+
+    /// Chunk size of the session
+    async fn chunk_size(&self) -> u64 {
+        self.state.lock().await.session.chunk_size
+    }
+
+    /// Change one file of the session and save it
+    ///
+    /// Unless `force` is set, the save is skipped when the session was
+    /// saved less than [`CHUNK_SAVE_INTERVAL`] ago; a later save or
+    /// [`flush`](Self::flush) writes the change.
+    async fn update(
+        &self,
+        index: usize,
+        force: bool,
+        change: impl FnOnce(&mut SessionFile),
+    ) -> Result<()> {
+        let snapshot = {
+            let mut state = self.state.lock().await;
+            change(&mut state.session.files[index]);
+            state.version += 1;
+            if !force && state.last_save.elapsed() < CHUNK_SAVE_INTERVAL {
+                return Ok(());
+            }
+            state.last_save = Instant::now();
+            (state.version, state.session.clone())
+        };
+        self.save(snapshot).await
+    }
+
+    /// Save the session if it changed since the last save
+    async fn flush(&self) -> Result<()> {
+        let snapshot = {
+            let state = self.state.lock().await;
+            (state.version, state.session.clone())
+        };
+        self.save(snapshot).await
+    }
+
+    /// Write a snapshot of the session unless a newer one was written
+    ///
+    /// Encoding and writing happen outside the session lock, so downloads
+    /// keep going while the file is saved.
+    async fn save(&self, (version, session): (u64, ResumableDownload)) -> Result<()> {
+        let mut saved = self.saved.lock().await;
+        if *saved >= version {
+            return Ok(());
+        }
+        session.save(self.path).await?;
+        *saved = version;
+        drop(saved);
+        Ok(())
+    }
+}
+
+/// Count the leading chunks of `file` that match the session, and truncate
+/// its output after them
+async fn verified_chunks(file: &SessionFile, chunk_size: u64) -> Result<usize> {
+    let mut output = match fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&file.output)
+        .await
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+
+    let available = output.metadata().await?.len() / chunk_size;
+    let mut buffer = vec![0u8; usize::try_from(chunk_size).unwrap_or(usize::MAX)];
+    let mut verified = 0;
+    for expected in file
+        .chunks
+        .iter()
+        .take(usize::try_from(available).unwrap_or(usize::MAX))
+    {
+        output.read_exact(&mut buffer).await?;
+        if hex::encode(md5::compute(&buffer).0) != *expected {
+            break;
+        }
+        verified += 1;
+    }
+
+    output.set_len(verified as u64 * chunk_size).await?;
+    Ok(verified)
+}
+
+/// MD5 of the file at `path` as lowercase hex
+async fn file_md5(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path).await?;
+    let mut context = md5::Context::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        context.consume(&buffer[..read]);
+    }
+    Ok(hex::encode(context.finalize().0))
+}
+
+/// Outcome of a [`DownloadManager`] run
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DownloadReport {
+    /// Files downloaded and verified by this run
+    pub downloaded: Vec<PathBuf>,
+    /// Files skipped because an earlier run completed them
+    pub skipped: Vec<PathBuf>,
+    /// Files that failed, with the error of their last attempt
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl DownloadReport {
+    /// Check whether every file of the session is now complete
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Runs the files of a [`ResumableDownload`] concurrently
+#[derive(Clone)]
+pub struct DownloadManager {
+    http_client: HttpClient,
+    retry_policy: RetryPolicy,
+    max_concurrent: usize,
+}
+
+impl std::fmt::Debug for DownloadManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DownloadManager")
+            .field("retry_policy", &self.retry_policy)
+            .field("max_concurrent", &self.max_concurrent)
+            .finish_non_exhaustive()
+    }
+}
+
+/// What happened to one file of a run
+enum FileOutcome {
+    Downloaded,
+    Skipped,
+}
+
+impl DownloadManager {
+    /// Create a manager running 4 downloads at once with the default retry
+    /// policy
+    pub fn new(http_client: HttpClient) -> Self {
+        Self {
+            http_client,
+            retry_policy: RetryPolicy::default(),
+            max_concurrent: 4,
+        }
+    }
+
+    /// Set the retry policy of each URL
+    #[must_use]
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set how many files are downloaded at once (at least one)
+    #[must_use]
+    pub fn with_max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent = max_concurrent.max(1);
+        self
+    }
+
+    /// Load the session at `session_path` and run it to completion
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session cannot be loaded or saved.
+    pub async fn resume(&self, session_path: &Path) -> Result<DownloadReport> {
+        let session = ResumableDownload::load(session_path).await?;
+        self.run(session, session_path).await
+    }
+
+    /// Download the files of `session`, saving progress to `session_path`
+    ///
+    /// The session file is saved when the run starts and ends, whenever a
+    /// file starts over or completes, and at most once a second as chunks
+    /// are written. Complete files that still match their MD5 are skipped. Every other
+    /// file is downloaded from its URLs in order, each with the retry
+    /// policy, resuming after its recorded chunks. A file that still fails
+    /// is reported in [`DownloadReport::failed`] without stopping the
+    /// others, and keeps its progress for the next run.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::Network` if the session file cannot be
+    /// written.
+    pub async fn run(
+        &self,
+        session: ResumableDownload,
+        session_path: &Path,
+    ) -> Result<DownloadReport> {
+        session.save(session_path).await?;
+
+        let count = session.files.len();
+        let store = SessionStore::new(session, session_path);
+        let store = &store;
+
+        let mut results = stream::iter(0..count)
+            .map(|index| async move {
+                let output = store.file(index).await.output;
+                let result = self.download_file(store, index).await;
+                (output, result)
+            })
+            .buffer_unordered(self.max_concurrent);
+
+        let mut report = DownloadReport::default();
+        while let Some((output, result)) = results.next().await {
+            match result {
+                Ok(FileOutcome::Downloaded) => {
+                    tracing::info!("Downloaded {}", output.display());
+                    report.downloaded.push(output);
+                }
+                Ok(FileOutcome::Skipped) => report.skipped.push(output),
+                Err(e) => {
+                    tracing::warn!("Failed to download {}: {}", output.display(), e);
+                    report.failed.push((output, e.to_string()));
+                }
+            }
+        }
+        drop(results);
+        store.flush().await?;
+        Ok(report)
+    }
+
+    /// Verify or download one file of the session
+    async fn download_file(&self, store: &SessionStore<'_>, index: usize) -> Result<FileOutcome> {
+        let file = store.file(index).await;
+        if file.complete {
+            if file_md5(&file.output).await.ok().as_deref() == Some(file.md5.as_str()) {
+                return Ok(FileOutcome::Skipped);
+            }
+            tracing::warn!(
+                "{} changed since it was completed, downloading it again",
+                file.output.display()
+            );
+            store
+                .update(index, true, |file| {
+                    file.complete = false;
+                    file.size = None;
+                    file.chunks.clear();
+                })
+                .await?;
+        }
+
+        if let Some(dir) = file.output.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+
+        let mut last_error = None;
+        for url in &file.urls {
+            let result = self
+                .retry_policy
+                .execute_for_host(url_host(url), || self.fetch(store, index, url))
+                .await;
+            match result {
+                Ok(()) => return Ok(FileOutcome::Downloaded),
+                Err(e) => {
+                    tracing::warn!("{url} failed for {}: {e}", file.output.display());
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            ProtocolError::InvalidEndpoint(format!("no URLs for {}", file.output.display()))
+        }))
+    }
+
+    /// Download the rest of one file from `url` and verify it
+    async fn fetch(&self, store: &SessionStore<'_>, index: usize, url: &str) -> Result<()> {
+        let chunk_size = store.chunk_size().await;
+        let entry = store.file(index).await;
+        let output = entry.output;
+        let mut offset = entry.chunks.len() as u64 * chunk_size;
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&output)
+            .await?;
+        // Drop anything written after the last recorded chunk
+        file.set_len(offset).await?;
+
+        let mut request = self.http_client.inner().get(url);
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={offset}-"));
+        }
+        let response = request.send().await?;
+
+        match response.status() {
+            reqwest::StatusCode::PARTIAL_CONTENT => {}
+            // The recorded chunks already hold the whole file
+            reqwest::StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+                drop(response);
+                return finish(store, index, &file).await;
+            }
+            status if status.is_success() => {
+                if offset > 0 {
+                    offset = 0;
+                    file.set_len(0).await?;
+                    store
+                        .update(index, true, |file| file.chunks.clear())
+                        .await?;
+                }
+            }
+            _ => return Err(error_for_status(&response)),
+        }
+        file.seek(SeekFrom::Start(offset)).await?;
+
+        let mut chunk = md5::Context::new();
+        let mut chunk_len = 0u64;
+        let mut body = response.bytes_stream();
+        while let Some(data) = body.next().await {
+            // A dropped connection is transient, so the retry resumes after
+            // the last recorded chunk
+            let data = data.map_err(|e| ProtocolError::Network(std::io::Error::other(e)))?;
+            let mut rest = &data[..];
+            while !rest.is_empty() {
+                let room = usize::try_from(chunk_size - chunk_len).unwrap_or(usize::MAX);
+                let (head, tail) = rest.split_at(room.min(rest.len()));
+                file.write_all(head).await?;
+                chunk.consume(head);
+                chunk_len += head.len() as u64;
+                rest = tail;
+
+                if chunk_len == chunk_size {
+                    file.flush().await?;
+                    let digest = hex::encode(
+                        std::mem::replace(&mut chunk, md5::Context::new())
+                            .finalize()
+                            .0,
+                    );
+                    store
+                        .update(index, false, |file| file.chunks.push(digest))
+                        .await?;
+                    chunk_len = 0;
+                }
+            }
+        }
+
+        finish(store, index, &file).await
+    }
+}
+
+/// Verify a fully written file and mark it complete
+///
+/// A file with the wrong MD5 is truncated and its chunks are dropped, so
+/// the retry downloads it from the start.
+async fn finish(store: &SessionStore<'_>, index: usize, file: &fs::File) -> Result<()> {
+    file.sync_all().await?;
+    let entry = store.file(index).await;
+    let actual = file_md5(&entry.output).await?;
+    if actual != entry.md5 {
+        file.set_len(0).await?;
+        store
+            .update(index, true, |file| file.chunks.clear())
+            .await?;
+        return Err(ProtocolError::ChecksumMismatch {
+            expected: entry.md5,
+            actual,
+        });
+    }
+
+    let size = file.metadata().await?.len();
+    store
+        .update(index, true, |file| {
+            file.complete = true;
+            file.size = Some(size);
+        })
+        .await
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+    const CHUNK: u64 = 64;
+
+    /// Serves `data`, honouring `Range: bytes=N-` requests
+    struct RangeResponder(Vec<u8>);
+
+    impl Respond for RangeResponder {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let start = request
+                .headers
+                .get("range")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("bytes="))
+                .and_then(|value| value.strip_suffix('-'))
+                .and_then(|value| value.parse::<usize>().ok());
+            match start {
+                None => ResponseTemplate::new(200).set_body_bytes(self.0.clone()),
+                Some(start) if start >= self.0.len() => ResponseTemplate::new(416),
+                Some(start) => ResponseTemplate::new(206).set_body_bytes(self.0[start..].to_vec()),
+            }
+        }
+    }
+
+    fn content(seed: u8, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| (i as u8).wrapping_mul(31) ^ seed)
+            .collect()
+    }
+
+    fn manager() -> DownloadManager {
+        DownloadManager::new(HttpClient::new().expect("Operation should succeed"))
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 2,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(1),
+                multiplier: 1.0,
+                jitter: false,
+                ..RetryPolicy::default()
+            })
+            .with_max_concurrent(2)
+    }
+
+    /// Range headers of the requests `server` received for `file_path`
+    async fn ranges(server: &MockServer, file_path: &str) -> Vec<Option<String>> {
+        server
+            .received_requests()
+            .await
+            .expect("Recording should be enabled")
+            .iter()
+            .filter(|request| request.url.path() == file_path)
+            .map(|request| {
+                request
+                    .headers
+                    .get("range")
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_download_resumes_to_completion() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let session_path = temp_dir.path().join("session.json");
+        let first = content(1, 1000);
+        let second = content(2, 700);
+
+        Mock::given(method("GET"))
+            .and(path("/first"))
+            .respond_with(RangeResponder(first.clone()))
+            .mount(&server)
+            .await;
+        // The second file's server goes away
+        let outage = Mock::given(method("GET"))
+            .and(path("/second"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount_as_scoped(&server)
+            .await;
+
+        let mut session = ResumableDownload::new(CHUNK);
+        session.add_file(
+            vec![format!("{}/first", server.uri())],
+            temp_dir.path().join("out/first"),
+            md5::compute(&first).0,
+        );
+        session.add_file(
+            vec![format!("{}/second", server.uri())],
+            temp_dir.path().join("out/second"),
+            md5::compute(&second).0,
+        );
+
+        let report = manager()
+            .run(session, &session_path)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(report.downloaded, [temp_dir.path().join("out/first")]);
+        assert_eq!(report.failed.len(), 1);
+        drop(outage);
+
+        // The process was killed in the middle of the second file: five
+        // chunks were recorded, the fifth was corrupted on disk, and a
+        // half-written sixth follows
+        let mut session = ResumableDownload::load(&session_path)
+            .await
+            .expect("Operation should succeed");
+        assert!(session.files[0].complete);
+        assert!(!session.is_complete());
+        let mut written = second[..5 * CHUNK as usize + 10].to_vec();
+        written[4 * CHUNK as usize] ^= 0xFF;
+        std::fs::write(temp_dir.path().join("out/second"), &written)
+            .expect("Operation should succeed");
+        session.files[1].chunks = second
+            .chunks(CHUNK as usize)
+            .take(5)
+            .map(|chunk| hex::encode(md5::compute(chunk).0))
+            .collect();
+        session
+            .save(&session_path)
+            .await
+            .expect("Operation should succeed");
+
+        let loaded = ResumableDownload::load(&session_path)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(loaded.files[1].chunks.len(), 4);
+        assert_eq!(
+            std::fs::metadata(temp_dir.path().join("out/second"))
+                .expect("Operation should succeed")
+                .len(),
+            4 * CHUNK
+        );
+
+        Mock::given(method("GET"))
+            .and(path("/second"))
+            .respond_with(RangeResponder(second.clone()))
+            .mount(&server)
+            .await;
+        let report = manager()
+            .resume(&session_path)
+            .await
+            .expect("Operation should succeed");
+        assert!(report.is_complete(), "{:?}", report.failed);
+        assert_eq!(report.skipped, [temp_dir.path().join("out/first")]);
+        assert_eq!(report.downloaded, [temp_dir.path().join("out/second")]);
+
+        assert_eq!(
+            std::fs::read(temp_dir.path().join("out/first")).expect("first"),
+            first
+        );
+        assert_eq!(
+            std::fs::read(temp_dir.path().join("out/second")).expect("second"),
+            second
+        );
+        // The first file was fetched once; the second resumed after chunk 4
+        assert_eq!(ranges(&server, "/first").await, [None]);
+        assert_eq!(
+            ranges(&server, "/second").await.last(),
+            Some(&Some(format!("bytes={}-", 4 * CHUNK)))
+        );
+
+        let session = ResumableDownload::load(&session_path)
+            .await
+            .expect("Operation should succeed");
+        assert!(session.is_complete());
+        assert_eq!(session.files[1].size, Some(700));
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_complete_file_and_bad_content_are_downloaded_again() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let session_path = temp_dir.path().join("session.json");
+        let data = content(3, 300);
+        let output = temp_dir.path().join("file");
+
+        // The first mirror serves the wrong content, the second the right one
+        Mock::given(method("GET"))
+            .and(path("/bad"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(content(4, 300)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/good"))
+            .respond_with(RangeResponder(data.clone()))
+            .mount(&server)
+            .await;
+
+        let mut session = ResumableDownload::new(CHUNK);
+        session.add_file(
+            vec![
+                format!("{}/bad", server.uri()),
+                format!("{}/good", server.uri()),
+            ],
+            &output,
+            md5::compute(&data).0,
+        );
+        let report = manager()
+            .run(session, &session_path)
+            .await
+            .expect("Operation should succeed");
+        assert!(report.is_complete(), "{:?}", report.failed);
+        assert_eq!(std::fs::read(&output).expect("file"), data);
+        // The first attempt and two retries
+        assert_eq!(ranges(&server, "/bad").await.len(), 3);
+
+        // A complete file damaged after the run is not skipped
+        std::fs::write(&output, b"damaged").expect("Operation should succeed");
+        let report = manager()
+            .resume(&session_path)
+            .await
+            .expect("Operation should succeed");
+        assert_eq!(report.downloaded, std::slice::from_ref(&output));
+        assert_eq!(std::fs::read(&output).expect("file"), data);
+    }
+
+    #[tokio::test]
+    async fn test_chunk_progress_saves_are_throttled() {
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let session_path = temp_dir.path().join("session.json");
+        // The session file as written, without checking partial files
+        let saved = || {
+            serde_json::from_slice::<ResumableDownload>(
+                &std::fs::read(&session_path).expect("Operation should succeed"),
+            )
+            .expect("Operation should succeed")
+        };
+        let mut session = ResumableDownload::new(CHUNK);
+        session.add_file(Vec::new(), temp_dir.path().join("file"), [0; 16]);
+        session
+            .save(&session_path)
+            .await
+            .expect("Operation should succeed");
+
+        // Chunks recorded right after a save wait for the next one
+        let store = SessionStore::new(session, &session_path);
+        for _ in 0..100 {
+            store
+                .update(0, false, |file| file.chunks.push("00".repeat(16)))
+                .await
+                .expect("Operation should succeed");
+        }
+        assert!(saved().files[0].chunks.is_empty());
+
+        store.flush().await.expect("Operation should succeed");
+        assert_eq!(saved().files[0].chunks.len(), 100);
+        assert!(!temp_dir.path().join("session.json.tmp").exists());
+
+        // Other changes are saved at once
+        store
+            .update(0, true, |file| file.complete = true)
+            .await
+            .expect("Operation should succeed");
+        assert!(saved().files[0].complete);
+    }
+
+    #[tokio::test]
+    async fn test_load_rejects_invalid_sessions() {
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let session_path = temp_dir.path().join("session.json");
+
+        std::fs::write(&session_path, b"not json").expect("Operation should succeed");
+        assert!(matches!(
+            ResumableDownload::load(&session_path).await,
+            Err(ProtocolError::Parse(_))
+        ));
+
+        std::fs::write(&session_path, br#"{"chunk_size":0,"files":[]}"#)
+            .expect("Operation should succeed");
+        assert!(matches!(
+            ResumableDownload::load(&session_path).await,
+            Err(ProtocolError::Parse(_))
+        ));
+
+        // Progress of a file that is gone is dropped
+        let mut session = ResumableDownload::new(CHUNK);
+        session.add_file(Vec::new(), temp_dir.path().join("missing"), [0; 16]);
+        session.files[0].chunks.push("00".repeat(16));
+        session
+            .save(&session_path)
+            .await
+            .expect("Operation should succeed");
+        let session = ResumableDownload::load(&session_path)
+            .await
+            .expect("Operation should succeed");
+        assert!(session.files[0].chunks.is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+//! Content verification of downloaded CDN files
+//!
+//! CDN files are named after an MD5 hash of their content. Configs are
+//! named after the MD5 of the whole file. BLTE data and patch files are
+//! named after their encoding key. That key is the MD5 of the BLTE header
+//! for chunked files and the MD5 of the whole file otherwise.
+//!
+//! Archives are the exception: they are named after the MD5 of their
+//! index footer, so they cannot be verified from their own bytes.
+
+use crate::error::{ProtocolError, Result};
+
+/// Length of the BLTE magic and header size fields
+const BLTE_PREAMBLE: usize = 8;
+
+/// Incremental MD5 check of a download against the key it was requested by
+pub struct ContentVerifier {
+    expected: [u8; 16],
+    full: md5::Context,
+    /// Leading bytes, kept until the BLTE header (if any) is complete
+    head: Vec<u8>,
+}
+
+impl ContentVerifier {
+    /// Create a verifier for `key`
+    ///
+    /// Returns `None` for keys that are not 16 bytes long, as they cannot
+    /// be MD5 hashes.
+    pub fn new(key: &[u8]) -> Option<Self> {
+        Some(Self {
+            expected: key.try_into().ok()?,
+            full: md5::Context::new(),
+            head: Vec::with_capacity(BLTE_PREAMBLE),
+        })
+    }
+
+    /// Feed the next chunk of the download
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.full.consume(chunk);
+
+        let mut rest = chunk;
+        while !rest.is_empty() && self.head.len() < self.head_limit() {
+            let take = (self.head_limit() - self.head.len()).min(rest.len());
+            self.head.extend_from_slice(&rest[..take]);
+            rest = &rest[take..];
+        }
+    }
+
+    /// Check the fed bytes against the key
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProtocolError::ChecksumMismatch`] if neither the whole
+    /// content nor its BLTE header hash to the key.
+    pub fn finish(self) -> Result<()> {
+        let header = match self.blte_header_size() {
+            Some(size) if self.head.len() == size => Some(md5::compute(&self.head).0),
+            _ => None,
+        };
+        let full = self.full.finalize().0;
+        if full == self.expected {
+            return Ok(());
+        }
+
+        let actual = header.unwrap_or(full);
+        if actual == self.expected {
+            Ok(())
+        } else {
+            Err(ProtocolError::ChecksumMismatch {
+                expected: hex::encode(self.expected),
+                actual: hex::encode(actual),
+            })
+        }
+    }
+
+    /// Size of the BLTE header, if the content is a chunked BLTE file
+    fn blte_header_size(&self) -> Option<usize> {
+        let preamble = self.head.get(..BLTE_PREAMBLE)?;
+        if &preamble[..4] != b"BLTE" {
+            return None;
+        }
+        let size = u32::from_be_bytes([preamble[4], preamble[5], preamble[6], preamble[7]]);
+        usize::try_from(size)
+            .ok()
+            .filter(|&size| size > BLTE_PREAMBLE)
+    }
+
+    /// Number of leading bytes needed to hash the BLTE header
+    fn head_limit(&self) -> usize {
+        self.blte_header_size().unwrap_or(BLTE_PREAMBLE)
+    }
+}
+
+/// Check `data` against the key it was requested by
+///
+/// Keys that are not 16 bytes long are not checked.
+///
+/// # Errors
+///
+/// Returns [`ProtocolError::ChecksumMismatch`] if `data` does not hash to
+/// `key`.
+pub fn verify_content(key: &[u8], data: &[u8]) -> Result<()> {
+    ContentVerifier::new(key).map_or(Ok(()), |mut verifier| {
+        verifier.update(data);
+        verifier.finish()
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    /// A chunked BLTE stream with a header of `header_size` bytes
+    fn chunked_blte(header_size: u32, body: &[u8]) -> Vec<u8> {
+        let mut data = b"BLTE".to_vec();
+        data.extend_from_slice(&header_size.to_be_bytes());
+        data.resize(header_size as usize, 0x0f);
+        data.extend_from_slice(body);
+        data
+    }
+
+    #[test]
+    fn test_whole_content_hash_matches() {
+        let data = b"build-name = test\n";
+        assert!(verify_content(&md5::compute(data).0, data).is_ok());
+    }
+
+    #[test]
+    fn test_blte_header_hash_matches() {
+        let data = chunked_blte(36, b"chunk data");
+        let key = md5::compute(&data[..36]).0;
+        assert!(verify_content(&key, &data).is_ok());
+
+        // Fed one byte at a time, the header is still recognized
+        let mut verifier = ContentVerifier::new(&key).expect("16 byte key");
+        for byte in &data {
+            verifier.update(std::slice::from_ref(byte));
+        }
+        assert!(verifier.finish().is_ok());
+    }
+
+    #[test]
+    fn test_mismatch_reports_both_hashes() {
+        let data = b"corrupted";
+        let key = [0xab; 16];
+        let err = verify_content(&key, data).expect_err("Content should not match");
+        assert!(err.should_retry());
+        assert!(matches!(
+            err,
+            ProtocolError::ChecksumMismatch { expected, actual }
+                if expected == hex::encode(key) && actual == hex::encode(md5::compute(data).0)
+        ));
+    }
+
+    #[test]
+    fn test_short_keys_are_not_checked() {
+        assert!(verify_content(&[0xab; 8], b"anything").is_ok());
+    }
+}
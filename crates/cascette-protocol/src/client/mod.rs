@@ -29,6 +29,9 @@
 //! 2. **TACT v1 HTTP** (Fallback): `http://us.patch.battle.net:1119`
 //! 3. **Ribbit TCP** (Final): `us.version.battle.net:1119`
 //!
+//! [`RibbitTactClient::query_via`] skips the fallback and uses a single
+//! [`Protocol`], for debugging or networks where only one of them works.
+//!
 //! ## Usage Examples
 //!
 //! ### Basic Query with Automatic Fallback
@@ -99,17 +102,23 @@
 //! }
 //! ```
 
+mod multi_region;
 pub mod region;
 // Ribbit TCP is not available on WASM (no raw TCP sockets)
+// Stale-while-revalidate needs a runtime for background refreshes
+#[cfg(not(target_arch = "wasm32"))]
+mod revalidate;
 #[cfg(not(target_arch = "wasm32"))]
 mod ribbit;
 mod tact;
 
+pub use multi_region::{MultiRegionClient, RegionHealth};
 pub use region::Region;
 #[cfg(not(target_arch = "wasm32"))]
-pub use ribbit::RibbitClient;
+pub use ribbit::{RibbitClient, RibbitClientBuilder, RibbitPoolConfig, RibbitPoolStats};
 pub use tact::TactClient;
 
+use bytes::Bytes;
 use cascette_formats::CascFormat;
 use cascette_formats::bpsv::BpsvDocument;
 use std::sync::Arc;
@@ -118,6 +127,31 @@ use std::time::Duration;
 use crate::config::ClientConfig;
 use crate::error::{ProtocolError, Result};
 
+/// Protocol used to query an endpoint, see [`RibbitTactClient::query_via`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Protocol {
+    /// TACT v2 over HTTPS ([`ClientConfig::tact_https_url`])
+    TactHttps,
+    /// TACT v1 over HTTP ([`ClientConfig::tact_http_url`])
+    TactHttp,
+    /// Ribbit over TCP ([`ClientConfig::ribbit_url`]); not available on WASM
+    RibbitTcp,
+    /// The fallback chain of [`RibbitTactClient::query`]
+    #[default]
+    Auto,
+}
+
+impl std::fmt::Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::TactHttps => "TACT HTTPS",
+            Self::TactHttp => "TACT HTTP",
+            Self::RibbitTcp => "Ribbit TCP",
+            Self::Auto => "automatic fallback",
+        })
+    }
+}
+
 /// Unified client providing transparent protocol fallback for NGDP/CASC operations.
 ///
 /// The `RibbitTactClient` is the main entry point for all NGDP protocol operations.
@@ -281,14 +315,19 @@ use crate::error::{ProtocolError, Result};
 ///
 /// The client is fully thread-safe and designed for concurrent usage. All internal
 /// state is protected by appropriate synchronization primitives, and the cache
-/// is shared efficiently across threads.
+/// is shared efficiently across threads. Clones are cheap and share the
+/// protocol clients, the cache and in-flight background refreshes.
+#[derive(Clone)]
 pub struct RibbitTactClient {
-    tact_https: Option<TactClient>,
-    tact_http: Option<TactClient>,
+    tact_https: Option<Arc<TactClient>>,
+    tact_http: Option<Arc<TactClient>>,
     #[cfg(not(target_arch = "wasm32"))]
-    ribbit_tcp: RibbitClient,
+    ribbit_tcp: Arc<RibbitClient>,
     cache: Arc<crate::cache::ProtocolCache>,
-    config: ClientConfig,
+    config: Arc<ClientConfig>,
+    /// Stale-while-revalidate state, if a grace window is configured
+    #[cfg(not(target_arch = "wasm32"))]
+    revalidator: Option<Arc<revalidate::Revalidator>>,
 }
 
 impl RibbitTactClient {
@@ -374,24 +413,73 @@ impl RibbitTactClient {
     /// - System resource allocation fails (e.g., insufficient memory)
     pub fn new(config: ClientConfig) -> Result<Self> {
         let cache = Arc::new(crate::cache::ProtocolCache::new(&config.cache_config)?);
+        Self::with_cache(config, cache)
+    }
+
+    /// Create a client that stores responses in an existing cache.
+    ///
+    /// Responses are keyed by [`ClientConfig::region`], so clients for
+    /// different regions can share `cache` without serving each other's
+    /// responses. `config.cache_config` is only used for TTLs.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError` if a protocol client cannot be created.
+    pub fn with_cache(
+        config: ClientConfig,
+        cache: Arc<crate::cache::ProtocolCache>,
+    ) -> Result<Self> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return Self::with_clock(config, cache, Arc::new(std::time::SystemTime::now));
+        #[cfg(target_arch = "wasm32")]
+        Self::build(config, cache)
+    }
+
+    /// [`with_cache`](Self::with_cache), judging cache freshness by `clock`
+    #[cfg(not(target_arch = "wasm32"))]
+    fn with_clock(
+        config: ClientConfig,
+        cache: Arc<crate::cache::ProtocolCache>,
+        clock: revalidate::Clock,
+    ) -> Result<Self> {
+        let grace = config.cache_config.revalidate_grace;
+        let mut client = Self::build(config, cache)?;
+        client.revalidator =
+            (!grace.is_zero()).then(|| Arc::new(revalidate::Revalidator::new(grace, clock)));
+        Ok(client)
+    }
 
+    fn build(config: ClientConfig, cache: Arc<crate::cache::ProtocolCache>) -> Result<Self> {
         // Initialize TACT HTTPS client
         let tact_https = if config.tact_https_url.is_empty() {
             None
         } else {
-            Some(TactClient::new(config.tact_https_url.clone(), true)?)
+            Some(Arc::new(TactClient::new(
+                config.tact_https_url.clone(),
+                true,
+            )?))
         };
 
         // Initialize TACT HTTP client
         let tact_http = if config.tact_http_url.is_empty() {
             None
         } else {
-            Some(TactClient::new(config.tact_http_url.clone(), false)?)
+            Some(Arc::new(TactClient::new(
+                config.tact_http_url.clone(),
+                false,
+            )?))
         };
 
         // Initialize Ribbit TCP client (not available on WASM)
         #[cfg(not(target_arch = "wasm32"))]
-        let ribbit_tcp = RibbitClient::new(config.ribbit_url.clone())?;
+        let ribbit_tcp = {
+            let client = RibbitClient::new(config.ribbit_url.clone())?
+                .verify_signatures(config.ribbit_verify_signatures);
+            Arc::new(match &config.ribbit_trust_anchors {
+                Some(pem) => client.with_trust_anchors_pem(pem)?,
+                None => client,
+            })
+        };
 
         Ok(Self {
             tact_https,
@@ -399,7 +487,9 @@ impl RibbitTactClient {
             #[cfg(not(target_arch = "wasm32"))]
             ribbit_tcp,
             cache,
-            config,
+            config: Arc::new(config),
+            #[cfg(not(target_arch = "wasm32"))]
+            revalidator: None,
         })
     }
 
@@ -577,20 +667,168 @@ impl RibbitTactClient {
     /// Most errors support automatic retry via [`ProtocolError::should_retry()`].
     /// The client automatically retries transient errors with exponential backoff.
     pub async fn query(&self, endpoint: &str) -> Result<BpsvDocument> {
+        self.query_raw(endpoint).await.map(|(_, document)| document)
+    }
+
+    /// Query an endpoint, returning the response bytes as the server sent
+    /// them together with the document parsed from them.
+    ///
+    /// The bytes are what the cache stores, so a cached response comes back
+    /// exactly as it was received: BPSV text for TACT, or the V1 MIME
+    /// message with its signature for Ribbit TCP.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`query`](Self::query).
+    pub async fn query_raw(&self, endpoint: &str) -> Result<(Bytes, BpsvDocument)> {
+        self.query_raw_via(endpoint, Protocol::Auto).await
+    }
+
+    /// Query an endpoint over one protocol, without falling back to others.
+    ///
+    /// [`Protocol::Auto`] behaves like [`query`](Self::query). Any other
+    /// protocol is used on its own, also for TCP-only endpoints such as
+    /// `v1/certs/`, and its error is returned as is. Caching is the same as
+    /// for [`query`](Self::query): a cached response is returned whichever
+    /// protocol fetched it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use cascette_protocol::{ClientConfig, Protocol, RibbitTactClient};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = RibbitTactClient::new(ClientConfig::default())?;
+    /// let versions = client
+    ///     .query_via("v1/products/wow/versions", Protocol::RibbitTcp)
+    ///     .await?;
+    /// println!("Found {} versions over TCP", versions.rows().len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProtocolError::ProtocolNotConfigured`] if the client has no
+    /// endpoint for `protocol`, [`ProtocolError::UnsupportedOnWasm`] for
+    /// Ribbit TCP on WASM, or the error of the chosen protocol.
+    pub async fn query_via(&self, endpoint: &str, protocol: Protocol) -> Result<BpsvDocument> {
+        self.query_raw_via(endpoint, protocol)
+            .await
+            .map(|(_, document)| document)
+    }
+
+    async fn query_raw_via(
+        &self,
+        endpoint: &str,
+        protocol: Protocol,
+    ) -> Result<(Bytes, BpsvDocument)> {
         // Validate endpoint
         validate_endpoint(endpoint)?;
 
-        // Build cache key with api/ prefix for proper organization
-        let cache_key = format!("api/ribbit/{endpoint}");
+        // Build cache key with api/ prefix for proper organization. The
+        // endpoint is the same in every region, so the region is part of the key
+        let cache_key = format!("api/ribbit/{}/{endpoint}", self.config.region);
 
         // Try cache first
         if let Some(cached) = self.cache.get(&cache_key)?
-            && let Ok(response) = <BpsvDocument as CascFormat>::parse(&cached)
+            && let Ok(response) = parse_cached(&cached)
         {
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(revalidator) = &self.revalidator {
+                match revalidator.freshness(&self.cache, &freshness_key(&cache_key))? {
+                    revalidate::Freshness::Fresh => {}
+                    revalidate::Freshness::Stale
+                        if self.spawn_refresh(revalidator, endpoint, protocol, &cache_key) =>
+                    {
+                        tracing::debug!("Serving stale {endpoint} while revalidating");
+                        self.cache.record_stale_served();
+                    }
+                    revalidate::Freshness::Stale | revalidate::Freshness::Expired => {
+                        tracing::debug!("Cached {endpoint} needs revalidation before use");
+                        return self.fetch_and_store(endpoint, protocol, &cache_key).await;
+                    }
+                }
+            }
             tracing::debug!("Cache hit for {endpoint}");
-            return Ok(response);
+            return Ok((Bytes::from(cached), response));
         }
 
+        self.fetch_and_store(endpoint, protocol, &cache_key).await
+    }
+
+    /// Query over `protocol` and cache the response under `cache_key`
+    async fn fetch_and_store(
+        &self,
+        endpoint: &str,
+        protocol: Protocol,
+        cache_key: &str,
+    ) -> Result<(Bytes, BpsvDocument)> {
+        let (raw, response) = self.query_protocol(endpoint, protocol).await?;
+
+        // Cache successful response; maintenance placeholders expire quickly
+        // so that recovery is noticed
+        let ttl = if crate::maintenance::is_probably_maintenance(&response, endpoint) {
+            tracing::warn!("{endpoint} returned no rows, assuming maintenance window");
+            self.config.cache_config.maintenance_ttl
+        } else {
+            self.determine_ttl(endpoint)
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(revalidator) = &self.revalidator {
+            self.cache
+                .store_with_ttl(cache_key, &raw, revalidator.retention(ttl))?;
+            revalidator.mark_fresh(&self.cache, &freshness_key(cache_key), ttl)?;
+            return Ok((raw, response));
+        }
+
+        self.cache.store_with_ttl(cache_key, &raw, ttl)?;
+        Ok((raw, response))
+    }
+
+    /// Refresh a stale response in the background
+    ///
+    /// At most one refresh per cache key runs at a time. A failed refresh
+    /// leaves the stale response in place until its grace window ends.
+    /// Returns `false` if there is no Tokio runtime to run the refresh on,
+    /// in which case the caller queries in the foreground.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn spawn_refresh(
+        &self,
+        revalidator: &Arc<revalidate::Revalidator>,
+        endpoint: &str,
+        protocol: Protocol,
+        cache_key: &str,
+    ) -> bool {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return false;
+        };
+        let Some(guard) = revalidator.begin_refresh(cache_key) else {
+            tracing::debug!("Refresh of {endpoint} already in progress");
+            return true;
+        };
+
+        let client = self.clone();
+        let endpoint = endpoint.to_string();
+        let cache_key = cache_key.to_string();
+        runtime.spawn(async move {
+            match client
+                .fetch_and_store(&endpoint, protocol, &cache_key)
+                .await
+            {
+                Ok(_) => tracing::debug!("Revalidated {endpoint}"),
+                Err(e) => {
+                    tracing::warn!("Revalidating {endpoint} failed, keeping stale response: {e}");
+                }
+            }
+            drop(guard);
+        });
+        true
+    }
+
+    /// Query over the fallback chain, or Ribbit TCP for TCP-only endpoints
+    async fn query_auto(&self, endpoint: &str) -> Result<(Bytes, BpsvDocument)> {
         // Check if this is a TCP-only endpoint (not available on WASM)
         #[cfg(not(target_arch = "wasm32"))]
         let is_tcp_only = endpoint.starts_with("v1/summary")
@@ -599,20 +837,20 @@ impl RibbitTactClient {
 
         // Try protocols in order
         #[cfg(not(target_arch = "wasm32"))]
-        let response = if is_tcp_only {
+        let (raw, response) = if is_tcp_only {
             // Skip TACT protocols for TCP-only endpoints
             tracing::debug!(
                 "Using Ribbit TCP directly for TCP-only endpoint: {}",
                 endpoint
             );
-            self.ribbit_tcp.query(endpoint).await?
+            self.query_ribbit_tcp(endpoint).await?
         } else {
             self.query_with_fallback(endpoint).await?
         };
 
         // On WASM, TCP-only endpoints are not supported
         #[cfg(target_arch = "wasm32")]
-        let response = {
+        let (raw, response) = {
             let is_tcp_only = endpoint.starts_with("v1/summary")
                 || endpoint.starts_with("v1/certs/")
                 || endpoint.starts_with("v1/ocsp/");
@@ -624,15 +862,98 @@ impl RibbitTactClient {
             self.query_with_fallback(endpoint).await?
         };
 
-        // Cache successful response
-        let ttl = self.determine_ttl(endpoint);
-        // Store serialized response
-        let data = response
-            .build()
-            .map_err(|e| ProtocolError::Parse(e.to_string()))?;
-        self.cache.store_with_ttl(&cache_key, &data, ttl)?;
+        Ok((raw, response))
+    }
+
+    /// Query over `protocol`, without caching
+    async fn query_protocol(
+        &self,
+        endpoint: &str,
+        protocol: Protocol,
+    ) -> Result<(Bytes, BpsvDocument)> {
+        let not_configured = || ProtocolError::ProtocolNotConfigured(protocol.to_string());
+        match protocol {
+            Protocol::Auto => self.query_auto(endpoint).await,
+            Protocol::TactHttps => {
+                query_tact(
+                    self.tact_https.as_ref().ok_or_else(not_configured)?,
+                    endpoint,
+                )
+                .await
+            }
+            Protocol::TactHttp => {
+                query_tact(
+                    self.tact_http.as_ref().ok_or_else(not_configured)?,
+                    endpoint,
+                )
+                .await
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Protocol::RibbitTcp if self.config.ribbit_url.is_empty() => Err(not_configured()),
+            #[cfg(not(target_arch = "wasm32"))]
+            Protocol::RibbitTcp => self.query_ribbit_tcp(endpoint).await,
+            #[cfg(target_arch = "wasm32")]
+            Protocol::RibbitTcp => Err(ProtocolError::UnsupportedOnWasm(format!(
+                "Ribbit TCP query for '{endpoint}' is not available on WASM"
+            ))),
+        }
+    }
 
-        Ok(response)
+    /// Query a product's `versions` endpoint, classifying maintenance responses.
+    ///
+    /// During maintenance windows Blizzard serves a valid document without
+    /// rows. Such responses are returned as
+    /// [`QueryOutcome::EmptyDuringMaintenance`](crate::maintenance::QueryOutcome::EmptyDuringMaintenance)
+    /// instead of an empty listing that would look like every build was
+    /// removed. They are cached with
+    /// [`CacheConfig::maintenance_ttl`](crate::CacheConfig::maintenance_ttl).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn query_versions(&self, product: &str) -> Result<crate::maintenance::QueryOutcome> {
+        let endpoint = format!("v1/products/{product}/versions");
+        let document = self.query(&endpoint).await?;
+        Ok(crate::maintenance::QueryOutcome::classify(
+            document, &endpoint,
+        ))
+    }
+
+    /// Query a product's `bgdl` endpoint as typed background download entries.
+    ///
+    /// Like [`query_versions`](Self::query_versions), the region is the one
+    /// of the configured endpoints. Products without a pending background
+    /// download return an empty list.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::NotFound` if the product has no `bgdl`
+    /// endpoint, or another error if the query fails or a row is malformed.
+    pub async fn query_bgdl(&self, product: &str) -> Result<Vec<crate::bgdl::BgdlEntry>> {
+        let endpoint = format!("v1/products/{product}/bgdl");
+        let document = match self.query(&endpoint).await {
+            Err(
+                ProtocolError::HttpStatus(reqwest::StatusCode::NOT_FOUND)
+                | ProtocolError::ClientError(reqwest::StatusCode::NOT_FOUND),
+            ) => return Err(ProtocolError::NotFound(endpoint)),
+            result => result?,
+        };
+        crate::bgdl::parse_bgdl(&document)
+    }
+
+    /// Query the `v1/summary` endpoint as a typed [`SummaryResponse`](crate::SummaryResponse).
+    ///
+    /// The summary is only served over Ribbit TCP, so this is not available
+    /// on WASM. Compare [`SummaryResponse::latest_seqn`](crate::SummaryResponse::latest_seqn) between polls to
+    /// detect new builds without fetching every product's versions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or the response is not a valid
+    /// summary document.
+    pub async fn query_summary(&self) -> Result<crate::summary::SummaryResponse> {
+        let document = self.query("v1/summary").await?;
+        crate::summary::SummaryResponse::from_bpsv(&document)
     }
 
     /// Get a reference to the underlying protocol cache.
@@ -767,20 +1088,33 @@ impl RibbitTactClient {
         &self.cache
     }
 
-    async fn query_with_fallback(&self, endpoint: &str) -> Result<BpsvDocument> {
+    /// Region this client queries, from [`ClientConfig::region`].
+    pub fn region(&self) -> Region {
+        self.config.region
+    }
+
+    /// Query Ribbit TCP, verifying V1 signatures as configured
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn query_ribbit_tcp(&self, endpoint: &str) -> Result<(Bytes, BpsvDocument)> {
+        let raw = self.ribbit_tcp.query_raw(endpoint).await?;
+        let response = self.ribbit_tcp.parse_response(&raw).await?;
+        Ok((Bytes::from(raw), response))
+    }
+
+    async fn query_with_fallback(&self, endpoint: &str) -> Result<(Bytes, BpsvDocument)> {
         let mut last_error = None;
 
         // Try TACT HTTPS
         if let Some(client) = &self.tact_https {
             tracing::debug!("Trying TACT HTTPS for {}", endpoint);
-            match client.query(endpoint).await {
+            match query_tact(client, endpoint).await {
                 Ok(response) => return Ok(response),
                 Err(e) => {
                     tracing::warn!("TACT HTTPS failed for {}: {}", endpoint, e);
-                    // If the error is non-retryable (e.g., HTTP 400, 401, 403, 404),
-                    // don't attempt fallback protocols
-                    if !e.should_retry() {
-                        tracing::info!("Non-retryable error, stopping fallback chain: {}", e);
+                    // Errors every protocol would repeat (404, 401/403, invalid
+                    // endpoint, parse errors) end the fallback chain
+                    if e.is_permanent() {
+                        tracing::info!("Permanent error, stopping fallback chain: {}", e);
                         return Err(e);
                     }
                     last_error = Some(e);
@@ -791,13 +1125,13 @@ impl RibbitTactClient {
         // Try TACT HTTP
         if let Some(client) = &self.tact_http {
             tracing::debug!("Trying TACT HTTP for {}", endpoint);
-            match client.query(endpoint).await {
+            match query_tact(client, endpoint).await {
                 Ok(response) => return Ok(response),
                 Err(e) => {
                     tracing::warn!("TACT HTTP failed for {}: {}", endpoint, e);
-                    // If the error is non-retryable, don't attempt Ribbit fallback
-                    if !e.should_retry() {
-                        tracing::info!("Non-retryable error, stopping fallback chain: {}", e);
+                    // A permanent error would repeat over Ribbit
+                    if e.is_permanent() {
+                        tracing::info!("Permanent error, stopping fallback chain: {}", e);
                         return Err(e);
                     }
                     last_error = Some(e);
@@ -809,7 +1143,7 @@ impl RibbitTactClient {
         #[cfg(not(target_arch = "wasm32"))]
         {
             tracing::debug!("Trying Ribbit TCP for {}", endpoint);
-            match self.ribbit_tcp.query(endpoint).await {
+            match self.query_ribbit_tcp(endpoint).await {
                 Ok(response) => Ok(response),
                 Err(e) => {
                     tracing::error!("All protocols failed for {}: {}", endpoint, e);
@@ -838,6 +1172,31 @@ impl RibbitTactClient {
     }
 }
 
+/// Cache key of the freshness entry for the response under `cache_key`
+#[cfg(not(target_arch = "wasm32"))]
+fn freshness_key(cache_key: &str) -> String {
+    format!("{cache_key}#fresh-until")
+}
+
+/// Query a TACT endpoint, keeping the body the document was parsed from
+async fn query_tact(client: &TactClient, endpoint: &str) -> Result<(Bytes, BpsvDocument)> {
+    let raw = client.query_raw(endpoint).await?;
+    let response = <BpsvDocument as CascFormat>::parse(&raw)
+        .map_err(|e| ProtocolError::Parse(format!("BPSV parse error: {e}")))?;
+    Ok((raw, response))
+}
+
+/// Parse a cached response
+///
+/// Cached V1 MIME responses had their signature checked when they were
+/// received, so only their BPSV part is parsed here.
+fn parse_cached(data: &[u8]) -> Result<BpsvDocument> {
+    if crate::mime_parser::is_v1_mime_response(data) {
+        return crate::mime_parser::parse_v1_mime_to_bpsv(data);
+    }
+    <BpsvDocument as CascFormat>::parse(data).map_err(|e| ProtocolError::Parse(e.to_string()))
+}
+
 /// Validate that endpoint is safe and well-formed
 fn validate_endpoint(endpoint: &str) -> Result<()> {
     if endpoint.is_empty() {
@@ -865,3 +1224,366 @@ fn validate_endpoint(endpoint: &str) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::CacheConfig;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use tempfile::TempDir;
+    use wiremock::matchers::{any, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const VERSIONS: &str = "v1/products/wow/versions";
+
+    fn versions_body(build: u32) -> String {
+        format!("Region!STRING:0|BuildId!DEC:4\n## seqn = {build}\nus|{build}\n")
+    }
+
+    fn build_id(document: &BpsvDocument) -> String {
+        document.rows()[0]
+            .get_raw(1)
+            .expect("Row should have a build ID")
+            .to_string()
+    }
+
+    /// Client with a 300 second TTL and 60 second grace window whose clock
+    /// reads `secs` seconds past the epoch
+    fn revalidating_client(
+        server: &MockServer,
+        temp_dir: &TempDir,
+        secs: &Arc<AtomicU64>,
+    ) -> RibbitTactClient {
+        let config = ClientConfig {
+            tact_https_url: String::new(),
+            tact_http_url: server.uri(),
+            ribbit_url: String::new(),
+            cache_config: CacheConfig {
+                cache_dir: Some(temp_dir.path().to_path_buf()),
+                ribbit_ttl: Duration::from_secs(300),
+                revalidate_grace: Duration::from_secs(60),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let cache = Arc::new(
+            crate::cache::ProtocolCache::new(&config.cache_config)
+                .expect("Operation should succeed"),
+        );
+        let secs = Arc::clone(secs);
+        let clock: revalidate::Clock = Arc::new(move || {
+            std::time::UNIX_EPOCH + Duration::from_secs(secs.load(Ordering::SeqCst))
+        });
+        RibbitTactClient::with_clock(config, cache, clock).expect("Operation should succeed")
+    }
+
+    async fn request_count(server: &MockServer) -> usize {
+        server
+            .received_requests()
+            .await
+            .map_or(0, |requests| requests.len())
+    }
+
+    #[tokio::test]
+    async fn test_stale_response_served_while_one_refresh_runs() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/wow/versions"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(versions_body(1)))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        // The refresh is slow, so the stale queries below overlap it
+        Mock::given(method("GET"))
+            .and(path("/wow/versions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(versions_body(2))
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let secs = Arc::new(AtomicU64::new(1_000));
+        let client = revalidating_client(&server, &temp_dir, &secs);
+
+        let document = client.query(VERSIONS).await.expect("Query should succeed");
+        assert_eq!(build_id(&document), "1");
+
+        // Past the TTL, within the grace window: answered from the cache
+        secs.store(1_310, Ordering::SeqCst);
+        for _ in 0..3 {
+            let document = client.query(VERSIONS).await.expect("Query should succeed");
+            assert_eq!(build_id(&document), "1");
+        }
+        let stats = client.cache().stats().expect("Operation should succeed");
+        assert_eq!(stats.stale_served, 3);
+
+        // The single refresh lands and the new response is fresh
+        let mut refreshed = false;
+        for _ in 0..100 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let document = client.query(VERSIONS).await.expect("Query should succeed");
+            if build_id(&document) == "2" {
+                refreshed = true;
+                break;
+            }
+        }
+        assert!(refreshed, "Background refresh should update the cache");
+        assert_eq!(request_count(&server).await, 2);
+
+        let served = client
+            .cache()
+            .stats()
+            .expect("Operation should succeed")
+            .stale_served;
+        let document = client.query(VERSIONS).await.expect("Query should succeed");
+        assert_eq!(build_id(&document), "2");
+        assert_eq!(
+            client
+                .cache()
+                .stats()
+                .expect("Operation should succeed")
+                .stale_served,
+            served
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_refresh_keeps_stale_response() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/wow/versions"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(versions_body(1)))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/wow/versions"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let secs = Arc::new(AtomicU64::new(1_000));
+        let client = revalidating_client(&server, &temp_dir, &secs);
+        client.query(VERSIONS).await.expect("Query should succeed");
+
+        secs.store(1_310, Ordering::SeqCst);
+        let document = client.query(VERSIONS).await.expect("Query should succeed");
+        assert_eq!(build_id(&document), "1");
+        for _ in 0..100 {
+            if request_count(&server).await >= 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(request_count(&server).await, 2);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // The failed refresh did not evict the stale response
+        let document = client.query(VERSIONS).await.expect("Query should succeed");
+        assert_eq!(build_id(&document), "1");
+        // Let the refresh this query started fail too
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Past the grace window the query waits for the network again
+        secs.store(1_361, Ordering::SeqCst);
+        let before = request_count(&server).await;
+        let err = client
+            .query(VERSIONS)
+            .await
+            .expect_err("Expired response should not be served");
+        assert!(err.is_permanent(), "unexpected error: {err}");
+        assert_eq!(request_count(&server).await, before + 1);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_error_stops_fallback_chain() {
+        let https = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/nonexistent/versions"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&https)
+            .await;
+
+        // The HTTP fallback must never be reached
+        let http = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&http)
+            .await;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let config = ClientConfig {
+            tact_https_url: https.uri(),
+            tact_http_url: http.uri(),
+            ribbit_url: String::new(),
+            cache_config: CacheConfig {
+                cache_dir: Some(temp_dir.path().to_path_buf()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let client = RibbitTactClient::new(config).expect("Operation should succeed");
+
+        let err = client
+            .query("v1/products/nonexistent/versions")
+            .await
+            .expect_err("Query should fail");
+        assert!(err.is_permanent(), "unexpected error: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_query_via_skips_fallback() {
+        let body = "Region!STRING:0|BuildId!DEC:4\n## seqn = 7\nus|1234\n";
+        let https = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1..)
+            .mount(&https)
+            .await;
+        let http = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/wow/versions"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .expect(1)
+            .mount(&http)
+            .await;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let config = ClientConfig {
+            tact_https_url: https.uri(),
+            tact_http_url: http.uri(),
+            ribbit_url: String::new(),
+            cache_config: CacheConfig {
+                cache_dir: Some(temp_dir.path().to_path_buf()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let client = RibbitTactClient::new(config).expect("Operation should succeed");
+        let endpoint = "v1/products/wow/versions";
+
+        // The HTTPS error is returned instead of falling back to HTTP
+        let err = client
+            .query_via(endpoint, Protocol::TactHttps)
+            .await
+            .expect_err("HTTPS query should fail");
+        assert!(
+            !matches!(err, ProtocolError::AllHostsFailed),
+            "unexpected error: {err}"
+        );
+
+        let err = client
+            .query_via(endpoint, Protocol::RibbitTcp)
+            .await
+            .expect_err("Ribbit is not configured");
+        assert!(matches!(err, ProtocolError::ProtocolNotConfigured(_)));
+
+        let document = client
+            .query_via(endpoint, Protocol::TactHttp)
+            .await
+            .expect("HTTP query should succeed");
+        assert_eq!(document.rows().len(), 1);
+
+        // Cached like `query`, whichever protocol is asked for afterwards
+        let document = client
+            .query_via(endpoint, Protocol::TactHttps)
+            .await
+            .expect("Cached query should succeed");
+        assert_eq!(document.rows().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_default_config_accepts_signed_ribbit_response() {
+        use crate::v1_mime::test_fixtures::{SIGNATURE_WITH_CERT, signed_mime};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Operation should succeed");
+        let addr = listener.local_addr().expect("Operation should succeed");
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.expect("Operation should succeed");
+            let mut buffer = [0; 1024];
+            let _ = stream.read(&mut buffer).await;
+            let _ = stream.write_all(&signed_mime(SIGNATURE_WITH_CERT)).await;
+            let _ = stream.shutdown().await;
+        });
+
+        // Signature verification on, no trust anchors
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let config = ClientConfig {
+            ribbit_url: format!("tcp://{addr}"),
+            cache_config: CacheConfig {
+                cache_dir: Some(temp_dir.path().to_path_buf()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(config.ribbit_verify_signatures);
+        assert!(config.ribbit_trust_anchors.is_none());
+        let client = RibbitTactClient::new(config).expect("Operation should succeed");
+
+        let document = client
+            .query_via(VERSIONS, Protocol::RibbitTcp)
+            .await
+            .expect("Signed response should verify");
+        assert_eq!(document.rows().len(), 1);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_query_raw_returns_bytes_as_sent() {
+        // Lower-case type names and the trailing blank line would not survive
+        // re-serializing the parsed document
+        let body = "Region!String:0|BuildId!DEC:4\n## seqn = 7\nus|1234\neu|1235\n\n";
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/wow/versions"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let config = ClientConfig {
+            tact_https_url: String::new(),
+            tact_http_url: server.uri(),
+            ribbit_url: String::new(),
+            cache_config: CacheConfig {
+                cache_dir: Some(temp_dir.path().to_path_buf()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let client = RibbitTactClient::new(config).expect("Operation should succeed");
+
+        let (raw, document) = client
+            .query_raw("v1/products/wow/versions")
+            .await
+            .expect("Query should succeed");
+        assert_eq!(raw, body.as_bytes());
+        assert_eq!(document.rows().len(), 2);
+
+        // The cache hands back the same bytes, and `query` the same document
+        let (cached, cached_document) = client
+            .query_raw("v1/products/wow/versions")
+            .await
+            .expect("Cached query should succeed");
+        assert_eq!(cached, raw);
+        assert_eq!(cached_document.rows().len(), 2);
+        let document = client
+            .query("v1/products/wow/versions")
+            .await
+            .expect("Cached query should succeed");
+        assert_eq!(document.rows().len(), 2);
+    }
+}
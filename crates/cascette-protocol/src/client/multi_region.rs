@@ -0,0 +1,419 @@
+//! Facade over one [`RibbitTactClient`] per region.
+//!
+//! A [`ClientConfig`] describes the endpoints of a single region. The
+//! [`MultiRegionClient`] derives a configuration per region from a base
+//! configuration, creates each region's client on first use and shares one
+//! [`ProtocolCache`] between them. Cache keys include the region, so an
+//! `eu` response is never served for a `us` query.
+
+use cascette_formats::bpsv::BpsvDocument;
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use super::{Region, RibbitTactClient};
+use crate::cache::ProtocolCache;
+use crate::config::ClientConfig;
+use crate::error::{ProtocolError, Result};
+
+/// Query outcomes recorded for one region
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegionHealth {
+    /// Number of successful queries, including cache hits
+    pub successes: u64,
+    /// Number of failed queries
+    pub failures: u64,
+    /// Number of failures since the last success
+    pub consecutive_failures: u64,
+    /// Message of the most recent failure
+    pub last_error: Option<String>,
+}
+
+impl RegionHealth {
+    /// Whether the most recent query for the region succeeded
+    ///
+    /// Regions that have not been queried yet count as healthy.
+    pub fn is_healthy(&self) -> bool {
+        self.consecutive_failures == 0
+    }
+
+    fn record<T>(&mut self, result: &Result<T>) {
+        match result {
+            Ok(_) => {
+                self.successes += 1;
+                self.consecutive_failures = 0;
+            }
+            Err(e) => {
+                self.failures += 1;
+                self.consecutive_failures += 1;
+                self.last_error = Some(e.to_string());
+            }
+        }
+    }
+}
+
+/// Client for querying several regions through one shared cache.
+///
+/// ```rust,no_run
+/// use cascette_protocol::{ClientConfig, MultiRegionClient, Region};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = MultiRegionClient::new(
+///         ClientConfig::default(),
+///         [Region::US, Region::EU, Region::KR],
+///     )?;
+///
+///     let eu = client.query(Region::EU, "v1/products/wow/versions").await?;
+///     println!("eu: {} rows", eu.rows().len());
+///
+///     for (region, result) in client.query_all_regions("v1/products/wow/versions").await {
+///         match result {
+///             Ok(versions) => println!("{region}: {} rows", versions.rows().len()),
+///             Err(e) => eprintln!("{region}: {e}"),
+///         }
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub struct MultiRegionClient {
+    base: ClientConfig,
+    regions: Vec<Region>,
+    overrides: HashMap<Region, ClientConfig>,
+    cache: Arc<ProtocolCache>,
+    clients: Mutex<HashMap<Region, Arc<RibbitTactClient>>>,
+    health: Mutex<HashMap<Region, RegionHealth>>,
+    max_concurrency: usize,
+}
+
+impl MultiRegionClient {
+    /// Default number of regions queried at once by
+    /// [`query_all_regions`](Self::query_all_regions)
+    pub const DEFAULT_MAX_CONCURRENCY: usize = 4;
+
+    /// Create a client for `regions`.
+    ///
+    /// Each region's configuration is `base` pointed at that region's
+    /// servers with [`ClientConfig::with_region`]. The shared cache is
+    /// created from `base.cache_config`; no region client is created until
+    /// it is first queried.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError` if the cache cannot be created.
+    pub fn new(base: ClientConfig, regions: impl IntoIterator<Item = Region>) -> Result<Self> {
+        let cache = Arc::new(ProtocolCache::new(&base.cache_config)?);
+        let mut unique = Vec::new();
+        for region in regions {
+            if !unique.contains(&region) {
+                unique.push(region);
+            }
+        }
+
+        Ok(Self {
+            base,
+            regions: unique,
+            overrides: HashMap::new(),
+            cache,
+            clients: Mutex::new(HashMap::new()),
+            health: Mutex::new(HashMap::new()),
+            max_concurrency: Self::DEFAULT_MAX_CONCURRENCY,
+        })
+    }
+
+    /// Limit how many regions [`query_all_regions`](Self::query_all_regions)
+    /// queries at once. Values below 1 are treated as 1.
+    #[must_use]
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Use `config` for `region` instead of deriving it from the base
+    /// configuration, e.g. for private or test servers.
+    ///
+    /// `config.region` is set to `region`, and `region` is added to the
+    /// region list if it is not already part of it.
+    #[must_use]
+    pub fn with_region_config(mut self, region: Region, mut config: ClientConfig) -> Self {
+        config.region = region;
+        self.overrides.insert(region, config);
+        self.clients
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&region);
+        if !self.regions.contains(&region) {
+            self.regions.push(region);
+        }
+        self
+    }
+
+    /// Regions this client queries, in the order they were given
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// Cache shared by all region clients
+    pub fn cache(&self) -> &Arc<ProtocolCache> {
+        &self.cache
+    }
+
+    /// The client for `region`, created on first use.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::UnknownRegion` if `region` is not one of this
+    /// client's regions, or another error if the client cannot be created.
+    pub fn client(&self, region: Region) -> Result<Arc<RibbitTactClient>> {
+        if !self.regions.contains(&region) {
+            return Err(ProtocolError::UnknownRegion(region.to_string()));
+        }
+
+        let mut clients = self.clients.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(client) = clients.get(&region) {
+            return Ok(Arc::clone(client));
+        }
+
+        let config = self
+            .overrides
+            .get(&region)
+            .cloned()
+            .unwrap_or_else(|| self.base.clone().with_region(region));
+        let client = Arc::new(RibbitTactClient::with_cache(
+            config,
+            Arc::clone(&self.cache),
+        )?);
+        clients.insert(region, Arc::clone(&client));
+        drop(clients);
+        Ok(client)
+    }
+
+    /// Query `endpoint` in `region`.
+    ///
+    /// The outcome is recorded in the region's [`RegionHealth`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::UnknownRegion` if `region` is not one of this
+    /// client's regions, or the error of [`RibbitTactClient::query`].
+    pub async fn query(&self, region: Region, endpoint: &str) -> Result<BpsvDocument> {
+        let client = self.client(region)?;
+        let result = client.query(endpoint).await;
+        self.health
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(region)
+            .or_default()
+            .record(&result);
+        result
+    }
+
+    /// Query `endpoint` in every region.
+    ///
+    /// At most [`with_max_concurrency`](Self::with_max_concurrency) regions
+    /// are queried at once. A failing region does not affect the others.
+    pub async fn query_all_regions(&self, endpoint: &str) -> HashMap<Region, Result<BpsvDocument>> {
+        stream::iter(self.regions.iter().copied())
+            .map(|region| async move { (region, self.query(region, endpoint).await) })
+            .buffer_unordered(self.max_concurrency)
+            .collect()
+            .await
+    }
+
+    /// Health of every region, including regions not queried yet
+    pub fn health(&self) -> HashMap<Region, RegionHealth> {
+        let health = self.health.lock().unwrap_or_else(PoisonError::into_inner);
+        self.regions
+            .iter()
+            .map(|region| (*region, health.get(region).cloned().unwrap_or_default()))
+            .collect()
+    }
+
+    /// Health of `region`, or `None` if it is not one of this client's regions
+    pub fn region_health(&self, region: Region) -> Option<RegionHealth> {
+        self.regions.contains(&region).then(|| {
+            self.health
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&region)
+                .cloned()
+                .unwrap_or_default()
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::config::CacheConfig;
+    use tempfile::TempDir;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn versions(region: &str, build: u32) -> String {
+        format!(
+            "Region!STRING:0|BuildConfig!HEX:16|CDNConfig!HEX:16|KeyRing!HEX:16|BuildId!DEC:4|VersionsName!String:0|ProductConfig!HEX:16\n\
+             ## seqn = 1\n\
+             {region}|0123456789abcdef0123456789abcdef|fedcba9876543210fedcba9876543210||{build}|1.0.0.{build}|\n"
+        )
+    }
+
+    /// Mock server answering `wow/versions` exactly once
+    async fn server_for(region: &str, build: u32) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/wow/versions"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(versions(region, build)))
+            .expect(1)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn config_for(server: &MockServer, temp_dir: &TempDir) -> ClientConfig {
+        ClientConfig {
+            tact_https_url: String::new(),
+            tact_http_url: server.uri(),
+            ribbit_url: String::new(),
+            cache_config: CacheConfig {
+                cache_dir: Some(temp_dir.path().to_path_buf()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn build_id(document: &BpsvDocument) -> String {
+        document.rows()[0]
+            .get_raw_by_name("BuildId", document.schema())
+            .expect("BuildId should be present")
+            .to_string()
+    }
+
+    // Regression test: both regions used to share the key
+    // `api/ribbit/wow/versions`, so the second region got the first one's
+    // cached response.
+    #[tokio::test]
+    async fn test_regions_sharing_cache_do_not_collide() {
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let us_server = server_for("us", 1000).await;
+        let eu_server = server_for("eu", 2000).await;
+
+        let cache = Arc::new(
+            ProtocolCache::new(&config_for(&us_server, &temp_dir).cache_config)
+                .expect("Operation should succeed"),
+        );
+        let us =
+            RibbitTactClient::with_cache(config_for(&us_server, &temp_dir), Arc::clone(&cache))
+                .expect("Operation should succeed");
+        let eu = RibbitTactClient::with_cache(
+            ClientConfig {
+                region: Region::EU,
+                ..config_for(&eu_server, &temp_dir)
+            },
+            Arc::clone(&cache),
+        )
+        .expect("Operation should succeed");
+
+        let us_versions = us.query("wow/versions").await.expect("us query");
+        let eu_versions = eu.query("wow/versions").await.expect("eu query");
+        assert_eq!(build_id(&us_versions), "1000");
+        assert_eq!(build_id(&eu_versions), "2000");
+
+        // Repeated queries are served from the shared cache, per region;
+        // the mocks fail the test if they are hit a second time
+        assert_eq!(
+            build_id(&us.query("wow/versions").await.expect("us")),
+            "1000"
+        );
+        assert_eq!(
+            build_id(&eu.query("wow/versions").await.expect("eu")),
+            "2000"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_all_regions() {
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let us_server = server_for("us", 1000).await;
+        let eu_server = server_for("eu", 2000).await;
+        let kr_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/wow/versions"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&kr_server)
+            .await;
+
+        let client = MultiRegionClient::new(config_for(&us_server, &temp_dir), [Region::US])
+            .expect("Operation should succeed")
+            .with_region_config(Region::US, config_for(&us_server, &temp_dir))
+            .with_region_config(Region::EU, config_for(&eu_server, &temp_dir))
+            .with_region_config(Region::KR, config_for(&kr_server, &temp_dir))
+            .with_max_concurrency(2);
+        assert_eq!(client.regions(), [Region::US, Region::EU, Region::KR]);
+
+        let results = client.query_all_regions("wow/versions").await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(build_id(results[&Region::US].as_ref().expect("us")), "1000");
+        assert_eq!(build_id(results[&Region::EU].as_ref().expect("eu")), "2000");
+        assert!(results[&Region::KR].is_err());
+
+        let eu = client
+            .query(Region::EU, "wow/versions")
+            .await
+            .expect("Cached query should succeed");
+        assert_eq!(build_id(&eu), "2000");
+
+        let health = client.health();
+        assert!(health[&Region::US].is_healthy());
+        assert_eq!(health[&Region::EU].successes, 2);
+        assert!(!health[&Region::KR].is_healthy());
+        assert_eq!(health[&Region::KR].failures, 1);
+        assert!(health[&Region::KR].last_error.is_some());
+    }
+
+    fn temp_config(temp_dir: &TempDir) -> ClientConfig {
+        ClientConfig {
+            cache_config: CacheConfig {
+                cache_dir: Some(temp_dir.path().to_path_buf()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_region() {
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let client = MultiRegionClient::new(temp_config(&temp_dir), [Region::US, Region::US])
+            .expect("Operation should succeed");
+        assert_eq!(client.regions(), [Region::US]);
+        assert_eq!(
+            client.region_health(Region::US),
+            Some(RegionHealth::default())
+        );
+        assert_eq!(client.region_health(Region::EU), None);
+
+        let err = client
+            .query(Region::EU, "v1/products/wow/versions")
+            .await
+            .expect_err("EU is not configured");
+        assert!(matches!(err, ProtocolError::UnknownRegion(name) if name == "eu"));
+    }
+
+    #[tokio::test]
+    async fn test_region_clients_are_created_once() {
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let client = MultiRegionClient::new(temp_config(&temp_dir), [Region::US, Region::EU])
+            .expect("Operation should succeed");
+        let eu = client.client(Region::EU).expect("Operation should succeed");
+        assert_eq!(eu.region(), Region::EU);
+        assert!(Arc::ptr_eq(
+            &eu,
+            &client.client(Region::EU).expect("Operation should succeed")
+        ));
+        assert!(Arc::ptr_eq(eu.cache(), client.cache()));
+    }
+}
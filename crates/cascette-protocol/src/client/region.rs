@@ -8,13 +8,19 @@
 //! China uses `.com.cn` domains operated separately from the global
 //! `.battle.net` infrastructure.
 
+use serde::{Deserialize, Serialize};
+
+use crate::error::ProtocolError;
+
 /// Game server region.
 ///
 /// Each region maps to specific TACT and Ribbit endpoints.
 /// China uses `.com.cn` domains; all other regions use `.battle.net`.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Region {
     /// United States
+    #[default]
     US,
     /// Europe
     EU,
@@ -29,6 +35,9 @@ pub enum Region {
 }
 
 impl Region {
+    /// All regions, in declaration order.
+    pub const ALL: [Self; 6] = [Self::US, Self::EU, Self::KR, Self::TW, Self::CN, Self::SG];
+
     /// TACT v2 HTTPS URL for this region (port 443).
     ///
     /// Used by [`TactClient::for_region()`](super::TactClient::for_region).
@@ -83,6 +92,18 @@ impl std::fmt::Display for Region {
     }
 }
 
+impl std::str::FromStr for Region {
+    type Err = ProtocolError;
+
+    /// Parse a region from its lowercase name (`us`, `eu`, ...), ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|region| region.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ProtocolError::UnknownRegion(s.to_string()))
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::expect_used)]
 mod tests {
@@ -160,4 +181,16 @@ mod tests {
         assert_eq!(Region::CN.to_string(), "cn");
         assert_eq!(Region::SG.to_string(), "sg");
     }
+
+    #[test]
+    fn test_region_from_str() {
+        for region in Region::ALL {
+            assert_eq!(region.to_string().parse::<Region>().ok(), Some(region));
+        }
+        assert_eq!("EU".parse::<Region>().ok(), Some(Region::EU));
+        assert!(matches!(
+            "xx".parse::<Region>(),
+            Err(ProtocolError::UnknownRegion(name)) if name == "xx"
+        ));
+    }
 }
@@ -0,0 +1,201 @@
+//! Stale-while-revalidate bookkeeping for [`RibbitTactClient`](super::RibbitTactClient)
+//!
+//! With [`CacheConfig::revalidate_grace`](crate::CacheConfig::revalidate_grace)
+//! set, responses stay in the cache for their TTL plus the grace window. A
+//! second entry next to each response records when its TTL ends, so the
+//! client can tell fresh, stale and expired responses apart:
+//!
+//! - fresh: within the TTL, served from the cache
+//! - stale: past the TTL but within the grace window, served from the
+//!   cache while one background query per key refreshes it
+//! - expired: past the grace window, queried before answering
+//!
+//! The freshness entries hold a Unix timestamp in milliseconds. They are
+//! stored in the same cache, so a disk cache keeps them across restarts.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::cache::ProtocolCache;
+use crate::error::Result;
+
+/// Source of the current time, replaceable in tests
+pub type Clock = Arc<dyn Fn() -> SystemTime + Send + Sync>;
+
+/// How a cached response relates to its TTL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    /// Within the TTL, or stored without a freshness entry
+    Fresh,
+    /// Past the TTL but within the grace window
+    Stale,
+    /// Past the grace window
+    Expired,
+}
+
+/// Grace window, clock and in-flight refreshes of a client
+pub struct Revalidator {
+    grace: Duration,
+    clock: Clock,
+    refreshing: Mutex<HashSet<String>>,
+}
+
+impl std::fmt::Debug for Revalidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Revalidator")
+            .field("grace", &self.grace)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Revalidator {
+    pub fn new(grace: Duration, clock: Clock) -> Self {
+        Self {
+            grace,
+            clock,
+            refreshing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// How long the cache keeps a response with `ttl`
+    pub fn retention(&self, ttl: Duration) -> Duration {
+        ttl.saturating_add(self.grace)
+    }
+
+    /// Freshness of the response whose freshness entry is `fresh_key`
+    ///
+    /// A missing or unreadable entry counts as fresh: the response was
+    /// stored without a grace window and its cache TTL still applies.
+    pub fn freshness(&self, cache: &ProtocolCache, fresh_key: &str) -> Result<Freshness> {
+        let Some(fresh_until) = cache
+            .get(fresh_key)?
+            .and_then(|data| String::from_utf8(data).ok())
+            .and_then(|text| text.parse::<u64>().ok())
+        else {
+            return Ok(Freshness::Fresh);
+        };
+
+        let now = self.now_ms();
+        let grace = u64::try_from(self.grace.as_millis()).unwrap_or(u64::MAX);
+        Ok(if now < fresh_until {
+            Freshness::Fresh
+        } else if now < fresh_until.saturating_add(grace) {
+            Freshness::Stale
+        } else {
+            Freshness::Expired
+        })
+    }
+
+    /// Record that a response stored now is fresh for `ttl`
+    pub fn mark_fresh(&self, cache: &ProtocolCache, fresh_key: &str, ttl: Duration) -> Result<()> {
+        let ttl_ms = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+        let fresh_until = self.now_ms().saturating_add(ttl_ms);
+        cache.store_with_ttl(
+            fresh_key,
+            fresh_until.to_string().as_bytes(),
+            self.retention(ttl),
+        )
+    }
+
+    /// Claim the refresh of `key`, unless one is already running
+    ///
+    /// The claim is released when the returned guard is dropped.
+    pub fn begin_refresh(self: &Arc<Self>, key: &str) -> Option<RefreshGuard> {
+        let claimed = self
+            .refreshing
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key.to_string());
+        claimed.then(|| RefreshGuard {
+            revalidator: Arc::clone(self),
+            key: key.to_string(),
+        })
+    }
+
+    fn now_ms(&self) -> u64 {
+        let since_epoch = (self.clock)()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        u64::try_from(since_epoch.as_millis()).unwrap_or(u64::MAX)
+    }
+}
+
+/// Claim on the background refresh of one cache key
+pub struct RefreshGuard {
+    revalidator: Arc<Revalidator>,
+    key: String,
+}
+
+impl Drop for RefreshGuard {
+    fn drop(&mut self) {
+        self.revalidator
+            .refreshing
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.key);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::CacheConfig;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    fn revalidator(secs: Arc<AtomicU64>) -> Arc<Revalidator> {
+        Arc::new(Revalidator::new(
+            Duration::from_secs(60),
+            Arc::new(move || UNIX_EPOCH + Duration::from_secs(secs.load(Ordering::SeqCst))),
+        ))
+    }
+
+    #[test]
+    fn test_freshness_windows() {
+        let cache = ProtocolCache::new(&CacheConfig::default()).expect("Cache should open");
+        let secs = Arc::new(AtomicU64::new(1_000));
+        let revalidator = revalidator(Arc::clone(&secs));
+
+        assert_eq!(
+            revalidator
+                .freshness(&cache, "fresh")
+                .expect("Lookup should succeed"),
+            Freshness::Fresh
+        );
+        revalidator
+            .mark_fresh(&cache, "fresh", Duration::from_secs(300))
+            .expect("Store should succeed");
+
+        for (at, expected) in [
+            (1_299, Freshness::Fresh),
+            (1_300, Freshness::Stale),
+            (1_359, Freshness::Stale),
+            (1_360, Freshness::Expired),
+        ] {
+            secs.store(at, Ordering::SeqCst);
+            assert_eq!(
+                revalidator
+                    .freshness(&cache, "fresh")
+                    .expect("Lookup should succeed"),
+                expected,
+                "at {at}"
+            );
+        }
+        assert_eq!(
+            revalidator.retention(Duration::from_secs(300)),
+            Duration::from_secs(360)
+        );
+    }
+
+    #[test]
+    fn test_refresh_claim_is_exclusive() {
+        let revalidator = revalidator(Arc::new(AtomicU64::new(0)));
+        let guard = revalidator.begin_refresh("key");
+        assert!(guard.is_some());
+        assert!(revalidator.begin_refresh("key").is_none());
+        assert!(revalidator.begin_refresh("other").is_some());
+        drop(guard);
+        assert!(revalidator.begin_refresh("key").is_some());
+    }
+}
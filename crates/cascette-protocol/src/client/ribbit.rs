@@ -1,14 +1,298 @@
 //! Ribbit TCP protocol implementation
+//!
+//! V1 responses are MIME messages carrying a detached CMS/PKCS#7 signature
+//! over the data part, which is verified by default. V2 responses are raw
+//! BPSV and carry no signature, so there is nothing to verify for them.
+//!
+//! By default every query opens its own connection. With
+//! [`RibbitClient::with_pool`] V1 commands reuse idle connections instead,
+//! which saves a TCP handshake per query when enumerating many products.
+//! A V1 response ends with its `Checksum:` line, so a kept-alive
+//! connection can carry the next command. V2 responses have no terminator
+//! and always use a connection of their own.
+//!
+//! With the `tls` feature, `RibbitClientBuilder::with_tls` wraps each
+//! connection in TLS, for Ribbit servers behind a TLS terminator. Pooled
+//! connections then also save the TLS handshake.
 
 use crate::error::{ProtocolError, Result};
 use crate::mime_parser::{is_v1_mime_response, parse_v1_mime_to_bpsv};
+use crate::v1_mime::certificate::{
+    CertificateFetcher, DEFAULT_CHAIN_DEPTH, parse_pem_certificates,
+};
+use crate::v1_mime::parse_v1_mime_response;
+use crate::v1_mime::signature::verify_signer_with_certificate;
+use crate::v1_mime::types::{CertificateInfo, SignerIdentifier, V1MimeResponse};
 use cascette_formats::CascFormat;
 use cascette_formats::bpsv::BpsvDocument;
-use std::time::Duration;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::collections::HashSet;
+use std::pin::Pin;
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
 use tokio::net::TcpStream;
+#[cfg(feature = "tls")]
+use tokio_rustls::{TlsConnector, client::TlsStream, rustls};
 use tracing::{debug, trace};
 
+/// Time to wait for a complete response
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest accepted response; V1 responses can be large due to signatures
+const MAX_RESPONSE_SIZE: usize = 50 * 1024 * 1024;
+
+/// Settings of the optional connection pool of a [`RibbitClient`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RibbitPoolConfig {
+    /// Number of idle connections kept open at most
+    pub max_idle: usize,
+    /// Idle connections unused for longer than this are closed instead of
+    /// reused
+    ///
+    /// Keep this below the server's own idle timeout, so connections are
+    /// rarely found closed by the server.
+    pub idle_timeout: Duration,
+}
+
+impl Default for RibbitPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_idle: 4,
+            idle_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Connection reuse counters of a pooled [`RibbitClient`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RibbitPoolStats {
+    /// Queries sent on a reused idle connection
+    pub hits: u64,
+    /// Queries that opened a new connection because none was idle
+    pub misses: u64,
+    /// Idle connections found closed by the server and replaced
+    pub reconnects: u64,
+}
+
+/// Connection to a Ribbit server, plain or wrapped in TLS
+#[derive(Debug)]
+enum RibbitStream {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl RibbitStream {
+    /// Underlying TCP connection
+    fn tcp(&self) -> &TcpStream {
+        match self {
+            Self::Plain(stream) => stream,
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => stream.get_ref().0,
+        }
+    }
+}
+
+impl AsyncRead for RibbitStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream.as_mut()).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for RibbitStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream.as_mut()).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream.as_mut()).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream.as_mut()).poll_shutdown(cx),
+        }
+    }
+}
+
+/// Idle connection waiting for the next command
+#[derive(Debug)]
+struct IdleConnection {
+    stream: RibbitStream,
+    since: Instant,
+    /// Responses received on this connection so far
+    served: u64,
+}
+
+/// Outcome of [`ConnectionPool::take`]
+enum Taken {
+    /// An open idle connection
+    Idle(IdleConnection),
+    /// Only connections closed by the server were idle
+    Stale,
+    /// No connection was idle
+    Empty,
+}
+
+#[derive(Debug)]
+struct ConnectionPool {
+    config: RibbitPoolConfig,
+    idle: Mutex<Vec<IdleConnection>>,
+    /// Set once the server is seen closing connections after each
+    /// response; queries then use one-shot connections
+    one_shot: AtomicBool,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    reconnects: AtomicU64,
+}
+
+impl ConnectionPool {
+    fn new(config: RibbitPoolConfig) -> Self {
+        Self {
+            config,
+            idle: Mutex::new(Vec::new()),
+            one_shot: AtomicBool::new(false),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            reconnects: AtomicU64::new(0),
+        }
+    }
+
+    /// Take the most recently used idle connection that is still open
+    ///
+    /// Expired connections are dropped. Connections the server has closed
+    /// are dropped and counted as reconnects.
+    fn take(&self) -> Taken {
+        let mut taken = Taken::Empty;
+        loop {
+            // Popped separately, as a closed connection locks `idle` again
+            let next = self
+                .idle
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .pop();
+            let Some(connection) = next else {
+                return taken;
+            };
+            if connection.since.elapsed() > self.config.idle_timeout {
+                continue;
+            }
+            if is_open(&connection.stream) {
+                return Taken::Idle(connection);
+            }
+            self.server_closed(&connection);
+            taken = Taken::Stale;
+        }
+    }
+
+    /// Return a connection after a complete response
+    fn put(&self, stream: RibbitStream, served: u64) {
+        if self.one_shot.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut idle = self.idle.lock().unwrap_or_else(PoisonError::into_inner);
+        if idle.len() < self.config.max_idle {
+            idle.push(IdleConnection {
+                stream,
+                since: Instant::now(),
+                served,
+            });
+        }
+    }
+
+    /// Record that the server closed an idle connection
+    fn server_closed(&self, connection: &IdleConnection) {
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+        if connection.served <= 1 {
+            self.close_after_response();
+        }
+    }
+
+    /// Switch to one-shot connections for a server that closes after
+    /// each response
+    fn close_after_response(&self) {
+        if !self.one_shot.swap(true, Ordering::Relaxed) {
+            debug!("Ribbit server closes connections after each response, disabling pooling");
+            self.idle
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+        }
+    }
+
+    fn stats(&self) -> RibbitPoolStats {
+        RibbitPoolStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Whether an idle connection is still usable
+///
+/// An idle connection has nothing to read, so end of stream means the
+/// server closed it and unexpected data means it is out of sync. Over TLS
+/// the check reads the underlying TCP connection, where a TLS close alert
+/// counts as unexpected data.
+fn is_open(stream: &RibbitStream) -> bool {
+    let mut byte = [0u8; 1];
+    matches!(stream.tcp().try_read(&mut byte), Err(e) if e.kind() == std::io::ErrorKind::WouldBlock)
+}
+
+/// Whether `buffer` holds a complete response
+///
+/// Only V1 responses are framed, ending with a `Checksum:` line. V2 and
+/// other unframed responses carry no length or terminator, so they end
+/// when the server closes the connection.
+fn is_complete_response(buffer: &[u8]) -> bool {
+    if !is_v1_mime_response(buffer) {
+        return false;
+    }
+
+    let Some(body) = buffer.strip_suffix(b"\n") else {
+        return false;
+    };
+    let body = body.strip_suffix(b"\r").unwrap_or(body);
+    let last_line = body.rsplit(|&b| b == b'\n').next().unwrap_or(body);
+    last_line
+        .strip_prefix(b"Checksum: ")
+        .is_some_and(|checksum| checksum.len() == 64 && checksum.iter().all(u8::is_ascii_hexdigit))
+}
+
+/// Response read from a connection
+struct Exchange {
+    response: Vec<u8>,
+    /// The server closed the connection after the response
+    closed: bool,
+}
+
 /// Ribbit TCP client
 #[derive(Debug)]
 pub struct RibbitClient {
@@ -16,6 +300,170 @@ pub struct RibbitClient {
     host: String,
     port: u16,
     connect_timeout: Duration,
+    verify_signatures: bool,
+    trust_anchors: Vec<CertificateInfo>,
+    /// Public keys of signer certificates already found to chain to an anchor
+    trusted_signers: Mutex<HashSet<Vec<u8>>>,
+    pool: Option<ConnectionPool>,
+    #[cfg(feature = "tls")]
+    tls: Option<TlsSettings>,
+}
+
+/// TLS connector of a [`RibbitClient`] and the name its server is verified
+/// against
+#[cfg(feature = "tls")]
+#[derive(Clone)]
+struct TlsSettings {
+    connector: TlsConnector,
+    server_name: rustls::pki_types::ServerName<'static>,
+}
+
+#[cfg(feature = "tls")]
+impl std::fmt::Debug for TlsSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlsSettings")
+            .field("server_name", &self.server_name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builder for a [`RibbitClient`] connecting to an explicit host and port
+///
+/// [`RibbitClient::new`] and [`RibbitClient::for_region`] cover the public
+/// Ribbit servers. The builder is meant for other servers, such as a local
+/// `cascette-ribbit` instance or a server behind a TLS terminator.
+///
+/// ```no_run
+/// # use cascette_protocol::RibbitClientBuilder;
+/// let client = RibbitClientBuilder::new("127.0.0.1", 1119)
+///     .verify_signatures(false)
+///     .build()?;
+/// # Ok::<(), cascette_protocol::ProtocolError>(())
+/// ```
+#[derive(Debug)]
+pub struct RibbitClientBuilder {
+    host: String,
+    port: u16,
+    connect_timeout: Duration,
+    verify_signatures: bool,
+    trust_anchors: Vec<CertificateInfo>,
+    pool: Option<RibbitPoolConfig>,
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<rustls::ClientConfig>>,
+}
+
+impl RibbitClientBuilder {
+    /// Start a client for `host` on `port`
+    pub fn new(host: &str, port: u16) -> Self {
+        Self {
+            host: host.to_string(),
+            port,
+            connect_timeout: Duration::from_secs(10),
+            verify_signatures: true,
+            trust_anchors: Vec::new(),
+            pool: None,
+            #[cfg(feature = "tls")]
+            tls: None,
+        }
+    }
+
+    /// Time to wait for a connection, including the TLS handshake
+    ///
+    /// Defaults to 10 seconds.
+    #[must_use]
+    pub const fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Enable or disable signature verification of V1 MIME responses
+    ///
+    /// See [`RibbitClient::verify_signatures`].
+    #[must_use]
+    pub const fn verify_signatures(mut self, verify: bool) -> Self {
+        self.verify_signatures = verify;
+        self
+    }
+
+    /// Trust the certificates of a PEM bundle to sign V1 responses
+    ///
+    /// See [`RibbitClient::with_trust_anchors_pem`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProtocolError::Parse`] if the bundle holds no certificate
+    /// or one of them cannot be parsed.
+    pub fn with_trust_anchors_pem(mut self, pem: &str) -> Result<Self> {
+        self.trust_anchors.extend(parse_pem_certificates(pem)?);
+        Ok(self)
+    }
+
+    /// Reuse connections for V1 commands
+    ///
+    /// See [`RibbitClient::with_pool`].
+    #[must_use]
+    pub const fn with_pool(mut self, config: RibbitPoolConfig) -> Self {
+        self.pool = Some(config);
+        self
+    }
+
+    /// Wrap every connection in TLS using `config`
+    ///
+    /// The server certificate is verified against the host passed to
+    /// [`new`](Self::new), using the roots and verifier of `config`. A
+    /// config built with `rustls::ClientConfig::builder()` uses the process
+    /// default crypto provider, which must be installed beforehand.
+    #[cfg(feature = "tls")]
+    #[must_use]
+    pub fn with_tls(mut self, config: rustls::ClientConfig) -> Self {
+        self.tls = Some(Arc::new(config));
+        self
+    }
+
+    /// Build the client
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProtocolError::InvalidEndpoint`] if the host is empty, or
+    /// with TLS enabled, if it is not a valid DNS name or IP address.
+    pub fn build(self) -> Result<RibbitClient> {
+        if self.host.is_empty() {
+            return Err(ProtocolError::InvalidEndpoint(
+                "Ribbit host is empty".to_string(),
+            ));
+        }
+
+        #[cfg(feature = "tls")]
+        let tls = self
+            .tls
+            .map(|config| {
+                let server_name = rustls::pki_types::ServerName::try_from(self.host.clone())
+                    .map_err(|e| {
+                        ProtocolError::InvalidEndpoint(format!(
+                            "Invalid TLS server name {}: {e}",
+                            self.host
+                        ))
+                    })?;
+                Ok::<_, ProtocolError>(TlsSettings {
+                    connector: TlsConnector::from(config),
+                    server_name,
+                })
+            })
+            .transpose()?;
+
+        Ok(RibbitClient {
+            url: format!("tcp://{}:{}", self.host, self.port),
+            host: self.host,
+            port: self.port,
+            connect_timeout: self.connect_timeout,
+            verify_signatures: self.verify_signatures,
+            trust_anchors: self.trust_anchors,
+            trusted_signers: Mutex::default(),
+            pool: self.pool.map(ConnectionPool::new),
+            #[cfg(feature = "tls")]
+            tls,
+        })
+    }
 }
 
 impl RibbitClient {
@@ -44,9 +492,83 @@ impl RibbitClient {
             host,
             port,
             connect_timeout: Duration::from_secs(10),
+            verify_signatures: true,
+            trust_anchors: Vec::new(),
+            trusted_signers: Mutex::default(),
+            pool: None,
+            #[cfg(feature = "tls")]
+            tls: None,
         })
     }
 
+    /// Reuse connections for V1 commands
+    ///
+    /// Up to `config.max_idle` connections are kept open after a response
+    /// and reused by later queries. An idle connection the server has
+    /// closed is replaced transparently. If the server closes connections
+    /// after each response, the client falls back to one-shot connections.
+    ///
+    /// A client talks to one host, so with one client per region this
+    /// keeps a pool per region.
+    #[must_use]
+    pub fn with_pool(mut self, config: RibbitPoolConfig) -> Self {
+        self.pool = Some(ConnectionPool::new(config));
+        self
+    }
+
+    /// Connection reuse counters, if pooling is enabled
+    pub fn pool_stats(&self) -> Option<RibbitPoolStats> {
+        self.pool.as_ref().map(ConnectionPool::stats)
+    }
+
+    /// Enable or disable signature verification of V1 MIME responses
+    ///
+    /// Enabled by default. With verification enabled, a V1 response whose
+    /// signature is missing or does not verify against the signer
+    /// certificate is rejected with
+    /// [`ProtocolError::SignatureVerificationFailed`]. When the signature
+    /// does not embed the signer certificate it is fetched from the
+    /// `certs/{ski}` endpoint and must carry the expected Subject Key
+    /// Identifier.
+    ///
+    /// When trust anchors are set with
+    /// [`with_trust_anchors_pem`](Self::with_trust_anchors_pem), the signer
+    /// certificate must also be one of them or chain to one through the
+    /// `certs/{ski}` endpoint. Without trust anchors the signature is only
+    /// checked against the signer certificate, which proves the response
+    /// was not altered but not who signed it.
+    ///
+    /// Disable this only for servers that do not sign their responses, such
+    /// as local test servers. V2 responses are raw BPSV without a signature
+    /// and are never verified.
+    #[must_use]
+    pub const fn verify_signatures(mut self, verify: bool) -> Self {
+        self.verify_signatures = verify;
+        self
+    }
+
+    /// Trust the certificates of a PEM bundle to sign V1 responses
+    ///
+    /// A V1 signer is trusted when its certificate has the public key of
+    /// an anchor, or when its chain, fetched by Authority Key Identifier
+    /// from the `certs/{ski}` endpoint, is signed link by link up to an
+    /// anchor. Certificates offered by the server are never trusted on
+    /// their own. Anchors accumulate over repeated calls.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProtocolError::Parse`] if the bundle holds no certificate
+    /// or one of them cannot be parsed.
+    pub fn with_trust_anchors_pem(mut self, pem: &str) -> Result<Self> {
+        self.trust_anchors.extend(parse_pem_certificates(pem)?);
+        Ok(self)
+    }
+
+    /// Whether V1 MIME response signatures are verified
+    pub const fn verifies_signatures(&self) -> bool {
+        self.verify_signatures
+    }
+
     /// Create a Ribbit TCP client for a specific region.
     pub fn for_region(region: super::Region) -> Result<Self> {
         Self::new(format!("tcp://{}", region.ribbit_address()))
@@ -61,20 +583,177 @@ impl RibbitClient {
     /// Query Ribbit endpoint with V1 MIME support
     pub async fn query_v1_mime(&self, endpoint: &str) -> Result<BpsvDocument> {
         let raw_response = self.query_raw(endpoint).await?;
+        self.parse_response(&raw_response).await
+    }
 
+    /// Parse a raw response from [`query_raw`](Self::query_raw)
+    ///
+    /// V1 MIME responses have their signature verified unless disabled
+    /// with [`verify_signatures`](Self::verify_signatures).
+    pub async fn parse_response(&self, raw_response: &[u8]) -> Result<BpsvDocument> {
         // Detect if this is a V1 MIME response
-        if is_v1_mime_response(&raw_response) {
+        if is_v1_mime_response(raw_response) {
+            if !self.verify_signatures {
+                debug!("Detected V1 MIME response, signature verification disabled");
+                return parse_v1_mime_to_bpsv(raw_response);
+            }
+
             debug!("Detected V1 MIME response, parsing with signature verification");
-            // Use the new MIME parser for V1 responses
-            parse_v1_mime_to_bpsv(&raw_response)
+            let response = parse_v1_mime_response(raw_response, None)?;
+            self.verify_v1_signature(&response).await?;
+
+            BpsvDocument::parse(response.data.as_bytes())
+                .map_err(|e| ProtocolError::Parse(format!("BPSV parse error: {e}")))
         } else {
             debug!("Detected V2 text response, parsing directly as BPSV");
             // Parse V2 response directly as BPSV
-            BpsvDocument::parse(&raw_response)
+            BpsvDocument::parse(raw_response)
                 .map_err(|e| ProtocolError::Parse(format!("BPSV parse error: {e}")))
         }
     }
 
+    /// Verify every signer of a parsed V1 response
+    ///
+    /// Signers are checked against their embedded certificate when the
+    /// signature carries one, otherwise against the certificate fetched by
+    /// Subject Key Identifier. With trust anchors configured, each signer
+    /// certificate must then be trusted, see
+    /// [`with_trust_anchors_pem`](Self::with_trust_anchors_pem).
+    async fn verify_v1_signature(&self, response: &V1MimeResponse) -> Result<()> {
+        let failed = |reason: String| ProtocolError::SignatureVerificationFailed(reason);
+
+        let signature = response
+            .signature_info
+            .as_ref()
+            .ok_or_else(|| failed("response carries no valid signature".to_string()))?;
+        if signature.signers.is_empty() {
+            return Err(failed("signature has no signers".to_string()));
+        }
+        let signed_content = response
+            .signed_content
+            .as_deref()
+            .ok_or_else(|| failed("response has no signed data part".to_string()))?;
+
+        for signer in &signature.signers {
+            let certificate = if let Some(certificate) = &signer.certificate {
+                certificate.clone()
+            } else {
+                let SignerIdentifier::SubjectKeyIdentifier(ski) = &signer.identifier else {
+                    return Err(failed(format!(
+                        "no certificate available for signer {}",
+                        signer.identifier
+                    )));
+                };
+
+                let certificate = CertificateFetcher::new(self)
+                    .fetch_by_ski(ski)
+                    .await
+                    .map_err(|e| failed(format!("failed to fetch certificate {ski}: {e}")))?;
+
+                let fetched_ski = certificate.subject_key_identifier.as_deref();
+                if !fetched_ski.is_some_and(|fetched| fetched.eq_ignore_ascii_case(ski)) {
+                    return Err(failed(format!(
+                        "fetched certificate has SKI {}, expected {ski}",
+                        fetched_ski.unwrap_or("<none>")
+                    )));
+                }
+                certificate
+            };
+
+            let valid = verify_signer_with_certificate(signer, signed_content, &certificate)
+                .map_err(|e| failed(format!("signer {}: {e}", signer.identifier)))?;
+            if !valid {
+                return Err(failed(format!(
+                    "signature from {} does not match response data",
+                    signer.identifier
+                )));
+            }
+
+            if self.trust_anchors.is_empty() {
+                debug!(
+                    "No trust anchors configured, not checking the chain of signer {}",
+                    signer.identifier
+                );
+                continue;
+            }
+            self.ensure_trusted(&certificate).await.map_err(|reason| {
+                failed(format!(
+                    "signer {} is not trusted: {reason}",
+                    signer.identifier
+                ))
+            })?;
+        }
+
+        debug!(
+            "Verified V1 signature from {} signer(s)",
+            signature.signers.len()
+        );
+        Ok(())
+    }
+
+    /// Check that a signer certificate is or chains to a trust anchor
+    ///
+    /// Returns the reason when it does not. Trusted signer keys are
+    /// remembered, so the chain is fetched once per signer.
+    async fn ensure_trusted(
+        &self,
+        certificate: &CertificateInfo,
+    ) -> std::result::Result<(), String> {
+        let key = certificate
+            .public_key
+            .as_ref()
+            .map(|key| key.key_bytes.clone())
+            .ok_or_else(|| "certificate has no public key".to_string())?;
+
+        let anchored = |signers: &HashSet<Vec<u8>>| {
+            signers.contains(&key)
+                || self.trust_anchors.iter().any(|anchor| {
+                    anchor
+                        .public_key
+                        .as_ref()
+                        .is_some_and(|anchor| anchor.key_bytes == key)
+                })
+        };
+        if anchored(
+            &self
+                .trusted_signers
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        ) {
+            return Ok(());
+        }
+
+        let ski = certificate
+            .subject_key_identifier
+            .as_deref()
+            .ok_or_else(|| "certificate has no SKI to fetch its chain by".to_string())?;
+        let chain = CertificateFetcher::new(self)
+            .fetch_chain(ski, DEFAULT_CHAIN_DEPTH)
+            .await
+            .map_err(|e| format!("failed to fetch certificate chain {ski}: {e}"))?;
+
+        let same_leaf = chain
+            .certificates
+            .first()
+            .and_then(|leaf| leaf.info.public_key.as_ref())
+            .is_some_and(|leaf| leaf.key_bytes == key);
+        if !same_leaf {
+            return Err(format!("chain for {ski} starts with a different key"));
+        }
+        if !chain
+            .is_anchored(&self.trust_anchors)
+            .map_err(|e| e.to_string())?
+        {
+            return Err(format!("chain for {ski} does not reach a trust anchor"));
+        }
+
+        self.trusted_signers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key);
+        Ok(())
+    }
+
     /// Query Ribbit endpoint and return raw response bytes
     pub async fn query_raw(&self, endpoint: &str) -> Result<Vec<u8>> {
         // Ribbit TCP accepts the full v1/products/ path
@@ -82,7 +761,13 @@ impl RibbitClient {
 
         // Connect to the single configured host
         let addr = format!("{}:{}", self.host, self.port);
-        self.query_host_raw(&addr, &command).await.map_err(|e| {
+        let result = match &self.pool {
+            Some(pool) if endpoint.starts_with("v1/") && !pool.one_shot.load(Ordering::Relaxed) => {
+                self.query_pooled(pool, &addr, &command).await
+            }
+            _ => self.query_host_raw(&addr, &command).await,
+        };
+        result.map_err(|e| {
             tracing::warn!("Failed to query {}: {}", self.url, e);
             e
         })
@@ -97,69 +782,79 @@ impl RibbitClient {
             .map_err(|e| ProtocolError::Parse(format!("Invalid UTF-8 response: {e}")))
     }
 
-    async fn query_host_raw(&self, host: &str, command: &str) -> Result<Vec<u8>> {
+    async fn connect(&self, host: &str) -> Result<RibbitStream> {
         trace!("Connecting to Ribbit host: {}", host);
 
         // Strip tcp:// prefix if present for TcpStream::connect
         let connect_addr = host.strip_prefix("tcp://").unwrap_or(host);
 
-        // Connect with timeout
-        let stream = tokio::time::timeout(self.connect_timeout, TcpStream::connect(connect_addr))
-            .await
-            .map_err(|_| ProtocolError::Timeout)??;
-
-        let mut stream = stream;
-
-        // Send command
-        trace!("Sending command: {}", command.trim());
-        stream.write_all(command.as_bytes()).await?;
-
-        // Shutdown write side to signal we're done sending
-        stream.shutdown().await?;
-
-        // Read response with a timeout
-        let read_timeout = Duration::from_secs(30);
-        let mut buffer = Vec::new();
-        let mut temp_buf = [0u8; 8192]; // Larger buffer for MIME responses
+        // Connect with timeout, which also covers the TLS handshake
+        tokio::time::timeout(self.connect_timeout, async {
+            let stream = TcpStream::connect(connect_addr).await?;
+            #[cfg(feature = "tls")]
+            if let Some(tls) = &self.tls {
+                trace!("Starting TLS handshake with {:?}", tls.server_name);
+                let stream = tls
+                    .connector
+                    .connect(tls.server_name.clone(), stream)
+                    .await?;
+                return Ok(RibbitStream::Tls(Box::new(stream)));
+            }
+            Ok(RibbitStream::Plain(stream))
+        })
+        .await
+        .map_err(|_| ProtocolError::Timeout)?
+    }
 
-        let read_result = tokio::time::timeout(read_timeout, async {
-            loop {
-                match stream.read(&mut temp_buf).await {
-                    Ok(0) => break, // Connection closed
-                    Ok(n) => {
-                        buffer.extend_from_slice(&temp_buf[..n]);
-
-                        // For V2 responses, check for double newline terminator
-                        // For V1 MIME responses, we need to read until connection closes
-                        // or we detect the complete MIME structure
-                        if buffer.ends_with(b"\n\n") {
-                            // Check if this might be a V1 MIME response that's not complete
-                            if !is_v1_mime_response(&buffer) {
-                                break;
-                            }
-                        }
+    /// Send `command` on a one-shot connection
+    async fn query_host_raw(&self, host: &str, command: &str) -> Result<Vec<u8>> {
+        let mut stream = self.connect(host).await?;
+        Ok(exchange(&mut stream, command, false).await?.response)
+    }
 
-                        // Safety limit - V1 responses can be larger due to signatures
-                        if buffer.len() > 50 * 1024 * 1024 {
-                            // 50MB max for V1 MIME responses
-                            return Err(ProtocolError::Parse("Response too large".to_string()));
+    /// Send `command` on an idle connection, or on a new one if none is
+    /// usable
+    async fn query_pooled(
+        &self,
+        pool: &ConnectionPool,
+        host: &str,
+        command: &str,
+    ) -> Result<Vec<u8>> {
+        match pool.take() {
+            Taken::Idle(mut connection) => {
+                match exchange(&mut connection.stream, command, true).await {
+                    Ok(exchange) => {
+                        pool.hits.fetch_add(1, Ordering::Relaxed);
+                        if exchange.closed {
+                            pool.close_after_response();
+                        } else {
+                            pool.put(connection.stream, connection.served + 1);
                         }
+                        return Ok(exchange.response);
                     }
-                    Err(e) => return Err(e.into()),
+                    // The server closed the connection before answering; it
+                    // went stale between the liveness check and the command
+                    Err(ProtocolError::Network(e)) => {
+                        trace!("Pooled Ribbit connection failed, reconnecting: {}", e);
+                        pool.server_closed(&connection);
+                    }
+                    Err(e) => return Err(e),
                 }
             }
-            Ok(())
-        })
-        .await;
-
-        match read_result {
-            Ok(Ok(())) => {
-                trace!("Received response: {} bytes", buffer.len());
-                Ok(buffer)
+            Taken::Stale => {}
+            Taken::Empty => {
+                pool.misses.fetch_add(1, Ordering::Relaxed);
             }
-            Ok(Err(e)) => Err(e),
-            Err(_) => Err(ProtocolError::Timeout),
         }
+
+        let mut stream = self.connect(host).await?;
+        let exchange = exchange(&mut stream, command, true).await?;
+        if exchange.closed {
+            pool.close_after_response();
+        } else {
+            pool.put(stream, 1);
+        }
+        Ok(exchange.response)
     }
 
     #[allow(dead_code)]
@@ -204,11 +899,67 @@ impl RibbitClient {
     }
 }
 
+/// Send `command` and read its response
+///
+/// A one-shot exchange shuts down the write side after the command, so the
+/// server sees the end of the request. A kept-alive exchange leaves the
+/// connection open and stops reading once the response is complete.
+///
+/// Returns `ProtocolError::Network` with `UnexpectedEof` if the server closes
+/// the connection without sending anything.
+async fn exchange(stream: &mut RibbitStream, command: &str, keep_alive: bool) -> Result<Exchange> {
+    trace!("Sending command: {}", command.trim());
+    stream.write_all(command.as_bytes()).await?;
+    if !keep_alive {
+        // Shutdown write side to signal we're done sending
+        stream.shutdown().await?;
+    }
+
+    let mut buffer = Vec::new();
+    let mut temp_buf = [0u8; 8192]; // Larger buffer for MIME responses
+
+    let closed = tokio::time::timeout(READ_TIMEOUT, async {
+        loop {
+            let n = stream.read(&mut temp_buf).await?;
+            if n == 0 {
+                // Connection closed
+                if buffer.is_empty() {
+                    return Err(ProtocolError::Network(
+                        std::io::ErrorKind::UnexpectedEof.into(),
+                    ));
+                }
+                return Ok(true);
+            }
+            buffer.extend_from_slice(&temp_buf[..n]);
+
+            if is_complete_response(&buffer) {
+                return Ok(false);
+            }
+            if buffer.len() > MAX_RESPONSE_SIZE {
+                return Err(ProtocolError::Parse("Response too large".to_string()));
+            }
+        }
+    })
+    .await
+    .map_err(|_| ProtocolError::Timeout)??;
+
+    trace!("Received response: {} bytes", buffer.len());
+    Ok(Exchange {
+        response: buffer,
+        closed,
+    })
+}
+
 #[cfg(test)]
 #[allow(clippy::expect_used)]
 mod tests {
     use super::*;
     use crate::error::ProtocolError;
+    use crate::v1_mime::test_fixtures::{
+        ISSUED_SIGNER_CERT_PEM, ISSUED_SIGNER_SKI, SIGNATURE_BY_ISSUED_SIGNER, SIGNATURE_WITH_CERT,
+        SIGNATURE_WITHOUT_CERT, SIGNER_CERT_PEM, SIGNING_ROOT_PEM, SIGNING_ROOT_SKI, VERSIONS_BPSV,
+        signed_mime,
+    };
     use std::net::SocketAddr;
     use std::sync::Arc;
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -284,6 +1035,199 @@ mod tests {
         }
     }
 
+    /// V1 MIME response ending with a valid `Checksum:` line
+    fn checksummed_mime() -> Vec<u8> {
+        use sha2::{Digest, Sha256};
+
+        let mut response = b"MIME-Version: 1.0\r\n\
+            Content-Type: multipart/alternative; boundary=\"BOUNDARY\"\r\n\
+            \r\n\
+            --BOUNDARY\r\n\
+            Content-Disposition: version\r\n\
+            \r\n"
+            .to_vec();
+        response.extend_from_slice(VERSIONS_BPSV);
+        response.extend_from_slice(b"\r\n--BOUNDARY--\r\n");
+        let checksum = hex::encode(Sha256::digest(&response));
+        response.extend_from_slice(format!("Checksum: {checksum}\r\n").as_bytes());
+        response
+    }
+
+    /// Serve `checksummed_mime` for every command line, closing each
+    /// connection after `per_connection` responses.
+    ///
+    /// Returns the address and the number of accepted connections.
+    async fn spawn_keep_alive_server(
+        per_connection: usize,
+    ) -> (SocketAddr, Arc<std::sync::atomic::AtomicUsize>) {
+        use tokio::io::{AsyncBufReadExt, BufReader};
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Operation should succeed");
+        let addr = listener.local_addr().expect("Operation should succeed");
+        let accepted = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = Arc::clone(&accepted);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut reader = BufReader::new(stream);
+                    for _ in 0..per_connection {
+                        let mut command = String::new();
+                        if reader.read_line(&mut command).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                        let _ = reader.get_mut().write_all(&checksummed_mime()).await;
+                    }
+                    let _ = reader.get_mut().shutdown().await;
+                });
+            }
+        });
+        (addr, accepted)
+    }
+
+    fn pooled_client(addr: SocketAddr, config: RibbitPoolConfig) -> RibbitClient {
+        RibbitClient::new(format!("tcp://{addr}"))
+            .expect("Operation should succeed")
+            .verify_signatures(false)
+            .with_pool(config)
+    }
+
+    #[test]
+    fn test_is_complete_response() {
+        let mime = checksummed_mime();
+        assert!(is_complete_response(&mime));
+        assert!(!is_complete_response(&mime[..mime.len() - 1]));
+        assert!(!is_complete_response(&mime[..mime.len() - 10]));
+        // A blank line does not end an unframed response
+        assert!(!is_complete_response(b"Region!STRING:0\nus\n\n"));
+        assert!(!is_complete_response(b"Region!STRING:0\nus\n"));
+    }
+
+    #[tokio::test]
+    async fn test_pooled_client_reuses_connection() {
+        let (addr, accepted) = spawn_keep_alive_server(usize::MAX).await;
+        let client = pooled_client(addr, RibbitPoolConfig::default());
+        assert_eq!(client.pool_stats(), Some(RibbitPoolStats::default()));
+
+        for _ in 0..3 {
+            let doc = client
+                .query("v1/products/wow/versions")
+                .await
+                .expect("Pooled query should succeed");
+            assert_eq!(doc.rows().len(), 1);
+        }
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            client.pool_stats(),
+            Some(RibbitPoolStats {
+                hits: 2,
+                misses: 1,
+                reconnects: 0,
+            })
+        );
+
+        // V2 commands bypass the pool
+        let raw = client
+            .query_raw("v2/products/wow/versions")
+            .await
+            .expect("One-shot query should succeed");
+        assert!(!raw.is_empty());
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+        assert_eq!(client.pool_stats().map(|stats| stats.hits), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_pooled_client_replaces_closed_connection() {
+        let (addr, accepted) = spawn_keep_alive_server(2).await;
+        let client = pooled_client(addr, RibbitPoolConfig::default());
+
+        for _ in 0..2 {
+            client
+                .query("v1/products/wow/versions")
+                .await
+                .expect("Pooled query should succeed");
+        }
+        // Let the server's close arrive
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        for _ in 0..2 {
+            client
+                .query("v1/products/wow/versions")
+                .await
+                .expect("Query after reconnect should succeed");
+        }
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            client.pool_stats(),
+            Some(RibbitPoolStats {
+                hits: 2,
+                misses: 1,
+                reconnects: 1,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pooled_client_falls_back_to_one_shot() {
+        let (addr, accepted) = spawn_keep_alive_server(1).await;
+        let client = pooled_client(addr, RibbitPoolConfig::default());
+
+        for _ in 0..4 {
+            client
+                .query("v1/products/wow/versions")
+                .await
+                .expect("Query should succeed against a closing server");
+        }
+        assert_eq!(accepted.load(Ordering::SeqCst), 4);
+
+        // The first connection is found closed once, then pooling stops
+        assert_eq!(
+            client.pool_stats(),
+            Some(RibbitPoolStats {
+                hits: 0,
+                misses: 1,
+                reconnects: 1,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pooled_client_idle_timeout() {
+        let (addr, accepted) = spawn_keep_alive_server(usize::MAX).await;
+        let client = pooled_client(
+            addr,
+            RibbitPoolConfig {
+                max_idle: 4,
+                idle_timeout: Duration::ZERO,
+            },
+        );
+
+        for _ in 0..2 {
+            client
+                .query("v1/products/wow/versions")
+                .await
+                .expect("Pooled query should succeed");
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            client.pool_stats(),
+            Some(RibbitPoolStats {
+                hits: 0,
+                misses: 2,
+                reconnects: 0,
+            })
+        );
+        assert_eq!(
+            RibbitClient::new("host:1119")
+                .expect("Operation should succeed")
+                .pool_stats(),
+            None
+        );
+    }
+
     #[tokio::test]
     async fn test_ribbit_client_creation() {
         let url = "tcp://host1:1119".to_string();
@@ -437,4 +1381,312 @@ mod tests {
         let client = RibbitClient::new("host:1119".to_string()).expect("Operation should succeed");
         assert_eq!(client.connect_timeout, Duration::from_secs(10));
     }
+
+    #[test]
+    fn test_builder_defaults() {
+        let client = RibbitClientBuilder::new("ribbit.example.com", 1120)
+            .build()
+            .expect("Operation should succeed");
+        assert_eq!(client.host, "ribbit.example.com");
+        assert_eq!(client.port, 1120);
+        assert_eq!(client.url, "tcp://ribbit.example.com:1120");
+        assert_eq!(client.connect_timeout, Duration::from_secs(10));
+        assert!(client.verifies_signatures());
+        assert!(client.pool_stats().is_none());
+    }
+
+    #[test]
+    fn test_builder_options() {
+        let client = RibbitClientBuilder::new("127.0.0.1", 1119)
+            .connect_timeout(Duration::from_secs(3))
+            .verify_signatures(false)
+            .with_pool(RibbitPoolConfig::default())
+            .build()
+            .expect("Operation should succeed");
+        assert_eq!(client.connect_timeout, Duration::from_secs(3));
+        assert!(!client.verifies_signatures());
+        assert_eq!(client.pool_stats(), Some(RibbitPoolStats::default()));
+    }
+
+    #[test]
+    fn test_builder_rejects_empty_host() {
+        let result = RibbitClientBuilder::new("", 1119).build();
+        assert!(matches!(result, Err(ProtocolError::InvalidEndpoint(_))));
+    }
+
+    #[tokio::test]
+    async fn test_builder_client_queries_plain_server() {
+        let (addr, accepted) = spawn_keep_alive_server(usize::MAX).await;
+        let client = RibbitClientBuilder::new("127.0.0.1", addr.port())
+            .verify_signatures(false)
+            .build()
+            .expect("Operation should succeed");
+
+        let doc = client
+            .query("v1/products/wow/versions")
+            .await
+            .expect("Query should succeed");
+        assert_eq!(doc.rows().len(), 1);
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_builder_rejects_invalid_tls_server_name() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config = rustls::ClientConfig::builder()
+            .with_root_certificates(rustls::RootCertStore::empty())
+            .with_no_client_auth();
+        let result = RibbitClientBuilder::new("not a host name", 1119)
+            .with_tls(config)
+            .build();
+        assert!(matches!(result, Err(ProtocolError::InvalidEndpoint(_))));
+
+        // Without TLS the name is only checked when connecting
+        assert!(
+            RibbitClientBuilder::new("not a host name", 1119)
+                .build()
+                .is_ok()
+        );
+    }
+
+    /// Serve canned responses chosen by command prefix until aborted
+    fn spawn_routed_server(
+        listener: TcpListener,
+        routes: Vec<(String, Vec<u8>)>,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buffer = [0; 1024];
+                let Ok(n) = stream.read(&mut buffer).await else {
+                    continue;
+                };
+                let command = String::from_utf8_lossy(&buffer[..n]).to_string();
+                if let Some((_, response)) = routes
+                    .iter()
+                    .find(|(prefix, _)| command.starts_with(prefix.as_str()))
+                {
+                    let _ = stream.write_all(response).await;
+                }
+                let _ = stream.shutdown().await;
+            }
+        })
+    }
+
+    async fn query_routed(
+        client_verifies: bool,
+        routes: Vec<(&'static str, Vec<u8>)>,
+    ) -> Result<BpsvDocument> {
+        let routes = routes
+            .into_iter()
+            .map(|(prefix, response)| (prefix.to_string(), response))
+            .collect();
+        query_anchored(client_verifies, Some(SIGNER_CERT_PEM), routes).await
+    }
+
+    async fn query_anchored(
+        client_verifies: bool,
+        trust_anchors: Option<&str>,
+        routes: Vec<(String, Vec<u8>)>,
+    ) -> Result<BpsvDocument> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Operation should succeed");
+        let addr = listener.local_addr().expect("Operation should succeed");
+        let server_handle = spawn_routed_server(listener, routes);
+
+        let mut client = RibbitClient::new(format!("tcp://{addr}"))
+            .expect("Operation should succeed")
+            .verify_signatures(client_verifies);
+        if let Some(pem) = trust_anchors {
+            client = client
+                .with_trust_anchors_pem(pem)
+                .expect("Trust anchors should parse");
+        }
+        let result = client.query("v1/products/wow/versions").await;
+
+        server_handle.abort();
+        result
+    }
+
+    #[test]
+    fn test_signature_verification_enabled_by_default() {
+        let client = RibbitClient::new("host:1119".to_string()).expect("Operation should succeed");
+        assert!(client.verifies_signatures());
+        assert!(!client.verify_signatures(false).verifies_signatures());
+    }
+
+    #[tokio::test]
+    async fn test_v1_signature_with_embedded_certificate() {
+        let response = signed_mime(SIGNATURE_WITH_CERT);
+        let doc = query_routed(true, vec![("v1/products", response)])
+            .await
+            .expect("Signed response should verify");
+
+        assert_eq!(doc.rows().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_v1_tampered_response_rejected() {
+        let mut response = signed_mime(SIGNATURE_WITH_CERT);
+        let pos = response
+            .windows(5)
+            .position(|w| w == b"61265")
+            .expect("Build id should be present");
+        response[pos] = b'9';
+
+        let result = query_routed(true, vec![("v1/products", response)]).await;
+
+        assert!(matches!(
+            result,
+            Err(ProtocolError::SignatureVerificationFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_v1_unsigned_response_requires_opt_out() {
+        let mut response = b"MIME-Version: 1.0\r\n\
+            Content-Type: multipart/alternative; boundary=\"BOUNDARY\"\r\n\
+            \r\n\
+            --BOUNDARY\r\n\
+            Content-Disposition: version\r\n\
+            \r\n"
+            .to_vec();
+        response.extend_from_slice(VERSIONS_BPSV);
+        response.extend_from_slice(b"\r\n--BOUNDARY--\r\n");
+
+        let result = query_routed(true, vec![("v1/products", response.clone())]).await;
+        assert!(matches!(
+            result,
+            Err(ProtocolError::SignatureVerificationFailed(_))
+        ));
+
+        let doc = query_routed(false, vec![("v1/products", response)])
+            .await
+            .expect("Unsigned response should parse with verification disabled");
+        assert_eq!(doc.rows().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_v1_signature_with_fetched_certificate() {
+        let response = signed_mime(SIGNATURE_WITHOUT_CERT);
+        let doc = query_routed(
+            true,
+            vec![
+                ("v1/products", response),
+                ("certs/", SIGNER_CERT_PEM.as_bytes().to_vec()),
+            ],
+        )
+        .await
+        .expect("Signature should verify with the fetched certificate");
+
+        assert_eq!(doc.rows().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_v1_fetched_certificate_ski_mismatch() {
+        let other_cert = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/ribbit_other_signer.pem"
+        ));
+        let response = signed_mime(SIGNATURE_WITHOUT_CERT);
+        let result = query_routed(
+            true,
+            vec![
+                ("v1/products", response),
+                ("certs/", other_cert.as_bytes().to_vec()),
+            ],
+        )
+        .await;
+
+        match result {
+            Err(ProtocolError::SignatureVerificationFailed(reason)) => {
+                assert!(reason.contains("expected"), "{reason}");
+            }
+            other => unreachable!("Expected signature verification failure, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_v1_signature_without_trust_anchor_checks_signer() {
+        let response = signed_mime(SIGNATURE_WITH_CERT);
+        let doc = query_anchored(
+            true,
+            None,
+            vec![("v1/products".to_string(), response.clone())],
+        )
+        .await
+        .expect("Signature should verify against the embedded certificate");
+        assert_eq!(doc.rows().len(), 1);
+
+        // The signature is still checked
+        let mut tampered = response;
+        let pos = tampered
+            .windows(5)
+            .position(|w| w == b"61265")
+            .expect("Build id should be present");
+        tampered[pos] = b'9';
+        let result = query_anchored(true, None, vec![("v1/products".to_string(), tampered)]).await;
+        assert!(matches!(
+            result,
+            Err(ProtocolError::SignatureVerificationFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_v1_self_signed_signer_not_anchored_rejected() {
+        let other_cert = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/ribbit_other_signer.pem"
+        ));
+        let response = signed_mime(SIGNATURE_WITH_CERT);
+        let result = query_anchored(
+            true,
+            Some(other_cert),
+            vec![
+                ("v1/products".to_string(), response),
+                ("certs/".to_string(), SIGNER_CERT_PEM.as_bytes().to_vec()),
+            ],
+        )
+        .await;
+
+        match result {
+            Err(ProtocolError::SignatureVerificationFailed(reason)) => {
+                assert!(reason.contains("trust anchor"), "{reason}");
+            }
+            other => unreachable!("Expected signature verification failure, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_v1_signer_chains_to_trusted_root() {
+        let routes = || {
+            vec![
+                (
+                    "v1/products".to_string(),
+                    signed_mime(SIGNATURE_BY_ISSUED_SIGNER),
+                ),
+                (
+                    format!("certs/{ISSUED_SIGNER_SKI}"),
+                    ISSUED_SIGNER_CERT_PEM.as_bytes().to_vec(),
+                ),
+                (
+                    format!("certs/{SIGNING_ROOT_SKI}"),
+                    SIGNING_ROOT_PEM.as_bytes().to_vec(),
+                ),
+            ]
+        };
+
+        let doc = query_anchored(true, Some(SIGNING_ROOT_PEM), routes())
+            .await
+            .expect("Signer issued by the trusted root should verify");
+        assert_eq!(doc.rows().len(), 1);
+
+        // The root served by the server is not trusted by itself
+        let result = query_anchored(true, Some(SIGNER_CERT_PEM), routes()).await;
+        assert!(matches!(
+            result,
+            Err(ProtocolError::SignatureVerificationFailed(_))
+        ));
+    }
 }
@@ -1,5 +1,6 @@
 //! TACT HTTP/HTTPS protocol implementation
 
+use bytes::Bytes;
 use cascette_formats::CascFormat;
 use cascette_formats::bpsv::BpsvDocument;
 use reqwest::{Client, StatusCode};
@@ -56,8 +57,15 @@ impl TactClient {
     }
 
     /// Query TACT endpoint
-    #[cfg(not(target_arch = "wasm32"))]
     pub async fn query(&self, endpoint: &str) -> Result<BpsvDocument> {
+        let body = self.query_raw(endpoint).await?;
+        <BpsvDocument as CascFormat>::parse(&body)
+            .map_err(|e| ProtocolError::Parse(format!("BPSV parse error: {e}")))
+    }
+
+    /// Query TACT endpoint and return the response body as sent
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn query_raw(&self, endpoint: &str) -> Result<Bytes> {
         // Transform TCP Ribbit endpoint format to TACT format
         // TCP: v1/products/{product}/versions -> TACT: /{product}/versions
         let tact_endpoint = if endpoint.starts_with("v1/products/") {
@@ -79,11 +87,7 @@ impl TactClient {
 
         // Check status
         match response.status() {
-            StatusCode::OK => {
-                let body = response.bytes().await?;
-                <BpsvDocument as CascFormat>::parse(&body)
-                    .map_err(|e| ProtocolError::Parse(format!("BPSV parse error: {e}")))
-            }
+            StatusCode::OK => Ok(response.bytes().await?),
             StatusCode::TOO_MANY_REQUESTS => Err(ProtocolError::RateLimited { retry_after: None }),
             StatusCode::SERVICE_UNAVAILABLE => Err(ProtocolError::ServiceUnavailable),
             status if status.is_server_error() => Err(ProtocolError::ServerError(status)),
@@ -91,12 +95,12 @@ impl TactClient {
         }
     }
 
-    /// Query TACT endpoint (WASM version)
+    /// Query TACT endpoint and return the response body as sent (WASM version)
     ///
     /// On WASM, timeout is not supported on the request builder, so we
     /// rely on the browser's default timeout behavior.
     #[cfg(target_arch = "wasm32")]
-    pub async fn query(&self, endpoint: &str) -> Result<BpsvDocument> {
+    pub async fn query_raw(&self, endpoint: &str) -> Result<Bytes> {
         // Transform TCP Ribbit endpoint format to TACT format
         // TCP: v1/products/{product}/versions -> TACT: /{product}/versions
         let tact_endpoint = if endpoint.starts_with("v1/products/") {
@@ -119,11 +123,7 @@ impl TactClient {
 
         // Check status
         match response.status() {
-            StatusCode::OK => {
-                let body = response.bytes().await?;
-                <BpsvDocument as CascFormat>::parse(&body)
-                    .map_err(|e| ProtocolError::Parse(format!("BPSV parse error: {e}")))
-            }
+            StatusCode::OK => Ok(response.bytes().await?),
             StatusCode::TOO_MANY_REQUESTS => Err(ProtocolError::RateLimited { retry_after: None }),
             StatusCode::SERVICE_UNAVAILABLE => Err(ProtocolError::ServiceUnavailable),
             status if status.is_server_error() => Err(ProtocolError::ServerError(status)),
@@ -1,14 +1,25 @@
 //! Configuration structures for protocol clients
 
+use cascette_cache::key::CacheKeyKind;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
+use crate::client::Region;
 use crate::error::Result;
 use crate::retry::RetryPolicy;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClientConfig {
+    /// Region served by the endpoints below
+    ///
+    /// Cached responses are keyed by region, so clients for different
+    /// regions can share one cache. Set this when pointing the endpoints at
+    /// a region other than US.
+    #[serde(default)]
+    pub region: Region,
+
     /// TACT HTTPS endpoint URL
     pub tact_https_url: String,
 
@@ -29,11 +40,28 @@ pub struct ClientConfig {
 
     /// Retry policy for failed requests
     pub retry_policy: RetryPolicy,
+
+    /// Verify the CMS signature of Ribbit TCP V1 responses
+    ///
+    /// Only disable this for servers that do not sign their responses,
+    /// such as local test servers.
+    pub ribbit_verify_signatures: bool,
+
+    /// PEM bundle of the certificates trusted to sign Ribbit TCP V1
+    /// responses
+    ///
+    /// Without trust anchors, V1 signatures are only checked against the
+    /// signer certificate; with them, the signer must also chain to one.
+    /// See
+    /// [`RibbitClient::with_trust_anchors_pem`](crate::RibbitClient::with_trust_anchors_pem).
+    #[serde(default)]
+    pub ribbit_trust_anchors: Option<String>,
 }
 
 impl Default for ClientConfig {
     fn default() -> Self {
         Self {
+            region: Region::US,
             tact_https_url: "https://us.version.battle.net".to_string(),
             tact_http_url: "http://us.patch.battle.net:1119".to_string(),
             ribbit_url: "tcp://us.version.battle.net:1119".to_string(),
@@ -41,20 +69,31 @@ impl Default for ClientConfig {
             connect_timeout: Duration::from_secs(10),
             request_timeout: Duration::from_secs(30),
             retry_policy: RetryPolicy::default(),
+            ribbit_verify_signatures: true,
+            ribbit_trust_anchors: None,
         }
     }
 }
 
 impl ClientConfig {
     /// Create configuration from environment variables
+    ///
+    /// `CASCETTE_REGION` selects the region; the endpoint URLs default to
+    /// that region's servers. `CASCETTE_RIBBIT_TRUST_ANCHORS` names a PEM
+    /// file of Ribbit signing trust anchors.
     pub fn from_env() -> Result<Self> {
+        let region: Region = std::env::var("CASCETTE_REGION")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_default();
         Ok(Self {
+            region,
             tact_https_url: std::env::var("CASCETTE_TACT_HTTPS_URL")
-                .unwrap_or_else(|_| "https://us.version.battle.net".to_string()),
+                .unwrap_or_else(|_| region.tact_https_url().to_string()),
             tact_http_url: std::env::var("CASCETTE_TACT_HTTP_URL")
-                .unwrap_or_else(|_| "http://us.patch.battle.net:1119".to_string()),
+                .unwrap_or_else(|_| region.tact_http_url().to_string()),
             ribbit_url: std::env::var("CASCETTE_RIBBIT_URL")
-                .unwrap_or_else(|_| "tcp://us.version.battle.net:1119".to_string()),
+                .unwrap_or_else(|_| format!("tcp://{}", region.ribbit_address())),
             cache_config: CacheConfig::from_env()?,
             connect_timeout: Duration::from_secs(
                 std::env::var("CASCETTE_CONNECT_TIMEOUT")
@@ -69,8 +108,39 @@ impl ClientConfig {
                     .unwrap_or(30),
             ),
             retry_policy: RetryPolicy::from_env()?,
+            ribbit_verify_signatures: std::env::var("CASCETTE_RIBBIT_VERIFY_SIGNATURES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(true),
+            ribbit_trust_anchors: std::env::var_os("CASCETTE_RIBBIT_TRUST_ANCHORS")
+                .map(std::fs::read_to_string)
+                .transpose()?,
         })
     }
+
+    /// Create the default configuration for `region`'s servers
+    pub fn for_region(region: Region) -> Self {
+        Self::default().with_region(region)
+    }
+
+    /// Point this configuration at `region`'s servers
+    ///
+    /// Endpoints that are disabled (empty) stay disabled. All other
+    /// settings are kept.
+    #[must_use]
+    pub fn with_region(mut self, region: Region) -> Self {
+        self.region = region;
+        if !self.tact_https_url.is_empty() {
+            self.tact_https_url = region.tact_https_url().to_string();
+        }
+        if !self.tact_http_url.is_empty() {
+            self.tact_http_url = region.tact_http_url().to_string();
+        }
+        if !self.ribbit_url.is_empty() {
+            self.ribbit_url = format!("tcp://{}", region.ribbit_address());
+        }
+        self
+    }
 }
 
 /// High-performance cache configuration optimized for NGDP protocol operations
@@ -95,6 +165,29 @@ pub struct CacheConfig {
 
     /// TTL for configuration files
     pub config_ttl: Duration,
+
+    /// TTL for responses that look like a maintenance window
+    ///
+    /// Kept short so that recovery from maintenance is noticed quickly.
+    pub maintenance_ttl: Duration,
+
+    /// How long past its TTL a Ribbit/TACT response may still be served
+    ///
+    /// Within this window [`RibbitTactClient`](crate::RibbitTactClient)
+    /// answers from the cache and refreshes the response in the
+    /// background. Zero (the default) disables stale-while-revalidate.
+    /// Background refreshes need a Tokio runtime, so WASM ignores this.
+    #[serde(default)]
+    pub revalidate_grace: Duration,
+
+    /// Age after which disk cache entries are evicted, by key kind
+    ///
+    /// CDN files are classified by content type: config files as
+    /// [`CacheKeyKind::Config`], archive indices as
+    /// [`CacheKeyKind::ArchiveIndex`], and data and patch files as
+    /// [`CacheKeyKind::Blte`]. Only applies with a `cache_dir`.
+    #[serde(default, with = "cascette_cache::config::by_kind_name")]
+    pub max_age_by_kind: BTreeMap<CacheKeyKind, Duration>,
 }
 
 impl Default for CacheConfig {
@@ -111,6 +204,9 @@ impl Default for CacheConfig {
             ribbit_ttl: Duration::from_secs(300), // 5 minutes for version info
             cdn_ttl: Duration::from_secs(3600),   // 1 hour for CDN content
             config_ttl: Duration::from_secs(1800), // 30 minutes for config files
+            maintenance_ttl: Duration::from_secs(30), // 30 seconds during maintenance
+            revalidate_grace: Duration::ZERO,
+            max_age_by_kind: BTreeMap::new(),
         }
     }
 }
@@ -154,6 +250,19 @@ impl CacheConfig {
                     .and_then(|s| s.parse().ok())
                     .unwrap_or(1800),
             ),
+            maintenance_ttl: Duration::from_secs(
+                std::env::var("CASCETTE_MAINTENANCE_TTL")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(30),
+            ),
+            revalidate_grace: Duration::from_secs(
+                std::env::var("CASCETTE_REVALIDATE_GRACE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(0),
+            ),
+            max_age_by_kind: BTreeMap::new(),
         })
     }
 
@@ -168,6 +277,9 @@ impl CacheConfig {
             ribbit_ttl: Duration::from_secs(180),      // 3 minutes for faster updates
             cdn_ttl: Duration::from_secs(7200),        // 2 hours for CDN content
             config_ttl: Duration::from_secs(900),      // 15 minutes for config files
+            maintenance_ttl: Duration::from_secs(30),  // 30 seconds during maintenance
+            revalidate_grace: Duration::ZERO,
+            max_age_by_kind: BTreeMap::new(),
         }
     }
 
@@ -175,15 +287,25 @@ impl CacheConfig {
     pub fn memory_optimized() -> Self {
         Self {
             cache_dir: None,
-            memory_max_items: 1000,                  // 1k items in memory
-            memory_max_size_bytes: 32 * 1024 * 1024, // 32MB memory cache
-            disk_max_size_bytes: 1024 * 1024 * 1024, // 1GB disk cache
-            disk_max_file_size: 10 * 1024 * 1024,    // 10MB max file size
-            ribbit_ttl: Duration::from_secs(600),    // 10 minutes
-            cdn_ttl: Duration::from_secs(3600),      // 1 hour
-            config_ttl: Duration::from_secs(1800),   // 30 minutes
+            memory_max_items: 1000,                   // 1k items in memory
+            memory_max_size_bytes: 32 * 1024 * 1024,  // 32MB memory cache
+            disk_max_size_bytes: 1024 * 1024 * 1024,  // 1GB disk cache
+            disk_max_file_size: 10 * 1024 * 1024,     // 10MB max file size
+            ribbit_ttl: Duration::from_secs(600),     // 10 minutes
+            cdn_ttl: Duration::from_secs(3600),       // 1 hour
+            config_ttl: Duration::from_secs(1800),    // 30 minutes
+            maintenance_ttl: Duration::from_secs(60), // 1 minute
+            revalidate_grace: Duration::ZERO,
+            max_age_by_kind: BTreeMap::new(),
         }
     }
+
+    /// Evict disk cache entries of `kind` once they are older than `max_age`
+    #[must_use]
+    pub fn with_max_age(mut self, kind: CacheKeyKind, max_age: Duration) -> Self {
+        self.max_age_by_kind.insert(kind, max_age);
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -199,6 +321,23 @@ pub struct CdnConfig {
 
     /// Connection pool size
     pub pool_size: usize,
+
+    /// Retry policy for failed downloads
+    pub retry_policy: RetryPolicy,
+
+    /// Maximum cumulative retries per minute across all downloads
+    pub retry_budget_per_minute: u32,
+
+    /// Maximum cumulative retries of 429 responses per minute
+    pub rate_limit_budget_per_minute: u32,
+
+    /// Verify downloaded content against the MD5 key it was requested by
+    #[serde(default = "default_verify_checksums")]
+    pub verify_checksums: bool,
+}
+
+const fn default_verify_checksums() -> bool {
+    true
 }
 
 impl Default for CdnConfig {
@@ -208,10 +347,53 @@ impl Default for CdnConfig {
             chunk_size: 4 * 1024 * 1024, // 4MB
             enable_progress: false,
             pool_size: 20,
+            retry_policy: RetryPolicy::default(),
+            retry_budget_per_minute: 120,
+            rate_limit_budget_per_minute: 10,
+            verify_checksums: true,
         }
     }
 }
 
+impl CdnConfig {
+    /// Set the retry policy for failed downloads
+    #[must_use]
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set the maximum cumulative retries per minute
+    ///
+    /// Once spent, transiently failing downloads return
+    /// [`ProtocolError::RetryBudgetExhausted`](crate::ProtocolError::RetryBudgetExhausted)
+    /// instead of retrying.
+    #[must_use]
+    pub fn with_retry_budget(mut self, retries_per_minute: u32) -> Self {
+        self.retry_budget_per_minute = retries_per_minute;
+        self
+    }
+
+    /// Set the maximum cumulative retries of rate-limited (429) responses per minute
+    #[must_use]
+    pub fn with_rate_limit_budget(mut self, retries_per_minute: u32) -> Self {
+        self.rate_limit_budget_per_minute = retries_per_minute;
+        self
+    }
+
+    /// Enable or disable verification of downloads against their key
+    ///
+    /// Enabled by default. A download whose MD5 does not match fails with
+    /// [`ProtocolError::ChecksumMismatch`](crate::ProtocolError::ChecksumMismatch)
+    /// and is retried. Disable it to download archives, which are not named
+    /// after a hash of their content.
+    #[must_use]
+    pub const fn verify_checksums(mut self, verify: bool) -> Self {
+        self.verify_checksums = verify;
+        self
+    }
+}
+
 #[cfg(test)]
 #[allow(
     unsafe_code,
@@ -297,6 +479,7 @@ mod tests {
         let retry_policy = test_retry_policy_from_env_with_suffix(suffix);
 
         ClientConfig {
+            region: Region::US,
             tact_https_url: std::env::var(format!("CASCETTE_TACT_HTTPS_URL{}", suffix))
                 .unwrap_or_else(|_| "https://us.version.battle.net".to_string()),
             tact_http_url: std::env::var(format!("CASCETTE_TACT_HTTP_URL{}", suffix))
@@ -326,6 +509,8 @@ mod tests {
                     .unwrap_or(30),
             ),
             retry_policy,
+            ribbit_verify_signatures: true,
+            ribbit_trust_anchors: None,
         }
     }
 
@@ -369,6 +554,9 @@ mod tests {
                     .and_then(|s| s.parse().ok())
                     .unwrap_or(1800),
             ),
+            maintenance_ttl: Duration::from_secs(30),
+            revalidate_grace: Duration::ZERO,
+            max_age_by_kind: BTreeMap::new(),
         }
     }
 
@@ -554,5 +742,26 @@ mod tests {
         assert_eq!(config.ribbit_url, deserialized.ribbit_url);
         assert_eq!(config.connect_timeout, deserialized.connect_timeout);
         assert_eq!(config.request_timeout, deserialized.request_timeout);
+        assert_eq!(config.region, deserialized.region);
+    }
+
+    #[test]
+    fn test_client_config_with_region() {
+        let config = ClientConfig::for_region(Region::EU);
+        assert_eq!(config.region, Region::EU);
+        assert_eq!(config.tact_https_url, "https://eu.version.battle.net");
+        assert_eq!(config.tact_http_url, "http://eu.patch.battle.net:1119");
+        assert_eq!(config.ribbit_url, "tcp://eu.version.battle.net:1119");
+
+        // Disabled endpoints stay disabled
+        let config = ClientConfig {
+            tact_https_url: String::new(),
+            ribbit_url: String::new(),
+            ..Default::default()
+        }
+        .with_region(Region::KR);
+        assert!(config.tact_https_url.is_empty());
+        assert_eq!(config.tact_http_url, "http://kr.patch.battle.net:1119");
+        assert!(config.ribbit_url.is_empty());
     }
 }
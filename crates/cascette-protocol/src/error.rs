@@ -33,18 +33,49 @@ pub enum ProtocolError {
     #[error("HTTP status: {0}")]
     HttpStatus(StatusCode),
 
+    /// The requested resource does not exist (HTTP 404); carries the URL or endpoint
+    #[error("Not found: {0}")]
+    NotFound(String),
+
     #[error("Server error: {0}")]
     ServerError(StatusCode),
 
+    /// Client error status (4xx other than 408/429); never retried
+    #[error("Client error: {0}")]
+    ClientError(StatusCode),
+
+    /// The host failed too often and its circuit breaker is open; carries the host
+    #[error("Circuit open for host {0}")]
+    CircuitOpen(String),
+
+    /// The per-client retry budget was spent; carries the last error seen
+    #[error("Retry budget exhausted: {0}")]
+    RetryBudgetExhausted(Box<Self>),
+
+    /// A V1 response signature was missing or did not verify
+    #[error("Signature verification failed: {0}")]
+    SignatureVerificationFailed(String),
+
     #[error("Invalid key")]
     InvalidKey,
 
+    /// Downloaded content did not hash to the key it was requested by
+    #[error("Checksum mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+
     #[error("Invalid endpoint: {0}")]
     InvalidEndpoint(String),
 
+    #[error("Unknown region: {0}")]
+    UnknownRegion(String),
+
     #[error("Range not supported")]
     RangeNotSupported,
 
+    /// A range response carried fewer bytes than requested
+    #[error("Incomplete range response: expected {expected} bytes, received {received}")]
+    IncompleteRange { expected: u64, received: u64 },
+
     #[error("Timeout")]
     Timeout,
 
@@ -56,59 +87,74 @@ pub enum ProtocolError {
 
     #[error("Unsupported on WASM: {0}")]
     UnsupportedOnWasm(String),
+
+    /// A protocol was requested explicitly but the client has no endpoint for it
+    #[error("Protocol not configured: {0}")]
+    ProtocolNotConfigured(String),
+}
+
+/// Retry classification of a [`ProtocolError`]
+///
+/// Retry loops use this to decide whether an error is worth another attempt
+/// and which budget the attempt is charged against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// The request will not succeed on retry (404 and other 4xx, parse errors)
+    Permanent,
+    /// The server asked us to slow down (429); honor Retry-After when present
+    RateLimited,
+    /// Transient failure (5xx, 408, timeouts, transport errors)
+    Transient,
 }
 
 impl ProtocolError {
-    /// Check if error is retryable
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn should_retry(&self) -> bool {
+    /// Classify this error for retry purposes
+    pub fn classification(&self) -> ErrorClass {
         match self {
-            // Transient errors that should be retried
+            Self::RateLimited { .. } => ErrorClass::RateLimited,
             Self::Network(_)
             | Self::ServerError(_)
-            | Self::RateLimited { .. }
             | Self::ServiceUnavailable
-            | Self::Timeout => true,
-            Self::Http(e) => e.is_timeout() || e.is_connect(),
-            Self::HttpStatus(status) => {
-                matches!(
-                    status,
-                    &StatusCode::TOO_MANY_REQUESTS
-                        | &StatusCode::INTERNAL_SERVER_ERROR
-                        | &StatusCode::BAD_GATEWAY
-                        | &StatusCode::SERVICE_UNAVAILABLE
-                        | &StatusCode::GATEWAY_TIMEOUT
-                )
-            }
-            _ => false,
+            | Self::Timeout
+            | Self::ChecksumMismatch { .. }
+            | Self::IncompleteRange { .. } => ErrorClass::Transient,
+            // On WASM, is_connect() is not available since the browser handles
+            // connection management, so only timeouts are considered transient.
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Http(e) if e.is_timeout() || e.is_connect() => ErrorClass::Transient,
+            #[cfg(target_arch = "wasm32")]
+            Self::Http(e) if e.is_timeout() => ErrorClass::Transient,
+            Self::HttpStatus(status) => match *status {
+                StatusCode::TOO_MANY_REQUESTS => ErrorClass::RateLimited,
+                StatusCode::REQUEST_TIMEOUT
+                | StatusCode::INTERNAL_SERVER_ERROR
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT => ErrorClass::Transient,
+                _ => ErrorClass::Permanent,
+            },
+            _ => ErrorClass::Permanent,
         }
     }
 
-    /// Check if error is retryable (WASM version)
-    ///
-    /// On WASM, reqwest doesn't expose `is_connect()` method since the browser
-    /// handles connection management. We check only for timeout errors.
-    #[cfg(target_arch = "wasm32")]
+    /// Check if error is retryable
     pub fn should_retry(&self) -> bool {
+        self.classification() != ErrorClass::Permanent
+    }
+
+    /// Check if the error can never succeed, whatever protocol or host is tried
+    ///
+    /// Narrower than `!should_retry()`: retry loops stop on any permanent
+    /// classification, but a fallback chain only gives up on errors that
+    /// every protocol would repeat. These are missing resources (404),
+    /// rejected credentials (401/403), invalid endpoints and parse errors.
+    pub fn is_permanent(&self) -> bool {
         match self {
-            // Transient errors that should be retried
-            Self::Network(_)
-            | Self::ServerError(_)
-            | Self::RateLimited { .. }
-            | Self::ServiceUnavailable
-            | Self::Timeout => true,
-            // On WASM, is_connect() is not available, only check timeout
-            Self::Http(e) => e.is_timeout(),
-            Self::HttpStatus(status) => {
-                matches!(
-                    status,
-                    &StatusCode::TOO_MANY_REQUESTS
-                        | &StatusCode::INTERNAL_SERVER_ERROR
-                        | &StatusCode::BAD_GATEWAY
-                        | &StatusCode::SERVICE_UNAVAILABLE
-                        | &StatusCode::GATEWAY_TIMEOUT
-                )
-            }
+            Self::HttpStatus(status) | Self::ClientError(status) => matches!(
+                *status,
+                StatusCode::NOT_FOUND | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+            ),
+            Self::NotFound(_) | Self::InvalidEndpoint(_) | Self::Parse(_) => true,
             _ => false,
         }
     }
@@ -123,3 +169,35 @@ impl ProtocolError {
 }
 
 pub type Result<T> = std::result::Result<T, ProtocolError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_permanent() {
+        for permanent in [
+            ProtocolError::HttpStatus(StatusCode::NOT_FOUND),
+            ProtocolError::ClientError(StatusCode::NOT_FOUND),
+            ProtocolError::HttpStatus(StatusCode::UNAUTHORIZED),
+            ProtocolError::ClientError(StatusCode::FORBIDDEN),
+            ProtocolError::NotFound("v1/products/none/versions".to_string()),
+            ProtocolError::InvalidEndpoint("bad".to_string()),
+            ProtocolError::Parse("bad".to_string()),
+        ] {
+            assert!(permanent.is_permanent(), "{permanent}");
+            assert!(!permanent.should_retry(), "{permanent}");
+        }
+
+        // Not retried, but another protocol may still succeed
+        for other in [
+            ProtocolError::AllHostsFailed,
+            ProtocolError::HttpStatus(StatusCode::BAD_REQUEST),
+            ProtocolError::Timeout,
+            ProtocolError::ServerError(StatusCode::BAD_GATEWAY),
+            ProtocolError::RateLimited { retry_after: None },
+        ] {
+            assert!(!other.is_permanent(), "{other}");
+        }
+    }
+}
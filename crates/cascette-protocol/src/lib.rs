@@ -366,30 +366,50 @@
 //! }
 //! ```
 
+pub mod bgdl;
+pub mod build_diff;
 pub mod cache;
 pub mod cdn;
 pub mod client;
 pub mod config;
 pub mod error;
+pub mod maintenance;
 pub mod mime_parser;
 pub mod optimized;
+pub mod product_config;
 pub mod retry;
+pub mod summary;
 pub mod transport;
 pub mod v1_mime;
+pub mod version_configs;
 
 // Re-export main types
+pub use bgdl::{BgdlEntry, parse_bgdl};
+pub use build_diff::{BuildDiff, FileDataIdDiff, FileDiff};
 pub use cdn::{CdnClient, CdnEndpoint, ContentType};
-pub use client::RibbitTactClient;
+pub use client::{MultiRegionClient, Protocol, RegionHealth, RibbitTactClient};
 pub use config::{CacheConfig, CdnConfig, ClientConfig};
-pub use error::{ProtocolError, Result};
-pub use retry::RetryPolicy;
+pub use error::{ErrorClass, ProtocolError, Result};
+pub use maintenance::QueryOutcome;
+pub use product_config::{ProductBinary, ProductConfig, ProductConfigSection, ProductConfigValues};
+pub use retry::{CircuitBreaker, CircuitState, RetryBudget, RetryPolicy};
+pub use summary::{ProductSummary, SummaryEndpoint, SummaryResponse};
 pub use transport::{HttpClient, HttpConfig};
+pub use version_configs::{BuildConfigSummary, CdnConfigSummary, ConfigDocument, VersionConfigs};
 
 // Re-export internal client types for advanced usage
-pub use client::Region;
 #[cfg(not(target_arch = "wasm32"))]
-pub use client::RibbitClient;
+pub use cdn::locate::{ArchiveLocator, ArchiveSpan};
+#[cfg(not(target_arch = "wasm32"))]
+pub use cdn::mirror::{ArchiveManifest, MirrorOptions, MirrorReport, MirroredArchive};
+#[cfg(not(target_arch = "wasm32"))]
+pub use cdn::progressive::{ProgressiveFileManager, ProgressiveReader};
+#[cfg(not(target_arch = "wasm32"))]
+pub use cdn::resume::{DownloadManager, DownloadReport, ResumableDownload, SessionFile};
+pub use client::Region;
 pub use client::TactClient;
+#[cfg(not(target_arch = "wasm32"))]
+pub use client::{RibbitClient, RibbitClientBuilder, RibbitPoolConfig, RibbitPoolStats};
 
 // Re-export optimization utilities for power users
 pub use optimized::{PooledBuffer, format_cache_key, get_buffer, intern_string, return_buffer};
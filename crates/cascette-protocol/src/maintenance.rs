@@ -0,0 +1,301 @@
+//! Detection of Blizzard maintenance-mode responses
+//!
+//! During maintenance windows the `versions` and `cdns` endpoints can return
+//! a well-formed BPSV document, with a valid schema and sequence number, that
+//! has no rows or only placeholder rows. Treating such a document as "all
+//! builds removed" tears down downstream state, so callers should check for
+//! maintenance before acting on an empty listing.
+//!
+//! Detection is heuristic:
+//! - [`is_probably_maintenance`] inspects the document alone
+//! - [`is_probably_maintenance_with_summary`] additionally requires the
+//!   summary to still advertise the product endpoint, which rules out a
+//!   product that was actually retired
+
+use crate::summary::{SummaryEndpoint, SummaryResponse};
+use cascette_formats::bpsv::{BpsvDocument, BpsvRow, BpsvSchema, BpsvValue};
+
+/// Result of a product query, distinguishing maintenance from real data
+#[derive(Debug, Clone)]
+pub enum QueryOutcome {
+    /// The endpoint returned a regular listing
+    Available(BpsvDocument),
+    /// The endpoint returned a valid document without usable rows, as seen
+    /// during maintenance windows; downstream state should be kept
+    EmptyDuringMaintenance(BpsvDocument),
+}
+
+impl QueryOutcome {
+    /// Classify a response from `endpoint`
+    pub fn classify(document: BpsvDocument, endpoint: &str) -> Self {
+        if is_probably_maintenance(&document, endpoint) {
+            Self::EmptyDuringMaintenance(document)
+        } else {
+            Self::Available(document)
+        }
+    }
+
+    /// Whether the response looks like a maintenance window
+    pub const fn is_maintenance(&self) -> bool {
+        matches!(self, Self::EmptyDuringMaintenance(_))
+    }
+
+    /// The response document
+    pub const fn document(&self) -> &BpsvDocument {
+        match self {
+            Self::Available(document) | Self::EmptyDuringMaintenance(document) => document,
+        }
+    }
+
+    /// Consume the outcome and return the response document
+    pub fn into_document(self) -> BpsvDocument {
+        match self {
+            Self::Available(document) | Self::EmptyDuringMaintenance(document) => document,
+        }
+    }
+}
+
+/// Check whether a response looks like a maintenance placeholder
+///
+/// Returns `true` for `versions` and `cdns` product endpoints whose document
+/// has a schema and a sequence number but no rows with data. `bgdl` is
+/// excluded because many products legitimately have no background
+/// downloads.
+pub fn is_probably_maintenance(document: &BpsvDocument, endpoint: &str) -> bool {
+    if !matches!(
+        product_endpoint(endpoint),
+        Some((_, SummaryEndpoint::Versions | SummaryEndpoint::Cdns))
+    ) {
+        return false;
+    }
+
+    let schema = document.schema();
+    if schema.fields().is_empty() || document.sequence_number().is_none() {
+        return false;
+    }
+
+    document
+        .rows()
+        .iter()
+        .all(|row| is_placeholder_row(row, schema))
+}
+
+/// Check for maintenance, confirmed against the summary
+///
+/// In addition to [`is_probably_maintenance`], the summary must still list
+/// the product endpoint. A product that was removed disappears from the
+/// summary, so its empty listing is not reported as maintenance.
+pub fn is_probably_maintenance_with_summary(
+    document: &BpsvDocument,
+    endpoint: &str,
+    summary: &SummaryResponse,
+) -> bool {
+    let Some((product, summary_endpoint)) = product_endpoint(endpoint) else {
+        return false;
+    };
+
+    is_probably_maintenance(document, endpoint)
+        && summary.latest_seqn(product, summary_endpoint).is_some()
+}
+
+/// Split `v1/products/{product}/{endpoint}` (or `{product}/{endpoint}`)
+fn product_endpoint(endpoint: &str) -> Option<(&str, SummaryEndpoint)> {
+    let mut segments = endpoint.trim_matches('/').rsplit('/');
+    let summary_endpoint = match segments.next()? {
+        "versions" => SummaryEndpoint::Versions,
+        "cdns" => SummaryEndpoint::Cdns,
+        "bgdl" => SummaryEndpoint::Bgdl,
+        _ => return None,
+    };
+    let product = segments.next().filter(|product| !product.is_empty())?;
+    Some((product, summary_endpoint))
+}
+
+/// A row carrying only its key column (`Region` or `Name`) and no data
+fn is_placeholder_row(row: &BpsvRow, schema: &BpsvSchema) -> bool {
+    schema
+        .fields()
+        .iter()
+        .zip(row.values())
+        .filter(|(field, _)| {
+            !field.name.eq_ignore_ascii_case("Region") && !field.name.eq_ignore_ascii_case("Name")
+        })
+        .all(|(_, value)| BpsvValue::is_empty(value))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::{CacheConfig, ClientConfig, RibbitTactClient};
+    use cascette_formats::CascFormat;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const EMPTY_VERSIONS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/maintenance_versions_empty.bpsv"
+    ));
+    const PLACEHOLDER_VERSIONS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/maintenance_versions_placeholder.bpsv"
+    ));
+    const SUMMARY: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/maintenance_summary.bpsv"
+    ));
+    const VERSIONS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/ribbit_versions.bpsv"
+    ));
+
+    fn parse(bpsv: &str) -> BpsvDocument {
+        <BpsvDocument as CascFormat>::parse(bpsv.as_bytes()).expect("Fixture should parse")
+    }
+
+    #[test]
+    fn test_empty_listing_is_maintenance() {
+        let document = parse(EMPTY_VERSIONS);
+        assert!(document.rows().is_empty());
+        assert!(is_probably_maintenance(
+            &document,
+            "v1/products/wow/versions"
+        ));
+        assert!(is_probably_maintenance(&document, "wow/cdns"));
+    }
+
+    #[test]
+    fn test_placeholder_rows_are_maintenance() {
+        let document = parse(PLACEHOLDER_VERSIONS);
+        assert_eq!(document.rows().len(), 2);
+        assert!(is_probably_maintenance(
+            &document,
+            "v1/products/wow/versions"
+        ));
+    }
+
+    #[test]
+    fn test_regular_listing_is_not_maintenance() {
+        let document = parse(VERSIONS);
+        assert!(!is_probably_maintenance(
+            &document,
+            "v1/products/wow/versions"
+        ));
+    }
+
+    #[test]
+    fn test_other_endpoints_are_not_maintenance() {
+        let document = parse(EMPTY_VERSIONS);
+        assert!(!is_probably_maintenance(&document, "v1/products/wow/bgdl"));
+        assert!(!is_probably_maintenance(&document, "v1/summary"));
+    }
+
+    #[test]
+    fn test_missing_seqn_is_not_maintenance() {
+        let document = parse(
+            "Region!STRING:0|BuildConfig!HEX:16|CDNConfig!HEX:16|KeyRing!HEX:16|BuildId!DEC:4|VersionsName!String:0|ProductConfig!HEX:16\n",
+        );
+        assert!(!is_probably_maintenance(
+            &document,
+            "v1/products/wow/versions"
+        ));
+    }
+
+    #[test]
+    fn test_summary_confirms_product_still_listed() {
+        let document = parse(EMPTY_VERSIONS);
+        let summary = SummaryResponse::from_bpsv(&parse(SUMMARY)).expect("Summary should convert");
+
+        assert!(is_probably_maintenance_with_summary(
+            &document,
+            "v1/products/wow/versions",
+            &summary
+        ));
+        // wow_classic advertises no cdns endpoint, d3 is not listed at all
+        assert!(!is_probably_maintenance_with_summary(
+            &document,
+            "v1/products/wow_classic/cdns",
+            &summary
+        ));
+        assert!(!is_probably_maintenance_with_summary(
+            &document,
+            "v1/products/d3/versions",
+            &summary
+        ));
+    }
+
+    #[test]
+    fn test_query_outcome_classify() {
+        let outcome = QueryOutcome::classify(parse(EMPTY_VERSIONS), "v1/products/wow/versions");
+        assert!(outcome.is_maintenance());
+        assert_eq!(outcome.document().sequence_number(), Some(3_016_512));
+
+        let outcome = QueryOutcome::classify(parse(VERSIONS), "v1/products/wow/versions");
+        assert!(!outcome.is_maintenance());
+        assert_eq!(outcome.into_document().rows().len(), 1);
+    }
+
+    fn client_for(server: &MockServer, temp_dir: &TempDir) -> RibbitTactClient {
+        let config = ClientConfig {
+            tact_https_url: String::new(),
+            tact_http_url: server.uri(),
+            ribbit_url: String::new(),
+            cache_config: CacheConfig {
+                cache_dir: Some(temp_dir.path().to_path_buf()),
+                maintenance_ttl: Duration::from_secs(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        RibbitTactClient::new(config).expect("Operation should succeed")
+    }
+
+    #[tokio::test]
+    async fn test_query_versions_reports_maintenance() {
+        let server = MockServer::start().await;
+        // The maintenance response must not be served from cache for long
+        Mock::given(method("GET"))
+            .and(path("/wow/versions"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(EMPTY_VERSIONS))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let client = client_for(&server, &temp_dir);
+
+        for _ in 0..2 {
+            let outcome = client
+                .query_versions("wow")
+                .await
+                .expect("Query should succeed");
+            assert!(outcome.is_maintenance());
+            tokio::time::sleep(Duration::from_millis(1100)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_versions_caches_regular_listing() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/wow/versions"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(VERSIONS))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let client = client_for(&server, &temp_dir);
+
+        for _ in 0..2 {
+            let outcome = client
+                .query_versions("wow")
+                .await
+                .expect("Query should succeed");
+            assert!(!outcome.is_maintenance());
+            tokio::time::sleep(Duration::from_millis(1100)).await;
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Typed product config served from the CDN `ProductPath`
+//!
+//! The `ProductConfig` hash in versions and BGDL responses names a JSON
+//! document under `tpr/configs/data`. It holds launcher settings that are
+//! not part of the build config:
+//!
+//! ```text
+//! {
+//!     "all": { "config": { "product": "WoW", "launch_arguments": [...], ... } },
+//!     "platform": { "win": { "config": { "uid": "wow", "binaries": {...} } } },
+//!     "enus": { "config": { "install": [...] } }
+//! }
+//! ```
+//!
+//! `all` applies to every platform, `platform` holds per-OS overrides and
+//! every other top-level key is a lowercase locale. Fields not modeled here
+//! are kept in `extra`.
+
+use crate::error::{ProtocolError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Parsed product config document
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProductConfig {
+    /// Settings shared by all platforms
+    #[serde(default)]
+    pub all: ProductConfigSection,
+    /// Per-platform settings keyed by platform (`win`, `mac`)
+    #[serde(default)]
+    pub platform: BTreeMap<String, ProductConfigSection>,
+    /// Per-locale settings keyed by lowercase locale (`enus`, `dede`)
+    #[serde(flatten)]
+    pub locales: BTreeMap<String, ProductConfigSection>,
+}
+
+/// A `{"config": {...}}` block of the product config
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProductConfigSection {
+    /// Settings of this block
+    #[serde(default)]
+    pub config: ProductConfigValues,
+}
+
+/// Settings inside a product config block
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProductConfigValues {
+    /// Product name (e.g. `WoW`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product: Option<String>,
+    /// Launcher product UID (e.g. `wow`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+    /// Arguments the launcher passes to every binary
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub launch_arguments: Vec<String>,
+    /// Locales the product can be installed in
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub supported_locales: Vec<String>,
+    /// Platform tags (e.g. `Windows`, `x86_64`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Launchable binaries keyed by role (`game`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub binaries: BTreeMap<String, ProductBinary>,
+    /// Install actions (shortcuts, uninstall registration) run by the launcher
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub install: Vec<serde_json::Value>,
+    /// Name of the key the product's encrypted files need, empty if none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decryption_key_name: Option<String>,
+    /// Fields not modeled above
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A launchable binary of the product
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProductBinary {
+    /// Path relative to the install directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relative_path: Option<String>,
+    /// Path of the ARM64 build, if there is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relative_path_arm64: Option<String>,
+    /// Arguments passed to this binary
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub launch_arguments: Vec<String>,
+    /// Fields not modeled above
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ProductConfig {
+    /// Parse a product config from its JSON bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::Parse` if the data is not a valid product
+    /// config document.
+    pub fn from_json(data: &[u8]) -> Result<Self> {
+        serde_json::from_slice(data)
+            .map_err(|e| ProtocolError::Parse(format!("Invalid product config: {e}")))
+    }
+
+    /// Settings for `platform` (e.g. `win`), if the config has any
+    pub fn platform(&self, platform: &str) -> Option<&ProductConfigValues> {
+        self.platform.get(platform).map(|section| &section.config)
+    }
+
+    /// Launcher UID for `platform`, falling back to the shared settings
+    pub fn uid(&self, platform: &str) -> Option<&str> {
+        self.platform(platform)
+            .and_then(|config| config.uid.as_deref())
+            .or(self.all.config.uid.as_deref())
+    }
+
+    /// The `game` binary for `platform`
+    pub fn game_binary(&self, platform: &str) -> Option<&ProductBinary> {
+        self.platform(platform)
+            .and_then(|config| config.binaries.get("game"))
+    }
+
+    /// Name of the key needed to decrypt the product, if it needs one
+    ///
+    /// Most products ship an empty `decryption_key_name`, which counts as
+    /// none.
+    pub fn decryption_key_name(&self) -> Option<&str> {
+        self.all
+            .config
+            .decryption_key_name
+            .as_deref()
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Arguments used to launch the game on `platform`
+    ///
+    /// Shared arguments come first, followed by those of the platform's
+    /// `game` binary.
+    pub fn launch_arguments(&self, platform: &str) -> Vec<&str> {
+        self.all
+            .config
+            .launch_arguments
+            .iter()
+            .chain(
+                self.game_binary(platform)
+                    .into_iter()
+                    .flat_map(|binary| &binary.launch_arguments),
+            )
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Install actions for `locale` (e.g. `enUS`)
+    pub fn install_actions(&self, locale: &str) -> &[serde_json::Value] {
+        self.locales
+            .get(&locale.to_ascii_lowercase())
+            .map_or(&[], |section| section.config.install.as_slice())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    const PRODUCT_CONFIG: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/product_config_wow.json"
+    ));
+
+    fn config() -> ProductConfig {
+        ProductConfig::from_json(PRODUCT_CONFIG.as_bytes()).expect("Fixture should parse")
+    }
+
+    #[test]
+    fn test_parse_fixture() {
+        let config = config();
+        assert_eq!(config.all.config.product.as_deref(), Some("WoW"));
+        assert_eq!(config.all.config.supported_locales.len(), 11);
+        assert_eq!(config.platform.len(), 2);
+        assert_eq!(config.uid("win"), Some("wow"));
+        assert_eq!(config.uid("linux"), None);
+        assert_eq!(config.all.config.decryption_key_name.as_deref(), Some(""));
+        assert_eq!(config.decryption_key_name(), None);
+
+        let game = config
+            .game_binary("win")
+            .expect("Windows has a game binary");
+        assert_eq!(game.relative_path.as_deref(), Some("_retail_\\Wow.exe"));
+        assert_eq!(
+            game.relative_path_arm64.as_deref(),
+            Some("_retail_\\WowARM64.exe")
+        );
+
+        // Unmodeled fields are kept
+        assert_eq!(
+            config.all.config.extra.get("update_method"),
+            Some(&serde_json::Value::from("ngdp"))
+        );
+    }
+
+    #[test]
+    fn test_launch_arguments_merge_shared_and_platform() {
+        let config = config();
+        assert_eq!(
+            config.launch_arguments("win"),
+            ["-launcherlogin", "-uid", "wow"]
+        );
+        assert_eq!(config.launch_arguments("mac"), ["-launcherlogin"]);
+    }
+
+    #[test]
+    fn test_install_actions_by_locale() {
+        let config = config();
+        assert_eq!(config.install_actions("enUS").len(), 2);
+        assert!(config.install_actions("deDE").is_empty());
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        let err = ProductConfig::from_json(b"{\"platform\": \"win\"}").expect_err("Should reject");
+        assert!(matches!(err, ProtocolError::Parse(_)));
+    }
+}
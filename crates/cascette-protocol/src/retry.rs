@@ -2,10 +2,12 @@
 
 use rand::{RngExt, rng};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::Duration;
 
-use crate::error::Result;
+use crate::error::{ErrorClass, ProtocolError, Result};
 
 /// Cross-platform async sleep function
 ///
@@ -21,6 +23,199 @@ async fn sleep(duration: Duration) {
     gloo_timers::future::TimeoutFuture::new(duration.as_millis() as u32).await;
 }
 
+/// Current wall-clock time in milliseconds
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> u64 {
+    js_sys::Date::now() as u64
+}
+
+/// Fixed-window budget capping how many retries may be spent per window
+///
+/// A budget is shared by every request issued through one client, so a CDN
+/// incident that fails thousands of requests at once cannot turn into a
+/// retry storm. Once the window's allowance is spent, failing requests
+/// return immediately until the window rolls over.
+#[derive(Debug)]
+pub struct RetryBudget {
+    /// Maximum retries per window (0 disables retries entirely)
+    limit: u32,
+    /// Window length
+    window: Duration,
+    /// Window start (ms since epoch) and retries spent in it
+    state: Mutex<(u64, u32)>,
+}
+
+impl RetryBudget {
+    /// Create a budget allowing `limit` retries per `window`
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            state: Mutex::new((now_ms(), 0)),
+        }
+    }
+
+    /// Create a budget allowing `limit` retries per minute
+    pub fn per_minute(limit: u32) -> Self {
+        Self::new(limit, Duration::from_secs(60))
+    }
+
+    /// Create a budget that never runs out
+    pub fn unlimited() -> Self {
+        Self::per_minute(u32::MAX)
+    }
+
+    /// Maximum retries per window
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    /// Try to spend one retry, returning `false` if the window is exhausted
+    pub fn try_spend(&self) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        self.roll_window(&mut state);
+        if state.1 < self.limit {
+            state.1 += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Retries left in the current window
+    pub fn remaining(&self) -> u32 {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        self.roll_window(&mut state);
+        self.limit.saturating_sub(state.1)
+    }
+
+    fn roll_window(&self, state: &mut (u64, u32)) {
+        let now = now_ms();
+        if now.saturating_sub(state.0) >= self.window.as_millis() as u64 {
+            *state = (now, 0);
+        }
+    }
+}
+
+/// State of one host's circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent; counts consecutive failures
+    Closed {
+        /// Consecutive failed requests
+        failures: u32,
+    },
+    /// Requests fail immediately until the reset time
+    Open {
+        /// When a probe may be sent (ms since epoch)
+        until_ms: u64,
+    },
+    /// A single probe request is in flight
+    HalfOpen {
+        /// When the probe was sent (ms since epoch)
+        since_ms: u64,
+    },
+}
+
+/// Per-host circuit breaker shared by every clone of a [`RetryPolicy`]
+///
+/// After `threshold` consecutive transient failures against a host, its
+/// circuit opens and requests to it fail with
+/// [`ProtocolError::CircuitOpen`] without being sent. Once `reset_after`
+/// has passed, one probe request is let through: success closes the
+/// circuit, failure opens it for another `reset_after`. A probe that never
+/// reports back (e.g. a cancelled request) is replaced after `reset_after`.
+///
+/// Only transient failures count. A host answering 404 is healthy, so
+/// permanent errors reset the failure count like successes do.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    reset_after: Duration,
+    hosts: Arc<Mutex<HashMap<String, CircuitState>>>,
+}
+
+impl CircuitBreaker {
+    /// Create a breaker opening after `threshold` consecutive failures
+    pub fn new(threshold: u32, reset_after: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            reset_after,
+            hosts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Current state of `host`'s circuit
+    pub fn state(&self, host: &str) -> CircuitState {
+        self.hosts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(host)
+            .copied()
+            .unwrap_or(CircuitState::Closed { failures: 0 })
+    }
+
+    /// Check whether a request to `host` may be sent
+    ///
+    /// Moves an open circuit whose reset time has passed to half-open, so
+    /// only the caller receiving `true` sends the probe.
+    fn try_acquire(&self, host: &str) -> bool {
+        let now = now_ms();
+        let reset_ms = self.reset_after.as_millis() as u64;
+        let mut hosts = self.hosts.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(state) = hosts.get_mut(host) else {
+            return true;
+        };
+        let probe_due = match *state {
+            CircuitState::Closed { .. } => return true,
+            CircuitState::Open { until_ms } => now >= until_ms,
+            CircuitState::HalfOpen { since_ms } => now.saturating_sub(since_ms) >= reset_ms,
+        };
+        if probe_due {
+            *state = CircuitState::HalfOpen { since_ms: now };
+        }
+        drop(hosts);
+        probe_due
+    }
+
+    /// Record the outcome of a request sent to `host`
+    fn record(&self, host: &str, transient_failure: bool) {
+        let mut hosts = self.hosts.lock().unwrap_or_else(PoisonError::into_inner);
+        if !transient_failure {
+            hosts.remove(host);
+            return;
+        }
+        let state = hosts
+            .entry(host.to_string())
+            .or_insert(CircuitState::Closed { failures: 0 });
+        let failures = match *state {
+            CircuitState::Closed { failures } => failures + 1,
+            CircuitState::Open { .. } | CircuitState::HalfOpen { .. } => self.threshold,
+        };
+        let opened = failures >= self.threshold;
+        *state = if opened {
+            CircuitState::Open {
+                until_ms: now_ms().saturating_add(self.reset_after.as_millis() as u64),
+            }
+        } else {
+            CircuitState::Closed { failures }
+        };
+        drop(hosts);
+
+        if opened {
+            tracing::warn!("Opening circuit for {host} after {failures} failures");
+        }
+    }
+}
+
+/// Retry and backoff settings for network requests
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RetryPolicy {
     /// Maximum retry attempts
@@ -37,6 +232,10 @@ pub struct RetryPolicy {
 
     /// Add jitter to prevent thundering herd
     pub jitter: bool,
+
+    /// Per-host circuit breaker used by the `*_for_host` methods
+    #[serde(skip)]
+    pub circuit_breaker: Option<CircuitBreaker>,
 }
 
 impl Default for RetryPolicy {
@@ -47,6 +246,7 @@ impl Default for RetryPolicy {
             max_backoff: Duration::from_secs(10),
             multiplier: 2.0,
             jitter: true,
+            circuit_breaker: None,
         }
     }
 }
@@ -83,6 +283,7 @@ impl RetryPolicy {
                 .ok()
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(true),
+            circuit_breaker: None,
         })
     }
 
@@ -95,22 +296,127 @@ impl RetryPolicy {
         Ok(Self::default())
     }
 
+    /// Stop sending requests to hosts that keep failing
+    ///
+    /// Requests made through [`execute_for_host`](Self::execute_for_host)
+    /// and [`execute_with_budget_for_host`](Self::execute_with_budget_for_host)
+    /// fail with [`ProtocolError::CircuitOpen`] once their host has failed
+    /// `threshold` times in a row, until a probe after `reset_after`
+    /// succeeds. See [`CircuitBreaker`]. Clones of the policy share the
+    /// breaker.
+    #[must_use]
+    pub fn with_circuit_breaker(mut self, threshold: u32, reset_after: Duration) -> Self {
+        self.circuit_breaker = Some(CircuitBreaker::new(threshold, reset_after));
+        self
+    }
+
     /// Execute a function with retry logic
-    pub async fn execute<F, Fut, T>(&self, mut f: F) -> Result<T>
+    pub async fn execute<F, Fut, T>(&self, f: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.execute_inner(None, None, f).await
+    }
+
+    /// Execute a request to `host` with retry logic and the circuit breaker
+    ///
+    /// Without a circuit breaker this behaves like [`execute`](Self::execute).
+    pub async fn execute_for_host<F, Fut, T>(&self, host: &str, f: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.execute_inner(Some(host), None, f).await
+    }
+
+    /// Execute a function with retry logic, charging retries against budgets
+    ///
+    /// Rate-limited failures (429) are charged against `rate_limit_budget`,
+    /// all other retryable failures against `retry_budget`. When the relevant
+    /// budget is spent the last error is returned wrapped in
+    /// [`ProtocolError::RetryBudgetExhausted`] without sleeping.
+    pub async fn execute_with_budget<F, Fut, T>(
+        &self,
+        retry_budget: &RetryBudget,
+        rate_limit_budget: &RetryBudget,
+        f: F,
+    ) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.execute_inner(None, Some((retry_budget, rate_limit_budget)), f)
+            .await
+    }
+
+    /// Execute a request to `host` with budgets and the circuit breaker
+    ///
+    /// Combines [`execute_with_budget`](Self::execute_with_budget) and
+    /// [`execute_for_host`](Self::execute_for_host).
+    pub async fn execute_with_budget_for_host<F, Fut, T>(
+        &self,
+        host: &str,
+        retry_budget: &RetryBudget,
+        rate_limit_budget: &RetryBudget,
+        f: F,
+    ) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.execute_inner(Some(host), Some((retry_budget, rate_limit_budget)), f)
+            .await
+    }
+
+    async fn execute_inner<F, Fut, T>(
+        &self,
+        host: Option<&str>,
+        budgets: Option<(&RetryBudget, &RetryBudget)>,
+        mut f: F,
+    ) -> Result<T>
     where
         F: FnMut() -> Fut,
         Fut: Future<Output = Result<T>>,
     {
         let mut attempt = 0;
         let mut backoff = self.initial_backoff;
+        let breaker = host.zip(self.circuit_breaker.as_ref());
 
         loop {
-            match f().await {
+            if let Some((host, breaker)) = breaker
+                && !breaker.try_acquire(host)
+            {
+                tracing::debug!("Circuit open for {host}, not sending request");
+                return Err(ProtocolError::CircuitOpen(host.to_string()));
+            }
+
+            let result = f().await;
+            if let Some((host, breaker)) = breaker {
+                breaker.record(
+                    host,
+                    result.as_ref().is_err_and(ProtocolError::should_retry),
+                );
+            }
+
+            match result {
                 Ok(result) => return Ok(result),
                 Err(e) if !e.should_retry() || attempt >= self.max_attempts => {
                     return Err(e);
                 }
                 Err(e) => {
+                    if let Some((retry_budget, rate_limit_budget)) = budgets {
+                        let budget = if e.classification() == ErrorClass::RateLimited {
+                            rate_limit_budget
+                        } else {
+                            retry_budget
+                        };
+                        if !budget.try_spend() {
+                            tracing::warn!("Retry budget exhausted, giving up: {}", e);
+                            return Err(ProtocolError::RetryBudgetExhausted(Box::new(e)));
+                        }
+                    }
+
                     attempt += 1;
                     tracing::warn!("Attempt {} failed: {}", attempt, e);
 
@@ -156,8 +462,7 @@ impl RetryPolicy {
 )]
 mod tests {
     use super::*;
-    use crate::error::ProtocolError;
-    use std::sync::{Arc, Mutex};
+    use std::sync::Arc;
     use std::time::Instant;
 
     #[test]
@@ -232,6 +537,7 @@ mod tests {
             max_backoff: Duration::from_secs(1),
             multiplier: 2.0,
             jitter: false,
+            circuit_breaker: None,
         };
 
         let call_count = Arc::new(Mutex::new(0));
@@ -258,6 +564,7 @@ mod tests {
             max_backoff: Duration::from_secs(1),
             multiplier: 2.0,
             jitter: false,
+            circuit_breaker: None,
         };
 
         let call_count = Arc::new(Mutex::new(0));
@@ -291,6 +598,7 @@ mod tests {
             max_backoff: Duration::from_secs(1),
             multiplier: 2.0,
             jitter: false,
+            circuit_breaker: None,
         };
 
         let call_count = Arc::new(Mutex::new(0));
@@ -320,6 +628,7 @@ mod tests {
             max_backoff: Duration::from_secs(1),
             multiplier: 2.0,
             jitter: false,
+            circuit_breaker: None,
         };
 
         let call_count = Arc::new(Mutex::new(0));
@@ -350,6 +659,7 @@ mod tests {
             max_backoff: Duration::from_millis(50),
             multiplier: 2.0,
             jitter: false,
+            circuit_breaker: None,
         };
 
         let call_count = Arc::new(Mutex::new(0));
@@ -371,4 +681,189 @@ mod tests {
         assert!(elapsed < Duration::from_millis(150)); // Conservative upper bound
         assert_eq!(*call_count.lock().expect("Operation should succeed"), 5); // initial + 4 retries
     }
+
+    #[test]
+    fn test_retry_budget_exhausts_and_reports_remaining() {
+        let budget = RetryBudget::per_minute(2);
+        assert_eq!(budget.remaining(), 2);
+        assert!(budget.try_spend());
+        assert!(budget.try_spend());
+        assert!(!budget.try_spend());
+        assert_eq!(budget.remaining(), 0);
+    }
+
+    #[test]
+    fn test_retry_budget_window_rolls_over() {
+        let budget = RetryBudget::new(1, Duration::from_millis(20));
+        assert!(budget.try_spend());
+        assert!(!budget.try_spend());
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(budget.try_spend());
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_budget_stops_when_exhausted() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            multiplier: 1.0,
+            jitter: false,
+            circuit_breaker: None,
+        };
+        let retry_budget = RetryBudget::per_minute(2);
+        let rate_limit_budget = RetryBudget::unlimited();
+
+        let call_count = Arc::new(Mutex::new(0));
+        let call_count_clone = Arc::clone(&call_count);
+
+        let result = policy
+            .execute_with_budget(&retry_budget, &rate_limit_budget, || async {
+                *call_count_clone.lock().expect("Operation should succeed") += 1;
+                Err::<i32, ProtocolError>(ProtocolError::Timeout)
+            })
+            .await;
+
+        assert!(matches!(
+            result.expect_err("Test operation should fail"),
+            ProtocolError::RetryBudgetExhausted(inner) if matches!(*inner, ProtocolError::Timeout)
+        ));
+        // Initial attempt + 2 budgeted retries
+        assert_eq!(*call_count.lock().expect("Operation should succeed"), 3);
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_budget_charges_rate_limits_separately() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            multiplier: 1.0,
+            jitter: false,
+            circuit_breaker: None,
+        };
+        let retry_budget = RetryBudget::per_minute(5);
+        let rate_limit_budget = RetryBudget::per_minute(1);
+
+        let result = policy
+            .execute_with_budget(&retry_budget, &rate_limit_budget, || async {
+                Err::<i32, ProtocolError>(ProtocolError::RateLimited {
+                    retry_after: Some(Duration::from_millis(1)),
+                })
+            })
+            .await;
+
+        assert!(matches!(
+            result.expect_err("Test operation should fail"),
+            ProtocolError::RetryBudgetExhausted(_)
+        ));
+        assert_eq!(rate_limit_budget.remaining(), 0);
+        assert_eq!(retry_budget.remaining(), 5);
+    }
+
+    fn circuit_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            multiplier: 1.0,
+            jitter: false,
+            circuit_breaker: None,
+        }
+        .with_circuit_breaker(3, Duration::from_millis(50))
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_after_threshold_and_fails_fast() {
+        let policy = circuit_policy();
+        let calls = Arc::new(Mutex::new(0));
+
+        let result = policy
+            .execute_for_host("cdn.example", || async {
+                *calls.lock().unwrap() += 1;
+                Err::<i32, ProtocolError>(ProtocolError::Timeout)
+            })
+            .await;
+        // The fourth attempt is refused once three have failed
+        assert!(matches!(result, Err(ProtocolError::CircuitOpen(host)) if host == "cdn.example"));
+        assert_eq!(*calls.lock().unwrap(), 3);
+
+        let breaker = policy.circuit_breaker.clone().unwrap();
+        assert!(matches!(
+            breaker.state("cdn.example"),
+            CircuitState::Open { .. }
+        ));
+
+        // Clones share the breaker; other hosts are unaffected
+        let clone = policy.clone();
+        let refused = clone
+            .execute_for_host("cdn.example", || async {
+                *calls.lock().unwrap() += 1;
+                Ok(1)
+            })
+            .await;
+        assert!(matches!(refused, Err(ProtocolError::CircuitOpen(_))));
+        assert_eq!(*calls.lock().unwrap(), 3);
+        assert_eq!(
+            clone
+                .execute_for_host("other.example", || async { Ok(2) })
+                .await
+                .unwrap(),
+            2
+        );
+
+        // Without a host the breaker is not consulted
+        assert_eq!(policy.execute(|| async { Ok(3) }).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_closes_after_successful_probe() {
+        let policy = circuit_policy();
+        let breaker = policy.circuit_breaker.clone().unwrap();
+        for _ in 0..3 {
+            breaker.record("cdn.example", true);
+        }
+        assert!(!breaker.try_acquire("cdn.example"));
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(
+            policy
+                .execute_for_host("cdn.example", || async { Ok(1) })
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            breaker.state("cdn.example"),
+            CircuitState::Closed { failures: 0 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_probe_reopens_circuit() {
+        let policy = circuit_policy();
+        let breaker = policy.circuit_breaker.clone().unwrap();
+        for _ in 0..3 {
+            breaker.record("cdn.example", true);
+        }
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        // Only one caller gets to probe
+        assert!(breaker.try_acquire("cdn.example"));
+        assert!(!breaker.try_acquire("cdn.example"));
+        breaker.record("cdn.example", true);
+        assert!(matches!(
+            breaker.state("cdn.example"),
+            CircuitState::Open { .. }
+        ));
+        assert!(!breaker.try_acquire("cdn.example"));
+
+        // Permanent errors mean the host answered
+        breaker.record("other.example", true);
+        breaker.record("other.example", false);
+        assert_eq!(
+            breaker.state("other.example"),
+            CircuitState::Closed { failures: 0 }
+        );
+    }
 }
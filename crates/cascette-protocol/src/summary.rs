@@ -0,0 +1,280 @@
+//! Typed view of the Ribbit `v1/summary` endpoint
+//!
+//! The summary lists the current sequence number of every product endpoint:
+//!
+//! ```text
+//! Product!STRING:0|Seqn!DEC:4|Flags!STRING:0
+//! ## seqn = 3016450
+//! wow|3016450|
+//! wow|3016400|cdn
+//! wow|2950123|bgdl
+//! ```
+//!
+//! An empty `Flags` value refers to the `versions` endpoint, `cdn` to
+//! `cdns` and `bgdl` to `bgdl`. Polling the summary and comparing sequence
+//! numbers detects new builds without fetching every product's versions.
+
+use crate::error::{ProtocolError, Result};
+use cascette_formats::bpsv::{BpsvDocument, BpsvValue};
+use std::collections::BTreeMap;
+use std::fmt;
+use tracing::debug;
+
+/// Product endpoint advertised in the summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SummaryEndpoint {
+    /// `v1/products/{product}/versions`
+    Versions,
+    /// `v1/products/{product}/cdns`
+    Cdns,
+    /// `v1/products/{product}/bgdl`
+    Bgdl,
+}
+
+impl SummaryEndpoint {
+    /// Map a summary `Flags` value to the endpoint it describes
+    pub fn from_flags(flags: &str) -> Option<Self> {
+        match flags.trim() {
+            "" => Some(Self::Versions),
+            "cdn" => Some(Self::Cdns),
+            "bgdl" => Some(Self::Bgdl),
+            _ => None,
+        }
+    }
+
+    /// Endpoint name as used in Ribbit paths
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Versions => "versions",
+            Self::Cdns => "cdns",
+            Self::Bgdl => "bgdl",
+        }
+    }
+}
+
+impl fmt::Display for SummaryEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One row of the summary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummaryEntry {
+    /// Product code (e.g. `wow`)
+    pub product: String,
+    /// Endpoint the sequence number belongs to
+    pub endpoint: SummaryEndpoint,
+    /// Current sequence number of the endpoint
+    pub seqn: u64,
+}
+
+/// Endpoints and sequence numbers advertised for one product
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProductSummary {
+    /// Product code (e.g. `wow`)
+    pub product: String,
+    /// Sequence number for each advertised endpoint
+    pub endpoints: BTreeMap<SummaryEndpoint, u64>,
+}
+
+impl ProductSummary {
+    /// Sequence number of `endpoint`, if the product advertises it
+    pub fn seqn(&self, endpoint: SummaryEndpoint) -> Option<u64> {
+        self.endpoints.get(&endpoint).copied()
+    }
+
+    /// Whether the product advertises `endpoint`
+    pub fn has_endpoint(&self, endpoint: SummaryEndpoint) -> bool {
+        self.endpoints.contains_key(&endpoint)
+    }
+}
+
+/// Parsed `v1/summary` response
+#[derive(Debug, Clone, Default)]
+pub struct SummaryResponse {
+    sequence_number: Option<u64>,
+    entries: Vec<SummaryEntry>,
+}
+
+impl SummaryResponse {
+    /// Build a summary from a parsed BPSV document
+    ///
+    /// Rows with unknown flags are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::Parse` if a row lacks the `Product` or `Seqn`
+    /// field or its sequence number is out of range.
+    pub fn from_bpsv(document: &BpsvDocument) -> Result<Self> {
+        let schema = document.schema();
+        let mut entries = Vec::with_capacity(document.row_count());
+
+        for row in document.rows() {
+            let product = row
+                .get_raw_by_name("Product", schema)
+                .filter(|product| !product.is_empty())
+                .ok_or_else(|| ProtocolError::Parse("Missing Product field".to_string()))?;
+            let seqn = row
+                .get_by_name("Seqn", schema)
+                .and_then(BpsvValue::as_dec)
+                .ok_or_else(|| ProtocolError::Parse("Missing Seqn field".to_string()))?;
+            let seqn = u64::try_from(seqn)
+                .map_err(|_| ProtocolError::Parse(format!("Invalid Seqn value: {seqn}")))?;
+            let flags = row.get_raw_by_name("Flags", schema).unwrap_or_default();
+
+            let Some(endpoint) = SummaryEndpoint::from_flags(flags) else {
+                debug!("Skipping summary row for {product} with flags '{flags}'");
+                continue;
+            };
+
+            entries.push(SummaryEntry {
+                product: product.to_string(),
+                endpoint,
+                seqn,
+            });
+        }
+
+        Ok(Self {
+            sequence_number: document.sequence_number(),
+            entries,
+        })
+    }
+
+    /// Sequence number of the summary document itself
+    pub const fn sequence_number(&self) -> Option<u64> {
+        self.sequence_number
+    }
+
+    /// All summary rows in document order
+    pub fn entries(&self) -> &[SummaryEntry] {
+        &self.entries
+    }
+
+    /// Group rows by product, sorted by product code
+    ///
+    /// If a product lists the same endpoint more than once, the highest
+    /// sequence number wins.
+    pub fn products(&self) -> Vec<ProductSummary> {
+        let mut products: BTreeMap<&str, BTreeMap<SummaryEndpoint, u64>> = BTreeMap::new();
+
+        for entry in &self.entries {
+            let seqn = products
+                .entry(entry.product.as_str())
+                .or_default()
+                .entry(entry.endpoint)
+                .or_insert(entry.seqn);
+            *seqn = (*seqn).max(entry.seqn);
+        }
+
+        products
+            .into_iter()
+            .map(|(product, endpoints)| ProductSummary {
+                product: product.to_string(),
+                endpoints,
+            })
+            .collect()
+    }
+
+    /// Latest sequence number of `endpoint` for `product`
+    pub fn latest_seqn(&self, product: &str, endpoint: SummaryEndpoint) -> Option<u64> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.product == product && entry.endpoint == endpoint)
+            .map(|entry| entry.seqn)
+            .max()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use cascette_formats::CascFormat;
+
+    fn summary() -> SummaryResponse {
+        let bpsv = "Product!STRING:0|Seqn!DEC:4|Flags!STRING:0\n\
+                    ## seqn = 3016450\n\
+                    wow|3016450|\n\
+                    wow|3016400|cdn\n\
+                    wow|2950123|bgdl\n\
+                    wow_classic|3016300|\n\
+                    agent|3016200|cdn\n\
+                    agent|3016100|future\n";
+        let document = <BpsvDocument as CascFormat>::parse(bpsv.as_bytes())
+            .expect("Summary BPSV should parse");
+        SummaryResponse::from_bpsv(&document).expect("Summary should convert")
+    }
+
+    #[test]
+    fn test_from_bpsv() {
+        let summary = summary();
+        assert_eq!(summary.sequence_number(), Some(3_016_450));
+        // The row with unknown flags is skipped
+        assert_eq!(summary.entries().len(), 5);
+        assert_eq!(
+            summary.entries()[1],
+            SummaryEntry {
+                product: "wow".to_string(),
+                endpoint: SummaryEndpoint::Cdns,
+                seqn: 3_016_400,
+            }
+        );
+    }
+
+    #[test]
+    fn test_products_grouped() {
+        let products = summary().products();
+        let names: Vec<_> = products.iter().map(|p| p.product.as_str()).collect();
+        assert_eq!(names, ["agent", "wow", "wow_classic"]);
+
+        let wow = &products[1];
+        assert_eq!(wow.seqn(SummaryEndpoint::Versions), Some(3_016_450));
+        assert_eq!(wow.seqn(SummaryEndpoint::Cdns), Some(3_016_400));
+        assert_eq!(wow.seqn(SummaryEndpoint::Bgdl), Some(2_950_123));
+
+        let agent = &products[0];
+        assert!(agent.has_endpoint(SummaryEndpoint::Cdns));
+        assert!(!agent.has_endpoint(SummaryEndpoint::Versions));
+    }
+
+    #[test]
+    fn test_latest_seqn() {
+        let summary = summary();
+        assert_eq!(
+            summary.latest_seqn("wow", SummaryEndpoint::Versions),
+            Some(3_016_450)
+        );
+        assert_eq!(
+            summary.latest_seqn("wow_classic", SummaryEndpoint::Cdns),
+            None
+        );
+        assert_eq!(summary.latest_seqn("d3", SummaryEndpoint::Versions), None);
+    }
+
+    #[test]
+    fn test_seqn_above_u32() {
+        let bpsv = "Product!STRING:0|Seqn!DEC:4|Flags!STRING:0\n\
+                    ## seqn = 20261014093015221\n\
+                    wow|20261014093015221|\n";
+        let document = <BpsvDocument as CascFormat>::parse(bpsv.as_bytes())
+            .expect("Summary BPSV should parse");
+        let summary = SummaryResponse::from_bpsv(&document).expect("Summary should convert");
+        assert_eq!(summary.sequence_number(), Some(20_261_014_093_015_221));
+        assert_eq!(
+            summary.latest_seqn("wow", SummaryEndpoint::Versions),
+            Some(20_261_014_093_015_221)
+        );
+    }
+
+    #[test]
+    fn test_missing_product_is_error() {
+        let bpsv = "Product!STRING:0|Seqn!DEC:4|Flags!STRING:0\n|3016450|\n";
+        let document = <BpsvDocument as CascFormat>::parse(bpsv.as_bytes())
+            .expect("Summary BPSV should parse");
+        assert!(matches!(
+            SummaryResponse::from_bpsv(&document),
+            Err(ProtocolError::Parse(_))
+        ));
+    }
+}
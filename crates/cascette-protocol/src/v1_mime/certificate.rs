@@ -12,6 +12,10 @@
 use crate::client::RibbitClient;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::error::{ProtocolError, Result};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::v1_mime::ocsp::{OcspStatus, ocsp_response_der, parse_ocsp_response};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::v1_mime::signature::verify_certificate_signature;
 use crate::v1_mime::types::CertificateInfo;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::v1_mime::types::PublicKeyInfo;
@@ -25,8 +29,119 @@ use tracing::{debug, warn};
 #[cfg(not(target_arch = "wasm32"))]
 use x509_cert::certificate::Certificate;
 #[cfg(not(target_arch = "wasm32"))]
+use x509_cert::ext::pkix::AuthorityKeyIdentifier;
+#[cfg(not(target_arch = "wasm32"))]
 use x509_cert::spki::SubjectPublicKeyInfoRef;
 
+/// Maximum number of certificates [`CertificateFetcher::fetch_chain`]
+/// follows by default
+#[cfg(not(target_arch = "wasm32"))]
+pub const DEFAULT_CHAIN_DEPTH: usize = 8;
+
+/// A certificate of a chain fetched by [`CertificateFetcher::fetch_chain`]
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct ChainCertificate {
+    /// Certificate information
+    pub info: CertificateInfo,
+    /// Authority Key Identifier (hex string) naming the issuer's SKI
+    pub authority_key_identifier: Option<String>,
+    /// PEM encoding of the certificate
+    pub pem: String,
+}
+
+/// Certificate chain from a signing certificate towards its root
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct CertificateChain {
+    /// Certificates, starting with the requested one, each followed by its issuer
+    pub certificates: Vec<ChainCertificate>,
+    /// Whether the chain ends in a self-signed root
+    pub reaches_root: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl CertificateChain {
+    /// Concatenate the PEM encodings of all certificates, leaf first
+    pub fn to_pem_bundle(&self) -> String {
+        self.certificates
+            .iter()
+            .map(|certificate| certificate.pem.as_str())
+            .collect()
+    }
+
+    /// Whether the chain leads to one of the trust `anchors`
+    ///
+    /// Walking from the first certificate, a certificate whose public key
+    /// belongs to an anchor, or whose signature verifies against an
+    /// anchor's key, ends the walk successfully. Otherwise the next
+    /// certificate must have signed it. Fetched roots are never trusted by
+    /// themselves, only the configured anchors are.
+    ///
+    /// # Errors
+    /// Returns an error if a certificate of the chain cannot be parsed
+    pub fn is_anchored(&self, anchors: &[CertificateInfo]) -> Result<bool> {
+        let key_of = |info: &CertificateInfo| info.public_key.as_ref().map(|k| k.key_bytes.clone());
+        let signed_by = |certificate: &Certificate, issuer: &CertificateInfo| {
+            issuer.public_key.as_ref().is_some_and(|key| {
+                verify_certificate_signature(certificate, key).unwrap_or_else(|e| {
+                    debug!("Certificate signature check failed: {}", e);
+                    false
+                })
+            })
+        };
+
+        for (i, link) in self.certificates.iter().enumerate() {
+            let key = key_of(&link.info);
+            if key.is_some() && anchors.iter().any(|anchor| key_of(anchor) == key) {
+                return Ok(true);
+            }
+
+            let certificate = CertificateFetcher::parse_certificate(&link.pem)?;
+            if anchors.iter().any(|anchor| signed_by(&certificate, anchor)) {
+                return Ok(true);
+            }
+
+            let issued = self
+                .certificates
+                .get(i + 1)
+                .is_some_and(|issuer| signed_by(&certificate, &issuer.info));
+            if !issued {
+                debug!("Certificate chain breaks at {}", link.info.subject);
+                return Ok(false);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+/// Parse every certificate of a PEM bundle
+///
+/// # Errors
+/// Returns an error if the bundle holds no certificate or one of them
+/// cannot be parsed
+#[cfg(not(target_arch = "wasm32"))]
+pub fn parse_pem_certificates(pem: &str) -> Result<Vec<CertificateInfo>> {
+    const END: &str = "-----END CERTIFICATE-----";
+
+    let mut certificates = Vec::new();
+    let mut rest = pem;
+    while let Some(end) = rest.find(END) {
+        let block = &rest[..end + END.len()];
+        let certificate = CertificateFetcher::parse_certificate(block)?;
+        certificates.push(CertificateFetcher::extract_certificate_info(&certificate)?);
+        rest = &rest[end + END.len()..];
+    }
+
+    if certificates.is_empty() {
+        return Err(ProtocolError::Parse(
+            "PEM bundle does not contain a certificate".to_string(),
+        ));
+    }
+    Ok(certificates)
+}
+
 /// Certificate fetcher for retrieving certificates by various identifiers
 ///
 /// **Note**: This is only available on native platforms as it requires
@@ -59,6 +174,111 @@ impl<'a> CertificateFetcher<'a> {
     pub async fn fetch_by_ski(&self, ski: &str) -> Result<CertificateInfo> {
         info!("Fetching certificate for SKI: {}", ski);
 
+        let cert = self.fetch_certificate(ski).await?;
+        Self::extract_certificate_info(&cert)
+    }
+
+    /// Fetch the certificate chain of a Subject Key Identifier (SKI)
+    ///
+    /// Starting with the certificate for `ski`, the Authority Key Identifier
+    /// of each certificate is fetched as the next one. The chain ends at a
+    /// self-signed root, at a certificate without an Authority Key
+    /// Identifier, or after `max_depth` certificates;
+    /// [`CertificateChain::reaches_root`] tells these apart.
+    ///
+    /// # Errors
+    /// Returns an error if a certificate cannot be fetched or parsed, or if
+    /// the endpoint returns a certificate with a different SKI
+    pub async fn fetch_chain(&self, ski: &str, max_depth: usize) -> Result<CertificateChain> {
+        info!("Fetching certificate chain for SKI: {}", ski);
+
+        let mut chain = CertificateChain {
+            certificates: Vec::new(),
+            reaches_root: false,
+        };
+        let mut next = Some(ski.to_string());
+
+        while let Some(ski) = next.take() {
+            if chain.certificates.len() >= max_depth {
+                debug!("Stopping certificate chain at depth {}", max_depth);
+                break;
+            }
+
+            let cert = self.fetch_certificate(&ski).await?;
+            let info = Self::extract_certificate_info(&cert)?;
+            Self::check_ski(&info, &ski)?;
+
+            let authority_key_identifier = Self::extract_authority_key_identifier(&cert);
+            let self_signed = cert.tbs_certificate.issuer == cert.tbs_certificate.subject;
+            if self_signed {
+                chain.reaches_root = true;
+            } else if let Some(aki) = &authority_key_identifier {
+                let seen = aki.eq_ignore_ascii_case(&ski)
+                    || chain.certificates.iter().any(|certificate| {
+                        certificate
+                            .info
+                            .subject_key_identifier
+                            .as_deref()
+                            .is_some_and(|seen| seen.eq_ignore_ascii_case(aki))
+                    });
+                if seen {
+                    warn!("Certificate chain for {} loops at {}", ski, aki);
+                } else {
+                    next = Some(aki.clone());
+                }
+            }
+
+            let der = cert
+                .to_der()
+                .map_err(|e| ProtocolError::Parse(format!("Failed to encode certificate: {e}")))?;
+            chain.certificates.push(ChainCertificate {
+                info,
+                authority_key_identifier,
+                pem: encode_pem_certificate(&der),
+            });
+        }
+
+        Ok(chain)
+    }
+
+    /// Fetch the OCSP revocation status of a Subject Key Identifier (SKI)
+    ///
+    /// The certificate is fetched from the certs endpoint for its serial
+    /// number, then the `v1/ocsp/{ski}` endpoint is queried; see the
+    /// [`ocsp`](crate::v1_mime::ocsp) module.
+    ///
+    /// # Errors
+    /// Returns an error if the certificate or OCSP response cannot be
+    /// fetched or parsed, the endpoint returns a certificate with a
+    /// different SKI, or the response has no status for the certificate
+    pub async fn fetch_ocsp_status(&self, ski: &str) -> Result<OcspStatus> {
+        info!("Fetching OCSP status for SKI: {}", ski);
+
+        let cert = self.fetch_certificate(ski).await?;
+        Self::check_ski(&Self::extract_certificate_info(&cert)?, ski)?;
+        let response = self.client.query_raw(&format!("v1/ocsp/{ski}")).await?;
+        let der = ocsp_response_der(&response)?;
+
+        parse_ocsp_response(&der, Some(cert.tbs_certificate.serial_number.as_bytes()))
+    }
+
+    /// Check that a fetched certificate is the one for `ski`
+    fn check_ski(info: &CertificateInfo, ski: &str) -> Result<()> {
+        if info
+            .subject_key_identifier
+            .as_deref()
+            .is_some_and(|fetched| fetched.eq_ignore_ascii_case(ski))
+        {
+            return Ok(());
+        }
+        Err(ProtocolError::Parse(format!(
+            "Fetched certificate has SKI {}, expected {ski}",
+            info.subject_key_identifier.as_deref().unwrap_or("<none>")
+        )))
+    }
+
+    /// Fetch and parse the certificate for a SKI from the certs endpoint
+    async fn fetch_certificate(&self, ski: &str) -> Result<Certificate> {
         // Use the certs endpoint with SKI as the identifier
         let endpoint = format!("certs/{ski}");
 
@@ -66,7 +286,7 @@ impl<'a> CertificateFetcher<'a> {
         let response = self.client.query_tcp_only(&endpoint).await?;
 
         // Parse the response to extract certificate
-        Self::parse_certificate_response(&response)
+        Self::parse_certificate(&response)
     }
 
     /// Fetch certificate by hash (for OCSP-style requests)
@@ -88,8 +308,16 @@ impl<'a> CertificateFetcher<'a> {
         Self::parse_certificate_response(&response)
     }
 
-    /// Parse certificate from a response string
+    /// Parse certificate information from a response string
     fn parse_certificate_response(response: &str) -> Result<CertificateInfo> {
+        let cert = Self::parse_certificate(response)?;
+
+        // Extract certificate information
+        Self::extract_certificate_info(&cert)
+    }
+
+    /// Parse certificate from a response string
+    fn parse_certificate(response: &str) -> Result<Certificate> {
         debug!("Parsing certificate response: {} bytes", response.len());
 
         // Look for PEM certificate markers
@@ -103,10 +331,7 @@ impl<'a> CertificateFetcher<'a> {
         let cert_pem = Self::extract_pem_certificate(response)?;
 
         // Parse the certificate
-        let cert = Self::parse_pem_certificate(&cert_pem)?;
-
-        // Extract certificate information
-        Self::extract_certificate_info(&cert)
+        Self::parse_pem_certificate(&cert_pem)
     }
 
     /// Extract PEM certificate from response text
@@ -198,6 +423,20 @@ impl<'a> CertificateFetcher<'a> {
         ))
     }
 
+    /// Extract the key identifier of the Authority Key Identifier extension
+    fn extract_authority_key_identifier(cert: &Certificate) -> Option<String> {
+        use x509_cert::der::oid::AssociatedOid;
+
+        let extension = cert
+            .tbs_certificate
+            .extensions
+            .as_ref()?
+            .iter()
+            .find(|ext| ext.extn_id == AuthorityKeyIdentifier::OID)?;
+        let aki = AuthorityKeyIdentifier::from_der(extension.extn_value.as_bytes()).ok()?;
+        aki.key_identifier.map(|id| hex::encode(id.as_bytes()))
+    }
+
     /// Extract public key information from DER-encoded `SubjectPublicKeyInfo`
     fn extract_public_key_info_from_der(spki_der: &[u8]) -> Result<PublicKeyInfo> {
         let spki_ref = SubjectPublicKeyInfoRef::from_der(spki_der)
@@ -263,6 +502,19 @@ impl<'a> CertificateFetcher<'a> {
     }
 }
 
+/// Encode a DER certificate as PEM with 64-character lines
+#[cfg(not(target_arch = "wasm32"))]
+fn encode_pem_certificate(der: &[u8]) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(der);
+    let mut pem = String::from("-----BEGIN CERTIFICATE-----\n");
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(&String::from_utf8_lossy(line));
+        pem.push('\n');
+    }
+    pem.push_str("-----END CERTIFICATE-----\n");
+    pem
+}
+
 /// Fetch multiple certificates by Subject Key Identifiers
 ///
 /// This is a convenience function for fetching multiple certificates concurrently.
@@ -425,6 +677,166 @@ Some footer text
         assert_eq!(estimate_rsa_key_size(&[0u8; 600]), 4096);
     }
 
+    /// Serve canned responses chosen by command prefix until aborted
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn routed_client(
+        routes: Vec<(String, Vec<u8>)>,
+    ) -> (RibbitClient, tokio::task::JoinHandle<()>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Listener should bind");
+        let addr = listener
+            .local_addr()
+            .expect("Listener should have an address");
+        let handle = tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buffer = [0; 1024];
+                let Ok(n) = stream.read(&mut buffer).await else {
+                    continue;
+                };
+                let command = String::from_utf8_lossy(&buffer[..n]).to_string();
+                if let Some((_, response)) = routes
+                    .iter()
+                    .find(|(prefix, _)| command.starts_with(prefix.as_str()))
+                {
+                    let _ = stream.write_all(response).await;
+                }
+                let _ = stream.shutdown().await;
+            }
+        });
+        let client = RibbitClient::new(format!("tcp://{addr}")).expect("Client should build");
+        (client, handle)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn cert_routes() -> Vec<(String, Vec<u8>)> {
+        use crate::v1_mime::test_fixtures::{CA_CERT_PEM, CA_SKI, LEAF_CERT_PEM, LEAF_SKI};
+
+        vec![
+            (
+                format!("certs/{LEAF_SKI}"),
+                LEAF_CERT_PEM.as_bytes().to_vec(),
+            ),
+            (format!("certs/{CA_SKI}"), CA_CERT_PEM.as_bytes().to_vec()),
+        ]
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_fetch_chain_follows_authority_key_identifier() {
+        use crate::v1_mime::test_fixtures::{CA_SKI, LEAF_SKI};
+
+        let (client, handle) = routed_client(cert_routes()).await;
+        let fetcher = CertificateFetcher::new(&client);
+
+        let chain = fetcher
+            .fetch_chain(LEAF_SKI, DEFAULT_CHAIN_DEPTH)
+            .await
+            .expect("Chain should be fetched");
+        assert!(chain.reaches_root);
+        assert_eq!(chain.certificates.len(), 2);
+        assert_eq!(
+            chain.certificates[0].authority_key_identifier.as_deref(),
+            Some(CA_SKI)
+        );
+        assert_eq!(
+            chain.certificates[1].info.subject_key_identifier.as_deref(),
+            Some(CA_SKI)
+        );
+
+        // The bundle holds both certificates, each parsing back
+        let bundle = chain.to_pem_bundle();
+        assert_eq!(bundle.matches("-----BEGIN CERTIFICATE-----").count(), 2);
+        for certificate in &chain.certificates {
+            CertificateFetcher::parse_certificate(&certificate.pem)
+                .expect("Chain PEM should parse");
+        }
+
+        let partial = fetcher
+            .fetch_chain(LEAF_SKI, 1)
+            .await
+            .expect("Chain should be fetched");
+        assert!(!partial.reaches_root);
+        assert_eq!(partial.certificates.len(), 1);
+
+        handle.abort();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_fetch_chain_rejects_mismatched_ski() {
+        use crate::v1_mime::test_fixtures::{CA_CERT_PEM, LEAF_SKI};
+
+        let (client, handle) = routed_client(vec![(
+            format!("certs/{LEAF_SKI}"),
+            CA_CERT_PEM.as_bytes().to_vec(),
+        )])
+        .await;
+
+        let result = CertificateFetcher::new(&client)
+            .fetch_chain(LEAF_SKI, DEFAULT_CHAIN_DEPTH)
+            .await;
+        assert!(matches!(result, Err(ProtocolError::Parse(_))));
+
+        handle.abort();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_fetch_ocsp_status() {
+        use crate::v1_mime::ocsp::OcspCertStatus;
+        use crate::v1_mime::test_fixtures::{
+            LEAF_CERT_PEM, LEAF_SKI, good, ocsp_mime, ocsp_response,
+        };
+
+        let leaf = CertificateFetcher::parse_certificate(LEAF_CERT_PEM)
+            .expect("Leaf certificate should parse");
+        let serial = leaf.tbs_certificate.serial_number.as_bytes();
+        let response = ocsp_mime(&ocsp_response(&[(&[0x7F], good()), (serial, good())]));
+
+        let mut routes = cert_routes();
+        routes.push((format!("v1/ocsp/{LEAF_SKI}"), response));
+        let (client, handle) = routed_client(routes).await;
+
+        let status = CertificateFetcher::new(&client)
+            .fetch_ocsp_status(LEAF_SKI)
+            .await
+            .expect("OCSP status should be fetched");
+        assert_eq!(status.status, OcspCertStatus::Good);
+        assert_eq!(
+            status.serial_number.trim_start_matches("00"),
+            hex::encode(serial).trim_start_matches("00")
+        );
+
+        handle.abort();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_fetch_ocsp_status_rejects_mismatched_ski() {
+        use crate::v1_mime::test_fixtures::{
+            CA_CERT_PEM, LEAF_SKI, good, ocsp_mime, ocsp_response,
+        };
+
+        let (client, handle) = routed_client(vec![
+            (format!("certs/{LEAF_SKI}"), CA_CERT_PEM.as_bytes().to_vec()),
+            (
+                format!("v1/ocsp/{LEAF_SKI}"),
+                ocsp_mime(&ocsp_response(&[(&[0x01], good())])),
+            ),
+        ])
+        .await;
+
+        let result = CertificateFetcher::new(&client)
+            .fetch_ocsp_status(LEAF_SKI)
+            .await;
+        assert!(matches!(result, Err(ProtocolError::Parse(_))));
+
+        handle.abort();
+    }
+
     #[test]
     fn test_validate_certificate_chain() {
         // Test empty chain
@@ -29,7 +29,10 @@
 //! ```
 
 pub mod certificate;
+pub mod ocsp;
 pub mod signature;
+#[cfg(test)]
+pub(crate) mod test_fixtures;
 pub mod types;
 
 use crate::error::{ProtocolError, Result};
@@ -37,6 +40,7 @@ use crate::v1_mime::signature::parse_and_verify_signature;
 use crate::v1_mime::types::V1MimeResponse;
 use base64::Engine;
 use mail_parser::{HeaderValue, MessageParser, PartType};
+use sha2::{Digest, Sha256};
 use tracing::{debug, trace, warn};
 
 /// Parse a V1 MIME response with signature verification
@@ -83,12 +87,15 @@ pub fn parse_v1_mime_response(
     );
 
     // Extract data and signature parts
-    let (data_content, signature_bytes) = extract_mime_parts(&message)?;
+    let (data_content, signature_bytes, signed_content) = extract_mime_parts(&message)?;
 
     // Parse and verify signature if present
     let signature_info = if let Some(sig_bytes) = signature_bytes {
-        // Use the message data without checksum for signature verification
-        let verification_data = signed_data.unwrap_or(message_data);
+        // The detached signature covers the data part body; fall back to the
+        // message data (without checksum) when no data part was found
+        let verification_data = signed_data
+            .or(signed_content.as_deref())
+            .unwrap_or(message_data);
 
         match parse_and_verify_signature(&sig_bytes, Some(verification_data)) {
             Ok(sig_info) => {
@@ -114,16 +121,45 @@ pub fn parse_v1_mime_response(
         data: data_content,
         signature_info,
         checksum,
+        signed_content,
     })
 }
 
+/// Extract the decoded body of the data part of a V1 MIME response
+///
+/// Unlike [`parse_v1_mime_response`], binary parts such as the DER of an
+/// `ocsp` response are returned as they are, and the signature is not
+/// checked. A checksum epilogue is still validated.
+///
+/// # Errors
+/// Returns an error if the checksum does not match, the message cannot be
+/// parsed, or it has no data part.
+pub fn extract_data_part(raw_response: &[u8]) -> Result<Vec<u8>> {
+    let (message_data, checksum) = extract_checksum_epilogue(raw_response);
+    if let Some(ref expected_checksum) = checksum {
+        validate_checksum(message_data, expected_checksum)?;
+    }
+
+    let message = MessageParser::default()
+        .parse(message_data)
+        .ok_or_else(|| ProtocolError::Parse("Failed to parse MIME message".to_string()))?;
+
+    message
+        .parts
+        .iter()
+        .find(|part| is_data_part(&find_content_disposition(&part.headers)))
+        .map(|part| part.contents().to_vec())
+        .ok_or_else(|| ProtocolError::Parse("No data part found in MIME response".to_string()))
+}
+
 /// Extract data and signature parts from parsed MIME message
 // NOTE: Complexity from iterating parts with header matching and content extraction.
 // Future: Extract helpers for disposition checking and content type handling.
 #[allow(clippy::cognitive_complexity)]
-fn extract_mime_parts(message: &mail_parser::Message) -> Result<(String, Option<Vec<u8>>)> {
+fn extract_mime_parts(message: &mail_parser::Message) -> Result<MimeParts> {
     let mut data_content = None;
     let mut signature_bytes = None;
+    let mut signed_content = None;
 
     // Process each MIME part
     for (i, part) in message.parts.iter().enumerate() {
@@ -136,6 +172,7 @@ fn extract_mime_parts(message: &mail_parser::Message) -> Result<(String, Option<
         // Classify part based on Content-Disposition
         if is_data_part(&disposition) {
             data_content = extract_text_content(part);
+            signed_content = extract_raw_body(message, part);
             debug!(
                 "Extracted data content: {} bytes",
                 data_content.as_ref().map_or(0, String::len)
@@ -161,7 +198,24 @@ fn extract_mime_parts(message: &mail_parser::Message) -> Result<(String, Option<
         ProtocolError::Parse("No data content found in MIME response".to_string())
     })?;
 
-    Ok((data, signature_bytes))
+    Ok((data, signature_bytes, signed_content))
+}
+
+/// Data content, signature bytes and raw signed bytes of a V1 MIME message
+type MimeParts = (String, Option<Vec<u8>>, Option<Vec<u8>>);
+
+/// Extract the undecoded body bytes of a MIME part
+///
+/// This is the exact byte range the detached signature covers, before any
+/// trimming done for data extraction.
+fn extract_raw_body(
+    message: &mail_parser::Message,
+    part: &mail_parser::MessagePart,
+) -> Option<Vec<u8>> {
+    let raw = message.raw_message.as_ref();
+    let start = part.offset_body as usize;
+    let end = part.offset_end as usize;
+    raw.get(start..end).map(<[u8]>::to_vec)
 }
 
 /// Find Content-Disposition header value
@@ -299,10 +353,16 @@ fn extract_checksum_epilogue(raw_response: &[u8]) -> (&[u8], Option<String>) {
     }
 }
 
-/// Validate MD5 checksum
+/// Validate the checksum epilogue
+///
+/// Ribbit servers send a SHA-256 checksum (64 hex characters); 32 hex
+/// characters are checked as MD5.
 fn validate_checksum(data: &[u8], expected_checksum: &str) -> Result<()> {
-    let calculated = md5::compute(data);
-    let calculated_hex = hex::encode(calculated.0);
+    let calculated_hex = if expected_checksum.len() == 64 {
+        hex::encode(Sha256::digest(data))
+    } else {
+        hex::encode(md5::compute(data).0)
+    };
 
     if calculated_hex.eq_ignore_ascii_case(expected_checksum) {
         debug!("Checksum validation successful: {}", calculated_hex);
@@ -396,5 +456,58 @@ mod tests {
 
         assert!(validate_checksum(data, expected).is_ok());
         assert!(validate_checksum(data, "wrong_checksum").is_err());
+
+        // SHA-256 of "test data"
+        let expected = "916f0027a575074ce72a331777c3478d6513f786a591bd892da1a577bf2335f9";
+        assert!(validate_checksum(data, expected).is_ok());
+        assert!(validate_checksum(data, &"0".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn test_signature_covers_data_part_body() {
+        let raw = test_fixtures::signed_mime(test_fixtures::SIGNATURE_WITH_CERT);
+        let parsed = parse_v1_mime_response(&raw, None).expect("Operation should succeed");
+
+        assert_eq!(
+            parsed.signed_content.as_deref(),
+            Some(test_fixtures::VERSIONS_BPSV)
+        );
+        let sig_info = parsed.signature_info.expect("Signature should be parsed");
+        assert_eq!(sig_info.signer_count, 1);
+        assert!(
+            sig_info.verification.is_valid,
+            "{:?}",
+            sig_info.verification
+        );
+    }
+
+    #[test]
+    fn test_signer_without_embedded_certificate() {
+        let raw = test_fixtures::signed_mime(test_fixtures::SIGNATURE_WITHOUT_CERT);
+        let parsed = parse_v1_mime_response(&raw, None).expect("Operation should succeed");
+
+        let sig_info = parsed.signature_info.expect("Signature should be parsed");
+        assert_eq!(sig_info.certificate_count, 0);
+        let signer = &sig_info.signers[0];
+        assert!(signer.certificate.is_none());
+        assert!(matches!(
+            &signer.identifier,
+            types::SignerIdentifier::SubjectKeyIdentifier(ski) if ski == test_fixtures::SIGNER_SKI
+        ));
+        assert!(!sig_info.verification.is_valid);
+    }
+
+    #[test]
+    fn test_tampered_data_fails_verification() {
+        let mut raw = test_fixtures::signed_mime(test_fixtures::SIGNATURE_WITH_CERT);
+        let pos = raw
+            .windows(5)
+            .position(|w| w == b"61265")
+            .expect("Build id should be present");
+        raw[pos] = b'9';
+
+        let parsed = parse_v1_mime_response(&raw, None).expect("Operation should succeed");
+        let sig_info = parsed.signature_info.expect("Signature should be parsed");
+        assert!(!sig_info.verification.is_valid);
     }
 }
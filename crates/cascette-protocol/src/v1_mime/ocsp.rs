@@ -0,0 +1,400 @@
+//! OCSP revocation status for Ribbit signing certificates
+//!
+//! The Ribbit `v1/ocsp/{ski}` endpoint serves an OCSP response (RFC 6960)
+//! for a signing certificate, in the `ocsp` data part of a V1 MIME message.
+//! [`ocsp_response_der`] extracts the DER response and
+//! [`parse_ocsp_response`] decodes it down to the single response for a
+//! certificate, reporting its status with the `producedAt`, `thisUpdate`
+//! and `nextUpdate` times.
+//!
+//! Only the parts needed for the status are decoded; the responder's
+//! signature over the response is not verified.
+
+use crate::error::{ProtocolError, Result};
+use crate::v1_mime::{extract_data_part, is_v1_mime_response};
+use base64::Engine;
+use der::asn1::{AnyRef, BitStringRef, GeneralizedTime, ObjectIdentifier, OctetStringRef, UintRef};
+use der::{Decode, Enumerated, Reader, Sequence, SliceReader, Tag, TagNumber, Tagged};
+use serde::Serialize;
+
+/// `id-pkix-ocsp-basic`, the only response type in use
+pub(super) const ID_PKIX_OCSP_BASIC: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.48.1.1");
+
+/// Revocation status of a certificate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum OcspCertStatus {
+    /// Certificate is not revoked
+    Good,
+    /// Certificate has been revoked
+    Revoked,
+    /// Responder does not know the certificate
+    Unknown,
+}
+
+/// OCSP status of one certificate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OcspStatus {
+    /// Serial number of the certificate the status is for (hex string)
+    pub serial_number: String,
+    /// Revocation status
+    pub status: OcspCertStatus,
+    /// When the responder signed the response (RFC 3339)
+    pub produced_at: String,
+    /// When the status was known to be correct (RFC 3339)
+    pub this_update: String,
+    /// When newer status will be available (RFC 3339), if announced
+    pub next_update: Option<String>,
+    /// When the certificate was revoked (RFC 3339), for revoked certificates
+    pub revocation_time: Option<String>,
+}
+
+impl OcspStatus {
+    /// Render the status as pretty-printed JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| ProtocolError::Parse(format!("Failed to serialize OCSP status: {e}")))
+    }
+}
+
+/// `OCSPResponseStatus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enumerated)]
+#[repr(u32)]
+pub(super) enum ResponseStatus {
+    Successful = 0,
+    MalformedRequest = 1,
+    InternalError = 2,
+    TryLater = 3,
+    SigRequired = 5,
+    Unauthorized = 6,
+}
+
+/// `OCSPResponse`
+#[derive(Sequence)]
+pub(super) struct OcspResponse<'a> {
+    pub(super) response_status: ResponseStatus,
+    #[asn1(context_specific = "0", optional = "true")]
+    pub(super) response_bytes: Option<ResponseBytes<'a>>,
+}
+
+/// `ResponseBytes`
+#[derive(Sequence)]
+pub(super) struct ResponseBytes<'a> {
+    pub(super) response_type: ObjectIdentifier,
+    pub(super) response: OctetStringRef<'a>,
+}
+
+/// `BasicOCSPResponse`
+#[derive(Sequence)]
+pub(super) struct BasicOcspResponse<'a> {
+    pub(super) tbs_response_data: ResponseData<'a>,
+    pub(super) signature_algorithm: AnyRef<'a>,
+    pub(super) signature: BitStringRef<'a>,
+    #[asn1(context_specific = "0", optional = "true")]
+    pub(super) certs: Option<AnyRef<'a>>,
+}
+
+/// `ResponseData`
+#[derive(Sequence)]
+pub(super) struct ResponseData<'a> {
+    #[asn1(context_specific = "0", optional = "true")]
+    pub(super) version: Option<u8>,
+    /// `ResponderID`, a choice of `[1] Name` and `[2] KeyHash`
+    pub(super) responder_id: AnyRef<'a>,
+    pub(super) produced_at: GeneralizedTime,
+    pub(super) responses: Vec<SingleResponse<'a>>,
+    #[asn1(context_specific = "1", optional = "true")]
+    pub(super) response_extensions: Option<AnyRef<'a>>,
+}
+
+/// `SingleResponse`
+#[derive(Sequence)]
+pub(super) struct SingleResponse<'a> {
+    pub(super) cert_id: CertId<'a>,
+    /// `CertStatus`, implicitly tagged `[0]` good, `[1]` revoked, `[2]` unknown
+    pub(super) cert_status: AnyRef<'a>,
+    pub(super) this_update: GeneralizedTime,
+    #[asn1(context_specific = "0", optional = "true")]
+    pub(super) next_update: Option<GeneralizedTime>,
+    #[asn1(context_specific = "1", optional = "true")]
+    pub(super) single_extensions: Option<AnyRef<'a>>,
+}
+
+/// `CertID`
+#[derive(Sequence)]
+pub(super) struct CertId<'a> {
+    pub(super) hash_algorithm: AnyRef<'a>,
+    pub(super) issuer_name_hash: OctetStringRef<'a>,
+    pub(super) issuer_key_hash: OctetStringRef<'a>,
+    pub(super) serial_number: UintRef<'a>,
+}
+
+/// Parse a DER OCSP response into the status of one certificate
+///
+/// With `serial_number`, the single response for that certificate is used;
+/// without it, the first one. Leading zero bytes of the serial number are
+/// ignored.
+///
+/// # Errors
+///
+/// Returns an error if the response is malformed, reports a status other
+/// than `successful`, is not a basic response, or has no single response
+/// for the certificate.
+pub fn parse_ocsp_response(der: &[u8], serial_number: Option<&[u8]>) -> Result<OcspStatus> {
+    let response = OcspResponse::from_der(der)
+        .map_err(|e| ProtocolError::Parse(format!("Invalid OCSP response: {e}")))?;
+    if response.response_status != ResponseStatus::Successful {
+        return Err(ProtocolError::Parse(format!(
+            "OCSP responder returned {:?}",
+            response.response_status
+        )));
+    }
+
+    let bytes = response
+        .response_bytes
+        .ok_or_else(|| ProtocolError::Parse("OCSP response has no response bytes".to_string()))?;
+    if bytes.response_type != ID_PKIX_OCSP_BASIC {
+        return Err(ProtocolError::Parse(format!(
+            "Unsupported OCSP response type {}",
+            bytes.response_type
+        )));
+    }
+    let basic = BasicOcspResponse::from_der(bytes.response.as_bytes())
+        .map_err(|e| ProtocolError::Parse(format!("Invalid basic OCSP response: {e}")))?;
+    let data = basic.tbs_response_data;
+
+    let single = match serial_number {
+        Some(serial) => data
+            .responses
+            .iter()
+            .find(|single| {
+                trim_leading_zeros(single.cert_id.serial_number.as_bytes())
+                    == trim_leading_zeros(serial)
+            })
+            .ok_or_else(|| {
+                ProtocolError::Parse(format!(
+                    "OCSP response has no status for serial {}",
+                    hex::encode(serial)
+                ))
+            })?,
+        None => data
+            .responses
+            .first()
+            .ok_or_else(|| ProtocolError::Parse("OCSP response has no responses".to_string()))?,
+    };
+
+    let (status, revocation_time) = decode_cert_status(&single.cert_status)?;
+    Ok(OcspStatus {
+        serial_number: hex::encode(single.cert_id.serial_number.as_bytes()),
+        status,
+        produced_at: data.produced_at.to_date_time().to_string(),
+        this_update: single.this_update.to_date_time().to_string(),
+        next_update: single
+            .next_update
+            .map(|time| time.to_date_time().to_string()),
+        revocation_time,
+    })
+}
+
+/// Extract the DER OCSP response from an endpoint response body
+///
+/// A V1 MIME body is replaced by its `ocsp` data part. The result is used
+/// as is when it is DER, and otherwise read as base64, between PEM markers
+/// if there are any.
+///
+/// # Errors
+///
+/// Returns an error if a MIME body has no valid data part, or if the
+/// response is neither DER nor valid base64.
+pub fn ocsp_response_der(body: &[u8]) -> Result<Vec<u8>> {
+    let data_part;
+    let body = if is_v1_mime_response(body) {
+        data_part = extract_data_part(body)?;
+        data_part.as_slice()
+    } else {
+        body
+    };
+    if body.first() == Some(&0x30) {
+        return Ok(body.to_vec());
+    }
+
+    let text = std::str::from_utf8(body)
+        .map_err(|e| ProtocolError::Parse(format!("Invalid UTF-8 OCSP response: {e}")))?;
+    let base64_content: String = if text.contains("-----BEGIN") {
+        text.lines()
+            .skip_while(|line| !line.starts_with("-----BEGIN"))
+            .skip(1)
+            .take_while(|line| !line.starts_with("-----END"))
+            .flat_map(str::split_whitespace)
+            .collect()
+    } else {
+        text.split_whitespace().collect()
+    };
+
+    base64::engine::general_purpose::STANDARD
+        .decode(base64_content)
+        .map_err(|e| ProtocolError::Parse(format!("Base64 decode error: {e}")))
+}
+
+/// Status and revocation time of an implicitly tagged `CertStatus`
+fn decode_cert_status(cert_status: &AnyRef<'_>) -> Result<(OcspCertStatus, Option<String>)> {
+    let Tag::ContextSpecific { number, .. } = cert_status.tag() else {
+        return Err(ProtocolError::Parse(format!(
+            "Unexpected OCSP certificate status tag {}",
+            cert_status.tag()
+        )));
+    };
+
+    match number {
+        TagNumber::N0 => Ok((OcspCertStatus::Good, None)),
+        TagNumber::N1 => {
+            // RevokedInfo starts with the revocation time
+            let revocation_time = SliceReader::new(cert_status.value())
+                .and_then(|mut reader| reader.decode::<GeneralizedTime>())
+                .map_err(|e| ProtocolError::Parse(format!("Invalid revocation info: {e}")))?;
+            Ok((
+                OcspCertStatus::Revoked,
+                Some(revocation_time.to_date_time().to_string()),
+            ))
+        }
+        TagNumber::N2 => Ok((OcspCertStatus::Unknown, None)),
+        other => Err(ProtocolError::Parse(format!(
+            "Unexpected OCSP certificate status [{other}]"
+        ))),
+    }
+}
+
+fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|&byte| byte != 0)
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::v1_mime::test_fixtures::{good, ocsp_mime, ocsp_response, ocsp_time};
+    use der::Encode;
+
+    fn revoked(secs: u64) -> Vec<u8> {
+        let revocation_time = ocsp_time(secs).to_der().expect("Time should encode");
+        let mut der = vec![
+            0xA1,
+            u8::try_from(revocation_time.len()).expect("Short time"),
+        ];
+        der.extend_from_slice(&revocation_time);
+        der
+    }
+
+    #[test]
+    fn test_parse_good_status() {
+        let der = ocsp_response(&[(&[0x01, 0x02], good())]);
+
+        let status = parse_ocsp_response(&der, None).expect("Response should parse");
+
+        assert_eq!(status.status, OcspCertStatus::Good);
+        assert_eq!(status.serial_number, "0102");
+        assert_eq!(status.produced_at, "2025-10-09T08:53:20Z");
+        assert_eq!(status.this_update, "2025-10-09T06:06:40Z");
+        assert_eq!(status.next_update.as_deref(), Some("2025-10-16T07:33:20Z"));
+        assert_eq!(status.revocation_time, None);
+    }
+
+    #[test]
+    fn test_parse_selects_status_by_serial() {
+        let der = ocsp_response(&[
+            (&[0x01], good()),
+            (&[0x02], revoked(1_700_000_000)),
+            (&[0x03], vec![0x82, 0x00]),
+        ]);
+
+        let revoked = parse_ocsp_response(&der, Some(&[0x00, 0x02])).expect("Serial 2 is listed");
+        assert_eq!(revoked.status, OcspCertStatus::Revoked);
+        assert_eq!(
+            revoked.revocation_time.as_deref(),
+            Some("2023-11-14T22:13:20Z")
+        );
+
+        let unknown = parse_ocsp_response(&der, Some(&[0x03])).expect("Serial 3 is listed");
+        assert_eq!(unknown.status, OcspCertStatus::Unknown);
+
+        assert!(parse_ocsp_response(&der, Some(&[0x04])).is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_unsuccessful_response() {
+        let der = OcspResponse {
+            response_status: ResponseStatus::TryLater,
+            response_bytes: None,
+        }
+        .to_der()
+        .expect("OCSP response should encode");
+
+        let err = parse_ocsp_response(&der, None).expect_err("TryLater has no status");
+        assert!(err.to_string().contains("TryLater"));
+        assert!(parse_ocsp_response(&[0x30, 0x03, 0x0A], None).is_err());
+    }
+
+    #[test]
+    fn test_ocsp_response_der_accepts_der_and_base64() {
+        let der = ocsp_response(&[(&[0x01], good())]);
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&der);
+
+        assert_eq!(ocsp_response_der(&der).expect("DER is kept"), der);
+        assert_eq!(
+            ocsp_response_der(encoded.as_bytes()).expect("Base64 should decode"),
+            der
+        );
+        let pem = format!(
+            "-----BEGIN OCSP RESPONSE-----\r\n{}\r\n{}\r\n-----END OCSP RESPONSE-----\r\n",
+            &encoded[..32],
+            &encoded[32..]
+        );
+        assert_eq!(
+            ocsp_response_der(pem.as_bytes()).expect("PEM should decode"),
+            der
+        );
+    }
+
+    #[test]
+    fn test_ocsp_response_der_extracts_mime_data_part() {
+        let der = ocsp_response(&[(&[0x01], good())]);
+        let mime = ocsp_mime(&der);
+
+        assert_eq!(
+            ocsp_response_der(&mime).expect("MIME data part should decode"),
+            der
+        );
+        let status = parse_ocsp_response(&ocsp_response_der(&mime).expect("Data part"), None)
+            .expect("Response should parse");
+        assert_eq!(status.status, OcspCertStatus::Good);
+
+        // A damaged message fails its checksum
+        let mut damaged = mime;
+        let at = damaged.len() / 3;
+        damaged[at] ^= 0x01;
+        assert!(matches!(
+            ocsp_response_der(&damaged),
+            Err(ProtocolError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_status_to_json() {
+        let der = ocsp_response(&[(&[0x01], good())]);
+        let json = parse_ocsp_response(&der, None)
+            .expect("Response should parse")
+            .to_json()
+            .expect("Status should serialize");
+
+        assert!(json.contains("\"status\": \"Good\""));
+        assert!(json.contains("\"next_update\": \"2025-10-16T07:33:20Z\""));
+    }
+}
@@ -108,6 +108,7 @@ pub fn parse_and_verify_signature(
         signer_count: signers.len(),
         certificate_count: certificates.len(),
         certificates,
+        signers,
         verification,
     })
 }
@@ -232,16 +233,46 @@ fn parse_signer_info(cms_signer: &CmsSignerInfo, certificates: &[CertificateInfo
     let digest_algorithm = oid_to_algorithm_name(&cms_signer.digest_alg.oid);
     let signature_algorithm = oid_to_algorithm_name(&cms_signer.signature_algorithm.oid);
 
+    // When signed attributes are present the signature covers their DER
+    // encoding (as a SET OF), not the content itself
+    let signed_attributes =
+        cms_signer
+            .signed_attrs
+            .as_ref()
+            .and_then(|attrs| match attrs.to_der() {
+                Ok(der) => Some(der),
+                Err(e) => {
+                    warn!("Failed to encode signed attributes: {}", e);
+                    None
+                }
+            });
+    let message_digest = cms_signer
+        .signed_attrs
+        .as_ref()
+        .and_then(extract_message_digest);
+
     SignerInfo {
         identifier,
         digest_algorithm,
         signature_algorithm,
         signature: cms_signer.signature.as_bytes().to_vec(),
         has_signed_attributes: cms_signer.signed_attrs.is_some(),
+        signed_attributes,
+        message_digest,
         certificate,
     }
 }
 
+/// Extract the `messageDigest` attribute (OID 1.2.840.113549.1.9.4)
+fn extract_message_digest(attrs: &cms::signed_data::SignedAttributes) -> Option<Vec<u8>> {
+    attrs
+        .iter()
+        .find(|attr| attr.oid.to_string() == "1.2.840.113549.1.9.4")
+        .and_then(|attr| attr.values.iter().next())
+        .and_then(|value| value.decode_as::<der::asn1::OctetString>().ok())
+        .map(|digest| digest.as_bytes().to_vec())
+}
+
 /// Find certificate matching the signer identifier
 fn find_matching_certificate(
     identifier: &SignerIdentifier,
@@ -380,19 +411,95 @@ fn verify_single_signature(signer: &SignerInfo, data: &[u8]) -> Result<bool> {
         ProtocolError::Parse("No certificate available for signature verification".to_string())
     })?;
 
-    let public_key = cert.public_key.as_ref().ok_or_else(|| {
+    verify_signer_with_certificate(signer, data, cert)
+}
+
+/// Verify one signer's signature over `data` using the given certificate
+///
+/// Used when the signer certificate is not embedded in the signature and
+/// has to be fetched separately. When the signer carries signed attributes,
+/// the `messageDigest` attribute is checked against `data` and the signature
+/// is verified over the encoded attributes.
+///
+/// # Errors
+/// Returns an error if the certificate has no usable public key or the
+/// digest/signature algorithm is not supported
+pub fn verify_signer_with_certificate(
+    signer: &SignerInfo,
+    data: &[u8],
+    certificate: &CertificateInfo,
+) -> Result<bool> {
+    let public_key = certificate.public_key.as_ref().ok_or_else(|| {
         ProtocolError::Parse("No public key available in certificate".to_string())
     })?;
 
+    let signed_bytes = if let Some(signed_attributes) = &signer.signed_attributes {
+        let expected = signer.message_digest.as_ref().ok_or_else(|| {
+            ProtocolError::Parse("Signed attributes lack a messageDigest".to_string())
+        })?;
+        if compute_digest(&signer.digest_algorithm, data)? != *expected {
+            debug!("messageDigest attribute does not match signed content");
+            return Ok(false);
+        }
+        signed_attributes.as_slice()
+    } else {
+        data
+    };
+
     // For RSA signatures, verify using the appropriate digest algorithm
     verify_rsa_signature(
         public_key,
-        data,
+        signed_bytes,
         &signer.signature,
         &signer.digest_algorithm,
     )
 }
 
+/// Verify that `certificate` was signed by the holder of `issuer_key`
+///
+/// Only RSA PKCS#1 v1.5 certificate signatures with SHA-256, SHA-384 or
+/// SHA-512 are supported.
+///
+/// # Errors
+/// Returns an error if the certificate cannot be encoded or its signature
+/// algorithm is not supported
+pub fn verify_certificate_signature(
+    certificate: &Certificate,
+    issuer_key: &PublicKeyInfo,
+) -> Result<bool> {
+    let algorithm = oid_to_algorithm_name(&certificate.signature_algorithm.oid);
+    let Some(digest_algorithm) = algorithm.strip_prefix("RSA with ") else {
+        return Err(ProtocolError::Parse(format!(
+            "Unsupported certificate signature algorithm: {algorithm}"
+        )));
+    };
+
+    let tbs = certificate
+        .tbs_certificate
+        .to_der()
+        .map_err(|e| ProtocolError::Parse(format!("Failed to encode certificate: {e}")))?;
+    let signature = certificate
+        .signature
+        .as_bytes()
+        .ok_or_else(|| ProtocolError::Parse("Certificate signature has unused bits".to_string()))?;
+
+    verify_rsa_signature(issuer_key, &tbs, signature, digest_algorithm)
+}
+
+/// Compute a digest of `data` with the named algorithm
+fn compute_digest(digest_algorithm: &str, data: &[u8]) -> Result<Vec<u8>> {
+    use sha2::Digest;
+
+    match digest_algorithm {
+        "SHA-256" => Ok(Sha256::digest(data).to_vec()),
+        "SHA-384" => Ok(Sha384::digest(data).to_vec()),
+        "SHA-512" => Ok(Sha512::digest(data).to_vec()),
+        _ => Err(ProtocolError::Parse(format!(
+            "Unsupported digest algorithm: {digest_algorithm}"
+        ))),
+    }
+}
+
 /// Verify RSA signature with specified digest algorithm
 fn verify_rsa_signature(
     public_key: &PublicKeyInfo,
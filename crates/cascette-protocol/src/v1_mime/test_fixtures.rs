@@ -0,0 +1,229 @@
+//! V1 MIME fixtures shared by the signature verification and OCSP tests
+//!
+//! The signatures were produced with `openssl cms -sign -binary -md sha256`
+//! over [`VERSIONS_BPSV`] using throwaway certificates: a self-signed one,
+//! and one issued by a test root. OCSP responses are built on the fly by
+//! [`ocsp_response`] and wrapped like the `v1/ocsp` endpoint by
+//! [`ocsp_mime`].
+
+#![allow(clippy::expect_used)]
+
+use crate::v1_mime::ocsp::{
+    BasicOcspResponse, CertId, ID_PKIX_OCSP_BASIC, OcspResponse, ResponseBytes, ResponseData,
+    ResponseStatus, SingleResponse,
+};
+use base64::Engine;
+use der::asn1::{AnyRef, BitStringRef, GeneralizedTime, OctetStringRef, UintRef};
+use der::{Decode, Encode, Tag, TagNumber};
+use std::time::Duration;
+
+/// Data part body covered by the fixture signatures
+pub const VERSIONS_BPSV: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/ribbit_versions.bpsv"
+));
+
+/// Detached signature with the signer certificate embedded
+pub const SIGNATURE_WITH_CERT: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/ribbit_versions.sig.der"
+));
+
+/// Detached signature identifying the signer by SKI, without certificates
+pub const SIGNATURE_WITHOUT_CERT: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/ribbit_versions_nocerts.sig.der"
+));
+
+/// PEM signer certificate, as served by the `certs/{ski}` endpoint
+pub const SIGNER_CERT_PEM: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/ribbit_signer.pem"
+));
+
+/// Subject Key Identifier of [`SIGNER_CERT_PEM`]
+pub const SIGNER_SKI: &str = "128d0e781495811d46185b8e0e539b987f9f7aff";
+
+/// Self-signed test CA certificate
+pub const CA_CERT_PEM: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/ribbit_tls_ca.pem"
+));
+
+/// Subject Key Identifier of [`CA_CERT_PEM`]
+pub const CA_SKI: &str = "0eb93f75584c48870052f86aacfd0396c9baf237";
+
+/// Certificate issued by [`CA_CERT_PEM`]
+pub const LEAF_CERT_PEM: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/ribbit_tls_cert.pem"
+));
+
+/// Subject Key Identifier of [`LEAF_CERT_PEM`]
+pub const LEAF_SKI: &str = "09d02c6725b7ecbba7ede05f79524e61ddd28ead";
+
+/// Build a V1 MIME response carrying [`VERSIONS_BPSV`] and `signature`
+pub fn signed_mime(signature: &[u8]) -> Vec<u8> {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(signature);
+
+    let mut raw = Vec::new();
+    raw.extend_from_slice(
+        b"MIME-Version: 1.0\r\n\
+          Content-Type: multipart/alternative; boundary=\"BOUNDARY\"\r\n\
+          \r\n\
+          --BOUNDARY\r\n\
+          Content-Type: text/plain\r\n\
+          Content-Disposition: version\r\n\
+          \r\n",
+    );
+    raw.extend_from_slice(VERSIONS_BPSV);
+    raw.extend_from_slice(
+        b"\r\n--BOUNDARY\r\n\
+          Content-Type: application/cms\r\n\
+          Content-Disposition: signature\r\n\
+          Content-Transfer-Encoding: base64\r\n\
+          \r\n",
+    );
+    raw.extend_from_slice(encoded.as_bytes());
+    raw.extend_from_slice(b"\r\n--BOUNDARY--\r\n");
+    raw
+}
+
+/// Self-signed RSA root that issued [`ISSUED_SIGNER_CERT_PEM`]
+pub const SIGNING_ROOT_PEM: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/ribbit_signing_root.pem"
+));
+
+/// Subject Key Identifier of [`SIGNING_ROOT_PEM`]
+pub const SIGNING_ROOT_SKI: &str = "42e25b1deaf5b3e5c77b1145b639ad9dd7466b92";
+
+/// Signer certificate issued by [`SIGNING_ROOT_PEM`]
+pub const ISSUED_SIGNER_CERT_PEM: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/ribbit_issued_signer.pem"
+));
+
+/// Subject Key Identifier of [`ISSUED_SIGNER_CERT_PEM`]
+pub const ISSUED_SIGNER_SKI: &str = "569948588b8f549070bd9d5a65d2bcba30cab7af";
+
+/// Detached signature by [`ISSUED_SIGNER_CERT_PEM`], identified by SKI
+pub const SIGNATURE_BY_ISSUED_SIGNER: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/ribbit_versions_issued.sig.der"
+));
+
+/// `producedAt` of [`ocsp_response`] responses
+const PRODUCED_AT: u64 = 1_760_000_000;
+/// `thisUpdate` of each single response
+const THIS_UPDATE: u64 = 1_759_990_000;
+/// `nextUpdate` of each single response
+const NEXT_UPDATE: u64 = 1_760_600_000;
+
+/// OCSP time `secs` seconds after the Unix epoch
+pub fn ocsp_time(secs: u64) -> GeneralizedTime {
+    GeneralizedTime::from_unix_duration(Duration::from_secs(secs))
+        .expect("Time should be representable")
+}
+
+/// DER OCSP response with one single response per `(serial, status)`
+///
+/// `status` is the DER of the implicitly tagged `CertStatus`.
+pub fn ocsp_response(singles: &[(&[u8], Vec<u8>)]) -> Vec<u8> {
+    let null_sequence = [0x05, 0x00];
+    let issuer_key_hash = [0xAA; 20];
+    let responder_key_hash = OctetStringRef::new(&issuer_key_hash)
+        .expect("Key hash should encode")
+        .to_der()
+        .expect("Key hash should encode");
+    let name_hash = [0xBB; 20];
+    let signature = [0x01, 0x02, 0x03];
+
+    let responses = singles
+        .iter()
+        .map(|(serial, status)| SingleResponse {
+            cert_id: CertId {
+                hash_algorithm: AnyRef::new(Tag::Sequence, &null_sequence)
+                    .expect("Algorithm should encode"),
+                issuer_name_hash: OctetStringRef::new(&name_hash).expect("Name hash should encode"),
+                issuer_key_hash: OctetStringRef::new(&issuer_key_hash)
+                    .expect("Key hash should encode"),
+                serial_number: UintRef::new(serial).expect("Serial should encode"),
+            },
+            cert_status: AnyRef::from_der(status).expect("Status should decode"),
+            this_update: ocsp_time(THIS_UPDATE),
+            next_update: Some(ocsp_time(NEXT_UPDATE)),
+            single_extensions: None,
+        })
+        .collect();
+
+    let basic = BasicOcspResponse {
+        tbs_response_data: ResponseData {
+            version: None,
+            responder_id: AnyRef::new(
+                Tag::ContextSpecific {
+                    constructed: true,
+                    number: TagNumber::N2,
+                },
+                &responder_key_hash,
+            )
+            .expect("Responder should encode"),
+            produced_at: ocsp_time(PRODUCED_AT),
+            responses,
+            response_extensions: None,
+        },
+        signature_algorithm: AnyRef::new(Tag::Sequence, &null_sequence)
+            .expect("Algorithm should encode"),
+        signature: BitStringRef::from_bytes(&signature).expect("Signature should encode"),
+        certs: None,
+    }
+    .to_der()
+    .expect("Basic response should encode");
+
+    OcspResponse {
+        response_status: ResponseStatus::Successful,
+        response_bytes: Some(ResponseBytes {
+            response_type: ID_PKIX_OCSP_BASIC,
+            response: OctetStringRef::new(&basic).expect("Response should encode"),
+        }),
+    }
+    .to_der()
+    .expect("OCSP response should encode")
+}
+
+/// DER of the `good` certificate status
+pub fn good() -> Vec<u8> {
+    vec![0x80, 0x00]
+}
+
+/// Wrap a DER OCSP response like the `v1/ocsp/{ski}` endpoint
+///
+/// The response is the base64 `ocsp` data part of a V1 MIME message,
+/// followed by a signature part and a SHA-256 checksum epilogue.
+pub fn ocsp_mime(der: &[u8]) -> Vec<u8> {
+    use sha2::{Digest, Sha256};
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    let message = format!(
+        "MIME-Version: 1.0\r\n\
+         Content-Type: multipart/alternative; boundary=\"BOUNDARY\"\r\n\
+         \r\n\
+         --BOUNDARY\r\n\
+         Content-Type: application/ocsp-response\r\n\
+         Content-Disposition: ocsp\r\n\
+         Content-Transfer-Encoding: base64\r\n\
+         \r\n\
+         {}\r\n\
+         --BOUNDARY\r\n\
+         Content-Type: application/cms\r\n\
+         Content-Disposition: signature\r\n\
+         Content-Transfer-Encoding: base64\r\n\
+         \r\n\
+         {}\r\n\
+         --BOUNDARY--\r\n",
+        engine.encode(der),
+        engine.encode(SIGNATURE_WITH_CERT),
+    );
+    let checksum = hex::encode(Sha256::digest(message.as_bytes()));
+    format!("{message}Checksum: {checksum}\r\n").into_bytes()
+}
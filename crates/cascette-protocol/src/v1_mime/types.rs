@@ -16,6 +16,8 @@ pub struct V1MimeResponse {
     pub signature_info: Option<SignatureInfo>,
     /// Checksum from epilogue if present
     pub checksum: Option<String>,
+    /// Bytes covered by the detached signature (the data part body)
+    pub signed_content: Option<Vec<u8>>,
 }
 
 /// Information about a PKCS#7 signature
@@ -35,6 +37,8 @@ pub struct SignatureInfo {
     pub certificate_count: usize,
     /// All certificates found in the signature
     pub certificates: Vec<CertificateInfo>,
+    /// Signers found in the signature
+    pub signers: Vec<SignerInfo>,
     /// Signature verification result
     pub verification: SignatureVerification,
 }
@@ -91,6 +95,10 @@ pub struct SignerInfo {
     pub signature: Vec<u8>,
     /// Whether this signer has signed attributes
     pub has_signed_attributes: bool,
+    /// DER-encoded signed attributes (the bytes the signature covers when present)
+    pub signed_attributes: Option<Vec<u8>>,
+    /// Value of the `messageDigest` signed attribute, if present
+    pub message_digest: Option<Vec<u8>>,
     /// The certificate used by this signer (if available)
     pub certificate: Option<CertificateInfo>,
 }
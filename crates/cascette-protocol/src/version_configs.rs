@@ -0,0 +1,559 @@
+//! Build, CDN and product configs of a versions row, fetched together
+//!
+//! A versions (or BGDL) row references three CDN documents: the build
+//! config, the CDN config and the product config. Describing a version
+//! needs all three, so [`CdnClient::fetch_version_configs`] downloads them
+//! concurrently and keeps one outcome per document. A failed download does
+//! not hide the others:
+//!
+//! ```text
+//! us 11.1.0.61491 (build 61491)
+//!   build config:   WOW-61491patch11.1.0_Retail, root 0f3b..., encoding 28ac...
+//!   cdn config:     error: Client error: 404 Not Found
+//!   product config: uid wow, no decryption key, launch -launcherlogin -uid wow
+//! ```
+//!
+//! [`VersionConfigs`] serializes with each document nested under the
+//! version entry, for JSON output.
+
+use std::fmt;
+
+use cascette_formats::config::{BuildConfig, CdnConfig as CdnConfigFile};
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+
+use crate::bgdl::BgdlEntry;
+use crate::cdn::{CdnClient, CdnEndpoint, ContentType};
+use crate::error::{ProtocolError, Result};
+use crate::product_config::ProductConfig;
+
+/// Platform whose launcher settings are shown by the text summary
+const SUMMARY_PLATFORM: &str = "win";
+
+/// Outcome of fetching one config document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ConfigDocument<T> {
+    /// The document was downloaded and parsed
+    Parsed {
+        /// The parsed document
+        document: T,
+    },
+    /// The row does not reference this document
+    NotReferenced,
+    /// Downloading or parsing the document failed
+    Failed {
+        /// Error message
+        error: String,
+    },
+}
+
+impl<T> ConfigDocument<T> {
+    fn from_result(result: Option<Result<T>>) -> Self {
+        match result {
+            Some(Ok(document)) => Self::Parsed { document },
+            Some(Err(e)) => Self::Failed {
+                error: e.to_string(),
+            },
+            None => Self::NotReferenced,
+        }
+    }
+
+    /// The parsed document, if it was fetched
+    pub const fn document(&self) -> Option<&T> {
+        match self {
+            Self::Parsed { document } => Some(document),
+            Self::NotReferenced | Self::Failed { .. } => None,
+        }
+    }
+
+    /// The error message, if fetching failed
+    pub fn error(&self) -> Option<&str> {
+        match self {
+            Self::Failed { error } => Some(error),
+            Self::Parsed { .. } | Self::NotReferenced => None,
+        }
+    }
+}
+
+/// Key fields of a build config
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BuildConfigSummary {
+    /// Build name (e.g. `WOW-61491patch11.1.0_Retail`)
+    pub build_name: Option<String>,
+    /// Build UID (e.g. `wow`)
+    pub build_uid: Option<String>,
+    /// Build product (e.g. `WoW`)
+    pub build_product: Option<String>,
+    /// Content key of the root file
+    pub root: Option<String>,
+    /// Content key of the encoding file
+    pub encoding: Option<String>,
+    /// Encoding key of the encoding file
+    pub encoding_key: Option<String>,
+    /// Content key of the install manifest
+    pub install: Option<String>,
+    /// Content key of the download manifest
+    pub download: Option<String>,
+    /// Hash of the patch config, if the build has patches
+    pub patch_config: Option<String>,
+}
+
+impl BuildConfigSummary {
+    /// Summarize a parsed build config
+    pub fn from_config(config: &BuildConfig) -> Self {
+        let encoding = config.encoding();
+        Self {
+            build_name: config.build_name().map(str::to_string),
+            build_uid: config.build_uid().map(str::to_string),
+            build_product: config.build_product().map(str::to_string),
+            root: config.root().map(str::to_string),
+            encoding_key: encoding.as_ref().and_then(|info| info.encoding_key.clone()),
+            encoding: encoding.map(|info| info.content_key),
+            install: config.install().into_iter().next().map(|i| i.content_key),
+            download: config.download().into_iter().next().map(|i| i.content_key),
+            patch_config: config.patch_config().map(str::to_string),
+        }
+    }
+}
+
+/// Archive layout of a CDN config
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CdnConfigSummary {
+    /// Number of data archives
+    pub archive_count: usize,
+    /// Hash of the combined archive index, if the config has one
+    pub archive_group: Option<String>,
+    /// Number of patch archives
+    pub patch_archive_count: usize,
+    /// Hash of the combined patch archive index, if the config has one
+    pub patch_archive_group: Option<String>,
+    /// Hash of the loose file index, if the config has one
+    pub file_index: Option<String>,
+}
+
+impl CdnConfigSummary {
+    /// Summarize a parsed CDN config
+    pub fn from_config(config: &CdnConfigFile) -> Self {
+        Self {
+            archive_count: config.archive_count(),
+            archive_group: config.archive_group().map(str::to_string),
+            patch_archive_count: config.patch_archives().len(),
+            patch_archive_group: config.patch_archive_group().map(str::to_string),
+            file_index: config.file_index().map(str::to_string),
+        }
+    }
+}
+
+/// The three config documents of one versions row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VersionConfigs {
+    /// Region code of the row
+    pub region: String,
+    /// Version string of the row
+    pub versions_name: String,
+    /// Build number of the row
+    pub build_id: u32,
+    /// Build config referenced by the row
+    pub build_config: ConfigDocument<BuildConfigSummary>,
+    /// CDN config referenced by the row
+    pub cdn_config: ConfigDocument<CdnConfigSummary>,
+    /// Product config referenced by the row
+    pub product_config: ConfigDocument<ProductConfig>,
+}
+
+impl VersionConfigs {
+    /// Whether every referenced document was fetched
+    pub fn is_complete(&self) -> bool {
+        self.build_config.error().is_none()
+            && self.cdn_config.error().is_none()
+            && self.product_config.error().is_none()
+    }
+}
+
+impl fmt::Display for VersionConfigs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} {} (build {})",
+            self.region, self.versions_name, self.build_id
+        )?;
+
+        write!(f, "  build config:   ")?;
+        write_document(f, &self.build_config, |f, build| {
+            f.write_str(build.build_name.as_deref().unwrap_or("unnamed build"))?;
+            write!(f, ", root {}", abbreviate(build.root.as_deref()))?;
+            write!(f, ", encoding {}", abbreviate(build.encoding.as_deref()))
+        })?;
+
+        write!(f, "  cdn config:     ")?;
+        write_document(f, &self.cdn_config, |f, cdn| {
+            write!(f, "{} archives", cdn.archive_count)?;
+            match &cdn.archive_group {
+                Some(group) => write!(f, ", archive group {}", abbreviate(Some(group)))?,
+                None => f.write_str(", no archive group")?,
+            }
+            if cdn.patch_archive_count > 0 {
+                write!(f, ", {} patch archives", cdn.patch_archive_count)?;
+            }
+            Ok(())
+        })?;
+
+        write!(f, "  product config: ")?;
+        write_document(f, &self.product_config, |f, product| {
+            write!(
+                f,
+                "uid {}",
+                product.uid(SUMMARY_PLATFORM).unwrap_or("unknown")
+            )?;
+            match product.decryption_key_name() {
+                Some(name) => write!(f, ", decryption key {name}")?,
+                None => f.write_str(", no decryption key")?,
+            }
+            let arguments = product.launch_arguments(SUMMARY_PLATFORM);
+            if !arguments.is_empty() {
+                write!(f, ", launch {}", arguments.join(" "))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Write one summary line, or the error or absence of the document.
+fn write_document<T>(
+    f: &mut fmt::Formatter<'_>,
+    document: &ConfigDocument<T>,
+    summary: impl FnOnce(&mut fmt::Formatter<'_>, &T) -> fmt::Result,
+) -> fmt::Result {
+    match document {
+        ConfigDocument::Parsed { document } => summary(f, document)?,
+        ConfigDocument::NotReferenced => f.write_str("not referenced")?,
+        ConfigDocument::Failed { error } => write!(f, "error: {error}")?,
+    }
+    writeln!(f)
+}
+
+/// First four hex characters of a hash, for the text summary.
+fn abbreviate(hash: Option<&str>) -> String {
+    hash.map_or_else(
+        || "none".to_string(),
+        |hash| format!("{}...", hash.get(..4).unwrap_or(hash)),
+    )
+}
+
+/// Decode a 16-byte config hash.
+pub(crate) fn config_key(hash: &str) -> Result<[u8; 16]> {
+    let mut key = [0u8; 16];
+    hex::decode_to_slice(hash, &mut key).map_err(|_| ProtocolError::InvalidKey)?;
+    Ok(key)
+}
+
+impl CdnClient {
+    /// Fetch the build, CDN and product configs referenced by `entry`
+    ///
+    /// The three documents are downloaded concurrently through
+    /// [`download`](Self::download) and
+    /// [`get_product_config`](Self::get_product_config), so cached documents
+    /// are not fetched again. Each document has its own outcome; a row
+    /// without a product config hash reports it as
+    /// [`ConfigDocument::NotReferenced`]. Versions rows parse into
+    /// [`BgdlEntry`] with [`parse_bgdl`](crate::parse_bgdl), as both
+    /// endpoints share a schema.
+    pub async fn fetch_version_configs(
+        &self,
+        endpoint: &CdnEndpoint,
+        entry: &BgdlEntry,
+    ) -> VersionConfigs {
+        let build_config = self.fetch_build_config_summary(endpoint, &entry.build_config);
+        let cdn_config = async {
+            let data = self
+                .download(
+                    endpoint,
+                    ContentType::Config,
+                    &config_key(&entry.cdn_config)?,
+                )
+                .await?;
+            let config = CdnConfigFile::parse(data.as_slice())
+                .map_err(|e| ProtocolError::Parse(format!("Invalid CDN config: {e}")))?;
+            Ok(CdnConfigSummary::from_config(&config))
+        };
+        let product_config = async {
+            match &entry.product_config {
+                Some(hash) => Some(self.get_product_config(endpoint, hash).await),
+                None => None,
+            }
+        };
+
+        let (build_config, cdn_config, product_config) =
+            futures::join!(build_config, cdn_config, product_config);
+
+        VersionConfigs {
+            region: entry.region.clone(),
+            versions_name: entry.versions_name.clone(),
+            build_id: entry.build_id,
+            build_config: ConfigDocument::from_result(Some(build_config)),
+            cdn_config: ConfigDocument::from_result(Some(cdn_config)),
+            product_config: ConfigDocument::from_result(product_config),
+        }
+    }
+
+    /// Download and summarize the build config with hash `hash`
+    pub(crate) async fn fetch_build_config_summary(
+        &self,
+        endpoint: &CdnEndpoint,
+        hash: &str,
+    ) -> Result<BuildConfigSummary> {
+        let data = self
+            .download(endpoint, ContentType::Config, &config_key(hash)?)
+            .await?;
+        let config = BuildConfig::parse(data.as_slice())
+            .map_err(|e| ProtocolError::Parse(format!("Invalid build config: {e}")))?;
+        Ok(BuildConfigSummary::from_config(&config))
+    }
+
+    /// Fetch the configs of several rows, such as one row per region
+    ///
+    /// At most `max_concurrency` rows (at least one) are fetched at once,
+    /// each with up to three concurrent downloads. The results are in the
+    /// order of `rows`.
+    pub async fn fetch_all_version_configs<'a>(
+        &self,
+        rows: impl IntoIterator<Item = (&'a CdnEndpoint, &'a BgdlEntry)>,
+        max_concurrency: usize,
+    ) -> Vec<VersionConfigs> {
+        stream::iter(rows)
+            .map(|(endpoint, entry)| self.fetch_version_configs(endpoint, entry))
+            .buffered(max_concurrency.max(1))
+            .collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::cache::ProtocolCache;
+    use crate::config::{CacheConfig, CdnConfig};
+    use crate::retry::RetryPolicy;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    // MD5 of the bodies below, which downloads are verified against
+    const BUILD_CONFIG: &str = "f731b0ceedb2b575dbf3b8ff5b3af1f0";
+    const CDN_CONFIG: &str = "9baea98f8aca42e616f083816a9fd66d";
+    const PRODUCT_CONFIG: &str = "53020d32e1a25648c8e1eafd5771935f";
+
+    const BUILD_CONFIG_BODY: &str = "# Build Configuration\n\
+        root = 0f3bc0ad4f27cdbe5d6b4e0bd3e4a9f1\n\
+        install = 6a8b2c3d4e5f60718293a4b5c6d7e8f9 1000\n\
+        download = 112233445566778899aabbccddeeff00 2000\n\
+        encoding = 28ac27d6d7b5fb4ea5e54b3bd1ab8a8c 1a2b3c4d5e6f708192a3b4c5d6e7f809\n\
+        encoding-size = 100 90\n\
+        build-name = WOW-61491patch11.1.0_Retail\n\
+        build-uid = wow\n\
+        build-product = WoW\n";
+
+    const CDN_CONFIG_BODY: &str = "# CDN Configuration\n\
+        archives = 00112233445566778899aabbccddeeff 0123456789abcdef0123456789abcdef\n\
+        archives-index-size = 100 200\n\
+        archive-group = fedcba9876543210fedcba9876543210\n";
+
+    fn config_path(hash: &str) -> String {
+        format!("/tpr/wow/config/{}/{}/{hash}", &hash[..2], &hash[2..4])
+    }
+
+    async fn mount(server: &MockServer, request_path: String, response: ResponseTemplate) {
+        Mock::given(method("GET"))
+            .and(path(request_path))
+            .respond_with(response)
+            .mount(server)
+            .await;
+    }
+
+    fn client(temp_dir: &TempDir) -> CdnClient {
+        let cache = ProtocolCache::new(&CacheConfig {
+            cache_dir: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .expect("Operation should succeed");
+        let config = CdnConfig::default().with_retry_policy(RetryPolicy {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            multiplier: 1.0,
+            jitter: false,
+            ..RetryPolicy::default()
+        });
+        CdnClient::new(Arc::new(cache), config).expect("Operation should succeed")
+    }
+
+    fn endpoint(server: &MockServer) -> CdnEndpoint {
+        CdnEndpoint {
+            host: server.uri().replace("http://", ""),
+            path: "tpr/wow".to_string(),
+            product_path: None,
+            scheme: Some("http".to_string()),
+            is_fallback: false,
+            strict: false,
+            max_hosts: None,
+        }
+    }
+
+    fn entry(region: &str, product_config: Option<&str>) -> BgdlEntry {
+        BgdlEntry {
+            region: region.to_string(),
+            build_config: BUILD_CONFIG.to_string(),
+            cdn_config: CDN_CONFIG.to_string(),
+            key_ring: None,
+            build_id: 61491,
+            versions_name: "11.1.0.61491".to_string(),
+            product_config: product_config.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_three_documents() {
+        let server = MockServer::start().await;
+        mount(
+            &server,
+            config_path(BUILD_CONFIG),
+            ResponseTemplate::new(200).set_body_string(BUILD_CONFIG_BODY),
+        )
+        .await;
+        mount(
+            &server,
+            config_path(CDN_CONFIG),
+            ResponseTemplate::new(200).set_body_string(CDN_CONFIG_BODY),
+        )
+        .await;
+        mount(
+            &server,
+            format!("/tpr/configs/data/53/02/{PRODUCT_CONFIG}"),
+            ResponseTemplate::new(200).set_body_bytes(
+                include_bytes!(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/tests/fixtures/product_config_wow.json"
+                ))
+                .to_vec(),
+            ),
+        )
+        .await;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let configs = client(&temp_dir)
+            .fetch_version_configs(&endpoint(&server), &entry("us", Some(PRODUCT_CONFIG)))
+            .await;
+        assert!(configs.is_complete());
+
+        let build = configs.build_config.document().expect("Build config");
+        assert_eq!(
+            build.build_name.as_deref(),
+            Some("WOW-61491patch11.1.0_Retail")
+        );
+        assert_eq!(
+            build.encoding_key.as_deref(),
+            Some("1a2b3c4d5e6f708192a3b4c5d6e7f809")
+        );
+        let cdn = configs.cdn_config.document().expect("CDN config");
+        assert_eq!(cdn.archive_count, 2);
+        assert!(cdn.archive_group.is_some());
+
+        assert_eq!(
+            configs.to_string(),
+            "us 11.1.0.61491 (build 61491)\n\
+             \x20 build config:   WOW-61491patch11.1.0_Retail, root 0f3b..., encoding 28ac...\n\
+             \x20 cdn config:     2 archives, archive group fedc...\n\
+             \x20 product config: uid wow, no decryption key, launch -launcherlogin -uid wow\n"
+        );
+
+        let json = serde_json::to_value(&configs).expect("Operation should succeed");
+        assert_eq!(json["build_config"]["status"], "parsed");
+        assert_eq!(json["cdn_config"]["document"]["archive_count"], 2);
+        assert_eq!(
+            json["product_config"]["document"]["all"]["config"]["product"],
+            "WoW"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_partial_failure_is_reported_inline() {
+        let server = MockServer::start().await;
+        mount(
+            &server,
+            config_path(BUILD_CONFIG),
+            ResponseTemplate::new(200).set_body_string(BUILD_CONFIG_BODY),
+        )
+        .await;
+        mount(&server, config_path(CDN_CONFIG), ResponseTemplate::new(404)).await;
+        mount(
+            &server,
+            format!("/tpr/configs/data/53/02/{PRODUCT_CONFIG}"),
+            ResponseTemplate::new(200).set_body_string("not json"),
+        )
+        .await;
+
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let client = client(&temp_dir);
+        let endpoint = endpoint(&server);
+        let us = entry("us", Some(PRODUCT_CONFIG));
+        let eu = entry("eu", None);
+        let results = client
+            .fetch_all_version_configs([(&endpoint, &us), (&endpoint, &eu)], 1)
+            .await;
+        assert_eq!(results.len(), 2);
+
+        let us = &results[0];
+        assert_eq!(us.region, "us");
+        assert!(!us.is_complete());
+        assert!(us.build_config.document().is_some());
+        assert!(us.cdn_config.error().is_some());
+        assert!(
+            us.product_config
+                .error()
+                .is_some_and(|e| e.contains("Invalid product config"))
+        );
+
+        let text = us.to_string();
+        assert!(text.contains("build config:   WOW-61491patch11.1.0_Retail"));
+        assert!(text.contains("cdn config:     error: "));
+        assert!(text.contains("product config: error: "));
+
+        let json = serde_json::to_value(us).expect("Operation should succeed");
+        assert_eq!(json["cdn_config"]["status"], "failed");
+        assert!(json["cdn_config"]["error"].is_string());
+
+        let eu = &results[1];
+        assert_eq!(eu.region, "eu");
+        assert_eq!(eu.product_config, ConfigDocument::NotReferenced);
+        assert!(eu.to_string().contains("product config: not referenced"));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_hash() {
+        let server = MockServer::start().await;
+        let temp_dir = TempDir::new().expect("Operation should succeed");
+        let mut entry = entry("us", None);
+        entry.build_config = "abcd".to_string();
+        entry.cdn_config = "zz".repeat(16);
+
+        let configs = client(&temp_dir)
+            .fetch_version_configs(&endpoint(&server), &entry)
+            .await;
+        assert_eq!(
+            configs.build_config.error(),
+            Some(ProtocolError::InvalidKey.to_string().as_str())
+        );
+        assert!(configs.cdn_config.error().is_some());
+        assert!(
+            server
+                .received_requests()
+                .await
+                .expect("Operation should succeed")
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,209 @@
+//! Integration tests for Ribbit over TLS.
+//!
+//! These tests start a local TLS server with the certificates in
+//! `tests/fixtures` and query it through `RibbitClientBuilder::with_tls`.
+//! Run them with `cargo test -p cascette-protocol --features tls`.
+
+#![cfg(feature = "tls")]
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::expect_used)]
+
+use cascette_protocol::{ProtocolError, RibbitClientBuilder, RibbitPoolConfig, RibbitPoolStats};
+use sha2::{Digest, Sha256};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, ServerConfig};
+
+const CA_PEM: &[u8] = include_bytes!("fixtures/ribbit_tls_ca.pem");
+const CERT_PEM: &[u8] = include_bytes!("fixtures/ribbit_tls_cert.pem");
+const KEY_PEM: &[u8] = include_bytes!("fixtures/ribbit_tls_key.pem");
+
+const VERSIONS_BPSV: &str = "Region!STRING:0|BuildConfig!HEX:16|CDNConfig!HEX:16|KeyRing!HEX:16|BuildId!DEC:4|VersionsName!String:0|ProductConfig!HEX:16\n\
+    ## seqn = 1\n\
+    us|be2bb98dc28aee05bbee519393696cdb|fac77b9ca52c84ac28ad83a7dbe1c829|3ca57fe7319a297346440e4d2a03a0cd|61265|11.1.7.61265|53020d32e1a25648c8e1eafd5771935f\n\n";
+
+/// V1 MIME response ending with a valid `Checksum:` line
+fn checksummed_mime() -> Vec<u8> {
+    let mut response = b"MIME-Version: 1.0\r\n\
+        Content-Type: multipart/alternative; boundary=\"BOUNDARY\"\r\n\
+        \r\n\
+        --BOUNDARY\r\n\
+        Content-Disposition: version\r\n\
+        \r\n"
+        .to_vec();
+    response.extend_from_slice(VERSIONS_BPSV.as_bytes());
+    response.extend_from_slice(b"\r\n--BOUNDARY--\r\n");
+    let checksum = hex::encode(Sha256::digest(&response));
+    response.extend_from_slice(format!("Checksum: {checksum}\r\n").as_bytes());
+    response
+}
+
+fn install_provider() {
+    let _ = tokio_rustls::rustls::crypto::ring::default_provider().install_default();
+}
+
+/// Client config trusting the fixture CA
+fn client_config() -> ClientConfig {
+    install_provider();
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(CertificateDer::from_pem_slice(CA_PEM).expect("CA certificate should parse"))
+        .expect("CA certificate should be accepted");
+    ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth()
+}
+
+/// Serve Ribbit over TLS: V1 commands get `checksummed_mime` on a kept-alive
+/// connection, V2 commands get raw BPSV and a closed connection.
+///
+/// Returns the address and the number of completed TLS handshakes.
+async fn spawn_tls_server() -> (SocketAddr, Arc<AtomicUsize>) {
+    install_provider();
+    let certs = vec![CertificateDer::from_pem_slice(CERT_PEM).expect("Certificate should parse")];
+    let key = PrivateKeyDer::from_pem_slice(KEY_PEM).expect("Key should parse");
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .expect("Server config should build");
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Listener should bind");
+    let addr = listener
+        .local_addr()
+        .expect("Listener should have an address");
+    let handshakes = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&handshakes);
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let acceptor = acceptor.clone();
+            let counter = Arc::clone(&counter);
+            tokio::spawn(async move {
+                let Ok(stream) = acceptor.accept(stream).await else {
+                    return;
+                };
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut reader = BufReader::new(stream);
+                loop {
+                    let mut command = String::new();
+                    if reader.read_line(&mut command).await.unwrap_or(0) == 0 {
+                        return;
+                    }
+                    if command.starts_with("v1/") {
+                        let _ = reader.get_mut().write_all(&checksummed_mime()).await;
+                    } else {
+                        let _ = reader.get_mut().write_all(VERSIONS_BPSV.as_bytes()).await;
+                        let _ = reader.get_mut().shutdown().await;
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    (addr, handshakes)
+}
+
+#[tokio::test]
+async fn test_query_over_tls() {
+    let (addr, handshakes) = spawn_tls_server().await;
+    let client = RibbitClientBuilder::new("localhost", addr.port())
+        .with_tls(client_config())
+        .build()
+        .expect("Client should build");
+
+    let raw = client
+        .query_raw("v2/products/wow/versions")
+        .await
+        .expect("Query over TLS should succeed");
+    assert_eq!(raw, VERSIONS_BPSV.as_bytes());
+
+    let doc = client
+        .parse_response(&raw)
+        .await
+        .expect("Response should parse");
+    assert_eq!(doc.rows().len(), 1);
+    assert_eq!(handshakes.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_pooled_tls_connection_is_reused() {
+    let (addr, handshakes) = spawn_tls_server().await;
+    let client = RibbitClientBuilder::new("localhost", addr.port())
+        .with_tls(client_config())
+        .verify_signatures(false)
+        .with_pool(RibbitPoolConfig::default())
+        .build()
+        .expect("Client should build");
+
+    for _ in 0..3 {
+        let doc = client
+            .query("v1/products/wow/versions")
+            .await
+            .expect("Pooled query over TLS should succeed");
+        assert_eq!(doc.rows().len(), 1);
+    }
+    assert_eq!(handshakes.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        client.pool_stats(),
+        Some(RibbitPoolStats {
+            hits: 2,
+            misses: 1,
+            reconnects: 0,
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_ip_address_server_name() {
+    let (addr, _) = spawn_tls_server().await;
+    let client = RibbitClientBuilder::new("127.0.0.1", addr.port())
+        .with_tls(client_config())
+        .build()
+        .expect("Client should build");
+
+    client
+        .query_raw("v2/products/wow/versions")
+        .await
+        .expect("Certificate should cover the IP address");
+}
+
+#[tokio::test]
+async fn test_untrusted_certificate_rejected() {
+    let (addr, handshakes) = spawn_tls_server().await;
+    install_provider();
+    let config = ClientConfig::builder()
+        .with_root_certificates(RootCertStore::empty())
+        .with_no_client_auth();
+    let client = RibbitClientBuilder::new("localhost", addr.port())
+        .with_tls(config)
+        .build()
+        .expect("Client should build");
+
+    let result = client.query_raw("v2/products/wow/versions").await;
+    assert!(
+        matches!(result, Err(ProtocolError::Network(_))),
+        "Handshake should fail, got {result:?}"
+    );
+    assert_eq!(handshakes.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn test_plain_client_fails_against_tls_server() {
+    let (addr, _) = spawn_tls_server().await;
+    let client = RibbitClientBuilder::new("127.0.0.1", addr.port())
+        .connect_timeout(std::time::Duration::from_secs(2))
+        .build()
+        .expect("Client should build");
+
+    // The server reads the command as a TLS handshake and answers with an
+    // alert, which is no Ribbit response
+    assert!(client.query("v2/products/wow/versions").await.is_err());
+}
@@ -57,11 +57,20 @@ fn bench_bpsv_generation(c: &mut Criterion) {
         cdn_path: "test/path".to_string(),
         tls_cert: None,
         tls_key: None,
+        rate_limit: 0.0,
+        rate_limit_burst: 1,
+        tcp_idle_timeout_secs: 10,
+        tcp_rate_limit: 0.0,
+        tcp_rate_limit_burst: 1,
+        tcp_rate_limit_exempt: Vec::new(),
+        signing: None,
+        admin_token: None,
+        disable_metrics: false,
     };
 
     let state = Arc::new(AppState::new(&config).expect("Failed to initialize benchmark AppState"));
-    let build = state
-        .database()
+    let database = state.database();
+    let build = database
         .latest_build("wow")
         .expect("Failed to get latest build for benchmark");
 
@@ -86,7 +95,8 @@ fn bench_bpsv_generation(c: &mut Criterion) {
     });
 
     group.bench_function(BenchmarkId::new("summary", "all"), |b| {
-        let products: Vec<&str> = state.database().products();
+        let database = state.database();
+        let products: Vec<&str> = database.products();
         b.iter(|| {
             let response =
                 cascette_ribbit::BpsvResponse::summary(black_box(&products), black_box(1730534400));
@@ -0,0 +1,111 @@
+//! Shell completions and man pages for the server binary.
+//!
+//! The binary's argument tree is generated into completion scripts for
+//! bash, zsh, fish, PowerShell and elvish, and into one troff man page per
+//! command. Man pages are named after the command path, e.g.
+//! `cascette-ribbit.1` and `cascette-ribbit-completions.1`, as `man`
+//! expects for subcommands.
+
+use clap::Command;
+use clap_complete::Shell;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Write the completion script of `command` for `shell` to `out`.
+pub fn write_completions(command: &Command, shell: Shell, out: &mut dyn Write) {
+    let mut command = command.clone();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, out);
+}
+
+/// Write a man page for `command` and each visible subcommand into `dir`.
+///
+/// The directory is created if needed. Returns the paths written, the
+/// top-level page first.
+///
+/// # Errors
+///
+/// Returns an error if the directory or a page cannot be written.
+pub fn write_manpages(command: &Command, dir: &Path) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let mut command = command.clone();
+    // Building fills in the display names of subcommands
+    command.build();
+    let mut written = Vec::new();
+    write_manpage(&command, dir, &mut written)?;
+    Ok(written)
+}
+
+fn write_manpage(command: &Command, dir: &Path, written: &mut Vec<PathBuf>) -> io::Result<()> {
+    let name = command
+        .get_display_name()
+        .unwrap_or_else(|| command.get_name());
+    let mut page = Vec::new();
+    clap_mangen::Man::new(command.clone()).render(&mut page)?;
+    let path = dir.join(format!("{name}.1"));
+    fs::write(&path, page)?;
+    written.push(path);
+
+    for subcommand in command.get_subcommands() {
+        if !subcommand.is_hide_set() && subcommand.get_name() != "help" {
+            write_manpage(subcommand, dir, written)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use cascette_ribbit::ServerConfig;
+    use clap::CommandFactory;
+
+    fn command() -> Command {
+        ServerConfig::command()
+            .subcommand(Command::new("completions").about("Print completions"))
+            .subcommand(
+                Command::new("manpages")
+                    .about("Write man pages")
+                    .subcommand(Command::new("nested").about("Nested command")),
+            )
+            .subcommand(Command::new("internal").hide(true))
+    }
+
+    #[test]
+    fn test_manpages_cover_visible_subcommands() {
+        let dir = tempfile::tempdir().unwrap();
+        let written = write_manpages(&command(), &dir.path().join("man1")).unwrap();
+
+        let names: Vec<_> = written
+            .iter()
+            .map(|path| path.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "cascette-ribbit.1",
+                "cascette-ribbit-completions.1",
+                "cascette-ribbit-manpages.1",
+                "cascette-ribbit-manpages-nested.1",
+            ]
+        );
+
+        let page = fs::read_to_string(&written[0]).unwrap();
+        assert!(page.starts_with(".ie"));
+        assert!(page.contains("http\\-bind"));
+    }
+
+    #[test]
+    fn test_bash_completions_list_flags_and_subcommands() {
+        let mut script = Vec::new();
+        write_completions(&command(), Shell::Bash, &mut script);
+        let script = String::from_utf8(script).unwrap();
+
+        assert!(script.contains("_cascette__ribbit()"));
+        assert!(script.contains("--http-bind"));
+        assert!(script.contains("completions"));
+        assert!(script.contains("complete -F _cascette__ribbit"));
+    }
+}
@@ -0,0 +1,173 @@
+//! Cascette Ribbit Server binary entry point.
+//!
+//! This is a thin wrapper around the cascette-ribbit library that:
+//! 1. Parses command-line arguments
+//! 2. Initializes logging
+//! 3. Loads configuration
+//! 4. Optionally runs a self-test against every protocol surface
+//! 5. Starts the server
+//!
+//! With the `docs` feature, the `completions` and `manpages` subcommands
+//! print shell completions or write man pages instead of starting the
+//! server.
+//!
+//! For library usage, see the cascette-ribbit crate documentation.
+
+use anyhow::{Result, bail};
+use cascette_ribbit::server::DEFAULT_SHUTDOWN_TIMEOUT;
+use cascette_ribbit::{Server, ServerConfig};
+use clap::Parser;
+#[cfg(feature = "docs")]
+use clap::{CommandFactory, Subcommand};
+#[cfg(feature = "docs")]
+use clap_complete::Shell;
+#[cfg(feature = "docs")]
+use std::path::PathBuf;
+
+#[cfg(feature = "docs")]
+mod docs;
+
+/// Command-line arguments: server configuration plus startup actions.
+#[derive(Debug, Parser)]
+#[command(
+    name = "cascette-ribbit",
+    about = "Complete Ribbit server for NGDP/CASC installations",
+    version,
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[cfg(feature = "docs")]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    config: ServerConfig,
+
+    /// Request TCP v1, TCP v2 and HTTP endpoints after startup and exit
+    /// with an error if any check fails
+    #[arg(long)]
+    self_test: bool,
+
+    /// Run the self-test, then shut down instead of serving
+    #[arg(long)]
+    self_test_only: bool,
+}
+
+/// Actions other than running the server.
+#[cfg(feature = "docs")]
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Shell to generate completions for
+        shell: Shell,
+    },
+    /// Write a man page for each command into a directory
+    Manpages {
+        /// Output directory, created if missing
+        dir: PathBuf,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let Cli {
+        #[cfg(feature = "docs")]
+        command,
+        config,
+        self_test,
+        self_test_only,
+    } = Cli::parse();
+
+    #[cfg(feature = "docs")]
+    match command {
+        Some(Command::Completions { shell }) => {
+            docs::write_completions(&Cli::command(), shell, &mut std::io::stdout());
+            return Ok(());
+        }
+        Some(Command::Manpages { dir }) => {
+            for path in docs::write_manpages(&Cli::command(), &dir)? {
+                println!("{}", path.display());
+            }
+            return Ok(());
+        }
+        None => {}
+    }
+
+    // Initialize tracing subscriber for logging
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .init();
+
+    tracing::info!("Cascette Ribbit Server starting...");
+
+    tracing::info!(
+        "Configuration loaded: HTTP={}, TCP={}, builds={:?}",
+        config.http_bind,
+        config.tcp_bind,
+        config.builds
+    );
+
+    // Validate configuration
+    config.validate()?;
+
+    // Create and run server
+    let mut server = Server::new(config)?;
+
+    if self_test || self_test_only {
+        let report = server.self_test().await?;
+        println!("{report}");
+
+        if !report.passed() {
+            server.graceful_shutdown(DEFAULT_SHUTDOWN_TIMEOUT).await?;
+            bail!("Self-test failed");
+        }
+        if self_test_only {
+            server.graceful_shutdown(DEFAULT_SHUTDOWN_TIMEOUT).await?;
+            return Ok(());
+        }
+    }
+
+    server.run().await?;
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "docs"))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manpages_cover_every_subcommand() {
+        let dir = tempfile::tempdir().unwrap();
+        let written = docs::write_manpages(&Cli::command(), dir.path()).unwrap();
+        let names: Vec<_> = written
+            .iter()
+            .map(|path| path.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "cascette-ribbit.1",
+                "cascette-ribbit-completions.1",
+                "cascette-ribbit-manpages.1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_subcommands_do_not_need_server_config() {
+        let cli = Cli::try_parse_from(["cascette-ribbit", "completions", "bash"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Completions { shell: Shell::Bash })
+        ));
+        assert!(
+            Cli::try_parse_from(["cascette-ribbit", "--self-test", "completions", "bash"]).is_err()
+        );
+    }
+}
@@ -84,6 +84,15 @@ async fn main() -> Result<()> {
         cdn_path: "tpr/wow".to_string(),
         tls_cert: None,
         tls_key: None,
+        rate_limit: 0.0,
+        rate_limit_burst: 1,
+        tcp_idle_timeout_secs: 10,
+        tcp_rate_limit: 0.0,
+        tcp_rate_limit_burst: 1,
+        tcp_rate_limit_exempt: Vec::new(),
+        signing: None,
+        admin_token: None,
+        disable_metrics: false,
     };
 
     // Validate configuration
@@ -27,9 +27,11 @@
 //! ```
 
 use crate::database::BuildRecord;
-use clap::Parser;
+use clap::{Args, Parser};
+use ipnet::IpNet;
 use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Server configuration loaded from CLI args and environment variables.
 #[derive(Debug, Clone, Parser)]
@@ -51,7 +53,7 @@ pub struct ServerConfig {
     #[arg(long, env = "CASCETTE_RIBBIT_TCP_BIND", default_value = "0.0.0.0:1119")]
     pub tcp_bind: SocketAddr,
 
-    /// Path to builds JSON database
+    /// Path to builds JSON database, or a directory of JSON files to merge
     #[arg(long, env = "CASCETTE_RIBBIT_BUILDS", default_value = "./builds.json")]
     pub builds: PathBuf,
 
@@ -74,6 +76,84 @@ pub struct ServerConfig {
     /// TLS private key file path (required if `tls_cert` is set)
     #[arg(long, env = "CASCETTE_RIBBIT_TLS_KEY")]
     pub tls_key: Option<PathBuf>,
+
+    /// HTTP requests per second allowed per client IP (0, the default,
+    /// disables limiting)
+    #[arg(long, env = "CASCETTE_RIBBIT_RATE_LIMIT", default_value_t = 0.0)]
+    pub rate_limit: f64,
+
+    /// HTTP requests a client IP may burst above the rate limit
+    #[arg(long, env = "CASCETTE_RIBBIT_RATE_LIMIT_BURST", default_value_t = 100)]
+    pub rate_limit_burst: u32,
+
+    /// Seconds a TCP connection may stay idle between requests (0, the
+    /// default, closes it after the first response)
+    #[arg(long, env = "CASCETTE_RIBBIT_TCP_IDLE_TIMEOUT", default_value_t = 0)]
+    pub tcp_idle_timeout_secs: u64,
+
+    /// TCP connections per second allowed per client IP (0, the default,
+    /// disables limiting)
+    #[arg(long, env = "CASCETTE_RIBBIT_TCP_RATE_LIMIT", default_value_t = 0.0)]
+    pub tcp_rate_limit: f64,
+
+    /// TCP connections a client IP may open at once above the rate limit
+    #[arg(
+        long,
+        env = "CASCETTE_RIBBIT_TCP_RATE_LIMIT_BURST",
+        default_value_t = 20
+    )]
+    pub tcp_rate_limit_burst: u32,
+
+    /// Networks exempt from the TCP rate limit, comma-separated CIDRs
+    /// (e.g. `127.0.0.0/8,::1/128` for local monitoring)
+    #[arg(
+        long,
+        env = "CASCETTE_RIBBIT_TCP_RATE_LIMIT_EXEMPT",
+        value_delimiter = ','
+    )]
+    pub tcp_rate_limit_exempt: Vec<IpNet>,
+
+    /// Signing certificate and key for TCP v1 responses (optional)
+    #[command(flatten)]
+    pub signing: Option<SigningConfig>,
+
+    /// Bearer token for `POST /admin/reload` (optional, the endpoint is
+    /// disabled without it)
+    #[arg(long, env = "CASCETTE_RIBBIT_ADMIN_TOKEN", hide_env_values = true)]
+    pub admin_token: Option<String>,
+
+    /// Disable the Prometheus metrics endpoint at `GET /metrics`
+    #[arg(long, env = "CASCETTE_RIBBIT_DISABLE_METRICS")]
+    pub disable_metrics: bool,
+}
+
+/// Certificate and private key used to sign TCP v1 responses.
+///
+/// When configured, v1 responses carry a CMS signature part over the BPSV
+/// payload, as Blizzard's servers do.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct SigningConfig {
+    /// Signing certificate file path (PEM)
+    #[arg(
+        id = "signing_cert",
+        long = "signing-cert",
+        value_name = "SIGNING_CERT",
+        env = "CASCETTE_RIBBIT_SIGNING_CERT",
+        required = false,
+        requires = "signing_key"
+    )]
+    pub cert_path: PathBuf,
+
+    /// Signing private key file path (PEM, PKCS#8 or PKCS#1 RSA)
+    #[arg(
+        id = "signing_key",
+        long = "signing-key",
+        value_name = "SIGNING_KEY",
+        env = "CASCETTE_RIBBIT_SIGNING_KEY",
+        required = false,
+        requires = "signing_cert"
+    )]
+    pub key_path: PathBuf,
 }
 
 impl ServerConfig {
@@ -106,6 +186,34 @@ impl ServerConfig {
         self.tls_cert.is_some() && self.tls_key.is_some()
     }
 
+    /// Check if HTTP rate limiting is enabled.
+    #[must_use]
+    pub fn has_rate_limit(&self) -> bool {
+        self.rate_limit > 0.0
+    }
+
+    /// Check if TCP rate limiting is enabled.
+    #[must_use]
+    pub fn has_tcp_rate_limit(&self) -> bool {
+        self.tcp_rate_limit > 0.0
+    }
+
+    /// Check if request metrics are collected and served at `/metrics`.
+    #[must_use]
+    pub const fn has_metrics(&self) -> bool {
+        !self.disable_metrics
+    }
+
+    /// Idle timeout between TCP requests, or `None` to close each TCP
+    /// connection after its first response.
+    #[must_use]
+    pub const fn tcp_idle_timeout(&self) -> Option<Duration> {
+        match self.tcp_idle_timeout_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
     /// Validate configuration.
     ///
     /// # Errors
@@ -114,6 +222,10 @@ impl ServerConfig {
     /// - Builds file doesn't exist
     /// - TLS cert is provided without key (or vice versa)
     /// - TLS cert/key files don't exist
+    /// - Signing cert/key files don't exist
+    /// - An HTTP or TCP rate limit is negative or not finite, or its burst
+    ///   is zero
+    /// - The admin token is empty
     pub fn validate(&self) -> Result<(), crate::error::ConfigError> {
         use crate::error::ConfigError;
 
@@ -156,6 +268,55 @@ impl ServerConfig {
             }
         }
 
+        // Validate response signing configuration
+        if let Some(signing) = &self.signing {
+            if !signing.cert_path.exists() {
+                return Err(ConfigError::Signing(format!(
+                    "signing certificate file not found: {}",
+                    signing.cert_path.display()
+                )));
+            }
+            if !signing.key_path.exists() {
+                return Err(ConfigError::Signing(format!(
+                    "signing private key file not found: {}",
+                    signing.key_path.display()
+                )));
+            }
+        }
+
+        // Validate HTTP rate limiting
+        if !self.rate_limit.is_finite() || self.rate_limit < 0.0 {
+            return Err(ConfigError::RateLimit(format!(
+                "rate limit must be a non-negative number of requests per second, got {}",
+                self.rate_limit
+            )));
+        }
+        if self.has_rate_limit() && self.rate_limit_burst == 0 {
+            return Err(ConfigError::RateLimit(
+                "rate limit burst must be at least 1".to_string(),
+            ));
+        }
+
+        // Validate TCP rate limiting
+        if !self.tcp_rate_limit.is_finite() || self.tcp_rate_limit < 0.0 {
+            return Err(ConfigError::RateLimit(format!(
+                "TCP rate limit must be a non-negative number of connections per second, got {}",
+                self.tcp_rate_limit
+            )));
+        }
+        if self.has_tcp_rate_limit() && self.tcp_rate_limit_burst == 0 {
+            return Err(ConfigError::RateLimit(
+                "TCP rate limit burst must be at least 1".to_string(),
+            ));
+        }
+
+        // An empty token would accept any "Bearer" header without one
+        if self.admin_token.as_deref().is_some_and(str::is_empty) {
+            return Err(ConfigError::AdminToken(
+                "admin token must not be empty".to_string(),
+            ));
+        }
+
         Ok(())
     }
 }
@@ -280,6 +441,15 @@ mod tests {
             cdn_path: "tpr/test".to_string(),
             tls_cert: Some(PathBuf::from("cert.pem")),
             tls_key: Some(PathBuf::from("key.pem")),
+            rate_limit: 0.0,
+            rate_limit_burst: 1,
+            tcp_idle_timeout_secs: 10,
+            tcp_rate_limit: 0.0,
+            tcp_rate_limit_burst: 1,
+            tcp_rate_limit_exempt: Vec::new(),
+            signing: None,
+            admin_token: None,
+            disable_metrics: false,
         };
 
         assert!(config.has_tls());
@@ -287,4 +457,175 @@ mod tests {
         config.tls_cert = None;
         assert!(!config.has_tls());
     }
+
+    #[test]
+    fn test_signing_args_are_optional_as_a_pair() {
+        let config = ServerConfig::try_parse_from(["cascette-ribbit"]).unwrap();
+        assert_eq!(config.signing, None);
+
+        let config = ServerConfig::try_parse_from([
+            "cascette-ribbit",
+            "--signing-cert",
+            "cert.pem",
+            "--signing-key",
+            "key.pem",
+        ])
+        .unwrap();
+        assert_eq!(
+            config.signing,
+            Some(SigningConfig {
+                cert_path: PathBuf::from("cert.pem"),
+                key_path: PathBuf::from("key.pem"),
+            })
+        );
+
+        assert!(
+            ServerConfig::try_parse_from(["cascette-ribbit", "--signing-cert", "cert.pem"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_args() {
+        let config = ServerConfig::try_parse_from(["cascette-ribbit"]).unwrap();
+        assert!(!config.has_rate_limit());
+        assert!(config.rate_limit_burst >= 1);
+
+        let config = ServerConfig::try_parse_from([
+            "cascette-ribbit",
+            "--rate-limit",
+            "20",
+            "--rate-limit-burst",
+            "5",
+        ])
+        .unwrap();
+        assert!(config.has_rate_limit());
+        assert_eq!(config.rate_limit_burst, 5);
+    }
+
+    #[test]
+    fn test_validate_rate_limit() {
+        let builds = tempfile::NamedTempFile::new().unwrap();
+        let mut config = ServerConfig::try_parse_from([
+            "cascette-ribbit",
+            "--builds",
+            builds.path().to_str().unwrap(),
+        ])
+        .unwrap();
+        assert!(config.validate().is_ok());
+
+        config.rate_limit = 50.0;
+        config.rate_limit_burst = 0;
+        assert!(matches!(
+            config.validate(),
+            Err(crate::error::ConfigError::RateLimit(_))
+        ));
+
+        // Burst is irrelevant while limiting is disabled
+        config.rate_limit = 0.0;
+        assert!(config.validate().is_ok());
+
+        config.rate_limit = f64::NAN;
+        assert!(config.validate().is_err());
+        config.rate_limit = -1.0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_tcp_rate_limit_args() {
+        let config = ServerConfig::try_parse_from(["cascette-ribbit"]).unwrap();
+        assert!(!config.has_tcp_rate_limit());
+        assert!(config.tcp_rate_limit_exempt.is_empty());
+
+        let config = ServerConfig::try_parse_from([
+            "cascette-ribbit",
+            "--tcp-rate-limit",
+            "10",
+            "--tcp-rate-limit-exempt",
+            "127.0.0.0/8,::1/128",
+        ])
+        .unwrap();
+        assert!(config.has_tcp_rate_limit());
+        assert!((config.tcp_rate_limit - 10.0).abs() < f64::EPSILON);
+        assert_eq!(
+            config.tcp_rate_limit_exempt,
+            vec![
+                "127.0.0.0/8".parse::<IpNet>().unwrap(),
+                "::1/128".parse::<IpNet>().unwrap()
+            ]
+        );
+
+        assert!(
+            ServerConfig::try_parse_from(["cascette-ribbit", "--tcp-rate-limit-exempt", "local"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_validate_tcp_rate_limit() {
+        let builds = tempfile::NamedTempFile::new().unwrap();
+        let mut config = ServerConfig::try_parse_from([
+            "cascette-ribbit",
+            "--builds",
+            builds.path().to_str().unwrap(),
+        ])
+        .unwrap();
+
+        config.tcp_rate_limit = 10.0;
+        config.tcp_rate_limit_burst = 0;
+        assert!(matches!(
+            config.validate(),
+            Err(crate::error::ConfigError::RateLimit(_))
+        ));
+        config.tcp_rate_limit = 0.0;
+        assert!(config.validate().is_ok());
+        config.tcp_rate_limit = f64::INFINITY;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_tcp_idle_timeout_args() {
+        let config = ServerConfig::try_parse_from(["cascette-ribbit"]).unwrap();
+        assert_eq!(config.tcp_idle_timeout(), None);
+
+        let config =
+            ServerConfig::try_parse_from(["cascette-ribbit", "--tcp-idle-timeout-secs", "10"])
+                .unwrap();
+        assert_eq!(config.tcp_idle_timeout(), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_admin_token() {
+        let builds = tempfile::NamedTempFile::new().unwrap();
+        let path = builds.path().to_str().unwrap();
+        let config = ServerConfig::try_parse_from(["cascette-ribbit", "--builds", path]).unwrap();
+        assert_eq!(config.admin_token, None);
+
+        let mut config = ServerConfig::try_parse_from([
+            "cascette-ribbit",
+            "--builds",
+            path,
+            "--admin-token",
+            "secret",
+        ])
+        .unwrap();
+        assert_eq!(config.admin_token.as_deref(), Some("secret"));
+        assert!(config.validate().is_ok());
+
+        config.admin_token = Some(String::new());
+        assert!(matches!(
+            config.validate(),
+            Err(crate::error::ConfigError::AdminToken(_))
+        ));
+    }
+
+    #[test]
+    fn test_disable_metrics() {
+        let config = ServerConfig::try_parse_from(["cascette-ribbit"]).unwrap();
+        assert!(config.has_metrics());
+
+        let config =
+            ServerConfig::try_parse_from(["cascette-ribbit", "--disable-metrics"]).unwrap();
+        assert!(!config.has_metrics());
+    }
 }
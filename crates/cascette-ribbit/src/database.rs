@@ -4,7 +4,7 @@
 
 use crate::error::DatabaseError;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
@@ -168,14 +168,53 @@ impl BuildDatabase {
     /// - Database is empty
     /// - Any build record fails validation
     pub fn from_file(path: &Path) -> Result<Self, DatabaseError> {
-        let file = File::open(path).map_err(|source| DatabaseError::LoadFailed {
-            path: path.to_path_buf(),
+        Self::from_builds(read_builds(path)?)
+    }
+
+    /// Load build database from all `*.json` files in a directory.
+    ///
+    /// Each file should contain a JSON array of `BuildRecord` objects, for
+    /// example one file per product. Files are read in name order and merged
+    /// into a single index. A build that appears in more than one file (same
+    /// product and build ID) is kept once, from the first file that lists it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DatabaseError` if:
+    /// - The directory or any file in it cannot be read
+    /// - Any file contains malformed JSON
+    /// - No builds are found in the directory
+    /// - Any build record fails validation
+    pub fn from_directory(dir: &Path) -> Result<Self, DatabaseError> {
+        let load_failed = |source| DatabaseError::LoadFailed {
+            path: dir.to_path_buf(),
             source,
-        })?;
+        };
+
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir).map_err(load_failed)? {
+            let path = entry.map_err(load_failed)?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut seen = HashSet::new();
+        let mut builds = Vec::new();
+        for path in &paths {
+            for build in read_builds(path)? {
+                if seen.insert((build.product.clone(), build.id)) {
+                    builds.push(build);
+                }
+            }
+        }
 
-        let reader = BufReader::new(file);
-        let builds: Vec<BuildRecord> = serde_json::from_reader(reader)?;
+        Self::from_builds(builds)
+    }
 
+    /// Validate and index a list of builds.
+    fn from_builds(builds: Vec<BuildRecord>) -> Result<Self, DatabaseError> {
         if builds.is_empty() {
             return Err(DatabaseError::EmptyDatabase);
         }
@@ -223,6 +262,13 @@ impl BuildDatabase {
         self.builds_by_product.keys().map(String::as_str).collect()
     }
 
+    /// Get the names of all loaded products, sorted.
+    pub fn loaded_products(&self) -> Vec<&str> {
+        let mut products = self.products();
+        products.sort_unstable();
+        products
+    }
+
     /// Get total number of builds loaded.
     #[must_use]
     pub const fn total_builds(&self) -> usize {
@@ -236,6 +282,17 @@ impl BuildDatabase {
     }
 }
 
+/// Read a JSON array of build records from a file.
+fn read_builds(path: &Path) -> Result<Vec<BuildRecord>, DatabaseError> {
+    let file = File::open(path).map_err(|source| DatabaseError::LoadFailed {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let reader = BufReader::new(file);
+    Ok(serde_json::from_reader(reader)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -304,4 +361,65 @@ mod tests {
         let err = BuildDatabase::from_file(temp_file.path()).unwrap_err();
         assert!(matches!(err, DatabaseError::EmptyDatabase));
     }
+
+    #[test]
+    fn test_database_from_directory_merges_files() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let wow = create_test_build();
+        let mut wow = BuildRecord {
+            product: "wow".to_string(),
+            ..wow
+        };
+        let mut wow_newer = wow.clone();
+        wow_newer.id = 2;
+        wow_newer.build_time = "2024-02-01T00:00:00+00:00".to_string();
+        let mut wowt = wow.clone();
+        wowt.product = "wowt".to_string();
+
+        std::fs::write(
+            dir.path().join("wow.json"),
+            serde_json::to_string(&vec![wow.clone(), wow_newer.clone()]).unwrap(),
+        )
+        .unwrap();
+        // Overlaps with wow.json on wow build 2
+        std::fs::write(
+            dir.path().join("wowt.json"),
+            serde_json::to_string(&vec![wowt, wow_newer]).unwrap(),
+        )
+        .unwrap();
+        // Non-JSON files are ignored
+        std::fs::write(dir.path().join("README.txt"), "not a database").unwrap();
+
+        let db = BuildDatabase::from_directory(dir.path()).unwrap();
+        assert_eq!(db.total_builds(), 3);
+        assert_eq!(db.loaded_products(), ["wow", "wowt"]);
+        assert_eq!(db.latest_build("wow").unwrap().id, 2);
+
+        // A build listed only in a later file is still merged
+        wow.id = 3;
+        wow.build_time = "2024-03-01T00:00:00+00:00".to_string();
+        std::fs::write(
+            dir.path().join("zz_extra.json"),
+            serde_json::to_string(&vec![wow]).unwrap(),
+        )
+        .unwrap();
+        let db = BuildDatabase::from_directory(dir.path()).unwrap();
+        assert_eq!(db.total_builds(), 4);
+        assert_eq!(db.latest_build("wow").unwrap().id, 3);
+    }
+
+    #[test]
+    fn test_database_from_directory_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = BuildDatabase::from_directory(dir.path()).unwrap_err();
+        assert!(matches!(err, DatabaseError::EmptyDatabase));
+
+        std::fs::write(dir.path().join("broken.json"), "[").unwrap();
+        let err = BuildDatabase::from_directory(dir.path()).unwrap_err();
+        assert!(matches!(err, DatabaseError::InvalidJson(_)));
+
+        let err = BuildDatabase::from_directory(&dir.path().join("missing")).unwrap_err();
+        assert!(matches!(err, DatabaseError::LoadFailed { .. }));
+    }
 }
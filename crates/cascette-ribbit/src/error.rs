@@ -61,6 +61,18 @@ pub enum ConfigError {
     /// Missing required configuration value
     #[error("Missing required configuration: {0}")]
     MissingRequired(String),
+
+    /// Response signing configuration error
+    #[error("Signing configuration error: {0}")]
+    Signing(String),
+
+    /// Invalid HTTP rate limit configuration
+    #[error("Rate limit configuration error: {0}")]
+    RateLimit(String),
+
+    /// Invalid admin endpoint configuration
+    #[error("Admin token configuration error: {0}")]
+    AdminToken(String),
 }
 
 /// Server runtime errors.
@@ -120,6 +132,10 @@ pub enum ProtocolError {
         /// Number of seconds before timeout
         seconds: u64,
     },
+
+    /// Failed to sign a response
+    #[error("Failed to sign response: {0}")]
+    Signing(String),
 }
 
 #[cfg(test)]
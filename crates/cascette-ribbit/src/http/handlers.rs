@@ -2,11 +2,11 @@
 
 use crate::config::CdnConfig;
 use crate::error::DatabaseError;
-use crate::responses::BpsvResponse;
+use crate::responses::{BpsvResponse, CachedResponse};
 use crate::server::AppState;
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode, header},
     response::{IntoResponse, Response},
 };
 use std::sync::Arc;
@@ -14,6 +14,7 @@ use std::sync::Arc;
 /// Handle GET /:product/versions endpoint.
 ///
 /// Returns BPSV-formatted version information for the specified product.
+/// The response carries an `ETag`; a matching `If-None-Match` gets `304`.
 ///
 /// # Errors
 ///
@@ -21,33 +22,31 @@ use std::sync::Arc;
 pub async fn handle_versions(
     Path(product): Path<String>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> Result<Response, AppError> {
     tracing::debug!("Handling versions request for product: {}", product);
 
+    let database = state.database();
+
     // Get latest build for product
-    let build = state
-        .database()
+    let build = database
         .latest_build(&product)
         .ok_or_else(|| AppError::NotFound(format!("Product not found: {product}")))?;
 
-    // Generate BPSV response
-    let seqn = state.current_seqn();
-    let response = BpsvResponse::versions(build, seqn);
+    // Generate BPSV response, once per database snapshot
+    let response = state
+        .http_responses()
+        .get_or_render("versions", &product, &database, || {
+            BpsvResponse::versions(build, state.current_seqn()).to_string()
+        });
 
-    Ok((
-        StatusCode::OK,
-        [(
-            axum::http::header::CONTENT_TYPE,
-            "text/plain; charset=utf-8",
-        )],
-        response.to_string(),
-    )
-        .into_response())
+    Ok(bpsv_response(&response, &headers))
 }
 
 /// Handle GET /:product/cdns endpoint.
 ///
 /// Returns BPSV-formatted CDN configuration for the specified product.
+/// The response carries an `ETag`; a matching `If-None-Match` gets `304`.
 ///
 /// # Errors
 ///
@@ -55,36 +54,34 @@ pub async fn handle_versions(
 pub async fn handle_cdns(
     Path(product): Path<String>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> Result<Response, AppError> {
     tracing::debug!("Handling cdns request for product: {}", product);
 
+    let database = state.database();
+
     // Verify product exists
-    let build = state
-        .database()
+    let build = database
         .latest_build(&product)
         .ok_or_else(|| AppError::NotFound(format!("Product not found: {product}")))?;
 
     // Resolve CDN config for this product (uses product-specific path if available)
     let cdn_config = CdnConfig::resolve_for_build(build, state.cdn_config());
 
-    // Generate BPSV response
-    let seqn = state.current_seqn();
-    let response = BpsvResponse::cdns(&cdn_config, seqn);
+    // Generate BPSV response, once per database snapshot
+    let response = state
+        .http_responses()
+        .get_or_render("cdns", &product, &database, || {
+            BpsvResponse::cdns(&cdn_config, state.current_seqn()).to_string()
+        });
 
-    Ok((
-        StatusCode::OK,
-        [(
-            axum::http::header::CONTENT_TYPE,
-            "text/plain; charset=utf-8",
-        )],
-        response.to_string(),
-    )
-        .into_response())
+    Ok(bpsv_response(&response, &headers))
 }
 
 /// Handle GET /:product/bgdl endpoint.
 ///
 /// Returns BPSV-formatted background download information (same format as versions).
+/// The response carries an `ETag`; a matching `If-None-Match` gets `304`.
 ///
 /// # Errors
 ///
@@ -92,35 +89,135 @@ pub async fn handle_cdns(
 pub async fn handle_bgdl(
     Path(product): Path<String>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> Result<Response, AppError> {
     tracing::debug!("Handling bgdl request for product: {}", product);
 
+    let database = state.database();
+
     // Get latest build for product
-    let build = state
-        .database()
+    let build = database
         .latest_build(&product)
         .ok_or_else(|| AppError::NotFound(format!("Product not found: {product}")))?;
 
     // Generate BPSV response (bgdl uses same format as versions)
-    let seqn = state.current_seqn();
-    let response = BpsvResponse::bgdl(build, seqn);
+    let response = state
+        .http_responses()
+        .get_or_render("bgdl", &product, &database, || {
+            BpsvResponse::bgdl(build, state.current_seqn()).to_string()
+        });
+
+    Ok(bpsv_response(&response, &headers))
+}
+
+/// Answer with a cached BPSV body and its `ETag`.
+///
+/// If the request's `If-None-Match` matches the `ETag`, the answer is
+/// `304 Not Modified` with an empty body instead.
+fn bpsv_response(response: &CachedResponse, headers: &HeaderMap) -> Response {
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| response.matches(value));
+    if not_modified {
+        return (
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, response.etag().to_string())],
+        )
+            .into_response();
+    }
+
+    (
+        StatusCode::OK,
+        [
+            (
+                header::CONTENT_TYPE,
+                "text/plain; charset=utf-8".to_string(),
+            ),
+            (header::ETAG, response.etag().to_string()),
+        ],
+        response.body().to_string(),
+    )
+        .into_response()
+}
+
+/// Handle POST /admin/reload endpoint.
+///
+/// Reloads the build database, see [`AppState::reload_database`]. The
+/// request must carry `Authorization: Bearer <admin token>`. A database
+/// that fails to load is reported and the previous one keeps serving.
+///
+/// # Errors
+///
+/// Returns `AppError` if admin endpoints are disabled, the token is
+/// missing or wrong, or the database cannot be loaded.
+pub async fn handle_admin_reload(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let Some(token) = state.admin_token() else {
+        return Err(AppError::NotFound(
+            "Admin endpoints are disabled".to_string(),
+        ));
+    };
+
+    let authorized = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|provided| constant_time_eq(provided.as_bytes(), token.as_bytes()));
+    if !authorized {
+        tracing::warn!("Rejected unauthorized build database reload");
+        return Err(AppError::Unauthorized);
+    }
+
+    let database = state.reload_database()?;
+    Ok((
+        StatusCode::OK,
+        format!(
+            "Reloaded {} builds for {} products\n",
+            database.total_builds(),
+            database.products().len()
+        ),
+    )
+        .into_response())
+}
+
+/// Handle GET /metrics endpoint.
+///
+/// Returns the request metrics in the Prometheus text exposition format.
+///
+/// # Errors
+///
+/// Returns `AppError` if metrics are disabled.
+pub async fn handle_metrics(State(state): State<Arc<AppState>>) -> Result<Response, AppError> {
+    let Some(metrics) = state.metrics() else {
+        return Err(AppError::NotFound("Metrics are disabled".to_string()));
+    };
 
     Ok((
         StatusCode::OK,
         [(
-            axum::http::header::CONTENT_TYPE,
-            "text/plain; charset=utf-8",
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
         )],
-        response.to_string(),
+        metrics.render(&state),
     )
         .into_response())
 }
 
+/// Compare two byte strings in time independent of where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
 /// Application-level error type for HTTP handlers.
 #[derive(Debug)]
 pub enum AppError {
     /// Resource not found (404)
     NotFound(String),
+    /// Missing or wrong admin token (401)
+    Unauthorized,
     /// Database error (500)
     Database(DatabaseError),
 }
@@ -129,6 +226,14 @@ impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, message) = match self {
             Self::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            Self::Unauthorized => {
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    [(header::WWW_AUTHENTICATE, "Bearer")],
+                    "Unauthorized",
+                )
+                    .into_response();
+            }
             Self::Database(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
         };
 
@@ -161,6 +266,15 @@ mod tests {
             cdn_path: "test/path".to_string(),
             tls_cert: None,
             tls_key: None,
+            rate_limit: 0.0,
+            rate_limit_burst: 1,
+            tcp_idle_timeout_secs: 10,
+            tcp_rate_limit: 0.0,
+            tcp_rate_limit_burst: 1,
+            tcp_rate_limit_exempt: Vec::new(),
+            signing: None,
+            admin_token: None,
+            disable_metrics: false,
         };
 
         Arc::new(AppState::new(&config).unwrap())
@@ -169,28 +283,56 @@ mod tests {
     #[tokio::test]
     async fn test_handle_versions() {
         let state = create_test_state();
-        let result = handle_versions(Path("test_product".to_string()), State(state)).await;
+        let result = handle_versions(
+            Path("test_product".to_string()),
+            State(state),
+            HeaderMap::new(),
+        )
+        .await;
         assert!(result.is_ok());
     }
 
     #[tokio::test]
     async fn test_handle_versions_not_found() {
         let state = create_test_state();
-        let result = handle_versions(Path("nonexistent".to_string()), State(state)).await;
+        let result = handle_versions(
+            Path("nonexistent".to_string()),
+            State(state),
+            HeaderMap::new(),
+        )
+        .await;
         assert!(result.is_err());
     }
 
     #[tokio::test]
     async fn test_handle_cdns() {
         let state = create_test_state();
-        let result = handle_cdns(Path("test_product".to_string()), State(state)).await;
+        let result = handle_cdns(
+            Path("test_product".to_string()),
+            State(state),
+            HeaderMap::new(),
+        )
+        .await;
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"", b"secret"));
+    }
+
     #[tokio::test]
     async fn test_handle_bgdl() {
         let state = create_test_state();
-        let result = handle_bgdl(Path("test_product".to_string()), State(state)).await;
+        let result = handle_bgdl(
+            Path("test_product".to_string()),
+            State(state),
+            HeaderMap::new(),
+        )
+        .await;
         assert!(result.is_ok());
     }
 }
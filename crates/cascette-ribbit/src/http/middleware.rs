@@ -0,0 +1,314 @@
+//! HTTP middleware.
+//!
+//! [`RateLimitLayer`] limits how fast each client IP may send requests using
+//! a token bucket per IP: every request takes one token, tokens refill at a
+//! fixed rate up to the burst capacity, and a client with an empty bucket
+//! gets `429 Too Many Requests` with a `Retry-After` header.
+//!
+//! Clients are identified by the peer address in [`ConnectInfo`], which
+//! [`crate::http::serve`] provides. Requests without it cannot be attributed
+//! and pass through. At most [`MAX_TRACKED_CLIENTS`] buckets are kept; a new
+//! client beyond that replaces the least recently seen one.
+//!
+//! [`record_metrics`] counts product endpoint requests in the state's
+//! [`Metrics`](crate::metrics::Metrics).
+
+use crate::metrics::Protocol;
+use crate::server::AppState;
+use axum::body::HttpBody;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower::{Layer, Service};
+
+/// Number of clients whose buckets are tracked at once
+pub const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Token bucket of one client.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// Position in [`Buckets::by_use`]
+    last_use: u64,
+}
+
+/// Buckets of the tracked clients, with their order of use.
+#[derive(Debug, Default)]
+struct Buckets {
+    by_ip: HashMap<IpAddr, Bucket>,
+    /// Clients by last request, least recent first
+    by_use: BTreeMap<u64, IpAddr>,
+    next_use: u64,
+}
+
+/// Per-IP token-bucket rate limiter.
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_second: f64,
+    max_clients: usize,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    /// Create a limiter allowing bursts of `capacity` requests, refilled at
+    /// `refill_per_second` requests per second.
+    #[must_use]
+    pub fn new(capacity: u32, refill_per_second: f64) -> Self {
+        Self {
+            capacity: f64::from(capacity.max(1)),
+            refill_per_second,
+            max_clients: MAX_TRACKED_CLIENTS,
+            buckets: Mutex::new(Buckets::default()),
+        }
+    }
+
+    /// Track at most `max_clients` clients instead of
+    /// [`MAX_TRACKED_CLIENTS`].
+    #[must_use]
+    pub fn with_max_clients(mut self, max_clients: usize) -> Self {
+        self.max_clients = max_clients.max(1);
+        self
+    }
+
+    /// Take a token for a request from `ip`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the time until a token is available if the bucket is empty.
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        self.check_at(ip, Instant::now())
+    }
+
+    /// Take a token for a request from `ip` at `now`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the time until a token is available if the bucket is empty.
+    pub fn check_at(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        let Buckets {
+            by_ip,
+            by_use,
+            next_use,
+        } = &mut *buckets;
+
+        if by_ip.len() >= self.max_clients
+            && !by_ip.contains_key(&ip)
+            && let Some((_, evicted)) = by_use.pop_first()
+        {
+            by_ip.remove(&evicted);
+        }
+
+        let last_use = *next_use;
+        *next_use += 1;
+        let bucket = by_ip.entry(ip).or_insert(Bucket {
+            tokens: self.capacity,
+            updated: now,
+            last_use,
+        });
+        by_use.remove(&bucket.last_use);
+        by_use.insert(last_use, ip);
+        bucket.tokens = self.refilled(*bucket, now);
+        bucket.updated = now;
+        bucket.last_use = last_use;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let missing = 1.0 - bucket.tokens;
+            drop(buckets);
+            Err(Duration::from_secs_f64(missing / self.refill_per_second))
+        }
+    }
+
+    /// Tokens in `bucket` at `now`.
+    fn refilled(&self, bucket: Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        elapsed
+            .mul_add(self.refill_per_second, bucket.tokens)
+            .min(self.capacity)
+    }
+}
+
+/// Tower layer applying a [`RateLimiter`] to a service.
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitLayer {
+    /// Create a layer sharing `limiter` across all wrapped services.
+    #[must_use]
+    pub const fn new(limiter: Arc<RateLimiter>) -> Self {
+        Self { limiter }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+/// Service rejecting requests from clients over their rate limit.
+#[derive(Debug, Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S> Service<Request> for RateLimit<S>
+where
+    S: Service<Request, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        if let Some(ConnectInfo(peer)) = request.extensions().get::<ConnectInfo<SocketAddr>>()
+            && let Err(retry_after) = self.limiter.check(peer.ip())
+        {
+            tracing::debug!("Rate limited HTTP request from {}", peer.ip());
+            return Box::pin(async move { Ok(too_many_requests(retry_after)) });
+        }
+        Box::pin(self.inner.call(request))
+    }
+}
+
+/// `429 Too Many Requests` with `Retry-After` in whole seconds, rounded up.
+fn too_many_requests(retry_after: Duration) -> Response {
+    let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, "Too Many Requests").into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(seconds.max(1)));
+    response
+}
+
+/// Record a `/{product}/{endpoint}` request in the state's metrics.
+///
+/// Responses with an error status count as failed requests.
+pub async fn record_metrics(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_owned();
+    let response = next.run(request).await;
+
+    if let Some(metrics) = state.metrics() {
+        let mut segments = path.trim_start_matches('/').splitn(2, '/');
+        let product = segments.next();
+        let endpoint = segments.next().unwrap_or_default();
+        let size = (!response.status().is_client_error() && !response.status().is_server_error())
+            .then(|| response.body().size_hint().exact())
+            .map(|exact| usize::try_from(exact.unwrap_or_default()).unwrap_or(usize::MAX));
+        metrics.record(Protocol::Http, endpoint, product, &state.database(), size);
+    }
+    response
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const CLIENT: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 1));
+    const OTHER: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 2));
+
+    #[test]
+    fn test_burst_then_limited() {
+        let limiter = RateLimiter::new(3, 1.0);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check_at(CLIENT, now).is_ok());
+        }
+        let retry_after = limiter.check_at(CLIENT, now).unwrap_err();
+        assert_eq!(retry_after, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_tokens_refill_over_time() {
+        let limiter = RateLimiter::new(2, 4.0);
+        let now = Instant::now();
+
+        assert!(limiter.check_at(CLIENT, now).is_ok());
+        assert!(limiter.check_at(CLIENT, now).is_ok());
+        assert!(limiter.check_at(CLIENT, now).is_err());
+
+        // One token after 250ms at 4 tokens per second
+        let later = now + Duration::from_millis(250);
+        assert!(limiter.check_at(CLIENT, later).is_ok());
+        assert!(limiter.check_at(CLIENT, later).is_err());
+
+        // Refill stops at the burst capacity
+        let much_later = now + Duration::from_secs(60);
+        assert!(limiter.check_at(CLIENT, much_later).is_ok());
+        assert!(limiter.check_at(CLIENT, much_later).is_ok());
+        assert!(limiter.check_at(CLIENT, much_later).is_err());
+    }
+
+    #[test]
+    fn test_clients_have_separate_buckets() {
+        let limiter = RateLimiter::new(1, 1.0);
+        let now = Instant::now();
+
+        assert!(limiter.check_at(CLIENT, now).is_ok());
+        assert!(limiter.check_at(CLIENT, now).is_err());
+        assert!(limiter.check_at(OTHER, now).is_ok());
+    }
+
+    #[test]
+    fn test_least_recently_seen_client_evicted() {
+        let third = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 3));
+        let limiter = RateLimiter::new(1, 1.0).with_max_clients(2);
+        let now = Instant::now();
+
+        assert!(limiter.check_at(CLIENT, now).is_ok());
+        assert!(limiter.check_at(OTHER, now).is_ok());
+        assert!(limiter.check_at(CLIENT, now).is_err());
+
+        // CLIENT was seen last, so OTHER makes room for the new client
+        assert!(limiter.check_at(third, now).is_ok());
+        assert!(limiter.check_at(CLIENT, now).is_err());
+        assert!(limiter.check_at(OTHER, now).is_ok());
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.by_ip.len(), 2);
+        assert_eq!(buckets.by_use.len(), 2);
+        drop(buckets);
+    }
+
+    #[test]
+    fn test_retry_after_rounds_up() {
+        let response = too_many_requests(Duration::from_millis(1500));
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+
+        let response = too_many_requests(Duration::from_millis(10));
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+    }
+}
@@ -3,23 +3,59 @@
 use crate::error::ServerError;
 use crate::server::AppState;
 use axum::Router;
+use middleware::RateLimitLayer;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use tokio::net::TcpListener;
 use tower_http::compression::CompressionLayer;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 
 pub mod handlers;
+pub mod middleware;
 
 /// Create HTTP router with all endpoints.
+///
+/// Rate limiting applies to every route when the state has a limiter; it
+/// needs the peer address, which [`serve`] provides. `POST /admin/reload`
+/// is only routed when the state has an admin token, and `GET /metrics`
+/// when it has metrics.
 pub fn create_router(state: Arc<AppState>) -> Router {
-    Router::new()
+    let router = Router::new()
         .route(
             "/{product}/versions",
             axum::routing::get(handlers::handle_versions),
         )
         .route("/{product}/cdns", axum::routing::get(handlers::handle_cdns))
         .route("/{product}/bgdl", axum::routing::get(handlers::handle_bgdl))
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::record_metrics,
+        ));
+
+    // Metrics are served unless disabled
+    let router = if state.metrics().is_some() {
+        router.route("/metrics", axum::routing::get(handlers::handle_metrics))
+    } else {
+        router
+    };
+
+    // Admin endpoints only exist when a token is configured
+    let router = if state.admin_token().is_some() {
+        router.route(
+            "/admin/reload",
+            axum::routing::post(handlers::handle_admin_reload),
+        )
+    } else {
+        router
+    };
+
+    let router = match state.rate_limiter() {
+        Some(limiter) => router.layer(RateLimitLayer::new(limiter.clone())),
+        None => router,
+    };
+
+    router
         .layer(TraceLayer::new_for_http())
         .layer(CompressionLayer::new())
         .layer(CorsLayer::permissive())
@@ -32,21 +68,44 @@ pub fn create_router(state: Arc<AppState>) -> Router {
 ///
 /// Returns `ServerError` if the server fails to bind or encounters a runtime error.
 pub async fn start_server(bind_addr: SocketAddr, state: Arc<AppState>) -> Result<(), ServerError> {
-    let app = create_router(state);
-
-    let listener = tokio::net::TcpListener::bind(bind_addr)
-        .await
-        .map_err(|source| ServerError::HttpBindFailed {
-            addr: bind_addr,
-            source,
-        })?;
+    let listener =
+        TcpListener::bind(bind_addr)
+            .await
+            .map_err(|source| ServerError::HttpBindFailed {
+                addr: bind_addr,
+                source,
+            })?;
 
     tracing::info!("HTTP server listening on {}", bind_addr);
 
-    axum::serve(listener, app)
-        .await
-        .map_err(|e| ServerError::Shutdown(format!("HTTP server error: {e}")))?;
+    serve(listener, state).await
+}
+
+/// Serve HTTP requests on a bound listener.
+///
+/// Runs until the shutdown signal in `state` is set. The listener is then
+/// closed, idle connections are dropped, and the call returns once
+/// in-flight requests have been answered.
+///
+/// # Errors
+///
+/// Returns `ServerError` if the server encounters a runtime error.
+pub async fn serve(listener: TcpListener, state: Arc<AppState>) -> Result<(), ServerError> {
+    let mut shutdown = state.shutdown_sender().subscribe();
+    let app = create_router(state);
+
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        // A dropped sender also ends the server
+        let _ = shutdown.wait_for(|&stop| stop).await;
+    })
+    .await
+    .map_err(|e| ServerError::Shutdown(format!("HTTP server error: {e}")))?;
 
+    tracing::info!("HTTP server stopped");
     Ok(())
 }
 
@@ -72,6 +131,15 @@ mod tests {
             cdn_path: "test/path".to_string(),
             tls_cert: None,
             tls_key: None,
+            rate_limit: 0.0,
+            rate_limit_burst: 1,
+            tcp_idle_timeout_secs: 10,
+            tcp_rate_limit: 0.0,
+            tcp_rate_limit_burst: 1,
+            tcp_rate_limit_exempt: Vec::new(),
+            signing: None,
+            admin_token: None,
+            disable_metrics: false,
         };
 
         let state = Arc::new(AppState::new(&config).unwrap());
@@ -14,7 +14,8 @@
 //! - `database`: JSON database loading and indexing
 //! - `http`: HTTP server and handlers
 //! - `tcp`: TCP server and handlers
-//! - `responses`: BPSV/MIME generation and checksums
+//! - `metrics`: Request counters served at `/metrics`
+//! - `responses`: BPSV/MIME generation, checksums and signatures
 //!
 //! # Example
 //!
@@ -53,13 +54,17 @@ pub mod config;
 pub mod database;
 pub mod error;
 pub mod http;
+pub mod metrics;
 pub mod responses;
+pub mod self_test;
 pub mod server;
 pub mod tcp;
 
 // Re-exports for public API
-pub use config::{CdnConfig, ServerConfig};
+pub use config::{CdnConfig, ServerConfig, SigningConfig};
 pub use database::{BuildDatabase, BuildRecord};
 pub use error::{ConfigError, DatabaseError, ProtocolError, ServerError};
-pub use responses::BpsvResponse;
+pub use metrics::Metrics;
+pub use responses::{BpsvResponse, ResponseSigner};
+pub use self_test::SelfTestReport;
 pub use server::{AppState, Server};
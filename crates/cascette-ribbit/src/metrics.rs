@@ -0,0 +1,435 @@
+//! Request metrics in the Prometheus text exposition format.
+//!
+//! [`Metrics`] counts the requests of every protocol surface, labelled by
+//! protocol (`tcp_v1`, `tcp_v2`, `http`), endpoint and product, along with
+//! failed requests and a histogram of response sizes. The HTTP listener
+//! serves them at `GET /metrics` unless disabled with `--disable-metrics`.
+//!
+//! Labels only take a fixed set of values, so clients cannot grow the
+//! number of series: endpoints other than the known ones are counted as
+//! `other`, and products not in the build database as `unknown`.
+//!
+//! ```text
+//! # HELP ribbit_requests_total Requests received.
+//! # TYPE ribbit_requests_total counter
+//! ribbit_requests_total{protocol="tcp_v2",endpoint="versions",product="wow"} 3
+//! ```
+
+use crate::database::BuildDatabase;
+use crate::server::AppState;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+/// Upper bounds of the response size histogram buckets, in bytes
+const SIZE_BUCKETS: [u64; 6] = [256, 1024, 4096, 16_384, 65_536, 262_144];
+
+/// Endpoints counted under their own name
+const ENDPOINTS: [&str; 4] = ["versions", "cdns", "bgdl", "summary"];
+
+/// Protocol a request was received over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Protocol {
+    /// TCP Ribbit v1 (MIME-wrapped)
+    TcpV1,
+    /// TCP Ribbit v2 (raw BPSV)
+    TcpV2,
+    /// HTTP/HTTPS TACT v2
+    Http,
+}
+
+impl Protocol {
+    /// Value of the `protocol` label.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::TcpV1 => "tcp_v1",
+            Self::TcpV2 => "tcp_v2",
+            Self::Http => "http",
+        }
+    }
+}
+
+/// Labels of one request series.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Series {
+    protocol: Protocol,
+    endpoint: &'static str,
+    product: String,
+}
+
+/// Counters of one request series.
+#[derive(Debug, Clone, Default)]
+struct Counters {
+    requests: u64,
+    errors: u64,
+    /// Responses per size bucket, not cumulative; the last is `+Inf`
+    size_buckets: [u64; SIZE_BUCKETS.len() + 1],
+    size_sum: u64,
+    size_count: u64,
+}
+
+/// Request counters for all protocols.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    series: Mutex<BTreeMap<Series, Counters>>,
+    invalid_commands: AtomicU64,
+}
+
+impl Metrics {
+    /// Create empty metrics.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a request for `endpoint` of `product`.
+    ///
+    /// `response_size` is the size of the response body, or `None` if the
+    /// request failed. `product` is counted as `unknown` unless `database`
+    /// has it.
+    pub fn record(
+        &self,
+        protocol: Protocol,
+        endpoint: &str,
+        product: Option<&str>,
+        database: &BuildDatabase,
+        response_size: Option<usize>,
+    ) {
+        let series = Series {
+            protocol,
+            endpoint: ENDPOINTS
+                .into_iter()
+                .find(|known| *known == endpoint)
+                .unwrap_or("other"),
+            product: product
+                .filter(|product| database.latest_build(product).is_some())
+                .map_or_else(|| "unknown".to_string(), str::to_string),
+        };
+
+        let mut all = self.series.lock().unwrap_or_else(PoisonError::into_inner);
+        let counters = all.entry(series).or_default();
+        counters.requests += 1;
+        match response_size {
+            Some(size) => {
+                let size = size as u64;
+                let bucket = SIZE_BUCKETS
+                    .iter()
+                    .position(|&bound| size <= bound)
+                    .unwrap_or(SIZE_BUCKETS.len());
+                counters.size_buckets[bucket] += 1;
+                counters.size_sum += size;
+                counters.size_count += 1;
+            }
+            None => counters.errors += 1,
+        }
+        drop(all);
+    }
+
+    /// Record a TCP command and the size of its response, or `None` if it
+    /// failed.
+    ///
+    /// Commands of an unknown protocol version are only counted as
+    /// invalid.
+    pub fn record_command(
+        &self,
+        command: &str,
+        database: &BuildDatabase,
+        response_size: Option<usize>,
+    ) {
+        let parts: Vec<&str> = command.split('/').collect();
+        let protocol = match parts[0] {
+            "v1" => Protocol::TcpV1,
+            "v2" => Protocol::TcpV2,
+            _ => {
+                self.invalid_commands.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        };
+        let (endpoint, product) = match parts.as_slice() {
+            [_, "summary"] => ("summary", None),
+            [_, "products", product, endpoint] => (*endpoint, Some(*product)),
+            _ => ("other", None),
+        };
+        self.record(protocol, endpoint, product, database, response_size);
+    }
+
+    /// Number of requests recorded for a series, for tests and monitoring
+    /// code that runs in-process.
+    #[must_use]
+    pub fn requests(&self, protocol: Protocol, endpoint: &str, product: &str) -> u64 {
+        self.counters(protocol, endpoint, product)
+            .map_or(0, |counters| counters.requests)
+    }
+
+    /// Number of failed requests recorded for a series.
+    #[must_use]
+    pub fn errors(&self, protocol: Protocol, endpoint: &str, product: &str) -> u64 {
+        self.counters(protocol, endpoint, product)
+            .map_or(0, |counters| counters.errors)
+    }
+
+    fn counters(&self, protocol: Protocol, endpoint: &str, product: &str) -> Option<Counters> {
+        let all = self.series.lock().unwrap_or_else(PoisonError::into_inner);
+        all.iter()
+            .find(|(series, _)| {
+                series.protocol == protocol
+                    && series.endpoint == endpoint
+                    && series.product == product
+            })
+            .map(|(_, counters)| counters.clone())
+    }
+
+    /// Render all metrics, plus server gauges from `state`, in the
+    /// Prometheus text exposition format.
+    #[must_use]
+    pub fn render(&self, state: &AppState) -> String {
+        let all = self
+            .series
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "ribbit_requests_total",
+            "counter",
+            "Requests received.",
+        );
+        for (series, counters) in &all {
+            sample(
+                &mut out,
+                "ribbit_requests_total",
+                series,
+                None,
+                counters.requests,
+            );
+        }
+
+        header(
+            &mut out,
+            "ribbit_request_errors_total",
+            "counter",
+            "Requests that failed.",
+        );
+        for (series, counters) in &all {
+            sample(
+                &mut out,
+                "ribbit_request_errors_total",
+                series,
+                None,
+                counters.errors,
+            );
+        }
+
+        header(
+            &mut out,
+            "ribbit_response_bytes",
+            "histogram",
+            "Size of response bodies in bytes.",
+        );
+        for (series, counters) in &all {
+            let mut cumulative = 0;
+            for (i, count) in counters.size_buckets.iter().enumerate() {
+                cumulative += count;
+                let bound = SIZE_BUCKETS
+                    .get(i)
+                    .map_or_else(|| "+Inf".to_string(), u64::to_string);
+                sample(
+                    &mut out,
+                    "ribbit_response_bytes_bucket",
+                    series,
+                    Some(&bound),
+                    cumulative,
+                );
+            }
+            sample(
+                &mut out,
+                "ribbit_response_bytes_sum",
+                series,
+                None,
+                counters.size_sum,
+            );
+            sample(
+                &mut out,
+                "ribbit_response_bytes_count",
+                series,
+                None,
+                counters.size_count,
+            );
+        }
+
+        header(
+            &mut out,
+            "ribbit_tcp_invalid_commands_total",
+            "counter",
+            "TCP commands of an unknown protocol version.",
+        );
+        let _ = writeln!(
+            out,
+            "ribbit_tcp_invalid_commands_total {}",
+            self.invalid_commands.load(Ordering::Relaxed)
+        );
+
+        if let Some(limiter) = state.tcp_rate_limiter() {
+            header(
+                &mut out,
+                "ribbit_tcp_rate_limited_connections_total",
+                "counter",
+                "TCP connections rejected by the rate limit.",
+            );
+            let _ = writeln!(
+                out,
+                "ribbit_tcp_rate_limited_connections_total {}",
+                limiter.dropped_connections()
+            );
+        }
+
+        header(
+            &mut out,
+            "ribbit_builds",
+            "gauge",
+            "Builds in the build database.",
+        );
+        let _ = writeln!(out, "ribbit_builds {}", state.database().total_builds());
+
+        header(
+            &mut out,
+            "ribbit_uptime_seconds",
+            "gauge",
+            "Seconds since the server started.",
+        );
+        let _ = writeln!(out, "ribbit_uptime_seconds {}", state.uptime_seconds());
+
+        out
+    }
+}
+
+/// Write the `HELP` and `TYPE` lines of a metric.
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    // write! to String is infallible but returns Result
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Write one sample of a request series, with an optional `le` label.
+fn sample(out: &mut String, name: &str, series: &Series, le: Option<&str>, value: u64) {
+    let _ = write!(
+        out,
+        "{name}{{protocol=\"{}\",endpoint=\"{}\",product=\"{}\"",
+        series.protocol.label(),
+        series.endpoint,
+        escape_label(&series.product)
+    );
+    if let Some(le) = le {
+        let _ = write!(out, ",le=\"{le}\"");
+    }
+    let _ = writeln!(out, "}} {value}");
+}
+
+/// Escape a label value for the text exposition format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn create_test_state() -> AppState {
+        let mut file = NamedTempFile::new().expect("Operation should succeed");
+        file.write_all(b"[{\"id\":1,\"product\":\"test_product\",\"version\":\"1.0.0\",\"build\":\"1\",\"build_config\":\"0123456789abcdef0123456789abcdef\",\"cdn_config\":\"fedcba9876543210fedcba9876543210\",\"product_config\":null,\"build_time\":\"2024-01-01T00:00:00+00:00\",\"encoding_ekey\":\"aaaabbbbccccddddeeeeffffaaaaffff\",\"root_ekey\":\"bbbbccccddddeeeeffffaaaabbbbcccc\",\"install_ekey\":\"ccccddddeeeeffffaaaabbbbccccdddd\",\"download_ekey\":\"ddddeeeeffffaaaabbbbccccddddeeee\"}]").expect("Operation should succeed");
+
+        let config = ServerConfig {
+            http_bind: "0.0.0.0:8080".parse().expect("Operation should succeed"),
+            tcp_bind: "0.0.0.0:1119".parse().expect("Operation should succeed"),
+            builds: file.path().to_path_buf(),
+            cdn_hosts: "cdn.test.com".to_string(),
+            cdn_path: "test/path".to_string(),
+            tls_cert: None,
+            tls_key: None,
+            rate_limit: 0.0,
+            rate_limit_burst: 1,
+            tcp_idle_timeout_secs: 10,
+            tcp_rate_limit: 0.0,
+            tcp_rate_limit_burst: 1,
+            tcp_rate_limit_exempt: Vec::new(),
+            signing: None,
+            admin_token: None,
+            disable_metrics: false,
+        };
+
+        AppState::new(&config).expect("Operation should succeed")
+    }
+
+    #[test]
+    fn test_record_command_labels() {
+        let state = create_test_state();
+        let database = state.database();
+        let metrics = Metrics::new();
+
+        metrics.record_command("v1/products/test_product/versions", &database, Some(100));
+        metrics.record_command("v2/products/test_product/cdns", &database, Some(100));
+        metrics.record_command("v2/products/test_product/cdns", &database, Some(100));
+        metrics.record_command("v1/summary", &database, Some(100));
+        metrics.record_command("v2/products/nonexistent/versions", &database, None);
+        metrics.record_command("v2/products/test_product/builds", &database, None);
+        metrics.record_command("v3/products/test_product/versions", &database, None);
+
+        assert_eq!(
+            metrics.requests(Protocol::TcpV1, "versions", "test_product"),
+            1
+        );
+        assert_eq!(metrics.requests(Protocol::TcpV2, "cdns", "test_product"), 2);
+        assert_eq!(metrics.requests(Protocol::TcpV1, "summary", "unknown"), 1);
+        assert_eq!(metrics.errors(Protocol::TcpV2, "versions", "unknown"), 1);
+        assert_eq!(metrics.errors(Protocol::TcpV2, "other", "test_product"), 1);
+        assert_eq!(metrics.invalid_commands.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_render_histogram() {
+        let state = create_test_state();
+        let database = state.database();
+        let metrics = Metrics::new();
+        for size in [10, 1000, 1_000_000] {
+            metrics.record(
+                Protocol::Http,
+                "versions",
+                Some("test_product"),
+                &database,
+                Some(size),
+            );
+        }
+
+        let text = metrics.render(&state);
+        let labels = r#"protocol="http",endpoint="versions",product="test_product""#;
+        assert!(text.contains(&format!("ribbit_requests_total{{{labels}}} 3\n")));
+        assert!(text.contains(&format!(
+            "ribbit_response_bytes_bucket{{{labels},le=\"256\"}} 1\n"
+        )));
+        assert!(text.contains(&format!(
+            "ribbit_response_bytes_bucket{{{labels},le=\"1024\"}} 2\n"
+        )));
+        assert!(text.contains(&format!(
+            "ribbit_response_bytes_bucket{{{labels},le=\"+Inf\"}} 3\n"
+        )));
+        assert!(text.contains(&format!("ribbit_response_bytes_sum{{{labels}}} 1001010\n")));
+        assert!(text.contains("ribbit_builds 1\n"));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("wow"), "wow");
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -5,6 +5,7 @@
 
 use crate::config::CdnConfig;
 use crate::database::BuildRecord;
+use sha2::{Digest, Sha256};
 use std::fmt;
 
 /// Supported BPSV response types.
@@ -116,6 +117,70 @@ impl BpsvResponse {
         }
     }
 
+    /// Derive a sequence number from build records.
+    ///
+    /// Hashes the records' serialized rows in sorted order, so the value is
+    /// the same for the same content regardless of record order, and changes
+    /// when any field changes. A server that reloads its database gets the
+    /// same seqn for unchanged content across restarts.
+    #[must_use]
+    pub fn compute_sequence_number(records: &[BuildRecord]) -> u32 {
+        let mut rows: Vec<String> = records
+            .iter()
+            .map(|record| {
+                [
+                    record.id.to_string().as_str(),
+                    &record.product,
+                    &record.version,
+                    &record.build,
+                    &record.build_config,
+                    &record.cdn_config,
+                    record.keyring.as_deref().unwrap_or(""),
+                    record.product_config.as_deref().unwrap_or(""),
+                    &record.build_time,
+                    &record.encoding_ekey,
+                    &record.root_ekey,
+                    &record.install_ekey,
+                    &record.download_ekey,
+                    record.cdn_path.as_deref().unwrap_or(""),
+                ]
+                .join("|")
+            })
+            .collect();
+        rows.sort_unstable();
+
+        let mut hasher = Sha256::new();
+        for row in &rows {
+            hasher.update(row.as_bytes());
+            hasher.update(b"\n");
+        }
+        let digest = hasher.finalize();
+        u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]])
+    }
+
+    /// Replace the sequence number with one derived from `records`.
+    ///
+    /// See [`compute_sequence_number`](Self::compute_sequence_number).
+    #[must_use]
+    pub fn with_auto_seqn(self, records: &[BuildRecord]) -> Self {
+        self.with_seqn(u64::from(Self::compute_sequence_number(records)))
+    }
+
+    /// Replace the sequence number in the footer, and in the rows of a
+    /// summary response.
+    #[must_use]
+    pub fn with_seqn(mut self, seqn: u64) -> Self {
+        let summary = self.response_type == BpsvResponseType::Summary;
+        for line in self.lines.iter_mut().skip(1) {
+            if line.starts_with("## seqn") {
+                *line = format!("## seqn = {seqn}");
+            } else if summary && let Some((product, _)) = line.rsplit_once('|') {
+                *line = format!("{product}|{seqn}");
+            }
+        }
+        self
+    }
+
     /// Get the response type.
     #[must_use]
     pub const fn response_type(&self) -> BpsvResponseType {
@@ -222,4 +287,58 @@ mod tests {
         let text = response.to_string();
         assert!(text.contains("abcdef1234567890abcdef1234567890"));
     }
+
+    #[test]
+    fn test_compute_sequence_number_is_stable() {
+        let build = create_test_build();
+        let mut other = create_test_build();
+        other.id = 2;
+        other.build = "32700".to_string();
+
+        let seqn = BpsvResponse::compute_sequence_number(&[build.clone(), other.clone()]);
+        assert_eq!(
+            seqn,
+            BpsvResponse::compute_sequence_number(&[build.clone(), other.clone()])
+        );
+        // Record order does not matter
+        assert_eq!(seqn, BpsvResponse::compute_sequence_number(&[other, build]));
+    }
+
+    #[test]
+    fn test_compute_sequence_number_changes_with_content() {
+        let build = create_test_build();
+        let seqn = BpsvResponse::compute_sequence_number(std::slice::from_ref(&build));
+
+        let mut changed = build.clone();
+        changed.keyring = Some("00112233445566778899aabbccddeeff".to_string());
+        assert_ne!(seqn, BpsvResponse::compute_sequence_number(&[changed]));
+
+        let mut changed = build.clone();
+        changed.cdn_config = "cf4672a701f0795b21ad63bf6b98ae0a".to_string();
+        assert_ne!(seqn, BpsvResponse::compute_sequence_number(&[changed]));
+
+        assert_ne!(
+            seqn,
+            BpsvResponse::compute_sequence_number(&[build.clone(), build])
+        );
+        assert_ne!(seqn, BpsvResponse::compute_sequence_number(&[]));
+    }
+
+    #[test]
+    fn test_with_auto_seqn() {
+        let build = create_test_build();
+        let seqn = BpsvResponse::compute_sequence_number(std::slice::from_ref(&build));
+
+        let text = BpsvResponse::versions(&build, 1)
+            .with_auto_seqn(std::slice::from_ref(&build))
+            .to_string();
+        assert!(text.ends_with(&format!("## seqn = {seqn}")));
+        assert_eq!(text.lines().count(), 9);
+
+        let text = BpsvResponse::summary(&["wow", "wow_classic"], 1)
+            .with_auto_seqn(std::slice::from_ref(&build))
+            .to_string();
+        assert!(text.contains(&format!("wow|{seqn}\nwow_classic|{seqn}\n")));
+        assert!(text.starts_with("Product!STRING:0|Seqn!DEC:4\n"));
+    }
 }
@@ -0,0 +1,204 @@
+//! Cache of rendered HTTP responses and their ETags.
+//!
+//! A BPSV response only changes when the build database does, so the HTTP
+//! handlers render each one once per database snapshot and answer later
+//! requests from the cache. Each cached body carries an `ETag` (the quoted
+//! hex SHA-256 of the body), which lets clients revalidate with
+//! `If-None-Match` and get `304 Not Modified` instead of the body.
+//!
+//! Entries are tied to the snapshot they were rendered from: a reloaded
+//! database misses every entry, so its responses get a new sequence number
+//! and a new `ETag`.
+
+use crate::database::BuildDatabase;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A rendered response body and its `ETag`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedResponse {
+    body: String,
+    etag: String,
+}
+
+impl CachedResponse {
+    /// Wrap a rendered body, computing its `ETag`.
+    #[must_use]
+    pub fn new(body: String) -> Self {
+        let etag = format!("\"{:x}\"", Sha256::digest(body.as_bytes()));
+        Self { body, etag }
+    }
+
+    /// Get the response body.
+    #[must_use]
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// Get the `ETag` header value, including the quotes.
+    #[must_use]
+    pub fn etag(&self) -> &str {
+        &self.etag
+    }
+
+    /// Check whether an `If-None-Match` header value matches the `ETag`.
+    ///
+    /// The header may list several tags, weak ones (`W/"..."`) included,
+    /// or be `*`.
+    #[must_use]
+    pub fn matches(&self, if_none_match: &str) -> bool {
+        if_none_match
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == self.etag)
+    }
+}
+
+/// Rendered responses by endpoint and product.
+#[derive(Debug, Default)]
+pub struct ResponseCache {
+    entries: Mutex<HashMap<(&'static str, String), Entry>>,
+}
+
+/// A cached response and the database snapshot it was rendered from.
+#[derive(Debug)]
+struct Entry {
+    database: Arc<BuildDatabase>,
+    response: Arc<CachedResponse>,
+}
+
+impl ResponseCache {
+    /// Create an empty cache.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the response for `endpoint` and `product` rendered from
+    /// `database`, calling `render` if it is not cached yet.
+    pub fn get_or_render(
+        &self,
+        endpoint: &'static str,
+        product: &str,
+        database: &Arc<BuildDatabase>,
+        render: impl FnOnce() -> String,
+    ) -> Arc<CachedResponse> {
+        let key = (endpoint, product.to_string());
+        {
+            let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(entry) = entries.get(&key)
+                && Arc::ptr_eq(&entry.database, database)
+            {
+                return entry.response.clone();
+            }
+        }
+
+        // Rendering happens outside the lock; a concurrent request for the
+        // same key renders the same body
+        let response = Arc::new(CachedResponse::new(render()));
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(
+                key,
+                Entry {
+                    database: database.clone(),
+                    response: response.clone(),
+                },
+            );
+        response
+    }
+
+    /// Drop all cached responses.
+    ///
+    /// Called when the database is reloaded, so the previous snapshot is
+    /// not kept alive by the cache.
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// Number of cached responses.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Check whether no responses are cached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn database() -> Arc<BuildDatabase> {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"[{\"id\":1,\"product\":\"wow\",\"version\":\"1.0.0\",\"build\":\"1\",\"build_config\":\"0123456789abcdef0123456789abcdef\",\"cdn_config\":\"fedcba9876543210fedcba9876543210\",\"product_config\":null,\"build_time\":\"2024-01-01T00:00:00+00:00\",\"encoding_ekey\":\"aaaabbbbccccddddeeeeffffaaaaffff\",\"root_ekey\":\"bbbbccccddddeeeeffffaaaabbbbcccc\",\"install_ekey\":\"ccccddddeeeeffffaaaabbbbccccdddd\",\"download_ekey\":\"ddddeeeeffffaaaabbbbccccddddeeee\"}]").unwrap();
+        Arc::new(BuildDatabase::from_file(file.path()).unwrap())
+    }
+
+    #[test]
+    fn test_etag_is_quoted_sha256() {
+        let response = CachedResponse::new("abc".to_string());
+        assert_eq!(
+            response.etag(),
+            "\"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad\""
+        );
+        assert_eq!(response.body(), "abc");
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let response = CachedResponse::new("abc".to_string());
+        let etag = response.etag().to_string();
+        assert!(response.matches(&etag));
+        assert!(response.matches(&format!("W/{etag}")));
+        assert!(response.matches(&format!("\"other\", {etag}")));
+        assert!(response.matches("*"));
+        assert!(!response.matches("\"other\""));
+        assert!(!response.matches(etag.trim_matches('"')));
+    }
+
+    #[test]
+    fn test_cache_is_tied_to_database_snapshot() {
+        let cache = ResponseCache::new();
+        let first = database();
+        let mut renders = 0;
+
+        let a = cache.get_or_render("versions", "wow", &first, || {
+            renders += 1;
+            "one".to_string()
+        });
+        let b = cache.get_or_render("versions", "wow", &first, || {
+            renders += 1;
+            "two".to_string()
+        });
+        assert_eq!(renders, 1);
+        assert!(Arc::ptr_eq(&a, &b));
+
+        // Other endpoints and products are cached separately
+        cache.get_or_render("cdns", "wow", &first, || "cdns".to_string());
+        cache.get_or_render("versions", "wowt", &first, || "wowt".to_string());
+        assert_eq!(cache.len(), 3);
+
+        let reloaded = database();
+        let c = cache.get_or_render("versions", "wow", &reloaded, || "two".to_string());
+        assert_eq!(c.body(), "two");
+        assert_ne!(a.etag(), c.etag());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
@@ -1,7 +1,8 @@
 //! Response generation for Ribbit protocol.
 //!
 //! This module handles generation of responses in Blizzard's BPSV (Blizzard Pipe-Separated Values)
-//! format, which is used across all Ribbit protocol variants.
+//! format, which is used across all Ribbit protocol variants, optional CMS
+//! signatures for TCP v1 responses, and the cache of rendered HTTP responses.
 //!
 //! # BPSV Format
 //!
@@ -31,5 +32,9 @@
 //! ```
 
 pub mod bpsv;
+pub mod cache;
+pub mod signing;
 
 pub use bpsv::BpsvResponse;
+pub use cache::{CachedResponse, ResponseCache};
+pub use signing::ResponseSigner;
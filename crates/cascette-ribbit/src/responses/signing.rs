@@ -0,0 +1,186 @@
+//! CMS signatures for TCP Ribbit v1 responses.
+//!
+//! Blizzard's v1 responses carry a detached CMS `SignedData` structure over the
+//! BPSV payload. [`ResponseSigner`] produces the same structure from a
+//! configured certificate and RSA private key:
+//!
+//! - SHA-256 digest with `contentType` and `messageDigest` signed attributes
+//! - RSA PKCS#1 v1.5 signature over the DER-encoded signed attributes
+//! - Signer identified by issuer and serial number, certificate embedded so
+//!   clients can verify without fetching it
+
+use crate::config::SigningConfig;
+use crate::error::{ConfigError, ProtocolError};
+use cms::builder::{SignedDataBuilder, SignerInfoBuilder};
+use cms::cert::{CertificateChoices, IssuerAndSerialNumber};
+use cms::signed_data::{EncapsulatedContentInfo, SignerIdentifier};
+use der::oid::db::rfc5911::ID_DATA;
+use der::oid::db::rfc5912::ID_SHA_256;
+use der::{DecodePem, Encode};
+use rsa::RsaPrivateKey;
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs1v15::SigningKey;
+use rsa::pkcs8::DecodePrivateKey;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use x509_cert::Certificate;
+use x509_cert::spki::AlgorithmIdentifierOwned;
+
+/// Signs v1 response payloads with a certificate and RSA private key.
+#[derive(Clone)]
+pub struct ResponseSigner {
+    certificate: Certificate,
+    signing_key: SigningKey<Sha256>,
+}
+
+impl ResponseSigner {
+    /// Load the signing certificate and key from the configured files.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::Signing` if either file cannot be read or parsed.
+    pub fn from_config(config: &SigningConfig) -> Result<Self, ConfigError> {
+        let read = |path: &std::path::Path| {
+            std::fs::read_to_string(path).map_err(|e| {
+                ConfigError::Signing(format!("failed to read {}: {e}", path.display()))
+            })
+        };
+
+        Self::from_pem(&read(&config.cert_path)?, &read(&config.key_path)?)
+    }
+
+    /// Create a signer from a PEM certificate and PEM private key.
+    ///
+    /// The key may be PKCS#8 (`PRIVATE KEY`) or PKCS#1 (`RSA PRIVATE KEY`).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::Signing` if the certificate or key is invalid.
+    pub fn from_pem(cert_pem: &str, key_pem: &str) -> Result<Self, ConfigError> {
+        let certificate = Certificate::from_pem(cert_pem)
+            .map_err(|e| ConfigError::Signing(format!("invalid signing certificate: {e}")))?;
+
+        let private_key = RsaPrivateKey::from_pkcs8_pem(key_pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(key_pem))
+            .map_err(|e| ConfigError::Signing(format!("invalid signing key: {e}")))?;
+
+        Ok(Self {
+            certificate,
+            signing_key: SigningKey::new(private_key),
+        })
+    }
+
+    /// The certificate embedded in signatures.
+    #[must_use]
+    pub const fn certificate(&self) -> &Certificate {
+        &self.certificate
+    }
+
+    /// Create a detached CMS signature over `content`.
+    ///
+    /// Returns the DER-encoded `ContentInfo` holding the `SignedData`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::Signing` if the signature cannot be built.
+    pub fn sign(&self, content: &[u8]) -> Result<Vec<u8>, ProtocolError> {
+        fn failed(e: impl fmt::Display) -> ProtocolError {
+            ProtocolError::Signing(e.to_string())
+        }
+
+        let digest = Sha256::digest(content);
+        let digest_algorithm = AlgorithmIdentifierOwned {
+            oid: ID_SHA_256,
+            parameters: None,
+        };
+
+        // Detached signature: the content travels in the data part
+        let content_info = EncapsulatedContentInfo {
+            econtent_type: ID_DATA,
+            econtent: None,
+        };
+
+        let signer_id = SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+            issuer: self.certificate.tbs_certificate.issuer.clone(),
+            serial_number: self.certificate.tbs_certificate.serial_number.clone(),
+        });
+
+        let signer_info = SignerInfoBuilder::new(
+            &self.signing_key,
+            signer_id,
+            digest_algorithm.clone(),
+            &content_info,
+            Some(digest.as_slice()),
+        )
+        .map_err(failed)?;
+
+        SignedDataBuilder::new(&content_info)
+            .add_digest_algorithm(digest_algorithm)
+            .map_err(failed)?
+            .add_certificate(CertificateChoices::Certificate(self.certificate.clone()))
+            .map_err(failed)?
+            .add_signer_info::<_, rsa::pkcs1v15::Signature>(signer_info)
+            .map_err(failed)?
+            .build()
+            .map_err(failed)?
+            .to_der()
+            .map_err(failed)
+    }
+}
+
+impl fmt::Debug for ResponseSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print key material
+        f.debug_struct("ResponseSigner")
+            .field(
+                "subject",
+                &self.certificate.tbs_certificate.subject.to_string(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cms::content_info::ContentInfo;
+    use cms::signed_data::SignedData;
+    use der::Decode;
+
+    const CERT_PEM: &str = include_str!("../../tests/fixtures/signing_cert.pem");
+    const KEY_PEM: &str = include_str!("../../tests/fixtures/signing_key.pem");
+
+    #[test]
+    fn test_sign_produces_detached_signed_data() {
+        let signer = ResponseSigner::from_pem(CERT_PEM, KEY_PEM).unwrap();
+        let der = signer.sign(b"Region!STRING:0\nus\n").unwrap();
+
+        let content_info = ContentInfo::from_der(&der).unwrap();
+        assert_eq!(
+            content_info.content_type,
+            der::oid::db::rfc5911::ID_SIGNED_DATA
+        );
+
+        let signed_data = content_info.content.decode_as::<SignedData>().unwrap();
+        assert!(signed_data.encap_content_info.econtent.is_none());
+        assert_eq!(signed_data.signer_infos.0.len(), 1);
+        assert_eq!(signed_data.certificates.unwrap().0.len(), 1);
+
+        let signer_info = signed_data.signer_infos.0.get(0).unwrap();
+        assert!(signer_info.signed_attrs.is_some());
+    }
+
+    #[test]
+    fn test_invalid_key_is_rejected() {
+        let result = ResponseSigner::from_pem(CERT_PEM, "not a key");
+        assert!(matches!(result, Err(ConfigError::Signing(_))));
+    }
+
+    #[test]
+    fn test_debug_hides_key() {
+        let signer = ResponseSigner::from_pem(CERT_PEM, KEY_PEM).unwrap();
+        let debug = format!("{signer:?}");
+        assert!(debug.contains("cascette-ribbit test signer"));
+        assert!(!debug.contains("signing_key"));
+    }
+}
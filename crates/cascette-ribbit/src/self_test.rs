@@ -0,0 +1,441 @@
+//! Self-test of a running server's protocol surfaces.
+//!
+//! A misconfigured deployment can serve one protocol correctly while another
+//! fails, e.g. TCP v1 answers but HTTP returns 404s. The self-test connects
+//! to the server like a client would and checks each surface:
+//!
+//! - TCP v1: `v1/summary`, versions and cdns; MIME structure, checksum and
+//!   BPSV payload
+//! - TCP v2: versions and cdns as raw BPSV
+//! - HTTP: versions and cdns; status, content type and BPSV body
+//!
+//! The parsed versions and cdns rows must then agree across all surfaces.
+
+use crate::error::ProtocolError;
+use cascette_formats::bpsv::{self, BpsvDocument};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{Duration, timeout};
+
+/// Time allowed for each request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Product endpoints compared across surfaces.
+const ENDPOINTS: [&str; 2] = ["versions", "cdns"];
+
+/// A protocol surface of the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Surface {
+    /// TCP Ribbit v1 (MIME-wrapped)
+    TcpV1,
+    /// TCP Ribbit v2 (raw BPSV)
+    TcpV2,
+    /// HTTP
+    Http,
+}
+
+impl fmt::Display for Surface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::TcpV1 => "TCP v1",
+            Self::TcpV2 => "TCP v2",
+            Self::Http => "HTTP",
+        })
+    }
+}
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    /// What was checked, e.g. `versions`
+    pub name: String,
+    /// Failure reason, if the check failed
+    pub error: Option<String>,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, result: Result<(), String>) -> Self {
+        Self {
+            name: name.into(),
+            error: result.err(),
+        }
+    }
+
+    /// Whether the check passed.
+    #[must_use]
+    pub const fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Checks run against one surface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SurfaceReport {
+    /// The surface checked
+    pub surface: Surface,
+    /// Individual checks in the order they ran
+    pub checks: Vec<Check>,
+}
+
+impl SurfaceReport {
+    /// Whether every check on the surface passed.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(Check::passed)
+    }
+}
+
+/// Result of a self-test run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestReport {
+    /// Product whose endpoints were requested
+    pub product: String,
+    /// Per-surface results
+    pub surfaces: Vec<SurfaceReport>,
+    /// Cross-surface comparison of the parsed responses
+    pub consistency: Vec<Check>,
+}
+
+impl SelfTestReport {
+    /// Whether every surface and the consistency checks passed.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.surfaces.iter().all(SurfaceReport::passed)
+            && self.consistency.iter().all(Check::passed)
+    }
+
+    /// Results for one surface.
+    #[must_use]
+    pub fn surface(&self, surface: Surface) -> Option<&SurfaceReport> {
+        self.surfaces
+            .iter()
+            .find(|report| report.surface == surface)
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_section(
+            f: &mut fmt::Formatter<'_>,
+            name: &dyn fmt::Display,
+            checks: &[Check],
+        ) -> fmt::Result {
+            let status = if checks.iter().all(Check::passed) {
+                "PASS"
+            } else {
+                "FAIL"
+            };
+            writeln!(f, "  {name}: {status}")?;
+            for check in checks {
+                if let Some(error) = &check.error {
+                    writeln!(f, "    {}: {error}", check.name)?;
+                }
+            }
+            Ok(())
+        }
+
+        writeln!(f, "Self-test for product '{}':", self.product)?;
+        for report in &self.surfaces {
+            write_section(f, &report.surface, &report.checks)?;
+        }
+        write_section(f, &"Consistency", &self.consistency)?;
+        write!(f, "Result: {}", if self.passed() { "PASS" } else { "FAIL" })
+    }
+}
+
+/// Run the self-test against a server.
+///
+/// `tcp_addr` serves both TCP v1 and v2. `product` must exist in the
+/// server's build database.
+pub async fn run(tcp_addr: SocketAddr, http_addr: SocketAddr, product: &str) -> SelfTestReport {
+    let mut surfaces = Vec::with_capacity(3);
+    // Parsed documents per endpoint, in `ENDPOINTS` order
+    let mut documents: Vec<(Surface, Vec<Option<BpsvDocument>>)> = Vec::with_capacity(3);
+
+    for surface in [Surface::TcpV1, Surface::TcpV2, Surface::Http] {
+        let mut checks = Vec::new();
+
+        if surface == Surface::TcpV1 {
+            let result = tcp_v1(tcp_addr, "v1/summary")
+                .await
+                .and_then(|summary| check_summary(&summary, product));
+            checks.push(Check::new("summary", result));
+        }
+
+        let mut parsed = Vec::with_capacity(ENDPOINTS.len());
+        for endpoint in ENDPOINTS {
+            let result = match surface {
+                Surface::TcpV1 => {
+                    tcp_v1(tcp_addr, &format!("v1/products/{product}/{endpoint}")).await
+                }
+                Surface::TcpV2 => {
+                    tcp_v2(tcp_addr, &format!("v2/products/{product}/{endpoint}")).await
+                }
+                Surface::Http => http(http_addr, &format!("/{product}/{endpoint}")).await,
+            }
+            .and_then(require_rows);
+
+            checks.push(Check::new(
+                endpoint,
+                result.as_ref().map(|_| ()).map_err(Clone::clone),
+            ));
+            parsed.push(result.ok());
+        }
+
+        surfaces.push(SurfaceReport { surface, checks });
+        documents.push((surface, parsed));
+    }
+
+    let consistency = ENDPOINTS
+        .iter()
+        .enumerate()
+        .map(|(index, endpoint)| {
+            let responses: Vec<_> = documents
+                .iter()
+                .filter_map(|(surface, parsed)| parsed[index].as_ref().map(|doc| (*surface, doc)))
+                .collect();
+            Check::new(*endpoint, check_consistent(&responses))
+        })
+        .collect();
+
+    SelfTestReport {
+        product: product.to_string(),
+        surfaces,
+        consistency,
+    }
+}
+
+/// Send a TCP command and read the response until the server closes.
+async fn tcp_request(addr: SocketAddr, command: &str) -> Result<String, String> {
+    // Shutting down the write side ends the request stream, so the
+    // connection is closed after one response instead of idling
+    request(addr, format!("{command}\r\n").as_bytes(), true).await
+}
+
+/// Write `request` to a new connection and read until it is closed.
+///
+/// With `close_write`, the write side is shut down after the request.
+async fn request(addr: SocketAddr, request: &[u8], close_write: bool) -> Result<String, String> {
+    let exchange = async {
+        let mut stream = TcpStream::connect(addr).await?;
+        stream.write_all(request).await?;
+        if close_write {
+            stream.shutdown().await?;
+        }
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+        Ok::<_, std::io::Error>(response)
+    };
+
+    let response = timeout(REQUEST_TIMEOUT, exchange)
+        .await
+        .map_err(|_| {
+            ProtocolError::Timeout {
+                seconds: REQUEST_TIMEOUT.as_secs(),
+            }
+            .to_string()
+        })?
+        .map_err(|e| format!("request to {addr} failed: {e}"))?;
+
+    String::from_utf8(response).map_err(|_| "response is not valid UTF-8".to_string())
+}
+
+async fn tcp_v1(addr: SocketAddr, command: &str) -> Result<BpsvDocument, String> {
+    let response = tcp_request(addr, command).await?;
+    parse_bpsv(&mime_payload(&response)?)
+}
+
+async fn tcp_v2(addr: SocketAddr, command: &str) -> Result<BpsvDocument, String> {
+    parse_bpsv(&tcp_request(addr, command).await?)
+}
+
+async fn http(addr: SocketAddr, path: &str) -> Result<BpsvDocument, String> {
+    let get = format!("GET {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n");
+    let response = request(addr, get.as_bytes(), false).await?;
+
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or("response has no header terminator")?;
+    let mut lines = head.split("\r\n");
+
+    let status = lines.next().unwrap_or_default();
+    if status.split(' ').nth(1) != Some("200") {
+        return Err(format!("unexpected status: {status}"));
+    }
+
+    let header = |name: &str| {
+        head.split("\r\n").skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    };
+
+    let content_type = header("content-type").unwrap_or_default();
+    if !content_type.starts_with("text/plain") {
+        return Err(format!("unexpected content type: '{content_type}'"));
+    }
+
+    let body = if header("transfer-encoding").is_some_and(|value| value.contains("chunked")) {
+        dechunk(body)?
+    } else {
+        body.to_string()
+    };
+    parse_bpsv(&body)
+}
+
+/// Decode a chunked HTTP/1.1 body.
+fn dechunk(mut body: &str) -> Result<String, String> {
+    let mut decoded = String::new();
+    loop {
+        let (size, rest) = body.split_once("\r\n").ok_or("truncated chunked body")?;
+        let size = usize::from_str_radix(size.split(';').next().unwrap_or_default().trim(), 16)
+            .map_err(|_| format!("invalid chunk size: '{size}'"))?;
+        if size == 0 {
+            return Ok(decoded);
+        }
+        let chunk = rest.get(..size).ok_or("truncated chunk")?;
+        decoded.push_str(chunk);
+        body = rest.get(size + 2..).ok_or("truncated chunk")?;
+    }
+}
+
+/// Validate a v1 MIME response and return its BPSV data part.
+fn mime_payload(response: &str) -> Result<String, String> {
+    let checksum_at = response
+        .rfind("Checksum: ")
+        .ok_or("missing Checksum line")?;
+    let (signed, checksum_line) = response.split_at(checksum_at);
+    let expected = checksum_line["Checksum: ".len()..].trim();
+    let actual = format!("{:x}", Sha256::digest(signed.as_bytes()));
+    if !expected.eq_ignore_ascii_case(&actual) {
+        return Err(format!(
+            "checksum mismatch: got {expected}, computed {actual}"
+        ));
+    }
+
+    let (headers, body) = signed
+        .split_once("\r\n\r\n")
+        .ok_or("missing MIME header terminator")?;
+    if !headers.starts_with("MIME-Version: 1.0") {
+        return Err("missing MIME-Version header".to_string());
+    }
+    let boundary = headers
+        .split("boundary=\"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .filter(|boundary| !boundary.is_empty())
+        .ok_or("missing multipart boundary")?;
+
+    let delimiter = format!("--{boundary}");
+    if !body.trim_end().ends_with(&format!("{delimiter}--")) {
+        return Err("missing closing boundary".to_string());
+    }
+
+    body.split(&delimiter)
+        .skip(1)
+        .filter_map(|part| part.strip_prefix("\r\n")?.split_once("\r\n\r\n"))
+        .find(|(part_headers, _)| part_headers.contains("Content-Type: text/plain"))
+        .map(|(_, data)| data.strip_suffix("\r\n").unwrap_or(data).to_string())
+        .ok_or_else(|| "missing text/plain data part".to_string())
+}
+
+fn parse_bpsv(content: &str) -> Result<BpsvDocument, String> {
+    bpsv::parse(content).map_err(|e| format!("invalid BPSV: {e}"))
+}
+
+fn require_rows(document: BpsvDocument) -> Result<BpsvDocument, String> {
+    if document.is_empty() {
+        Err("response has no rows".to_string())
+    } else {
+        Ok(document)
+    }
+}
+
+/// The summary must list the product under test.
+fn check_summary(summary: &BpsvDocument, product: &str) -> Result<(), String> {
+    let schema = summary.schema();
+    if summary
+        .rows()
+        .iter()
+        .any(|row| row.get_raw_by_name("Product", schema) == Some(product))
+    {
+        Ok(())
+    } else {
+        Err(format!("summary does not list product '{product}'"))
+    }
+}
+
+/// All parsed responses must have the same fields and rows.
+///
+/// Sequence numbers are ignored since each response is generated anew.
+fn check_consistent(responses: &[(Surface, &BpsvDocument)]) -> Result<(), String> {
+    let Some(((reference_surface, reference), others)) = responses.split_first() else {
+        return Err("no surface returned a valid response".to_string());
+    };
+
+    let rows = |document: &BpsvDocument| -> Vec<Vec<String>> {
+        document
+            .rows()
+            .iter()
+            .map(|row| row.raw_values().to_vec())
+            .collect()
+    };
+
+    for (surface, document) in others {
+        if document.field_names() != reference.field_names() || rows(document) != rows(reference) {
+            return Err(format!(
+                "{surface} response differs from {reference_surface} response"
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BPSV: &str = "Region!STRING:0|BuildId!DEC:4\n## seqn = 1\nus|42\n";
+
+    fn mime(body: &str) -> String {
+        let signed = format!(
+            "MIME-Version: 1.0\r\nContent-Type: multipart/alternative; boundary=\"B\"\r\n\r\n\
+             --B\r\nContent-Type: text/plain\r\nContent-Disposition: version\r\n\r\n{body}\r\n--B--\r\n"
+        );
+        let checksum = format!("{:x}", Sha256::digest(signed.as_bytes()));
+        format!("{signed}Checksum: {checksum}\r\n")
+    }
+
+    #[test]
+    fn test_mime_payload() {
+        assert_eq!(mime_payload(&mime(BPSV)).unwrap(), BPSV);
+
+        let tampered = mime(BPSV).replace("us|42", "eu|42");
+        assert!(mime_payload(&tampered).unwrap_err().contains("checksum"));
+        assert!(mime_payload(BPSV).is_err());
+    }
+
+    #[test]
+    fn test_dechunk() {
+        assert_eq!(
+            dechunk("5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n").unwrap(),
+            "hello world"
+        );
+        assert!(dechunk("5\r\nhel").is_err());
+    }
+
+    #[test]
+    fn test_check_consistent() {
+        let a = parse_bpsv(BPSV).unwrap();
+        let b = parse_bpsv(&BPSV.replace("seqn = 1", "seqn = 2")).unwrap();
+        let c = parse_bpsv(&BPSV.replace("42", "43")).unwrap();
+
+        assert!(check_consistent(&[(Surface::TcpV1, &a), (Surface::Http, &b)]).is_ok());
+        let error = check_consistent(&[(Surface::TcpV1, &a), (Surface::Http, &c)]).unwrap_err();
+        assert!(error.contains("HTTP"));
+        assert!(check_consistent(&[]).is_err());
+    }
+}
@@ -2,24 +2,73 @@
 //!
 //! Manages shared state between HTTP and TCP servers, including the build database
 //! and configuration.
+//!
+//! The build database can be reloaded while the server runs, on `SIGHUP` (Unix)
+//! or through `POST /admin/reload`, see [`AppState::reload_database`].
+//!
+//! Shutdown is coordinated through a `watch` channel in [`AppState`]: once it
+//! is set, listeners stop accepting connections and in-flight requests are
+//! allowed to finish, see [`Server::graceful_shutdown`].
 
 use crate::config::{CdnConfig, ServerConfig};
 use crate::database::BuildDatabase;
-use crate::error::ServerError;
+use crate::error::{ConfigError, DatabaseError, ServerError};
+use crate::http::middleware::RateLimiter;
+use crate::metrics::Metrics;
+use crate::responses::{ResponseCache, ResponseSigner};
+use crate::self_test::{self, SelfTestReport};
+use crate::tcp::rate_limit::TcpRateLimiter;
+use arc_swap::ArcSwap;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// How long [`Server::run`] waits for in-flight requests after a shutdown
+/// signal.
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
 
 /// Shared application state for HTTP and TCP servers.
 #[derive(Debug, Clone)]
 pub struct AppState {
-    /// Build database (loaded once at startup)
-    database: Arc<BuildDatabase>,
+    /// Build database, replaced as a whole on reload
+    database: Arc<ArcSwap<BuildDatabase>>,
+
+    /// Build database file or directory, read again on reload
+    builds: PathBuf,
 
     /// Default CDN configuration
     cdn_config: CdnConfig,
 
+    /// Rendered HTTP responses and their ETags, per database snapshot
+    http_responses: Arc<ResponseCache>,
+
+    /// Signer for TCP v1 responses (if configured)
+    signer: Option<Arc<ResponseSigner>>,
+
+    /// Per-IP limiter for HTTP requests (if enabled)
+    rate_limiter: Option<Arc<RateLimiter>>,
+
+    /// Per-IP limiter for TCP connections (if enabled)
+    tcp_rate_limiter: Option<Arc<TcpRateLimiter>>,
+
+    /// Idle timeout between pipelined TCP requests (`None` closes after one)
+    tcp_idle_timeout: Option<Duration>,
+
+    /// Bearer token for the admin endpoints (disabled if `None`)
+    admin_token: Option<String>,
+
+    /// Request counters served at `/metrics` (disabled if `None`)
+    metrics: Option<Arc<Metrics>>,
+
     /// Server start time (for metrics)
     started_at: SystemTime,
+
+    /// Set to `true` when the server begins shutting down
+    shutdown: watch::Sender<bool>,
 }
 
 impl AppState {
@@ -27,11 +76,12 @@ impl AppState {
     ///
     /// # Errors
     ///
-    /// Returns `ServerError` if database cannot be loaded.
+    /// Returns `ServerError` if database cannot be loaded or the signing
+    /// certificate/key is invalid.
     pub fn new(config: &ServerConfig) -> Result<Self, ServerError> {
         tracing::info!("Loading build database from {:?}", config.builds);
 
-        let database = BuildDatabase::from_file(&config.builds)?;
+        let database = load_database(&config.builds)?;
 
         tracing::info!(
             "Loaded {} builds for {} products",
@@ -41,17 +91,94 @@ impl AppState {
 
         let cdn_config = config.default_cdn_config();
 
+        let signer = config
+            .signing
+            .as_ref()
+            .map(ResponseSigner::from_config)
+            .transpose()?
+            .map(Arc::new);
+        if let Some(signer) = &signer {
+            tracing::info!("Signing TCP v1 responses as {signer:?}");
+        }
+
+        let rate_limiter = config.has_rate_limit().then(|| {
+            tracing::info!(
+                "Rate limiting HTTP to {} requests/s per client (burst {})",
+                config.rate_limit,
+                config.rate_limit_burst
+            );
+            Arc::new(RateLimiter::new(config.rate_limit_burst, config.rate_limit))
+        });
+
+        let tcp_rate_limiter = config.has_tcp_rate_limit().then(|| {
+            tracing::info!(
+                "Rate limiting TCP to {} connections/s per client (burst {}, {} exempt networks)",
+                config.tcp_rate_limit,
+                config.tcp_rate_limit_burst,
+                config.tcp_rate_limit_exempt.len()
+            );
+            Arc::new(TcpRateLimiter::new(
+                config.tcp_rate_limit_burst,
+                config.tcp_rate_limit,
+                config.tcp_rate_limit_exempt.clone(),
+            ))
+        });
+
         Ok(Self {
-            database: Arc::new(database),
+            database: Arc::new(ArcSwap::from_pointee(database)),
+            builds: config.builds.clone(),
             cdn_config,
+            http_responses: Arc::new(ResponseCache::new()),
+            signer,
+            rate_limiter,
+            tcp_rate_limiter,
+            tcp_idle_timeout: config.tcp_idle_timeout(),
+            admin_token: config.admin_token.clone(),
+            metrics: config.has_metrics().then(|| Arc::new(Metrics::new())),
             started_at: SystemTime::now(),
+            shutdown: watch::Sender::new(false),
         })
     }
 
-    /// Get reference to build database.
+    /// Get the current build database.
+    ///
+    /// The returned snapshot is unaffected by later reloads, so a request
+    /// that takes one snapshot answers consistently from it.
     #[must_use]
-    pub const fn database(&self) -> &Arc<BuildDatabase> {
-        &self.database
+    pub fn database(&self) -> Arc<BuildDatabase> {
+        self.database.load_full()
+    }
+
+    /// Load the build database again and swap it in.
+    ///
+    /// The new database replaces the current one only if it loads and
+    /// validates. On failure the error is logged and returned, and the
+    /// previous database keeps serving. Requests holding a snapshot from
+    /// [`Self::database`] finish with the old one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DatabaseError` if the database cannot be read or is invalid.
+    pub fn reload_database(&self) -> Result<Arc<BuildDatabase>, DatabaseError> {
+        let database = match load_database(&self.builds) {
+            Ok(database) => Arc::new(database),
+            Err(e) => {
+                tracing::error!(
+                    "Failed to reload build database from {:?}, keeping the previous one: {e}",
+                    self.builds
+                );
+                return Err(e);
+            }
+        };
+
+        self.database.store(database.clone());
+        self.http_responses.clear();
+        tracing::info!(
+            "Reloaded build database: {} builds for {} products",
+            database.total_builds(),
+            database.products().len()
+        );
+        Ok(database)
     }
 
     /// Get default CDN configuration.
@@ -60,6 +187,65 @@ impl AppState {
         &self.cdn_config
     }
 
+    /// Get the cache of rendered HTTP responses.
+    #[must_use]
+    pub fn http_responses(&self) -> &ResponseCache {
+        &self.http_responses
+    }
+
+    /// Get the TCP v1 response signer, if signing is configured.
+    #[must_use]
+    pub fn signer(&self) -> Option<&ResponseSigner> {
+        self.signer.as_deref()
+    }
+
+    /// Get the HTTP rate limiter, if rate limiting is enabled.
+    #[must_use]
+    pub const fn rate_limiter(&self) -> Option<&Arc<RateLimiter>> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// Get the TCP connection rate limiter, if TCP rate limiting is enabled.
+    #[must_use]
+    pub const fn tcp_rate_limiter(&self) -> Option<&Arc<TcpRateLimiter>> {
+        self.tcp_rate_limiter.as_ref()
+    }
+
+    /// Get the idle timeout between TCP requests on one connection.
+    ///
+    /// `None` means each connection is closed after its first response.
+    #[must_use]
+    pub const fn tcp_idle_timeout(&self) -> Option<Duration> {
+        self.tcp_idle_timeout
+    }
+
+    /// Get the bearer token for the admin endpoints, if they are enabled.
+    #[must_use]
+    pub fn admin_token(&self) -> Option<&str> {
+        self.admin_token.as_deref()
+    }
+
+    /// Get the request metrics, if they are enabled.
+    #[must_use]
+    pub const fn metrics(&self) -> Option<&Arc<Metrics>> {
+        self.metrics.as_ref()
+    }
+
+    /// Get the shutdown signal.
+    ///
+    /// Connection handlers subscribe to it to stop accepting work; sending
+    /// `true` starts a shutdown.
+    #[must_use]
+    pub const fn shutdown_sender(&self) -> &watch::Sender<bool> {
+        &self.shutdown
+    }
+
+    /// Check whether the server is shutting down.
+    #[must_use]
+    pub fn is_shutting_down(&self) -> bool {
+        *self.shutdown.borrow()
+    }
+
     /// Get current sequence number (Unix timestamp).
     ///
     /// Used for BPSV sequence numbers to enable client-side caching.
@@ -84,10 +270,19 @@ impl AppState {
 /// Server orchestration.
 pub struct Server {
     /// Shared application state
-    #[allow(dead_code)] // Used in Phase 4 for HTTP/TCP handlers
     state: Arc<AppState>,
     /// Server configuration
     config: ServerConfig,
+    /// Listener tasks, once started
+    running: Option<RunningServer>,
+}
+
+/// Listener tasks of a started server.
+struct RunningServer {
+    http_addr: SocketAddr,
+    tcp_addr: SocketAddr,
+    http: JoinHandle<Result<(), ServerError>>,
+    tcp: JoinHandle<Result<(), ServerError>>,
 }
 
 impl Server {
@@ -102,27 +297,33 @@ impl Server {
         // Load application state (includes database)
         let state = AppState::new(&config)?;
 
+        let database = state.database();
         tracing::info!(
             "Server initialized with {} builds across {} products",
-            state.database().total_builds(),
-            state.database().products().len()
+            database.total_builds(),
+            database.products().len()
         );
 
         Ok(Self {
             state: Arc::new(state),
             config,
+            running: None,
         })
     }
 
-    /// Run the server (start HTTP and TCP listeners).
+    /// Bind the HTTP and TCP listeners and start serving in the background.
     ///
-    /// This starts both HTTP and TCP servers concurrently.
-    /// The server runs until interrupted or an error occurs.
+    /// On Unix, `SIGHUP` reloads the build database from then on until
+    /// shutdown. Calling this on a running server does nothing.
     ///
     /// # Errors
     ///
-    /// Returns `ServerError` if server binding fails.
-    pub async fn run(self) -> Result<(), ServerError> {
+    /// Returns `ServerError` if either listener cannot be bound.
+    pub async fn start(&mut self) -> Result<(), ServerError> {
+        if self.running.is_some() {
+            return Ok(());
+        }
+
         tracing::info!("Starting Cascette Ribbit Server");
         tracing::info!("HTTP server binding to: {}", self.config.http_bind);
         tracing::info!("TCP server binding to: {}", self.config.tcp_bind);
@@ -133,46 +334,220 @@ impl Server {
             tracing::info!("TLS disabled (HTTP only)");
         }
 
-        // Start HTTP and TCP servers concurrently
-        let http_state = self.state.clone();
-        let tcp_state = self.state.clone();
         let http_bind = self.config.http_bind;
+        let http_listener =
+            TcpListener::bind(http_bind)
+                .await
+                .map_err(|source| ServerError::HttpBindFailed {
+                    addr: http_bind,
+                    source,
+                })?;
         let tcp_bind = self.config.tcp_bind;
-
-        let http_server = tokio::spawn(async move {
-            if let Err(e) = crate::http::start_server(http_bind, http_state).await {
-                tracing::error!("HTTP server failed: {e}");
+        let tcp_listener =
+            TcpListener::bind(tcp_bind)
+                .await
+                .map_err(|source| ServerError::TcpBindFailed {
+                    addr: tcp_bind,
+                    source,
+                })?;
+
+        let http_addr = http_listener.local_addr().unwrap_or(http_bind);
+        let tcp_addr = tcp_listener.local_addr().unwrap_or(tcp_bind);
+
+        let http = tokio::spawn(crate::http::serve(http_listener, self.state.clone()));
+        let tcp = tokio::spawn(crate::tcp::serve(tcp_listener, self.state.clone()));
+
+        #[cfg(unix)]
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+            Ok(hangup) => {
+                tokio::spawn(reload_on_hangup(self.state.clone(), hangup));
             }
-        });
+            Err(e) => tracing::warn!("Failed to listen for SIGHUP, reloading disabled: {e}"),
+        }
 
-        let tcp_server = tokio::spawn(async move {
-            if let Err(e) = crate::tcp::start_server(tcp_bind, tcp_state).await {
-                tracing::error!("TCP server failed: {e}");
-            }
+        self.running = Some(RunningServer {
+            http_addr,
+            tcp_addr,
+            http,
+            tcp,
         });
+        Ok(())
+    }
+
+    /// Address the HTTP listener is bound to, once started.
+    #[must_use]
+    pub fn http_addr(&self) -> Option<SocketAddr> {
+        self.running.as_ref().map(|running| running.http_addr)
+    }
 
-        // Wait for shutdown signal
-        tokio::signal::ctrl_c().await.map_err(|e| {
-            ServerError::Shutdown(format!("Failed to listen for shutdown signal: {e}"))
-        })?;
+    /// Address the TCP listener is bound to, once started.
+    #[must_use]
+    pub fn tcp_addr(&self) -> Option<SocketAddr> {
+        self.running.as_ref().map(|running| running.tcp_addr)
+    }
 
+    /// Run the server (start HTTP and TCP listeners).
+    ///
+    /// This starts both HTTP and TCP servers concurrently.
+    /// The server runs until Ctrl-C or `SIGTERM`, then shuts down gracefully
+    /// within [`DEFAULT_SHUTDOWN_TIMEOUT`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `ServerError` if server binding fails or in-flight requests
+    /// do not finish in time.
+    pub async fn run(mut self) -> Result<(), ServerError> {
+        self.start().await?;
+
+        wait_for_shutdown_signal().await?;
         tracing::info!("Shutdown signal received, stopping server");
 
-        // Wait for servers to shutdown gracefully
-        http_server.abort();
-        tcp_server.abort();
+        self.graceful_shutdown(DEFAULT_SHUTDOWN_TIMEOUT).await
+    }
+
+    /// Stop accepting connections and wait for in-flight requests.
+    ///
+    /// New TCP and HTTP connections are refused right away. Requests already
+    /// being served get up to `timeout` to complete; idle connections are
+    /// closed. Listeners still busy after `timeout` are aborted. Shutdown is
+    /// final: the server cannot be started again afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ServerError::Shutdown` if requests were still in flight
+    /// after `timeout`, or the error a listener stopped with.
+    pub async fn graceful_shutdown(&mut self, timeout: Duration) -> Result<(), ServerError> {
+        self.state.shutdown_sender().send_replace(true);
 
+        let Some(mut running) = self.running.take() else {
+            return Ok(());
+        };
+
+        let drained = tokio::time::timeout(timeout, async {
+            [(&mut running.http).await, (&mut running.tcp).await]
+        })
+        .await;
+
+        let Ok(results) = drained else {
+            running.http.abort();
+            running.tcp.abort();
+            return Err(ServerError::Shutdown(format!(
+                "Requests still in flight after {}s, connections aborted",
+                timeout.as_secs_f64()
+            )));
+        };
+
+        tracing::info!("Server stopped");
+
+        for result in results {
+            result.map_err(|e| ServerError::Shutdown(format!("Listener task failed: {e}")))??;
+        }
         Ok(())
     }
 
-    /// Get shared application state (for testing).
-    #[cfg(test)]
+    /// Request every protocol surface of the running server and verify the
+    /// responses, see [`crate::self_test`].
+    ///
+    /// Starts the server if needed. Unspecified bind addresses are reached
+    /// over loopback. The first product in the build database is used.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ServerError` if the server cannot be started. Failed checks
+    /// are reported in the returned [`SelfTestReport`], not as errors.
+    pub async fn self_test(&mut self) -> Result<SelfTestReport, ServerError> {
+        self.start().await?;
+
+        let (Some(tcp_addr), Some(http_addr)) = (self.tcp_addr(), self.http_addr()) else {
+            return Err(ServerError::Shutdown(
+                "Server has been shut down".to_string(),
+            ));
+        };
+        let Some(product) = self
+            .state
+            .database()
+            .loaded_products()
+            .first()
+            .map(|p| (*p).to_string())
+        else {
+            return Err(ServerError::Config(ConfigError::MissingRequired(
+                "a product in the build database to self-test".to_string(),
+            )));
+        };
+
+        Ok(self_test::run(loopback(tcp_addr), loopback(http_addr), &product).await)
+    }
+
+    /// Get shared application state, e.g. to read the TCP rate limiter's
+    /// dropped-connection count.
     #[must_use]
     pub const fn state(&self) -> &Arc<AppState> {
         &self.state
     }
 }
 
+/// Load the build database from a JSON file or a directory of them.
+fn load_database(builds: &Path) -> Result<BuildDatabase, DatabaseError> {
+    if builds.is_dir() {
+        BuildDatabase::from_directory(builds)
+    } else {
+        BuildDatabase::from_file(builds)
+    }
+}
+
+/// Reload the build database on every `SIGHUP` until shutdown.
+#[cfg(unix)]
+async fn reload_on_hangup(state: Arc<AppState>, mut hangup: tokio::signal::unix::Signal) {
+    let mut shutdown = state.shutdown_sender().subscribe();
+    loop {
+        tokio::select! {
+            _ = shutdown.wait_for(|&stop| stop) => break,
+            received = hangup.recv() => {
+                if received.is_none() {
+                    break;
+                }
+                tracing::info!("SIGHUP received, reloading build database");
+                // Failures are logged and the previous database is kept
+                let _ = state.reload_database();
+            }
+        }
+    }
+}
+
+/// Replace an unspecified IP (`0.0.0.0`, `::`) with loopback.
+fn loopback(mut addr: SocketAddr) -> SocketAddr {
+    if addr.ip().is_unspecified() {
+        addr.set_ip(match addr {
+            SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+            SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+        });
+    }
+    addr
+}
+
+/// Wait for Ctrl-C, or `SIGTERM` on Unix.
+async fn wait_for_shutdown_signal() -> Result<(), ServerError> {
+    let failed = |e: std::io::Error| {
+        ServerError::Shutdown(format!("Failed to listen for shutdown signal: {e}"))
+    };
+
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut terminate = signal(SignalKind::terminate()).map_err(failed)?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.map_err(failed),
+            _ = terminate.recv() => Ok(()),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await.map_err(failed)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -210,6 +585,15 @@ mod tests {
             cdn_path: "test/path".to_string(),
             tls_cert: None,
             tls_key: None,
+            rate_limit: 0.0,
+            rate_limit_burst: 1,
+            tcp_idle_timeout_secs: 10,
+            tcp_rate_limit: 0.0,
+            tcp_rate_limit_burst: 1,
+            tcp_rate_limit_exempt: Vec::new(),
+            signing: None,
+            admin_token: None,
+            disable_metrics: false,
         };
 
         let state = AppState::new(&config).unwrap();
@@ -229,6 +613,15 @@ mod tests {
             cdn_path: "test/path".to_string(),
             tls_cert: None,
             tls_key: None,
+            rate_limit: 0.0,
+            rate_limit_burst: 1,
+            tcp_idle_timeout_secs: 10,
+            tcp_rate_limit: 0.0,
+            tcp_rate_limit_burst: 1,
+            tcp_rate_limit_exempt: Vec::new(),
+            signing: None,
+            admin_token: None,
+            disable_metrics: false,
         };
 
         let state = AppState::new(&config).unwrap();
@@ -250,6 +643,15 @@ mod tests {
             cdn_path: "test/path".to_string(),
             tls_cert: None,
             tls_key: None,
+            rate_limit: 0.0,
+            rate_limit_burst: 1,
+            tcp_idle_timeout_secs: 10,
+            tcp_rate_limit: 0.0,
+            tcp_rate_limit_burst: 1,
+            tcp_rate_limit_exempt: Vec::new(),
+            signing: None,
+            admin_token: None,
+            disable_metrics: false,
         };
 
         let state = AppState::new(&config).unwrap();
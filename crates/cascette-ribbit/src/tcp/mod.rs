@@ -4,11 +4,14 @@ use crate::error::{ProtocolError, ServerError};
 use crate::server::AppState;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
 use tokio::time::{Duration, timeout};
 
 pub mod handlers;
+pub mod rate_limit;
 pub mod v1;
 pub mod v2;
 
@@ -28,72 +31,176 @@ pub async fn start_server(bind_addr: SocketAddr, state: Arc<AppState>) -> Result
 
     tracing::info!("TCP server listening on {bind_addr}");
 
-    loop {
-        let (socket, addr) = listener
-            .accept()
-            .await
-            .map_err(|e| ServerError::Shutdown(format!("Failed to accept TCP connection: {e}")))?;
+    serve(listener, state).await
+}
 
-        let state = state.clone();
+/// Serve Ribbit v1/v2 requests on a bound listener.
+///
+/// Runs until the shutdown signal in `state` is set. The listener is then
+/// closed and the call returns once open connections have been served.
+///
+/// # Errors
+///
+/// Returns `ServerError` if accepting a connection fails.
+pub async fn serve(listener: TcpListener, state: Arc<AppState>) -> Result<(), ServerError> {
+    let mut shutdown = state.shutdown_sender().subscribe();
+    let mut connections = JoinSet::new();
 
-        // Spawn a task for each connection
-        tokio::spawn(async move {
-            if let Err(e) = handle_connection(socket, state).await {
-                tracing::warn!("TCP connection from {addr} failed: {e}");
+    loop {
+        tokio::select! {
+            _ = shutdown.wait_for(|&stop| stop) => break,
+            accepted = listener.accept() => {
+                let (socket, addr) = accepted.map_err(|e| {
+                    ServerError::Shutdown(format!("Failed to accept TCP connection: {e}"))
+                })?;
+
+                if let Some(limiter) = state.tcp_rate_limiter()
+                    && limiter.check(addr.ip()).is_err()
+                {
+                    tracing::debug!("Rate limited TCP connection from {}", addr.ip());
+                    connections.spawn(reject_connection(socket));
+                    continue;
+                }
+
+                let state = state.clone();
+
+                // Spawn a task for each connection
+                connections.spawn(async move {
+                    if let Err(e) = handle_connection(socket, state).await {
+                        tracing::warn!("TCP connection from {addr} failed: {e}");
+                    }
+                });
             }
-        });
+            // Reap finished connections
+            Some(_) = connections.join_next(), if !connections.is_empty() => {}
+        }
     }
+
+    drop(listener);
+    tracing::info!(
+        "TCP server stopped accepting connections, {} still open",
+        connections.len()
+    );
+    while connections.join_next().await.is_some() {}
+
+    Ok(())
+}
+
+/// Time a new connection has to send its first command.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time a rate limited client has to accept the rejection.
+const REJECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Send a rate limited client [`rate_limit::RATE_LIMITED_RESPONSE`] and
+/// close the connection.
+async fn reject_connection(mut socket: TcpStream) {
+    let _ = timeout(REJECT_TIMEOUT, async {
+        socket.write_all(rate_limit::RATE_LIMITED_RESPONSE).await?;
+        socket.shutdown().await
+    })
+    .await;
 }
 
 /// Handle a single TCP connection.
 ///
+/// Commands are newline-terminated lines, answered in the order they
+/// arrive. After each response the connection waits up to
+/// [`AppState::tcp_idle_timeout`] for the next command, and is closed when
+/// the client shuts down its write side or the timeout passes. Without an
+/// idle timeout the connection is closed after the first response.
+///
+/// A connection that is waiting for a command when shutdown begins is
+/// closed; once the client has started sending a command, it is answered.
+///
 /// # Errors
 ///
 /// Returns `ProtocolError` if connection handling fails.
-async fn handle_connection(
-    mut socket: TcpStream,
-    state: Arc<AppState>,
-) -> Result<(), ProtocolError> {
+async fn handle_connection(socket: TcpStream, state: Arc<AppState>) -> Result<(), ProtocolError> {
     let addr = socket.peer_addr()?;
     tracing::debug!("Accepted TCP connection from {addr}");
 
-    // Read command with timeout
-    let mut reader = BufReader::new(&mut socket);
-    let mut command = String::new();
+    let mut reader = BufReader::new(socket);
+    let mut shutdown = state.shutdown_sender().subscribe();
+    let mut served = 0_usize;
 
-    let read_result = timeout(Duration::from_secs(10), reader.read_line(&mut command)).await;
+    loop {
+        let wait = if served == 0 {
+            COMMAND_TIMEOUT
+        } else if let Some(idle) = state.tcp_idle_timeout() {
+            idle
+        } else {
+            break;
+        };
+
+        let command = match timeout(wait, read_command(&mut reader, &mut shutdown)).await {
+            Ok(Ok(Some(command))) => command,
+            Ok(Ok(None)) => {
+                tracing::debug!("TCP connection from {addr} closed after {served} commands");
+                if served == 0 {
+                    return Ok(());
+                }
+                break;
+            }
+            Ok(Err(e)) => return Err(ProtocolError::Io(e)),
+            Err(_) if served == 0 => {
+                return Err(ProtocolError::Timeout {
+                    seconds: COMMAND_TIMEOUT.as_secs(),
+                });
+            }
+            Err(_) => {
+                tracing::debug!("TCP connection from {addr} idle, closing");
+                break;
+            }
+        };
 
-    match read_result {
-        Ok(Ok(0)) => {
-            tracing::debug!("TCP connection closed by client: {addr}");
-            return Ok(());
+        let command = command.trim();
+        if command.is_empty() {
+            continue;
         }
-        Ok(Ok(_)) => {
-            // Command received, process it
-            let command = command.trim();
-            tracing::debug!("Received TCP command from {addr}: {command}");
+        tracing::debug!("Received TCP command from {addr}: {command}");
 
-            let response = handlers::handle_command(command, &state)?;
+        let result = handlers::handle_command(command, &state);
+        if let Some(metrics) = state.metrics() {
+            let size = result.as_ref().ok().map(String::len);
+            metrics.record_command(command, &state.database(), size);
+        }
+        let response = result?;
 
-            // Write response
-            socket.write_all(response.as_bytes()).await?;
-            socket.flush().await?;
+        let socket = reader.get_mut();
+        socket.write_all(response.as_bytes()).await?;
+        socket.flush().await?;
+        served += 1;
 
-            tracing::debug!("Sent TCP response to {addr}: {} bytes", response.len());
-        }
-        Ok(Err(e)) => {
-            return Err(ProtocolError::Io(e));
-        }
-        Err(_) => {
-            return Err(ProtocolError::Timeout { seconds: 10 });
-        }
+        tracing::debug!("Sent TCP response to {addr}: {} bytes", response.len());
     }
 
-    // Close connection immediately after response
-    socket.shutdown().await?;
+    reader.into_inner().shutdown().await?;
     Ok(())
 }
 
+/// Read the command line of a connection.
+///
+/// Returns `None` if the client closes its write side, or the server starts
+/// shutting down, before sending anything.
+async fn read_command<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    shutdown: &mut watch::Receiver<bool>,
+) -> std::io::Result<Option<String>> {
+    let started = tokio::select! {
+        biased;
+        filled = reader.fill_buf() => !filled?.is_empty(),
+        _ = shutdown.wait_for(|&stop| stop) => false,
+    };
+    if !started {
+        return Ok(None);
+    }
+
+    let mut command = String::new();
+    reader.read_line(&mut command).await?;
+    Ok(Some(command))
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
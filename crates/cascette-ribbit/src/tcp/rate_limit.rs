@@ -0,0 +1,126 @@
+//! Per-IP rate limiting of TCP connections.
+//!
+//! Every accepted connection takes one token from its client's bucket in a
+//! [`RateLimiter`]. A client over its limit is sent [`RATE_LIMITED_RESPONSE`]
+//! and disconnected before any command is read, so the connection costs no
+//! database lookups or response signing. Clients in an exempt network, such
+//! as a monitoring host, are never limited.
+
+use crate::http::middleware::RateLimiter;
+use ipnet::IpNet;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Response sent to a client over its TCP rate limit before closing.
+pub const RATE_LIMITED_RESPONSE: &[u8] = b"Too Many Requests\r\n";
+
+/// Per-IP connection limiter for the TCP listener.
+#[derive(Debug)]
+pub struct TcpRateLimiter {
+    limiter: RateLimiter,
+    exempt: Vec<IpNet>,
+    dropped: AtomicU64,
+}
+
+impl TcpRateLimiter {
+    /// Create a limiter allowing bursts of `capacity` connections, refilled
+    /// at `refill_per_second` connections per second, for clients outside
+    /// the `exempt` networks.
+    #[must_use]
+    pub fn new(capacity: u32, refill_per_second: f64, exempt: Vec<IpNet>) -> Self {
+        Self {
+            limiter: RateLimiter::new(capacity, refill_per_second),
+            exempt,
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Admit a connection from `ip`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the time until a token is available if the client is over
+    /// its limit. The connection is counted as dropped.
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        self.check_at(ip, Instant::now())
+    }
+
+    /// Admit a connection from `ip` at `now`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the time until a token is available if the client is over
+    /// its limit. The connection is counted as dropped.
+    pub fn check_at(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        if self.is_exempt(ip) {
+            return Ok(());
+        }
+        self.limiter.check_at(ip, now).inspect_err(|_| {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        })
+    }
+
+    /// Check whether `ip` is in an exempt network.
+    ///
+    /// IPv4 clients of a dual-stack listener are matched by their IPv4
+    /// address.
+    #[must_use]
+    pub fn is_exempt(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.exempt.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Number of connections rejected for being over the limit.
+    #[must_use]
+    pub fn dropped_connections(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    const CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+    const MONITOR: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 1, 2, 3));
+
+    #[test]
+    fn test_over_limit_connections_are_counted() {
+        let limiter = TcpRateLimiter::new(2, 1.0, Vec::new());
+        let now = Instant::now();
+
+        assert!(limiter.check_at(CLIENT, now).is_ok());
+        assert!(limiter.check_at(CLIENT, now).is_ok());
+        assert!(limiter.check_at(CLIENT, now).is_err());
+        assert!(limiter.check_at(CLIENT, now).is_err());
+        assert_eq!(limiter.dropped_connections(), 2);
+
+        assert!(
+            limiter
+                .check_at(CLIENT, now + Duration::from_secs(1))
+                .is_ok()
+        );
+        assert_eq!(limiter.dropped_connections(), 2);
+    }
+
+    #[test]
+    fn test_exempt_networks() {
+        let limiter = TcpRateLimiter::new(1, 1.0, vec!["10.0.0.0/8".parse().unwrap()]);
+        let now = Instant::now();
+
+        for _ in 0..10 {
+            assert!(limiter.check_at(MONITOR, now).is_ok());
+        }
+        assert!(limiter.check_at(CLIENT, now).is_ok());
+        assert!(limiter.check_at(CLIENT, now).is_err());
+        assert_eq!(limiter.dropped_connections(), 1);
+
+        // An IPv4-mapped address from a dual-stack listener
+        let mapped = IpAddr::V6(Ipv4Addr::new(10, 1, 2, 3).to_ipv6_mapped());
+        assert!(limiter.is_exempt(mapped));
+        assert!(!limiter.is_exempt(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+    }
+}
@@ -2,14 +2,16 @@
 
 use crate::config::CdnConfig;
 use crate::error::ProtocolError;
-use crate::responses::BpsvResponse;
+use crate::responses::{BpsvResponse, ResponseSigner};
 use crate::server::AppState;
+use base64::Engine as _;
 use sha2::{Digest, Sha256};
 use std::fmt::Write as _;
 
 /// Handle TCP Ribbit v1 command.
 ///
-/// v1 commands return MIME-wrapped BPSV responses with SHA-256 checksums.
+/// v1 commands return MIME-wrapped BPSV responses with SHA-256 checksums,
+/// plus a CMS signature part when the server has a signer configured.
 ///
 /// Supported commands:
 /// - `v1/summary` (TCP v1 only - list all products)
@@ -23,7 +25,7 @@ use std::fmt::Write as _;
 pub fn handle_v1_command(command: &str, state: &AppState) -> Result<String, ProtocolError> {
     // Special case: v1/summary endpoint
     if command == "v1/summary" {
-        return Ok(handle_summary(state));
+        return handle_summary(state);
     }
 
     // Parse command format: v1/products/{product}/{endpoint}
@@ -38,22 +40,24 @@ pub fn handle_v1_command(command: &str, state: &AppState) -> Result<String, Prot
     let product = parts[2];
     let endpoint = parts[3];
 
+    let database = state.database();
+
     // Get build for product
-    let build = state
-        .database()
+    let build = database
         .latest_build(product)
         .ok_or_else(|| ProtocolError::InvalidCommand(format!("Product not found: {product}")))?;
 
     let seqn = state.current_seqn();
 
-    // Generate appropriate BPSV response
-    let bpsv = match endpoint {
-        "versions" => BpsvResponse::versions(build, seqn),
+    // Generate appropriate BPSV response; the data part disposition names
+    // the endpoint like Blizzard's servers do
+    let (bpsv, disposition) = match endpoint {
+        "versions" => (BpsvResponse::versions(build, seqn), "version"),
         "cdns" => {
             let cdn_config = CdnConfig::resolve_for_build(build, state.cdn_config());
-            BpsvResponse::cdns(&cdn_config, seqn)
+            (BpsvResponse::cdns(&cdn_config, seqn), "cdns")
         }
-        "bgdl" => BpsvResponse::bgdl(build, seqn),
+        "bgdl" => (BpsvResponse::bgdl(build, seqn), "bgdl"),
         _ => {
             return Err(ProtocolError::InvalidCommand(format!(
                 "Unknown v1 endpoint: {endpoint}"
@@ -62,20 +66,21 @@ pub fn handle_v1_command(command: &str, state: &AppState) -> Result<String, Prot
     };
 
     // Wrap in MIME with checksum
-    Ok(wrap_in_mime(&bpsv.to_string()))
+    wrap_in_mime(&bpsv.to_string(), disposition, state.signer())
 }
 
 /// Handle v1/summary command (TCP v1 only).
 ///
 /// Returns list of all available products.
-fn handle_summary(state: &AppState) -> String {
-    let products = state.database().products();
+fn handle_summary(state: &AppState) -> Result<String, ProtocolError> {
+    let database = state.database();
+    let products = database.products();
     let seqn = state.current_seqn();
 
     let bpsv = BpsvResponse::summary(&products, seqn);
 
     // Wrap in MIME with checksum
-    wrap_in_mime(&bpsv.to_string())
+    wrap_in_mime(&bpsv.to_string(), "summary", state.signer())
 }
 
 /// Wrap BPSV content in MIME multipart/alternative with SHA-256 checksum.
@@ -87,29 +92,61 @@ fn handle_summary(state: &AppState) -> String {
 /// \r\n
 /// --RibbitBoundary\r\n
 /// Content-Type: text/plain\r\n
-/// Content-Disposition: data\r\n
+/// Content-Disposition: [version|cdns|bgdl|summary]\r\n
 /// \r\n
 /// [BPSV content with LF line endings]
 /// \r\n
 /// --RibbitBoundary--\r\n
 /// Checksum: [64-character SHA-256 hex]\r\n
 /// ```
-fn wrap_in_mime(bpsv_content: &str) -> String {
-    let mime_parts = [
+///
+/// With a signer, a signature part is added after the data part. The
+/// signature covers the BPSV content exactly as sent:
+/// ```text
+/// --RibbitBoundary\r\n
+/// Content-Type: application/cms\r\n
+/// Content-Disposition: signature\r\n
+/// Content-Transfer-Encoding: base64\r\n
+/// \r\n
+/// [base64 DER CMS SignedData]\r\n
+/// ```
+fn wrap_in_mime(
+    bpsv_content: &str,
+    disposition: &str,
+    signer: Option<&ResponseSigner>,
+) -> Result<String, ProtocolError> {
+    let signature = signer
+        .map(|signer| signer.sign(bpsv_content.as_bytes()))
+        .transpose()?
+        .map(|der| base64::engine::general_purpose::STANDARD.encode(der));
+
+    let disposition = format!("Content-Disposition: {disposition}\r\n");
+    let mut mime_parts = vec![
         "MIME-Version: 1.0\r\n",
         "Content-Type: multipart/alternative; boundary=\"RibbitBoundary\"\r\n",
         "\r\n",
         "--RibbitBoundary\r\n",
         "Content-Type: text/plain\r\n",
-        "Content-Disposition: data\r\n",
+        &disposition,
         "\r\n",
         bpsv_content,
         "\r\n",
-        "--RibbitBoundary--\r\n",
     ];
+    if let Some(signature) = &signature {
+        mime_parts.extend([
+            "--RibbitBoundary\r\n",
+            "Content-Type: application/cms\r\n",
+            "Content-Disposition: signature\r\n",
+            "Content-Transfer-Encoding: base64\r\n",
+            "\r\n",
+            signature,
+            "\r\n",
+        ]);
+    }
+    mime_parts.push("--RibbitBoundary--\r\n");
 
     // Calculate SHA-256 checksum of everything before "Checksum:" line
-    let content_before_checksum = mime_parts.join("");
+    let content_before_checksum = mime_parts.concat();
     let mut hasher = Sha256::new();
     hasher.update(content_before_checksum.as_bytes());
     let checksum = format!("{:x}", hasher.finalize());
@@ -119,7 +156,7 @@ fn wrap_in_mime(bpsv_content: &str) -> String {
     // write! to String is infallible but returns Result
     let _ = write!(&mut result, "Checksum: {checksum}\r\n");
 
-    result
+    Ok(result)
 }
 
 #[cfg(test)]
@@ -142,6 +179,15 @@ mod tests {
             cdn_path: "test/path".to_string(),
             tls_cert: None,
             tls_key: None,
+            rate_limit: 0.0,
+            rate_limit_burst: 1,
+            tcp_idle_timeout_secs: 10,
+            tcp_rate_limit: 0.0,
+            tcp_rate_limit_burst: 1,
+            tcp_rate_limit_exempt: Vec::new(),
+            signing: None,
+            admin_token: None,
+            disable_metrics: false,
         };
 
         Arc::new(AppState::new(&config).unwrap())
@@ -173,7 +219,7 @@ mod tests {
     #[test]
     fn test_mime_wrapping() {
         let bpsv = "Header!STRING:0\ndata1|data2\n## seqn = 123";
-        let mime = wrap_in_mime(bpsv);
+        let mime = wrap_in_mime(bpsv, "version", None).unwrap();
 
         assert!(mime.contains("MIME-Version: 1.0\r\n"));
         assert!(mime.contains("multipart/alternative"));
@@ -192,7 +238,7 @@ mod tests {
     #[test]
     fn test_checksum_calculation() {
         let bpsv = "test content";
-        let mime = wrap_in_mime(bpsv);
+        let mime = wrap_in_mime(bpsv, "version", None).unwrap();
 
         // Extract checksum
         let checksum_line = mime.lines().last().unwrap();
@@ -206,4 +252,25 @@ mod tests {
 
         assert_eq!(checksum, expected);
     }
+
+    #[test]
+    fn test_signed_mime_wrapping() {
+        let signer = ResponseSigner::from_pem(
+            include_str!("../../tests/fixtures/signing_cert.pem"),
+            include_str!("../../tests/fixtures/signing_key.pem"),
+        )
+        .unwrap();
+        let bpsv = "Header!STRING:0\ndata1\n## seqn = 123";
+        let mime = wrap_in_mime(bpsv, "version", Some(&signer)).unwrap();
+
+        // Data part first, then the signature part, then the closing boundary
+        let data = mime.find("Content-Disposition: version\r\n").unwrap();
+        let signature = mime.find("Content-Disposition: signature\r\n").unwrap();
+        let closing = mime.find("--RibbitBoundary--\r\n").unwrap();
+        assert!(data < signature && signature < closing);
+        assert!(mime.contains("Content-Transfer-Encoding: base64\r\n"));
+
+        let unsigned = wrap_in_mime(bpsv, "version", None).unwrap();
+        assert!(!unsigned.contains("Content-Disposition: signature"));
+    }
 }
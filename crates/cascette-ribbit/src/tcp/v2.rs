@@ -30,9 +30,10 @@ pub fn handle_v2_command(command: &str, state: &AppState) -> Result<String, Prot
     let product = parts[2];
     let endpoint = parts[3];
 
+    let database = state.database();
+
     // Get build for product
-    let build = state
-        .database()
+    let build = database
         .latest_build(product)
         .ok_or_else(|| ProtocolError::InvalidCommand(format!("Product not found: {product}")))?;
 
@@ -76,6 +77,15 @@ mod tests {
             cdn_path: "test/path".to_string(),
             tls_cert: None,
             tls_key: None,
+            rate_limit: 0.0,
+            rate_limit_burst: 1,
+            tcp_idle_timeout_secs: 10,
+            tcp_rate_limit: 0.0,
+            tcp_rate_limit_burst: 1,
+            tcp_rate_limit_exempt: Vec::new(),
+            signing: None,
+            admin_token: None,
+            disable_metrics: false,
         };
 
         Arc::new(AppState::new(&config).unwrap())
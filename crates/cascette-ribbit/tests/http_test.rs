@@ -67,6 +67,15 @@ async fn start_test_server() -> (SocketAddr, Arc<AppState>) {
         cdn_path: "test/path".to_string(),
         tls_cert: None,
         tls_key: None,
+        rate_limit: 0.0,
+        rate_limit_burst: 1,
+        tcp_idle_timeout_secs: 10,
+        tcp_rate_limit: 0.0,
+        tcp_rate_limit_burst: 1,
+        tcp_rate_limit_exempt: Vec::new(),
+        signing: None,
+        admin_token: None,
+        disable_metrics: false,
     };
 
     let state = Arc::new(AppState::new(&config).expect("Failed to initialize AppState"));
@@ -282,3 +291,55 @@ async fn test_http_bgdl_endpoint() {
     assert!(body.contains("sg|"));
     assert!(body.contains("xx|"));
 }
+
+#[tokio::test]
+async fn test_http_etag_revalidation() {
+    let (addr, _state) = start_test_server().await;
+    let client = reqwest::Client::new();
+
+    for endpoint in ["versions", "cdns", "bgdl"] {
+        let url = format!("http://{addr}/wow/{endpoint}");
+        let first = client
+            .get(&url)
+            .send()
+            .await
+            .expect("Failed to send first request");
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = first
+            .headers()
+            .get("etag")
+            .expect("Response should have an ETag")
+            .to_str()
+            .expect("ETag should be valid UTF-8")
+            .to_string();
+        let body = first.text().await.expect("Failed to read response body");
+        assert_eq!(etag.len(), 66, "{endpoint}: {etag}");
+
+        // The body is served from the cache, unchanged
+        let again = client
+            .get(&url)
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert_eq!(again.headers()["etag"], etag.as_str());
+        assert_eq!(again.text().await.unwrap(), body);
+
+        let revalidated = client
+            .get(&url)
+            .header("If-None-Match", &etag)
+            .send()
+            .await
+            .expect("Failed to send conditional request");
+        assert_eq!(revalidated.status(), StatusCode::NOT_MODIFIED, "{endpoint}");
+        assert_eq!(revalidated.headers()["etag"], etag.as_str());
+        assert!(revalidated.text().await.unwrap().is_empty());
+
+        let stale = client
+            .get(&url)
+            .header("If-None-Match", "\"stale\"")
+            .send()
+            .await
+            .expect("Failed to send conditional request");
+        assert_eq!(stale.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,234 @@
+//! Integration tests for the `/metrics` endpoint.
+//!
+//! These tests start a real server, send TCP and HTTP requests, and check
+//! the counters exposed in the Prometheus text format.
+
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::expect_used)]
+
+use axum::http::StatusCode;
+use cascette_ribbit::metrics::Protocol;
+use cascette_ribbit::{Server, ServerConfig};
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Create test database file.
+fn create_test_db() -> NamedTempFile {
+    let mut file = NamedTempFile::new().expect("Failed to create temporary test database file");
+    let json = r#"[{
+        "id": 1,
+        "product": "wow",
+        "version": "1.14.2.42597",
+        "build": "42597",
+        "build_config": "0123456789abcdef0123456789abcdef",
+        "cdn_config": "fedcba9876543210fedcba9876543210",
+        "keyring": null,
+        "product_config": null,
+        "build_time": "2024-01-01T00:00:00+00:00",
+        "encoding_ekey": "aaaabbbbccccddddeeeeffffaaaaffff",
+        "root_ekey": "bbbbccccddddeeeeffffaaaabbbbcccc",
+        "install_ekey": "ccccddddeeeeffffaaaabbbbccccdddd",
+        "download_ekey": "ddddeeeeffffaaaabbbbccccddddeeee"
+    }]"#;
+    file.write_all(json.as_bytes())
+        .expect("Failed to write test JSON data to temporary file");
+    file
+}
+
+/// Start a server on random ports.
+async fn start_test_server(db_file: &NamedTempFile, disable_metrics: bool) -> Server {
+    // Install ring crypto provider for reqwest (idempotent)
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let config = ServerConfig {
+        http_bind: "127.0.0.1:0"
+            .parse()
+            .expect("Failed to parse HTTP bind address"),
+        tcp_bind: "127.0.0.1:0"
+            .parse()
+            .expect("Failed to parse TCP bind address"),
+        builds: db_file.path().to_path_buf(),
+        cdn_hosts: "cdn.test.com".to_string(),
+        cdn_path: "test/path".to_string(),
+        tls_cert: None,
+        tls_key: None,
+        rate_limit: 0.0,
+        rate_limit_burst: 1,
+        tcp_idle_timeout_secs: 0,
+        tcp_rate_limit: 0.0,
+        tcp_rate_limit_burst: 1,
+        tcp_rate_limit_exempt: Vec::new(),
+        signing: None,
+        admin_token: None,
+        disable_metrics,
+    };
+
+    let mut server = Server::new(config).expect("Failed to create server");
+    server.start().await.expect("Failed to start server");
+    server
+}
+
+/// Send `command` on its own connection and read the whole response.
+async fn tcp_request(addr: SocketAddr, command: &str) -> String {
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .expect("Failed to connect to TCP server");
+    stream
+        .write_all(format!("{command}\r\n").as_bytes())
+        .await
+        .expect("Failed to send command");
+
+    let mut response = String::new();
+    tokio::time::timeout(Duration::from_secs(5), stream.read_to_string(&mut response))
+        .await
+        .expect("Connection should close before the timeout")
+        .expect("Failed to read response");
+    response
+}
+
+/// Parse the text exposition format line by line into a map from series
+/// (name and labels, as written) to value.
+///
+/// Fails the test on any line that is neither a comment nor a sample, on
+/// samples without a preceding `TYPE` line, and on repeated series. All of
+/// the server's values are integers.
+fn parse_exposition(text: &str) -> HashMap<String, u64> {
+    let mut typed = Vec::new();
+    let mut samples = HashMap::new();
+
+    for line in text.lines() {
+        if let Some(comment) = line.strip_prefix("# ") {
+            let mut words = comment.splitn(3, ' ');
+            let kind = words.next().unwrap();
+            let name = words.next().expect("Comment should name a metric");
+            assert!(words.next().is_some(), "Comment without text: {line}");
+            if kind == "TYPE" {
+                typed.push(name.to_string());
+            } else {
+                assert_eq!(kind, "HELP", "Unknown comment: {line}");
+            }
+            continue;
+        }
+
+        let (series, value) = line.rsplit_once(' ').expect("Sample should have a value");
+        let value: u64 = value.parse().expect("Sample value should be an integer");
+
+        let name = series.split('{').next().unwrap();
+        assert!(
+            name.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':'),
+            "Invalid metric name: {line}"
+        );
+        let labels = &series[name.len()..];
+        if !labels.is_empty() {
+            let labels = labels
+                .strip_prefix('{')
+                .and_then(|labels| labels.strip_suffix('}'))
+                .expect("Labels should be braced");
+            for label in labels.split(',') {
+                let (key, value) = label.split_once('=').expect("Label should be key=value");
+                assert!(!key.is_empty(), "Empty label name: {line}");
+                assert!(
+                    value.len() >= 2 && value.starts_with('"') && value.ends_with('"'),
+                    "Label value should be quoted: {line}"
+                );
+            }
+        }
+        assert!(
+            typed.iter().any(|typed| name.starts_with(typed.as_str())),
+            "Sample before its TYPE line: {line}"
+        );
+        assert!(
+            samples.insert(series.to_string(), value).is_none(),
+            "Repeated series: {line}"
+        );
+    }
+    samples
+}
+
+async fn fetch_metrics(http_addr: SocketAddr) -> HashMap<String, u64> {
+    let response = reqwest::get(format!("http://{http_addr}/metrics"))
+        .await
+        .expect("Failed to request metrics");
+    assert_eq!(response.status(), StatusCode::OK);
+    let text = response.text().await.expect("Failed to read metrics");
+    parse_exposition(&text)
+}
+
+#[tokio::test]
+async fn test_counters_increment_for_tcp_and_http_requests() {
+    let db_file = create_test_db();
+    let server = start_test_server(&db_file, false).await;
+    let tcp_addr = server.tcp_addr().unwrap();
+    let http_addr = server.http_addr().unwrap();
+
+    let v1 = tcp_request(tcp_addr, "v1/products/wow/versions").await;
+    assert!(v1.contains("MIME-Version"));
+    tcp_request(tcp_addr, "v2/products/wow/cdns").await;
+    tcp_request(tcp_addr, "v2/products/wow/cdns").await;
+    // Unknown products fail without adding a series for their name
+    tcp_request(tcp_addr, "v2/products/nonexistent/versions").await;
+
+    let client = reqwest::Client::new();
+    for path in ["wow/versions", "wow/bgdl", "nonexistent/versions"] {
+        client
+            .get(format!("http://{http_addr}/{path}"))
+            .send()
+            .await
+            .expect("Failed to send GET request");
+    }
+
+    let samples = fetch_metrics(http_addr).await;
+    let requests = |labels: &str| samples[&format!("ribbit_requests_total{{{labels}}}")];
+    let errors = |labels: &str| samples[&format!("ribbit_request_errors_total{{{labels}}}")];
+
+    let v1_versions = r#"protocol="tcp_v1",endpoint="versions",product="wow""#;
+    assert_eq!(requests(v1_versions), 1);
+    let v2_cdns = r#"protocol="tcp_v2",endpoint="cdns",product="wow""#;
+    assert_eq!(requests(v2_cdns), 2);
+    assert_eq!(errors(v2_cdns), 0);
+    let v2_unknown = r#"protocol="tcp_v2",endpoint="versions",product="unknown""#;
+    assert_eq!(errors(v2_unknown), 1);
+
+    let http_versions = r#"protocol="http",endpoint="versions",product="wow""#;
+    assert_eq!(requests(http_versions), 1);
+    let http_unknown = r#"protocol="http",endpoint="versions",product="unknown""#;
+    assert_eq!(errors(http_unknown), 1);
+    assert!(!samples.keys().any(|series| series.contains("nonexistent")));
+
+    // The v1 response size is counted in full
+    let size = samples[&format!("ribbit_response_bytes_sum{{{v1_versions}}}")];
+    assert_eq!(size, v1.len() as u64);
+    let count = samples[&format!("ribbit_response_bytes_bucket{{{v1_versions},le=\"+Inf\"}}")];
+    assert_eq!(count, 1);
+    assert_eq!(samples["ribbit_builds"], 1);
+
+    // The same counters are readable in-process
+    let metrics = server.state().metrics().expect("Metrics should be enabled");
+    assert_eq!(metrics.requests(Protocol::TcpV2, "cdns", "wow"), 2);
+    assert_eq!(metrics.requests(Protocol::Http, "bgdl", "wow"), 1);
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint_can_be_disabled() {
+    let db_file = create_test_db();
+    let server = start_test_server(&db_file, true).await;
+    let http_addr = server.http_addr().unwrap();
+
+    assert!(server.state().metrics().is_none());
+    let response = reqwest::get(format!("http://{http_addr}/metrics"))
+        .await
+        .expect("Failed to request metrics");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Other endpoints are unaffected
+    let response = reqwest::get(format!("http://{http_addr}/wow/versions"))
+        .await
+        .expect("Failed to send GET request");
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,219 @@
+//! Integration tests for per-IP HTTP and TCP rate limiting.
+//!
+//! These tests start a real server with a small token bucket and send
+//! requests, or open TCP connections, faster than it refills.
+
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::expect_used)]
+
+use cascette_ribbit::{Server, ServerConfig};
+use reqwest::StatusCode;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Create test database file.
+fn create_test_db() -> NamedTempFile {
+    let mut file = NamedTempFile::new().expect("Failed to create temporary test database file");
+    let json = r#"[{
+        "id": 1,
+        "product": "wow",
+        "version": "1.14.2.42597",
+        "build": "42597",
+        "build_config": "0123456789abcdef0123456789abcdef",
+        "cdn_config": "fedcba9876543210fedcba9876543210",
+        "keyring": null,
+        "product_config": null,
+        "build_time": "2024-01-01T00:00:00+00:00",
+        "encoding_ekey": "aaaabbbbccccddddeeeeffffaaaaffff",
+        "root_ekey": "bbbbccccddddeeeeffffaaaabbbbcccc",
+        "install_ekey": "ccccddddeeeeffffaaaabbbbccccdddd",
+        "download_ekey": "ddddeeeeffffaaaabbbbccccddddeeee"
+    }]"#;
+    file.write_all(json.as_bytes())
+        .expect("Failed to write test JSON data to temporary file");
+    file
+}
+
+/// Start a server on random ports with the given rate limit.
+async fn start_test_server(db_file: &NamedTempFile, rate: f64, burst: u32) -> (Server, SocketAddr) {
+    // Install ring crypto provider for reqwest (idempotent)
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let config = ServerConfig {
+        http_bind: "127.0.0.1:0"
+            .parse()
+            .expect("Failed to parse HTTP bind address"),
+        tcp_bind: "127.0.0.1:0"
+            .parse()
+            .expect("Failed to parse TCP bind address"),
+        builds: db_file.path().to_path_buf(),
+        cdn_hosts: "cdn.test.com".to_string(),
+        cdn_path: "test/path".to_string(),
+        tls_cert: None,
+        tls_key: None,
+        rate_limit: rate,
+        rate_limit_burst: burst,
+        tcp_idle_timeout_secs: 10,
+        tcp_rate_limit: 0.0,
+        tcp_rate_limit_burst: 1,
+        tcp_rate_limit_exempt: Vec::new(),
+        signing: None,
+        admin_token: None,
+        disable_metrics: false,
+    };
+
+    let mut server = Server::new(config).expect("Failed to create server");
+    server.start().await.expect("Failed to start server");
+    let addr = server.http_addr().expect("HTTP listener should be bound");
+    (server, addr)
+}
+
+#[tokio::test]
+async fn test_requests_over_limit_get_429() {
+    let db_file = create_test_db();
+    let (_server, addr) = start_test_server(&db_file, 1.0, 3).await;
+    let client = reqwest::Client::new();
+
+    let mut statuses = Vec::new();
+    for path in ["versions", "cdns", "bgdl", "versions", "cdns"] {
+        let response = client
+            .get(format!("http://{addr}/wow/{path}"))
+            .send()
+            .await
+            .expect("Request should complete");
+        statuses.push(response.status());
+
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after: u64 = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .expect("429 should carry Retry-After")
+                .to_str()
+                .unwrap()
+                .parse()
+                .expect("Retry-After should be whole seconds");
+            assert_eq!(retry_after, 1);
+        }
+    }
+
+    // The burst is served, then every route is limited
+    assert_eq!(&statuses[..3], &[StatusCode::OK; 3]);
+    assert_eq!(&statuses[3..], &[StatusCode::TOO_MANY_REQUESTS; 2]);
+}
+
+#[tokio::test]
+async fn test_limit_recovers_after_refill() {
+    let db_file = create_test_db();
+    let (_server, addr) = start_test_server(&db_file, 20.0, 1).await;
+    let client = reqwest::Client::new();
+    let url = format!("http://{addr}/wow/versions");
+
+    let first = client.get(&url).send().await.unwrap();
+    assert_eq!(first.status(), StatusCode::OK);
+    let second = client.get(&url).send().await.unwrap();
+    assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // One token refills every 50ms
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let third = client.get(&url).send().await.unwrap();
+    assert_eq!(third.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_zero_rate_disables_limiting() {
+    let db_file = create_test_db();
+    let (_server, addr) = start_test_server(&db_file, 0.0, 1).await;
+    let client = reqwest::Client::new();
+
+    for _ in 0..10 {
+        let response = client
+            .get(format!("http://{addr}/wow/versions"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
+
+/// Start a server on random ports with the given TCP rate limit.
+async fn start_tcp_test_server(
+    db_file: &NamedTempFile,
+    rate: f64,
+    burst: u32,
+    exempt: &[&str],
+) -> (Server, SocketAddr) {
+    let config = ServerConfig {
+        http_bind: "127.0.0.1:0".parse().unwrap(),
+        tcp_bind: "127.0.0.1:0".parse().unwrap(),
+        builds: db_file.path().to_path_buf(),
+        cdn_hosts: "cdn.test.com".to_string(),
+        cdn_path: "test/path".to_string(),
+        tls_cert: None,
+        tls_key: None,
+        rate_limit: 0.0,
+        rate_limit_burst: 1,
+        tcp_idle_timeout_secs: 0,
+        tcp_rate_limit: rate,
+        tcp_rate_limit_burst: burst,
+        tcp_rate_limit_exempt: exempt.iter().map(|net| net.parse().unwrap()).collect(),
+        signing: None,
+        admin_token: None,
+        disable_metrics: false,
+    };
+
+    let mut server = Server::new(config).expect("Failed to create server");
+    server.start().await.expect("Failed to start server");
+    let addr = server.tcp_addr().expect("TCP listener should be bound");
+    (server, addr)
+}
+
+/// Send one v2 command on a new connection and read until it closes.
+async fn tcp_request(addr: SocketAddr) -> String {
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .expect("Failed to connect to TCP server");
+    // A rejected connection may already be closed for writing
+    let _ = stream.write_all(b"v2/products/wow/versions\n").await;
+    let mut response = String::new();
+    tokio::time::timeout(Duration::from_secs(5), stream.read_to_string(&mut response))
+        .await
+        .expect("Response should arrive before the timeout")
+        .expect("Failed to read response");
+    response
+}
+
+#[tokio::test]
+async fn test_tcp_connections_over_limit_are_rejected() {
+    let db_file = create_test_db();
+    let (server, addr) = start_tcp_test_server(&db_file, 1.0, 2, &[]).await;
+
+    let mut responses = Vec::new();
+    for _ in 0..4 {
+        responses.push(tcp_request(addr).await);
+    }
+
+    assert!(responses[..2].iter().all(|r| r.contains("1.14.2.42597")));
+    assert!(responses[2..].iter().all(|r| r == "Too Many Requests\r\n"));
+
+    let limiter = server
+        .state()
+        .tcp_rate_limiter()
+        .expect("TCP rate limiting should be enabled");
+    assert_eq!(limiter.dropped_connections(), 2);
+}
+
+#[tokio::test]
+async fn test_tcp_exempt_networks_are_not_limited() {
+    let db_file = create_test_db();
+    let (server, addr) = start_tcp_test_server(&db_file, 1.0, 1, &["127.0.0.0/8"]).await;
+
+    for _ in 0..5 {
+        assert!(tcp_request(addr).await.contains("1.14.2.42597"));
+    }
+    let limiter = server.state().tcp_rate_limiter().unwrap();
+    assert_eq!(limiter.dropped_connections(), 0);
+}
@@ -0,0 +1,246 @@
+//! Integration tests for reloading the build database while serving.
+//!
+//! These tests start a real server, rewrite its database file and trigger a
+//! reload through `POST /admin/reload`. `SIGHUP` is covered in
+//! `sighup_reload_test.rs`, since the signal reaches every server in the
+//! test process.
+
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::expect_used)]
+
+use axum::http::StatusCode;
+use cascette_ribbit::{Server, ServerConfig};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+
+const ADMIN_TOKEN: &str = "reload-secret";
+
+/// JSON database with one `wow` build per version.
+fn database_json(versions: &[&str]) -> String {
+    let builds: Vec<String> = versions
+        .iter()
+        .enumerate()
+        .map(|(id, version)| {
+            format!(
+                r#"{{
+                    "id": {id},
+                    "product": "wow",
+                    "version": "{version}",
+                    "build": "{id}",
+                    "build_config": "0123456789abcdef0123456789abcdef",
+                    "cdn_config": "fedcba9876543210fedcba9876543210",
+                    "keyring": null,
+                    "product_config": null,
+                    "build_time": "2024-01-0{day}T00:00:00+00:00",
+                    "encoding_ekey": "aaaabbbbccccddddeeeeffffaaaaffff",
+                    "root_ekey": "bbbbccccddddeeeeffffaaaabbbbcccc",
+                    "install_ekey": "ccccddddeeeeffffaaaabbbbccccdddd",
+                    "download_ekey": "ddddeeeeffffaaaabbbbccccddddeeee"
+                }}"#,
+                day = id + 1
+            )
+        })
+        .collect();
+    format!("[{}]", builds.join(","))
+}
+
+fn write_database(path: &Path, contents: &str) {
+    std::fs::write(path, contents).expect("Failed to write test database");
+}
+
+/// Start a server on random ports with the admin endpoint enabled.
+async fn start_test_server(db_file: &NamedTempFile) -> Server {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let config = ServerConfig {
+        http_bind: "127.0.0.1:0"
+            .parse()
+            .expect("Failed to parse HTTP bind address"),
+        tcp_bind: "127.0.0.1:0"
+            .parse()
+            .expect("Failed to parse TCP bind address"),
+        builds: db_file.path().to_path_buf(),
+        cdn_hosts: "cdn.test.com".to_string(),
+        cdn_path: "test/path".to_string(),
+        tls_cert: None,
+        tls_key: None,
+        rate_limit: 0.0,
+        rate_limit_burst: 1,
+        tcp_idle_timeout_secs: 10,
+        tcp_rate_limit: 0.0,
+        tcp_rate_limit_burst: 1,
+        tcp_rate_limit_exempt: Vec::new(),
+        signing: None,
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        disable_metrics: false,
+    };
+
+    let mut server = Server::new(config).expect("Failed to create server");
+    server.start().await.expect("Failed to start server");
+    server
+}
+
+async fn versions(client: &reqwest::Client, addr: SocketAddr) -> String {
+    client
+        .get(format!("http://{addr}/wow/versions"))
+        .send()
+        .await
+        .expect("Failed to send versions request")
+        .text()
+        .await
+        .expect("Failed to read versions response")
+}
+
+async fn reload(
+    client: &reqwest::Client,
+    addr: SocketAddr,
+    token: Option<&str>,
+) -> reqwest::Response {
+    let request = client.post(format!("http://{addr}/admin/reload"));
+    let request = match token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    };
+    request.send().await.expect("Failed to send reload request")
+}
+
+#[tokio::test]
+async fn test_admin_reload_swaps_database() {
+    let db_file = NamedTempFile::new().expect("Failed to create temporary test database file");
+    write_database(db_file.path(), &database_json(&["1.0.0.1"]));
+    let mut server = start_test_server(&db_file).await;
+    let addr = server.http_addr().expect("HTTP listener should be bound");
+    let client = reqwest::Client::new();
+
+    assert!(versions(&client, addr).await.contains("1.0.0.1"));
+    write_database(db_file.path(), &database_json(&["1.0.0.1", "1.0.0.2"]));
+
+    // Without the right token nothing is reloaded
+    let response = reload(&client, addr, None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = reload(&client, addr, Some("wrong")).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(versions(&client, addr).await.contains("1.0.0.1"));
+
+    let response = reload(&client, addr, Some(ADMIN_TOKEN)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.text().await.unwrap(),
+        "Reloaded 2 builds for 1 products\n"
+    );
+    assert!(versions(&client, addr).await.contains("1.0.0.2"));
+
+    server
+        .graceful_shutdown(Duration::from_secs(5))
+        .await
+        .expect("Shutdown should complete");
+}
+
+#[tokio::test]
+async fn test_reload_invalidates_etag() {
+    let db_file = NamedTempFile::new().expect("Failed to create temporary test database file");
+    write_database(db_file.path(), &database_json(&["1.0.0.1"]));
+    let mut server = start_test_server(&db_file).await;
+    let addr = server.http_addr().expect("HTTP listener should be bound");
+    let client = reqwest::Client::new();
+    let url = format!("http://{addr}/wow/versions");
+
+    let etag = |response: &reqwest::Response| {
+        response.headers()["etag"]
+            .to_str()
+            .expect("ETag should be valid UTF-8")
+            .to_string()
+    };
+    let before = etag(&client.get(&url).send().await.unwrap());
+
+    write_database(db_file.path(), &database_json(&["1.0.0.1", "1.0.0.2"]));
+    let response = reload(&client, addr, Some(ADMIN_TOKEN)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The old ETag no longer matches, so the new database is served
+    let response = client
+        .get(&url)
+        .header("If-None-Match", &before)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let after = etag(&response);
+    assert_ne!(before, after);
+    assert!(response.text().await.unwrap().contains("1.0.0.2"));
+
+    let response = client
+        .get(&url)
+        .header("If-None-Match", &after)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    server
+        .graceful_shutdown(Duration::from_secs(5))
+        .await
+        .expect("Shutdown should complete");
+}
+
+#[tokio::test]
+async fn test_failed_reload_keeps_previous_database() {
+    let db_file = NamedTempFile::new().expect("Failed to create temporary test database file");
+    write_database(db_file.path(), &database_json(&["1.0.0.1"]));
+    let mut server = start_test_server(&db_file).await;
+    let addr = server.http_addr().expect("HTTP listener should be bound");
+    let client = reqwest::Client::new();
+
+    for broken in ["[{", "[]"] {
+        write_database(db_file.path(), broken);
+        let response = reload(&client, addr, Some(ADMIN_TOKEN)).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body = versions(&client, addr).await;
+        assert!(body.contains("1.0.0.1"), "{broken}: {body}");
+    }
+
+    server
+        .graceful_shutdown(Duration::from_secs(5))
+        .await
+        .expect("Shutdown should complete");
+}
+
+#[tokio::test]
+async fn test_admin_endpoint_disabled_without_token() {
+    let db_file = NamedTempFile::new().expect("Failed to create temporary test database file");
+    write_database(db_file.path(), &database_json(&["1.0.0.1"]));
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let config = ServerConfig {
+        http_bind: "127.0.0.1:0".parse().unwrap(),
+        tcp_bind: "127.0.0.1:0".parse().unwrap(),
+        builds: db_file.path().to_path_buf(),
+        cdn_hosts: "cdn.test.com".to_string(),
+        cdn_path: "test/path".to_string(),
+        tls_cert: None,
+        tls_key: None,
+        rate_limit: 0.0,
+        rate_limit_burst: 1,
+        tcp_idle_timeout_secs: 10,
+        tcp_rate_limit: 0.0,
+        tcp_rate_limit_burst: 1,
+        tcp_rate_limit_exempt: Vec::new(),
+        signing: None,
+        admin_token: None,
+        disable_metrics: false,
+    };
+    let mut server = Server::new(config).expect("Failed to create server");
+    server.start().await.expect("Failed to start server");
+    let addr = server.http_addr().expect("HTTP listener should be bound");
+
+    let response = reload(&reqwest::Client::new(), addr, Some(ADMIN_TOKEN)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    server
+        .graceful_shutdown(Duration::from_secs(5))
+        .await
+        .expect("Shutdown should complete");
+}
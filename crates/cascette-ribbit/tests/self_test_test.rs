@@ -0,0 +1,243 @@
+//! Integration tests for the server self-test.
+//!
+//! Each failure case puts a fake listener in front of one surface that
+//! corrupts its responses, and checks that the self-test blames that surface.
+
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::expect_used)]
+
+use cascette_ribbit::self_test::{self, Surface};
+use cascette_ribbit::tcp::handlers::handle_command;
+use cascette_ribbit::{AppState, Server, ServerConfig};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tempfile::NamedTempFile;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+/// Create test database file.
+fn create_test_db() -> NamedTempFile {
+    let mut file = NamedTempFile::new().expect("Failed to create temporary test database file");
+    let json = r#"[{
+        "id": 1,
+        "product": "wow",
+        "version": "1.14.2.42597",
+        "build": "42597",
+        "build_config": "0123456789abcdef0123456789abcdef",
+        "cdn_config": "fedcba9876543210fedcba9876543210",
+        "keyring": null,
+        "product_config": null,
+        "build_time": "2024-01-01T00:00:00+00:00",
+        "encoding_ekey": "aaaabbbbccccddddeeeeffffaaaaffff",
+        "root_ekey": "bbbbccccddddeeeeffffaaaabbbbcccc",
+        "install_ekey": "ccccddddeeeeffffaaaabbbbccccdddd",
+        "download_ekey": "ddddeeeeffffaaaabbbbccccddddeeee"
+    }]"#;
+    file.write_all(json.as_bytes())
+        .expect("Failed to write test JSON data to temporary file");
+    file
+}
+
+fn test_config(db_file: &NamedTempFile) -> ServerConfig {
+    ServerConfig {
+        http_bind: "127.0.0.1:0"
+            .parse()
+            .expect("Failed to parse HTTP bind address"),
+        tcp_bind: "127.0.0.1:0"
+            .parse()
+            .expect("Failed to parse TCP bind address"),
+        builds: db_file.path().to_path_buf(),
+        cdn_hosts: "cdn.test.com".to_string(),
+        cdn_path: "test/path".to_string(),
+        tls_cert: None,
+        tls_key: None,
+        rate_limit: 0.0,
+        rate_limit_burst: 1,
+        tcp_idle_timeout_secs: 10,
+        tcp_rate_limit: 0.0,
+        tcp_rate_limit_burst: 1,
+        tcp_rate_limit_exempt: Vec::new(),
+        signing: None,
+        admin_token: None,
+        disable_metrics: false,
+    }
+}
+
+/// Start a server on random ports.
+async fn start_test_server(db_file: &NamedTempFile) -> Server {
+    let mut server = Server::new(test_config(db_file)).expect("Failed to create server");
+    server.start().await.expect("Failed to start server");
+    server
+}
+
+/// Serve TCP commands like the real server, passing each response through
+/// `tamper` first.
+async fn fake_tcp(db_file: &NamedTempFile, tamper: fn(&str, String) -> String) -> SocketAddr {
+    let state = Arc::new(AppState::new(&test_config(db_file)).expect("Failed to create app state"));
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind fake TCP server");
+    let addr = listener
+        .local_addr()
+        .expect("Fake TCP server has no address");
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let state = state.clone();
+            tokio::spawn(async move {
+                let mut stream = BufReader::new(stream);
+                let mut command = String::new();
+                stream.read_line(&mut command).await.unwrap();
+                let command = command.trim();
+                let response = handle_command(command, &state).unwrap();
+                let response = tamper(command, response);
+                stream
+                    .get_mut()
+                    .write_all(response.as_bytes())
+                    .await
+                    .unwrap();
+            });
+        }
+    });
+    addr
+}
+
+/// Answer every HTTP request with a 404.
+async fn fake_http_not_found() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind fake HTTP server");
+    let addr = listener
+        .local_addr()
+        .expect("Fake HTTP server has no address");
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await.unwrap();
+            stream
+                .write_all(
+                    b"HTTP/1.1 404 Not Found\r\nContent-Type: text/plain\r\n\
+                      Content-Length: 9\r\nConnection: close\r\n\r\nNot Found",
+                )
+                .await
+                .unwrap();
+        }
+    });
+    addr
+}
+
+fn failed_surfaces(report: &self_test::SelfTestReport) -> Vec<Surface> {
+    report
+        .surfaces
+        .iter()
+        .filter(|surface| !surface.passed())
+        .map(|surface| surface.surface)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_self_test_passes_on_real_server() {
+    let db_file = create_test_db();
+    let mut server = start_test_server(&db_file).await;
+
+    let report = server.self_test().await.expect("Self-test should run");
+    assert!(report.passed(), "{report}");
+    assert_eq!(report.product, "wow");
+    assert_eq!(report.surfaces.len(), 3);
+    assert!(report.to_string().ends_with("Result: PASS"));
+}
+
+#[tokio::test]
+async fn test_self_test_detects_bad_v1_checksum() {
+    let db_file = create_test_db();
+    let server = start_test_server(&db_file).await;
+    let tcp_addr = fake_tcp(&db_file, |command, response| {
+        if command.starts_with("v1/") {
+            // Flip the last checksum digit
+            let trimmed = response.trim_end();
+            let last = if trimmed.ends_with('0') { "1" } else { "0" };
+            format!("{}{last}\r\n", &trimmed[..trimmed.len() - 1])
+        } else {
+            response
+        }
+    })
+    .await;
+
+    let report = self_test::run(tcp_addr, server.http_addr().unwrap(), "wow").await;
+    assert_eq!(failed_surfaces(&report), [Surface::TcpV1]);
+    let v1 = report.surface(Surface::TcpV1).unwrap();
+    assert!(
+        v1.checks
+            .iter()
+            .all(|check| check.error.as_ref().unwrap().contains("checksum mismatch"))
+    );
+    assert!(report.to_string().contains("TCP v1: FAIL"));
+}
+
+#[tokio::test]
+async fn test_self_test_detects_invalid_v2_bpsv() {
+    let db_file = create_test_db();
+    let server = start_test_server(&db_file).await;
+    let tcp_addr = fake_tcp(&db_file, |command, response| {
+        if command.starts_with("v2/") {
+            "Region|BuildConfig\nus\n".to_string()
+        } else {
+            response
+        }
+    })
+    .await;
+
+    let report = self_test::run(tcp_addr, server.http_addr().unwrap(), "wow").await;
+    assert!(!report.passed());
+    assert_eq!(failed_surfaces(&report), [Surface::TcpV2]);
+}
+
+#[tokio::test]
+async fn test_self_test_detects_http_errors() {
+    let db_file = create_test_db();
+    let server = start_test_server(&db_file).await;
+    let http_addr = fake_http_not_found().await;
+
+    let report = self_test::run(server.tcp_addr().unwrap(), http_addr, "wow").await;
+    assert_eq!(failed_surfaces(&report), [Surface::Http]);
+    let http = report.surface(Surface::Http).unwrap();
+    assert!(
+        http.checks[0]
+            .error
+            .as_ref()
+            .unwrap()
+            .contains("unexpected status: HTTP/1.1 404")
+    );
+}
+
+#[tokio::test]
+async fn test_self_test_detects_inconsistent_surfaces() {
+    let db_file = create_test_db();
+    let server = start_test_server(&db_file).await;
+    let tcp_addr = fake_tcp(&db_file, |command, response| {
+        if command == "v2/products/wow/versions" {
+            response.replace(
+                "0123456789abcdef0123456789abcdef",
+                "00000000000000000000000000000000",
+            )
+        } else {
+            response
+        }
+    })
+    .await;
+
+    let report = self_test::run(tcp_addr, server.http_addr().unwrap(), "wow").await;
+    assert!(failed_surfaces(&report).is_empty());
+    assert!(!report.passed());
+
+    let failed: Vec<_> = report
+        .consistency
+        .iter()
+        .filter(|check| !check.passed())
+        .map(|check| check.name.as_str())
+        .collect();
+    assert_eq!(failed, ["versions"]);
+    assert!(report.to_string().contains("Consistency: FAIL"));
+}
@@ -0,0 +1,157 @@
+//! Integration tests for graceful server shutdown.
+//!
+//! These tests start a real server, begin requests, and shut it down while
+//! the requests are in flight.
+
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::expect_used)]
+
+use cascette_ribbit::{Server, ServerConfig};
+use std::io::Write;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Create test database file.
+fn create_test_db() -> NamedTempFile {
+    let mut file = NamedTempFile::new().expect("Failed to create temporary test database file");
+    let json = r#"[{
+        "id": 1,
+        "product": "wow",
+        "version": "1.14.2.42597",
+        "build": "42597",
+        "build_config": "0123456789abcdef0123456789abcdef",
+        "cdn_config": "fedcba9876543210fedcba9876543210",
+        "keyring": null,
+        "product_config": null,
+        "build_time": "2024-01-01T00:00:00+00:00",
+        "encoding_ekey": "aaaabbbbccccddddeeeeffffaaaaffff",
+        "root_ekey": "bbbbccccddddeeeeffffaaaabbbbcccc",
+        "install_ekey": "ccccddddeeeeffffaaaabbbbccccdddd",
+        "download_ekey": "ddddeeeeffffaaaabbbbccccddddeeee"
+    }]"#;
+    file.write_all(json.as_bytes())
+        .expect("Failed to write test JSON data to temporary file");
+    file
+}
+
+/// Start a server on random ports.
+async fn start_test_server(db_file: &NamedTempFile) -> Server {
+    let config = ServerConfig {
+        http_bind: "127.0.0.1:0"
+            .parse()
+            .expect("Failed to parse HTTP bind address"),
+        tcp_bind: "127.0.0.1:0"
+            .parse()
+            .expect("Failed to parse TCP bind address"),
+        builds: db_file.path().to_path_buf(),
+        cdn_hosts: "cdn.test.com".to_string(),
+        cdn_path: "test/path".to_string(),
+        tls_cert: None,
+        tls_key: None,
+        rate_limit: 0.0,
+        rate_limit_burst: 1,
+        tcp_idle_timeout_secs: 10,
+        tcp_rate_limit: 0.0,
+        tcp_rate_limit_burst: 1,
+        tcp_rate_limit_exempt: Vec::new(),
+        signing: None,
+        admin_token: None,
+        disable_metrics: false,
+    };
+
+    let mut server = Server::new(config).expect("Failed to create server");
+    server.start().await.expect("Failed to start server");
+    server
+}
+
+async fn read_to_string(stream: &mut TcpStream) -> String {
+    let mut response = String::new();
+    tokio::time::timeout(Duration::from_secs(5), stream.read_to_string(&mut response))
+        .await
+        .expect("Response should arrive before the timeout")
+        .expect("Failed to read response");
+    response
+}
+
+#[tokio::test]
+async fn test_shutdown_drains_in_flight_requests() {
+    let db_file = create_test_db();
+    let mut server = start_test_server(&db_file).await;
+    let tcp_addr = server.tcp_addr().expect("TCP listener should be bound");
+    let http_addr = server.http_addr().expect("HTTP listener should be bound");
+
+    // Begin one request on each protocol without finishing it
+    let mut tcp = TcpStream::connect(tcp_addr)
+        .await
+        .expect("Failed to connect to TCP server");
+    tcp.write_all(b"v2/products/wow/")
+        .await
+        .expect("Failed to send partial command");
+    let mut http = TcpStream::connect(http_addr)
+        .await
+        .expect("Failed to connect to HTTP server");
+    http.write_all(b"GET /wow/versions HTTP/1.1\r\nHost: localhost\r\n")
+        .await
+        .expect("Failed to send partial request");
+
+    // An idle connection is closed by the shutdown
+    let mut idle = TcpStream::connect(tcp_addr)
+        .await
+        .expect("Failed to connect to TCP server");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let shutdown =
+        tokio::spawn(async move { server.graceful_shutdown(Duration::from_secs(5)).await });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // New connections are refused
+    assert!(TcpStream::connect(tcp_addr).await.is_err());
+    assert!(TcpStream::connect(http_addr).await.is_err());
+    assert_eq!(read_to_string(&mut idle).await, "");
+
+    // In-flight requests are still answered
+    tcp.write_all(b"versions\n")
+        .await
+        .expect("Failed to finish command");
+    let response = read_to_string(&mut tcp).await;
+    assert!(response.contains("Region!STRING"));
+    assert!(response.contains("1.14.2.42597"));
+
+    http.write_all(b"Connection: close\r\n\r\n")
+        .await
+        .expect("Failed to finish request");
+    let response = read_to_string(&mut http).await;
+    assert!(response.starts_with("HTTP/1.1 200"));
+    assert!(response.contains("1.14.2.42597"));
+
+    shutdown
+        .await
+        .expect("Shutdown task should not panic")
+        .expect("Shutdown should complete within the timeout");
+}
+
+#[tokio::test]
+async fn test_shutdown_times_out_on_stalled_request() {
+    let db_file = create_test_db();
+    let mut server = start_test_server(&db_file).await;
+    let tcp_addr = server.tcp_addr().expect("TCP listener should be bound");
+
+    // A client that starts a command and never finishes it
+    let mut stalled = TcpStream::connect(tcp_addr)
+        .await
+        .expect("Failed to connect to TCP server");
+    stalled
+        .write_all(b"v2/products/wow/")
+        .await
+        .expect("Failed to send partial command");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let result = server.graceful_shutdown(Duration::from_millis(200)).await;
+    assert!(matches!(
+        result,
+        Err(cascette_ribbit::ServerError::Shutdown(_))
+    ));
+    assert!(server.tcp_addr().is_none());
+}
@@ -0,0 +1,109 @@
+//! Integration test for reloading the build database on `SIGHUP`.
+//!
+//! Kept in its own test binary: the signal is delivered to the whole test
+//! process and would reload every server running in it.
+
+#![cfg(unix)]
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::expect_used)]
+
+use cascette_ribbit::{Server, ServerConfig};
+use std::time::Duration;
+use tempfile::NamedTempFile;
+
+/// JSON database with a single `wow` build.
+fn database_json(version: &str) -> String {
+    format!(
+        r#"[{{
+            "id": 1,
+            "product": "wow",
+            "version": "{version}",
+            "build": "1",
+            "build_config": "0123456789abcdef0123456789abcdef",
+            "cdn_config": "fedcba9876543210fedcba9876543210",
+            "keyring": null,
+            "product_config": null,
+            "build_time": "2024-01-01T00:00:00+00:00",
+            "encoding_ekey": "aaaabbbbccccddddeeeeffffaaaaffff",
+            "root_ekey": "bbbbccccddddeeeeffffaaaabbbbcccc",
+            "install_ekey": "ccccddddeeeeffffaaaabbbbccccdddd",
+            "download_ekey": "ddddeeeeffffaaaabbbbccccddddeeee"
+        }}]"#
+    )
+}
+
+#[tokio::test]
+async fn test_sighup_reloads_database() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let db_file = NamedTempFile::new().expect("Failed to create temporary test database file");
+    std::fs::write(db_file.path(), database_json("1.0.0.1")).expect("Failed to write database");
+
+    let config = ServerConfig {
+        http_bind: "127.0.0.1:0"
+            .parse()
+            .expect("Failed to parse HTTP bind address"),
+        tcp_bind: "127.0.0.1:0"
+            .parse()
+            .expect("Failed to parse TCP bind address"),
+        builds: db_file.path().to_path_buf(),
+        cdn_hosts: "cdn.test.com".to_string(),
+        cdn_path: "test/path".to_string(),
+        tls_cert: None,
+        tls_key: None,
+        rate_limit: 0.0,
+        rate_limit_burst: 1,
+        tcp_idle_timeout_secs: 10,
+        tcp_rate_limit: 0.0,
+        tcp_rate_limit_burst: 1,
+        tcp_rate_limit_exempt: Vec::new(),
+        signing: None,
+        admin_token: None,
+        disable_metrics: false,
+    };
+    let mut server = Server::new(config).expect("Failed to create server");
+    server.start().await.expect("Failed to start server");
+    let url = format!(
+        "http://{}/wow/versions",
+        server.http_addr().expect("HTTP listener should be bound")
+    );
+    let client = reqwest::Client::new();
+    let versions = || async {
+        client
+            .get(&url)
+            .send()
+            .await
+            .expect("Failed to send versions request")
+            .text()
+            .await
+            .expect("Failed to read versions response")
+    };
+
+    // A malformed database is rejected and the loaded one keeps serving
+    std::fs::write(db_file.path(), "[{").expect("Failed to write database");
+    // The server listens for SIGHUP once started
+    let hangup = || {
+        let status = std::process::Command::new("kill")
+            .args(["-HUP", &std::process::id().to_string()])
+            .status()
+            .expect("Failed to run kill");
+        assert!(status.success());
+    };
+    hangup();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(versions().await.contains("1.0.0.1"));
+
+    std::fs::write(db_file.path(), database_json("1.0.0.2")).expect("Failed to write database");
+    hangup();
+    let reloaded = tokio::time::timeout(Duration::from_secs(5), async {
+        while !versions().await.contains("1.0.0.2") {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await;
+    assert!(reloaded.is_ok(), "SIGHUP should reload the database");
+
+    server
+        .graceful_shutdown(Duration::from_secs(5))
+        .await
+        .expect("Shutdown should complete");
+}
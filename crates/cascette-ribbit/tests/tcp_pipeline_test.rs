@@ -0,0 +1,201 @@
+//! Integration tests for pipelined TCP requests.
+//!
+//! These tests start a real server and send several commands over one TCP
+//! connection.
+
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::expect_used)]
+
+use cascette_ribbit::{Server, ServerConfig};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tempfile::NamedTempFile;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Create test database file.
+fn create_test_db() -> NamedTempFile {
+    let mut file = NamedTempFile::new().expect("Failed to create temporary test database file");
+    let json = r#"[{
+        "id": 1,
+        "product": "wow",
+        "version": "1.14.2.42597",
+        "build": "42597",
+        "build_config": "0123456789abcdef0123456789abcdef",
+        "cdn_config": "fedcba9876543210fedcba9876543210",
+        "keyring": null,
+        "product_config": null,
+        "build_time": "2024-01-01T00:00:00+00:00",
+        "encoding_ekey": "aaaabbbbccccddddeeeeffffaaaaffff",
+        "root_ekey": "bbbbccccddddeeeeffffaaaabbbbcccc",
+        "install_ekey": "ccccddddeeeeffffaaaabbbbccccdddd",
+        "download_ekey": "ddddeeeeffffaaaabbbbccccddddeeee"
+    }]"#;
+    file.write_all(json.as_bytes())
+        .expect("Failed to write test JSON data to temporary file");
+    file
+}
+
+/// Start a server on random ports with the given TCP idle timeout.
+async fn start_test_server(
+    db_file: &NamedTempFile,
+    idle_timeout_secs: u64,
+) -> (Server, SocketAddr) {
+    let config = ServerConfig {
+        http_bind: "127.0.0.1:0"
+            .parse()
+            .expect("Failed to parse HTTP bind address"),
+        tcp_bind: "127.0.0.1:0"
+            .parse()
+            .expect("Failed to parse TCP bind address"),
+        builds: db_file.path().to_path_buf(),
+        cdn_hosts: "cdn.test.com".to_string(),
+        cdn_path: "test/path".to_string(),
+        tls_cert: None,
+        tls_key: None,
+        rate_limit: 0.0,
+        rate_limit_burst: 1,
+        tcp_idle_timeout_secs: idle_timeout_secs,
+        tcp_rate_limit: 0.0,
+        tcp_rate_limit_burst: 1,
+        tcp_rate_limit_exempt: Vec::new(),
+        signing: None,
+        admin_token: None,
+        disable_metrics: false,
+    };
+
+    let mut server = Server::new(config).expect("Failed to create server");
+    server.start().await.expect("Failed to start server");
+    let addr = server.tcp_addr().expect("TCP listener should be bound");
+    (server, addr)
+}
+
+async fn read_to_string(stream: &mut TcpStream) -> String {
+    let mut response = String::new();
+    tokio::time::timeout(Duration::from_secs(5), stream.read_to_string(&mut response))
+        .await
+        .expect("Connection should close before the timeout")
+        .expect("Failed to read response");
+    response
+}
+
+/// Byte offset of `needle` in `haystack`, failing the test if it is missing.
+fn position(haystack: &str, needle: &str) -> usize {
+    haystack
+        .find(needle)
+        .expect("Response should contain every expected document")
+}
+
+#[tokio::test]
+async fn test_pipelined_requests_answered_in_order() {
+    let db_file = create_test_db();
+    let (_server, addr) = start_test_server(&db_file, 10).await;
+
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .expect("Failed to connect to TCP server");
+    stream
+        .write_all(
+            b"v2/products/wow/versions\r\nv2/products/wow/cdns\r\nv1/products/wow/versions\r\n",
+        )
+        .await
+        .expect("Failed to send commands");
+    stream.shutdown().await.expect("Failed to close write side");
+
+    let response = read_to_string(&mut stream).await;
+
+    // Two v2 BPSV documents followed by a v1 MIME message
+    let versions = position(&response, "Region!STRING:0");
+    let cdns = position(&response, "Path!STRING:0");
+    let mime = position(&response, "MIME-Version");
+    assert!(versions < cdns && cdns < mime);
+    assert_eq!(response.matches("Region!STRING:0").count(), 2);
+    assert_eq!(response.matches("1.14.2.42597").count(), 2 * 7);
+    assert!(response.contains("cdn.test.com"));
+}
+
+/// Send `command` on its own connection and read the whole response.
+async fn one_shot(addr: SocketAddr, command: &str) -> String {
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .expect("Failed to connect to TCP server");
+    stream
+        .write_all(format!("{command}\n").as_bytes())
+        .await
+        .expect("Failed to send command");
+    stream.shutdown().await.expect("Failed to close write side");
+    read_to_string(&mut stream).await
+}
+
+#[tokio::test]
+async fn test_requests_in_sequence_reuse_connection() {
+    let db_file = create_test_db();
+    let (_server, addr) = start_test_server(&db_file, 10).await;
+
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .expect("Failed to connect to TCP server");
+
+    // Wait for each response before sending the next command
+    for (command, marker) in [
+        ("v2/products/wow/versions", "Region!STRING:0"),
+        ("v2/products/wow/cdns", "Name!STRING:0"),
+        ("v2/products/wow/bgdl", "Region!STRING:0"),
+    ] {
+        // Responses differ only in their seqn, which keeps its length
+        let expected = one_shot(addr, command).await;
+
+        stream
+            .write_all(format!("{command}\n").as_bytes())
+            .await
+            .expect("Failed to send command");
+        let mut response = vec![0_u8; expected.len()];
+        tokio::time::timeout(Duration::from_secs(5), stream.read_exact(&mut response))
+            .await
+            .expect("Response should arrive before the timeout")
+            .expect("Connection closed before the response was complete");
+
+        let response = String::from_utf8(response).expect("Response should be UTF-8");
+        assert!(response.starts_with(marker), "{command}: {response}");
+        assert_eq!(response.lines().count(), expected.lines().count());
+    }
+}
+
+#[tokio::test]
+async fn test_idle_connection_is_closed() {
+    let db_file = create_test_db();
+    let (_server, addr) = start_test_server(&db_file, 1).await;
+
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .expect("Failed to connect to TCP server");
+    stream
+        .write_all(b"v2/products/wow/versions\n")
+        .await
+        .expect("Failed to send command");
+
+    // The write side stays open, so only the idle timeout ends the exchange
+    let started = Instant::now();
+    let response = read_to_string(&mut stream).await;
+    assert!(response.contains("1.14.2.42597"));
+    assert!(started.elapsed() >= Duration::from_millis(900));
+}
+
+#[tokio::test]
+async fn test_zero_idle_timeout_answers_one_request() {
+    let db_file = create_test_db();
+    let (_server, addr) = start_test_server(&db_file, 0).await;
+
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .expect("Failed to connect to TCP server");
+    stream
+        .write_all(b"v2/products/wow/versions\nv2/products/wow/cdns\n")
+        .await
+        .expect("Failed to send commands");
+
+    let response = read_to_string(&mut stream).await;
+    assert!(response.contains("Region!STRING:0"));
+    assert!(!response.contains("Path!STRING:0"));
+}
@@ -3,7 +3,8 @@
 #![allow(clippy::unwrap_used)]
 #![allow(clippy::expect_used)]
 
-use cascette_ribbit::{AppState, ServerConfig};
+use cascette_protocol::{ProtocolError, RibbitClient};
+use cascette_ribbit::{AppState, ServerConfig, SigningConfig};
 use sha2::{Digest, Sha256};
 use std::io::Write;
 use std::net::SocketAddr;
@@ -51,6 +52,22 @@ fn create_test_db() -> NamedTempFile {
 
 /// Start test TCP server on random port.
 async fn start_test_server() -> (SocketAddr, Arc<AppState>) {
+    start_test_server_with_signing(None).await
+}
+
+/// Signing configuration using the test certificate fixtures.
+fn test_signing_config() -> SigningConfig {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    SigningConfig {
+        cert_path: fixtures.join("signing_cert.pem"),
+        key_path: fixtures.join("signing_key.pem"),
+    }
+}
+
+/// Start test TCP server on random port, optionally signing v1 responses.
+async fn start_test_server_with_signing(
+    signing: Option<SigningConfig>,
+) -> (SocketAddr, Arc<AppState>) {
     let db_file = create_test_db();
     let config = ServerConfig {
         http_bind: "127.0.0.1:0"
@@ -64,6 +81,15 @@ async fn start_test_server() -> (SocketAddr, Arc<AppState>) {
         cdn_path: "test/path".to_string(),
         tls_cert: None,
         tls_key: None,
+        rate_limit: 0.0,
+        rate_limit_burst: 1,
+        tcp_idle_timeout_secs: 10,
+        tcp_rate_limit: 0.0,
+        tcp_rate_limit_burst: 1,
+        tcp_rate_limit_exempt: Vec::new(),
+        signing,
+        admin_token: None,
+        disable_metrics: false,
     };
 
     let state = Arc::new(AppState::new(&config).expect("Failed to initialize AppState"));
@@ -172,7 +198,7 @@ fn verify_mime_format(response: &str) {
         "Missing content part Content-Type"
     );
     assert!(
-        response.contains("Content-Disposition: data"),
+        response.contains("Content-Disposition: "),
         "Missing Content-Disposition"
     );
 }
@@ -398,3 +424,72 @@ async fn test_tcp_v1_crlf_line_endings() {
         "Boundary should use CRLF"
     );
 }
+
+#[tokio::test]
+async fn test_tcp_v1_signed_response_includes_signature_part() {
+    let (addr, _state) = start_test_server_with_signing(Some(test_signing_config())).await;
+
+    let response = send_tcp_v1_command(addr, "v1/products/wow/versions").await;
+
+    assert!(response.contains("Content-Disposition: version"));
+    assert!(response.contains("Content-Type: application/cms"));
+    assert!(response.contains("Content-Disposition: signature"));
+
+    // Checksum still covers everything before the epilogue
+    let (content, checksum_line) = response
+        .rsplit_once("Checksum: ")
+        .expect("Response should have a checksum");
+    let expected = format!("{:x}", Sha256::digest(content.as_bytes()));
+    assert_eq!(checksum_line.trim(), expected);
+}
+
+#[tokio::test]
+async fn test_tcp_v1_signed_response_verifies_with_client() {
+    let (addr, _state) = start_test_server_with_signing(Some(test_signing_config())).await;
+
+    // Signature verification is enabled by default and trusts the
+    // server's signing certificate as an anchor
+    let signing_cert = std::fs::read_to_string(test_signing_config().cert_path)
+        .expect("Signing certificate should be readable");
+    let client = RibbitClient::new(format!("tcp://{addr}"))
+        .expect("Failed to create client")
+        .with_trust_anchors_pem(&signing_cert)
+        .expect("Signing certificate should parse");
+    assert!(client.verifies_signatures());
+
+    let versions = client
+        .query("v1/products/wow/versions")
+        .await
+        .expect("Signed versions response should verify");
+    assert!(!versions.rows().is_empty());
+
+    // Every v1 endpoint is signed, not only versions
+    for endpoint in ["cdns", "bgdl"] {
+        client
+            .query(&format!("v1/products/wow/{endpoint}"))
+            .await
+            .expect("Signed response should verify");
+    }
+
+    let summary = client
+        .query("v1/summary")
+        .await
+        .expect("Signed summary response should verify");
+    assert!(!summary.rows().is_empty());
+}
+
+#[tokio::test]
+async fn test_tcp_v1_unsigned_response_rejected_by_client() {
+    let (addr, _state) = start_test_server().await;
+
+    let client = RibbitClient::new(format!("tcp://{addr}")).expect("Failed to create client");
+    let result = client.query("v1/products/wow/versions").await;
+    assert!(matches!(
+        result,
+        Err(ProtocolError::SignatureVerificationFailed(_))
+    ));
+
+    // Opting out of verification accepts the unsigned response
+    let client = client.verify_signatures(false);
+    assert!(client.query("v1/products/wow/versions").await.is_ok());
+}
@@ -49,6 +49,15 @@ async fn start_test_server() -> (SocketAddr, Arc<AppState>) {
         cdn_path: "test/path".to_string(),
         tls_cert: None,
         tls_key: None,
+        rate_limit: 0.0,
+        rate_limit_burst: 1,
+        tcp_idle_timeout_secs: 10,
+        tcp_rate_limit: 0.0,
+        tcp_rate_limit_burst: 1,
+        tcp_rate_limit_exempt: Vec::new(),
+        signing: None,
+        admin_token: None,
+        disable_metrics: false,
     };
 
     let state = Arc::new(AppState::new(&config).expect("Failed to initialize AppState"));